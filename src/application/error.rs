@@ -1,4 +1,4 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use thiserror::Error;
 
 use crate::domain::{Cents, WalletId};
@@ -27,12 +27,86 @@ pub enum AppError {
         to_currency: String,
     },
 
+    #[error("No exchange rate from {from_currency} to {to_currency} on or before {as_of}")]
+    ExchangeRateNotFound {
+        from_currency: String,
+        to_currency: String,
+        as_of: DateTime<Utc>,
+    },
+
+    #[error("Invalid exchange rate: {0}")]
+    InvalidExchangeRate(String),
+
+    #[error("Invalid currency: {0}")]
+    InvalidCurrency(String),
+
+    #[error("Currency {0} already has transfers recorded; its minor units cannot change")]
+    CurrencyInUse(String),
+
+    #[error("Locale profile not found: {0}")]
+    LocaleNotFound(String),
+
+    #[error("Invalid locale profile: {0}")]
+    InvalidLocale(String),
+
+    #[error("Invalid category: {0}")]
+    InvalidCategory(String),
+
+    #[error("Invalid tag: {0}")]
+    InvalidTag(String),
+
+    #[error("Invalid budget: {0}")]
+    InvalidBudget(String),
+
     #[error("Invalid amount: {0}")]
     InvalidAmount(String),
 
     #[error("Wallet is archived: {0}")]
     WalletArchived(String),
 
+    #[error("Wallet {0} has sub-wallets; record transfers in one of them")]
+    WalletHasSubWallets(String),
+
+    #[error("Invalid wallet hierarchy: {0}")]
+    InvalidWalletHierarchy(String),
+
+    #[error("Payee not found: {0}")]
+    PayeeNotFound(String),
+
+    #[error("Payee already exists: {0}")]
+    PayeeAlreadyExists(String),
+
+    #[error("Invalid payee: {0}")]
+    InvalidPayee(String),
+
+    #[error("Attachment not found: {0}")]
+    AttachmentNotFound(String),
+
+    #[error("Invalid attachment: {0}")]
+    InvalidAttachment(String),
+
+    #[error("Period locked: {scope} is closed through {lock_date}; {date} needs a lock override")]
+    PeriodLocked {
+        scope: String,
+        lock_date: NaiveDate,
+        date: NaiveDate,
+    },
+
+    #[error("Invalid period lock: {0}")]
+    InvalidPeriodLock(String),
+
+    #[error("Year already closed: {0}")]
+    YearAlreadyClosed(i32),
+
+    #[error("Invalid year close: {0}")]
+    InvalidYearClose(String),
+
+    #[error("Invalid opening balance: {0}")]
+    InvalidOpeningBalance(String),
+
+    #[error("Balance assertion not found: {0}")]
+    BalanceAssertionNotFound(String),
+
     #[error("Cannot reverse more than original amount")]
     ReversalExceedsOriginal {
         original_id: WalletId,
@@ -41,6 +115,57 @@ pub enum AppError {
         requested: Cents,
     },
 
+    #[error("Envelope not found: {0}")]
+    EnvelopeNotFound(String),
+
+    #[error("Envelope already exists: {0}")]
+    EnvelopeAlreadyExists(String),
+
+    #[error("Invalid envelope: {0}")]
+    InvalidEnvelope(String),
+
+    #[error("Invalid date range: {0}")]
+    InvalidDateRange(String),
+
+    #[error("Invalid amendment: {0}")]
+    InvalidAmendment(String),
+
+    #[error("Invalid split transfer: {0}")]
+    InvalidSplit(String),
+
+    #[error("Invalid trade: {0}")]
+    InvalidTrade(String),
+
+    #[error("Insufficient units in wallet {wallet_name}: holding {held}, required {required}")]
+    InsufficientUnits {
+        wallet_name: String,
+        held: i64,
+        required: i64,
+    },
+
+    #[error("No price for {commodity} on or before {as_of}")]
+    PriceNotFound {
+        commodity: String,
+        as_of: DateTime<Utc>,
+    },
+
+    #[error("No reconciliation in progress for wallet: {0}")]
+    NoOpenReconciliation(String),
+
+    #[error("A reconciliation is already in progress for wallet: {0}")]
+    ReconciliationInProgress(String),
+
+    #[error(
+        "Reconciliation of {wallet_name} is off by {difference}: cleared transfers do not add up to the statement"
+    )]
+    ReconciliationDifference {
+        wallet_name: String,
+        difference: Cents,
+    },
+
+    #[error("Invalid reconciliation: {0}")]
+    InvalidReconciliation(String),
+
     #[error("Scheduled transfer not found: {0}")]
     ScheduledTransferNotFound(String),
 
@@ -1,7 +1,10 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::domain::Cents;
+use crate::domain::{
+    BudgetFilter, CategoryNode, Cents, LotId, LotMethod, NetWorthPoint, PeriodType, SampleInterval,
+    build_category_tree, category_at_depth,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CategoryReport {
@@ -9,6 +12,9 @@ pub struct CategoryReport {
     pub to_date: DateTime<Utc>,
     pub categories: Vec<CategorySummary>,
     pub total: Cents,
+    /// Reporting currency when totals were converted; `None` means amounts are summed as stored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +26,55 @@ pub struct CategorySummary {
     pub percentage: f64,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagReport {
+    pub from_date: DateTime<Utc>,
+    pub to_date: DateTime<Utc>,
+    pub tags: Vec<TagSummary>,
+    /// Total of the tagged transfers, each counted once however many tags it has
+    pub total: Cents,
+    /// Reporting currency when totals were converted; `None` means amounts are summed as stored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+}
+
+/// Totals of one tag. Transfers with several tags count under each, so the
+/// percentages of a report can add up to more than 100.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagSummary {
+    pub tag: String,
+    pub total: Cents,
+    pub count: i64,
+    pub average: Cents,
+    /// Share of the report total
+    pub percentage: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PayeeReport {
+    pub from_date: DateTime<Utc>,
+    pub to_date: DateTime<Utc>,
+    /// Payees by total, largest first
+    pub payees: Vec<PayeeSummary>,
+    /// Total of the transfers with a payee, including payees cut off by a limit
+    pub total: Cents,
+}
+
+/// Totals and frequency of the transfers with one payee
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PayeeSummary {
+    pub payee: String,
+    pub total: Cents,
+    pub count: i64,
+    pub average: Cents,
+    /// Share of the report total
+    pub percentage: f64,
+    pub first_date: DateTime<Utc>,
+    pub last_date: DateTime<Utc>,
+    /// Average number of days between transfers (none for a single transfer)
+    pub average_interval_days: Option<f64>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IncomeExpenseReport {
     pub from_date: DateTime<Utc>,
@@ -36,6 +91,9 @@ pub struct CashFlowReport {
     pub from_date: DateTime<Utc>,
     pub to_date: DateTime<Utc>,
     pub periods: Vec<CashFlowPeriod>,
+    /// Reporting currency when totals were converted; `None` means amounts are summed as stored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,6 +103,9 @@ pub struct CashFlowPeriod {
     pub inflow: Cents,
     pub outflow: Cents,
     pub net: Cents,
+    /// Category totals of the period, as in the category report
+    #[serde(default)]
+    pub categories: Vec<CategorySummary>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -55,12 +116,165 @@ pub struct NetWorthReport {
     pub net_worth: Cents,
     pub assets: Vec<WalletBalance>,
     pub liabilities: Vec<WalletBalance>,
+    /// Reporting currency when totals were converted; `None` means amounts are summed as stored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+}
+
+/// Net worth sampled at the end of every day, week or month of a range.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetWorthHistory {
+    pub from_date: NaiveDate,
+    pub to_date: NaiveDate,
+    pub interval: SampleInterval,
+    /// Currency every balance was converted into
+    pub currency: String,
+    pub points: Vec<NetWorthPoint>,
+}
+
+/// Limit against actual spending of every budget in each of its completed
+/// periods that overlap a range.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetVarianceReport {
+    pub from_date: NaiveDate,
+    pub to_date: NaiveDate,
+    pub budgets: Vec<BudgetVariance>,
+}
+
+/// Periods of one budget in a variance report, with how often it was exceeded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetVariance {
+    pub budget: String,
+    pub category: String,
+    /// Further categories, wallets and tags the budget is scoped by
+    pub filter: BudgetFilter,
+    pub period_type: PeriodType,
+    /// Currency of the amounts; `None` when the budget counts any currency
+    pub currency: Option<String>,
+    pub periods: Vec<BudgetVariancePeriod>,
+    pub total_limit: Cents,
+    pub total_actual: Cents,
+    pub total_variance: Cents,
+    /// Number of periods where spending went over the limit
+    pub periods_exceeded: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetVariancePeriod {
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    /// Limit of the period, with what rolled over into it
+    pub limit: Cents,
+    pub actual: Cents,
+    /// Limit minus actual: negative when the budget was exceeded
+    pub variance: Cents,
+    /// Variance as a share of the limit
+    pub variance_percentage: f64,
+}
+
+impl BudgetVariancePeriod {
+    pub fn new(
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+        limit: Cents,
+        actual: Cents,
+    ) -> Self {
+        let variance = limit - actual;
+        let variance_percentage = if limit != 0 {
+            ((variance as f64) / (limit as f64)) * 100.0
+        } else {
+            0.0
+        };
+        Self {
+            period_start,
+            period_end,
+            limit,
+            actual,
+            variance,
+            variance_percentage,
+        }
+    }
+
+    pub fn is_exceeded(&self) -> bool {
+        self.actual > self.limit
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WalletBalance {
     pub wallet_name: String,
     pub balance: Cents,
+    /// Currency the balance is expressed in (the wallet's, or the reporting currency)
+    #[serde(default)]
+    pub currency: String,
+    /// Balance of the wallet and all its sub-wallets; totals only count `balance`
+    #[serde(default)]
+    pub subtotal: Cents,
+    /// Level in the wallet tree (0 for a top-level wallet)
+    #[serde(default)]
+    pub depth: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HoldingsReport {
+    pub as_of: DateTime<Utc>,
+    pub holdings: Vec<Holding>,
+    /// Totals are summed as stored, across the holdings' currencies
+    pub total_market_value: Cents,
+    pub total_cost_basis: Cents,
+    pub total_unrealized_gain: Cents,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Holding {
+    pub wallet_name: String,
+    pub commodity: String,
+    /// Units held, in the commodity's minor units
+    pub quantity: i64,
+    /// Number of decimals of `quantity`
+    pub quantity_decimals: u32,
+    /// Latest known price per unit in `currency` (`None` if no price was ever recorded)
+    pub price: Option<f64>,
+    pub price_date: Option<DateTime<Utc>>,
+    /// The holding wallet's currency
+    pub currency: String,
+    pub market_value: Cents,
+    pub cost_basis: Cents,
+    pub unrealized_gain: Cents,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RealizedGainsReport {
+    pub year: i32,
+    pub from_date: DateTime<Utc>,
+    pub to_date: DateTime<Utc>,
+    /// One entry per lot consumed by a sale, in sale order
+    pub gains: Vec<RealizedGain>,
+    /// Totals are summed as stored, across the holdings' currencies
+    pub total_proceeds: Cents,
+    pub total_cost_basis: Cents,
+    pub total_gain: Cents,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RealizedGain {
+    pub wallet_name: String,
+    pub commodity: String,
+    /// The holding wallet's currency
+    pub currency: String,
+    /// Buy transfer that opened the lot
+    pub lot_id: LotId,
+    pub acquired_at: DateTime<Utc>,
+    pub disposed_at: DateTime<Utc>,
+    /// Units sold from the lot, in the commodity's minor units
+    pub quantity: i64,
+    /// Number of decimals of `quantity`
+    pub quantity_decimals: u32,
+    pub cost_basis: Cents,
+    pub proceeds: Cents,
+    /// Proceeds minus cost basis (negative for a loss)
+    pub gain: Cents,
+    pub method: LotMethod,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -78,6 +292,76 @@ pub struct PeriodSummary {
     pub total_income: Cents,
     pub total_expense: Cents,
     pub net: Cents,
+    /// Category totals of the period, as in the category report
+    #[serde(default)]
+    pub categories: Vec<CategorySummary>,
+}
+
+impl CategoryReport {
+    /// Fold categories deeper than `depth` into their ancestor at that depth.
+    pub fn rolled_up(mut self, depth: usize) -> Self {
+        self.categories = roll_up_categories(self.categories, depth);
+        self
+    }
+
+    /// The categories as a tree, each node with the totals of its whole subtree.
+    pub fn tree(&self) -> Vec<CategoryNode> {
+        build_category_tree(
+            self.categories
+                .iter()
+                .map(|c| (c.category.as_str(), c.total, c.count)),
+        )
+    }
+}
+
+impl CashFlowReport {
+    /// Fold each period's categories deeper than `depth` into their ancestor at that depth.
+    pub fn rolled_up(mut self, depth: usize) -> Self {
+        for period in &mut self.periods {
+            period.categories = roll_up_categories(std::mem::take(&mut period.categories), depth);
+        }
+        self
+    }
+}
+
+impl PeriodComparisonReport {
+    /// Fold both periods' categories deeper than `depth` into their ancestor at that depth.
+    pub fn rolled_up(mut self, depth: usize) -> Self {
+        for period in [&mut self.current_period, &mut self.previous_period] {
+            period.categories = roll_up_categories(std::mem::take(&mut period.categories), depth);
+        }
+        self
+    }
+}
+
+/// Merge category summaries into their ancestors at `depth` ("food:groceries"
+/// and "food:restaurants" become "food" at depth 1). Totals, counts and
+/// percentages add up; averages are recomputed. Largest total first.
+pub fn roll_up_categories(categories: Vec<CategorySummary>, depth: usize) -> Vec<CategorySummary> {
+    let mut rolled: Vec<CategorySummary> = Vec::new();
+    for summary in categories {
+        let category = category_at_depth(&summary.category, depth);
+        match rolled.iter_mut().find(|r| r.category == category) {
+            Some(existing) => {
+                existing.total += summary.total;
+                existing.count += summary.count;
+                existing.percentage += summary.percentage;
+            }
+            None => rolled.push(CategorySummary {
+                category: category.to_string(),
+                ..summary
+            }),
+        }
+    }
+    for summary in &mut rolled {
+        summary.average = if summary.count > 0 {
+            summary.total / summary.count
+        } else {
+            0
+        };
+    }
+    rolled.sort_by(|a, b| b.total.cmp(&a.total).then(a.category.cmp(&b.category)));
+    rolled
 }
 
 // Helper struct for repository aggregation
@@ -1,21 +1,61 @@
-use chrono::{DateTime, Utc};
-use std::collections::HashMap;
+use anyhow::Context;
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::{HashMap, HashSet};
 
 use crate::domain::{
-    Budget, Cents, IntegrityReport, PeriodType, RecurrencePattern, ScheduleStatus,
-    ScheduledTransfer, Transfer, TransferId, Wallet, WalletId, WalletType, build_integrity_report,
+    Amendment, AssertionCheck, Attachment, AttachmentId, BalanceAssertion, Budget, BudgetCarry,
+    BudgetEvent, BudgetFilter, Cents, ClearedState, CommodityPrice, Currency, Envelope,
+    EnvelopeAssignment, EnvelopeMonth, ExchangeRate, IntegrityIssue, IntegrityReport,
+    LocaleProfile, LockOverride, LockedAction, Lot, LotDisposal, LotMethod, LotPick,
+    MAX_ATTACHMENT_BYTES, MAX_MINOR_UNITS, MatchCandidate, Money, Payee, PayeeId, PeriodLock,
+    PeriodType, Rate, Reconciliation, RecurrencePattern, RegisterEntry, RolloverPolicy,
+    RoundingMode, SampleInterval, ScheduleStatus, ScheduledTransfer, SplitId, StatementLine,
+    TagMatch, Transfer, TransferId, Wallet, WalletId, WalletType, YearClose, allocate_sale,
+    balances_on, build_integrity_report, build_register, carry_over, cleared_balance,
+    closing_entries, content_hash, convert_amount, count_lock_violations, count_unrecorded_edits,
+    detect_payee, end_of_day, find_covering_lock, find_match_candidates, lock_in_force,
+    minor_unit_factor, month_bounds, month_start, net_worth_point, normalize_category,
+    normalize_tags, opening_entries, reached_thresholds, sample_dates, settle_balance,
+    start_of_day, total_reversed_amount, wallet_ancestors, wallet_tree,
 };
 use crate::storage::Repository;
 
 use super::{
-    AppError, CashFlowPeriod, CashFlowReport, CategoryReport, CategorySummary, IncomeExpenseReport,
-    NetWorthReport, PeriodComparisonReport, PeriodSummary, WalletBalance,
+    AppError, BudgetVariance, BudgetVariancePeriod, BudgetVarianceReport, CashFlowPeriod,
+    CashFlowReport, CategoryAggregate, CategoryReport, CategorySummary, Holding, HoldingsReport,
+    IncomeExpenseReport, NetWorthHistory, NetWorthReport, PayeeReport, PayeeSummary,
+    PeriodComparisonReport, PeriodSummary, RealizedGain, RealizedGainsReport, TagReport,
+    TagSummary, WalletBalance,
 };
 
+/// Settings key holding the name of the ledger's locale profile
+const LOCALE_SETTING: &str = "locale";
+
+/// Settings key holding the command budget events are piped to
+const BUDGET_ALERT_COMMAND_SETTING: &str = "budget_alert_command";
+
+/// Settings key holding the file budget events are appended to
+const BUDGET_ALERT_FILE_SETTING: &str = "budget_alert_file";
+
+/// How long a budget alert command may run before it is stopped
+const BUDGET_ALERT_COMMAND_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// How converted amounts are rounded to the target currency's minor unit
+const CONVERSION_ROUNDING: RoundingMode = RoundingMode::HalfUp;
+
 /// Application service providing high-level operations for the ledger.
 /// This is the primary interface for any client (CLI, API, TUI, etc.).
 pub struct LedgerService {
     repo: Repository,
+    /// Budget alerts raised since they were last taken
+    budget_alerts: std::sync::Mutex<PendingBudgetAlerts>,
+}
+
+/// Budget events and alert failures waiting for the client to take them
+#[derive(Default)]
+struct PendingBudgetAlerts {
+    events: Vec<BudgetEvent>,
+    errors: Vec<String>,
 }
 
 /// Result of creating a transfer
@@ -23,6 +63,423 @@ pub struct TransferResult {
     pub transfer: Transfer,
     pub from_wallet_name: String,
     pub to_wallet_name: String,
+    /// Set when the transfer crossed currencies. `transfer` is then the outgoing leg
+    /// into the source currency's exchange wallet.
+    pub converted: Option<Conversion>,
+}
+
+/// Currency conversion applied to a cross-currency transfer
+pub struct Conversion {
+    pub rate: Rate,
+    pub amount_cents: Cents,
+    pub currency: String,
+    /// Incoming leg from the destination currency's exchange wallet
+    pub leg: Transfer,
+}
+
+/// A transfer to be recorded, built with `TransferRequest::new` and the `with_*` methods
+pub struct TransferRequest {
+    pub from_wallet: String,
+    pub to_wallet: String,
+    pub amount_cents: Cents,
+    pub timestamp: DateTime<Utc>,
+    pub description: Option<String>,
+    pub category: Option<String>,
+    pub tags: Vec<String>,
+    /// Payee name or alias; when absent the payee is detected from the description.
+    /// The payee's default category and wallet fill in a missing category and an
+    /// empty `from_wallet` or `to_wallet`.
+    pub payee: Option<String>,
+    /// Rate for cross-currency transfers (looked up from the rate store when absent)
+    pub exchange_rate: Option<Rate>,
+    pub force: bool,
+    /// Reference of the bank line or receipt the transfer comes from
+    pub external_ref: Option<String>,
+    pub cleared: ClearedState,
+    /// Lets the transfer into a locked period; the override is logged
+    pub lock_override: Option<LockOverrideRequest>,
+}
+
+impl TransferRequest {
+    pub fn new(
+        from_wallet: impl Into<String>,
+        to_wallet: impl Into<String>,
+        amount_cents: Cents,
+        timestamp: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            from_wallet: from_wallet.into(),
+            to_wallet: to_wallet.into(),
+            amount_cents,
+            timestamp,
+            description: None,
+            category: None,
+            tags: Vec::new(),
+            payee: None,
+            exchange_rate: None,
+            force: false,
+            external_ref: None,
+            cleared: ClearedState::Uncleared,
+            lock_override: None,
+        }
+    }
+
+    pub fn with_description(mut self, description: Option<String>) -> Self {
+        self.description = description;
+        self
+    }
+
+    pub fn with_category(mut self, category: Option<String>) -> Self {
+        self.category = category;
+        self
+    }
+
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    pub fn with_payee(mut self, payee: Option<String>) -> Self {
+        self.payee = payee;
+        self
+    }
+
+    pub fn with_exchange_rate(mut self, rate: Option<Rate>) -> Self {
+        self.exchange_rate = rate;
+        self
+    }
+
+    pub fn with_force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    pub fn with_external_ref(mut self, external_ref: Option<String>) -> Self {
+        self.external_ref = external_ref;
+        self
+    }
+
+    pub fn with_cleared(mut self, cleared: ClearedState) -> Self {
+        self.cleared = cleared;
+        self
+    }
+
+    pub fn with_lock_override(mut self, lock_override: Option<LockOverrideRequest>) -> Self {
+        self.lock_override = lock_override;
+        self
+    }
+}
+
+/// Corrections to a recorded transfer, built with `TransferEdit::new` and the `with_*` methods.
+/// Anything not set keeps its current value.
+#[derive(Default)]
+pub struct TransferEdit {
+    /// New description; `Some(None)` clears it
+    pub description: Option<Option<String>>,
+    /// New category; `Some(None)` clears it
+    pub category: Option<Option<String>>,
+    pub tags: Option<Vec<String>>,
+    pub timestamp: Option<DateTime<Utc>>,
+    pub amount_cents: Option<Cents>,
+    /// Who is making the correction
+    pub author: Option<String>,
+    /// Why the correction is made
+    pub reason: Option<String>,
+    pub force: bool,
+    /// Lets the correction into a locked period; the override is logged
+    pub lock_override: Option<LockOverrideRequest>,
+}
+
+impl TransferEdit {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_description(mut self, description: Option<String>) -> Self {
+        self.description = Some(description);
+        self
+    }
+
+    pub fn with_category(mut self, category: Option<String>) -> Self {
+        self.category = Some(category);
+        self
+    }
+
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = Some(tags);
+        self
+    }
+
+    pub fn with_timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    pub fn with_amount(mut self, amount_cents: Cents) -> Self {
+        self.amount_cents = Some(amount_cents);
+        self
+    }
+
+    pub fn with_author(mut self, author: Option<String>) -> Self {
+        self.author = author;
+        self
+    }
+
+    pub fn with_reason(mut self, reason: Option<String>) -> Self {
+        self.reason = reason;
+        self
+    }
+
+    pub fn with_force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    pub fn with_lock_override(mut self, lock_override: Option<LockOverrideRequest>) -> Self {
+        self.lock_override = lock_override;
+        self
+    }
+}
+
+/// Permission to record or change a transfer inside a locked period. The reason
+/// and author are logged with the transfer it let through.
+#[derive(Debug, Clone)]
+pub struct LockOverrideRequest {
+    pub reason: String,
+    pub author: Option<String>,
+}
+
+impl LockOverrideRequest {
+    pub fn new(reason: impl Into<String>) -> Self {
+        Self {
+            reason: reason.into(),
+            author: None,
+        }
+    }
+
+    pub fn with_author(mut self, author: Option<String>) -> Self {
+        self.author = author;
+        self
+    }
+}
+
+/// One leg of a split transfer to be recorded
+pub struct SplitLeg {
+    pub to_wallet: String,
+    pub amount_cents: Cents,
+    pub category: Option<String>,
+    pub description: Option<String>,
+}
+
+/// A wallet's real-world balance, to be posted as its opening balance. Liabilities
+/// are given as the amount owed.
+#[derive(Debug, Clone)]
+pub struct OpeningBalance {
+    pub wallet: String,
+    pub amount_cents: Cents,
+}
+
+impl OpeningBalance {
+    pub fn new(wallet: impl Into<String>, amount_cents: Cents) -> Self {
+        Self {
+            wallet: wallet.into(),
+            amount_cents,
+        }
+    }
+}
+
+/// A budget to be created, built with `BudgetRequest::new` and the `with_*` methods
+pub struct BudgetRequest {
+    pub name: String,
+    pub category: String,
+    pub amount_cents: Cents,
+    pub period_type: PeriodType,
+    /// Currency of the limit (only transfers into wallets of this currency count)
+    pub currency: Option<String>,
+    /// Further categories counted next to `category`
+    pub categories: Vec<String>,
+    /// Only count transfers from these wallets (by name)
+    pub from_wallets: Vec<String>,
+    /// Only count transfers to these wallets (by name)
+    pub to_wallets: Vec<String>,
+    /// Only count transfers carrying these tags (any or all of them, see `tag_match`)
+    pub tags: Vec<String>,
+    pub tag_match: TagMatch,
+    pub rollover: RolloverPolicy,
+    /// Day in the first period whose leftover carries over
+    pub rollover_since: Option<NaiveDate>,
+    /// Percentages of the limit that raise a budget event
+    pub alert_thresholds: Vec<u32>,
+}
+
+impl BudgetRequest {
+    pub fn new(
+        name: impl Into<String>,
+        category: impl Into<String>,
+        amount_cents: Cents,
+        period_type: PeriodType,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            category: category.into(),
+            amount_cents,
+            period_type,
+            currency: None,
+            categories: Vec::new(),
+            from_wallets: Vec::new(),
+            to_wallets: Vec::new(),
+            tags: Vec::new(),
+            tag_match: TagMatch::Any,
+            rollover: RolloverPolicy::None,
+            rollover_since: None,
+            alert_thresholds: Vec::new(),
+        }
+    }
+
+    pub fn with_categories(mut self, categories: Vec<String>) -> Self {
+        self.categories = categories;
+        self
+    }
+
+    pub fn with_from_wallets(mut self, wallets: Vec<String>) -> Self {
+        self.from_wallets = wallets;
+        self
+    }
+
+    pub fn with_to_wallets(mut self, wallets: Vec<String>) -> Self {
+        self.to_wallets = wallets;
+        self
+    }
+
+    pub fn with_tags(mut self, tags: Vec<String>, tag_match: TagMatch) -> Self {
+        self.tags = tags;
+        self.tag_match = tag_match;
+        self
+    }
+
+    pub fn with_currency(mut self, currency: Option<String>) -> Self {
+        self.currency = currency;
+        self
+    }
+
+    pub fn with_rollover(mut self, rollover: RolloverPolicy, since: Option<NaiveDate>) -> Self {
+        self.rollover = rollover;
+        self.rollover_since = since;
+        self
+    }
+
+    pub fn with_alert_thresholds(mut self, thresholds: Vec<u32>) -> Self {
+        self.alert_thresholds = thresholds;
+        self
+    }
+}
+
+/// A buy or sell of commodity units, built with `TradeRequest::new` and the `with_*` methods
+pub struct TradeRequest {
+    /// Wallet holding the commodity
+    pub holding_wallet: String,
+    /// Cash wallet paying for a buy or receiving the proceeds of a sell
+    pub cash_wallet: String,
+    /// Units traded, in the commodity's minor units
+    pub quantity: i64,
+    /// Price per unit, in the holding wallet's currency
+    pub unit_price: f64,
+    pub timestamp: DateTime<Utc>,
+    pub description: Option<String>,
+    /// Buy even if it would make the cash wallet negative
+    pub force: bool,
+    /// How a sale picks lots (the wallet's default when absent)
+    pub lot_method: Option<LotMethod>,
+    /// Lots a sale takes units from, for specific identification
+    pub lots: Vec<LotPick>,
+    /// Lets the trade into a locked period; the override is logged
+    pub lock_override: Option<LockOverrideRequest>,
+}
+
+impl TradeRequest {
+    pub fn new(
+        holding_wallet: impl Into<String>,
+        cash_wallet: impl Into<String>,
+        quantity: i64,
+        unit_price: f64,
+        timestamp: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            holding_wallet: holding_wallet.into(),
+            cash_wallet: cash_wallet.into(),
+            quantity,
+            unit_price,
+            timestamp,
+            description: None,
+            force: false,
+            lot_method: None,
+            lots: Vec::new(),
+            lock_override: None,
+        }
+    }
+
+    pub fn with_description(mut self, description: Option<String>) -> Self {
+        self.description = description;
+        self
+    }
+
+    pub fn with_force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    pub fn with_lot_method(mut self, method: Option<LotMethod>) -> Self {
+        self.lot_method = method;
+        self
+    }
+
+    pub fn with_lots(mut self, lots: Vec<LotPick>) -> Self {
+        self.lots = lots;
+        self
+    }
+
+    pub fn with_lock_override(mut self, lock_override: Option<LockOverrideRequest>) -> Self {
+        self.lock_override = lock_override;
+        self
+    }
+}
+
+/// Result of creating a split transfer
+pub struct SplitResult {
+    pub split_id: SplitId,
+    pub from_wallet_name: String,
+    pub total: Cents,
+    pub legs: Vec<TransferResult>,
+}
+
+/// Result of posting opening balances
+pub struct OpeningBalancesResult {
+    /// Transfers posted, one per wallet with a non-zero balance
+    pub posted: Vec<TransferResult>,
+    /// Wallets whose opening balance had already been posted, with that transfer
+    pub already_posted: Vec<(String, Transfer)>,
+}
+
+/// Result of asserting a balance
+pub struct BalanceAssertionResult {
+    /// The assertion, with the balance computed once any adjustment was posted
+    pub check: AssertionCheck,
+    /// Transfer posted against the adjustments wallet to make the assertion hold
+    pub adjustment: Option<TransferResult>,
+}
+
+/// Result of selling commodity units
+pub struct SaleResult {
+    /// The sale itself: units and proceeds leaving the holding wallet
+    pub trade: TransferResult,
+    /// Cost of the lots consumed
+    pub cost_basis: Cents,
+    /// Proceeds minus cost basis (negative for a loss)
+    pub realized_gain: Cents,
+    /// Transfer booking the gain or loss, shares a split ID with the sale (none at break-even)
+    pub gain_leg: Option<Transfer>,
+    /// Lots consumed by the sale
+    pub disposals: Vec<LotDisposal>,
 }
 
 /// Result of reversing a transfer
@@ -32,6 +489,17 @@ pub struct ReversalResult {
     pub from_wallet_name: String,
     pub to_wallet_name: String,
     pub is_partial: bool,
+    /// Reversals of every leg when the original is part of a split (empty otherwise)
+    pub split_reversals: Vec<Transfer>,
+}
+
+/// Result of amending a transfer
+pub struct AmendmentResult {
+    /// The transfer with its corrected values
+    pub transfer: Transfer,
+    pub amendment: Amendment,
+    /// Other legs of the split moved to the new date along with the transfer
+    pub split_amendments: Vec<Amendment>,
 }
 
 /// Detailed wallet information
@@ -41,6 +509,8 @@ pub struct WalletInfo {
     pub incoming_count: i64,
     pub outgoing_count: i64,
     pub last_activity: Option<DateTime<Utc>>,
+    /// Units held, for wallets holding a commodity
+    pub quantity: Option<i64>,
 }
 
 /// Detailed transfer information
@@ -50,32 +520,105 @@ pub struct TransferInfo {
     pub to_wallet: Wallet,
     pub total_reversed: Cents,
     pub reversals: Vec<Transfer>,
+    /// All legs of the split this transfer belongs to (empty if not a split)
+    pub split_legs: Vec<Transfer>,
+    /// Corrections made to the transfer, oldest first
+    pub amendments: Vec<Amendment>,
+    pub payee: Option<Payee>,
+    /// Files attached to the transfer
+    pub attachments: Vec<Attachment>,
 }
 
 /// Balance entry for a wallet
 pub struct BalanceEntry {
     pub wallet: Wallet,
     pub balance: Cents,
+    /// Balance of the wallet and all its sub-wallets
+    pub subtotal: Cents,
+    /// Level in the wallet tree (0 for a top-level wallet)
+    pub depth: usize,
+}
+
+/// Transfers of a wallet in date order with the balance after each one
+pub struct WalletRegister {
+    pub wallet: Wallet,
+    /// Balance before the first day of the register
+    pub opening_balance: Cents,
+    pub entries: Vec<RegisterEntry>,
+}
+
+impl WalletRegister {
+    /// Balance after the last transfer of the register
+    pub fn closing_balance(&self) -> Cents {
+        self.entries
+            .last()
+            .map(|entry| entry.balance)
+            .unwrap_or(self.opening_balance)
+    }
 }
 
 /// Filter for querying transfers
 pub struct TransferFilter {
     pub wallet: Option<String>,
     pub category: Option<String>,
+    /// Keep transfers carrying these tags (any or all of them, see `tag_match`)
+    pub tags: Vec<String>,
+    pub tag_match: TagMatch,
     pub from_date: Option<DateTime<Utc>>,
     pub to_date: Option<DateTime<Utc>>,
     pub limit: Option<usize>,
 }
 
+/// Where a reconcile session stands against its statement
+pub struct ReconciliationStatus {
+    pub reconciliation: Reconciliation,
+    pub wallet_name: String,
+    /// Balance of the wallet's cleared and reconciled transfers
+    pub cleared_balance: Cents,
+    /// Statement balance minus cleared balance; zero when the session can finish
+    pub difference: Cents,
+    /// Transfers up to the statement date still to be ticked off or already
+    /// ticked in this session (not yet reconciled), oldest first
+    pub pending: Vec<Transfer>,
+}
+
 /// Budget status information
 pub struct BudgetStatus {
     pub budget: Budget,
     pub spent: Cents,
+    /// Carried in from past periods under the budget's rollover policy
+    pub carried: Cents,
     pub remaining: Cents,
     pub period_start: DateTime<Utc>,
     pub period_end: DateTime<Utc>,
 }
 
+/// Where budget events are delivered besides the ledger itself
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BudgetAlertHooks {
+    /// Shell command run for each event, with the event as JSON on stdin
+    pub command: Option<String>,
+    /// File each event is appended to as a line of JSON
+    pub file: Option<String>,
+}
+
+impl BudgetStatus {
+    /// Limit of the current period with what was carried in
+    pub fn effective_limit(&self) -> Cents {
+        self.budget.amount_cents + self.carried
+    }
+}
+
+/// Envelopes of one currency in a month, next to the pool they are filled from
+pub struct EnvelopeStatus {
+    pub month: NaiveDate,
+    pub currency: String,
+    /// Income through the end of the month not yet assigned to an envelope;
+    /// negative when more was assigned than came in
+    pub to_be_assigned: Cents,
+    pub envelopes: Vec<EnvelopeMonth>,
+}
+
 /// Forecast result showing projected balances
 pub struct ForecastResult {
     pub start_date: DateTime<Utc>,
@@ -101,7 +644,10 @@ pub struct ForecastEvent {
 impl LedgerService {
     /// Create a new ledger service with the given repository.
     pub fn new(repo: Repository) -> Self {
-        Self { repo }
+        Self {
+            repo,
+            budget_alerts: std::sync::Mutex::default(),
+        }
     }
 
     /// Initialize a new database at the given path.
@@ -111,10 +657,11 @@ impl LedgerService {
         Ok(Self::new(repo))
     }
 
-    /// Connect to an existing database.
+    /// Connect to an existing database, applying any pending migrations.
     pub async fn connect(database_path: &str) -> Result<Self, AppError> {
         let db_url = format!("sqlite:{}", database_path);
         let repo = Repository::connect(&db_url).await?;
+        repo.migrate().await?;
         Ok(Self::new(repo))
     }
 
@@ -144,6 +691,37 @@ impl LedgerService {
         Ok(wallet)
     }
 
+    /// Create an asset wallet holding units of a commodity, valued in `currency`.
+    pub async fn create_commodity_wallet(
+        &self,
+        name: String,
+        commodity: String,
+        currency: String,
+        description: Option<String>,
+    ) -> Result<Wallet, AppError> {
+        let commodity = commodity.trim().to_uppercase();
+        if commodity.is_empty() {
+            return Err(AppError::InvalidTrade("empty commodity code".to_string()));
+        }
+        if commodity == currency.trim().to_uppercase() {
+            return Err(AppError::InvalidTrade(format!(
+                "{} cannot be held in units of itself",
+                commodity
+            )));
+        }
+        if self.repo.get_wallet_by_name(&name).await?.is_some() {
+            return Err(AppError::WalletAlreadyExists(name));
+        }
+
+        let mut wallet = Wallet::new(name, WalletType::Asset, currency).with_commodity(commodity);
+        if let Some(desc) = description {
+            wallet = wallet.with_description(desc);
+        }
+
+        self.repo.save_wallet(&wallet).await?;
+        Ok(wallet)
+    }
+
     /// Get a wallet by name.
     pub async fn get_wallet(&self, name: &str) -> Result<Wallet, AppError> {
         self.repo
@@ -159,6 +737,10 @@ impl LedgerService {
         let (incoming_count, outgoing_count) =
             self.repo.count_transfers_for_wallet(wallet.id).await?;
         let last_activity = self.repo.get_last_activity(wallet.id).await?;
+        let quantity = match wallet.commodity {
+            Some(_) => Some(self.repo.compute_quantity(wallet.id).await?),
+            None => None,
+        };
 
         Ok(WalletInfo {
             wallet,
@@ -166,6 +748,7 @@ impl LedgerService {
             incoming_count,
             outgoing_count,
             last_activity,
+            quantity,
         })
     }
 
@@ -174,106 +757,663 @@ impl LedgerService {
         Ok(self.repo.list_wallets(include_archived).await?)
     }
 
-    /// Archive a wallet.
+    /// Create a wallet under `parent_name`. It takes the parent's type and currency.
+    pub async fn create_sub_wallet(
+        &self,
+        name: String,
+        parent_name: &str,
+        description: Option<String>,
+    ) -> Result<Wallet, AppError> {
+        let parent = self.get_wallet(parent_name).await?;
+        if parent.is_archived() {
+            return Err(AppError::WalletArchived(parent.name));
+        }
+        if self.repo.get_wallet_by_name(&name).await?.is_some() {
+            return Err(AppError::WalletAlreadyExists(name));
+        }
+
+        let mut wallet =
+            Wallet::new(name, parent.wallet_type, parent.currency.clone()).with_parent(parent.id);
+        if let Some(desc) = description {
+            wallet = wallet.with_description(desc);
+        }
+
+        self.repo.save_wallet(&wallet).await?;
+        Ok(wallet)
+    }
+
+    /// Move a wallet under another wallet, or to the top level with `None`.
+    /// Parent and child must share a type and a currency so subtotals add up.
+    pub async fn move_wallet(
+        &self,
+        name: &str,
+        parent_name: Option<&str>,
+    ) -> Result<Wallet, AppError> {
+        let mut wallet = self.get_wallet(name).await?;
+        let parent = match parent_name {
+            Some(parent_name) => Some(self.get_wallet(parent_name).await?),
+            None => None,
+        };
+
+        if let Some(parent) = &parent {
+            if parent.is_archived() {
+                return Err(AppError::WalletArchived(parent.name.clone()));
+            }
+            if parent.wallet_type != wallet.wallet_type || parent.currency != wallet.currency {
+                return Err(AppError::InvalidWalletHierarchy(format!(
+                    "'{}' ({}, {}) cannot hold '{}' ({}, {}); parent and sub-wallet need the same type and currency",
+                    parent.name,
+                    parent.wallet_type,
+                    parent.currency,
+                    wallet.name,
+                    wallet.wallet_type,
+                    wallet.currency
+                )));
+            }
+            let wallets = self.repo.list_wallets(true).await?;
+            if parent.id == wallet.id
+                || wallet_ancestors(parent, &wallets)
+                    .iter()
+                    .any(|ancestor| ancestor.id == wallet.id)
+            {
+                return Err(AppError::InvalidWalletHierarchy(format!(
+                    "'{}' cannot be moved under itself or one of its sub-wallets",
+                    wallet.name
+                )));
+            }
+        }
+
+        wallet.parent_id = parent.map(|parent| parent.id);
+        self.repo
+            .update_wallet_parent(wallet.id, wallet.parent_id)
+            .await?;
+        Ok(wallet)
+    }
+
+    /// Allow or forbid transfers into a wallet while it has sub-wallets.
+    pub async fn set_wallet_allow_postings(
+        &self,
+        name: &str,
+        allow: bool,
+    ) -> Result<Wallet, AppError> {
+        let wallet = self.get_wallet(name).await?;
+        self.repo
+            .update_wallet_allow_postings(wallet.id, allow)
+            .await?;
+        Ok(wallet.with_allow_postings(allow))
+    }
+
+    /// List the sub-wallets directly under a wallet.
+    pub async fn list_sub_wallets(&self, name: &str) -> Result<Vec<Wallet>, AppError> {
+        let wallet = self.get_wallet(name).await?;
+        Ok(self
+            .repo
+            .list_wallets(false)
+            .await?
+            .into_iter()
+            .filter(|w| w.parent_id == Some(wallet.id))
+            .collect())
+    }
+
+    /// Transfers go into leaf wallets; a wallet with sub-wallets only takes them
+    /// when it allows postings.
+    async fn ensure_postable(&self, wallet: &Wallet) -> Result<(), AppError> {
+        if !wallet.allow_postings && self.repo.has_child_wallets(wallet.id).await? {
+            return Err(AppError::WalletHasSubWallets(wallet.name.clone()));
+        }
+        Ok(())
+    }
+
+    /// Archive a wallet. Its sub-wallets must be archived first.
     pub async fn archive_wallet(&self, name: &str) -> Result<Wallet, AppError> {
         let wallet = self.get_wallet(name).await?;
+        if self.repo.has_child_wallets(wallet.id).await? {
+            return Err(AppError::InvalidWalletHierarchy(format!(
+                "'{}' has sub-wallets; archive or move them first",
+                wallet.name
+            )));
+        }
         self.repo.archive_wallet(wallet.id).await?;
         // Return the wallet (note: archived_at won't be updated in this instance)
         Ok(wallet)
     }
 
-    /// Get balance for a single wallet.
+    /// Get balance for a single wallet, with the subtotal of its sub-wallets.
     pub async fn get_balance(&self, name: &str) -> Result<BalanceEntry, AppError> {
         let wallet = self.get_wallet(name).await?;
-        let balance = self.repo.compute_balance(wallet.id).await?;
-        Ok(BalanceEntry { wallet, balance })
+        if !self.repo.has_child_wallets(wallet.id).await? {
+            let balance = self.repo.compute_balance(wallet.id).await?;
+            let wallets = self.repo.list_wallets(true).await?;
+            let depth = wallet_ancestors(&wallet, &wallets).len();
+            return Ok(BalanceEntry {
+                wallet,
+                balance,
+                subtotal: balance,
+                depth,
+            });
+        }
+
+        self.get_all_balances()
+            .await?
+            .into_iter()
+            .find(|entry| entry.wallet.id == wallet.id)
+            .ok_or_else(|| AppError::WalletArchived(name.to_string()))
     }
 
-    /// Get balances for all wallets.
+    /// Get balances for all wallets in tree order: every wallet is followed by
+    /// its sub-wallets, and siblings are sorted by name.
     pub async fn get_all_balances(&self) -> Result<Vec<BalanceEntry>, AppError> {
         let wallets = self.repo.list_wallets(false).await?;
         let balances = self.repo.compute_all_balances().await?;
+        Ok(Self::balance_entries(wallets, &balances))
+    }
 
-        Ok(wallets
+    /// Get the balance of a wallet at the end of `date`, counting only the
+    /// transfers dated up to that day. Archived wallets count as well, as they
+    /// may have held money then.
+    pub async fn get_balance_as_of(
+        &self,
+        name: &str,
+        date: NaiveDate,
+    ) -> Result<BalanceEntry, AppError> {
+        let wallet = self.get_wallet(name).await?;
+        self.get_all_balances_as_of(date)
+            .await?
             .into_iter()
-            .map(|wallet| {
-                let balance = balances.get(&wallet.id).copied().unwrap_or(0);
-                BalanceEntry { wallet, balance }
-            })
-            .collect())
+            .find(|entry| entry.wallet.id == wallet.id)
+            .ok_or_else(|| AppError::WalletNotFound(name.to_string()))
     }
 
-    // ========================
-    // Transfer operations
-    // ========================
+    /// Get balances for all wallets at the end of `date`, in tree order,
+    /// archived wallets included.
+    pub async fn get_all_balances_as_of(
+        &self,
+        date: NaiveDate,
+    ) -> Result<Vec<BalanceEntry>, AppError> {
+        let wallets = self.repo.list_wallets(true).await?;
+        let balances = self
+            .repo
+            .compute_all_balances_before(end_of_day(date))
+            .await?;
+        Ok(Self::balance_entries(wallets, &balances))
+    }
 
-    /// Record a new transfer.
-    #[allow(clippy::too_many_arguments)]
-    pub async fn record_transfer(
+    /// Get the register of a wallet: its transfers from `from` to `to` (both
+    /// days included, open-ended when omitted) in date order, with the balance
+    /// after each transfer.
+    pub async fn get_register(
         &self,
-        from_wallet_name: &str,
-        to_wallet_name: &str,
-        amount_cents: Cents,
-        timestamp: DateTime<Utc>,
-        description: Option<String>,
-        category: Option<String>,
-        force: bool,
-    ) -> Result<TransferResult, AppError> {
-        // Validate amount
-        if amount_cents <= 0 {
+        name: &str,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+    ) -> Result<WalletRegister, AppError> {
+        let wallet = self.get_wallet(name).await?;
+        let start = from.map(start_of_day);
+        let end = to.map(end_of_day);
+
+        let opening_balance = match start {
+            Some(start) => self.repo.compute_balance_before(wallet.id, start).await?,
+            None => 0,
+        };
+        let transfers: Vec<Transfer> = self
+            .repo
+            .list_transfers_for_wallet(wallet.id)
+            .await?
+            .into_iter()
+            .filter(|t| start.is_none_or(|start| t.timestamp >= start))
+            .filter(|t| end.is_none_or(|end| t.timestamp < end))
+            .collect();
+        let entries = build_register(wallet.id, opening_balance, transfers);
+
+        Ok(WalletRegister {
+            wallet,
+            opening_balance,
+            entries,
+        })
+    }
+
+    /// Arrange balances as entries in wallet tree order.
+    fn balance_entries(
+        wallets: Vec<Wallet>,
+        balances: &HashMap<WalletId, Cents>,
+    ) -> Vec<BalanceEntry> {
+        let tree = wallet_tree(&wallets, balances);
+
+        let mut by_id: HashMap<WalletId, Wallet> = wallets
+            .into_iter()
+            .map(|wallet| (wallet.id, wallet))
+            .collect();
+        tree.into_iter()
+            .filter_map(|entry| {
+                by_id.remove(&entry.wallet_id).map(|wallet| BalanceEntry {
+                    wallet,
+                    balance: entry.balance,
+                    subtotal: entry.subtotal,
+                    depth: entry.depth,
+                })
+            })
+            .collect()
+    }
+
+    // ========================
+    // Transfer operations
+    // ========================
+
+    /// Record a new transfer.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn record_transfer(
+        &self,
+        from_wallet_name: &str,
+        to_wallet_name: &str,
+        amount_cents: Cents,
+        timestamp: DateTime<Utc>,
+        description: Option<String>,
+        category: Option<String>,
+        force: bool,
+    ) -> Result<TransferResult, AppError> {
+        let request =
+            TransferRequest::new(from_wallet_name, to_wallet_name, amount_cents, timestamp)
+                .with_description(description)
+                .with_category(category)
+                .with_force(force);
+        self.record_transfer_with(request).await
+    }
+
+    /// Record a new transfer described by a request.
+    /// Transfers between wallets of different currencies are converted with the
+    /// request's rate, or the stored rate effective at the transfer date.
+    /// Budgets the transfer pushes past an alert threshold raise budget events.
+    pub async fn record_transfer_with(
+        &self,
+        request: TransferRequest,
+    ) -> Result<TransferResult, AppError> {
+        let result = self.post_transfer(request).await?;
+        let mut recorded = vec![&result.transfer];
+        recorded.extend(result.converted.as_ref().map(|conversion| &conversion.leg));
+        self.raise_budget_alerts(&recorded).await;
+        Ok(result)
+    }
+
+    async fn post_transfer(
+        &self,
+        mut request: TransferRequest,
+    ) -> Result<TransferResult, AppError> {
+        let amount_cents = request.amount_cents;
+        request.category = clean_category(request.category)?;
+        request.tags = clean_tags(&request.tags)?;
+        let payee = self.apply_payee(&mut request).await?;
+
+        // Validate amount
+        if amount_cents <= 0 {
             return Err(AppError::InvalidAmount(
                 "Amount must be positive".to_string(),
             ));
         }
 
         // Get wallets
-        let from_wallet = self.get_wallet(from_wallet_name).await?;
-        let to_wallet = self.get_wallet(to_wallet_name).await?;
+        let from_wallet = self.get_wallet(&request.from_wallet).await?;
+        let to_wallet = self.get_wallet(&request.to_wallet).await?;
 
         // Check if archived
         if from_wallet.is_archived() {
-            return Err(AppError::WalletArchived(from_wallet_name.to_string()));
+            return Err(AppError::WalletArchived(request.from_wallet));
         }
         if to_wallet.is_archived() {
-            return Err(AppError::WalletArchived(to_wallet_name.to_string()));
+            return Err(AppError::WalletArchived(request.to_wallet));
         }
-
-        // Validate currencies match
-        if from_wallet.currency != to_wallet.currency {
-            return Err(AppError::CurrencyMismatch {
-                from_currency: from_wallet.currency.clone(),
-                to_currency: to_wallet.currency.clone(),
-            });
+        for wallet in [&from_wallet, &to_wallet] {
+            if wallet.is_holding() {
+                return Err(holding_transfer_error(wallet));
+            }
+            self.ensure_postable(wallet).await?;
         }
 
         // Validate balance if wallet doesn't allow negative
-        if !from_wallet.allow_negative && !force {
+        if !from_wallet.allow_negative && !request.force {
             let current_balance = self.repo.compute_balance(from_wallet.id).await?;
             if current_balance < amount_cents {
                 return Err(AppError::InsufficientFunds {
-                    wallet_name: from_wallet_name.to_string(),
+                    wallet_name: request.from_wallet,
                     balance: current_balance,
                     required: amount_cents,
                 });
             }
         }
 
+        if from_wallet.currency != to_wallet.currency {
+            return self
+                .record_exchange(request, payee, from_wallet, to_wallet)
+                .await;
+        }
+
+        if request.exchange_rate.is_some() {
+            return Err(AppError::InvalidExchangeRate(format!(
+                "both wallets use {}, no rate applies",
+                from_wallet.currency
+            )));
+        }
+        let lock_override = self
+            .check_period_lock(
+                &[&from_wallet, &to_wallet],
+                &[request.timestamp],
+                request.lock_override.take(),
+            )
+            .await?;
+
         // Create and save transfer
-        let mut transfer = Transfer::new(from_wallet.id, to_wallet.id, amount_cents, timestamp);
+        let mut transfer = Transfer::new(
+            from_wallet.id,
+            to_wallet.id,
+            amount_cents,
+            request.timestamp,
+        );
 
-        if let Some(desc) = description {
+        if let Some(desc) = request.description {
             transfer = transfer.with_description(desc);
         }
-        if let Some(cat) = category {
+        if let Some(cat) = request.category {
             transfer = transfer.with_category(cat);
         }
+        transfer = transfer.with_tags(request.tags);
+        if let Some(payee) = payee {
+            transfer = transfer.with_payee(payee.id);
+        }
+        if let Some(ext_ref) = request.external_ref {
+            transfer = transfer.with_external_ref(ext_ref);
+        }
+        transfer = transfer.with_cleared(request.cleared);
 
-        self.repo.save_transfer(&mut transfer).await?;
+        let overrides = lock_overrides(
+            &[transfer.id],
+            LockedAction::Transfer,
+            lock_override.as_ref(),
+        );
+        self.repo
+            .save_transfers(std::slice::from_mut(&mut transfer), &overrides)
+            .await?;
 
         Ok(TransferResult {
             transfer,
             from_wallet_name: from_wallet.name,
             to_wallet_name: to_wallet.name,
+            converted: None,
+        })
+    }
+
+    /// Record a cross-currency transfer as two legs sharing a split ID: the source
+    /// amount goes into the source currency's exchange wallet, and the converted
+    /// amount comes out of the destination currency's exchange wallet.
+    async fn record_exchange(
+        &self,
+        mut request: TransferRequest,
+        payee: Option<Payee>,
+        from_wallet: Wallet,
+        to_wallet: Wallet,
+    ) -> Result<TransferResult, AppError> {
+        let rate = match request.exchange_rate {
+            Some(rate) => rate,
+            None => {
+                self.get_exchange_rate(
+                    &from_wallet.currency,
+                    &to_wallet.currency,
+                    request.timestamp,
+                )
+                .await?
+            }
+        };
+
+        let from_currency = self.get_currency(&from_wallet.currency).await?;
+        let to_currency = self.get_currency(&to_wallet.currency).await?;
+        let converted_cents = Money::new(request.amount_cents, from_currency)
+            .convert(&rate, to_currency, CONVERSION_ROUNDING)
+            .ok_or_else(|| AppError::InvalidAmount("Converted amount is too large".to_string()))?
+            .amount;
+        if converted_cents <= 0 {
+            return Err(AppError::InvalidAmount(
+                "Converted amount must be positive".to_string(),
+            ));
+        }
+
+        let from_exchange = self
+            .get_or_create_exchange_wallet(&from_wallet.currency)
+            .await?;
+        let to_exchange = self
+            .get_or_create_exchange_wallet(&to_wallet.currency)
+            .await?;
+        // Both legs count: a lock on an exchange wallet covers them too
+        let lock_override = self
+            .check_period_lock(
+                &[&from_wallet, &from_exchange, &to_exchange, &to_wallet],
+                &[request.timestamp],
+                request.lock_override.take(),
+            )
+            .await?;
+
+        let split_id = uuid::Uuid::new_v4();
+        let mut outgoing = Transfer::new(
+            from_wallet.id,
+            from_exchange.id,
+            request.amount_cents,
+            request.timestamp,
+        )
+        .with_split(split_id);
+        let mut incoming = Transfer::new(
+            to_exchange.id,
+            to_wallet.id,
+            converted_cents,
+            request.timestamp,
+        )
+        .with_split(split_id);
+
+        if let Some(desc) = request.description {
+            outgoing = outgoing.with_description(desc.clone());
+            incoming = incoming.with_description(desc);
+        }
+        // The category, tags and payee belong to what was received, in the destination currency
+        if let Some(cat) = request.category {
+            incoming = incoming.with_category(cat);
+        }
+        incoming = incoming.with_tags(request.tags);
+        if let Some(payee) = payee {
+            incoming = incoming.with_payee(payee.id);
+        }
+        // The statement reference stays with the transfer the caller gets back
+        if let Some(ext_ref) = request.external_ref {
+            outgoing = outgoing.with_external_ref(ext_ref);
+        }
+        outgoing = outgoing.with_cleared(request.cleared);
+
+        let overrides = lock_overrides(
+            &[outgoing.id, incoming.id],
+            LockedAction::Transfer,
+            lock_override.as_ref(),
+        );
+        let mut legs = [outgoing, incoming];
+        self.repo.save_transfers(&mut legs, &overrides).await?;
+        let [outgoing, incoming] = legs;
+
+        Ok(TransferResult {
+            transfer: outgoing,
+            from_wallet_name: from_wallet.name,
+            to_wallet_name: to_wallet.name,
+            converted: Some(Conversion {
+                rate,
+                amount_cents: converted_cents,
+                currency: to_wallet.currency,
+                leg: incoming,
+            }),
+        })
+    }
+
+    /// Get the equity wallet that balances conversions in a currency, creating it if needed.
+    async fn get_or_create_exchange_wallet(&self, currency: &str) -> Result<Wallet, AppError> {
+        self.get_or_create_counterpart_wallet(
+            format!("Exchange {}", currency),
+            WalletType::Equity,
+            currency,
+            "Currency exchange counterpart",
+        )
+        .await
+    }
+
+    /// Get a wallet the ledger books against automatically, creating it if needed.
+    async fn get_or_create_counterpart_wallet(
+        &self,
+        name: String,
+        wallet_type: WalletType,
+        currency: &str,
+        description: &str,
+    ) -> Result<Wallet, AppError> {
+        if let Some(wallet) = self.repo.get_wallet_by_name(&name).await? {
+            if wallet.currency != currency {
+                return Err(AppError::CurrencyMismatch {
+                    from_currency: currency.to_string(),
+                    to_currency: wallet.currency,
+                });
+            }
+            return Ok(wallet);
+        }
+
+        self.create_wallet(
+            name,
+            wallet_type,
+            currency.to_string(),
+            Some(description.to_string()),
+        )
+        .await
+    }
+
+    /// Record a split transfer: one logical transaction from a single source wallet
+    /// into several destination wallets, each leg with its own amount and category.
+    /// All legs are stored atomically and share a split ID.
+    pub async fn record_split_transfer(
+        &self,
+        from_wallet_name: &str,
+        legs: Vec<SplitLeg>,
+        timestamp: DateTime<Utc>,
+        description: Option<String>,
+        force: bool,
+    ) -> Result<SplitResult, AppError> {
+        self.record_split_transfer_with(from_wallet_name, legs, timestamp, description, force, None)
+            .await
+    }
+
+    /// Record a split transfer, letting it into a locked period with an override.
+    pub async fn record_split_transfer_with(
+        &self,
+        from_wallet_name: &str,
+        legs: Vec<SplitLeg>,
+        timestamp: DateTime<Utc>,
+        description: Option<String>,
+        force: bool,
+        lock_override: Option<LockOverrideRequest>,
+    ) -> Result<SplitResult, AppError> {
+        if legs.is_empty() {
+            return Err(AppError::InvalidSplit(
+                "A split needs at least one leg".to_string(),
+            ));
+        }
+        if legs.iter().any(|leg| leg.amount_cents <= 0) {
+            return Err(AppError::InvalidAmount(
+                "Every split leg amount must be positive".to_string(),
+            ));
+        }
+        let legs = legs
+            .into_iter()
+            .map(|mut leg| {
+                leg.category = clean_category(leg.category)?;
+                Ok(leg)
+            })
+            .collect::<Result<Vec<SplitLeg>, AppError>>()?;
+
+        let from_wallet = self.get_wallet(from_wallet_name).await?;
+        if from_wallet.is_archived() {
+            return Err(AppError::WalletArchived(from_wallet_name.to_string()));
+        }
+        if from_wallet.is_holding() {
+            return Err(holding_transfer_error(&from_wallet));
+        }
+        self.ensure_postable(&from_wallet).await?;
+
+        // Resolve and validate every destination before writing anything
+        let mut to_wallets = Vec::with_capacity(legs.len());
+        for leg in &legs {
+            let to_wallet = self.get_wallet(&leg.to_wallet).await?;
+            if to_wallet.is_archived() {
+                return Err(AppError::WalletArchived(leg.to_wallet.clone()));
+            }
+            if to_wallet.is_holding() {
+                return Err(holding_transfer_error(&to_wallet));
+            }
+            self.ensure_postable(&to_wallet).await?;
+            if from_wallet.currency != to_wallet.currency {
+                return Err(AppError::CurrencyMismatch {
+                    from_currency: from_wallet.currency.clone(),
+                    to_currency: to_wallet.currency.clone(),
+                });
+            }
+            to_wallets.push(to_wallet);
+        }
+        let touched: Vec<&Wallet> = std::iter::once(&from_wallet).chain(&to_wallets).collect();
+        let lock_override = self
+            .check_period_lock(&touched, &[timestamp], lock_override)
+            .await?;
+
+        let total: Cents = legs.iter().map(|leg| leg.amount_cents).sum();
+
+        // The source must cover the whole split, not just each leg
+        if !from_wallet.allow_negative && !force {
+            let current_balance = self.repo.compute_balance(from_wallet.id).await?;
+            if current_balance < total {
+                return Err(AppError::InsufficientFunds {
+                    wallet_name: from_wallet_name.to_string(),
+                    balance: current_balance,
+                    required: total,
+                });
+            }
+        }
+
+        let split_id = uuid::Uuid::new_v4();
+        let mut transfers: Vec<Transfer> = legs
+            .into_iter()
+            .zip(&to_wallets)
+            .map(|(leg, to_wallet)| {
+                let mut transfer =
+                    Transfer::new(from_wallet.id, to_wallet.id, leg.amount_cents, timestamp)
+                        .with_split(split_id);
+                if let Some(desc) = leg.description.or_else(|| description.clone()) {
+                    transfer = transfer.with_description(desc);
+                }
+                if let Some(cat) = leg.category {
+                    transfer = transfer.with_category(cat);
+                }
+                transfer
+            })
+            .collect();
+
+        let ids: Vec<TransferId> = transfers.iter().map(|t| t.id).collect();
+        let overrides = lock_overrides(&ids, LockedAction::Transfer, lock_override.as_ref());
+        self.repo.save_transfers(&mut transfers, &overrides).await?;
+
+        self.raise_budget_alerts(&transfers.iter().collect::<Vec<_>>())
+            .await;
+
+        let legs = transfers
+            .into_iter()
+            .zip(to_wallets)
+            .map(|(transfer, to_wallet)| TransferResult {
+                transfer,
+                from_wallet_name: from_wallet.name.clone(),
+                to_wallet_name: to_wallet.name,
+                converted: None,
+            })
+            .collect();
+
+        Ok(SplitResult {
+            split_id,
+            from_wallet_name: from_wallet.name,
+            total,
+            legs,
         })
     }
 
@@ -299,6 +1439,16 @@ impl LedgerService {
 
         let total_reversed = self.repo.get_total_reversed(id).await?;
         let reversals = self.repo.get_reversals_for_transfer(id).await?;
+        let split_legs = match transfer.split_id {
+            Some(split_id) => self.repo.get_split_legs(split_id).await?,
+            None => Vec::new(),
+        };
+        let amendments = self.repo.list_amendments(Some(id)).await?;
+        let payee = match transfer.payee_id {
+            Some(payee_id) => self.repo.get_payee(payee_id).await?,
+            None => None,
+        };
+        let attachments = self.repo.list_attachments(Some(id)).await?;
 
         Ok(TransferInfo {
             transfer,
@@ -306,6 +1456,10 @@ impl LedgerService {
             to_wallet,
             total_reversed,
             reversals,
+            split_legs,
+            amendments,
+            payee,
+            attachments,
         })
     }
 
@@ -340,6 +1494,8 @@ impl LedgerService {
             .list_transfers_filtered(
                 wallet_id,
                 filter.category.as_deref(),
+                &clean_tags(&filter.tags)?,
+                filter.tag_match,
                 filter.from_date,
                 filter.to_date,
                 filter.limit,
@@ -348,10 +1504,22 @@ impl LedgerService {
     }
 
     /// Reverse a transfer (full or partial).
+    /// A leg of a split is always reversed together with the other legs, in full.
     pub async fn reverse_transfer(
         &self,
         transfer_id: TransferId,
         amount_cents: Option<Cents>,
+    ) -> Result<ReversalResult, AppError> {
+        self.reverse_transfer_with(transfer_id, amount_cents, None)
+            .await
+    }
+
+    /// Reverse a transfer, letting the reversal into a locked period with an override.
+    pub async fn reverse_transfer_with(
+        &self,
+        transfer_id: TransferId,
+        amount_cents: Option<Cents>,
+        lock_override: Option<LockOverrideRequest>,
     ) -> Result<ReversalResult, AppError> {
         // Get original transfer
         let original = self
@@ -360,6 +1528,28 @@ impl LedgerService {
             .await?
             .ok_or_else(|| AppError::TransferNotFound(transfer_id.to_string()))?;
 
+        // A lot that sales have taken units from cannot be undone under them
+        if original.is_trade()
+            && self
+                .repo
+                .count_active_disposals_for_lot(original.id)
+                .await?
+                > 0
+        {
+            return Err(AppError::InvalidTrade(
+                "Units of this lot were sold; reverse those sales first".to_string(),
+            ));
+        }
+
+        if let Some(split_id) = original.split_id {
+            if amount_cents.is_some_and(|amount| amount != original.amount_cents) {
+                return Err(AppError::InvalidSplit(
+                    "Split transfers can only be reversed in full".to_string(),
+                ));
+            }
+            return self.reverse_split(original, split_id, lock_override).await;
+        }
+
         // Get wallets for names
         let from_wallet = self
             .repo
@@ -372,9 +1562,17 @@ impl LedgerService {
             .get_wallet(original.to_wallet)
             .await?
             .ok_or_else(|| AppError::WalletNotFound(original.to_wallet.to_string()))?;
+        let lock_override = self
+            .check_period_lock(&[&from_wallet, &to_wallet], &[Utc::now()], lock_override)
+            .await?;
 
         // Determine reversal amount
         let reversal_amount = amount_cents.unwrap_or(original.amount_cents);
+        if original.is_trade() && reversal_amount != original.amount_cents {
+            return Err(AppError::InvalidTrade(
+                "Trades can only be reversed in full".to_string(),
+            ));
+        }
 
         // Validate reversal amount
         let already_reversed = self.repo.get_total_reversed(transfer_id).await?;
@@ -389,147 +1587,2911 @@ impl LedgerService {
             });
         }
 
-        // Create the reversal
-        let is_partial = reversal_amount != original.amount_cents;
-        let mut reversal = if is_partial {
-            original.create_partial_reversal(reversal_amount)
+        // Create the reversal
+        let is_partial = reversal_amount != original.amount_cents;
+        let mut reversal = if is_partial {
+            original.create_partial_reversal(reversal_amount)
+        } else {
+            original.create_reversal()
+        };
+
+        // Inherit category from original
+        if reversal.category.is_none() {
+            reversal.category = original.category.clone();
+        }
+
+        let overrides = lock_overrides(
+            &[reversal.id],
+            LockedAction::Reversal,
+            lock_override.as_ref(),
+        );
+        self.repo
+            .save_transfers(std::slice::from_mut(&mut reversal), &overrides)
+            .await?;
+
+        Ok(ReversalResult {
+            reversal,
+            original,
+            from_wallet_name: from_wallet.name,
+            to_wallet_name: to_wallet.name,
+            is_partial,
+            split_reversals: Vec::new(),
+        })
+    }
+
+    /// Reverse every leg of a split as a unit. The reversal legs form a new split.
+    async fn reverse_split(
+        &self,
+        original: Transfer,
+        split_id: SplitId,
+        lock_override: Option<LockOverrideRequest>,
+    ) -> Result<ReversalResult, AppError> {
+        let legs = self.repo.get_split_legs(split_id).await?;
+        let reversal_split_id = uuid::Uuid::new_v4();
+
+        let wallets = self.repo.list_wallets(true).await?;
+        let touched: Vec<&Wallet> = wallets
+            .iter()
+            .filter(|w| {
+                legs.iter()
+                    .any(|leg| leg.from_wallet == w.id || leg.to_wallet == w.id)
+            })
+            .collect();
+        let lock_override = self
+            .check_period_lock(&touched, &[Utc::now()], lock_override)
+            .await?;
+
+        let mut reversals = Vec::with_capacity(legs.len());
+        for leg in &legs {
+            let already_reversed = self.repo.get_total_reversed(leg.id).await?;
+            if already_reversed > 0 {
+                return Err(AppError::ReversalExceedsOriginal {
+                    original_id: leg.id,
+                    original_amount: leg.amount_cents,
+                    already_reversed,
+                    requested: leg.amount_cents,
+                });
+            }
+
+            let mut reversal = leg.create_reversal().with_split(reversal_split_id);
+            if reversal.category.is_none() {
+                reversal.category = leg.category.clone();
+            }
+            reversals.push(reversal);
+        }
+
+        let ids: Vec<TransferId> = reversals.iter().map(|r| r.id).collect();
+        let overrides = lock_overrides(&ids, LockedAction::Reversal, lock_override.as_ref());
+        self.repo.save_transfers(&mut reversals, &overrides).await?;
+
+        let from_wallet = self.get_wallet_by_id(original.from_wallet).await?;
+        let to_wallet = self.get_wallet_by_id(original.to_wallet).await?;
+        let reversal = reversals
+            .iter()
+            .find(|r| r.reverses == Some(original.id))
+            .cloned()
+            .ok_or_else(|| AppError::TransferNotFound(original.id.to_string()))?;
+
+        Ok(ReversalResult {
+            reversal,
+            original,
+            from_wallet_name: from_wallet.name,
+            to_wallet_name: to_wallet.name,
+            is_partial: false,
+            split_reversals: reversals,
+        })
+    }
+
+    /// Correct the description, category, tags, date or amount of a recorded transfer.
+    /// The transfer keeps its ID and sequence number; what changed is kept as an
+    /// append-only amendment. A new date moves every leg of a split.
+    pub async fn amend_transfer(
+        &self,
+        transfer_id: TransferId,
+        edit: TransferEdit,
+    ) -> Result<AmendmentResult, AppError> {
+        let original = self
+            .repo
+            .get_transfer(transfer_id)
+            .await?
+            .ok_or_else(|| AppError::TransferNotFound(transfer_id.to_string()))?;
+
+        let mut amended = original.clone();
+        if let Some(description) = edit.description {
+            amended.description = description;
+        }
+        if let Some(category) = edit.category {
+            amended.category = clean_category(category)?;
+        }
+        if let Some(tags) = edit.tags {
+            amended.tags = clean_tags(&tags)?;
+        }
+        if let Some(timestamp) = edit.timestamp {
+            amended.timestamp = timestamp;
+        }
+        if let Some(amount_cents) = edit.amount_cents {
+            amended.amount_cents = amount_cents;
+        }
+
+        let amount_changed = amended.amount_cents != original.amount_cents;
+        let date_changed = amended.timestamp != original.timestamp;
+        if (amount_changed || date_changed) && original.cleared == ClearedState::Reconciled {
+            return Err(AppError::InvalidAmendment(
+                "the transfer is reconciled against a bank statement; only its description, category and tags can change".to_string(),
+            ));
+        }
+        if amount_changed {
+            self.validate_amount_amendment(&original, amended.amount_cents, edit.force)
+                .await?;
+        }
+
+        let split_legs = match original.split_id {
+            Some(split_id) if date_changed => self.repo.get_split_legs(split_id).await?,
+            _ => Vec::new(),
+        };
+        if date_changed && original.is_trade() {
+            return Err(AppError::InvalidTrade(
+                "the date of a trade decides its lot order; reverse the trade instead".to_string(),
+            ));
+        }
+        for leg in split_legs.iter().filter(|leg| leg.id != original.id) {
+            if leg.is_trade() {
+                return Err(AppError::InvalidTrade(
+                    "the date of a trade decides its lot order; reverse the trade instead"
+                        .to_string(),
+                ));
+            }
+            if leg.cleared == ClearedState::Reconciled {
+                return Err(AppError::InvalidSplit(
+                    "another leg of the split is reconciled against a bank statement".to_string(),
+                ));
+            }
+        }
+
+        // The locks close both the day the transfer leaves and the day it moves to
+        let wallets = self.repo.list_wallets(true).await?;
+        let touched: Vec<&Wallet> = wallets
+            .iter()
+            .filter(|w| {
+                std::iter::once(&original)
+                    .chain(&split_legs)
+                    .any(|t| t.from_wallet == w.id || t.to_wallet == w.id)
+            })
+            .collect();
+        let lock_override = self
+            .check_period_lock(
+                &touched,
+                &[original.timestamp, amended.timestamp],
+                edit.lock_override.clone(),
+            )
+            .await?;
+
+        let stamp = |amendment: Amendment| {
+            let amendment = match &edit.author {
+                Some(author) => amendment.with_author(author),
+                None => amendment,
+            };
+            match &edit.reason {
+                Some(reason) => amendment.with_reason(reason),
+                None => amendment,
+            }
+        };
+
+        let amendment = Amendment::between(&original, &amended)
+            .map(stamp)
+            .ok_or_else(|| AppError::InvalidAmendment("nothing to change".to_string()))?;
+
+        let mut changes = vec![(amended.clone(), amendment.clone())];
+        let mut split_amendments = Vec::new();
+        for leg in split_legs.into_iter().filter(|leg| leg.id != original.id) {
+            let mut moved = leg.clone();
+            moved.timestamp = amended.timestamp;
+            if let Some(leg_amendment) = Amendment::between(&leg, &moved).map(stamp) {
+                split_amendments.push(leg_amendment.clone());
+                changes.push((moved, leg_amendment));
+            }
+        }
+
+        let ids: Vec<TransferId> = changes.iter().map(|(t, _)| t.id).collect();
+        let overrides = lock_overrides(&ids, LockedAction::Amendment, lock_override.as_ref());
+        self.repo.amend_transfers(&changes, &overrides).await?;
+
+        Ok(AmendmentResult {
+            transfer: amended,
+            amendment,
+            split_amendments,
+        })
+    }
+
+    /// Check that a transfer's amount can become `amount_cents`.
+    async fn validate_amount_amendment(
+        &self,
+        original: &Transfer,
+        amount_cents: Cents,
+        force: bool,
+    ) -> Result<(), AppError> {
+        if amount_cents <= 0 {
+            return Err(AppError::InvalidAmount(
+                "Amount must be positive".to_string(),
+            ));
+        }
+        if original.is_trade() {
+            return Err(AppError::InvalidTrade(
+                "a trade's amount follows its units and price; reverse the trade instead"
+                    .to_string(),
+            ));
+        }
+        if original.is_split_leg() {
+            return Err(AppError::InvalidSplit(
+                "the amounts of split legs cannot be amended; reverse the split instead"
+                    .to_string(),
+            ));
+        }
+        if original.is_reversal() {
+            return Err(AppError::InvalidAmendment(
+                "the amount of a reversal cannot be amended".to_string(),
+            ));
+        }
+
+        let already_reversed = self.repo.get_total_reversed(original.id).await?;
+        if amount_cents < already_reversed {
+            return Err(AppError::InvalidAmendment(format!(
+                "the amount cannot go below the {} cents already reversed",
+                already_reversed
+            )));
+        }
+
+        let increase = amount_cents - original.amount_cents;
+        let from_wallet = self.get_wallet_by_id(original.from_wallet).await?;
+        if increase > 0 && !from_wallet.allow_negative && !force {
+            let balance = self.repo.compute_balance(from_wallet.id).await?;
+            if balance < increase {
+                return Err(AppError::InsufficientFunds {
+                    wallet_name: from_wallet.name,
+                    balance,
+                    required: increase,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// List every amendment in the ledger, oldest first.
+    pub async fn list_amendments(&self) -> Result<Vec<Amendment>, AppError> {
+        Ok(self.repo.list_amendments(None).await?)
+    }
+
+    // ========================
+    // Attachment operations
+    // ========================
+
+    /// Attach a file to a transfer. `filename` is reduced to its last path component.
+    pub async fn attach_file(
+        &self,
+        transfer_id: TransferId,
+        filename: &str,
+        data: &[u8],
+    ) -> Result<Attachment, AppError> {
+        self.repo
+            .get_transfer(transfer_id)
+            .await?
+            .ok_or_else(|| AppError::TransferNotFound(transfer_id.to_string()))?;
+
+        let filename = filename
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or_default()
+            .trim();
+        if filename.is_empty() {
+            return Err(AppError::InvalidAttachment(
+                "file name cannot be empty".to_string(),
+            ));
+        }
+        if data.is_empty() {
+            return Err(AppError::InvalidAttachment(format!(
+                "{} is empty",
+                filename
+            )));
+        }
+        if data.len() > MAX_ATTACHMENT_BYTES {
+            return Err(AppError::InvalidAttachment(format!(
+                "{} is larger than {} MiB",
+                filename,
+                MAX_ATTACHMENT_BYTES / (1024 * 1024)
+            )));
+        }
+
+        let attachment = Attachment::new(transfer_id, filename, data);
+        self.repo.save_attachment(&attachment, data).await?;
+        Ok(attachment)
+    }
+
+    /// List the files attached to a transfer.
+    pub async fn list_attachments(
+        &self,
+        transfer_id: TransferId,
+    ) -> Result<Vec<Attachment>, AppError> {
+        Ok(self.repo.list_attachments(Some(transfer_id)).await?)
+    }
+
+    /// List the files attached to any transfer.
+    pub async fn list_all_attachments(&self) -> Result<Vec<Attachment>, AppError> {
+        Ok(self.repo.list_attachments(None).await?)
+    }
+
+    /// Get an attachment with its content, checked against its hash.
+    pub async fn read_attachment(
+        &self,
+        id: AttachmentId,
+    ) -> Result<(Attachment, Vec<u8>), AppError> {
+        let attachment = self
+            .repo
+            .get_attachment(id)
+            .await?
+            .ok_or_else(|| AppError::AttachmentNotFound(id.to_string()))?;
+        let data = self
+            .repo
+            .get_attachment_data(&attachment.hash)
+            .await?
+            .ok_or_else(|| AppError::AttachmentNotFound(attachment.hash.clone()))?;
+        if content_hash(&data) != attachment.hash {
+            return Err(AppError::InvalidAttachment(format!(
+                "content of {} does not match its hash",
+                attachment.filename
+            )));
+        }
+        Ok((attachment, data))
+    }
+
+    /// Remove an attachment from its transfer.
+    pub async fn remove_attachment(&self, id: AttachmentId) -> Result<Attachment, AppError> {
+        let attachment = self
+            .repo
+            .get_attachment(id)
+            .await?
+            .ok_or_else(|| AppError::AttachmentNotFound(id.to_string()))?;
+        self.repo.delete_attachment(&attachment).await?;
+        Ok(attachment)
+    }
+
+    /// Stored attachment contents as (hash, data) pairs, for backups.
+    pub async fn list_attachment_data(&self) -> Result<Vec<(String, Vec<u8>)>, AppError> {
+        Ok(self.repo.list_attachment_data().await?)
+    }
+
+    // ========================
+    // Payee operations
+    // ========================
+
+    /// Create a payee. Its name and aliases must not name another payee.
+    pub async fn create_payee(
+        &self,
+        name: String,
+        aliases: Vec<String>,
+        default_category: Option<String>,
+        default_wallet: Option<&str>,
+    ) -> Result<Payee, AppError> {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return Err(AppError::InvalidPayee("name cannot be empty".to_string()));
+        }
+        self.ensure_payee_name_free(&name).await?;
+
+        let mut payee = Payee::new(name);
+        for alias in aliases {
+            let alias = alias.trim().to_string();
+            if alias.is_empty() {
+                return Err(AppError::InvalidPayee("alias cannot be empty".to_string()));
+            }
+            if payee.is_named(&alias) {
+                continue;
+            }
+            self.ensure_payee_name_free(&alias).await?;
+            payee.aliases.push(alias);
+        }
+        if let Some(category) = clean_category(default_category)? {
+            payee = payee.with_default_category(category);
+        }
+        if let Some(wallet_name) = default_wallet {
+            payee = payee.with_default_wallet(self.get_wallet(wallet_name).await?.id);
+        }
+
+        self.repo.save_payee(&payee).await?;
+        Ok(payee)
+    }
+
+    /// Get a payee by its name or one of its aliases.
+    pub async fn get_payee(&self, name: &str) -> Result<Payee, AppError> {
+        self.repo
+            .get_payee_by_name(name.trim())
+            .await?
+            .ok_or_else(|| AppError::PayeeNotFound(name.to_string()))
+    }
+
+    /// List all payees.
+    pub async fn list_payees(&self) -> Result<Vec<Payee>, AppError> {
+        Ok(self.repo.list_payees().await?)
+    }
+
+    /// Add another spelling under which the payee shows up in descriptions.
+    pub async fn add_payee_alias(&self, name: &str, alias: &str) -> Result<Payee, AppError> {
+        let payee = self.get_payee(name).await?;
+        let alias = alias.trim();
+        if alias.is_empty() {
+            return Err(AppError::InvalidPayee("alias cannot be empty".to_string()));
+        }
+        if !payee.is_named(alias) {
+            self.ensure_payee_name_free(alias).await?;
+            self.repo.add_payee_alias(payee.id, alias).await?;
+        }
+        self.get_payee(&payee.name).await
+    }
+
+    /// Remove one of the payee's aliases.
+    pub async fn remove_payee_alias(&self, name: &str, alias: &str) -> Result<Payee, AppError> {
+        let payee = self.get_payee(name).await?;
+        if !self.repo.remove_payee_alias(payee.id, alias.trim()).await? {
+            return Err(AppError::InvalidPayee(format!(
+                "{} has no alias '{}'",
+                payee.name,
+                alias.trim()
+            )));
+        }
+        self.get_payee(&payee.name).await
+    }
+
+    /// Set the category and wallet the payee's transfers default to (`None` clears them).
+    pub async fn set_payee_defaults(
+        &self,
+        name: &str,
+        default_category: Option<String>,
+        default_wallet: Option<&str>,
+    ) -> Result<Payee, AppError> {
+        let payee = self.get_payee(name).await?;
+        let category = clean_category(default_category)?;
+        let wallet_id = match default_wallet {
+            Some(wallet_name) => Some(self.get_wallet(wallet_name).await?.id),
+            None => None,
+        };
+        self.repo
+            .update_payee_defaults(payee.id, category.as_deref(), wallet_id)
+            .await?;
+        self.get_payee(&payee.name).await
+    }
+
+    /// Fail if a payee already goes by `name`, as its name or an alias.
+    async fn ensure_payee_name_free(&self, name: &str) -> Result<(), AppError> {
+        if let Some(existing) = self.repo.get_payee_by_name(name).await? {
+            return Err(AppError::PayeeAlreadyExists(format!(
+                "'{}' already names {}",
+                name, existing.name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Find the payee of a transfer request, the one it names or else the one its
+    /// description mentions, and fill in the request's missing category and wallet
+    /// from the payee's defaults.
+    async fn apply_payee(&self, request: &mut TransferRequest) -> Result<Option<Payee>, AppError> {
+        let payee = match (&request.payee, &request.description) {
+            (Some(name), _) => Some(self.get_payee(name).await?),
+            (None, Some(description)) => {
+                let payees = self.repo.list_payees().await?;
+                detect_payee(description, &payees).cloned()
+            }
+            (None, None) => None,
+        };
+
+        let open_side = if request.from_wallet.trim().is_empty() {
+            Some(&mut request.from_wallet)
+        } else if request.to_wallet.trim().is_empty() {
+            Some(&mut request.to_wallet)
+        } else {
+            None
+        };
+        if let Some(side) = open_side {
+            let payee = payee.as_ref().ok_or_else(|| {
+                AppError::InvalidPayee("a transfer without both wallets needs a payee".to_string())
+            })?;
+            let wallet_id = payee.default_wallet.ok_or_else(|| {
+                AppError::InvalidPayee(format!("{} has no default wallet", payee.name))
+            })?;
+            *side = self.get_wallet_by_id(wallet_id).await?.name;
+        }
+
+        if let Some(payee) = &payee
+            && request.category.is_none()
+        {
+            request.category = payee.default_category.clone();
+        }
+        Ok(payee)
+    }
+
+    // ========================
+    // Period lock operations
+    // ========================
+
+    /// Close the books through `lock_date`, for the whole ledger or for one wallet
+    /// and its sub-wallets. Transfers dated on or before the lock date can then only
+    /// be recorded, reversed or amended with a logged override.
+    pub async fn lock_period(
+        &self,
+        wallet_name: Option<&str>,
+        lock_date: NaiveDate,
+        author: Option<String>,
+        reason: Option<String>,
+    ) -> Result<PeriodLock, AppError> {
+        let wallet_id = self.lock_scope(wallet_name).await?;
+        self.save_period_lock(wallet_id, Some(lock_date), author, reason)
+            .await
+    }
+
+    /// Lift the lock of the ledger, or of one wallet.
+    pub async fn unlock_period(
+        &self,
+        wallet_name: Option<&str>,
+        author: Option<String>,
+        reason: Option<String>,
+    ) -> Result<PeriodLock, AppError> {
+        let wallet_id = self.lock_scope(wallet_name).await?;
+        let locks = self.repo.list_period_locks().await?;
+        if lock_in_force(&locks, wallet_id, Utc::now()).is_none() {
+            return Err(AppError::InvalidPeriodLock(format!(
+                "{} is not locked",
+                wallet_name.unwrap_or("the ledger")
+            )));
+        }
+        self.save_period_lock(wallet_id, None, author, reason).await
+    }
+
+    /// The wallet a lock applies to; `None` for the whole ledger.
+    async fn lock_scope(&self, wallet_name: Option<&str>) -> Result<Option<WalletId>, AppError> {
+        Ok(match wallet_name {
+            Some(name) => Some(self.get_wallet(name).await?.id),
+            None => None,
+        })
+    }
+
+    async fn save_period_lock(
+        &self,
+        wallet_id: Option<WalletId>,
+        lock_date: Option<NaiveDate>,
+        author: Option<String>,
+        reason: Option<String>,
+    ) -> Result<PeriodLock, AppError> {
+        let mut lock = PeriodLock::new(wallet_id, lock_date);
+        if let Some(author) = author {
+            lock = lock.with_author(author);
+        }
+        if let Some(reason) = reason {
+            lock = lock.with_reason(reason);
+        }
+        self.repo.save_period_lock(&lock).await?;
+        Ok(lock)
+    }
+
+    /// The locks in force: the ledger's first, then the wallets'.
+    pub async fn get_period_locks(&self) -> Result<Vec<PeriodLock>, AppError> {
+        let locks = self.repo.list_period_locks().await?;
+        let now = Utc::now();
+        let mut scopes: Vec<Option<WalletId>> = Vec::new();
+        for lock in &locks {
+            if !scopes.contains(&lock.wallet_id) {
+                scopes.push(lock.wallet_id);
+            }
+        }
+        scopes.sort_by_key(|scope| scope.is_some());
+        Ok(scopes
+            .into_iter()
+            .filter_map(|scope| lock_in_force(&locks, scope, now).cloned())
+            .collect())
+    }
+
+    /// Every lock ever set, moved or lifted, oldest first.
+    pub async fn list_period_locks(&self) -> Result<Vec<PeriodLock>, AppError> {
+        Ok(self.repo.list_period_locks().await?)
+    }
+
+    /// Every override let into a locked period, oldest first.
+    pub async fn list_lock_overrides(&self) -> Result<Vec<LockOverride>, AppError> {
+        Ok(self.repo.list_lock_overrides().await?)
+    }
+
+    /// Check that a change dated at `timestamps` and touching `touched` wallets stays
+    /// out of every locked period. Returns the override when one was needed to let
+    /// the change through; the caller logs it in the transaction saving the change.
+    async fn check_period_lock(
+        &self,
+        touched: &[&Wallet],
+        timestamps: &[DateTime<Utc>],
+        lock_override: Option<LockOverrideRequest>,
+    ) -> Result<Option<LockOverrideRequest>, AppError> {
+        let locks = self.repo.list_period_locks().await?;
+        if locks.is_empty() {
+            return Ok(None);
+        }
+        let wallets = self.repo.list_wallets(true).await?;
+        let now = Utc::now();
+        for timestamp in timestamps {
+            let Some(lock) = find_covering_lock(&locks, &wallets, touched, *timestamp, now) else {
+                continue;
+            };
+            return match lock_override {
+                Some(lock_override) if lock_override.reason.trim().is_empty() => Err(
+                    AppError::InvalidPeriodLock("a lock override needs a reason".to_string()),
+                ),
+                Some(lock_override) => Ok(Some(lock_override)),
+                None => {
+                    let scope = match lock.wallet_id {
+                        Some(id) => format!("wallet {}", self.get_wallet_by_id(id).await?.name),
+                        None => "the ledger".to_string(),
+                    };
+                    Err(AppError::PeriodLocked {
+                        scope,
+                        lock_date: lock.lock_date.unwrap_or_default(),
+                        date: timestamp.date_naive(),
+                    })
+                }
+            };
+        }
+        Ok(None)
+    }
+
+    // ========================
+    // Year close operations
+    // ========================
+
+    /// Close a financial year: post closing entries that bring every income and
+    /// expense wallet back to zero against the "Retained Earnings" equity wallet of
+    /// its currency, and lock the ledger through the last day of the year. Years
+    /// are closed once each, in order, after they have ended.
+    pub async fn close_year(&self, year: i32) -> Result<YearClose, AppError> {
+        let closes = self.repo.list_year_closes().await?;
+        if closes.iter().any(|close| close.year == year) {
+            return Err(AppError::YearAlreadyClosed(year));
+        }
+        if let Some(latest) = closes.last()
+            && latest.year > year
+        {
+            return Err(AppError::InvalidYearClose(format!(
+                "{} is already closed",
+                latest.year
+            )));
+        }
+        let close = YearClose::new(year);
+        if close.end() > close.closed_at {
+            return Err(AppError::InvalidYearClose(format!(
+                "{} has not ended yet",
+                year
+            )));
+        }
+
+        let wallets = self.repo.list_wallets(true).await?;
+        let balances = self.repo.compute_all_balances_before(close.end()).await?;
+        let mut retained_earnings = HashMap::new();
+        for wallet in &wallets {
+            let to_close = matches!(wallet.wallet_type, WalletType::Income | WalletType::Expense)
+                && balances
+                    .get(&wallet.id)
+                    .is_some_and(|balance| *balance != 0);
+            if to_close && !retained_earnings.contains_key(&wallet.currency) {
+                let retained = self
+                    .get_or_create_counterpart_wallet(
+                        format!("Retained Earnings {}", wallet.currency),
+                        WalletType::Equity,
+                        &wallet.currency,
+                        "Net income of closed years",
+                    )
+                    .await?;
+                retained_earnings.insert(wallet.currency.clone(), retained.id);
+            }
+        }
+
+        let timestamp = close.closing_timestamp();
+        let description = format!("Year-end close {}", year);
+        let mut entries: Vec<Transfer> =
+            closing_entries(&wallets, &balances, &retained_earnings, timestamp)
+                .into_iter()
+                .map(|entry| {
+                    entry
+                        .with_description(description.clone())
+                        .with_split(close.closing_split_id)
+                })
+                .collect();
+        self.repo.save_year_close(&close, &mut entries).await?;
+
+        // Keep the closed year closed, unless the ledger lock already covers it
+        let locks = self.repo.list_period_locks().await?;
+        if !lock_in_force(&locks, None, Utc::now()).is_some_and(|lock| lock.covers(timestamp)) {
+            self.save_period_lock(None, Some(timestamp.date_naive()), None, Some(description))
+                .await?;
+        }
+
+        Ok(close)
+    }
+
+    /// Closed years, oldest first.
+    pub async fn list_year_closes(&self) -> Result<Vec<YearClose>, AppError> {
+        Ok(self.repo.list_year_closes().await?)
+    }
+
+    /// Move the history of a closed year out of the live file at `database_path`
+    /// into a read-only archive file at `archive_path`. The live file is rebuilt to
+    /// start with one opening-balance transfer per wallet, dated on the last second
+    /// of the year against the "Opening Balances" equity wallet of its currency,
+    /// followed by the transfers dated after the year. Holdings carry their units
+    /// over as one lot at their remaining cost.
+    ///
+    /// The service is closed and a new one is returned on the rebuilt file.
+    pub async fn archive_year(
+        self,
+        database_path: &str,
+        year: i32,
+        archive_path: &str,
+    ) -> Result<(Self, YearClose), AppError> {
+        let closes = self.repo.list_year_closes().await?;
+        let Some(mut close) = closes.iter().find(|close| close.year == year).cloned() else {
+            return Err(AppError::InvalidYearClose(format!(
+                "{} is not closed",
+                year
+            )));
+        };
+        if let Some(archived) = closes
+            .iter()
+            .find(|close| close.year >= year && close.is_archived())
+        {
+            return Err(AppError::InvalidYearClose(format!(
+                "{} is already archived",
+                archived.year
+            )));
+        }
+        if std::path::Path::new(archive_path).exists() {
+            return Err(AppError::InvalidYearClose(format!(
+                "{} already exists",
+                archive_path
+            )));
+        }
+
+        let end = close.end();
+        let timestamp = close.closing_timestamp();
+        let wallets = self.repo.list_wallets(true).await?;
+        let balances = self.repo.compute_all_balances_before(end).await?;
+        let quantities = self.repo.compute_all_quantities_before(end).await?;
+        let mut opening_balances = HashMap::new();
+        for wallet in &wallets {
+            let to_open = balances
+                .get(&wallet.id)
+                .is_some_and(|balance| *balance != 0);
+            if to_open && !opening_balances.contains_key(&wallet.currency) {
+                let opening = self.opening_balances_wallet(&wallet.currency).await?;
+                opening_balances.insert(wallet.currency.clone(), opening.id);
+            }
+        }
+
+        let opening_split_id = uuid::Uuid::new_v4();
+        let description = format!("Opening balance after {}", year);
+        let mut openings = Vec::new();
+        for entry in opening_entries(&wallets, &balances, &opening_balances, timestamp) {
+            let mut entry = entry
+                .with_description(description.clone())
+                .with_split(opening_split_id)
+                .with_cleared(ClearedState::Cleared);
+            let holding = wallets.iter().find(|w| w.id == entry.to_wallet);
+            if let Some(holding) = holding
+                && let Some(commodity) = &holding.commodity
+                && let Some(quantity) = quantities.get(&holding.id).filter(|q| **q > 0)
+            {
+                let commodity = self.get_currency(commodity).await?;
+                let currency = self.get_currency(&holding.currency).await?;
+                let unit_price = entry.amount_cents as f64
+                    / *quantity as f64
+                    / minor_unit_factor(1.0, &commodity, &currency);
+                entry = entry.with_quantity(*quantity, unit_price);
+            }
+            openings.push(entry);
+        }
+
+        // The opening balances are dated inside the closed year on purpose
+        let touched: Vec<&Wallet> = wallets.iter().collect();
+        let lock_override = self
+            .check_period_lock(
+                &touched,
+                &[timestamp],
+                Some(LockOverrideRequest::new(format!(
+                    "Opening balances after archiving {}",
+                    year
+                ))),
+            )
+            .await?;
+
+        // The archive is a copy of the ledger as it stands; the live file is then
+        // rebuilt from it next to the current one and moved over it
+        self.repo.backup_to(archive_path).await?;
+        let archive_path = std::fs::canonicalize(archive_path)
+            .with_context(|| format!("Failed to resolve {}", archive_path))?
+            .to_string_lossy()
+            .into_owned();
+        self.repo.close().await;
+
+        let rollover_path = format!("{}.rollover", database_path);
+        if std::path::Path::new(&rollover_path).exists() {
+            std::fs::remove_file(&rollover_path)
+                .with_context(|| format!("Failed to remove {}", rollover_path))?;
+        }
+        let rolled = Self::init(&rollover_path).await?;
+        rolled.repo.roll_forward_from(&archive_path, end).await?;
+        close.opening_split_id = Some(opening_split_id);
+        close.archive_path = Some(archive_path.clone());
+        let ids: Vec<TransferId> = openings.iter().map(|t| t.id).collect();
+        let overrides = lock_overrides(&ids, LockedAction::Transfer, lock_override.as_ref());
+        rolled
+            .repo
+            .save_year_archive(&close, &mut openings, &overrides)
+            .await?;
+        rolled.repo.close().await;
+
+        std::fs::rename(&rollover_path, database_path)
+            .with_context(|| format!("Failed to replace {}", database_path))?;
+        let mut permissions = std::fs::metadata(&archive_path)
+            .with_context(|| format!("Failed to read {}", archive_path))?
+            .permissions();
+        permissions.set_readonly(true);
+        std::fs::set_permissions(&archive_path, permissions)
+            .with_context(|| format!("Failed to make {} read-only", archive_path))?;
+
+        let service = Self::connect(database_path).await?;
+        Ok((service, close))
+    }
+
+    /// Open the ledger at `database_path` together with the history of its
+    /// archived years, for reports that span them. The service works on a private
+    /// in-memory copy, so nothing recorded through it is kept.
+    pub async fn open_with_archives(database_path: &str) -> Result<Self, AppError> {
+        let live = Self::connect(database_path).await?;
+        let closes = live.repo.list_year_closes().await?;
+        live.repo.close().await;
+
+        let repo = Repository::open_in_memory_copy(database_path).await?;
+        // Newest first: each archive also holds the opening balances of the one before
+        for close in closes.iter().rev() {
+            if let Some(archive_path) = &close.archive_path {
+                repo.merge_archive(archive_path, close.end(), close.opening_split_id)
+                    .await?;
+            }
+        }
+        Ok(Self::new(repo))
+    }
+
+    // ========================
+    // Opening balance operations
+    // ========================
+
+    /// Start wallets off with their real-world balances as of a date. Each balance
+    /// is posted against the "Opening Balances" equity wallet of the wallet's
+    /// currency, which is created on first use. Assets are given as what they
+    /// hold and liabilities as what is owed. Wallets whose opening balance was
+    /// already posted are left alone and reported back; the others are posted
+    /// together, or not at all.
+    pub async fn record_opening_balances(
+        &self,
+        balances: Vec<OpeningBalance>,
+        as_of: NaiveDate,
+    ) -> Result<OpeningBalancesResult, AppError> {
+        let timestamp = as_of.and_time(chrono::NaiveTime::MIN).and_utc();
+
+        let mut wallets: Vec<(Wallet, Cents)> = Vec::new();
+        for balance in balances {
+            let wallet = self.get_wallet(&balance.wallet).await?;
+            if wallets.iter().any(|(w, _)| w.id == wallet.id) {
+                return Err(AppError::InvalidOpeningBalance(format!(
+                    "'{}' is listed twice",
+                    wallet.name
+                )));
+            }
+            if wallet.is_archived() {
+                return Err(AppError::WalletArchived(wallet.name));
+            }
+            if wallet.is_holding() {
+                return Err(AppError::InvalidOpeningBalance(format!(
+                    "'{}' holds {}; buy the units in instead",
+                    wallet.name,
+                    wallet.commodity.as_deref().unwrap_or("units")
+                )));
+            }
+            // Liabilities carry what is owed as a negative balance
+            let signed = match wallet.wallet_type {
+                WalletType::Asset => balance.amount_cents,
+                WalletType::Liability => -balance.amount_cents,
+                other => {
+                    return Err(AppError::InvalidOpeningBalance(format!(
+                        "'{}' is an {} wallet; opening balances are for assets and liabilities",
+                        wallet.name, other
+                    )));
+                }
+            };
+            self.ensure_postable(&wallet).await?;
+            wallets.push((wallet, signed));
+        }
+        let touched: Vec<&Wallet> = wallets.iter().map(|(w, _)| w).collect();
+        self.check_period_lock(&touched, &[timestamp], None).await?;
+
+        let mut result = OpeningBalancesResult {
+            posted: Vec::new(),
+            already_posted: Vec::new(),
+        };
+        let mut transfers = Vec::new();
+        let mut names = Vec::new();
+        for (wallet, signed) in wallets {
+            let opening = self.opening_balances_wallet(&wallet.currency).await?;
+            // A fully reversed opening balance no longer counts, so a corrected one can be posted
+            let history = self.repo.list_transfers_for_wallet(wallet.id).await?;
+            let existing = history
+                .iter()
+                .find(|t| {
+                    (t.from_wallet == opening.id || t.to_wallet == opening.id)
+                        && !t.is_reversal()
+                        && total_reversed_amount(t.id, &history) < t.amount_cents
+                })
+                .cloned();
+            if let Some(existing) = existing {
+                result.already_posted.push((wallet.name, existing));
+                continue;
+            }
+            let Some(transfer) = settle_balance(wallet.id, opening.id, signed, timestamp) else {
+                continue;
+            };
+            transfers.push(
+                transfer
+                    .with_description("Opening balance")
+                    .with_cleared(ClearedState::Cleared),
+            );
+            names.push((wallet.id, wallet.name, opening.name));
+        }
+
+        self.repo.save_transfers(&mut transfers, &[]).await?;
+        for (transfer, (wallet_id, wallet_name, opening_name)) in transfers.into_iter().zip(names) {
+            // A positive balance comes in from the opening balances wallet
+            let (from_wallet_name, to_wallet_name) = if transfer.to_wallet == wallet_id {
+                (opening_name, wallet_name)
+            } else {
+                (wallet_name, opening_name)
+            };
+            result.posted.push(TransferResult {
+                transfer,
+                from_wallet_name,
+                to_wallet_name,
+                converted: None,
+            });
+        }
+        Ok(result)
+    }
+
+    /// The equity wallet opening balances of a currency are posted against.
+    async fn opening_balances_wallet(&self, currency: &str) -> Result<Wallet, AppError> {
+        self.get_or_create_counterpart_wallet(
+            format!("Opening Balances {}", currency),
+            WalletType::Equity,
+            currency,
+            "Opening balances",
+        )
+        .await
+    }
+
+    // ========================
+    // Balance assertion operations
+    // ========================
+
+    /// Assert that a wallet held exactly `balance_cents` at the end of `date`, as
+    /// the ledger shows it (negative for what is owed on a liability). The
+    /// assertion is kept and checked by `check_integrity` from then on; asserting
+    /// the same wallet and day again replaces it. With `adjust`, a difference is
+    /// posted against the "Adjustments" equity wallet of the wallet's currency on
+    /// the last second of the day, so that the assertion holds.
+    pub async fn assert_balance(
+        &self,
+        wallet_name: &str,
+        date: NaiveDate,
+        balance_cents: Cents,
+        adjust: bool,
+    ) -> Result<BalanceAssertionResult, AppError> {
+        let wallet = self.get_wallet(wallet_name).await?;
+        let assertion = BalanceAssertion::new(wallet.id, date, balance_cents);
+        let actual = self
+            .repo
+            .compute_balance_before(wallet.id, assertion.cutoff())
+            .await?;
+        let mut check = AssertionCheck::new(assertion, actual);
+
+        let mut adjustment = None;
+        if adjust && !check.holds() {
+            if wallet.is_archived() {
+                return Err(AppError::WalletArchived(wallet.name));
+            }
+            if wallet.is_holding() {
+                return Err(holding_transfer_error(&wallet));
+            }
+            self.ensure_postable(&wallet).await?;
+            let adjustments = self
+                .get_or_create_counterpart_wallet(
+                    format!("Adjustments {}", wallet.currency),
+                    WalletType::Equity,
+                    &wallet.currency,
+                    "Balance adjustments",
+                )
+                .await?;
+            let timestamp = check.assertion.adjustment_timestamp();
+            self.check_period_lock(&[&wallet, &adjustments], &[timestamp], None)
+                .await?;
+
+            if let Some(transfer) =
+                settle_balance(wallet.id, adjustments.id, check.difference(), timestamp)
+            {
+                let transfer = transfer.with_description(format!("Balance adjustment on {}", date));
+                let (from_wallet_name, to_wallet_name) = if transfer.to_wallet == wallet.id {
+                    (adjustments.name, wallet.name.clone())
+                } else {
+                    (wallet.name.clone(), adjustments.name)
+                };
+                adjustment = Some(TransferResult {
+                    transfer,
+                    from_wallet_name,
+                    to_wallet_name,
+                    converted: None,
+                });
+            }
+        }
+
+        // The adjustment and the assertion it satisfies are stored together
+        self.repo
+            .save_balance_assertion(
+                &check.assertion,
+                adjustment.as_mut().map(|result| &mut result.transfer),
+            )
+            .await?;
+        if adjustment.is_some() {
+            check.actual_cents = self
+                .repo
+                .compute_balance_before(wallet.id, check.assertion.cutoff())
+                .await?;
+        }
+        Ok(BalanceAssertionResult { check, adjustment })
+    }
+
+    /// Every assertion with the balance the ledger computes for it, by day.
+    pub async fn check_balance_assertions(&self) -> Result<Vec<AssertionCheck>, AppError> {
+        let mut checks = Vec::new();
+        for assertion in self.repo.list_balance_assertions().await? {
+            let actual = self
+                .repo
+                .compute_balance_before(assertion.wallet_id, assertion.cutoff())
+                .await?;
+            checks.push(AssertionCheck::new(assertion, actual));
+        }
+        Ok(checks)
+    }
+
+    /// Every assertion, by day.
+    pub async fn list_balance_assertions(&self) -> Result<Vec<BalanceAssertion>, AppError> {
+        Ok(self.repo.list_balance_assertions().await?)
+    }
+
+    /// Drop the assertion made for a wallet and day.
+    pub async fn remove_balance_assertion(
+        &self,
+        wallet_name: &str,
+        date: NaiveDate,
+    ) -> Result<(), AppError> {
+        let wallet = self.get_wallet(wallet_name).await?;
+        if !self.repo.delete_balance_assertion(wallet.id, date).await? {
+            return Err(AppError::BalanceAssertionNotFound(format!(
+                "{} on {}",
+                wallet.name, date
+            )));
+        }
+        Ok(())
+    }
+
+    // ========================
+    // Currency operations
+    // ========================
+
+    /// Get a currency with its minor units: a registered currency if any,
+    /// otherwise the built-in ISO 4217 table (two decimals for unknown codes).
+    pub async fn get_currency(&self, code: &str) -> Result<Currency, AppError> {
+        let code = code.trim();
+        if code.is_empty() {
+            return Err(AppError::InvalidCurrency("empty currency code".to_string()));
+        }
+        match self.repo.get_currency(code).await? {
+            Some(currency) => Ok(currency),
+            None => Ok(Currency::iso(code)),
+        }
+    }
+
+    /// Get the currency of a wallet.
+    pub async fn get_wallet_currency(&self, wallet_name: &str) -> Result<Currency, AppError> {
+        let wallet = self.get_wallet(wallet_name).await?;
+        self.get_currency(&wallet.currency).await
+    }
+
+    /// Get the currency of every wallet, keyed by wallet ID (useful for display).
+    pub async fn get_wallet_currencies(&self) -> Result<HashMap<WalletId, Currency>, AppError> {
+        let wallets = self.repo.list_wallets(true).await?;
+        let mut by_code: HashMap<String, Currency> = HashMap::new();
+        let mut currencies = HashMap::new();
+        for wallet in wallets {
+            if !by_code.contains_key(&wallet.currency) {
+                let currency = self.get_currency(&wallet.currency).await?;
+                by_code.insert(wallet.currency.clone(), currency);
+            }
+            currencies.insert(wallet.id, by_code[&wallet.currency].clone());
+        }
+        Ok(currencies)
+    }
+
+    /// Register a currency's minor units (e.g. a crypto asset with 8 decimals).
+    /// Changing the minor units of a currency that already has transfers is refused,
+    /// since the stored amounts would change meaning.
+    pub async fn register_currency(
+        &self,
+        code: &str,
+        minor_units: u32,
+    ) -> Result<Currency, AppError> {
+        if code.trim().is_empty() {
+            return Err(AppError::InvalidCurrency("empty currency code".to_string()));
+        }
+        if minor_units > MAX_MINOR_UNITS {
+            return Err(AppError::InvalidCurrency(format!(
+                "{} decimals is more than the supported {}",
+                minor_units, MAX_MINOR_UNITS
+            )));
+        }
+
+        let currency = Currency::new(code.trim(), minor_units);
+        let current = self.get_currency(&currency.code).await?;
+        if current.minor_units != minor_units
+            && self
+                .repo
+                .count_transfers_in_currency(&currency.code)
+                .await?
+                > 0
+        {
+            return Err(AppError::CurrencyInUse(currency.code));
+        }
+
+        self.repo.save_currency(&currency).await?;
+        Ok(currency)
+    }
+
+    /// List registered currencies (built-in ones are not included).
+    pub async fn list_currencies(&self) -> Result<Vec<Currency>, AppError> {
+        Ok(self.repo.list_currencies().await?)
+    }
+
+    /// Parse an amount in the given currency's minor units.
+    pub async fn parse_amount(
+        &self,
+        input: &str,
+        currency_code: &str,
+        rounding: RoundingMode,
+    ) -> Result<Money, AppError> {
+        let currency = self.get_currency(currency_code).await?;
+        Money::parse(input, currency, rounding)
+            .map_err(|e| AppError::InvalidAmount(format!("'{}': {}", input, e)))
+    }
+
+    // ========================
+    // Locale operations
+    // ========================
+
+    /// Get a locale profile by name: built-in profiles first, then custom ones.
+    pub async fn get_locale(&self, name: &str) -> Result<LocaleProfile, AppError> {
+        if let Some(profile) = LocaleProfile::find_builtin(name) {
+            return Ok(profile);
+        }
+        self.repo
+            .get_locale_profile(name)
+            .await?
+            .ok_or_else(|| AppError::LocaleNotFound(name.to_string()))
+    }
+
+    /// List built-in and custom locale profiles.
+    pub async fn list_locales(&self) -> Result<Vec<LocaleProfile>, AppError> {
+        let mut profiles = LocaleProfile::builtin();
+        profiles.extend(self.repo.list_locale_profiles().await?);
+        Ok(profiles)
+    }
+
+    /// Save a custom locale profile. Built-in names cannot be redefined.
+    pub async fn save_locale(&self, profile: LocaleProfile) -> Result<LocaleProfile, AppError> {
+        if profile.name.trim().is_empty() {
+            return Err(AppError::InvalidLocale("empty profile name".to_string()));
+        }
+        if LocaleProfile::find_builtin(&profile.name).is_some() {
+            return Err(AppError::InvalidLocale(format!(
+                "'{}' is a built-in profile",
+                profile.name
+            )));
+        }
+        profile.validate().map_err(AppError::InvalidLocale)?;
+
+        self.repo.save_locale_profile(&profile).await?;
+        Ok(profile)
+    }
+
+    /// The locale configured for this ledger, if any.
+    pub async fn get_default_locale(&self) -> Result<Option<LocaleProfile>, AppError> {
+        match self.repo.get_setting(LOCALE_SETTING).await? {
+            Some(name) => Ok(Some(self.get_locale(&name).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Set the ledger's locale, or clear it to go back to the plain format.
+    pub async fn set_default_locale(
+        &self,
+        name: Option<&str>,
+    ) -> Result<Option<LocaleProfile>, AppError> {
+        match name {
+            Some(name) => {
+                let profile = self.get_locale(name).await?;
+                self.repo.set_setting(LOCALE_SETTING, &profile.name).await?;
+                Ok(Some(profile))
+            }
+            None => {
+                self.repo.delete_setting(LOCALE_SETTING).await?;
+                Ok(None)
+            }
+        }
+    }
+
+    // ========================
+    // Exchange rate operations
+    // ========================
+
+    /// Store a dated exchange rate: one unit of `base` is worth `rate` units of `quote`.
+    pub async fn add_exchange_rate(
+        &self,
+        base: &str,
+        quote: &str,
+        rate: Rate,
+        effective_date: DateTime<Utc>,
+    ) -> Result<ExchangeRate, AppError> {
+        let base = base.trim().to_uppercase();
+        let quote = quote.trim().to_uppercase();
+        if base == quote {
+            return Err(AppError::InvalidExchangeRate(format!(
+                "{} cannot be converted into itself",
+                base
+            )));
+        }
+
+        let exchange_rate = ExchangeRate::new(base, quote, rate, effective_date);
+        self.repo.save_exchange_rate(&exchange_rate).await?;
+        Ok(exchange_rate)
+    }
+
+    /// List all stored exchange rates.
+    pub async fn list_exchange_rates(&self) -> Result<Vec<ExchangeRate>, AppError> {
+        Ok(self.repo.list_exchange_rates().await?)
+    }
+
+    /// Get the rate converting `from` into `to` as of a date. Uses the most recent
+    /// stored rate for the pair, or the inverse of a rate stored the other way round.
+    pub async fn get_exchange_rate(
+        &self,
+        from: &str,
+        to: &str,
+        as_of: DateTime<Utc>,
+    ) -> Result<Rate, AppError> {
+        let from = from.to_uppercase();
+        let to = to.to_uppercase();
+        if from == to {
+            return Ok(Rate::ONE);
+        }
+
+        let direct = self.repo.find_exchange_rate(&from, &to, as_of).await?;
+        let inverse = self.repo.find_exchange_rate(&to, &from, as_of).await?;
+
+        match (direct, inverse) {
+            (Some(direct), Some(inverse)) if inverse.effective_date > direct.effective_date => {
+                Ok(inverse.rate.inverse())
+            }
+            (Some(direct), _) => Ok(direct.rate),
+            (None, Some(inverse)) => Ok(inverse.rate.inverse()),
+            (None, None) => Err(AppError::ExchangeRateNotFound {
+                from_currency: from,
+                to_currency: to,
+                as_of,
+            }),
+        }
+    }
+
+    /// Rates converting minor units of each of the given currencies into minor
+    /// units of `target` as of a date (the exchange rate adjusted for decimals).
+    async fn conversion_rates(
+        &self,
+        currencies: impl IntoIterator<Item = &String>,
+        target: &str,
+        as_of: DateTime<Utc>,
+    ) -> Result<HashMap<String, Rate>, AppError> {
+        let target_currency = self.get_currency(target).await?;
+        let mut rates = HashMap::new();
+        for currency in currencies {
+            if !rates.contains_key(currency) {
+                let rate = self.get_exchange_rate(currency, target, as_of).await?;
+                let source_currency = self.get_currency(currency).await?;
+                let per_minor_unit = rate
+                    .between_minor_units(&source_currency, &target_currency)
+                    .ok_or_else(|| {
+                        AppError::InvalidExchangeRate(format!(
+                            "{} {}/{} is out of range",
+                            rate, currency, target
+                        ))
+                    })?;
+                rates.insert(currency.clone(), per_minor_unit);
+            }
+        }
+        Ok(rates)
+    }
+
+    // ========================
+    // Investment operations
+    // ========================
+
+    /// Buy commodity units: the cost (quantity x unit price) moves from the cash
+    /// wallet into the holding wallet as a new lot, and the price is added to the
+    /// price history.
+    pub async fn record_buy(&self, request: TradeRequest) -> Result<TransferResult, AppError> {
+        let (holding, cash, amount_cents) = self.prepare_trade(&request).await?;
+        let lock_override = self
+            .check_period_lock(
+                &[&holding, &cash],
+                &[request.timestamp],
+                request.lock_override.clone(),
+            )
+            .await?;
+
+        if !cash.allow_negative && !request.force {
+            let current_balance = self.repo.compute_balance(cash.id).await?;
+            if current_balance < amount_cents {
+                return Err(AppError::InsufficientFunds {
+                    wallet_name: cash.name,
+                    balance: current_balance,
+                    required: amount_cents,
+                });
+            }
+        }
+
+        let mut transfer = Transfer::new(cash.id, holding.id, amount_cents, request.timestamp)
+            .with_quantity(request.quantity, request.unit_price);
+        if let Some(desc) = &request.description {
+            transfer = transfer.with_description(desc.clone());
+        }
+        let overrides = lock_overrides(
+            &[transfer.id],
+            LockedAction::Transfer,
+            lock_override.as_ref(),
+        );
+        self.repo
+            .save_transfers(std::slice::from_mut(&mut transfer), &overrides)
+            .await?;
+        self.record_trade_price(&holding, &request).await?;
+
+        Ok(TransferResult {
+            transfer,
+            from_wallet_name: cash.name,
+            to_wallet_name: holding.name,
+            converted: None,
+        })
+    }
+
+    /// Sell commodity units: the proceeds (quantity x unit price) move from the
+    /// holding wallet into the cash wallet. The lots consumed are picked with the
+    /// request's method (the wallet's default otherwise), and the difference between
+    /// the proceeds and their cost is booked as a realized gain from the
+    /// "Realized Gains <CCY>" income wallet, or a loss into "Realized Losses <CCY>".
+    pub async fn record_sell(&self, request: TradeRequest) -> Result<SaleResult, AppError> {
+        let (holding, cash, proceeds) = self.prepare_trade(&request).await?;
+
+        let held = self.repo.compute_quantity(holding.id).await?;
+        if held < request.quantity {
+            return Err(AppError::InsufficientUnits {
+                wallet_name: holding.name,
+                held,
+                required: request.quantity,
+            });
+        }
+
+        let method = match request.lot_method {
+            Some(method) => method,
+            None if !request.lots.is_empty() => LotMethod::Specific,
+            None => holding.lot_method,
+        };
+        let lots = self.open_lots(&holding).await?;
+        let allocations = allocate_sale(&lots, request.quantity, proceeds, method, &request.lots)
+            .map_err(AppError::InvalidTrade)?;
+        let cost_basis: Cents = allocations.iter().map(|a| a.cost_cents).sum();
+        let realized_gain = proceeds - cost_basis;
+
+        let mut trade = Transfer::new(holding.id, cash.id, proceeds, request.timestamp)
+            .with_quantity(request.quantity, request.unit_price);
+        if let Some(desc) = &request.description {
+            trade = trade.with_description(desc.clone());
+        }
+        let mut transfers = vec![trade];
+        let gain_wallet = match realized_gain.cmp(&0) {
+            std::cmp::Ordering::Greater => Some(
+                self.get_or_create_counterpart_wallet(
+                    format!("Realized Gains {}", holding.currency),
+                    WalletType::Income,
+                    &holding.currency,
+                    "Realized gains on investments",
+                )
+                .await?,
+            ),
+            std::cmp::Ordering::Less => Some(
+                self.get_or_create_counterpart_wallet(
+                    format!("Realized Losses {}", holding.currency),
+                    WalletType::Expense,
+                    &holding.currency,
+                    "Realized losses on investments",
+                )
+                .await?,
+            ),
+            std::cmp::Ordering::Equal => None,
+        };
+        let touched: Vec<&Wallet> = [&holding, &cash]
+            .into_iter()
+            .chain(gain_wallet.as_ref())
+            .collect();
+        let lock_override = self
+            .check_period_lock(
+                &touched,
+                &[request.timestamp],
+                request.lock_override.clone(),
+            )
+            .await?;
+        if let Some(gain_wallet) = &gain_wallet {
+            let commodity = holding.commodity.as_deref().unwrap_or_default();
+            let leg = if realized_gain > 0 {
+                Transfer::new(gain_wallet.id, holding.id, realized_gain, request.timestamp)
+                    .with_description(format!("Realized gain on {}", commodity))
+            } else {
+                Transfer::new(
+                    holding.id,
+                    gain_wallet.id,
+                    -realized_gain,
+                    request.timestamp,
+                )
+                .with_description(format!("Realized loss on {}", commodity))
+            };
+            // The sale and its gain are one transaction, reversed together
+            let split_id = uuid::Uuid::new_v4();
+            transfers[0].split_id = Some(split_id);
+            transfers.push(leg.with_split(split_id));
+        }
+
+        let disposals: Vec<LotDisposal> = allocations
+            .iter()
+            .map(|allocation| LotDisposal {
+                id: uuid::Uuid::new_v4(),
+                sell_transfer_id: transfers[0].id,
+                wallet_id: holding.id,
+                lot_id: allocation.lot_id,
+                acquired_at: lots
+                    .iter()
+                    .find(|lot| lot.id == allocation.lot_id)
+                    .map(|lot| lot.acquired_at)
+                    .unwrap_or(request.timestamp),
+                disposed_at: request.timestamp,
+                quantity: allocation.quantity,
+                cost_cents: allocation.cost_cents,
+                proceeds_cents: allocation.proceeds_cents,
+                method,
+            })
+            .collect();
+
+        let ids: Vec<TransferId> = transfers.iter().map(|t| t.id).collect();
+        let overrides = lock_overrides(&ids, LockedAction::Transfer, lock_override.as_ref());
+        self.repo
+            .save_sale(&mut transfers, &disposals, &overrides)
+            .await?;
+        self.record_trade_price(&holding, &request).await?;
+
+        let mut transfers = transfers.into_iter();
+        let transfer = transfers
+            .next()
+            .ok_or_else(|| AppError::InvalidTrade("sale was not recorded".to_string()))?;
+        Ok(SaleResult {
+            trade: TransferResult {
+                transfer,
+                from_wallet_name: holding.name,
+                to_wallet_name: cash.name,
+                converted: None,
+            },
+            cost_basis,
+            realized_gain,
+            gain_leg: transfers.next(),
+            disposals,
+        })
+    }
+
+    /// Validate a trade and compute its cash amount in the holding wallet's currency.
+    async fn prepare_trade(
+        &self,
+        request: &TradeRequest,
+    ) -> Result<(Wallet, Wallet, Cents), AppError> {
+        if request.quantity <= 0 {
+            return Err(AppError::InvalidTrade(
+                "Quantity must be positive".to_string(),
+            ));
+        }
+        if !CommodityPrice::is_valid_price(request.unit_price) {
+            return Err(AppError::InvalidTrade(format!(
+                "unit price {} (must be positive)",
+                request.unit_price
+            )));
+        }
+
+        let holding = self.get_wallet(&request.holding_wallet).await?;
+        let cash = self.get_wallet(&request.cash_wallet).await?;
+        for wallet in [&holding, &cash] {
+            if wallet.is_archived() {
+                return Err(AppError::WalletArchived(wallet.name.clone()));
+            }
+            self.ensure_postable(wallet).await?;
+        }
+        let Some(commodity) = &holding.commodity else {
+            return Err(AppError::InvalidTrade(format!(
+                "'{}' does not hold a commodity",
+                holding.name
+            )));
+        };
+        if cash.is_holding() {
+            return Err(AppError::InvalidTrade(format!(
+                "'{}' holds units, not cash",
+                cash.name
+            )));
+        }
+        if holding.currency != cash.currency {
+            return Err(AppError::CurrencyMismatch {
+                from_currency: cash.currency,
+                to_currency: holding.currency,
+            });
+        }
+
+        let units = self.get_currency(commodity).await?;
+        let currency = self.get_currency(&holding.currency).await?;
+        let amount_cents = Money::new(request.quantity, units)
+            .at_price(request.unit_price, currency)
+            .amount;
+        if amount_cents <= 0 {
+            return Err(AppError::InvalidAmount(
+                "Trade amount rounds to zero".to_string(),
+            ));
+        }
+
+        Ok((holding, cash, amount_cents))
+    }
+
+    /// Add a trade's unit price to the price history, in the holding's currency.
+    async fn record_trade_price(
+        &self,
+        holding: &Wallet,
+        request: &TradeRequest,
+    ) -> Result<(), AppError> {
+        if let Some(commodity) = &holding.commodity {
+            let price = CommodityPrice::new(
+                commodity.clone(),
+                holding.currency.clone(),
+                request.unit_price,
+                request.timestamp,
+            );
+            self.repo.save_commodity_price(&price).await?;
+        }
+        Ok(())
+    }
+
+    /// Set how sales from a holding wallet pick lots when the sale does not say.
+    pub async fn set_lot_method(
+        &self,
+        wallet_name: &str,
+        method: LotMethod,
+    ) -> Result<Wallet, AppError> {
+        let wallet = self.get_wallet(wallet_name).await?;
+        if !wallet.is_holding() {
+            return Err(AppError::InvalidTrade(format!(
+                "'{}' does not hold a commodity",
+                wallet.name
+            )));
+        }
+        if method == LotMethod::Specific {
+            return Err(AppError::InvalidTrade(
+                "specific identification is chosen per sale, not as a default".to_string(),
+            ));
+        }
+
+        self.repo
+            .update_wallet_lot_method(wallet.id, method)
+            .await?;
+        Ok(wallet.with_lot_method(method))
+    }
+
+    /// List the open lots of a holding wallet, oldest first.
+    pub async fn list_lots(&self, wallet_name: &str) -> Result<Vec<Lot>, AppError> {
+        let wallet = self.get_wallet(wallet_name).await?;
+        if !wallet.is_holding() {
+            return Err(AppError::InvalidTrade(format!(
+                "'{}' does not hold a commodity",
+                wallet.name
+            )));
+        }
+        self.open_lots(&wallet).await
+    }
+
+    /// List every lot disposal recorded by sales, reversed ones included.
+    pub async fn list_lot_disposals(&self) -> Result<Vec<LotDisposal>, AppError> {
+        Ok(self.repo.list_lot_disposals().await?)
+    }
+
+    /// Replay a holding wallet's trades into its open lots: every buy opens a lot
+    /// and every sale takes out the units its disposals name. Reversed trades and
+    /// their reversals cancel out; sales recorded before lot tracking consume FIFO.
+    async fn open_lots(&self, wallet: &Wallet) -> Result<Vec<Lot>, AppError> {
+        let trades: Vec<Transfer> = self
+            .repo
+            .list_transfers_for_wallet(wallet.id)
+            .await?
+            .into_iter()
+            .filter(|t| t.is_trade())
+            .collect();
+        let reversed: std::collections::HashSet<TransferId> =
+            trades.iter().filter_map(|t| t.reverses).collect();
+        let mut disposals: HashMap<TransferId, Vec<LotDisposal>> = HashMap::new();
+        for disposal in self.repo.list_lot_disposals_for_wallet(wallet.id).await? {
+            disposals
+                .entry(disposal.sell_transfer_id)
+                .or_default()
+                .push(disposal);
+        }
+
+        let mut lots: Vec<Lot> = Vec::new();
+        for trade in trades
+            .iter()
+            .filter(|t| !t.is_reversal() && !reversed.contains(&t.id))
+        {
+            let quantity = trade.quantity.unwrap_or(0);
+            if trade.to_wallet == wallet.id {
+                lots.push(Lot::new(
+                    trade.id,
+                    wallet.id,
+                    trade.timestamp,
+                    quantity,
+                    trade.amount_cents,
+                ));
+                continue;
+            }
+
+            match disposals.get(&trade.id) {
+                Some(taken) => {
+                    for disposal in taken {
+                        if let Some(lot) = lots.iter_mut().find(|l| l.id == disposal.lot_id) {
+                            lot.dispose(disposal.quantity, disposal.cost_cents);
+                        }
+                    }
+                }
+                None => {
+                    let open: Vec<Lot> = lots.iter().filter(|l| l.is_open()).cloned().collect();
+                    let allocations =
+                        allocate_sale(&open, quantity, trade.amount_cents, LotMethod::Fifo, &[])
+                            .map_err(AppError::InvalidTrade)?;
+                    for allocation in allocations {
+                        if let Some(lot) = lots.iter_mut().find(|l| l.id == allocation.lot_id) {
+                            lot.dispose(allocation.quantity, allocation.cost_cents);
+                        }
+                    }
+                }
+            }
+        }
+
+        lots.retain(|lot| lot.is_open());
+        Ok(lots)
+    }
+
+    /// Store a dated price: one unit of `commodity` is worth `price` units of `currency`.
+    pub async fn add_price(
+        &self,
+        commodity: &str,
+        currency: &str,
+        price: f64,
+        effective_date: DateTime<Utc>,
+    ) -> Result<CommodityPrice, AppError> {
+        if !CommodityPrice::is_valid_price(price) {
+            return Err(AppError::InvalidTrade(format!(
+                "price {} (must be positive)",
+                price
+            )));
+        }
+        let commodity = commodity.trim().to_uppercase();
+        let currency = currency.trim().to_uppercase();
+        if commodity.is_empty() || currency.is_empty() {
+            return Err(AppError::InvalidTrade(
+                "a price needs a commodity and a currency".to_string(),
+            ));
+        }
+
+        let price = CommodityPrice::new(commodity, currency, price, effective_date);
+        self.repo.save_commodity_price(&price).await?;
+        Ok(price)
+    }
+
+    /// List stored prices, optionally for a single commodity.
+    pub async fn list_prices(
+        &self,
+        commodity: Option<&str>,
+    ) -> Result<Vec<CommodityPrice>, AppError> {
+        let commodity = commodity.map(|c| c.trim().to_uppercase());
+        Ok(self
+            .repo
+            .list_commodity_prices(commodity.as_deref())
+            .await?)
+    }
+
+    /// Get the latest price of a commodity as of a date, expressed in `currency`.
+    /// A price stored in another currency is converted at the exchange rate of its date.
+    pub async fn get_price(
+        &self,
+        commodity: &str,
+        currency: &str,
+        as_of: DateTime<Utc>,
+    ) -> Result<CommodityPrice, AppError> {
+        let commodity = commodity.trim().to_uppercase();
+        let currency = currency.trim().to_uppercase();
+        let mut price = self
+            .repo
+            .find_commodity_price(&commodity, as_of)
+            .await?
+            .ok_or(AppError::PriceNotFound { commodity, as_of })?;
+
+        if price.currency != currency {
+            let rate = self
+                .get_exchange_rate(&price.currency, &currency, price.effective_date)
+                .await?;
+            price.price *= rate.to_f64();
+            price.currency = currency;
+        }
+        Ok(price)
+    }
+
+    /// Value of `quantity` units at a price, in the price's currency.
+    async fn market_value(
+        &self,
+        commodity: &str,
+        quantity: i64,
+        price: &CommodityPrice,
+    ) -> Result<Cents, AppError> {
+        let units = self.get_currency(commodity).await?;
+        let currency = self.get_currency(&price.currency).await?;
+        Ok(Money::new(quantity, units)
+            .at_price(price.price, currency)
+            .amount)
+    }
+
+    /// Market value of every holding wallet at the latest known price, keyed by
+    /// wallet ID. Holdings without any recorded price are left out.
+    async fn holding_values(
+        &self,
+        as_of: DateTime<Utc>,
+    ) -> Result<HashMap<WalletId, Cents>, AppError> {
+        let mut values = HashMap::new();
+        for wallet in self.repo.list_wallets(false).await? {
+            let Some(commodity) = &wallet.commodity else {
+                continue;
+            };
+            let quantity = self.repo.compute_quantity(wallet.id).await?;
+            match self.get_price(commodity, &wallet.currency, as_of).await {
+                Ok(price) => {
+                    let value = self.market_value(commodity, quantity, &price).await?;
+                    values.insert(wallet.id, value);
+                }
+                Err(AppError::PriceNotFound { .. }) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(values)
+    }
+
+    /// Report every holding with its units, latest price, market value,
+    /// cost basis (of its open lots) and unrealized gain.
+    pub async fn get_holdings_report(&self) -> Result<HoldingsReport, AppError> {
+        let now = Utc::now();
+        let mut holdings = Vec::new();
+
+        for wallet in self.repo.list_wallets(false).await? {
+            let Some(commodity) = wallet.commodity.clone() else {
+                continue;
+            };
+            let lots = self.open_lots(&wallet).await?;
+            if lots.is_empty() {
+                continue;
+            }
+            let quantity: i64 = lots.iter().map(|lot| lot.remaining_quantity).sum();
+            let cost_basis: Cents = lots.iter().map(|lot| lot.remaining_cost).sum();
+
+            let price = match self.get_price(&commodity, &wallet.currency, now).await {
+                Ok(price) => Some(price),
+                Err(AppError::PriceNotFound { .. }) => None,
+                Err(e) => return Err(e),
+            };
+            // Without a price the holding is carried at cost
+            let market_value = match &price {
+                Some(price) => self.market_value(&commodity, quantity, price).await?,
+                None => cost_basis,
+            };
+
+            holdings.push(Holding {
+                wallet_name: wallet.name,
+                quantity_decimals: self.get_currency(&commodity).await?.minor_units,
+                commodity,
+                quantity,
+                price: price.as_ref().map(|p| p.price),
+                price_date: price.as_ref().map(|p| p.effective_date),
+                currency: wallet.currency,
+                market_value,
+                cost_basis,
+                unrealized_gain: market_value - cost_basis,
+            });
+        }
+
+        Ok(HoldingsReport {
+            as_of: now,
+            total_market_value: holdings.iter().map(|h| h.market_value).sum(),
+            total_cost_basis: holdings.iter().map(|h| h.cost_basis).sum(),
+            total_unrealized_gain: holdings.iter().map(|h| h.unrealized_gain).sum(),
+            holdings,
+        })
+    }
+
+    /// Report the gains and losses realized by sales made in a calendar year,
+    /// one row per lot consumed. Reversed sales are left out.
+    pub async fn get_realized_gains_report(
+        &self,
+        year: i32,
+    ) -> Result<RealizedGainsReport, AppError> {
+        let from_date = year_start(year)?;
+        let to_date = year_start(year + 1)?;
+        let disposals = self
+            .repo
+            .list_lot_disposals_between(from_date, to_date)
+            .await?;
+
+        let mut wallets: HashMap<WalletId, Wallet> = HashMap::new();
+        let mut gains = Vec::with_capacity(disposals.len());
+        for disposal in disposals {
+            if !wallets.contains_key(&disposal.wallet_id) {
+                let wallet = self.get_wallet_by_id(disposal.wallet_id).await?;
+                wallets.insert(wallet.id, wallet);
+            }
+            let wallet = &wallets[&disposal.wallet_id];
+            let commodity = wallet.commodity.clone().unwrap_or_default();
+
+            gains.push(RealizedGain {
+                wallet_name: wallet.name.clone(),
+                quantity_decimals: self.get_currency(&commodity).await?.minor_units,
+                commodity,
+                currency: wallet.currency.clone(),
+                lot_id: disposal.lot_id,
+                acquired_at: disposal.acquired_at,
+                disposed_at: disposal.disposed_at,
+                quantity: disposal.quantity,
+                cost_basis: disposal.cost_cents,
+                proceeds: disposal.proceeds_cents,
+                gain: disposal.gain(),
+                method: disposal.method,
+            });
+        }
+
+        Ok(RealizedGainsReport {
+            year,
+            from_date,
+            to_date,
+            total_proceeds: gains.iter().map(|g| g.proceeds).sum(),
+            total_cost_basis: gains.iter().map(|g| g.cost_basis).sum(),
+            total_gain: gains.iter().map(|g| g.gain).sum(),
+            gains,
+        })
+    }
+
+    /// Find the transfer already tied to a bank reference, if any.
+    pub async fn find_transfer_by_external_ref(
+        &self,
+        external_ref: &str,
+    ) -> Result<Option<Transfer>, AppError> {
+        Ok(self.repo.get_transfer_by_external_ref(external_ref).await?)
+    }
+
+    /// Find existing transfers that could be the ledger entry for a bank
+    /// statement line, best first. Transfers that have been reversed are left out.
+    pub async fn find_statement_matches(
+        &self,
+        line: &StatementLine,
+        window_days: i64,
+    ) -> Result<Vec<MatchCandidate>, AppError> {
+        let mut transfers = self
+            .repo
+            .list_transfers_for_wallet(line.from_wallet)
+            .await?;
+        if line.to_wallet != line.from_wallet {
+            for transfer in self.repo.list_transfers_for_wallet(line.to_wallet).await? {
+                if transfer.from_wallet != line.from_wallet
+                    && transfer.to_wallet != line.from_wallet
+                {
+                    transfers.push(transfer);
+                }
+            }
+        }
+        let reversed: std::collections::HashSet<TransferId> =
+            transfers.iter().filter_map(|t| t.reverses).collect();
+        transfers.retain(|t| !reversed.contains(&t.id));
+
+        Ok(find_match_candidates(line, &transfers, window_days))
+    }
+
+    /// Tie a transfer to the statement line it showed up on: it becomes cleared
+    /// (reconciled ones stay reconciled) and keeps the bank's reference.
+    pub async fn match_statement_line(
+        &self,
+        transfer_id: TransferId,
+        external_ref: Option<String>,
+    ) -> Result<Transfer, AppError> {
+        let transfer = self
+            .repo
+            .get_transfer(transfer_id)
+            .await?
+            .ok_or_else(|| AppError::TransferNotFound(transfer_id.to_string()))?;
+        if let (Some(existing), Some(new)) = (&transfer.external_ref, &external_ref)
+            && existing != new
+        {
+            return Err(AppError::InvalidReconciliation(format!(
+                "transfer {} is already matched to bank reference {}",
+                transfer_id, existing
+            )));
+        }
+
+        self.repo
+            .update_transfer_match(transfer_id, external_ref.as_deref())
+            .await?;
+        self.repo
+            .get_transfer(transfer_id)
+            .await?
+            .ok_or_else(|| AppError::TransferNotFound(transfer_id.to_string()))
+    }
+
+    // ========================
+    // Reconciliation operations
+    // ========================
+
+    /// Start reconciling a wallet against a bank statement ending on
+    /// `statement_date` with `statement_balance`.
+    pub async fn start_reconciliation(
+        &self,
+        wallet_name: &str,
+        statement_date: DateTime<Utc>,
+        statement_balance: Cents,
+    ) -> Result<ReconciliationStatus, AppError> {
+        let wallet = self.get_wallet(wallet_name).await?;
+        if self
+            .repo
+            .get_open_reconciliation(wallet.id)
+            .await?
+            .is_some()
+        {
+            return Err(AppError::ReconciliationInProgress(wallet.name));
+        }
+
+        let reconciliation = Reconciliation::new(wallet.id, statement_date, statement_balance);
+        self.repo.save_reconciliation(&reconciliation).await?;
+        self.reconciliation_status(wallet, reconciliation).await
+    }
+
+    /// Show the open reconcile session of a wallet.
+    pub async fn get_reconciliation_status(
+        &self,
+        wallet_name: &str,
+    ) -> Result<ReconciliationStatus, AppError> {
+        let (wallet, reconciliation) = self.open_reconciliation(wallet_name).await?;
+        self.reconciliation_status(wallet, reconciliation).await
+    }
+
+    /// Tick off transfers that appear on the statement being reconciled.
+    pub async fn tick_transfers(
+        &self,
+        wallet_name: &str,
+        ids: &[TransferId],
+    ) -> Result<ReconciliationStatus, AppError> {
+        let (wallet, reconciliation) = self.open_reconciliation(wallet_name).await?;
+        for id in ids {
+            let transfer = self.get_transfer_for_reconciliation(*id, &wallet).await?;
+            if !reconciliation.covers(transfer.timestamp) {
+                return Err(AppError::InvalidReconciliation(format!(
+                    "transfer {} is dated after the statement ({})",
+                    id,
+                    reconciliation.statement_date.format("%Y-%m-%d")
+                )));
+            }
+        }
+
+        self.repo
+            .update_transfers_cleared(ids, ClearedState::Cleared)
+            .await?;
+        self.reconciliation_status(wallet, reconciliation).await
+    }
+
+    /// Undo ticks made in the open reconcile session.
+    pub async fn untick_transfers(
+        &self,
+        wallet_name: &str,
+        ids: &[TransferId],
+    ) -> Result<ReconciliationStatus, AppError> {
+        let (wallet, reconciliation) = self.open_reconciliation(wallet_name).await?;
+        for id in ids {
+            self.get_transfer_for_reconciliation(*id, &wallet).await?;
+        }
+
+        self.repo
+            .update_transfers_cleared(ids, ClearedState::Uncleared)
+            .await?;
+        self.reconciliation_status(wallet, reconciliation).await
+    }
+
+    /// Finish the open reconcile session: once the cleared balance matches the
+    /// statement, every cleared transfer of the wallet becomes reconciled.
+    pub async fn finish_reconciliation(
+        &self,
+        wallet_name: &str,
+    ) -> Result<ReconciliationStatus, AppError> {
+        let (wallet, mut reconciliation) = self.open_reconciliation(wallet_name).await?;
+        let status = self
+            .reconciliation_status(wallet.clone(), reconciliation.clone())
+            .await?;
+        if status.difference != 0 {
+            return Err(AppError::ReconciliationDifference {
+                wallet_name: wallet.name,
+                difference: status.difference,
+            });
+        }
+
+        let cleared: Vec<Transfer> = self
+            .repo
+            .list_transfers_for_wallet(wallet.id)
+            .await?
+            .into_iter()
+            .filter(|t| t.cleared == ClearedState::Cleared)
+            .collect();
+        reconciliation.finished_at = Some(Utc::now());
+        self.repo
+            .finish_reconciliation(&reconciliation, &cleared)
+            .await?;
+
+        self.reconciliation_status(wallet, reconciliation).await
+    }
+
+    /// Abandon the open reconcile session. Ticked transfers stay cleared.
+    pub async fn cancel_reconciliation(&self, wallet_name: &str) -> Result<(), AppError> {
+        let (_, reconciliation) = self.open_reconciliation(wallet_name).await?;
+        self.repo
+            .delete_open_reconciliation(reconciliation.id)
+            .await?;
+        Ok(())
+    }
+
+    /// List reconcile sessions, newest statement first (all wallets when None).
+    pub async fn list_reconciliations(
+        &self,
+        wallet_name: Option<&str>,
+    ) -> Result<Vec<Reconciliation>, AppError> {
+        let wallet_id = match wallet_name {
+            Some(name) => Some(self.get_wallet(name).await?.id),
+            None => None,
+        };
+        Ok(self.repo.list_reconciliations(wallet_id).await?)
+    }
+
+    async fn open_reconciliation(
+        &self,
+        wallet_name: &str,
+    ) -> Result<(Wallet, Reconciliation), AppError> {
+        let wallet = self.get_wallet(wallet_name).await?;
+        match self.repo.get_open_reconciliation(wallet.id).await? {
+            Some(reconciliation) => Ok((wallet, reconciliation)),
+            None => Err(AppError::NoOpenReconciliation(wallet.name)),
+        }
+    }
+
+    /// Fetch a transfer that can be ticked or unticked in a wallet's session.
+    async fn get_transfer_for_reconciliation(
+        &self,
+        id: TransferId,
+        wallet: &Wallet,
+    ) -> Result<Transfer, AppError> {
+        let transfer = self
+            .repo
+            .get_transfer(id)
+            .await?
+            .ok_or_else(|| AppError::TransferNotFound(id.to_string()))?;
+        if transfer.from_wallet != wallet.id && transfer.to_wallet != wallet.id {
+            return Err(AppError::InvalidReconciliation(format!(
+                "transfer {} does not touch {}",
+                id, wallet.name
+            )));
+        }
+        if transfer.cleared == ClearedState::Reconciled {
+            return Err(AppError::InvalidReconciliation(format!(
+                "transfer {} is already reconciled",
+                id
+            )));
+        }
+        Ok(transfer)
+    }
+
+    async fn reconciliation_status(
+        &self,
+        wallet: Wallet,
+        reconciliation: Reconciliation,
+    ) -> Result<ReconciliationStatus, AppError> {
+        let transfers = self.repo.list_transfers_for_wallet(wallet.id).await?;
+        let cleared_balance = cleared_balance(wallet.id, &transfers);
+        let pending = transfers
+            .into_iter()
+            .filter(|t| t.cleared != ClearedState::Reconciled && reconciliation.covers(t.timestamp))
+            .collect();
+
+        Ok(ReconciliationStatus {
+            difference: reconciliation.difference(cleared_balance),
+            wallet_name: wallet.name,
+            cleared_balance,
+            pending,
+            reconciliation,
+        })
+    }
+
+    // ========================
+    // Integrity operations
+    // ========================
+
+    /// Check ledger integrity and return a report.
+    pub async fn check_integrity(&self) -> Result<IntegrityReport, AppError> {
+        let stats = self.repo.get_integrity_stats().await?;
+        let wallets = self.repo.list_wallets(true).await?;
+        let balances = self.repo.compute_all_balances().await?;
+
+        let mut report = build_integrity_report(
+            &wallets,
+            &balances,
+            stats.wallet_count,
+            stats.transfer_count,
+            stats.has_sequence_gaps,
+            stats.invalid_wallet_refs,
+            stats.invalid_amounts,
+        );
+        if stats.modified_reconciled > 0 {
+            report
+                .issues
+                .push(IntegrityIssue::ReconciledTransfersModified(
+                    stats.modified_reconciled,
+                ));
+        }
+        if stats.reversed_reconciled > 0 {
+            report
+                .issues
+                .push(IntegrityIssue::ReconciledTransfersReversed(
+                    stats.reversed_reconciled,
+                ));
+        }
+
+        let amendments = self.repo.list_amendments(None).await?;
+        if !amendments.is_empty() {
+            let transfers = self.repo.list_transfers().await?;
+            let unrecorded = count_unrecorded_edits(&transfers, &amendments);
+            if unrecorded > 0 {
+                report
+                    .issues
+                    .push(IntegrityIssue::UnrecordedEdits(unrecorded));
+            }
+        }
+
+        // Locks set after the fact show what was already recorded inside them
+        let locks = self.repo.list_period_locks().await?;
+        if !locks.is_empty() {
+            let transfers = self.repo.list_transfers().await?;
+            let overridden: HashSet<TransferId> = self
+                .repo
+                .list_lock_overrides()
+                .await?
+                .into_iter()
+                .map(|o| o.transfer_id)
+                .collect();
+            let violations =
+                count_lock_violations(&locks, &wallets, &transfers, &amendments, &overridden);
+            if violations > 0 {
+                report
+                    .issues
+                    .push(IntegrityIssue::LockedPeriodViolations(violations));
+            }
+        }
+
+        let failed = self
+            .check_balance_assertions()
+            .await?
+            .iter()
+            .filter(|check| !check.holds())
+            .count() as i64;
+        if failed > 0 {
+            report
+                .issues
+                .push(IntegrityIssue::FailedBalanceAssertions(failed));
+        }
+
+        Ok(report)
+    }
+
+    /// Get a map of wallet IDs to names (useful for display).
+    pub async fn get_wallet_names(&self) -> Result<HashMap<WalletId, String>, AppError> {
+        let wallets = self.repo.list_wallets(true).await?;
+        Ok(wallets.into_iter().map(|w| (w.id, w.name)).collect())
+    }
+
+    // ========================
+    // Budget operations
+    // ========================
+
+    /// Create a new budget.
+    pub async fn create_budget(
+        &self,
+        name: String,
+        category: String,
+        amount_cents: Cents,
+        period_type: PeriodType,
+    ) -> Result<Budget, AppError> {
+        self.create_budget_with(BudgetRequest::new(
+            name,
+            category,
+            amount_cents,
+            period_type,
+        ))
+        .await
+    }
+
+    /// Create a new budget described by a request.
+    pub async fn create_budget_with(&self, request: BudgetRequest) -> Result<Budget, AppError> {
+        // Check if budget already exists
+        if self.repo.get_budget_by_name(&request.name).await?.is_some() {
+            return Err(AppError::WalletAlreadyExists(request.name)); // Reuse error type
+        }
+
+        // The category may be left empty when the filter scopes the budget
+        let category = match request.category.trim() {
+            "" => String::new(),
+            category => normalize_category(category).map_err(AppError::InvalidCategory)?,
+        };
+        let mut categories: Vec<String> = Vec::new();
+        for extra in request.categories {
+            let extra = normalize_category(&extra).map_err(AppError::InvalidCategory)?;
+            if extra != category && !categories.contains(&extra) {
+                categories.push(extra);
+            }
+        }
+        let filter = BudgetFilter {
+            categories,
+            from_wallets: self.budget_wallet_ids(&request.from_wallets).await?,
+            to_wallets: self.budget_wallet_ids(&request.to_wallets).await?,
+            tags: clean_tags(&request.tags)?,
+            tag_match: request.tag_match,
+        };
+        if category.is_empty() && filter.is_empty() {
+            return Err(AppError::InvalidBudget(format!(
+                "{} needs a category, wallet or tag to count",
+                request.name
+            )));
+        }
+
+        let mut budget = Budget::new(
+            request.name,
+            category,
+            request.period_type,
+            request.amount_cents,
+        )
+        .with_filter(filter)
+        .with_rollover(request.rollover, request.rollover_since)
+        .with_alert_thresholds(clean_thresholds(request.alert_thresholds)?);
+        if let Some(currency) = request.currency {
+            budget = budget.with_currency(self.get_currency(&currency).await?.code);
+        }
+
+        self.repo.save_budget(&budget).await?;
+        Ok(budget)
+    }
+
+    /// IDs of the wallets named in a budget filter, without repeats.
+    async fn budget_wallet_ids(&self, names: &[String]) -> Result<Vec<WalletId>, AppError> {
+        let mut ids = Vec::new();
+        for name in names {
+            let id = self.get_wallet(name).await?.id;
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Get a budget by name.
+    pub async fn get_budget(&self, name: &str) -> Result<Budget, AppError> {
+        self.repo
+            .get_budget_by_name(name)
+            .await?
+            .ok_or_else(|| AppError::WalletNotFound(name.to_string())) // Reuse error type
+    }
+
+    /// List all budgets.
+    pub async fn list_budgets(&self) -> Result<Vec<Budget>, AppError> {
+        Ok(self.repo.list_budgets().await?)
+    }
+
+    /// Delete a budget.
+    pub async fn delete_budget(&self, name: &str) -> Result<Budget, AppError> {
+        let budget = self.get_budget(name).await?;
+        self.repo.delete_budget(name).await?;
+        Ok(budget)
+    }
+
+    /// Change how a budget carries what is left of a period into the next one.
+    pub async fn set_budget_rollover(
+        &self,
+        name: &str,
+        rollover: RolloverPolicy,
+        since: Option<NaiveDate>,
+    ) -> Result<Budget, AppError> {
+        let budget = self.get_budget(name).await?.with_rollover(rollover, since);
+        self.repo
+            .update_budget_rollover(budget.id, rollover, since)
+            .await?;
+        Ok(budget)
+    }
+
+    /// Set the percentages of a budget's limit that raise a budget event.
+    pub async fn set_budget_alert_thresholds(
+        &self,
+        name: &str,
+        thresholds: Vec<u32>,
+    ) -> Result<Budget, AppError> {
+        let budget = self
+            .get_budget(name)
+            .await?
+            .with_alert_thresholds(clean_thresholds(thresholds)?);
+        self.repo
+            .update_budget_alert_thresholds(budget.id, &budget.alert_thresholds)
+            .await?;
+        Ok(budget)
+    }
+
+    /// Events raised by all budgets, or by one, most recent first.
+    pub async fn list_budget_events(
+        &self,
+        name: Option<&str>,
+    ) -> Result<Vec<BudgetEvent>, AppError> {
+        let budget_id = match name {
+            Some(name) => Some(self.get_budget(name).await?.id),
+            None => None,
+        };
+        Ok(self.repo.list_budget_events(budget_id).await?)
+    }
+
+    /// Take the budget events raised since the last call, oldest first.
+    pub fn take_budget_events(&self) -> Vec<BudgetEvent> {
+        std::mem::take(&mut self.pending_budget_alerts().events)
+    }
+
+    /// Take the reasons budget alerts could not be checked since the last call.
+    /// The transfers that triggered the check were recorded all the same.
+    pub fn take_budget_alert_errors(&self) -> Vec<String> {
+        std::mem::take(&mut self.pending_budget_alerts().errors)
+    }
+
+    fn pending_budget_alerts(&self) -> std::sync::MutexGuard<'_, PendingBudgetAlerts> {
+        self.budget_alerts.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The command and file budget events are delivered to.
+    pub async fn get_budget_alert_hooks(&self) -> Result<BudgetAlertHooks, AppError> {
+        Ok(BudgetAlertHooks {
+            command: self.repo.get_setting(BUDGET_ALERT_COMMAND_SETTING).await?,
+            file: self.repo.get_setting(BUDGET_ALERT_FILE_SETTING).await?,
+        })
+    }
+
+    /// Set the command and file budget events are delivered to; `None` clears one.
+    pub async fn set_budget_alert_hooks(
+        &self,
+        hooks: BudgetAlertHooks,
+    ) -> Result<BudgetAlertHooks, AppError> {
+        for (key, value) in [
+            (BUDGET_ALERT_COMMAND_SETTING, &hooks.command),
+            (BUDGET_ALERT_FILE_SETTING, &hooks.file),
+        ] {
+            match value.as_deref().map(str::trim) {
+                Some(value) if !value.is_empty() => self.repo.set_setting(key, value).await?,
+                _ => self.repo.delete_setting(key).await?,
+            }
+        }
+        self.get_budget_alert_hooks().await
+    }
+
+    /// Get budget status (spending vs limit for current period).
+    pub async fn get_budget_status(&self, name: &str) -> Result<BudgetStatus, AppError> {
+        let budget = self.get_budget(name).await?;
+        self.budget_status(budget).await
+    }
+
+    /// Get status for all budgets.
+    pub async fn get_all_budget_statuses(&self) -> Result<Vec<BudgetStatus>, AppError> {
+        let budgets = self.list_budgets().await?;
+        let mut statuses = Vec::new();
+
+        for budget in budgets {
+            statuses.push(self.budget_status(budget).await?);
+        }
+
+        Ok(statuses)
+    }
+
+    /// Past periods of a budget since its rollover start, oldest first, with
+    /// what was carried into and out of each. The last one's carry is what the
+    /// current period starts with.
+    pub async fn get_budget_rollover_history(
+        &self,
+        name: &str,
+    ) -> Result<Vec<BudgetCarry>, AppError> {
+        let budget = self.get_budget(name).await?;
+        self.budget_carries(&budget, Utc::now()).await
+    }
+
+    /// Compare every budget (or just `budget`) with its actual spending in each
+    /// completed period overlapping `from..=to`, from its first period on.
+    /// Limits include what rolled over into a period under the budget's policy.
+    pub async fn get_budget_variance_report(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        budget: Option<&str>,
+    ) -> Result<BudgetVarianceReport, AppError> {
+        if from > to {
+            return Err(AppError::InvalidDateRange(format!(
+                "{} is after {}",
+                from, to
+            )));
+        }
+        let budgets = match budget {
+            Some(name) => vec![self.get_budget(name).await?],
+            None => self.list_budgets().await?,
+        };
+
+        let now = Utc::now();
+        let mut variances = Vec::new();
+        for budget in budgets {
+            let carries: HashMap<DateTime<Utc>, BudgetCarry> =
+                if budget.rollover == RolloverPolicy::None {
+                    HashMap::new()
+                } else {
+                    self.budget_carries(&budget, now)
+                        .await?
+                        .into_iter()
+                        .map(|carry| (carry.period_start, carry))
+                        .collect()
+                };
+
+            // Periods before the budget existed had no limit to compare against
+            let mut periods = Vec::new();
+            for (start, end) in budget
+                .period_type
+                .periods_between(
+                    start_of_day(from).max(budget.first_period_start()),
+                    end_of_day(to),
+                )
+                .into_iter()
+                .filter(|(_, end)| *end <= now)
+            {
+                let period = match carries.get(&start) {
+                    Some(carry) => {
+                        BudgetVariancePeriod::new(start, end, carry.effective_limit(), carry.spent)
+                    }
+                    None => BudgetVariancePeriod::new(
+                        start,
+                        end,
+                        budget.amount_cents,
+                        self.budget_spent(&budget, start, end).await?,
+                    ),
+                };
+                periods.push(period);
+            }
+
+            variances.push(BudgetVariance {
+                total_limit: periods.iter().map(|p| p.limit).sum(),
+                total_actual: periods.iter().map(|p| p.actual).sum(),
+                total_variance: periods.iter().map(|p| p.variance).sum(),
+                periods_exceeded: periods.iter().filter(|p| p.is_exceeded()).count(),
+                budget: budget.name,
+                category: budget.category,
+                filter: budget.filter,
+                period_type: budget.period_type,
+                currency: budget.currency,
+                periods,
+            });
+        }
+
+        Ok(BudgetVarianceReport {
+            from_date: from,
+            to_date: to,
+            budgets: variances,
+        })
+    }
+
+    async fn budget_status(&self, budget: Budget) -> Result<BudgetStatus, AppError> {
+        let now = Utc::now();
+        let (period_start, period_end) = budget.current_period(now);
+        let spent = self.budget_spent(&budget, period_start, period_end).await?;
+        let carried = if budget.rollover == RolloverPolicy::None {
+            0
         } else {
-            original.create_reversal()
+            self.budget_carries(&budget, now)
+                .await?
+                .last()
+                .map_or(0, |carry| carry.carried_out)
         };
+        let remaining = budget.amount_cents + carried - spent;
 
-        // Inherit category from original
-        if reversal.category.is_none() {
-            reversal.category = original.category.clone();
-        }
+        Ok(BudgetStatus {
+            budget,
+            spent,
+            carried,
+            remaining,
+            period_start,
+            period_end,
+        })
+    }
 
-        self.repo.save_transfer(&mut reversal).await?;
+    /// Periods of a budget from its rollover start up to the one holding `now`.
+    async fn budget_carries(
+        &self,
+        budget: &Budget,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<BudgetCarry>, AppError> {
+        let (current_start, _) = budget.current_period(now);
+        let mut periods = Vec::new();
+        for (start, end) in budget
+            .period_type
+            .periods_between(budget.rollover_start(), current_start)
+        {
+            periods.push((start, end, self.budget_spent(budget, start, end).await?));
+        }
+        Ok(carry_over(budget, &periods))
+    }
 
-        Ok(ReversalResult {
-            reversal,
-            original,
-            from_wallet_name: from_wallet.name,
-            to_wallet_name: to_wallet.name,
-            is_partial,
-        })
+    /// Check budget alerts for transfers that are already saved. A failure must
+    /// not fail the call that saved them, so it is queued next to the events.
+    async fn raise_budget_alerts(&self, transfers: &[&Transfer]) {
+        if let Err(e) = self.check_budget_alerts(transfers).await {
+            self.pending_budget_alerts().errors.push(e.to_string());
+        }
     }
 
-    // ========================
-    // Integrity operations
-    // ========================
+    /// Raise an event for every alert threshold the budgets matching `transfers`
+    /// have reached in the periods holding them. Only periods that have not
+    /// ended yet count, and each threshold fires once per period.
+    async fn check_budget_alerts(&self, transfers: &[&Transfer]) -> Result<(), AppError> {
+        let budgets: Vec<Budget> = self
+            .repo
+            .list_budgets()
+            .await?
+            .into_iter()
+            .filter(|budget| !budget.alert_thresholds.is_empty())
+            .collect();
+        if budgets.is_empty() {
+            return Ok(());
+        }
 
-    /// Check ledger integrity and return a report.
-    pub async fn check_integrity(&self) -> Result<IntegrityReport, AppError> {
-        let stats = self.repo.get_integrity_stats().await?;
-        let wallets = self.repo.list_wallets(true).await?;
-        let balances = self.repo.compute_all_balances().await?;
+        let now = Utc::now();
+        let mut events = Vec::new();
+        for budget in &budgets {
+            let mut checked: Vec<DateTime<Utc>> = Vec::new();
+            for transfer in transfers {
+                if !budget.matches(transfer) {
+                    continue;
+                }
+                let (period_start, period_end) = budget.current_period(transfer.timestamp);
+                if period_end <= now || checked.contains(&period_start) {
+                    continue;
+                }
+                if let Some(currency) = &budget.currency {
+                    let to_wallet = self.repo.get_wallet(transfer.to_wallet).await?;
+                    if to_wallet
+                        .is_none_or(|wallet| !wallet.currency.eq_ignore_ascii_case(currency))
+                    {
+                        continue;
+                    }
+                }
+                checked.push(period_start);
 
-        let report = build_integrity_report(
-            &wallets,
-            &balances,
-            stats.wallet_count,
-            stats.transfer_count,
-            stats.has_sequence_gaps,
-            stats.invalid_wallet_refs,
-            stats.invalid_amounts,
-        );
+                let carried = if budget.rollover == RolloverPolicy::None {
+                    0
+                } else {
+                    self.budget_carries(budget, transfer.timestamp)
+                        .await?
+                        .last()
+                        .map_or(0, |carry| carry.carried_out)
+                };
+                let limit = budget.amount_cents + carried;
+                let spent = self.budget_spent(budget, period_start, period_end).await?;
+                for threshold in reached_thresholds(&budget.alert_thresholds, limit, spent) {
+                    let event = BudgetEvent {
+                        budget_id: budget.id,
+                        budget: budget.name.clone(),
+                        threshold,
+                        period_start,
+                        period_end,
+                        limit,
+                        spent,
+                        currency: budget.currency.clone(),
+                        transfer_id: Some(transfer.id),
+                        fired_at: now,
+                        delivery_error: None,
+                    };
+                    if self.repo.save_budget_event(&event).await? {
+                        events.push(event);
+                    }
+                }
+            }
+        }
 
-        Ok(report)
+        if !events.is_empty() {
+            let hooks = self.get_budget_alert_hooks().await?;
+            for event in &mut events {
+                event.delivery_error = deliver_budget_event(&hooks, event).await.err();
+            }
+            self.pending_budget_alerts().events.extend(events);
+        }
+        Ok(())
     }
 
-    /// Get a map of wallet IDs to names (useful for display).
-    pub async fn get_wallet_names(&self) -> Result<HashMap<WalletId, String>, AppError> {
-        let wallets = self.repo.list_wallets(true).await?;
-        Ok(wallets.into_iter().map(|w| (w.id, w.name)).collect())
+    /// What counts against a budget in `[from, to)`.
+    async fn budget_spent(
+        &self,
+        budget: &Budget,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Cents, AppError> {
+        Ok(self.repo.sum_transfers_for_budget(budget, from, to).await?)
     }
 
     // ========================
-    // Budget operations
+    // Envelope operations
     // ========================
 
-    /// Create a new budget.
-    pub async fn create_budget(
+    /// Create an envelope for the spending in `category` (sub-categories
+    /// included) into wallets of `currency`, budgeted from the month of
+    /// `start_month` on.
+    pub async fn create_envelope(
         &self,
         name: String,
         category: String,
-        amount_cents: Cents,
-        period_type: PeriodType,
-    ) -> Result<Budget, AppError> {
-        // Check if budget already exists
-        if self.repo.get_budget_by_name(&name).await?.is_some() {
-            return Err(AppError::WalletAlreadyExists(name)); // Reuse error type
+        currency: String,
+        start_month: NaiveDate,
+    ) -> Result<Envelope, AppError> {
+        if self.repo.get_envelope_by_name(&name).await?.is_some() {
+            return Err(AppError::EnvelopeAlreadyExists(name));
         }
+        let category = normalize_category(&category).map_err(AppError::InvalidCategory)?;
+        let currency = self.get_currency(&currency).await?.code;
 
-        let budget = Budget::new(name, category, period_type, amount_cents);
-        self.repo.save_budget(&budget).await?;
-        Ok(budget)
+        let envelope = Envelope::new(name, category, currency, start_month);
+        self.repo.save_envelope(&envelope).await?;
+        Ok(envelope)
     }
 
-    /// Get a budget by name.
-    pub async fn get_budget(&self, name: &str) -> Result<Budget, AppError> {
+    /// Get an envelope by name.
+    pub async fn get_envelope(&self, name: &str) -> Result<Envelope, AppError> {
         self.repo
-            .get_budget_by_name(name)
+            .get_envelope_by_name(name)
             .await?
-            .ok_or_else(|| AppError::WalletNotFound(name.to_string())) // Reuse error type
+            .ok_or_else(|| AppError::EnvelopeNotFound(name.to_string()))
     }
 
-    /// List all budgets.
-    pub async fn list_budgets(&self) -> Result<Vec<Budget>, AppError> {
-        Ok(self.repo.list_budgets().await?)
+    /// List all envelopes.
+    pub async fn list_envelopes(&self) -> Result<Vec<Envelope>, AppError> {
+        Ok(self.repo.list_envelopes().await?)
     }
 
-    /// Delete a budget.
-    pub async fn delete_budget(&self, name: &str) -> Result<Budget, AppError> {
-        let budget = self.get_budget(name).await?;
-        self.repo.delete_budget(name).await?;
-        Ok(budget)
+    /// Delete an envelope; what was assigned to it goes back to the pool.
+    pub async fn delete_envelope(&self, name: &str) -> Result<Envelope, AppError> {
+        let envelope = self.get_envelope(name).await?;
+        self.repo.delete_envelope(envelope.id).await?;
+        Ok(envelope)
     }
 
-    /// Get budget status (spending vs limit for current period).
-    pub async fn get_budget_status(&self, name: &str) -> Result<BudgetStatus, AppError> {
-        let budget = self.get_budget(name).await?;
-        let (period_start, period_end) = budget.current_period(Utc::now());
+    /// Assign money from the "to be assigned" pool to an envelope for the month
+    /// of `month`; a negative amount puts money back into the pool.
+    pub async fn assign_to_envelope(
+        &self,
+        name: &str,
+        month: NaiveDate,
+        amount_cents: Cents,
+    ) -> Result<EnvelopeAssignment, AppError> {
+        if amount_cents == 0 {
+            return Err(AppError::InvalidAmount(
+                "Assigned amount cannot be zero".to_string(),
+            ));
+        }
+        let envelope = self.get_envelope(name).await?;
+        Self::check_envelope_month(&envelope, month)?;
 
-        let spent = self
-            .repo
-            .sum_transfers_by_category(&budget.category, period_start, period_end)
+        let assignment = EnvelopeAssignment::new(envelope.id, month, amount_cents);
+        self.repo
+            .save_envelope_assignments(std::slice::from_ref(&assignment))
             .await?;
+        Ok(assignment)
+    }
+
+    /// Move money between two envelopes of the same currency in the month of
+    /// `month`. Returns the assignment taking it out of `from` and the one
+    /// putting it into `to`.
+    pub async fn move_between_envelopes(
+        &self,
+        from: &str,
+        to: &str,
+        month: NaiveDate,
+        amount_cents: Cents,
+    ) -> Result<(EnvelopeAssignment, EnvelopeAssignment), AppError> {
+        if amount_cents <= 0 {
+            return Err(AppError::InvalidAmount(
+                "Moved amount must be positive".to_string(),
+            ));
+        }
+        let source = self.get_envelope(from).await?;
+        let target = self.get_envelope(to).await?;
+        if source.id == target.id {
+            return Err(AppError::InvalidEnvelope(format!(
+                "cannot move money from {} to itself",
+                source.name
+            )));
+        }
+        if source.currency != target.currency {
+            return Err(AppError::InvalidEnvelope(format!(
+                "{} is in {} but {} is in {}",
+                source.name, source.currency, target.name, target.currency
+            )));
+        }
+        Self::check_envelope_month(&source, month)?;
+        Self::check_envelope_month(&target, month)?;
 
-        let remaining = budget.amount_cents - spent;
+        let taken =
+            EnvelopeAssignment::new(source.id, month, -amount_cents).with_counterpart(target.id);
+        let given =
+            EnvelopeAssignment::new(target.id, month, amount_cents).with_counterpart(source.id);
+        self.repo
+            .save_envelope_assignments(&[taken.clone(), given.clone()])
+            .await?;
+        Ok((taken, given))
+    }
 
-        Ok(BudgetStatus {
-            budget,
-            spent,
-            remaining,
-            period_start,
-            period_end,
-        })
+    /// List every assignment, by month.
+    pub async fn list_envelope_assignments(&self) -> Result<Vec<EnvelopeAssignment>, AppError> {
+        Ok(self.repo.list_envelope_assignments().await?)
     }
 
-    /// Get status for all budgets.
-    pub async fn get_all_budget_statuses(&self) -> Result<Vec<BudgetStatus>, AppError> {
-        let budgets = self.list_budgets().await?;
+    /// Status of every envelope in the month of `month`, one entry per currency:
+    /// what was assigned in the month, the spending of the month (activity) and
+    /// what is available, carried over from the months before.
+    pub async fn get_envelope_status(
+        &self,
+        month: NaiveDate,
+    ) -> Result<Vec<EnvelopeStatus>, AppError> {
+        let month = month_start(month);
+        let (month_begin, month_end) = month_bounds(month);
+        let envelopes = self.repo.list_envelopes().await?;
+        let assignments = self.repo.list_envelope_assignments().await?;
+
+        let mut currencies: Vec<String> = envelopes.iter().map(|e| e.currency.clone()).collect();
+        currencies.sort();
+        currencies.dedup();
+
         let mut statuses = Vec::new();
+        for currency in currencies {
+            let mut months = Vec::new();
+            let mut assigned_total: Cents = 0;
+            for envelope in envelopes.iter().filter(|e| e.currency == currency) {
+                let own: Vec<&EnvelopeAssignment> = assignments
+                    .iter()
+                    .filter(|a| a.envelope_id == envelope.id && a.month <= month)
+                    .collect();
+                let assigned_through: Cents = own.iter().map(|a| a.amount_cents).sum();
+                assigned_total += assigned_through;
+
+                let (assigned, activity, available) = if month < envelope.start_month {
+                    (0, 0, 0)
+                } else {
+                    let spent = self
+                        .repo
+                        .sum_transfers_by_category(
+                            &envelope.category,
+                            Some(&currency),
+                            month_begin,
+                            month_end,
+                        )
+                        .await?;
+                    let spent_through = self
+                        .repo
+                        .sum_transfers_by_category(
+                            &envelope.category,
+                            Some(&currency),
+                            start_of_day(envelope.start_month),
+                            month_end,
+                        )
+                        .await?;
+                    let assigned: Cents = own
+                        .iter()
+                        .filter(|a| a.month == month)
+                        .map(|a| a.amount_cents)
+                        .sum();
+                    (assigned, -spent, assigned_through - spent_through)
+                };
+                months.push(EnvelopeMonth {
+                    envelope: envelope.clone(),
+                    month,
+                    assigned,
+                    activity,
+                    available,
+                });
+            }
 
-        for budget in budgets {
-            let (period_start, period_end) = budget.current_period(Utc::now());
-            let spent = self
-                .repo
-                .sum_transfers_by_category(&budget.category, period_start, period_end)
-                .await?;
-            let remaining = budget.amount_cents - spent;
+            // The pool is fed by the income since the first envelope's month
+            let pool_start = envelopes
+                .iter()
+                .filter(|e| e.currency == currency)
+                .map(|e| e.start_month)
+                .min()
+                .unwrap_or(month);
+            let income = if month < pool_start {
+                0
+            } else {
+                self.repo
+                    .sum_income(&currency, start_of_day(pool_start), month_end)
+                    .await?
+            };
 
-            statuses.push(BudgetStatus {
-                budget,
-                spent,
-                remaining,
-                period_start,
-                period_end,
+            statuses.push(EnvelopeStatus {
+                month,
+                currency,
+                to_be_assigned: income - assigned_total,
+                envelopes: months,
             });
         }
-
         Ok(statuses)
     }
 
+    /// Money can only be assigned from an envelope's first month on.
+    fn check_envelope_month(envelope: &Envelope, month: NaiveDate) -> Result<(), AppError> {
+        if month_start(month) < envelope.start_month {
+            return Err(AppError::InvalidEnvelope(format!(
+                "{} is budgeted from {} on",
+                envelope.name,
+                envelope.start_month.format("%Y-%m")
+            )));
+        }
+        Ok(())
+    }
+
     // ========================
     // Scheduled Transfer operations
     // ========================
@@ -576,6 +4538,9 @@ impl LedgerService {
         if to_wallet.is_archived() {
             return Err(AppError::WalletArchived(to_wallet_name.to_string()));
         }
+        for wallet in [&from_wallet, &to_wallet] {
+            self.ensure_postable(wallet).await?;
+        }
 
         // Validate currencies match
         if from_wallet.currency != to_wallet.currency {
@@ -601,7 +4566,7 @@ impl LedgerService {
         if let Some(desc) = description {
             scheduled = scheduled.with_description(desc);
         }
-        if let Some(cat) = category {
+        if let Some(cat) = clean_category(category)? {
             scheduled = scheduled.with_category(cat);
         }
 
@@ -609,6 +4574,20 @@ impl LedgerService {
         Ok(scheduled)
     }
 
+    /// Replace the tags given to the transfers a schedule creates.
+    pub async fn set_scheduled_transfer_tags(
+        &self,
+        name: &str,
+        tags: Vec<String>,
+    ) -> Result<ScheduledTransfer, AppError> {
+        let scheduled = self.get_scheduled_transfer(name).await?;
+        let tags = clean_tags(&tags)?;
+        self.repo
+            .update_scheduled_transfer_tags(scheduled.id, &tags)
+            .await?;
+        Ok(scheduled.with_tags(tags))
+    }
+
     /// Get a scheduled transfer by name.
     pub async fn get_scheduled_transfer(&self, name: &str) -> Result<ScheduledTransfer, AppError> {
         self.repo
@@ -728,17 +4707,17 @@ impl LedgerService {
                 )))?;
 
         // Create the actual transfer
-        let result = self
-            .record_transfer(
-                &from_wallet.name,
-                &to_wallet.name,
-                scheduled.amount_cents,
-                exec_date,
-                scheduled.description.clone(),
-                scheduled.category.clone(),
-                force, // Use force flag from scheduled execution
-            )
-            .await?;
+        let request = TransferRequest::new(
+            &from_wallet.name,
+            &to_wallet.name,
+            scheduled.amount_cents,
+            exec_date,
+        )
+        .with_description(scheduled.description.clone())
+        .with_category(scheduled.category.clone())
+        .with_tags(scheduled.tags.clone())
+        .with_force(force); // Use force flag from scheduled execution
+        let result = self.record_transfer_with(request).await?;
 
         // Update last_executed_at
         self.repo
@@ -881,67 +4860,293 @@ impl LedgerService {
             .unwrap()
             .and_utc();
 
-        while current_month_date <= end_date {
-            let month_start = current_month_date;
-            let month_end = if current_month_date.month() == 12 {
-                current_month_date
-                    .date_naive()
-                    .with_year(current_month_date.year() + 1)
-                    .unwrap()
-                    .with_month(1)
-                    .unwrap()
-            } else {
-                current_month_date
-                    .date_naive()
-                    .with_month(current_month_date.month() + 1)
-                    .unwrap()
-            }
-            .and_hms_opt(0, 0, 0)
-            .unwrap()
-            .and_utc();
+        while current_month_date <= end_date {
+            let month_start = current_month_date;
+            let month_end = if current_month_date.month() == 12 {
+                current_month_date
+                    .date_naive()
+                    .with_year(current_month_date.year() + 1)
+                    .unwrap()
+                    .with_month(1)
+                    .unwrap()
+            } else {
+                current_month_date
+                    .date_naive()
+                    .with_month(current_month_date.month() + 1)
+                    .unwrap()
+            }
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc();
+
+            // Check if we already have a snapshot for this month
+            let has_snapshot = snapshots
+                .iter()
+                .any(|s| s.date >= month_start && s.date < month_end);
+
+            if !has_snapshot && month_end > now {
+                // Add end-of-month snapshot
+                snapshots.push(ForecastSnapshot {
+                    date: month_end - Duration::days(1),
+                    wallet_balances: balances.clone(),
+                    event: None,
+                });
+            }
+
+            current_month_date = month_end;
+        }
+
+        // Sort snapshots by date
+        snapshots.sort_by_key(|s| s.date);
+
+        Ok(ForecastResult {
+            start_date,
+            end_date,
+            snapshots,
+        })
+    }
+
+    // ========================
+    // Reporting methods
+    // ========================
+
+    /// Generate a category spending report for the given date range.
+    /// Categories are listed as recorded; use `CategoryReport::rolled_up` or
+    /// `CategoryReport::tree` to group sub-categories under their parents.
+    pub async fn get_category_report(
+        &self,
+        from_date: DateTime<Utc>,
+        to_date: DateTime<Utc>,
+    ) -> Result<CategoryReport, AppError> {
+        let (categories, total) = self.category_summaries(from_date, to_date, None).await?;
+
+        Ok(CategoryReport {
+            from_date,
+            to_date,
+            categories,
+            total,
+            currency: None,
+        })
+    }
+
+    /// Generate a category spending report with every amount converted into
+    /// `currency`, using the rates in effect at the end of the range.
+    pub async fn get_category_report_converted(
+        &self,
+        from_date: DateTime<Utc>,
+        to_date: DateTime<Utc>,
+        currency: &str,
+    ) -> Result<CategoryReport, AppError> {
+        let (categories, total) = self
+            .category_summaries(from_date, to_date, Some(currency))
+            .await?;
+
+        Ok(CategoryReport {
+            from_date,
+            to_date,
+            categories,
+            total,
+            currency: Some(currency.to_uppercase()),
+        })
+    }
+
+    /// Generate a tag spending report for the given date range.
+    pub async fn get_tag_report(
+        &self,
+        from_date: DateTime<Utc>,
+        to_date: DateTime<Utc>,
+    ) -> Result<TagReport, AppError> {
+        let (tags, total) = self.tag_summaries(from_date, to_date, None).await?;
+
+        Ok(TagReport {
+            from_date,
+            to_date,
+            tags,
+            total,
+            currency: None,
+        })
+    }
+
+    /// Generate a tag spending report with every amount converted into `currency`,
+    /// using the rates in effect at the end of the range.
+    pub async fn get_tag_report_converted(
+        &self,
+        from_date: DateTime<Utc>,
+        to_date: DateTime<Utc>,
+        currency: &str,
+    ) -> Result<TagReport, AppError> {
+        let (tags, total) = self
+            .tag_summaries(from_date, to_date, Some(currency))
+            .await?;
+
+        Ok(TagReport {
+            from_date,
+            to_date,
+            tags,
+            total,
+            currency: Some(currency.to_uppercase()),
+        })
+    }
+
+    /// Generate a top-payees report for the given date range: what went to or came
+    /// from each payee and how often, largest total first. A limit keeps only the
+    /// top payees; the report total still covers all of them.
+    pub async fn get_payee_report(
+        &self,
+        from_date: DateTime<Utc>,
+        to_date: DateTime<Utc>,
+        limit: Option<usize>,
+    ) -> Result<PayeeReport, AppError> {
+        let aggregates = self.repo.aggregate_by_payee(from_date, to_date).await?;
+        let names: HashMap<PayeeId, String> = self
+            .repo
+            .list_payees()
+            .await?
+            .into_iter()
+            .map(|payee| (payee.id, payee.name))
+            .collect();
+        let total: Cents = aggregates.iter().map(|(_, _, amount, _, _)| amount).sum();
 
-            // Check if we already have a snapshot for this month
-            let has_snapshot = snapshots
-                .iter()
-                .any(|s| s.date >= month_start && s.date < month_end);
+        let mut payees: Vec<PayeeSummary> = aggregates
+            .into_iter()
+            .map(|(payee_id, count, payee_total, first_date, last_date)| {
+                let days = (last_date - first_date).num_seconds() as f64 / 86_400.0;
+                PayeeSummary {
+                    payee: names
+                        .get(&payee_id)
+                        .cloned()
+                        .unwrap_or_else(|| payee_id.to_string()),
+                    total: payee_total,
+                    count,
+                    average: if count > 0 { payee_total / count } else { 0 },
+                    percentage: if total > 0 {
+                        (payee_total as f64 / total as f64) * 100.0
+                    } else {
+                        0.0
+                    },
+                    first_date,
+                    last_date,
+                    average_interval_days: (count > 1).then(|| days / (count - 1) as f64),
+                }
+            })
+            .collect();
+        payees.sort_by(|a, b| {
+            b.total
+                .cmp(&a.total)
+                .then(b.count.cmp(&a.count))
+                .then(a.payee.cmp(&b.payee))
+        });
+        if let Some(limit) = limit {
+            payees.truncate(limit);
+        }
 
-            if !has_snapshot && month_end > now {
-                // Add end-of-month snapshot
-                snapshots.push(ForecastSnapshot {
-                    date: month_end - Duration::days(1),
-                    wallet_balances: balances.clone(),
-                    event: None,
-                });
+        Ok(PayeeReport {
+            from_date,
+            to_date,
+            payees,
+            total,
+        })
+    }
+
+    /// Tag totals for a date range with their share of the total of tagged
+    /// transfers, largest first. With a currency, amounts are converted at the
+    /// rates in effect at the end of the range.
+    async fn tag_summaries(
+        &self,
+        from_date: DateTime<Utc>,
+        to_date: DateTime<Utc>,
+        currency: Option<&str>,
+    ) -> Result<(Vec<TagSummary>, Cents), AppError> {
+        let aggregates = self
+            .repo
+            .aggregate_by_tag_and_currency(from_date, to_date)
+            .await?;
+        let totals = self.repo.sum_tagged_by_currency(from_date, to_date).await?;
+        let rates = match currency {
+            Some(currency) => {
+                self.conversion_rates(totals.iter().map(|(c, _)| c), currency, to_date)
+                    .await?
             }
+            None => HashMap::new(),
+        };
+        let convert = |amount: Cents, from: &str| match rates.get(from) {
+            Some(rate) => convert_at(amount, rate),
+            None => Ok(amount),
+        };
 
-            current_month_date = month_end;
+        // Merge the per-currency rows of each tag
+        let mut merged: HashMap<String, (Cents, i64)> = HashMap::new();
+        for (tag, agg_currency, count, total) in aggregates {
+            let entry = merged.entry(tag).or_insert((0, 0));
+            entry.0 += convert(total, &agg_currency)?;
+            entry.1 += count;
         }
+        let total: Cents = totals
+            .iter()
+            .map(|(agg_currency, amount)| convert(*amount, agg_currency))
+            .sum::<Result<Cents, AppError>>()?;
 
-        // Sort snapshots by date
-        snapshots.sort_by_key(|s| s.date);
+        let mut tags: Vec<TagSummary> = merged
+            .into_iter()
+            .map(|(tag, (tag_total, count))| TagSummary {
+                tag,
+                total: tag_total,
+                count,
+                average: if count > 0 { tag_total / count } else { 0 },
+                percentage: if total > 0 {
+                    (tag_total as f64 / total as f64) * 100.0
+                } else {
+                    0.0
+                },
+            })
+            .collect();
+        tags.sort_by(|a, b| b.total.cmp(&a.total).then(a.tag.cmp(&b.tag)));
 
-        Ok(ForecastResult {
-            start_date,
-            end_date,
-            snapshots,
-        })
+        Ok((tags, total))
     }
 
-    // ========================
-    // Reporting methods
-    // ========================
-
-    /// Generate a category spending report for the given date range.
-    pub async fn get_category_report(
+    /// Category totals for a date range with their share of the overall total,
+    /// largest first. With a currency, amounts are converted at the rates in
+    /// effect at the end of the range.
+    async fn category_summaries(
         &self,
         from_date: DateTime<Utc>,
         to_date: DateTime<Utc>,
-    ) -> Result<CategoryReport, AppError> {
-        let aggregates = self.repo.aggregate_by_category(from_date, to_date).await?;
+        currency: Option<&str>,
+    ) -> Result<(Vec<CategorySummary>, Cents), AppError> {
+        let aggregates = match currency {
+            None => self.repo.aggregate_by_category(from_date, to_date).await?,
+            Some(currency) => {
+                let aggregates = self
+                    .repo
+                    .aggregate_by_category_and_currency(from_date, to_date)
+                    .await?;
+                let rates = self
+                    .conversion_rates(aggregates.iter().map(|(c, _)| c), currency, to_date)
+                    .await?;
+
+                // Merge the per-currency rows of each category
+                let mut merged: HashMap<String, (Cents, i64)> = HashMap::new();
+                for (agg_currency, agg) in aggregates {
+                    let entry = merged.entry(agg.category).or_insert((0, 0));
+                    entry.0 += convert_at(agg.total, &rates[&agg_currency])?;
+                    entry.1 += agg.count;
+                }
+                merged
+                    .into_iter()
+                    .map(|(category, (total, count))| CategoryAggregate {
+                        category,
+                        count,
+                        total,
+                        average: if count > 0 { total / count } else { 0 },
+                    })
+                    .collect()
+            }
+        };
 
         let total: Cents = aggregates.iter().map(|a| a.total).sum();
 
-        let categories: Vec<CategorySummary> = aggregates
+        let mut categories: Vec<CategorySummary> = aggregates
             .into_iter()
             .map(|agg| CategorySummary {
                 category: agg.category,
@@ -955,13 +5160,9 @@ impl LedgerService {
                 },
             })
             .collect();
+        categories.sort_by(|a, b| b.total.cmp(&a.total).then(a.category.cmp(&b.category)));
 
-        Ok(CategoryReport {
-            from_date,
-            to_date,
-            categories,
-            total,
-        })
+        Ok((categories, total))
     }
 
     /// Generate an income vs expense report for the given date range.
@@ -1027,54 +5228,9 @@ impl LedgerService {
         to_date: DateTime<Utc>,
         period: PeriodType,
     ) -> Result<CashFlowReport, AppError> {
-        use chrono::{Datelike, Duration};
-
         let mut periods = Vec::new();
-        let mut current = from_date;
-
-        while current < to_date {
-            let (period_start, period_end) = match period {
-                PeriodType::Weekly => {
-                    let start = current;
-                    let end = (current + Duration::days(7)).min(to_date);
-                    (start, end)
-                }
-                PeriodType::Monthly => {
-                    let start = current;
-                    let next_month = if current.month() == 12 {
-                        current
-                            .date_naive()
-                            .with_year(current.year() + 1)
-                            .unwrap()
-                            .with_month(1)
-                            .unwrap()
-                    } else {
-                        current
-                            .date_naive()
-                            .with_month(current.month() + 1)
-                            .unwrap()
-                    };
-                    let end = next_month
-                        .and_hms_opt(0, 0, 0)
-                        .unwrap()
-                        .and_utc()
-                        .min(to_date);
-                    (start, end)
-                }
-                PeriodType::Yearly => {
-                    let start = current;
-                    let next_year = current
-                        .date_naive()
-                        .with_year(current.year() + 1)
-                        .unwrap()
-                        .and_hms_opt(0, 0, 0)
-                        .unwrap()
-                        .and_utc();
-                    let end = next_year.min(to_date);
-                    (start, end)
-                }
-            };
 
+        for (period_start, period_end) in cashflow_periods(from_date, to_date, period) {
             // Get wallet type aggregates for this period
             let wallet_type_aggregates = self
                 .repo
@@ -1094,6 +5250,9 @@ impl LedgerService {
                 .unwrap_or(0);
 
             let net = inflow - outflow;
+            let (categories, _) = self
+                .category_summaries(period_start, period_end, None)
+                .await?;
 
             periods.push(CashFlowPeriod {
                 period_start,
@@ -1101,30 +5260,87 @@ impl LedgerService {
                 inflow,
                 outflow,
                 net,
+                categories,
             });
+        }
+
+        Ok(CashFlowReport {
+            from_date,
+            to_date,
+            periods,
+            currency: None,
+        })
+    }
+
+    /// Generate a cash flow report with asset flows converted into `currency`,
+    /// using the rates in effect at the end of each period.
+    pub async fn get_cashflow_report_converted(
+        &self,
+        from_date: DateTime<Utc>,
+        to_date: DateTime<Utc>,
+        period: PeriodType,
+        currency: &str,
+    ) -> Result<CashFlowReport, AppError> {
+        let mut periods = Vec::new();
+
+        for (period_start, period_end) in cashflow_periods(from_date, to_date, period) {
+            let aggregates = self
+                .repo
+                .aggregate_by_wallet_type_and_currency(period_start, period_end)
+                .await?;
+            let asset_flows: Vec<(&String, &(Cents, Cents))> = aggregates
+                .iter()
+                .filter(|((wallet_type, _), _)| *wallet_type == WalletType::Asset)
+                .map(|((_, flow_currency), flows)| (flow_currency, flows))
+                .collect();
+            let rates = self
+                .conversion_rates(asset_flows.iter().map(|(c, _)| *c), currency, period_end)
+                .await?;
 
-            current = period_end;
+            let mut inflow: Cents = 0;
+            let mut outflow: Cents = 0;
+            for (flow_currency, (flow_in, flow_out)) in asset_flows {
+                inflow += convert_at(*flow_in, &rates[flow_currency])?;
+                outflow += convert_at(*flow_out, &rates[flow_currency])?;
+            }
+            let (categories, _) = self
+                .category_summaries(period_start, period_end, Some(currency))
+                .await?;
+
+            periods.push(CashFlowPeriod {
+                period_start,
+                period_end,
+                inflow,
+                outflow,
+                net: inflow - outflow,
+                categories,
+            });
         }
 
         Ok(CashFlowReport {
             from_date,
             to_date,
             periods,
+            currency: Some(currency.to_uppercase()),
         })
     }
 
     /// Generate a net worth report (current assets - liabilities).
+    /// Holdings are valued at their latest known price rather than their cash cost.
     pub async fn get_net_worth_report(&self) -> Result<NetWorthReport, AppError> {
         let wallets = self.list_wallets(false).await?;
-        let balances = self.repo.compute_all_balances().await?;
+        let mut balances = self.repo.compute_all_balances().await?;
+        balances.extend(self.holding_values(Utc::now()).await?);
 
         let mut total_assets: Cents = 0;
         let mut total_liabilities: Cents = 0;
         let mut assets = Vec::new();
         let mut liabilities = Vec::new();
 
-        for wallet in wallets {
-            let balance = balances.get(&wallet.id).copied().unwrap_or(0);
+        let by_id: HashMap<WalletId, &Wallet> = wallets.iter().map(|w| (w.id, w)).collect();
+        for entry in wallet_tree(&wallets, &balances) {
+            let wallet = by_id[&entry.wallet_id];
+            let balance = entry.balance;
 
             match wallet.wallet_type {
                 WalletType::Asset => {
@@ -1132,6 +5348,9 @@ impl LedgerService {
                     assets.push(WalletBalance {
                         wallet_name: wallet.name.clone(),
                         balance,
+                        currency: wallet.currency.clone(),
+                        subtotal: entry.subtotal,
+                        depth: entry.depth,
                     });
                 }
                 WalletType::Liability => {
@@ -1140,6 +5359,9 @@ impl LedgerService {
                     liabilities.push(WalletBalance {
                         wallet_name: wallet.name.clone(),
                         balance: balance.abs(),
+                        currency: wallet.currency.clone(),
+                        subtotal: entry.subtotal.abs(),
+                        depth: entry.depth,
                     });
                 }
                 _ => {} // Ignore income, expense, equity for net worth calculation
@@ -1155,6 +5377,160 @@ impl LedgerService {
             net_worth,
             assets,
             liabilities,
+            currency: None,
+        })
+    }
+
+    /// Generate a net worth report with every balance converted into `currency`
+    /// at the latest known rates (holdings at their latest price).
+    pub async fn get_net_worth_report_converted(
+        &self,
+        currency: &str,
+    ) -> Result<NetWorthReport, AppError> {
+        let now = Utc::now();
+        let wallets = self.list_wallets(false).await?;
+        let mut balances = self.repo.compute_all_balances().await?;
+        balances.extend(self.holding_values(now).await?);
+        let rates = self
+            .conversion_rates(
+                wallets
+                    .iter()
+                    .filter(|w| matches!(w.wallet_type, WalletType::Asset | WalletType::Liability))
+                    .map(|w| &w.currency),
+                currency,
+                now,
+            )
+            .await?;
+
+        let mut total_assets: Cents = 0;
+        let mut total_liabilities: Cents = 0;
+        let mut assets = Vec::new();
+        let mut liabilities = Vec::new();
+
+        let by_id: HashMap<WalletId, &Wallet> = wallets.iter().map(|w| (w.id, w)).collect();
+        for entry in wallet_tree(&wallets, &balances) {
+            let wallet = by_id[&entry.wallet_id];
+
+            match wallet.wallet_type {
+                WalletType::Asset => {
+                    let balance = convert_at(entry.balance, &rates[&wallet.currency])?;
+                    total_assets += balance;
+                    assets.push(WalletBalance {
+                        wallet_name: wallet.name.clone(),
+                        balance,
+                        currency: currency.to_uppercase(),
+                        subtotal: convert_at(entry.subtotal, &rates[&wallet.currency])?,
+                        depth: entry.depth,
+                    });
+                }
+                WalletType::Liability => {
+                    let balance = convert_at(entry.balance.abs(), &rates[&wallet.currency])?;
+                    total_liabilities += balance;
+                    liabilities.push(WalletBalance {
+                        wallet_name: wallet.name.clone(),
+                        balance,
+                        currency: currency.to_uppercase(),
+                        subtotal: convert_at(entry.subtotal.abs(), &rates[&wallet.currency])?,
+                        depth: entry.depth,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        Ok(NetWorthReport {
+            as_of: now,
+            total_assets,
+            total_liabilities,
+            net_worth: total_assets - total_liabilities,
+            assets,
+            liabilities,
+            currency: Some(currency.to_uppercase()),
+        })
+    }
+
+    /// Net worth, total assets and total liabilities at the end of every
+    /// `interval` from `from` to `to` (both included), in `currency`. Each day's
+    /// balances are converted at the rates in effect on that day, and holdings
+    /// valued at their latest price on or before it (at their cash cost while
+    /// they have none). The balances before the range and the daily changes
+    /// within it are each summed in one query.
+    pub async fn get_net_worth_history(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        interval: SampleInterval,
+        currency: &str,
+    ) -> Result<NetWorthHistory, AppError> {
+        if from > to {
+            return Err(AppError::InvalidDateRange(format!(
+                "{} is after {}",
+                from, to
+            )));
+        }
+        let currency = self.get_currency(currency).await?.code;
+
+        // Archived wallets may have held money earlier in the range
+        let wallets: Vec<Wallet> = self
+            .repo
+            .list_wallets(true)
+            .await?
+            .into_iter()
+            .filter(|w| matches!(w.wallet_type, WalletType::Asset | WalletType::Liability))
+            .collect();
+        let wallet_types: HashMap<WalletId, WalletType> = wallets
+            .iter()
+            .map(|wallet| (wallet.id, wallet.wallet_type))
+            .collect();
+        let (start, end) = (start_of_day(from), end_of_day(to));
+        let dates = sample_dates(from, to, interval);
+        let balances = balances_on(
+            &self.repo.compute_all_balances_before(start).await?,
+            &self.repo.daily_balance_changes(start, end).await?,
+            &dates,
+        );
+        let units = balances_on(
+            &self.repo.compute_all_quantities_before(start).await?,
+            &self.repo.daily_quantity_changes(start, end).await?,
+            &dates,
+        );
+
+        let mut points = Vec::with_capacity(dates.len());
+        for ((&date, balances), units) in dates.iter().zip(&balances).zip(&units) {
+            // The last instant of the day, so rates and prices dated that day count
+            let as_of = end_of_day(date) - chrono::Duration::nanoseconds(1);
+            let mut held = Vec::new();
+            for wallet in &wallets {
+                let mut value = balances.get(&wallet.id).copied().unwrap_or(0);
+                if let Some(commodity) = &wallet.commodity {
+                    let quantity = units.get(&wallet.id).copied().unwrap_or(0);
+                    match self.get_price(commodity, &wallet.currency, as_of).await {
+                        Ok(price) => value = self.market_value(commodity, quantity, &price).await?,
+                        Err(AppError::PriceNotFound { .. }) => {}
+                        Err(e) => return Err(e),
+                    }
+                }
+                // Empty wallets need no rate, so currencies not held yet cannot fail a sample
+                if value != 0 {
+                    held.push((wallet, value));
+                }
+            }
+            let rates = self
+                .conversion_rates(held.iter().map(|(w, _)| &w.currency), &currency, as_of)
+                .await?;
+            let values = held
+                .iter()
+                .map(|(w, value)| Ok((w.id, convert_at(*value, &rates[&w.currency])?)))
+                .collect::<Result<HashMap<WalletId, Cents>, AppError>>()?;
+            points.push(net_worth_point(date, &wallet_types, &values));
+        }
+
+        Ok(NetWorthHistory {
+            from_date: from,
+            to_date: to,
+            interval,
+            currency,
+            points,
         })
     }
 
@@ -1235,6 +5611,13 @@ impl LedgerService {
             .unwrap_or(0);
         let previous_net = previous_income - previous_expense;
 
+        let (current_categories, _) = self
+            .category_summaries(current_start, current_end, None)
+            .await?;
+        let (previous_categories, _) = self
+            .category_summaries(previous_start, previous_end, None)
+            .await?;
+
         let change = current_net - previous_net;
         let change_percentage = if previous_net != 0 {
             ((change as f64) / (previous_net as f64)) * 100.0
@@ -1249,6 +5632,7 @@ impl LedgerService {
                 total_income: current_income,
                 total_expense: current_expense,
                 net: current_net,
+                categories: current_categories,
             },
             previous_period: PeriodSummary {
                 period_start: previous_start,
@@ -1256,6 +5640,7 @@ impl LedgerService {
                 total_income: previous_income,
                 total_expense: previous_expense,
                 net: previous_net,
+                categories: previous_categories,
             },
             change,
             change_percentage,
@@ -1280,3 +5665,192 @@ impl LedgerService {
             .ok_or_else(|| AppError::WalletNotFound(id.to_string()))
     }
 }
+
+/// Split a date range into consecutive reporting periods.
+fn cashflow_periods(
+    from_date: DateTime<Utc>,
+    to_date: DateTime<Utc>,
+    period: PeriodType,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    use chrono::{Datelike, Duration};
+
+    let mut periods = Vec::new();
+    let mut current = from_date;
+
+    while current < to_date {
+        let (period_start, period_end) = match period {
+            PeriodType::Weekly => {
+                let start = current;
+                let end = (current + Duration::days(7)).min(to_date);
+                (start, end)
+            }
+            PeriodType::Monthly => {
+                let start = current;
+                let next_month = if current.month() == 12 {
+                    current
+                        .date_naive()
+                        .with_year(current.year() + 1)
+                        .unwrap()
+                        .with_month(1)
+                        .unwrap()
+                } else {
+                    current
+                        .date_naive()
+                        .with_month(current.month() + 1)
+                        .unwrap()
+                };
+                let end = next_month
+                    .and_hms_opt(0, 0, 0)
+                    .unwrap()
+                    .and_utc()
+                    .min(to_date);
+                (start, end)
+            }
+            PeriodType::Yearly => {
+                let start = current;
+                let next_year = current
+                    .date_naive()
+                    .with_year(current.year() + 1)
+                    .unwrap()
+                    .and_hms_opt(0, 0, 0)
+                    .unwrap()
+                    .and_utc();
+                let end = next_year.min(to_date);
+                (start, end)
+            }
+        };
+
+        periods.push((period_start, period_end));
+        current = period_end;
+    }
+
+    periods
+}
+
+/// Normalize an optional category as entered (see `normalize_category`).
+fn clean_category(category: Option<String>) -> Result<Option<String>, AppError> {
+    category
+        .map(|c| normalize_category(&c).map_err(AppError::InvalidCategory))
+        .transpose()
+}
+
+/// Alert thresholds as entered: ascending, without repeats. Zero is rejected.
+fn clean_thresholds(mut thresholds: Vec<u32>) -> Result<Vec<u32>, AppError> {
+    if thresholds.contains(&0) {
+        return Err(AppError::InvalidBudget(
+            "alert thresholds must be above 0%".to_string(),
+        ));
+    }
+    thresholds.sort_unstable();
+    thresholds.dedup();
+    Ok(thresholds)
+}
+
+/// Hand a budget event to the configured file (one JSON line per event) and
+/// command (the JSON on stdin, with the budget, threshold and spent share in
+/// `PECUNIO_BUDGET`, `PECUNIO_THRESHOLD` and `PECUNIO_PERCENTAGE`). A command
+/// still running after `BUDGET_ALERT_COMMAND_TIMEOUT` is killed.
+async fn deliver_budget_event(hooks: &BudgetAlertHooks, event: &BudgetEvent) -> Result<(), String> {
+    use std::io::Write;
+    use tokio::io::AsyncWriteExt;
+
+    let json = serde_json::to_string(event).map_err(|e| e.to_string())?;
+    if let Some(path) = &hooks.file {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("{}: {}", path, e))?;
+        writeln!(file, "{}", json).map_err(|e| format!("{}: {}", path, e))?;
+    }
+    if let Some(command) = &hooks.command {
+        let (shell, flag) = if cfg!(windows) {
+            ("cmd", "/C")
+        } else {
+            ("sh", "-c")
+        };
+        let mut child = tokio::process::Command::new(shell)
+            .arg(flag)
+            .arg(command)
+            .env("PECUNIO_BUDGET", &event.budget)
+            .env("PECUNIO_THRESHOLD", event.threshold.to_string())
+            .env("PECUNIO_PERCENTAGE", format!("{:.1}", event.percentage()))
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("{}: {}", command, e))?;
+        let run = async {
+            if let Some(mut stdin) = child.stdin.take() {
+                // A command that does not read its input is fine
+                let _ = stdin.write_all(format!("{}\n", json).as_bytes()).await;
+            }
+            child.wait().await
+        };
+        let status = tokio::time::timeout(BUDGET_ALERT_COMMAND_TIMEOUT, run)
+            .await
+            .map_err(|_| {
+                format!(
+                    "{}: still running after {}s, stopped",
+                    command,
+                    BUDGET_ALERT_COMMAND_TIMEOUT.as_secs()
+                )
+            })?
+            .map_err(|e| format!("{}: {}", command, e))?;
+        if !status.success() {
+            return Err(format!("{}: {}", command, status));
+        }
+    }
+    Ok(())
+}
+
+/// Normalize tags as entered (see `normalize_tags`).
+fn clean_tags(tags: &[String]) -> Result<Vec<String>, AppError> {
+    normalize_tags(tags).map_err(AppError::InvalidTag)
+}
+
+/// Error for a plain transfer touching a holding wallet, whose units only move by trading.
+fn holding_transfer_error(wallet: &Wallet) -> AppError {
+    AppError::InvalidTrade(format!(
+        "'{}' holds {}; use buy or sell to move it",
+        wallet.name,
+        wallet.commodity.as_deref().unwrap_or("units")
+    ))
+}
+
+/// Convert minor units at a report's conversion rate.
+fn convert_at(amount: Cents, rate: &Rate) -> Result<Cents, AppError> {
+    convert_amount(amount, rate, CONVERSION_ROUNDING).ok_or_else(|| {
+        AppError::InvalidAmount(format!("{} is too large to convert at {}", amount, rate))
+    })
+}
+
+/// Log entries for an override against every transfer it lets into a locked
+/// period; none without an override.
+fn lock_overrides(
+    transfer_ids: &[TransferId],
+    action: LockedAction,
+    lock_override: Option<&LockOverrideRequest>,
+) -> Vec<LockOverride> {
+    let Some(lock_override) = lock_override else {
+        return Vec::new();
+    };
+    transfer_ids
+        .iter()
+        .map(|id| {
+            let logged = LockOverride::new(*id, action, lock_override.reason.trim());
+            match &lock_override.author {
+                Some(author) => logged.with_author(author),
+                None => logged,
+            }
+        })
+        .collect()
+}
+
+/// Midnight UTC on January 1st of a year.
+fn year_start(year: i32) -> Result<DateTime<Utc>, AppError> {
+    chrono::NaiveDate::from_ymd_opt(year, 1, 1)
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|dt| dt.and_utc())
+        .ok_or_else(|| AppError::InvalidAmount(format!("Invalid year: {}", year)))
+}
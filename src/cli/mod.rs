@@ -1,10 +1,17 @@
 use anyhow::{Context, Result};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
 use clap::{Parser, Subcommand};
 use uuid::Uuid;
 
-use crate::application::LedgerService;
-use crate::domain::{WalletType, format_cents, parse_cents};
+use crate::application::{
+    BudgetAlertHooks, BudgetRequest, LedgerService, LockOverrideRequest, OpeningBalance,
+    TradeRequest, TransferEdit, TransferRequest,
+};
+use crate::domain::{
+    AmendedField, Cents, Currency, LocaleProfile, LotMethod, LotPick, NegativeStyle, Rate,
+    RoundingMode, SampleInterval, SymbolPlacement, TagMatch, Wallet, WalletType, YearClose,
+    category_leaf, normalize_tags, wallet_path, wallet_tree,
+};
 
 /// Pecunio - Personal Finance Ledger
 #[derive(Parser)]
@@ -20,6 +27,14 @@ pub struct Cli {
     #[arg(short, long, global = true)]
     pub verbose: bool,
 
+    /// Locale profile for amounts (defaults to the ledger's configured locale)
+    #[arg(long, global = true)]
+    pub locale: Option<String>,
+
+    /// Include the history of archived years in reports
+    #[arg(long, global = true)]
+    pub with_archives: bool,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -38,22 +53,69 @@ pub enum Commands {
         /// Amount to transfer (e.g., "50.00" or "50")
         amount: String,
 
-        /// Source wallet name
-        #[arg(long)]
-        from: String,
+        /// Source wallet name (defaults to the payee's wallet)
+        #[arg(long, required_unless_present = "payee")]
+        from: Option<String>,
 
-        /// Destination wallet name
-        #[arg(long)]
-        to: String,
+        /// Destination wallet name (defaults to the payee's wallet)
+        #[arg(long, required_unless_present = "payee")]
+        to: Option<String>,
 
         /// Description of the transfer
         #[arg(short, long)]
         description: Option<String>,
 
-        /// Category for budgeting (e.g., "groceries", "utilities")
+        /// Category for budgeting (e.g., "groceries", or "food:groceries" for a sub-category)
         #[arg(short, long)]
         category: Option<String>,
 
+        /// Tag the transfer (repeat or comma-separate for several)
+        #[arg(long = "tag", value_delimiter = ',')]
+        tags: Vec<String>,
+
+        /// Payee name or alias (detected from the description when omitted)
+        #[arg(long)]
+        payee: Option<String>,
+
+        /// Exchange rate for wallets in different currencies (defaults to the stored rate)
+        #[arg(long)]
+        rate: Option<Rate>,
+
+        /// Rounding for amounts with too many decimals: reject, half-up, half-even, truncate
+        #[arg(long, default_value = "reject")]
+        round: String,
+
+        /// Force transfer even if it would make wallet balance negative
+        #[arg(long)]
+        force: bool,
+
+        /// Date of the transfer (ISO 8601 format: YYYY-MM-DD, defaults to now)
+        #[arg(long)]
+        date: Option<String>,
+
+        /// Record it inside a locked period; the reason is logged with the transfer
+        #[arg(long, value_name = "REASON")]
+        override_lock: Option<String>,
+    },
+
+    /// Record a split transfer: one transaction from a wallet into several legs
+    Split {
+        /// Source wallet name
+        #[arg(long)]
+        from: String,
+
+        /// Leg as WALLET=AMOUNT[,CATEGORY] (repeat for each leg)
+        #[arg(long = "leg", required = true)]
+        legs: Vec<String>,
+
+        /// Description of the whole transaction
+        #[arg(short, long)]
+        description: Option<String>,
+
+        /// Rounding for amounts with too many decimals: reject, half-up, half-even, truncate
+        #[arg(long, default_value = "reject")]
+        round: String,
+
         /// Force transfer even if it would make wallet balance negative
         #[arg(long)]
         force: bool,
@@ -61,12 +123,113 @@ pub enum Commands {
         /// Date of the transfer (ISO 8601 format: YYYY-MM-DD, defaults to now)
         #[arg(long)]
         date: Option<String>,
+
+        /// Record it inside a locked period; the reason is logged with the transfer
+        #[arg(long, value_name = "REASON")]
+        override_lock: Option<String>,
+    },
+
+    /// Start wallets off with their real-world balances
+    OpeningBalances {
+        /// Balance as WALLET=AMOUNT (repeat for each wallet); liabilities as the amount owed
+        #[arg(long = "balance", required = true)]
+        balances: Vec<String>,
+
+        /// Date the balances are as of (YYYY-MM-DD)
+        #[arg(long)]
+        as_of: String,
+
+        /// Rounding for amounts with too many decimals: reject, half-up, half-even, truncate
+        #[arg(long, default_value = "reject")]
+        round: String,
+    },
+
+    /// Buy units of a commodity into a holding wallet, paid from a cash wallet
+    Buy {
+        /// Units to buy (e.g., "10" or "2.5")
+        quantity: String,
+
+        /// Holding wallet name
+        wallet: String,
+
+        /// Price per unit, in the holding wallet's currency (e.g., 98.50)
+        #[arg(long)]
+        price: f64,
+
+        /// Cash wallet paying for the units
+        #[arg(long)]
+        from: String,
+
+        /// Description of the trade
+        #[arg(short, long)]
+        description: Option<String>,
+
+        /// Rounding for quantities with too many decimals: reject, half-up, half-even, truncate
+        #[arg(long, default_value = "reject")]
+        round: String,
+
+        /// Buy even if it would make the cash wallet negative
+        #[arg(long)]
+        force: bool,
+
+        /// Date of the trade (ISO 8601 format: YYYY-MM-DD, defaults to now)
+        #[arg(long)]
+        date: Option<String>,
+
+        /// Record it inside a locked period; the reason is logged with the transfer
+        #[arg(long, value_name = "REASON")]
+        override_lock: Option<String>,
+    },
+
+    /// Sell units of a commodity from a holding wallet into a cash wallet
+    Sell {
+        /// Units to sell (e.g., "10" or "2.5")
+        quantity: String,
+
+        /// Holding wallet name
+        wallet: String,
+
+        /// Price per unit, in the holding wallet's currency (e.g., 104.20)
+        #[arg(long)]
+        price: f64,
+
+        /// Cash wallet receiving the proceeds
+        #[arg(long)]
+        to: String,
+
+        /// Lots to sell from: fifo, lifo, average (defaults to the wallet's method)
+        #[arg(long)]
+        method: Option<String>,
+
+        /// Sell from a specific lot as LOT_ID=UNITS (repeatable, sums to the quantity)
+        #[arg(long = "lot")]
+        lots: Vec<String>,
+
+        /// Description of the trade
+        #[arg(short, long)]
+        description: Option<String>,
+
+        /// Rounding for quantities with too many decimals: reject, half-up, half-even, truncate
+        #[arg(long, default_value = "reject")]
+        round: String,
+
+        /// Date of the trade (ISO 8601 format: YYYY-MM-DD, defaults to now)
+        #[arg(long)]
+        date: Option<String>,
+
+        /// Record it inside a locked period; the reason is logged with the transfer
+        #[arg(long, value_name = "REASON")]
+        override_lock: Option<String>,
     },
 
     /// Show balance for a wallet or all wallets
     Balance {
         /// Wallet name (omit for all wallets)
         wallet: Option<String>,
+
+        /// Balance at the end of this day (YYYY-MM-DD) instead of today
+        #[arg(long)]
+        as_of: Option<String>,
     },
 
     /// List recent transfers
@@ -75,10 +238,18 @@ pub enum Commands {
         #[arg(long)]
         wallet: Option<String>,
 
-        /// Filter by category
+        /// Filter by category (sub-categories included)
         #[arg(long)]
         category: Option<String>,
 
+        /// Filter by tag (repeat or comma-separate; matches any of them)
+        #[arg(long = "tag", value_delimiter = ',')]
+        tags: Vec<String>,
+
+        /// Only show transfers carrying every given tag
+        #[arg(long, requires = "tags")]
+        all_tags: bool,
+
         /// Filter from date (YYYY-MM-DD)
         #[arg(long)]
         from_date: Option<String>,
@@ -103,6 +274,10 @@ pub enum Commands {
         /// Amount to reverse (omit for full reversal)
         #[arg(short, long)]
         amount: Option<String>,
+
+        /// Reverse inside a locked period; the reason is logged with the reversal
+        #[arg(long, value_name = "REASON")]
+        override_lock: Option<String>,
     },
 
     /// Show detailed transfer information
@@ -110,16 +285,130 @@ pub enum Commands {
     ShowTransfer {
         /// Transfer ID
         id: String,
+
+        /// Write the transfer's attachments into this directory
+        #[arg(long, value_name = "DIR")]
+        extract: Option<String>,
+
+        /// Only extract this attachment (ID as listed by show)
+        #[arg(long, requires = "extract")]
+        attachment: Option<String>,
+    },
+
+    /// Attach files (receipts, invoices) to a transfer
+    Attach {
+        /// Transfer ID
+        id: String,
+
+        /// Files to attach
+        #[arg(required = true)]
+        files: Vec<String>,
+    },
+
+    /// Remove an attachment from its transfer
+    Detach {
+        /// Attachment ID
+        id: String,
+    },
+
+    /// Correct a transfer's description, category, tags, date or amount (kept in its history)
+    Edit {
+        /// Transfer ID
+        id: String,
+
+        /// New description
+        #[arg(short, long, conflicts_with = "clear_description")]
+        description: Option<String>,
+
+        /// Remove the description
+        #[arg(long)]
+        clear_description: bool,
+
+        /// New category
+        #[arg(short, long, conflicts_with = "clear_category")]
+        category: Option<String>,
+
+        /// Remove the category
+        #[arg(long)]
+        clear_category: bool,
+
+        /// Replace the tags (comma-separated, empty to remove them all)
+        #[arg(long)]
+        tags: Option<String>,
+
+        /// New date (ISO 8601 format: YYYY-MM-DD)
+        #[arg(long)]
+        date: Option<String>,
+
+        /// New amount (e.g., "50.00")
+        #[arg(short, long)]
+        amount: Option<String>,
+
+        /// Why the transfer is corrected
+        #[arg(long)]
+        reason: Option<String>,
+
+        /// Who is correcting the transfer (defaults to $USER)
+        #[arg(long)]
+        author: Option<String>,
+
+        /// Raise the amount even if it would make the source wallet negative
+        #[arg(long)]
+        force: bool,
+
+        /// Correct a transfer inside a locked period; the reason is logged with it
+        #[arg(long, value_name = "REASON")]
+        override_lock: Option<String>,
     },
 
     /// Budget management commands
     #[command(subcommand)]
     Budget(BudgetCommands),
 
+    /// Envelope (zero-based) budgeting: assign income to envelopes that roll over
+    #[command(subcommand)]
+    Envelope(EnvelopeCommands),
+
     /// Scheduled transfer management commands
     #[command(subcommand)]
     Scheduled(ScheduledCommands),
 
+    /// Exchange rate management commands
+    #[command(subcommand)]
+    Rate(RateCommands),
+
+    /// Commodity price history
+    #[command(subcommand)]
+    Price(PriceCommands),
+
+    /// Reconcile a wallet against a bank statement
+    #[command(subcommand)]
+    Reconcile(ReconcileCommands),
+
+    /// Payees: who transfers go to or come from
+    #[command(subcommand)]
+    Payee(PayeeCommands),
+
+    /// Close the books through a date, for the ledger or one wallet
+    #[command(subcommand)]
+    Lock(LockCommands),
+
+    /// Year-end closing and archiving
+    #[command(subcommand)]
+    Year(YearCommands),
+
+    /// Balance assertions: what a wallet held on a day, per the statement
+    #[command(subcommand)]
+    Assertion(AssertionCommands),
+
+    /// Currency management commands (minor units)
+    #[command(subcommand)]
+    Currency(CurrencyCommands),
+
+    /// Locale profiles for reading and writing amounts
+    #[command(subcommand)]
+    Locale(LocaleCommands),
+
     /// Forecast future balances based on scheduled transfers
     Forecast {
         /// Number of months to forecast
@@ -137,7 +426,7 @@ pub enum Commands {
 
     /// Export data to CSV or JSON
     Export {
-        /// What to export: transfers, balances, budgets, scheduled, full
+        /// What to export: transfers, balances, budgets, scheduled, gains, full
         export_type: String,
 
         /// Output file (stdout if omitted)
@@ -147,6 +436,10 @@ pub enum Commands {
         /// Format: csv, json (default: csv for most types, json for full)
         #[arg(short, long)]
         format: Option<String>,
+
+        /// Year of realized gains to export (defaults to the current year)
+        #[arg(long)]
+        year: Option<i32>,
     },
 
     /// Import data from CSV or JSON
@@ -162,10 +455,23 @@ pub enum Commands {
         #[arg(long)]
         dry_run: bool,
 
-        /// Skip duplicate records
+        /// Skip records already in the ledger (same ID or external reference)
         #[arg(long)]
         skip_duplicates: bool,
 
+        /// Match statement lines to transfers entered by hand (by amount, date and
+        /// description); matches are marked cleared instead of imported again
+        #[arg(long = "match")]
+        match_existing: bool,
+
+        /// Days either side of a line's date to look for its transfer
+        #[arg(long, default_value = "3")]
+        match_window: i64,
+
+        /// Settle an ambiguous line as LINE=TRANSFER_ID, or LINE=new to import it (repeatable)
+        #[arg(long = "confirm")]
+        confirm: Vec<String>,
+
         /// Create wallets that don't exist
         #[arg(long)]
         create_wallets: bool,
@@ -173,6 +479,10 @@ pub enum Commands {
         /// Validate without importing
         #[arg(long)]
         validate: bool,
+
+        /// Import lines dated inside a locked period; the reason is logged with each transfer
+        #[arg(long, value_name = "REASON")]
+        override_lock: Option<String>,
     },
 }
 
@@ -184,25 +494,70 @@ pub enum WalletCommands {
         name: String,
 
         /// Wallet type: asset, liability, income, expense, equity
-        #[arg(short = 't', long = "type")]
-        wallet_type: String,
+        #[arg(
+            short = 't',
+            long = "type",
+            required_unless_present = "parent",
+            conflicts_with = "parent"
+        )]
+        wallet_type: Option<String>,
 
         /// Currency code (e.g., EUR, USD)
-        #[arg(short, long, default_value = "EUR")]
+        #[arg(short, long, default_value = "EUR", conflicts_with = "parent")]
         currency: String,
 
         /// Description
         #[arg(short, long)]
         description: Option<String>,
+
+        /// Hold units of this commodity (e.g., a fund ticker); asset wallets only
+        #[arg(long, conflicts_with = "parent")]
+        commodity: Option<String>,
+
+        /// Lots a sale takes units from by default: fifo, lifo, average
+        #[arg(long, requires = "commodity")]
+        lot_method: Option<String>,
+
+        /// Create the wallet under this wallet; it takes the parent's type and currency
+        #[arg(short, long)]
+        parent: Option<String>,
+
+        /// Accept transfers even once the wallet has sub-wallets
+        #[arg(long)]
+        allow_postings: bool,
     },
 
-    /// List all wallets
+    /// List all wallets, sub-wallets under their parent
     List {
         /// Include archived wallets
         #[arg(long)]
         all: bool,
     },
 
+    /// Move a wallet under another wallet, or back to the top level
+    Move {
+        /// Wallet name
+        name: String,
+
+        /// New parent wallet (same type and currency)
+        #[arg(short, long, required_unless_present = "root", conflicts_with = "root")]
+        parent: Option<String>,
+
+        /// Make the wallet a top-level wallet
+        #[arg(long)]
+        root: bool,
+    },
+
+    /// Allow or deny transfers into a wallet that has sub-wallets
+    Postings {
+        /// Wallet name
+        name: String,
+
+        /// allow or deny
+        #[arg(value_parser = ["allow", "deny"])]
+        setting: String,
+    },
+
     /// Archive a wallet (soft delete)
     Archive {
         /// Wallet name
@@ -214,6 +569,34 @@ pub enum WalletCommands {
         /// Wallet name
         name: String,
     },
+
+    /// List the open lots of a holding wallet
+    Lots {
+        /// Holding wallet name
+        name: String,
+    },
+
+    /// Show the transfers of a wallet in date order with a running balance
+    Register {
+        /// Wallet name
+        name: String,
+
+        /// First day (YYYY-MM-DD, defaults to the first transfer)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Last day (YYYY-MM-DD, defaults to the last transfer)
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Output format: table, csv
+        #[arg(long, default_value = "table")]
+        format: String,
+
+        /// Output file for csv (stdout if omitted)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -223,9 +606,26 @@ pub enum BudgetCommands {
         /// Budget name (must be unique)
         name: String,
 
-        /// Category to track
+        /// Category to track, sub-categories included (e.g., "food" covers "food:groceries");
+        /// repeat for several
         #[arg(short, long)]
-        category: String,
+        category: Vec<String>,
+
+        /// Only count transfers from this wallet (repeat for several)
+        #[arg(long = "from-wallet")]
+        from_wallets: Vec<String>,
+
+        /// Only count transfers to this wallet (repeat for several)
+        #[arg(long = "to-wallet")]
+        to_wallets: Vec<String>,
+
+        /// Only count transfers with this tag (repeat or comma-separate; matches any of them)
+        #[arg(long = "tag", value_delimiter = ',')]
+        tags: Vec<String>,
+
+        /// Only count transfers carrying every given tag
+        #[arg(long, requires = "tags")]
+        all_tags: bool,
 
         /// Budget amount (e.g., "400" or "400.00")
         #[arg(short, long)]
@@ -234,6 +634,22 @@ pub enum BudgetCommands {
         /// Period: weekly, monthly, yearly
         #[arg(short, long)]
         period: String,
+
+        /// Currency of the amount; only spending in this currency counts (omit for any)
+        #[arg(long)]
+        currency: Option<String>,
+
+        /// What is left at the end of a period: none, carry-positive, carry-both
+        #[arg(long, default_value = "none")]
+        rollover: String,
+
+        /// First day whose period carries over, YYYY-MM-DD (default: creation day)
+        #[arg(long)]
+        rollover_since: Option<String>,
+
+        /// Percentages of the limit that raise an alert (e.g., "80,100")
+        #[arg(long = "alert", value_delimiter = ',')]
+        alert_thresholds: Vec<u32>,
     },
 
     /// List all budgets
@@ -242,6 +658,56 @@ pub enum BudgetCommands {
     /// Show budget status (spending vs limit)
     Status,
 
+    /// Set how a budget carries what is left of a period into the next one
+    Rollover {
+        /// Budget name
+        name: String,
+
+        /// Policy: none, carry-positive, carry-both
+        policy: String,
+
+        /// First day whose period carries over, YYYY-MM-DD (default: keep the current one)
+        #[arg(long)]
+        since: Option<String>,
+    },
+
+    /// Show the amounts a budget carried from period to period
+    RolloverHistory {
+        /// Budget name
+        name: String,
+    },
+
+    /// Set the percentages of a budget's limit that raise an alert
+    Alerts {
+        /// Budget name
+        name: String,
+
+        /// Percentages (e.g., "80,100"); omit to turn alerts off
+        #[arg(value_delimiter = ',')]
+        thresholds: Vec<u32>,
+    },
+
+    /// List the alerts budgets have raised, most recent first
+    Events {
+        /// Only show the alerts of this budget
+        name: Option<String>,
+    },
+
+    /// Show or set where budget alerts are delivered
+    Notify {
+        /// Shell command run for each alert, with the alert as JSON on stdin
+        #[arg(long)]
+        command: Option<String>,
+
+        /// File each alert is appended to as a line of JSON
+        #[arg(long)]
+        file: Option<String>,
+
+        /// Stop delivering alerts to a command or file
+        #[arg(long, conflicts_with_all = ["command", "file"])]
+        clear: bool,
+    },
+
     /// Delete a budget
     Delete {
         /// Budget name
@@ -250,43 +716,114 @@ pub enum BudgetCommands {
 }
 
 #[derive(Subcommand)]
-pub enum ScheduledCommands {
-    /// Create a new scheduled transfer
+pub enum EnvelopeCommands {
+    /// Create an envelope for the spending in a category
     Create {
-        /// Scheduled transfer name (must be unique)
+        /// Envelope name (must be unique)
         name: String,
 
-        /// Source wallet name
+        /// Category it pays for, sub-categories included
+        #[arg(short, long)]
+        category: String,
+
+        /// Currency of the envelope and of the income it is filled from
+        #[arg(long, default_value = "EUR")]
+        currency: String,
+
+        /// First month budgeted (YYYY-MM, defaults to the current month)
         #[arg(long)]
-        from: String,
+        start: Option<String>,
+    },
 
-        /// Destination wallet name
+    /// List all envelopes
+    List,
+
+    /// Assign money from "to be assigned" to an envelope (negative to take it back)
+    Assign {
+        /// Envelope name
+        name: String,
+
+        /// Amount (e.g., "250.00", or "-50" to return money to the pool)
+        #[arg(allow_hyphen_values = true)]
+        amount: String,
+
+        /// Month (YYYY-MM, defaults to the current month)
         #[arg(long)]
+        month: Option<String>,
+    },
+
+    /// Move money from one envelope to another
+    Move {
+        /// Envelope to take the money from
+        from: String,
+
+        /// Envelope to put it in
         to: String,
 
-        /// Amount to transfer (e.g., "50.00" or "50")
-        #[arg(short, long)]
+        /// Amount (e.g., "40.00")
         amount: String,
 
-        /// Recurrence pattern: daily, weekly, monthly, yearly
-        #[arg(short, long)]
-        pattern: String,
-
-        /// Start date (ISO 8601 format: YYYY-MM-DD)
+        /// Month (YYYY-MM, defaults to the current month)
         #[arg(long)]
-        start_date: String,
+        month: Option<String>,
+    },
 
-        /// Optional end date (ISO 8601 format: YYYY-MM-DD)
+    /// Show assigned, activity and available per envelope for a month
+    Status {
+        /// Month (YYYY-MM, defaults to the current month)
         #[arg(long)]
-        end_date: Option<String>,
+        month: Option<String>,
+    },
 
-        /// Description of the transfer
-        #[arg(short, long)]
+    /// Delete an envelope; its money goes back to "to be assigned"
+    Delete {
+        /// Envelope name
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ScheduledCommands {
+    /// Create a new scheduled transfer
+    Create {
+        /// Scheduled transfer name (must be unique)
+        name: String,
+
+        /// Source wallet name
+        #[arg(long)]
+        from: String,
+
+        /// Destination wallet name
+        #[arg(long)]
+        to: String,
+
+        /// Amount to transfer (e.g., "50.00" or "50")
+        #[arg(short, long)]
+        amount: String,
+
+        /// Recurrence pattern: daily, weekly, monthly, yearly
+        #[arg(short, long)]
+        pattern: String,
+
+        /// Start date (ISO 8601 format: YYYY-MM-DD)
+        #[arg(long)]
+        start_date: String,
+
+        /// Optional end date (ISO 8601 format: YYYY-MM-DD)
+        #[arg(long)]
+        end_date: Option<String>,
+
+        /// Description of the transfer
+        #[arg(short, long)]
         description: Option<String>,
 
         /// Category for budgeting
         #[arg(short, long)]
         category: Option<String>,
+
+        /// Tag every transfer the schedule creates (repeat or comma-separate)
+        #[arg(long = "tag", value_delimiter = ',')]
+        tags: Vec<String>,
     },
 
     /// List all scheduled transfers
@@ -342,6 +879,349 @@ pub enum ScheduledCommands {
     },
 }
 
+#[derive(Subcommand)]
+pub enum RateCommands {
+    /// Record an exchange rate: 1 BASE = RATE QUOTE
+    Add {
+        /// Base currency (e.g., EUR)
+        base: String,
+
+        /// Quote currency (e.g., USD)
+        quote: String,
+
+        /// Units of quote currency per unit of base currency (e.g., 1.08)
+        rate: Rate,
+
+        /// Effective date (YYYY-MM-DD, defaults to now)
+        #[arg(long)]
+        date: Option<String>,
+    },
+
+    /// List stored exchange rates
+    List,
+}
+
+#[derive(Subcommand)]
+pub enum ReconcileCommands {
+    /// Start reconciling a wallet against a statement
+    Start {
+        /// Wallet name
+        wallet: String,
+
+        /// Statement end date (YYYY-MM-DD)
+        #[arg(long)]
+        date: String,
+
+        /// Ending balance on the statement (e.g., "1234.56" or "-80.00")
+        #[arg(long, allow_hyphen_values = true)]
+        balance: String,
+    },
+
+    /// Show the open session: cleared balance, difference and transfers to tick
+    Status {
+        /// Wallet name
+        wallet: String,
+    },
+
+    /// Tick off transfers that appear on the statement
+    Tick {
+        /// Wallet name
+        wallet: String,
+
+        /// Transfer IDs
+        #[arg(required = true)]
+        ids: Vec<String>,
+    },
+
+    /// Undo ticks made in the open session
+    Untick {
+        /// Wallet name
+        wallet: String,
+
+        /// Transfer IDs
+        #[arg(required = true)]
+        ids: Vec<String>,
+    },
+
+    /// Finish the session, locking the cleared transfers as reconciled
+    Finish {
+        /// Wallet name
+        wallet: String,
+    },
+
+    /// Abandon the open session (ticked transfers stay cleared)
+    Cancel {
+        /// Wallet name
+        wallet: String,
+    },
+
+    /// List reconcile sessions
+    List {
+        /// Wallet name (omit for all wallets)
+        wallet: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum PriceCommands {
+    /// Record a price: 1 COMMODITY = PRICE CURRENCY
+    Add {
+        /// Commodity code (e.g., VWCE)
+        commodity: String,
+
+        /// Price per unit (e.g., 98.50)
+        price: f64,
+
+        /// Currency of the price (e.g., EUR)
+        #[arg(short, long)]
+        currency: String,
+
+        /// Effective date (YYYY-MM-DD, defaults to now)
+        #[arg(long)]
+        date: Option<String>,
+    },
+
+    /// List stored prices
+    List {
+        /// Only show prices of this commodity
+        commodity: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum PayeeCommands {
+    /// Create a payee
+    Create {
+        /// Payee name
+        name: String,
+
+        /// Other spelling found in descriptions (repeat for several)
+        #[arg(long = "alias")]
+        aliases: Vec<String>,
+
+        /// Category for the payee's transfers when none is given
+        #[arg(short, long)]
+        category: Option<String>,
+
+        /// Wallet for the side a transfer with this payee leaves open (e.g., an expense wallet)
+        #[arg(short, long)]
+        wallet: Option<String>,
+    },
+
+    /// Add an alias to a payee
+    Alias {
+        /// Payee name or alias
+        name: String,
+
+        /// New alias
+        alias: String,
+    },
+
+    /// Remove an alias from a payee
+    Unalias {
+        /// Payee name
+        name: String,
+
+        /// Alias to remove
+        alias: String,
+    },
+
+    /// Set a payee's default category and wallet (omitted ones are cleared)
+    Defaults {
+        /// Payee name or alias
+        name: String,
+
+        /// Default category
+        #[arg(short, long)]
+        category: Option<String>,
+
+        /// Default wallet
+        #[arg(short, long)]
+        wallet: Option<String>,
+    },
+
+    /// List payees
+    List,
+
+    /// Show a payee
+    Show {
+        /// Payee name or alias
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum LockCommands {
+    /// Lock every day up to and including DATE
+    Set {
+        /// Last locked day (YYYY-MM-DD)
+        date: String,
+
+        /// Lock only this wallet and its sub-wallets (the whole ledger when omitted)
+        #[arg(short, long)]
+        wallet: Option<String>,
+
+        /// Why the period is closed
+        #[arg(long)]
+        reason: Option<String>,
+
+        /// Who closes the period (defaults to $USER)
+        #[arg(long)]
+        author: Option<String>,
+    },
+
+    /// Lift the lock of the ledger or of one wallet
+    Clear {
+        /// Wallet whose lock to lift (the ledger's when omitted)
+        #[arg(short, long)]
+        wallet: Option<String>,
+
+        /// Why the lock is lifted
+        #[arg(long)]
+        reason: Option<String>,
+
+        /// Who lifts the lock (defaults to $USER)
+        #[arg(long)]
+        author: Option<String>,
+    },
+
+    /// Show the locks in force
+    Show,
+
+    /// Show every lock set or lifted, and the overrides let through
+    History,
+}
+
+#[derive(Subcommand)]
+pub enum YearCommands {
+    /// Close a year: move its income and expenses into retained earnings
+    Close {
+        /// Year to close (e.g., 2025)
+        year: i32,
+
+        /// Also move the year's history into this read-only database file
+        #[arg(long, value_name = "PATH")]
+        archive: Option<String>,
+    },
+
+    /// Archive a year that is already closed
+    Archive {
+        /// Year to archive
+        year: i32,
+
+        /// Database file to create for the year's history
+        #[arg(long, value_name = "PATH")]
+        to: String,
+    },
+
+    /// List closed years
+    List,
+}
+
+#[derive(Subcommand)]
+pub enum AssertionCommands {
+    /// Assert that a wallet held exactly AMOUNT at the end of a day
+    Add {
+        /// Wallet name
+        wallet: String,
+
+        /// Balance as the ledger shows it (negative for what is owed on a liability)
+        #[arg(allow_hyphen_values = true)]
+        amount: String,
+
+        /// Day of the balance (YYYY-MM-DD)
+        #[arg(long)]
+        date: String,
+
+        /// Post the difference against the adjustments wallet so the assertion holds
+        #[arg(long)]
+        adjust: bool,
+
+        /// Rounding for amounts with too many decimals: reject, half-up, half-even, truncate
+        #[arg(long, default_value = "reject")]
+        round: String,
+    },
+
+    /// Check every assertion against the computed balances
+    Check,
+
+    /// Drop the assertion of a wallet for a day
+    Remove {
+        /// Wallet name
+        wallet: String,
+
+        /// Day of the assertion (YYYY-MM-DD)
+        #[arg(long)]
+        date: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CurrencyCommands {
+    /// Register a currency's number of decimals (e.g., a crypto asset)
+    Add {
+        /// Currency code (e.g., ETH)
+        code: String,
+
+        /// Number of decimals of the minor unit
+        #[arg(long)]
+        decimals: u32,
+    },
+
+    /// Show the number of decimals used for a currency
+    Show {
+        /// Currency code (e.g., JPY)
+        code: String,
+    },
+
+    /// List registered currencies
+    List,
+}
+
+#[derive(Subcommand)]
+pub enum LocaleCommands {
+    /// Define a custom locale profile
+    Add {
+        /// Profile name (e.g., "de-AT")
+        name: String,
+
+        /// Decimal separator
+        #[arg(long, default_value = ".")]
+        decimal: char,
+
+        /// Grouping (thousands) separator (omit for no grouping)
+        #[arg(long)]
+        grouping: Option<char>,
+
+        /// Currency symbol placement: none, before, after
+        #[arg(long, default_value = "none")]
+        symbol: String,
+
+        /// Put a space between the currency symbol and the amount
+        #[arg(long)]
+        symbol_space: bool,
+
+        /// Negative amounts: leading-minus, trailing-minus, parentheses
+        #[arg(long, default_value = "leading-minus")]
+        negative: String,
+    },
+
+    /// Use a locale profile for this ledger
+    Use {
+        /// Profile name (e.g., "de-DE", or "plain" for the default)
+        name: String,
+    },
+
+    /// Show a locale profile with sample amounts (the active one if omitted)
+    Show {
+        /// Profile name
+        name: Option<String>,
+    },
+
+    /// List built-in and custom locale profiles
+    List,
+}
+
 #[derive(Subcommand)]
 pub enum ReportCommands {
     /// Category spending breakdown
@@ -357,10 +1237,22 @@ pub enum ReportCommands {
         /// Output format: table, json, csv
         #[arg(long, default_value = "table")]
         format: String,
+
+        /// Convert amounts into this reporting currency (e.g., EUR)
+        #[arg(long)]
+        currency: Option<String>,
+
+        /// Roll sub-categories up into their parents at this depth (1 = top level)
+        #[arg(long)]
+        depth: Option<usize>,
+
+        /// Show categories as a tree with subtotals
+        #[arg(long)]
+        tree: bool,
     },
 
-    /// Income vs Expense analysis
-    IncomeExpense {
+    /// Spending by tag (a transfer with several tags counts under each)
+    Tags {
         /// Start date (YYYY-MM-DD, defaults to start of current month)
         #[arg(long)]
         from: Option<String>,
@@ -372,10 +1264,14 @@ pub enum ReportCommands {
         /// Output format: table, json, csv
         #[arg(long, default_value = "table")]
         format: String,
+
+        /// Convert amounts into this reporting currency (e.g., EUR)
+        #[arg(long)]
+        currency: Option<String>,
     },
 
-    /// Cash flow over time
-    Cashflow {
+    /// Top payees by total, with how often they were paid
+    Payees {
         /// Start date (YYYY-MM-DD, defaults to start of current month)
         #[arg(long)]
         from: Option<String>,
@@ -384,24 +1280,40 @@ pub enum ReportCommands {
         #[arg(long)]
         to: Option<String>,
 
-        /// Period: weekly, monthly, yearly
-        #[arg(long, default_value = "monthly")]
-        period: String,
+        /// Number of payees to show
+        #[arg(long, default_value = "10")]
+        limit: usize,
 
         /// Output format: table, json, csv
         #[arg(long, default_value = "table")]
         format: String,
     },
 
-    /// Net worth summary
-    NetWorth {
+    /// Income vs Expense analysis
+    IncomeExpense {
+        /// Start date (YYYY-MM-DD, defaults to start of current month)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// End date (YYYY-MM-DD, defaults to now)
+        #[arg(long)]
+        to: Option<String>,
+
         /// Output format: table, json, csv
         #[arg(long, default_value = "table")]
         format: String,
     },
 
-    /// Compare current period to previous
-    Compare {
+    /// Cash flow over time
+    Cashflow {
+        /// Start date (YYYY-MM-DD, defaults to start of current month)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// End date (YYYY-MM-DD, defaults to now)
+        #[arg(long)]
+        to: Option<String>,
+
         /// Period: weekly, monthly, yearly
         #[arg(long, default_value = "monthly")]
         period: String,
@@ -409,8 +1321,102 @@ pub enum ReportCommands {
         /// Output format: table, json, csv
         #[arg(long, default_value = "table")]
         format: String,
+
+        /// Convert amounts into this reporting currency (e.g., EUR)
+        #[arg(long)]
+        currency: Option<String>,
+
+        /// Break each period down by category, rolled up at this depth (1 = top level)
+        #[arg(long)]
+        depth: Option<usize>,
     },
-}
+
+    /// Net worth summary
+    NetWorth {
+        /// Output format: table, json, csv
+        #[arg(long, default_value = "table")]
+        format: String,
+
+        /// Convert amounts into this reporting currency (e.g., EUR)
+        #[arg(long)]
+        currency: Option<String>,
+    },
+
+    /// Net worth, assets and liabilities at the end of every day, week or month
+    NetWorthHistory {
+        /// First day (YYYY-MM-DD, defaults to a year before --to)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Last day (YYYY-MM-DD, defaults to today)
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Sample at the end of every: day, week, month
+        #[arg(long, default_value = "month")]
+        interval: String,
+
+        /// Currency to convert into (defaults to the one asset and liability wallets share)
+        #[arg(long)]
+        currency: Option<String>,
+
+        /// Output format: chart, table, json, csv
+        #[arg(long, default_value = "chart")]
+        format: String,
+    },
+
+    /// Budget limits against actual spending in each completed period
+    BudgetVariance {
+        /// First day (YYYY-MM-DD, defaults to a year before --to)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Last day (YYYY-MM-DD, defaults to today)
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Only this budget
+        #[arg(long)]
+        budget: Option<String>,
+
+        /// Output format: table, json, csv
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+
+    /// Investment holdings: market value, cost basis and unrealized gain
+    Holdings {
+        /// Output format: table, json, csv
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+
+    /// Gains and losses realized by sales in a calendar year, per lot
+    Gains {
+        /// Year (defaults to the current year)
+        #[arg(long)]
+        year: Option<i32>,
+
+        /// Output format: table, json, csv
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+
+    /// Compare current period to previous
+    Compare {
+        /// Period: weekly, monthly, yearly
+        #[arg(long, default_value = "monthly")]
+        period: String,
+
+        /// Output format: table, json, csv
+        #[arg(long, default_value = "table")]
+        format: String,
+
+        /// Compare categories too, rolled up at this depth (1 = top level)
+        #[arg(long)]
+        depth: Option<usize>,
+    },
+}
 
 impl Cli {
     async fn auto_execute_scheduled(&self, service: &LedgerService) -> Result<()> {
@@ -423,15 +1429,27 @@ impl Cli {
                 "[Auto-exec] Executed {} scheduled transfer(s)",
                 results.len()
             );
+            let currencies = service.get_wallet_currencies().await?;
+            let locale = &active_locale(service, self.locale.as_deref()).await?;
             for result in results {
                 eprintln!(
                     "  {} -> {}: {}",
                     result.from_wallet_name,
                     result.to_wallet_name,
-                    format_cents(result.transfer.amount_cents)
+                    wallet_money(
+                        locale,
+                        &currencies,
+                        result.transfer.from_wallet,
+                        result.transfer.amount_cents
+                    )
                 );
             }
         }
+        // Alerts go to stderr so they never mix with a command's output
+        let locale = active_locale(service, self.locale.as_deref()).await?;
+        for line in budget_warnings(service, &locale).await? {
+            eprintln!("{}", line);
+        }
         Ok(())
     }
 
@@ -451,7 +1469,8 @@ impl Cli {
 
             Commands::Wallet(wallet_cmd) => {
                 let service = LedgerService::connect(&self.database).await?;
-                run_wallet_command(&service, wallet_cmd).await?;
+                let locale = active_locale(&service, self.locale.as_deref()).await?;
+                run_wallet_command(&service, &locale, wallet_cmd).await?;
             }
 
             Commands::Transfer {
@@ -460,12 +1479,30 @@ impl Cli {
                 to,
                 description,
                 category,
+                tags,
+                payee,
+                rate,
+                round,
                 force,
                 date,
+                override_lock,
             } => {
                 let service = LedgerService::connect(&self.database).await?;
-                let amount_cents =
-                    parse_cents(&amount).context("Invalid amount format. Use '50.00' or '50'")?;
+                let locale = active_locale(&service, self.locale.as_deref()).await?;
+                let currency = match (&from, &payee) {
+                    (Some(from), _) => service.get_wallet_currency(from).await?,
+                    // The amount leaves the payee's wallet
+                    (None, Some(payee)) => {
+                        let payee = service.get_payee(payee).await?;
+                        let wallet_id = payee.default_wallet.with_context(|| {
+                            format!("Payee {} has no default wallet; give --from", payee.name)
+                        })?;
+                        let wallet = service.get_wallet_by_id(wallet_id).await?;
+                        service.get_currency(&wallet.currency).await?
+                    }
+                    (None, None) => anyhow::bail!("Give --from or --payee"),
+                };
+                let amount_cents = parse_money(&locale, &amount, &currency, &round)?;
 
                 // Parse date or use now
                 let timestamp = match date {
@@ -475,120 +1512,569 @@ impl Cli {
                     None => Utc::now(),
                 };
 
+                // An open side is filled in from the payee's default wallet
+                let request = TransferRequest::new(
+                    from.unwrap_or_default(),
+                    to.unwrap_or_default(),
+                    amount_cents,
+                    timestamp,
+                )
+                .with_description(description)
+                .with_category(category)
+                .with_tags(tags)
+                .with_payee(payee)
+                .with_exchange_rate(rate)
+                .with_force(force)
+                .with_lock_override(lock_override(override_lock, None));
+                let result = service.record_transfer_with(request).await?;
+
+                println!(
+                    "Recorded transfer: {} {} -> {} ({})",
+                    format_money(&locale, result.transfer.amount_cents, &currency),
+                    result.from_wallet_name,
+                    result.to_wallet_name,
+                    result.transfer.id
+                );
+                if let Some(conversion) = &result.converted {
+                    let to_currency = service.get_currency(&conversion.currency).await?;
+                    println!(
+                        "  Converted at {}: {} ({})",
+                        conversion.rate,
+                        locale.format_money(conversion.amount_cents, &to_currency),
+                        conversion.leg.id
+                    );
+                }
+                print_budget_warnings(&service, &locale).await?;
+            }
+
+            Commands::Split {
+                from,
+                legs,
+                description,
+                round,
+                force,
+                date,
+                override_lock,
+            } => {
+                let service = LedgerService::connect(&self.database).await?;
+                let locale = active_locale(&service, self.locale.as_deref()).await?;
+                let currency = service.get_wallet_currency(&from).await?;
+                let legs = legs
+                    .iter()
+                    .map(|leg| parse_split_leg(&locale, leg, &currency, &round))
+                    .collect::<Result<Vec<_>>>()?;
+
+                let timestamp = match date {
+                    Some(date_str) => parse_date(&date_str).with_context(|| {
+                        format!("Invalid date format '{}'. Use YYYY-MM-DD", date_str)
+                    })?,
+                    None => Utc::now(),
+                };
+
                 let result = service
-                    .record_transfer(
+                    .record_split_transfer_with(
                         &from,
-                        &to,
-                        amount_cents,
+                        legs,
                         timestamp,
                         description,
-                        category,
                         force,
+                        lock_override(override_lock, None),
                     )
                     .await?;
 
                 println!(
-                    "Recorded transfer: {} {} -> {} ({})",
-                    format_cents(result.transfer.amount_cents),
+                    "Recorded split: {} from {} in {} legs ({})",
+                    format_money(&locale, result.total, &currency),
                     result.from_wallet_name,
-                    result.to_wallet_name,
-                    result.transfer.id
+                    result.legs.len(),
+                    result.split_id
+                );
+                for leg in &result.legs {
+                    println!(
+                        "  {} -> {}{} ({})",
+                        format_money(&locale, leg.transfer.amount_cents, &currency),
+                        leg.to_wallet_name,
+                        leg.transfer
+                            .category
+                            .as_ref()
+                            .map(|c| format!(" [{}]", c))
+                            .unwrap_or_default(),
+                        leg.transfer.id
+                    );
+                }
+                print_budget_warnings(&service, &locale).await?;
+            }
+
+            Commands::OpeningBalances {
+                balances,
+                as_of,
+                round,
+            } => {
+                let service = LedgerService::connect(&self.database).await?;
+                let locale = active_locale(&service, self.locale.as_deref()).await?;
+                let as_of = NaiveDate::parse_from_str(&as_of, "%Y-%m-%d")
+                    .with_context(|| format!("Invalid date format '{}'. Use YYYY-MM-DD", as_of))?;
+                let mut opening = Vec::new();
+                for spec in &balances {
+                    let (wallet, amount) = spec.split_once('=').ok_or_else(|| {
+                        anyhow::anyhow!("Invalid balance '{}'. Use WALLET=AMOUNT", spec)
+                    })?;
+                    let currency = service.get_wallet_currency(wallet.trim()).await?;
+                    let amount_cents = parse_money(&locale, amount, &currency, &round)
+                        .with_context(|| format!("Invalid amount in balance '{}'", spec))?;
+                    opening.push(OpeningBalance::new(wallet.trim(), amount_cents));
+                }
+
+                let result = service.record_opening_balances(opening, as_of).await?;
+                for posted in &result.posted {
+                    let currency = service.get_wallet_currency(&posted.to_wallet_name).await?;
+                    println!(
+                        "Posted {} from {} to {} ({})",
+                        format_money(&locale, posted.transfer.amount_cents, &currency),
+                        posted.from_wallet_name,
+                        posted.to_wallet_name,
+                        posted.transfer.id
+                    );
+                }
+                for (wallet, existing) in &result.already_posted {
+                    println!(
+                        "Skipped {}: opening balance already posted on {} ({})",
+                        wallet,
+                        existing.timestamp.format("%Y-%m-%d"),
+                        existing.id
+                    );
+                }
+                if result.posted.is_empty() && result.already_posted.is_empty() {
+                    println!("Nothing to post: every balance is zero.");
+                }
+            }
+
+            Commands::Buy {
+                quantity,
+                wallet,
+                price,
+                from,
+                description,
+                round,
+                force,
+                date,
+                override_lock,
+            } => {
+                let service = LedgerService::connect(&self.database).await?;
+                let locale = active_locale(&service, self.locale.as_deref()).await?;
+                let units = holding_units(&service, &wallet).await?;
+                let quantity = parse_money(&locale, &quantity, &units, &round)?;
+
+                let timestamp = match date {
+                    Some(date_str) => parse_date(&date_str).with_context(|| {
+                        format!("Invalid date format '{}'. Use YYYY-MM-DD", date_str)
+                    })?,
+                    None => Utc::now(),
+                };
+
+                let request = TradeRequest::new(wallet, from, quantity, price, timestamp)
+                    .with_description(description)
+                    .with_force(force)
+                    .with_lock_override(lock_override(override_lock, None));
+                let result = service.record_buy(request).await?;
+                print_trade(&service, &locale, "Bought", &units, &result).await?;
+            }
+
+            Commands::Sell {
+                quantity,
+                wallet,
+                price,
+                to,
+                method,
+                lots,
+                description,
+                round,
+                date,
+                override_lock,
+            } => {
+                let service = LedgerService::connect(&self.database).await?;
+                let locale = active_locale(&service, self.locale.as_deref()).await?;
+                let units = holding_units(&service, &wallet).await?;
+                let quantity = parse_money(&locale, &quantity, &units, &round)?;
+
+                let timestamp = match date {
+                    Some(date_str) => parse_date(&date_str).with_context(|| {
+                        format!("Invalid date format '{}'. Use YYYY-MM-DD", date_str)
+                    })?,
+                    None => Utc::now(),
+                };
+
+                let method = method
+                    .map(|m| {
+                        m.parse::<LotMethod>().map_err(|e| {
+                            anyhow::anyhow!(
+                                "Invalid lot method '{}'. Valid methods: fifo, lifo, average. Error: {}",
+                                m,
+                                e
+                            )
+                        })
+                    })
+                    .transpose()?;
+                let lots = lots
+                    .iter()
+                    .map(|spec| parse_lot_pick(&locale, spec, &units, &round))
+                    .collect::<Result<Vec<_>>>()?;
+
+                let request = TradeRequest::new(wallet, to, quantity, price, timestamp)
+                    .with_description(description)
+                    .with_lot_method(method)
+                    .with_lots(lots)
+                    .with_lock_override(lock_override(override_lock, None));
+                let result = service.record_sell(request).await?;
+                print_trade(&service, &locale, "Sold", &units, &result.trade).await?;
+
+                let currency = service
+                    .get_wallet_currency(&result.trade.from_wallet_name)
+                    .await?;
+                let label = if result.realized_gain < 0 {
+                    "loss"
+                } else {
+                    "gain"
+                };
+                println!(
+                    "  Cost basis {}, realized {} {} over {} lot(s)",
+                    locale.format_money(result.cost_basis, &currency),
+                    label,
+                    locale.format_money(result.realized_gain.abs(), &currency),
+                    result.disposals.len()
                 );
             }
 
-            Commands::Balance { wallet } => {
+            Commands::Balance { wallet, as_of } => {
                 let service = LedgerService::connect(&self.database).await?;
-                run_balance_command(&service, wallet).await?;
+                let locale = active_locale(&service, self.locale.as_deref()).await?;
+                let as_of = as_of.as_deref().map(parse_day).transpose()?;
+                run_balance_command(&service, &locale, wallet, as_of).await?;
             }
 
             Commands::Transfers {
                 wallet,
                 category,
+                tags,
+                all_tags,
                 from_date,
                 to_date,
                 limit,
             } => {
                 let service = LedgerService::connect(&self.database).await?;
-                run_transfers_command(&service, wallet, category, from_date, to_date, limit)
-                    .await?;
+                let locale = active_locale(&service, self.locale.as_deref()).await?;
+                let tag_match = if all_tags {
+                    TagMatch::All
+                } else {
+                    TagMatch::Any
+                };
+                run_transfers_command(
+                    &service, &locale, wallet, category, tags, tag_match, from_date, to_date, limit,
+                )
+                .await?;
             }
 
             Commands::Check => {
                 let service = LedgerService::connect(&self.database).await?;
-                run_check_command(&service).await?;
+                let locale = active_locale(&service, self.locale.as_deref()).await?;
+                run_check_command(&service, &locale).await?;
             }
 
-            Commands::Reverse { id, amount } => {
+            Commands::Reverse {
+                id,
+                amount,
+                override_lock,
+            } => {
                 let service = LedgerService::connect(&self.database).await?;
+                let locale = active_locale(&service, self.locale.as_deref()).await?;
                 let transfer_id =
                     Uuid::parse_str(&id).context("Invalid transfer ID format (expected UUID)")?;
 
+                let info = service.get_transfer_info(transfer_id).await?;
+                let currency = service.get_currency(&info.from_wallet.currency).await?;
                 let amount_cents = amount
-                    .map(|a| parse_cents(&a))
+                    .map(|a| parse_money(&locale, &a, &currency, "reject"))
                     .transpose()
                     .context("Invalid amount format for partial reversal")?;
 
-                let result = service.reverse_transfer(transfer_id, amount_cents).await?;
+                let result = service
+                    .reverse_transfer_with(
+                        transfer_id,
+                        amount_cents,
+                        lock_override(override_lock, None),
+                    )
+                    .await?;
+
+                if !result.split_reversals.is_empty() {
+                    let wallet_names = service.get_wallet_names().await?;
+                    let currencies = service.get_wallet_currencies().await?;
+                    println!("Reversed split of {} legs:", result.split_reversals.len());
+                    for reversal in &result.split_reversals {
+                        println!(
+                            "  {} {} -> {} ({})",
+                            wallet_money(
+                                &locale,
+                                &currencies,
+                                reversal.from_wallet,
+                                reversal.amount_cents
+                            ),
+                            wallet_name(&wallet_names, reversal.from_wallet),
+                            wallet_name(&wallet_names, reversal.to_wallet),
+                            reversal.id
+                        );
+                    }
+                    return Ok(());
+                }
 
                 if result.is_partial {
                     println!(
                         "Partially reversed: {} of {}",
-                        format_cents(result.reversal.amount_cents),
-                        format_cents(result.original.amount_cents)
+                        format_money(&locale, result.reversal.amount_cents, &currency),
+                        format_money(&locale, result.original.amount_cents, &currency)
                     );
                 } else {
                     println!(
                         "Reversed transfer: {} {} -> {}",
-                        format_cents(result.original.amount_cents),
+                        format_money(&locale, result.original.amount_cents, &currency),
                         result.from_wallet_name,
                         result.to_wallet_name
                     );
                 }
                 println!(
                     "Created reversal: {} {} -> {} ({})",
-                    format_cents(result.reversal.amount_cents),
+                    format_money(&locale, result.reversal.amount_cents, &currency),
                     result.to_wallet_name,
                     result.from_wallet_name,
                     result.reversal.id
                 );
             }
 
-            Commands::ShowTransfer { id } => {
+            Commands::ShowTransfer {
+                id,
+                extract,
+                attachment,
+            } => {
+                let service = LedgerService::connect(&self.database).await?;
+                let locale = active_locale(&service, self.locale.as_deref()).await?;
+                let transfer_id =
+                    Uuid::parse_str(&id).context("Invalid transfer ID format (expected UUID)")?;
+
+                match extract {
+                    Some(dir) => {
+                        let attachment_id = attachment
+                            .map(|id| {
+                                Uuid::parse_str(&id)
+                                    .context("Invalid attachment ID format (expected UUID)")
+                            })
+                            .transpose()?;
+                        run_extract_attachments_command(&service, transfer_id, &dir, attachment_id)
+                            .await?;
+                    }
+                    None => run_show_transfer_command(&service, &locale, transfer_id).await?,
+                }
+            }
+
+            Commands::Attach { id, files } => {
+                let service = LedgerService::connect(&self.database).await?;
+                let transfer_id =
+                    Uuid::parse_str(&id).context("Invalid transfer ID format (expected UUID)")?;
+
+                for path in files {
+                    let data = std::fs::read(&path)
+                        .with_context(|| format!("Failed to read file: {}", path))?;
+                    let attachment = service.attach_file(transfer_id, &path, &data).await?;
+                    println!(
+                        "Attached {} ({}, {}): {}",
+                        attachment.filename,
+                        attachment.media_type,
+                        format_size(attachment.size),
+                        attachment.id
+                    );
+                }
+            }
+
+            Commands::Detach { id } => {
+                let service = LedgerService::connect(&self.database).await?;
+                let attachment_id =
+                    Uuid::parse_str(&id).context("Invalid attachment ID format (expected UUID)")?;
+
+                let attachment = service.remove_attachment(attachment_id).await?;
+                println!(
+                    "Removed {} from transfer {}",
+                    attachment.filename, attachment.transfer_id
+                );
+            }
+
+            Commands::Edit {
+                id,
+                description,
+                clear_description,
+                category,
+                clear_category,
+                tags,
+                date,
+                amount,
+                reason,
+                author,
+                force,
+                override_lock,
+            } => {
                 let service = LedgerService::connect(&self.database).await?;
+                let locale = active_locale(&service, self.locale.as_deref()).await?;
                 let transfer_id =
                     Uuid::parse_str(&id).context("Invalid transfer ID format (expected UUID)")?;
 
-                run_show_transfer_command(&service, transfer_id).await?;
+                let author = author.or_else(|| std::env::var("USER").ok());
+                let mut edit = TransferEdit::new()
+                    .with_reason(reason)
+                    .with_lock_override(lock_override(override_lock, author.clone()))
+                    .with_author(author)
+                    .with_force(force);
+                if description.is_some() || clear_description {
+                    edit = edit.with_description(description);
+                }
+                if category.is_some() || clear_category {
+                    edit = edit.with_category(category);
+                }
+                if let Some(tags) = tags {
+                    edit = edit.with_tags(
+                        tags.split(',')
+                            .map(|t| t.trim().to_string())
+                            .filter(|t| !t.is_empty())
+                            .collect(),
+                    );
+                }
+                if let Some(date) = date {
+                    edit = edit.with_timestamp(parse_date(&date)?);
+                }
+                if let Some(amount) = amount {
+                    let info = service.get_transfer_info(transfer_id).await?;
+                    let currency = service.get_currency(&info.from_wallet.currency).await?;
+                    edit = edit.with_amount(
+                        parse_money(&locale, &amount, &currency, "reject")
+                            .context("Invalid amount format")?,
+                    );
+                }
+
+                let result = service.amend_transfer(transfer_id, edit).await?;
+                let changed: Vec<&str> = result
+                    .amendment
+                    .changes
+                    .iter()
+                    .map(|c| c.field.as_str())
+                    .collect();
+                println!(
+                    "Amended transfer {}: {}",
+                    result.transfer.id,
+                    changed.join(", ")
+                );
+                if !result.split_amendments.is_empty() {
+                    println!(
+                        "  Moved {} other split legs to the new date",
+                        result.split_amendments.len()
+                    );
+                }
             }
 
             Commands::Budget(budget_cmd) => {
                 let service = LedgerService::connect(&self.database).await?;
-                run_budget_command(&service, budget_cmd).await?;
+                let locale = active_locale(&service, self.locale.as_deref()).await?;
+                run_budget_command(&service, &locale, budget_cmd).await?;
+            }
+
+            Commands::Envelope(envelope_cmd) => {
+                let service = LedgerService::connect(&self.database).await?;
+                let locale = active_locale(&service, self.locale.as_deref()).await?;
+                run_envelope_command(&service, &locale, envelope_cmd).await?;
             }
 
             Commands::Scheduled(scheduled_cmd) => {
                 let service = LedgerService::connect(&self.database).await?;
-                run_scheduled_command(&service, scheduled_cmd).await?;
+                let locale = active_locale(&service, self.locale.as_deref()).await?;
+                run_scheduled_command(&service, &locale, scheduled_cmd).await?;
+                print_budget_warnings(&service, &locale).await?;
+            }
+
+            Commands::Rate(rate_cmd) => {
+                let service = LedgerService::connect(&self.database).await?;
+                run_rate_command(&service, rate_cmd).await?;
+            }
+
+            Commands::Price(price_cmd) => {
+                let service = LedgerService::connect(&self.database).await?;
+                run_price_command(&service, price_cmd).await?;
+            }
+
+            Commands::Reconcile(reconcile_cmd) => {
+                let service = LedgerService::connect(&self.database).await?;
+                let locale = active_locale(&service, self.locale.as_deref()).await?;
+                run_reconcile_command(&service, &locale, reconcile_cmd).await?;
+            }
+
+            Commands::Payee(payee_cmd) => {
+                let service = LedgerService::connect(&self.database).await?;
+                run_payee_command(&service, payee_cmd).await?;
+            }
+
+            Commands::Lock(lock_cmd) => {
+                let service = LedgerService::connect(&self.database).await?;
+                run_lock_command(&service, lock_cmd).await?;
+            }
+
+            Commands::Year(year_cmd) => {
+                let service = LedgerService::connect(&self.database).await?;
+                run_year_command(service, &self.database, year_cmd).await?;
+            }
+
+            Commands::Assertion(assertion_cmd) => {
+                let service = LedgerService::connect(&self.database).await?;
+                let locale = active_locale(&service, self.locale.as_deref()).await?;
+                run_assertion_command(&service, &locale, assertion_cmd).await?;
+            }
+
+            Commands::Currency(currency_cmd) => {
+                let service = LedgerService::connect(&self.database).await?;
+                run_currency_command(&service, currency_cmd).await?;
+            }
+
+            Commands::Locale(locale_cmd) => {
+                let service = LedgerService::connect(&self.database).await?;
+                run_locale_command(&service, self.locale.as_deref(), locale_cmd).await?;
             }
 
             Commands::Forecast { months, wallet } => {
                 let service = LedgerService::connect(&self.database).await?;
-                run_forecast_command(&service, months, wallet.as_deref()).await?;
+                let locale = active_locale(&service, self.locale.as_deref()).await?;
+                run_forecast_command(&service, &locale, months, wallet.as_deref()).await?;
             }
 
             Commands::Report(report_cmd) => {
-                let service = LedgerService::connect(&self.database).await?;
-                run_report_command(&service, report_cmd).await?;
+                let service = if self.with_archives {
+                    LedgerService::open_with_archives(&self.database).await?
+                } else {
+                    LedgerService::connect(&self.database).await?
+                };
+                let locale = active_locale(&service, self.locale.as_deref()).await?;
+                run_report_command(&service, &locale, report_cmd).await?;
             }
 
             Commands::Export {
                 export_type,
                 output,
                 format,
+                year,
             } => {
                 let service = LedgerService::connect(&self.database).await?;
-                run_export_command(&service, &export_type, output.as_deref(), format.as_deref())
-                    .await?;
+                let locale = configured_locale(&service, self.locale.as_deref()).await?;
+                run_export_command(
+                    &service,
+                    locale,
+                    &export_type,
+                    output.as_deref(),
+                    format.as_deref(),
+                    year,
+                )
+                .await?;
             }
 
             Commands::Import {
@@ -596,20 +2082,32 @@ impl Cli {
                 input,
                 dry_run,
                 skip_duplicates,
+                match_existing,
+                match_window,
+                confirm,
                 create_wallets,
                 validate,
+                override_lock,
             } => {
                 let service = LedgerService::connect(&self.database).await?;
-                run_import_command(
-                    &service,
-                    &import_type,
-                    input.as_deref(),
+                let confirmed = confirm
+                    .iter()
+                    .map(|spec| parse_confirmation(spec))
+                    .collect::<Result<_>>()?;
+                let options = crate::io::ImportOptions {
                     dry_run,
                     skip_duplicates,
-                    create_wallets,
-                    validate,
-                )
-                .await?;
+                    create_missing_wallets: create_wallets,
+                    validate_only: validate,
+                    locale: configured_locale(&service, self.locale.as_deref()).await?,
+                    match_existing,
+                    match_window_days: Some(match_window),
+                    confirmed,
+                    lock_override: lock_override(override_lock, None),
+                };
+                run_import_command(&service, &import_type, input.as_deref(), options).await?;
+                let locale = active_locale(&service, self.locale.as_deref()).await?;
+                print_budget_warnings(&service, &locale).await?;
             }
         }
 
@@ -617,26 +2115,90 @@ impl Cli {
     }
 }
 
-async fn run_wallet_command(service: &LedgerService, cmd: WalletCommands) -> Result<()> {
+async fn run_wallet_command(
+    service: &LedgerService,
+    locale: &LocaleProfile,
+    cmd: WalletCommands,
+) -> Result<()> {
     match cmd {
         WalletCommands::Create {
             name,
             wallet_type,
             currency,
             description,
+            commodity,
+            lot_method,
+            parent,
+            allow_postings,
         } => {
-            let wt: WalletType = wallet_type.parse().map_err(|e| {
-                anyhow::anyhow!(
-                    "Invalid wallet type '{}'. Valid types: asset, liability, income, expense, equity. Error: {}",
-                    wallet_type,
-                    e
-                )
-            })?;
-
-            let wallet = service
-                .create_wallet(name.clone(), wt, currency, description)
-                .await?;
-            println!("Created wallet: {} ({})", wallet.name, wallet.wallet_type);
+            let wallet = match parent {
+                Some(parent) => {
+                    service
+                        .create_sub_wallet(name.clone(), &parent, description)
+                        .await?
+                }
+                None => {
+                    // clap requires a type when there is no parent
+                    let wallet_type = wallet_type.unwrap_or_default();
+                    let wt: WalletType = wallet_type.parse().map_err(|e| {
+                        anyhow::anyhow!(
+                            "Invalid wallet type '{}'. Valid types: asset, liability, income, expense, equity. Error: {}",
+                            wallet_type,
+                            e
+                        )
+                    })?;
+
+                    match commodity {
+                        Some(commodity) => {
+                            if wt != WalletType::Asset {
+                                anyhow::bail!("Only asset wallets can hold a commodity");
+                            }
+                            let wallet = service
+                                .create_commodity_wallet(
+                                    name.clone(),
+                                    commodity,
+                                    currency,
+                                    description,
+                                )
+                                .await?;
+                            match lot_method {
+                                Some(method) => {
+                                    let method: LotMethod = method.parse().map_err(|e| {
+                                        anyhow::anyhow!(
+                                            "Invalid lot method '{}'. Valid methods: fifo, lifo, average. Error: {}",
+                                            method,
+                                            e
+                                        )
+                                    })?;
+                                    service.set_lot_method(&wallet.name, method).await?
+                                }
+                                None => wallet,
+                            }
+                        }
+                        None => {
+                            service
+                                .create_wallet(name.clone(), wt, currency, description)
+                                .await?
+                        }
+                    }
+                }
+            };
+            let wallet = if allow_postings {
+                service
+                    .set_wallet_allow_postings(&wallet.name, true)
+                    .await?
+            } else {
+                wallet
+            };
+            let wallets = service.list_wallets(false).await?;
+            let path = wallet_path(&wallet, &wallets);
+            match &wallet.commodity {
+                Some(commodity) => println!(
+                    "Created wallet: {} ({}, holds {})",
+                    path, wallet.wallet_type, commodity
+                ),
+                None => println!("Created wallet: {} ({})", path, wallet.wallet_type),
+            }
         }
 
         WalletCommands::List { all } => {
@@ -644,17 +2206,47 @@ async fn run_wallet_command(service: &LedgerService, cmd: WalletCommands) -> Res
             if wallets.is_empty() {
                 println!("No wallets found.");
             } else {
-                println!("{:<20} {:<12} {:<8}", "NAME", "TYPE", "CURRENCY");
-                println!("{}", "-".repeat(44));
-                for wallet in wallets {
+                let by_id: std::collections::HashMap<Uuid, &Wallet> =
+                    wallets.iter().map(|w| (w.id, w)).collect();
+                println!("{:<24} {:<12} {:<8}", "NAME", "TYPE", "CURRENCY");
+                println!("{}", "-".repeat(46));
+                for entry in wallet_tree(&wallets, &std::collections::HashMap::new()) {
+                    let wallet = by_id[&entry.wallet_id];
                     println!(
-                        "{:<20} {:<12} {:<8}",
-                        wallet.name, wallet.wallet_type, wallet.currency
+                        "{:<24} {:<12} {:<8}",
+                        tree_label(&wallet.name, entry.depth),
+                        wallet.wallet_type,
+                        wallet.currency
                     );
                 }
             }
         }
 
+        WalletCommands::Move { name, parent, root } => {
+            let wallet = service
+                .move_wallet(&name, if root { None } else { parent.as_deref() })
+                .await?;
+            let wallets = service.list_wallets(false).await?;
+            println!("Moved wallet: {}", wallet_path(&wallet, &wallets));
+        }
+
+        WalletCommands::Postings { name, setting } => {
+            let wallet = service
+                .set_wallet_allow_postings(&name, setting == "allow")
+                .await?;
+            if wallet.allow_postings {
+                println!(
+                    "{} accepts transfers alongside its sub-wallets",
+                    wallet.name
+                );
+            } else {
+                println!(
+                    "{} only takes transfers while it has no sub-wallets",
+                    wallet.name
+                );
+            }
+        }
+
         WalletCommands::Archive { name } => {
             service.archive_wallet(&name).await?;
             println!("Archived wallet: {}", name);
@@ -663,11 +2255,22 @@ async fn run_wallet_command(service: &LedgerService, cmd: WalletCommands) -> Res
         WalletCommands::Show { name } => {
             let info = service.get_wallet_info(&name).await?;
             let wallet = &info.wallet;
+            let currency = service.get_currency(&wallet.currency).await?;
+
+            let wallets = service.list_wallets(true).await?;
+            let sub_wallets = service.list_sub_wallets(&name).await?;
 
             println!("Wallet: {}", wallet.name);
             println!("  ID:             {}", wallet.id);
+            if wallet.parent_id.is_some() {
+                println!("  Path:           {}", wallet_path(wallet, &wallets));
+            }
             println!("  Type:           {}", wallet.wallet_type);
             println!("  Currency:       {}", wallet.currency);
+            if let Some(commodity) = &wallet.commodity {
+                println!("  Commodity:      {}", commodity);
+                println!("  Lot method:     {}", wallet.lot_method);
+            }
             println!(
                 "  Allow negative: {}",
                 if wallet.allow_negative { "yes" } else { "no" }
@@ -684,10 +2287,34 @@ async fn run_wallet_command(service: &LedgerService, cmd: WalletCommands) -> Res
             }
             println!();
             println!(
-                "  Balance:        {} {}",
-                format_cents(info.balance),
-                wallet.currency
+                "  Balance:        {}",
+                locale.format_money(info.balance, &currency)
             );
+            if !sub_wallets.is_empty() {
+                let entry = service.get_balance(&name).await?;
+                println!(
+                    "  Subtotal:       {}",
+                    locale.format_money(entry.subtotal, &currency)
+                );
+                let names: Vec<&str> = sub_wallets.iter().map(|w| w.name.as_str()).collect();
+                println!("  Sub-wallets:    {}", names.join(", "));
+                println!(
+                    "  Postings:       {}",
+                    if wallet.allow_postings {
+                        "allowed"
+                    } else {
+                        "sub-wallets only"
+                    }
+                );
+            }
+            if let (Some(commodity), Some(quantity)) = (&wallet.commodity, info.quantity) {
+                let units = service.get_currency(commodity).await?;
+                println!(
+                    "  Units:          {} {}",
+                    format_money(locale, quantity, &units),
+                    commodity
+                );
+            }
             println!(
                 "  Transfers:      {} ({} in, {} out)",
                 info.incoming_count + info.outgoing_count,
@@ -698,21 +2325,119 @@ async fn run_wallet_command(service: &LedgerService, cmd: WalletCommands) -> Res
                 println!("  Last activity:  {}", last.format("%Y-%m-%d %H:%M:%S"));
             }
         }
+
+        WalletCommands::Lots { name } => {
+            let lots = service.list_lots(&name).await?;
+            if lots.is_empty() {
+                println!("No open lots.");
+                return Ok(());
+            }
+            let units = holding_units(service, &name).await?;
+            let currency = service.get_wallet_currency(&name).await?;
+
+            println!(
+                "{:<36} {:<12} {:>14} {:>14} {:>14}",
+                "LOT", "ACQUIRED", "UNITS", "REMAINING", "COST"
+            );
+            println!("{}", "-".repeat(94));
+            for lot in &lots {
+                println!(
+                    "{:<36} {:<12} {:>14} {:>14} {:>14}",
+                    lot.id,
+                    lot.acquired_at.format("%Y-%m-%d"),
+                    format_money(locale, lot.quantity, &units),
+                    format_money(locale, lot.remaining_quantity, &units),
+                    format_money(locale, lot.remaining_cost, &currency)
+                );
+            }
+        }
+
+        WalletCommands::Register {
+            name,
+            from,
+            to,
+            format,
+            output,
+        } => {
+            let from = from.as_deref().map(parse_day).transpose()?;
+            let to = to.as_deref().map(parse_day).transpose()?;
+
+            if format == "csv" {
+                use crate::io::Exporter;
+                use std::fs::File;
+                use std::io::{Write, stdout};
+
+                let writer: Box<dyn Write> = match output.as_deref() {
+                    Some(path) => {
+                        let file = File::create(path)
+                            .with_context(|| format!("Failed to create output file: {}", path))?;
+                        Box::new(file)
+                    }
+                    None => Box::new(stdout()),
+                };
+                let count = Exporter::new(service)
+                    .with_locale(Some(locale.clone()))
+                    .export_register_csv(writer, &name, from, to)
+                    .await?;
+                if output.is_some() {
+                    eprintln!("Exported {} register entries", count);
+                }
+                return Ok(());
+            }
+
+            let register = service.get_register(&name, from, to).await?;
+            let currency = service.get_currency(&register.wallet.currency).await?;
+            let wallet_names = service.get_wallet_names().await?;
+
+            println!("Register: {}", register.wallet.name);
+            println!(
+                "{:<12} {:<20} {:<24} {:>12} {:>12}",
+                "DATE", "COUNTERPART", "DESCRIPTION", "AMOUNT", "BALANCE"
+            );
+            println!("{}", "-".repeat(84));
+            println!(
+                "{:<12} {:<20} {:<24} {:>12} {:>12}",
+                from.map(|d| d.to_string()).unwrap_or_default(),
+                "",
+                "Opening balance",
+                "",
+                format_money(locale, register.opening_balance, &currency)
+            );
+            for entry in &register.entries {
+                println!(
+                    "{:<12} {:<20} {:<24} {:>12} {:>12}",
+                    entry.transfer.timestamp.format("%Y-%m-%d").to_string(),
+                    truncate(wallet_name(&wallet_names, entry.counterpart), 20),
+                    truncate(entry.transfer.description.as_deref().unwrap_or(""), 24),
+                    format_money(locale, entry.amount_cents, &currency),
+                    format_money(locale, entry.balance, &currency)
+                );
+            }
+            println!("{}", "-".repeat(84));
+            println!(
+                "{:<58} {:>12} {:>12}",
+                format!("{} transfers", register.entries.len()),
+                "",
+                format_money(locale, register.closing_balance(), &currency)
+            );
+        }
     }
     Ok(())
 }
 
 async fn run_export_command(
     service: &LedgerService,
+    locale: Option<LocaleProfile>,
     export_type: &str,
     output: Option<&str>,
     _format: Option<&str>,
+    year: Option<i32>,
 ) -> Result<()> {
     use crate::io::Exporter;
     use std::fs::File;
     use std::io::{Write, stdout};
 
-    let exporter = Exporter::new(service);
+    let exporter = Exporter::new(service).with_locale(locale);
 
     // Determine output writer
     let writer: Box<dyn Write> = match output {
@@ -749,6 +2474,13 @@ async fn run_export_command(
                 eprintln!("Exported {} scheduled transfers", count);
             }
         }
+        "gains" => {
+            let year = year.unwrap_or_else(|| Utc::now().year());
+            let count = exporter.export_realized_gains_csv(writer, year).await?;
+            if output.is_some() {
+                eprintln!("Exported {} realized gains for {}", count, year);
+            }
+        }
         "full" => {
             let snapshot = exporter.export_full_json(writer).await?;
             if output.is_some() {
@@ -763,7 +2495,7 @@ async fn run_export_command(
         }
         _ => {
             anyhow::bail!(
-                "Invalid export type '{}'. Valid types: transfers, balances, budgets, scheduled, full",
+                "Invalid export type '{}'. Valid types: transfers, balances, budgets, scheduled, gains, full",
                 export_type
             );
         }
@@ -776,12 +2508,9 @@ async fn run_import_command(
     service: &LedgerService,
     import_type: &str,
     input: Option<&str>,
-    dry_run: bool,
-    skip_duplicates: bool,
-    create_wallets: bool,
-    validate: bool,
+    options: crate::io::ImportOptions,
 ) -> Result<()> {
-    use crate::io::{ImportOptions, Importer};
+    use crate::io::Importer;
     use std::fs::File;
     use std::io::{Read, stdin};
 
@@ -797,12 +2526,7 @@ async fn run_import_command(
         None => Box::new(stdin()),
     };
 
-    let options = ImportOptions {
-        dry_run,
-        skip_duplicates,
-        create_missing_wallets: create_wallets,
-        validate_only: validate,
-    };
+    let preview = options.validate_only || options.dry_run;
 
     let result = match import_type {
         "transfers" => importer.import_transfers_csv(reader, options).await?,
@@ -816,15 +2540,45 @@ async fn run_import_command(
     };
 
     // Display results
-    if validate || dry_run {
+    if preview {
         println!("Validation successful");
     } else {
         println!("Import complete");
     }
     println!("  Imported: {}", result.imported);
+    println!("  Matched:  {}", result.matched);
     println!("  Skipped:  {}", result.skipped);
     println!("  Errors:   {}", result.errors.len());
 
+    if !result.ambiguous.is_empty() {
+        let wallet_names = service.get_wallet_names().await?;
+        println!(
+            "\nNeeds confirmation ({} lines, settle with --confirm LINE=ID or LINE=new):",
+            result.ambiguous.len()
+        );
+        for ambiguous in &result.ambiguous {
+            println!(
+                "  Line {}: {} on {} {}",
+                ambiguous.line,
+                crate::domain::format_cents(ambiguous.amount_cents),
+                ambiguous.timestamp.format("%Y-%m-%d"),
+                ambiguous.description.as_deref().unwrap_or("")
+            );
+            for candidate in &ambiguous.candidates {
+                let transfer = &candidate.transfer;
+                println!(
+                    "    {} {} {} -> {} {} (score {:.2})",
+                    transfer.id,
+                    transfer.timestamp.format("%Y-%m-%d"),
+                    wallet_name(&wallet_names, transfer.from_wallet),
+                    wallet_name(&wallet_names, transfer.to_wallet),
+                    transfer.description.as_deref().unwrap_or(""),
+                    candidate.score
+                );
+            }
+        }
+    }
+
     if !result.errors.is_empty() {
         println!("\nErrors:");
         for error in result.errors.iter().take(10) {
@@ -847,55 +2601,1097 @@ async fn run_import_command(
     Ok(())
 }
 
-async fn run_report_command(service: &LedgerService, cmd: ReportCommands) -> Result<()> {
+async fn run_rate_command(service: &LedgerService, cmd: RateCommands) -> Result<()> {
+    match cmd {
+        RateCommands::Add {
+            base,
+            quote,
+            rate,
+            date,
+        } => {
+            let effective_date = match date {
+                Some(date_str) => parse_date(&date_str).with_context(|| {
+                    format!("Invalid date format '{}'. Use YYYY-MM-DD", date_str)
+                })?,
+                None => Utc::now(),
+            };
+
+            let exchange_rate = service
+                .add_exchange_rate(&base, &quote, rate, effective_date)
+                .await?;
+            println!(
+                "Recorded rate: 1 {} = {} {} (from {})",
+                exchange_rate.base,
+                exchange_rate.rate,
+                exchange_rate.quote,
+                exchange_rate.effective_date.format("%Y-%m-%d")
+            );
+        }
+
+        RateCommands::List => {
+            let rates = service.list_exchange_rates().await?;
+            if rates.is_empty() {
+                println!("No exchange rates found.");
+                return Ok(());
+            }
+
+            println!(
+                "{:<6} {:<6} {:>14} {:<12}",
+                "BASE", "QUOTE", "RATE", "EFFECTIVE"
+            );
+            println!("{}", "-".repeat(41));
+            for rate in rates {
+                println!(
+                    "{:<6} {:<6} {:>14} {:<12}",
+                    rate.base,
+                    rate.quote,
+                    rate.rate,
+                    rate.effective_date.format("%Y-%m-%d")
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_price_command(service: &LedgerService, cmd: PriceCommands) -> Result<()> {
+    match cmd {
+        PriceCommands::Add {
+            commodity,
+            price,
+            currency,
+            date,
+        } => {
+            let effective_date = match date {
+                Some(date_str) => parse_date(&date_str).with_context(|| {
+                    format!("Invalid date format '{}'. Use YYYY-MM-DD", date_str)
+                })?,
+                None => Utc::now(),
+            };
+
+            let price = service
+                .add_price(&commodity, &currency, price, effective_date)
+                .await?;
+            println!(
+                "Recorded price: 1 {} = {} {} (from {})",
+                price.commodity,
+                price.price,
+                price.currency,
+                price.effective_date.format("%Y-%m-%d")
+            );
+        }
+
+        PriceCommands::List { commodity } => {
+            let prices = service.list_prices(commodity.as_deref()).await?;
+            if prices.is_empty() {
+                println!("No prices found.");
+                return Ok(());
+            }
+
+            println!(
+                "{:<10} {:<8} {:>14} {:<12}",
+                "COMMODITY", "CURRENCY", "PRICE", "EFFECTIVE"
+            );
+            println!("{}", "-".repeat(47));
+            for price in prices {
+                println!(
+                    "{:<10} {:<8} {:>14} {:<12}",
+                    price.commodity,
+                    price.currency,
+                    price.price,
+                    price.effective_date.format("%Y-%m-%d")
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_reconcile_command(
+    service: &LedgerService,
+    locale: &LocaleProfile,
+    cmd: ReconcileCommands,
+) -> Result<()> {
+    match cmd {
+        ReconcileCommands::Start {
+            wallet,
+            date,
+            balance,
+        } => {
+            let statement_date = parse_date(&date)
+                .with_context(|| format!("Invalid date format '{}'. Use YYYY-MM-DD", date))?;
+            let currency = service.get_wallet_currency(&wallet).await?;
+            let balance = parse_money(locale, &balance, &currency, "reject")?;
+
+            let status = service
+                .start_reconciliation(&wallet, statement_date, balance)
+                .await?;
+            print_reconciliation_status(service, locale, &status).await?;
+        }
+
+        ReconcileCommands::Status { wallet } => {
+            let status = service.get_reconciliation_status(&wallet).await?;
+            print_reconciliation_status(service, locale, &status).await?;
+        }
+
+        ReconcileCommands::Tick { wallet, ids } => {
+            let ids = parse_transfer_ids(&ids)?;
+            let status = service.tick_transfers(&wallet, &ids).await?;
+            print_reconciliation_difference(service, locale, &status).await?;
+        }
+
+        ReconcileCommands::Untick { wallet, ids } => {
+            let ids = parse_transfer_ids(&ids)?;
+            let status = service.untick_transfers(&wallet, &ids).await?;
+            print_reconciliation_difference(service, locale, &status).await?;
+        }
+
+        ReconcileCommands::Finish { wallet } => {
+            let status = service.finish_reconciliation(&wallet).await?;
+            println!(
+                "Reconciled {} through {}",
+                status.wallet_name,
+                status.reconciliation.statement_date.format("%Y-%m-%d")
+            );
+        }
+
+        ReconcileCommands::Cancel { wallet } => {
+            service.cancel_reconciliation(&wallet).await?;
+            println!("Cancelled reconciliation of {}", wallet);
+        }
+
+        ReconcileCommands::List { wallet } => {
+            let sessions = service.list_reconciliations(wallet.as_deref()).await?;
+            if sessions.is_empty() {
+                println!("No reconciliations found.");
+                return Ok(());
+            }
+
+            let wallet_names = service.get_wallet_names().await?;
+            let currencies = service.get_wallet_currencies().await?;
+            println!(
+                "{:<20} {:<12} {:>14} {:<12}",
+                "WALLET", "STATEMENT", "BALANCE", "FINISHED"
+            );
+            println!("{}", "-".repeat(61));
+            for session in sessions {
+                println!(
+                    "{:<20} {:<12} {:>14} {:<12}",
+                    truncate(wallet_name(&wallet_names, session.wallet_id), 20),
+                    session.statement_date.format("%Y-%m-%d"),
+                    wallet_money(
+                        locale,
+                        &currencies,
+                        session.wallet_id,
+                        session.statement_balance
+                    ),
+                    session
+                        .finished_at
+                        .map(|dt| dt.format("%Y-%m-%d").to_string())
+                        .unwrap_or_else(|| "in progress".to_string())
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn print_reconciliation_status(
+    service: &LedgerService,
+    locale: &LocaleProfile,
+    status: &crate::application::ReconciliationStatus,
+) -> Result<()> {
+    let wallet_names = service.get_wallet_names().await?;
+    let currency = service.get_wallet_currency(&status.wallet_name).await?;
+
+    println!(
+        "Reconciling {} to statement of {}",
+        status.wallet_name,
+        status.reconciliation.statement_date.format("%Y-%m-%d")
+    );
+    println!();
+    if status.pending.is_empty() {
+        println!("No transfers to tick off.");
+    } else {
+        println!(
+            "{:<36} {:<2} {:<12} {:>12} {:<15} DESCRIPTION",
+            "ID", "C", "DATE", "AMOUNT", "COUNTERPART"
+        );
+        println!("{}", "-".repeat(100));
+        for transfer in &status.pending {
+            // Money leaving the wallet shows as negative
+            let (amount, counterpart) = if transfer.from_wallet == status.reconciliation.wallet_id {
+                (-transfer.amount_cents, transfer.to_wallet)
+            } else {
+                (transfer.amount_cents, transfer.from_wallet)
+            };
+            println!(
+                "{:<36} {:<2} {:<12} {:>12} {:<15} {}",
+                transfer.id,
+                transfer.cleared.marker(),
+                transfer.timestamp.format("%Y-%m-%d"),
+                format_money(locale, amount, &currency),
+                truncate(wallet_name(&wallet_names, counterpart), 15),
+                truncate(transfer.description.as_deref().unwrap_or(""), 30)
+            );
+        }
+    }
+    println!();
+    print_reconciliation_difference(service, locale, status).await
+}
+
+async fn print_reconciliation_difference(
+    service: &LedgerService,
+    locale: &LocaleProfile,
+    status: &crate::application::ReconciliationStatus,
+) -> Result<()> {
+    let currency = service.get_wallet_currency(&status.wallet_name).await?;
+    println!(
+        "Statement balance: {}",
+        locale.format_money(status.reconciliation.statement_balance, &currency)
+    );
+    println!(
+        "Cleared balance:   {}",
+        locale.format_money(status.cleared_balance, &currency)
+    );
+    println!(
+        "Difference:        {}{}",
+        locale.format_money(status.difference, &currency),
+        if status.difference == 0 {
+            "  (ready to finish)"
+        } else {
+            ""
+        }
+    );
+    Ok(())
+}
+
+/// Parse an import confirmation given as `LINE=TRANSFER_ID` or `LINE=new`.
+fn parse_confirmation(spec: &str) -> Result<(usize, Option<Uuid>)> {
+    let (line, answer) = spec.split_once('=').ok_or_else(|| {
+        anyhow::anyhow!("Invalid confirmation '{}'. Use LINE=ID or LINE=new", spec)
+    })?;
+    let line: usize = line
+        .trim()
+        .parse()
+        .with_context(|| format!("Invalid line number '{}'", line))?;
+    let answer = answer.trim();
+    if answer.eq_ignore_ascii_case("new") {
+        return Ok((line, None));
+    }
+    let id = Uuid::parse_str(answer).context("Invalid transfer ID format (expected UUID)")?;
+    Ok((line, Some(id)))
+}
+
+fn parse_transfer_ids(ids: &[String]) -> Result<Vec<Uuid>> {
+    ids.iter()
+        .map(|id| Uuid::parse_str(id).context("Invalid transfer ID format (expected UUID)"))
+        .collect()
+}
+
+async fn run_payee_command(service: &LedgerService, cmd: PayeeCommands) -> Result<()> {
+    match cmd {
+        PayeeCommands::Create {
+            name,
+            aliases,
+            category,
+            wallet,
+        } => {
+            let payee = service
+                .create_payee(name, aliases, category, wallet.as_deref())
+                .await?;
+            println!("Created payee: {}", payee.name);
+        }
+
+        PayeeCommands::Alias { name, alias } => {
+            let payee = service.add_payee_alias(&name, &alias).await?;
+            println!("{} aliases: {}", payee.name, payee.aliases.join(", "));
+        }
+
+        PayeeCommands::Unalias { name, alias } => {
+            let payee = service.remove_payee_alias(&name, &alias).await?;
+            println!("Removed alias '{}' from {}", alias.trim(), payee.name);
+        }
+
+        PayeeCommands::Defaults {
+            name,
+            category,
+            wallet,
+        } => {
+            let payee = service
+                .set_payee_defaults(&name, category, wallet.as_deref())
+                .await?;
+            println!("Updated defaults of {}", payee.name);
+        }
+
+        PayeeCommands::List => {
+            let payees = service.list_payees().await?;
+            if payees.is_empty() {
+                println!("No payees found.");
+                return Ok(());
+            }
+            let wallet_names = service.get_wallet_names().await?;
+
+            println!("{:<24} {:<20} {:<16} ALIASES", "NAME", "CATEGORY", "WALLET");
+            println!("{}", "-".repeat(80));
+            for payee in payees {
+                println!(
+                    "{:<24} {:<20} {:<16} {}",
+                    truncate(&payee.name, 24),
+                    truncate(payee.default_category.as_deref().unwrap_or("-"), 20),
+                    truncate(
+                        payee
+                            .default_wallet
+                            .and_then(|id| wallet_names.get(&id))
+                            .map(String::as_str)
+                            .unwrap_or("-"),
+                        16
+                    ),
+                    payee.aliases.join(", ")
+                );
+            }
+        }
+
+        PayeeCommands::Show { name } => {
+            let payee = service.get_payee(&name).await?;
+            println!("Payee: {}", payee.name);
+            if !payee.aliases.is_empty() {
+                println!("  Aliases:  {}", payee.aliases.join(", "));
+            }
+            if let Some(category) = &payee.default_category {
+                println!("  Category: {}", category);
+            }
+            if let Some(wallet_id) = payee.default_wallet {
+                let wallet = service.get_wallet_by_id(wallet_id).await?;
+                println!("  Wallet:   {}", wallet.name);
+            }
+            println!("  Created:  {}", payee.created_at.format("%Y-%m-%d"));
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_lock_command(service: &LedgerService, cmd: LockCommands) -> Result<()> {
+    match cmd {
+        LockCommands::Set {
+            date,
+            wallet,
+            reason,
+            author,
+        } => {
+            let lock_date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+                .with_context(|| format!("Invalid date format '{}'. Use YYYY-MM-DD", date))?;
+            let author = author.or_else(|| std::env::var("USER").ok());
+            let lock = service
+                .lock_period(wallet.as_deref(), lock_date, author, reason)
+                .await?;
+            println!(
+                "Locked {} through {}",
+                wallet.as_deref().unwrap_or("the ledger"),
+                lock_date
+            );
+            if let Some(reason) = &lock.reason {
+                println!("  Reason: {}", reason);
+            }
+        }
+
+        LockCommands::Clear {
+            wallet,
+            reason,
+            author,
+        } => {
+            let author = author.or_else(|| std::env::var("USER").ok());
+            service
+                .unlock_period(wallet.as_deref(), author, reason)
+                .await?;
+            println!("Unlocked {}", wallet.as_deref().unwrap_or("the ledger"));
+        }
+
+        LockCommands::Show => {
+            let locks = service.get_period_locks().await?;
+            if locks.is_empty() {
+                println!("No periods are locked.");
+                return Ok(());
+            }
+            let wallet_names = service.get_wallet_names().await?;
+
+            println!("{:<24} {:<12} {:<12} REASON", "SCOPE", "THROUGH", "SET ON");
+            println!("{}", "-".repeat(72));
+            for lock in locks {
+                println!(
+                    "{:<24} {:<12} {:<12} {}",
+                    truncate(&lock_scope_name(&wallet_names, lock.wallet_id), 24),
+                    lock.lock_date.map(|d| d.to_string()).unwrap_or_default(),
+                    lock.locked_at.format("%Y-%m-%d"),
+                    lock.reason.as_deref().unwrap_or("")
+                );
+            }
+        }
+
+        LockCommands::History => {
+            let locks = service.list_period_locks().await?;
+            let overrides = service.list_lock_overrides().await?;
+            if locks.is_empty() && overrides.is_empty() {
+                println!("No periods have been locked.");
+                return Ok(());
+            }
+            let wallet_names = service.get_wallet_names().await?;
+
+            println!("Locks:");
+            for lock in &locks {
+                let change = match lock.lock_date {
+                    Some(date) => format!("locked through {}", date),
+                    None => "unlocked".to_string(),
+                };
+                println!(
+                    "  {}  {} {}{}{}",
+                    lock.locked_at.format("%Y-%m-%d %H:%M"),
+                    lock_scope_name(&wallet_names, lock.wallet_id),
+                    change,
+                    lock.author
+                        .as_ref()
+                        .map(|a| format!(" by {}", a))
+                        .unwrap_or_default(),
+                    lock.reason
+                        .as_ref()
+                        .map(|r| format!(": {}", r))
+                        .unwrap_or_default()
+                );
+            }
+
+            if !overrides.is_empty() {
+                println!();
+                println!("Overrides:");
+                for logged in &overrides {
+                    println!(
+                        "  {}  {} {}{}: {}",
+                        logged.created_at.format("%Y-%m-%d %H:%M"),
+                        logged.action,
+                        logged.transfer_id,
+                        logged
+                            .author
+                            .as_ref()
+                            .map(|a| format!(" by {}", a))
+                            .unwrap_or_default(),
+                        logged.reason
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_year_command(service: LedgerService, database: &str, cmd: YearCommands) -> Result<()> {
+    match cmd {
+        YearCommands::Close { year, archive } => {
+            let close = service.close_year(year).await?;
+            println!("Closed {}", close.year);
+            println!("  Income and expenses moved to retained earnings");
+            println!(
+                "  Ledger locked through {}",
+                close.closing_timestamp().date_naive()
+            );
+            if let Some(archive) = archive {
+                let (_, close) = service.archive_year(database, year, &archive).await?;
+                print_year_archive(&close);
+            }
+        }
+
+        YearCommands::Archive { year, to } => {
+            let (_, close) = service.archive_year(database, year, &to).await?;
+            print_year_archive(&close);
+        }
+
+        YearCommands::List => {
+            let closes = service.list_year_closes().await?;
+            if closes.is_empty() {
+                println!("No years have been closed.");
+                return Ok(());
+            }
+
+            println!("{:<6} {:<12} ARCHIVE", "YEAR", "CLOSED ON");
+            println!("{}", "-".repeat(60));
+            for close in closes {
+                println!(
+                    "{:<6} {:<12} {}",
+                    close.year,
+                    close.closed_at.format("%Y-%m-%d"),
+                    close.archive_path.as_deref().unwrap_or("-")
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn print_year_archive(close: &YearClose) {
+    println!(
+        "Archived {} to {}",
+        close.year,
+        close.archive_path.as_deref().unwrap_or_default()
+    );
+    println!(
+        "  The ledger now starts with opening balances on {}",
+        close.closing_timestamp().date_naive()
+    );
+    println!("  Use --with-archives to include the archived history in reports");
+}
+
+async fn run_assertion_command(
+    service: &LedgerService,
+    locale: &LocaleProfile,
+    cmd: AssertionCommands,
+) -> Result<()> {
+    match cmd {
+        AssertionCommands::Add {
+            wallet,
+            amount,
+            date,
+            adjust,
+            round,
+        } => {
+            let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+                .with_context(|| format!("Invalid date format '{}'. Use YYYY-MM-DD", date))?;
+            let currency = service.get_wallet_currency(&wallet).await?;
+            let balance = parse_money(locale, &amount, &currency, &round)?;
+            let result = service
+                .assert_balance(&wallet, date, balance, adjust)
+                .await?;
+
+            if let Some(adjustment) = &result.adjustment {
+                println!(
+                    "Adjusted: {} from {} to {} ({})",
+                    format_money(locale, adjustment.transfer.amount_cents, &currency),
+                    adjustment.from_wallet_name,
+                    adjustment.to_wallet_name,
+                    adjustment.transfer.id
+                );
+            }
+            if result.check.holds() {
+                println!(
+                    "Asserted {} at {} on {}: holds",
+                    wallet,
+                    format_money(locale, balance, &currency),
+                    date
+                );
+            } else {
+                println!(
+                    "Asserted {} at {} on {}: computed balance is {} (off by {})",
+                    wallet,
+                    format_money(locale, balance, &currency),
+                    date,
+                    format_money(locale, result.check.actual_cents, &currency),
+                    format_money(locale, result.check.difference(), &currency)
+                );
+                println!("  Use --adjust to post the difference against the adjustments wallet");
+            }
+        }
+
+        AssertionCommands::Check => {
+            let checks = service.check_balance_assertions().await?;
+            if checks.is_empty() {
+                println!("No balance assertions.");
+                return Ok(());
+            }
+            let wallet_names = service.get_wallet_names().await?;
+
+            println!(
+                "{:<12} {:<24} {:>14} {:>14} STATUS",
+                "DATE", "WALLET", "ASSERTED", "COMPUTED"
+            );
+            println!("{}", "-".repeat(74));
+            let mut failed = 0;
+            for check in &checks {
+                let name = wallet_name(&wallet_names, check.assertion.wallet_id);
+                let currency = service.get_wallet_currency(name).await?;
+                if !check.holds() {
+                    failed += 1;
+                }
+                println!(
+                    "{:<12} {:<24} {:>14} {:>14} {}",
+                    check.assertion.date.to_string(),
+                    truncate(name, 24),
+                    format_money(locale, check.assertion.balance_cents, &currency),
+                    format_money(locale, check.actual_cents, &currency),
+                    if check.holds() { "ok" } else { "FAILED" }
+                );
+            }
+            println!();
+            println!(
+                "{} of {} assertions hold",
+                checks.len() - failed,
+                checks.len()
+            );
+        }
+
+        AssertionCommands::Remove { wallet, date } => {
+            let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+                .with_context(|| format!("Invalid date format '{}'. Use YYYY-MM-DD", date))?;
+            service.remove_balance_assertion(&wallet, date).await?;
+            println!("Removed the assertion of {} on {}", wallet, date);
+        }
+    }
+
+    Ok(())
+}
+
+/// "The ledger", or the name of the wallet a lock applies to.
+fn lock_scope_name(
+    names: &std::collections::HashMap<Uuid, String>,
+    wallet_id: Option<Uuid>,
+) -> String {
+    match wallet_id {
+        Some(id) => wallet_name(names, id).to_string(),
+        None => "The ledger".to_string(),
+    }
+}
+
+async fn run_currency_command(service: &LedgerService, cmd: CurrencyCommands) -> Result<()> {
+    match cmd {
+        CurrencyCommands::Add { code, decimals } => {
+            let currency = service.register_currency(&code, decimals).await?;
+            println!(
+                "Registered currency: {} ({} decimals)",
+                currency.code, currency.minor_units
+            );
+        }
+
+        CurrencyCommands::Show { code } => {
+            let currency = service.get_currency(&code).await?;
+            println!("{}: {} decimals", currency.code, currency.minor_units);
+        }
+
+        CurrencyCommands::List => {
+            let currencies = service.list_currencies().await?;
+            if currencies.is_empty() {
+                println!("No registered currencies (built-in ISO 4217 decimals apply).");
+                return Ok(());
+            }
+
+            println!("{:<8} {:>8}", "CODE", "DECIMALS");
+            println!("{}", "-".repeat(17));
+            for currency in currencies {
+                println!("{:<8} {:>8}", currency.code, currency.minor_units);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_locale_command(
+    service: &LedgerService,
+    locale_flag: Option<&str>,
+    cmd: LocaleCommands,
+) -> Result<()> {
+    match cmd {
+        LocaleCommands::Add {
+            name,
+            decimal,
+            grouping,
+            symbol,
+            symbol_space,
+            negative,
+        } => {
+            let placement: SymbolPlacement = symbol.parse().map_err(|e| {
+                anyhow::anyhow!(
+                    "Invalid symbol placement '{}'. Valid: none, before, after. Error: {}",
+                    symbol,
+                    e
+                )
+            })?;
+            let negative_style: NegativeStyle = negative.parse().map_err(|e| {
+                anyhow::anyhow!(
+                    "Invalid negative style '{}'. Valid: leading-minus, trailing-minus, parentheses. Error: {}",
+                    negative,
+                    e
+                )
+            })?;
+
+            let profile = service
+                .save_locale(
+                    LocaleProfile::new(name, decimal)
+                        .with_grouping(grouping)
+                        .with_symbol(placement, symbol_space)
+                        .with_negative_style(negative_style),
+                )
+                .await?;
+            println!("Saved locale profile: {}", profile.name);
+            print_locale_samples(&profile);
+        }
+
+        LocaleCommands::Use { name } => {
+            let profile = service.set_default_locale(Some(&name)).await?;
+            if let Some(profile) = profile {
+                println!("Using locale profile: {}", profile.name);
+                print_locale_samples(&profile);
+            }
+        }
+
+        LocaleCommands::Show { name } => {
+            let profile = match name {
+                Some(name) => service.get_locale(&name).await?,
+                None => active_locale(service, locale_flag).await?,
+            };
+            println!("Locale profile: {}", profile.name);
+            println!("  Decimal separator:  '{}'", profile.decimal_separator);
+            println!(
+                "  Grouping separator: {}",
+                profile
+                    .grouping_separator
+                    .map(|c| format!("'{}'", c))
+                    .unwrap_or_else(|| "none".to_string())
+            );
+            println!(
+                "  Symbol placement:   {}{}",
+                profile.symbol_placement,
+                if profile.symbol_space {
+                    " (spaced)"
+                } else {
+                    ""
+                }
+            );
+            println!("  Negative style:     {}", profile.negative_style);
+            print_locale_samples(&profile);
+        }
+
+        LocaleCommands::List => {
+            let active = active_locale(service, locale_flag).await?;
+            let eur = service.get_currency("EUR").await?;
+
+            println!("{:<14} {:>18} {:>18}", "NAME", "EXAMPLE", "NEGATIVE");
+            println!("{}", "-".repeat(52));
+            for profile in service.list_locales().await? {
+                println!(
+                    "{:<14} {:>18} {:>18}{}",
+                    profile.name,
+                    profile.format_money(123456, &eur),
+                    profile.format_money(-1250, &eur),
+                    if profile.name == active.name {
+                        "  (active)"
+                    } else {
+                        ""
+                    }
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn print_locale_samples(profile: &LocaleProfile) {
+    println!(
+        "  Examples:           {}, {}, {}",
+        profile.format_money(123456, &Currency::iso("EUR")),
+        profile.format_money(-1250, &Currency::iso("USD")),
+        profile.format_money(1500000, &Currency::iso("JPY"))
+    );
+}
+
+/// The locale profile chosen with `--locale`, else the one configured for the ledger.
+async fn configured_locale(
+    service: &LedgerService,
+    name: Option<&str>,
+) -> Result<Option<LocaleProfile>> {
+    Ok(match name {
+        Some(name) => Some(service.get_locale(name).await?),
+        None => service.get_default_locale().await?,
+    })
+}
+
+/// The locale profile for terminal input and output (plain decimals when none is configured).
+async fn active_locale(service: &LedgerService, name: Option<&str>) -> Result<LocaleProfile> {
+    Ok(configured_locale(service, name).await?.unwrap_or_default())
+}
+
+async fn run_report_command(
+    service: &LedgerService,
+    locale: &LocaleProfile,
+    cmd: ReportCommands,
+) -> Result<()> {
     use crate::domain::PeriodType;
 
-    match cmd {
-        ReportCommands::Spending { from, to, format } => {
+    match cmd {
+        ReportCommands::Spending {
+            from,
+            to,
+            format,
+            currency,
+            depth,
+            tree,
+        } => {
+            let (from_date, to_date) = parse_date_range(from, to)?;
+            let report = match currency {
+                Some(currency) => {
+                    service
+                        .get_category_report_converted(from_date, to_date, &currency)
+                        .await?
+                }
+                None => service.get_category_report(from_date, to_date).await?,
+            };
+            let report_currency = report_currency(service, report.currency.as_deref()).await?;
+
+            if tree {
+                let nodes = report.tree();
+                if format == "json" {
+                    println!("{}", serde_json::to_string_pretty(&nodes)?);
+                    return Ok(());
+                }
+                println!("Category Spending Report");
+                println!(
+                    "Period: {} to {}",
+                    from_date.format("%Y-%m-%d"),
+                    to_date.format("%Y-%m-%d")
+                );
+                if let Some(currency) = &report.currency {
+                    println!("Currency: {}", currency);
+                }
+                println!();
+                println!(
+                    "{:<30} {:>12} {:>8} {:>8}",
+                    "CATEGORY", "TOTAL", "COUNT", "PERCENT"
+                );
+                println!("{}", "-".repeat(61));
+                for node in nodes.iter().flat_map(|root| root.flatten(depth)) {
+                    let label = format!(
+                        "{}{}",
+                        "  ".repeat(node.depth() - 1),
+                        category_leaf(&node.category)
+                    );
+                    println!(
+                        "{:<30} {:>12} {:>8} {:>7.1}%",
+                        truncate(&label, 30),
+                        format_money(locale, node.total, &report_currency),
+                        node.count,
+                        if report.total > 0 {
+                            node.total as f64 / report.total as f64 * 100.0
+                        } else {
+                            0.0
+                        }
+                    );
+                }
+                println!("{}", "-".repeat(61));
+                println!(
+                    "{:<30} {:>12}",
+                    "TOTAL",
+                    format_money(locale, report.total, &report_currency)
+                );
+                return Ok(());
+            }
+            let report = match depth {
+                Some(depth) => report.rolled_up(depth),
+                None => report,
+            };
+
+            match format.as_str() {
+                "json" => {
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                }
+                "csv" => {
+                    println!("category,total,count,average,percentage");
+                    for cat in &report.categories {
+                        println!(
+                            "{},{},{},{},{:.2}",
+                            cat.category, cat.total, cat.count, cat.average, cat.percentage
+                        );
+                    }
+                }
+                _ => {
+                    // Table format
+                    println!("Category Spending Report");
+                    println!(
+                        "Period: {} to {}",
+                        from_date.format("%Y-%m-%d"),
+                        to_date.format("%Y-%m-%d")
+                    );
+                    if let Some(currency) = &report.currency {
+                        println!("Currency: {}", currency);
+                    }
+                    println!();
+                    println!(
+                        "{:<20} {:>12} {:>8} {:>12} {:>8}",
+                        "CATEGORY", "TOTAL", "COUNT", "AVERAGE", "PERCENT"
+                    );
+                    println!("{}", "-".repeat(65));
+
+                    for cat in &report.categories {
+                        println!(
+                            "{:<20} {:>12} {:>8} {:>12} {:>7.1}%",
+                            truncate(&cat.category, 20),
+                            format_money(locale, cat.total, &report_currency),
+                            cat.count,
+                            format_money(locale, cat.average, &report_currency),
+                            cat.percentage
+                        );
+                    }
+
+                    println!("{}", "-".repeat(65));
+                    println!(
+                        "{:<20} {:>12}",
+                        "TOTAL",
+                        format_money(locale, report.total, &report_currency)
+                    );
+                }
+            }
+        }
+
+        ReportCommands::Tags {
+            from,
+            to,
+            format,
+            currency,
+        } => {
             let (from_date, to_date) = parse_date_range(from, to)?;
-            let report = service.get_category_report(from_date, to_date).await?;
+            let report = match currency {
+                Some(currency) => {
+                    service
+                        .get_tag_report_converted(from_date, to_date, &currency)
+                        .await?
+                }
+                None => service.get_tag_report(from_date, to_date).await?,
+            };
+            let report_currency = report_currency(service, report.currency.as_deref()).await?;
 
             match format.as_str() {
                 "json" => {
                     println!("{}", serde_json::to_string_pretty(&report)?);
                 }
                 "csv" => {
-                    println!("category,total,count,average,percentage");
-                    for cat in &report.categories {
+                    println!("tag,total,count,average,percentage");
+                    for tag in &report.tags {
                         println!(
                             "{},{},{},{},{:.2}",
-                            cat.category, cat.total, cat.count, cat.average, cat.percentage
+                            tag.tag, tag.total, tag.count, tag.average, tag.percentage
                         );
                     }
                 }
                 _ => {
                     // Table format
-                    println!("Category Spending Report");
+                    println!("Tag Spending Report");
                     println!(
                         "Period: {} to {}",
                         from_date.format("%Y-%m-%d"),
                         to_date.format("%Y-%m-%d")
                     );
+                    if let Some(currency) = &report.currency {
+                        println!("Currency: {}", currency);
+                    }
                     println!();
+                    if report.tags.is_empty() {
+                        println!("No tagged transfers in this period.");
+                        return Ok(());
+                    }
                     println!(
                         "{:<20} {:>12} {:>8} {:>12} {:>8}",
-                        "CATEGORY", "TOTAL", "COUNT", "AVERAGE", "PERCENT"
+                        "TAG", "TOTAL", "COUNT", "AVERAGE", "PERCENT"
                     );
                     println!("{}", "-".repeat(65));
 
-                    for cat in &report.categories {
+                    for tag in &report.tags {
                         println!(
                             "{:<20} {:>12} {:>8} {:>12} {:>7.1}%",
-                            truncate(&cat.category, 20),
-                            format_cents(cat.total),
-                            cat.count,
-                            format_cents(cat.average),
-                            cat.percentage
+                            truncate(&tag.tag, 20),
+                            format_money(locale, tag.total, &report_currency),
+                            tag.count,
+                            format_money(locale, tag.average, &report_currency),
+                            tag.percentage
                         );
                     }
 
                     println!("{}", "-".repeat(65));
-                    println!("{:<20} {:>12}", "TOTAL", format_cents(report.total));
+                    println!(
+                        "{:<20} {:>12}",
+                        "TAGGED TOTAL",
+                        format_money(locale, report.total, &report_currency)
+                    );
+                }
+            }
+        }
+
+        ReportCommands::Payees {
+            from,
+            to,
+            limit,
+            format,
+        } => {
+            let (from_date, to_date) = parse_date_range(from, to)?;
+            let report = service
+                .get_payee_report(from_date, to_date, Some(limit))
+                .await?;
+            let report_currency = report_currency(service, None).await?;
+
+            match format.as_str() {
+                "json" => {
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                }
+                "csv" => {
+                    println!(
+                        "payee,total,count,average,percentage,last_date,average_interval_days"
+                    );
+                    for payee in &report.payees {
+                        println!(
+                            "{},{},{},{},{:.2},{},{}",
+                            payee.payee,
+                            payee.total,
+                            payee.count,
+                            payee.average,
+                            payee.percentage,
+                            payee.last_date.format("%Y-%m-%d"),
+                            payee
+                                .average_interval_days
+                                .map(|days| format!("{:.1}", days))
+                                .unwrap_or_default()
+                        );
+                    }
+                }
+                _ => {
+                    // Table format
+                    println!("Top Payees Report");
+                    println!(
+                        "Period: {} to {}",
+                        from_date.format("%Y-%m-%d"),
+                        to_date.format("%Y-%m-%d")
+                    );
+                    println!();
+                    if report.payees.is_empty() {
+                        println!("No transfers with a payee in this period.");
+                        return Ok(());
+                    }
+                    println!(
+                        "{:<20} {:>12} {:>6} {:>12} {:>8} {:>10} {:>7}",
+                        "PAYEE", "TOTAL", "COUNT", "AVERAGE", "PERCENT", "LAST", "EVERY"
+                    );
+                    println!("{}", "-".repeat(82));
+
+                    for payee in &report.payees {
+                        println!(
+                            "{:<20} {:>12} {:>6} {:>12} {:>7.1}% {:>10} {:>7}",
+                            truncate(&payee.payee, 20),
+                            format_money(locale, payee.total, &report_currency),
+                            payee.count,
+                            format_money(locale, payee.average, &report_currency),
+                            payee.percentage,
+                            payee.last_date.format("%Y-%m-%d"),
+                            payee
+                                .average_interval_days
+                                .map(|days| format!("{:.0}d", days))
+                                .unwrap_or_else(|| "-".to_string())
+                        );
+                    }
+
+                    println!("{}", "-".repeat(82));
+                    println!(
+                        "{:<20} {:>12}",
+                        "TOTAL",
+                        format_money(locale, report.total, &report_currency)
+                    );
                 }
             }
         }
@@ -925,10 +3721,16 @@ async fn run_report_command(service: &LedgerService, cmd: ReportCommands) -> Res
                         to_date.format("%Y-%m-%d")
                     );
                     println!();
-                    println!("Total Income:   {:>15}", format_cents(report.total_income));
-                    println!("Total Expense:  {:>15}", format_cents(report.total_expense));
+                    println!(
+                        "Total Income:   {:>15}",
+                        locale.format_cents(report.total_income)
+                    );
+                    println!(
+                        "Total Expense:  {:>15}",
+                        locale.format_cents(report.total_expense)
+                    );
                     println!("{}", "-".repeat(32));
-                    println!("Net:            {:>15}", format_cents(report.net));
+                    println!("Net:            {:>15}", locale.format_cents(report.net));
 
                     if !report.expense_categories.is_empty() {
                         println!();
@@ -938,7 +3740,7 @@ async fn run_report_command(service: &LedgerService, cmd: ReportCommands) -> Res
                                 "  {}. {:<18} {:>12} ({:.1}%)",
                                 i + 1,
                                 truncate(&cat.category, 18),
-                                format_cents(cat.total),
+                                locale.format_cents(cat.total),
                                 cat.percentage
                             );
                         }
@@ -952,6 +3754,8 @@ async fn run_report_command(service: &LedgerService, cmd: ReportCommands) -> Res
             to,
             period,
             format,
+            currency,
+            depth,
         } => {
             let (from_date, to_date) = parse_date_range(from, to)?;
             let period_type: PeriodType = period.parse().map_err(|e| {
@@ -962,9 +3766,23 @@ async fn run_report_command(service: &LedgerService, cmd: ReportCommands) -> Res
                 )
             })?;
 
-            let report = service
-                .get_cashflow_report(from_date, to_date, period_type)
-                .await?;
+            let report = match currency {
+                Some(currency) => {
+                    service
+                        .get_cashflow_report_converted(from_date, to_date, period_type, &currency)
+                        .await?
+                }
+                None => {
+                    service
+                        .get_cashflow_report(from_date, to_date, period_type)
+                        .await?
+                }
+            };
+            let report = match depth {
+                Some(depth) => report.rolled_up(depth),
+                None => report,
+            };
+            let report_currency = report_currency(service, report.currency.as_deref()).await?;
 
             match format.as_str() {
                 "json" => {
@@ -991,6 +3809,9 @@ async fn run_report_command(service: &LedgerService, cmd: ReportCommands) -> Res
                         from_date.format("%Y-%m-%d"),
                         to_date.format("%Y-%m-%d")
                     );
+                    if let Some(currency) = &report.currency {
+                        println!("Currency: {}", currency);
+                    }
                     println!();
                     println!(
                         "{:<12} {:>12} {:>12} {:>12}",
@@ -1003,35 +3824,450 @@ async fn run_report_command(service: &LedgerService, cmd: ReportCommands) -> Res
                         println!(
                             "{:<12} {:>12} {:>12} {:>12}",
                             truncate(&period_label, 12),
-                            format_cents(period.inflow),
-                            format_cents(period.outflow),
-                            format_cents(period.net)
+                            format_money(locale, period.inflow, &report_currency),
+                            format_money(locale, period.outflow, &report_currency),
+                            format_money(locale, period.net, &report_currency)
+                        );
+                        if depth.is_some() {
+                            for cat in &period.categories {
+                                println!(
+                                    "  {:<24} {:>12}",
+                                    truncate(&cat.category, 24),
+                                    format_money(locale, cat.total, &report_currency)
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        ReportCommands::NetWorthHistory {
+            from,
+            to,
+            interval,
+            currency,
+            format,
+        } => {
+            let to = match to {
+                Some(to) => parse_day(&to)?,
+                None => Utc::now().date_naive(),
+            };
+            let from = match from {
+                Some(from) => parse_day(&from)?,
+                None => to
+                    .checked_sub_months(chrono::Months::new(12))
+                    .and_then(|day| day.succ_opt())
+                    .unwrap_or(to),
+            };
+            let interval: SampleInterval = interval
+                .parse()
+                .map_err(|e| anyhow::anyhow!("{}. Valid intervals: day, week, month", e))?;
+            let currency = match currency {
+                Some(currency) => currency,
+                None => {
+                    let mut currencies: Vec<String> = service
+                        .list_wallets(true)
+                        .await?
+                        .into_iter()
+                        .filter(|w| {
+                            matches!(w.wallet_type, WalletType::Asset | WalletType::Liability)
+                        })
+                        .map(|w| w.currency)
+                        .collect();
+                    currencies.sort();
+                    currencies.dedup();
+                    match currencies.as_slice() {
+                        [] => "EUR".to_string(),
+                        [currency] => currency.clone(),
+                        _ => anyhow::bail!(
+                            "Wallets hold {}; give --currency to convert into",
+                            currencies.join(", ")
+                        ),
+                    }
+                }
+            };
+            let history = service
+                .get_net_worth_history(from, to, interval, &currency)
+                .await?;
+            let currency = report_currency(service, Some(&history.currency)).await?;
+
+            match format.as_str() {
+                "json" => {
+                    println!("{}", serde_json::to_string_pretty(&history)?);
+                }
+                "csv" => {
+                    println!("date,total_assets,total_liabilities,net_worth");
+                    for point in &history.points {
+                        println!(
+                            "{},{},{},{}",
+                            point.date,
+                            point.total_assets,
+                            point.total_liabilities,
+                            point.net_worth
+                        );
+                    }
+                }
+                "table" => {
+                    println!("Net Worth History");
+                    println!(
+                        "Period: {} to {}, every {}, in {}",
+                        history.from_date, history.to_date, history.interval, history.currency
+                    );
+                    println!();
+                    println!(
+                        "{:<12} {:>15} {:>15} {:>15}",
+                        "DATE", "ASSETS", "LIABILITIES", "NET WORTH"
+                    );
+                    println!("{}", "-".repeat(60));
+                    for point in &history.points {
+                        println!(
+                            "{:<12} {:>15} {:>15} {:>15}",
+                            point.date.to_string(),
+                            format_money(locale, point.total_assets, &currency),
+                            format_money(locale, point.total_liabilities, &currency),
+                            format_money(locale, point.net_worth, &currency)
+                        );
+                    }
+                }
+                _ => {
+                    // Chart format
+                    println!("Net Worth History");
+                    println!(
+                        "Period: {} to {}, every {}, in {}",
+                        history.from_date, history.to_date, history.interval, history.currency
+                    );
+                    println!();
+                    let values: Vec<Cents> = history.points.iter().map(|p| p.net_worth).collect();
+                    let (Some(first), Some(last)) = (history.points.first(), history.points.last())
+                    else {
+                        return Ok(());
+                    };
+                    // One row of the chart per 60 samples, labelled with its first date
+                    for (row, chunk) in sparkline(&values).chunks(60).enumerate() {
+                        println!(
+                            "{:<12} {}",
+                            history.points[row * 60].date.to_string(),
+                            chunk.iter().collect::<String>()
+                        );
+                    }
+                    println!();
+                    let min = values.iter().min().copied().unwrap_or(0);
+                    let max = values.iter().max().copied().unwrap_or(0);
+                    println!(
+                        "{:<8} {:>15}   {:<8} {:>15}",
+                        "Low",
+                        format_money(locale, min, &currency),
+                        "High",
+                        format_money(locale, max, &currency)
+                    );
+                    println!(
+                        "{:<8} {:>15}   {:<8} {:>15}",
+                        "Start",
+                        format_money(locale, first.net_worth, &currency),
+                        "End",
+                        format_money(locale, last.net_worth, &currency)
+                    );
+                    println!(
+                        "{:<8} {:>15}",
+                        "Change",
+                        format_money(locale, last.net_worth - first.net_worth, &currency)
+                    );
+                }
+            }
+        }
+
+        ReportCommands::BudgetVariance {
+            from,
+            to,
+            budget,
+            format,
+        } => {
+            let to = match to {
+                Some(to) => parse_day(&to)?,
+                None => Utc::now().date_naive(),
+            };
+            let from = match from {
+                Some(from) => parse_day(&from)?,
+                None => to
+                    .checked_sub_months(chrono::Months::new(12))
+                    .and_then(|day| day.succ_opt())
+                    .unwrap_or(to),
+            };
+            let report = service
+                .get_budget_variance_report(from, to, budget.as_deref())
+                .await?;
+
+            match format.as_str() {
+                "json" => {
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                }
+                "csv" => {
+                    println!(
+                        "budget,period_start,period_end,limit,actual,variance,variance_percentage,exceeded"
+                    );
+                    for variance in &report.budgets {
+                        for period in &variance.periods {
+                            println!(
+                                "{},{},{},{},{},{},{:.1},{}",
+                                variance.budget,
+                                period.period_start.format("%Y-%m-%d"),
+                                period.period_end.format("%Y-%m-%d"),
+                                period.limit,
+                                period.actual,
+                                period.variance,
+                                period.variance_percentage,
+                                period.is_exceeded()
+                            );
+                        }
+                    }
+                }
+                _ => {
+                    // Table format
+                    println!("Budget Variance Report");
+                    println!("Period: {} to {}", report.from_date, report.to_date);
+                    if report.budgets.is_empty() {
+                        println!();
+                        println!("No budgets found.");
+                        return Ok(());
+                    }
+                    let names = service.get_wallet_names().await?;
+                    for variance in &report.budgets {
+                        let currency =
+                            report_currency(service, variance.currency.as_deref()).await?;
+                        println!();
+                        println!(
+                            "{} ({}, {})",
+                            variance.budget,
+                            budget_scope(&variance.category, &variance.filter, &names),
+                            variance.period_type
+                        );
+                        if variance.periods.is_empty() {
+                            println!("  No completed periods in range.");
+                            continue;
+                        }
+                        println!(
+                            "{:<12} {:>12} {:>12} {:>12} {:>10}",
+                            "PERIOD", "LIMIT", "ACTUAL", "VARIANCE", "VARIANCE %"
+                        );
+                        println!("{}", "-".repeat(62));
+                        for period in &variance.periods {
+                            println!(
+                                "{:<12} {:>12} {:>12} {:>12} {:>9.1}%{}",
+                                period.period_start.format("%Y-%m-%d").to_string(),
+                                format_money(locale, period.limit, &currency),
+                                format_money(locale, period.actual, &currency),
+                                format_money(locale, period.variance, &currency),
+                                period.variance_percentage,
+                                if period.is_exceeded() { "  over" } else { "" }
+                            );
+                        }
+                        println!("{}", "-".repeat(62));
+                        println!(
+                            "{:<12} {:>12} {:>12} {:>12}",
+                            "Total",
+                            format_money(locale, variance.total_limit, &currency),
+                            format_money(locale, variance.total_actual, &currency),
+                            format_money(locale, variance.total_variance, &currency)
+                        );
+                    }
+
+                    println!();
+                    println!("{:<20} {:>8} {:>9}", "BUDGET", "PERIODS", "EXCEEDED");
+                    println!("{}", "-".repeat(39));
+                    for variance in &report.budgets {
+                        println!(
+                            "{:<20} {:>8} {:>9}",
+                            truncate(&variance.budget, 20),
+                            variance.periods.len(),
+                            variance.periods_exceeded
+                        );
+                    }
+                }
+            }
+        }
+
+        ReportCommands::Holdings { format } => {
+            let report = service.get_holdings_report().await?;
+
+            match format.as_str() {
+                "json" => {
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                }
+                "csv" => {
+                    println!(
+                        "wallet,commodity,quantity,price,currency,market_value,cost_basis,unrealized_gain"
+                    );
+                    for holding in &report.holdings {
+                        println!(
+                            "{},{},{},{},{},{},{},{}",
+                            holding.wallet_name,
+                            holding.commodity,
+                            holding.quantity,
+                            holding.price.map(|p| p.to_string()).unwrap_or_default(),
+                            holding.currency,
+                            holding.market_value,
+                            holding.cost_basis,
+                            holding.unrealized_gain
+                        );
+                    }
+                }
+                _ => {
+                    // Table format
+                    println!("Holdings Report");
+                    println!("As of: {}", report.as_of.format("%Y-%m-%d %H:%M:%S"));
+                    println!();
+
+                    if report.holdings.is_empty() {
+                        println!("No holdings.");
+                        return Ok(());
+                    }
+
+                    println!(
+                        "{:<20} {:>14} {:>12} {:>14} {:>14} {:>14}",
+                        "WALLET", "UNITS", "PRICE", "VALUE", "COST", "GAIN"
+                    );
+                    println!("{}", "-".repeat(93));
+                    for holding in &report.holdings {
+                        let units =
+                            Currency::new(holding.commodity.clone(), holding.quantity_decimals);
+                        let currency = service.get_currency(&holding.currency).await?;
+                        println!(
+                            "{:<20} {:>14} {:>12} {:>14} {:>14} {:>14}",
+                            truncate(&holding.wallet_name, 20),
+                            format_money(locale, holding.quantity, &units),
+                            holding
+                                .price
+                                .map(|p| p.to_string())
+                                .unwrap_or_else(|| "-".to_string()),
+                            format_money(locale, holding.market_value, &currency),
+                            format_money(locale, holding.cost_basis, &currency),
+                            format_money(locale, holding.unrealized_gain, &currency)
+                        );
+                    }
+                    println!("{}", "-".repeat(93));
+                    let plain = report_currency(service, None).await?;
+                    println!(
+                        "{:<20} {:>14} {:>12} {:>14} {:>14} {:>14}",
+                        "TOTAL",
+                        "",
+                        "",
+                        format_money(locale, report.total_market_value, &plain),
+                        format_money(locale, report.total_cost_basis, &plain),
+                        format_money(locale, report.total_unrealized_gain, &plain)
+                    );
+                }
+            }
+        }
+
+        ReportCommands::Gains { year, format } => {
+            let year = year.unwrap_or_else(|| Utc::now().year());
+            let report = service.get_realized_gains_report(year).await?;
+
+            match format.as_str() {
+                "json" => {
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                }
+                "csv" => {
+                    println!(
+                        "wallet,commodity,lot_id,acquired_at,disposed_at,quantity,currency,cost_basis,proceeds,gain,method"
+                    );
+                    for gain in &report.gains {
+                        println!(
+                            "{},{},{},{},{},{},{},{},{},{},{}",
+                            gain.wallet_name,
+                            gain.commodity,
+                            gain.lot_id,
+                            gain.acquired_at.format("%Y-%m-%d"),
+                            gain.disposed_at.format("%Y-%m-%d"),
+                            gain.quantity,
+                            gain.currency,
+                            gain.cost_basis,
+                            gain.proceeds,
+                            gain.gain,
+                            gain.method
+                        );
+                    }
+                }
+                _ => {
+                    // Table format
+                    println!("Realized Gains Report");
+                    println!("Year: {}", report.year);
+                    println!();
+
+                    if report.gains.is_empty() {
+                        println!("No sales.");
+                        return Ok(());
+                    }
+
+                    println!(
+                        "{:<20} {:<12} {:<12} {:>14} {:>14} {:>14} {:>14}",
+                        "WALLET", "ACQUIRED", "SOLD", "UNITS", "COST", "PROCEEDS", "GAIN"
+                    );
+                    println!("{}", "-".repeat(106));
+                    for gain in &report.gains {
+                        let units = Currency::new(gain.commodity.clone(), gain.quantity_decimals);
+                        let currency = service.get_currency(&gain.currency).await?;
+                        println!(
+                            "{:<20} {:<12} {:<12} {:>14} {:>14} {:>14} {:>14}",
+                            truncate(&gain.wallet_name, 20),
+                            gain.acquired_at.format("%Y-%m-%d"),
+                            gain.disposed_at.format("%Y-%m-%d"),
+                            format_money(locale, gain.quantity, &units),
+                            format_money(locale, gain.cost_basis, &currency),
+                            format_money(locale, gain.proceeds, &currency),
+                            format_money(locale, gain.gain, &currency)
                         );
                     }
+                    println!("{}", "-".repeat(106));
+                    let plain = report_currency(service, None).await?;
+                    println!(
+                        "{:<20} {:<12} {:<12} {:>14} {:>14} {:>14} {:>14}",
+                        "TOTAL",
+                        "",
+                        "",
+                        "",
+                        format_money(locale, report.total_cost_basis, &plain),
+                        format_money(locale, report.total_proceeds, &plain),
+                        format_money(locale, report.total_gain, &plain)
+                    );
                 }
             }
         }
 
-        ReportCommands::NetWorth { format } => {
-            let report = service.get_net_worth_report().await?;
+        ReportCommands::NetWorth { format, currency } => {
+            let report = match currency {
+                Some(currency) => service.get_net_worth_report_converted(&currency).await?,
+                None => service.get_net_worth_report().await?,
+            };
+            let report_currency = report_currency(service, report.currency.as_deref()).await?;
 
             match format.as_str() {
                 "json" => {
                     println!("{}", serde_json::to_string_pretty(&report)?);
                 }
                 "csv" => {
-                    println!("type,wallet,balance");
+                    println!("type,wallet,balance,subtotal");
                     for asset in &report.assets {
-                        println!("asset,{},{}", asset.wallet_name, asset.balance);
+                        println!(
+                            "asset,{},{},{}",
+                            asset.wallet_name, asset.balance, asset.subtotal
+                        );
                     }
                     for liability in &report.liabilities {
-                        println!("liability,{},{}", liability.wallet_name, liability.balance);
+                        println!(
+                            "liability,{},{},{}",
+                            liability.wallet_name, liability.balance, liability.subtotal
+                        );
                     }
                 }
                 _ => {
                     // Table format
                     println!("Net Worth Report");
                     println!("As of: {}", report.as_of.format("%Y-%m-%d %H:%M:%S"));
+                    if let Some(currency) = &report.currency {
+                        println!("Currency: {}", currency);
+                    }
                     println!();
 
                     if !report.assets.is_empty() {
@@ -1039,15 +4275,19 @@ async fn run_report_command(service: &LedgerService, cmd: ReportCommands) -> Res
                         for asset in &report.assets {
                             println!(
                                 "  {:<25} {:>15}",
-                                truncate(&asset.wallet_name, 25),
-                                format_cents(asset.balance)
+                                truncate(&tree_label(&asset.wallet_name, asset.depth), 25),
+                                format_money(
+                                    locale,
+                                    asset.subtotal,
+                                    &service.get_currency(&asset.currency).await?
+                                )
                             );
                         }
                         println!("  {:<25} {:>15}", "", "-".repeat(15));
                         println!(
                             "  {:<25} {:>15}",
                             "Total Assets",
-                            format_cents(report.total_assets)
+                            format_money(locale, report.total_assets, &report_currency)
                         );
                         println!();
                     }
@@ -1057,26 +4297,38 @@ async fn run_report_command(service: &LedgerService, cmd: ReportCommands) -> Res
                         for liability in &report.liabilities {
                             println!(
                                 "  {:<25} {:>15}",
-                                truncate(&liability.wallet_name, 25),
-                                format_cents(liability.balance)
+                                truncate(&tree_label(&liability.wallet_name, liability.depth), 25),
+                                format_money(
+                                    locale,
+                                    liability.subtotal,
+                                    &service.get_currency(&liability.currency).await?
+                                )
                             );
                         }
                         println!("  {:<25} {:>15}", "", "-".repeat(15));
                         println!(
                             "  {:<25} {:>15}",
                             "Total Liabilities",
-                            format_cents(report.total_liabilities)
+                            format_money(locale, report.total_liabilities, &report_currency)
                         );
                         println!();
                     }
 
                     println!("{}", "=".repeat(44));
-                    println!("{:<25} {:>15}", "Net Worth", format_cents(report.net_worth));
+                    println!(
+                        "{:<25} {:>15}",
+                        "Net Worth",
+                        format_money(locale, report.net_worth, &report_currency)
+                    );
                 }
             }
         }
 
-        ReportCommands::Compare { period, format } => {
+        ReportCommands::Compare {
+            period,
+            format,
+            depth,
+        } => {
             let period_type: PeriodType = period.parse().map_err(|e| {
                 anyhow::anyhow!(
                     "Invalid period '{}'. Valid: weekly, monthly, yearly. Error: {}",
@@ -1086,6 +4338,10 @@ async fn run_report_command(service: &LedgerService, cmd: ReportCommands) -> Res
             })?;
 
             let report = service.get_period_comparison(period_type).await?;
+            let report = match depth {
+                Some(depth) => report.rolled_up(depth),
+                None => report,
+            };
 
             match format.as_str() {
                 "json" => {
@@ -1118,13 +4374,16 @@ async fn run_report_command(service: &LedgerService, cmd: ReportCommands) -> Res
                     );
                     println!(
                         "  Income:  {:>15}",
-                        format_cents(report.current_period.total_income)
+                        locale.format_cents(report.current_period.total_income)
                     );
                     println!(
                         "  Expense: {:>15}",
-                        format_cents(report.current_period.total_expense)
+                        locale.format_cents(report.current_period.total_expense)
+                    );
+                    println!(
+                        "  Net:     {:>15}",
+                        locale.format_cents(report.current_period.net)
                     );
-                    println!("  Net:     {:>15}", format_cents(report.current_period.net));
                     println!();
 
                     println!(
@@ -1134,24 +4393,64 @@ async fn run_report_command(service: &LedgerService, cmd: ReportCommands) -> Res
                     );
                     println!(
                         "  Income:  {:>15}",
-                        format_cents(report.previous_period.total_income)
+                        locale.format_cents(report.previous_period.total_income)
                     );
                     println!(
                         "  Expense: {:>15}",
-                        format_cents(report.previous_period.total_expense)
+                        locale.format_cents(report.previous_period.total_expense)
                     );
                     println!(
                         "  Net:     {:>15}",
-                        format_cents(report.previous_period.net)
+                        locale.format_cents(report.previous_period.net)
                     );
                     println!();
 
                     println!("{}", "=".repeat(44));
                     println!(
                         "Change:  {:>15} ({:+.1}%)",
-                        format_cents(report.change),
+                        locale.format_cents(report.change),
                         report.change_percentage
                     );
+
+                    if depth.is_some() {
+                        let mut categories: Vec<&str> = Vec::new();
+                        for cat in report
+                            .current_period
+                            .categories
+                            .iter()
+                            .chain(&report.previous_period.categories)
+                        {
+                            if !categories.contains(&cat.category.as_str()) {
+                                categories.push(&cat.category);
+                            }
+                        }
+                        let total_of = |period: &crate::application::PeriodSummary, name: &str| {
+                            period
+                                .categories
+                                .iter()
+                                .find(|c| c.category == name)
+                                .map(|c| c.total)
+                                .unwrap_or(0)
+                        };
+
+                        println!();
+                        println!(
+                            "{:<24} {:>12} {:>12} {:>12}",
+                            "CATEGORY", "CURRENT", "PREVIOUS", "CHANGE"
+                        );
+                        println!("{}", "-".repeat(63));
+                        for name in categories {
+                            let current = total_of(&report.current_period, name);
+                            let previous = total_of(&report.previous_period, name);
+                            println!(
+                                "{:<24} {:>12} {:>12} {:>12}",
+                                truncate(name, 24),
+                                locale.format_cents(current),
+                                locale.format_cents(previous),
+                                locale.format_cents(current - previous)
+                            );
+                        }
+                    }
                 }
             }
         }
@@ -1189,29 +4488,62 @@ fn parse_date_range(
     Ok((from_date, to_date))
 }
 
-async fn run_balance_command(service: &LedgerService, wallet: Option<String>) -> Result<()> {
+async fn run_balance_command(
+    service: &LedgerService,
+    locale: &LocaleProfile,
+    wallet: Option<String>,
+    as_of: Option<NaiveDate>,
+) -> Result<()> {
+    if let Some(date) = as_of {
+        println!("As of {}", date);
+    }
     match wallet {
         Some(name) => {
-            let entry = service.get_balance(&name).await?;
+            let entry = match as_of {
+                Some(date) => service.get_balance_as_of(&name, date).await?,
+                None => service.get_balance(&name).await?,
+            };
+            let currency = service.get_currency(&entry.wallet.currency).await?;
             println!(
-                "{}: {} {}",
+                "{}: {}",
                 entry.wallet.name,
-                format_cents(entry.balance),
-                entry.wallet.currency
+                locale.format_money(entry.balance, &currency)
             );
+            if entry.subtotal != entry.balance {
+                println!(
+                    "  with sub-wallets: {}",
+                    locale.format_money(entry.subtotal, &currency)
+                );
+            }
         }
         None => {
-            let entries = service.get_all_balances().await?;
+            let entries = match as_of {
+                Some(date) => service.get_all_balances_as_of(date).await?,
+                None => service.get_all_balances().await?,
+            };
             if entries.is_empty() {
                 println!("No wallets found.");
             } else {
-                println!("{:<20} {:>12} {:<8}", "WALLET", "BALANCE", "CURRENCY");
-                println!("{}", "-".repeat(44));
-                for entry in entries {
+                let currencies = service.get_wallet_currencies().await?;
+                println!(
+                    "{:<24} {:>12} {:>12} {:<8}",
+                    "WALLET", "BALANCE", "SUBTOTAL", "CURRENCY"
+                );
+                println!("{}", "-".repeat(59));
+                for (i, entry) in entries.iter().enumerate() {
+                    // Subtotals are shown for wallets with sub-wallets
+                    let has_children = entries
+                        .get(i + 1)
+                        .is_some_and(|next| next.depth > entry.depth);
                     println!(
-                        "{:<20} {:>12} {:<8}",
-                        entry.wallet.name,
-                        format_cents(entry.balance),
+                        "{:<24} {:>12} {:>12} {:<8}",
+                        tree_label(&entry.wallet.name, entry.depth),
+                        wallet_money(locale, &currencies, entry.wallet.id, entry.balance),
+                        if has_children {
+                            wallet_money(locale, &currencies, entry.wallet.id, entry.subtotal)
+                        } else {
+                            String::new()
+                        },
                         entry.wallet.currency
                     );
                 }
@@ -1221,10 +4553,14 @@ async fn run_balance_command(service: &LedgerService, wallet: Option<String>) ->
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn run_transfers_command(
     service: &LedgerService,
+    locale: &LocaleProfile,
     wallet: Option<String>,
     category: Option<String>,
+    tags: Vec<String>,
+    tag_match: TagMatch,
     from_date: Option<String>,
     to_date: Option<String>,
     limit: Option<usize>,
@@ -1244,6 +4580,8 @@ async fn run_transfers_command(
     let filter = TransferFilter {
         wallet,
         category,
+        tags,
+        tag_match,
         from_date: from_date_parsed,
         to_date: to_date_parsed,
         limit,
@@ -1255,12 +4593,13 @@ async fn run_transfers_command(
         println!("No transfers found.");
     } else {
         let wallet_names = service.get_wallet_names().await?;
+        let currencies = service.get_wallet_currencies().await?;
 
         println!(
-            "{:<12} {:>10} {:<15} {:<15} DESCRIPTION",
-            "DATE", "AMOUNT", "FROM", "TO"
+            "{:<12} {:<2} {:>10} {:<15} {:<15} DESCRIPTION",
+            "DATE", "C", "AMOUNT", "FROM", "TO"
         );
-        println!("{}", "-".repeat(70));
+        println!("{}", "-".repeat(73));
 
         // Show all transfers (limit already applied in query)
         for transfer in transfers.iter().rev() {
@@ -1273,22 +4612,32 @@ async fn run_transfers_command(
                 .map(|s| s.as_str())
                 .unwrap_or("?");
             let date = transfer.timestamp.format("%Y-%m-%d");
-            let desc = transfer.description.as_deref().unwrap_or("");
+            let desc = std::iter::once(transfer.description.clone().unwrap_or_default())
+                .chain(transfer.tags.iter().map(|tag| format!("#{}", tag)))
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ");
 
             println!(
-                "{:<12} {:>10} {:<15} {:<15} {}",
+                "{:<12} {:<2} {:>10} {:<15} {:<15} {}",
                 date,
-                format_cents(transfer.amount_cents),
+                transfer.cleared.marker(),
+                wallet_money(
+                    locale,
+                    &currencies,
+                    transfer.from_wallet,
+                    transfer.amount_cents
+                ),
                 truncate(from_name, 15),
                 truncate(to_name, 15),
-                truncate(desc, 30)
+                truncate(&desc, 30)
             );
         }
     }
     Ok(())
 }
 
-async fn run_check_command(service: &LedgerService) -> Result<()> {
+async fn run_check_command(service: &LedgerService, locale: &LocaleProfile) -> Result<()> {
     println!("Checking ledger integrity...\n");
 
     let report = service.check_integrity().await?;
@@ -1306,13 +4655,17 @@ async fn run_check_command(service: &LedgerService) -> Result<()> {
         WalletType::Equity,
     ] {
         let balance = report.balance_by_type.get(&wt).copied().unwrap_or(0);
-        println!("  {:<12} {:>12}", format!("{}:", wt), format_cents(balance));
+        println!(
+            "  {:<12} {:>12}",
+            format!("{}:", wt),
+            locale.format_cents(balance)
+        );
     }
     println!("  {}", "-".repeat(26));
     println!(
         "  {:<12} {:>12}  {}",
         "Total:",
-        format_cents(report.total_balance),
+        locale.format_cents(report.total_balance),
         if report.is_balanced {
             "OK"
         } else {
@@ -1321,6 +4674,26 @@ async fn run_check_command(service: &LedgerService) -> Result<()> {
     );
     println!();
 
+    // Subtotals per wallet tree, when wallets are nested
+    if report.wallet_tree.iter().any(|entry| entry.has_children) {
+        let wallets = service.list_wallets(true).await?;
+        let names: std::collections::HashMap<Uuid, &str> =
+            wallets.iter().map(|w| (w.id, w.name.as_str())).collect();
+        println!("Balance by wallet:");
+        for entry in report
+            .wallet_tree
+            .iter()
+            .filter(|entry| entry.has_children || entry.depth > 0)
+        {
+            println!(
+                "  {:<24} {:>12}",
+                tree_label(names.get(&entry.wallet_id).unwrap_or(&"?"), entry.depth),
+                locale.format_cents(entry.subtotal)
+            );
+        }
+        println!();
+    }
+
     if report.is_healthy() {
         println!("Ledger is consistent.");
     } else {
@@ -1334,9 +4707,14 @@ async fn run_check_command(service: &LedgerService) -> Result<()> {
     Ok(())
 }
 
-async fn run_show_transfer_command(service: &LedgerService, transfer_id: uuid::Uuid) -> Result<()> {
+async fn run_show_transfer_command(
+    service: &LedgerService,
+    locale: &LocaleProfile,
+    transfer_id: uuid::Uuid,
+) -> Result<()> {
     let info = service.get_transfer_info(transfer_id).await?;
     let transfer = &info.transfer;
+    let currency = service.get_currency(&info.from_wallet.currency).await?;
 
     println!("Transfer: {}", transfer.id);
     println!("  Sequence:    {}", transfer.sequence);
@@ -1345,10 +4723,25 @@ async fn run_show_transfer_command(service: &LedgerService, transfer_id: uuid::U
         transfer.timestamp.format("%Y-%m-%d %H:%M:%S")
     );
     println!(
-        "  Amount:      {} {}",
-        format_cents(transfer.amount_cents),
-        info.from_wallet.currency
+        "  Amount:      {}",
+        locale.format_money(transfer.amount_cents, &currency)
     );
+    if let Some(quantity) = transfer.quantity {
+        let commodity = info
+            .from_wallet
+            .commodity
+            .as_ref()
+            .or(info.to_wallet.commodity.as_ref());
+        if let Some(commodity) = commodity {
+            let units = service.get_currency(commodity).await?;
+            println!(
+                "  Quantity:    {} {} @ {}",
+                format_money(locale, quantity, &units),
+                commodity,
+                transfer.unit_price.unwrap_or(0.0)
+            );
+        }
+    }
     println!("  From:        {}", info.from_wallet.name);
     println!("  To:          {}", info.to_wallet.name);
     if let Some(cat) = &transfer.category {
@@ -1357,17 +4750,58 @@ async fn run_show_transfer_command(service: &LedgerService, transfer_id: uuid::U
     if let Some(desc) = &transfer.description {
         println!("  Description: {}", desc);
     }
+    if let Some(payee) = &info.payee {
+        println!("  Payee:       {}", payee.name);
+    }
     if !transfer.tags.is_empty() {
         println!("  Tags:        {}", transfer.tags.join(", "));
     }
     if let Some(ext_ref) = &transfer.external_ref {
         println!("  External ref: {}", ext_ref);
     }
+    println!("  Cleared:     {}", transfer.cleared);
     println!(
         "  Recorded at: {}",
         transfer.recorded_at.format("%Y-%m-%d %H:%M:%S")
     );
 
+    // Show the other legs of a split transaction
+    if let Some(split_id) = transfer.split_id {
+        let wallet_names = service.get_wallet_names().await?;
+        let currencies = service.get_wallet_currencies().await?;
+        let legs_currency = |leg: &crate::domain::Transfer| currencies.get(&leg.from_wallet);
+        let single_currency = info
+            .split_legs
+            .iter()
+            .all(|leg| legs_currency(leg) == Some(&currency));
+
+        println!();
+        println!("  Split: {}", split_id);
+        if single_currency {
+            let total: Cents = info.split_legs.iter().map(|leg| leg.amount_cents).sum();
+            println!(
+                "    {} legs, total {}",
+                info.split_legs.len(),
+                format_money(locale, total, &currency)
+            );
+        } else {
+            println!("    {} legs", info.split_legs.len());
+        }
+        for leg in &info.split_legs {
+            println!(
+                "    {} {:>10} {} -> {}{}",
+                if leg.id == transfer.id { "*" } else { "-" },
+                wallet_money(locale, &currencies, leg.from_wallet, leg.amount_cents),
+                wallet_name(&wallet_names, leg.from_wallet),
+                wallet_name(&wallet_names, leg.to_wallet),
+                leg.category
+                    .as_ref()
+                    .map(|c| format!(" [{}]", c))
+                    .unwrap_or_default()
+            );
+        }
+    }
+
     // Show reversal info
     if let Some(reverses_id) = transfer.reverses {
         println!();
@@ -1382,24 +4816,283 @@ async fn run_show_transfer_command(service: &LedgerService, transfer_id: uuid::U
         println!("  Reversal status:");
         println!(
             "    Reversed:  {} ({:.0}%)",
-            format_cents(info.total_reversed),
+            format_money(locale, info.total_reversed, &currency),
             percentage
         );
-        println!("    Remaining: {}", format_cents(remaining));
+        println!(
+            "    Remaining: {}",
+            format_money(locale, remaining, &currency)
+        );
         println!("    Reversals:");
         for rev in &info.reversals {
             println!(
                 "      - {} on {} ({})",
-                format_cents(rev.amount_cents),
+                format_money(locale, rev.amount_cents, &currency),
                 rev.timestamp.format("%Y-%m-%d"),
                 rev.id
             );
         }
     }
 
+    if !info.amendments.is_empty() {
+        println!();
+        println!("  Amendments:");
+        for amendment in &info.amendments {
+            println!(
+                "    {} by {}{}",
+                amendment.amended_at.format("%Y-%m-%d %H:%M:%S"),
+                amendment.author.as_deref().unwrap_or("unknown"),
+                amendment
+                    .reason
+                    .as_ref()
+                    .map(|r| format!(": {}", r))
+                    .unwrap_or_default()
+            );
+            for change in &amendment.changes {
+                let show = |value: &Option<String>| {
+                    amended_value(locale, &currency, change.field, value.as_deref())
+                };
+                println!(
+                    "      {}: {} -> {}",
+                    change.field,
+                    show(&change.old_value),
+                    show(&change.new_value)
+                );
+            }
+        }
+    }
+
+    if !info.attachments.is_empty() {
+        println!();
+        println!("  Attachments:");
+        for attachment in &info.attachments {
+            println!(
+                "    {}  {} ({}, {})",
+                attachment.id,
+                attachment.filename,
+                attachment.media_type,
+                format_size(attachment.size)
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Write a transfer's attachments (or just one of them) into a directory.
+/// Existing files are never overwritten.
+async fn run_extract_attachments_command(
+    service: &LedgerService,
+    transfer_id: uuid::Uuid,
+    dir: &str,
+    attachment_id: Option<uuid::Uuid>,
+) -> Result<()> {
+    let attachments: Vec<_> = service
+        .list_attachments(transfer_id)
+        .await?
+        .into_iter()
+        .filter(|a| attachment_id.is_none_or(|id| a.id == id))
+        .collect();
+    if attachments.is_empty() {
+        match attachment_id {
+            Some(id) => anyhow::bail!("Transfer {} has no attachment {}", transfer_id, id),
+            None => {
+                println!("Transfer {} has no attachments.", transfer_id);
+                return Ok(());
+            }
+        }
+    }
+
+    let dir = std::path::Path::new(dir);
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+    let mut written: Vec<String> = Vec::new();
+    for attachment in attachments {
+        let (attachment, data) = service.read_attachment(attachment.id).await?;
+        // Two attachments with the same name are told apart by their ID
+        let filename = if written.contains(&attachment.filename) {
+            format!(
+                "{}-{}",
+                &attachment.id.to_string()[..8],
+                attachment.filename
+            )
+        } else {
+            attachment.filename.clone()
+        };
+        let path = dir.join(&filename);
+        if path.exists() {
+            anyhow::bail!("{} already exists", path.display());
+        }
+        std::fs::write(&path, &data)
+            .with_context(|| format!("Failed to write file: {}", path.display()))?;
+        println!("Extracted {}", path.display());
+        written.push(filename);
+    }
+
+    Ok(())
+}
+
+/// Human-readable size of a file.
+fn format_size(bytes: i64) -> String {
+    match bytes {
+        b if b >= 1024 * 1024 => format!("{:.1} MiB", b as f64 / (1024.0 * 1024.0)),
+        b if b >= 1024 => format!("{:.1} KiB", b as f64 / 1024.0),
+        b => format!("{} B", b),
+    }
+}
+
+/// Render a value from a transfer's amendment history for display.
+fn amended_value(
+    locale: &LocaleProfile,
+    currency: &Currency,
+    field: AmendedField,
+    value: Option<&str>,
+) -> String {
+    let Some(value) = value else {
+        return "(none)".to_string();
+    };
+    match field {
+        AmendedField::Amount => value
+            .parse::<Cents>()
+            .map(|cents| format_money(locale, cents, currency))
+            .unwrap_or_else(|_| value.to_string()),
+        AmendedField::Date => chrono::DateTime::parse_from_rfc3339(value)
+            .map(|dt| dt.format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|_| value.to_string()),
+        _ => format!("\"{}\"", value),
+    }
+}
+
+/// The units a holding wallet counts its commodity in (the commodity's decimals).
+async fn holding_units(service: &LedgerService, wallet_name: &str) -> Result<Currency> {
+    let wallet = service.get_wallet(wallet_name).await?;
+    let commodity = wallet
+        .commodity
+        .ok_or_else(|| anyhow::anyhow!("Wallet '{}' does not hold a commodity", wallet_name))?;
+    Ok(service.get_currency(&commodity).await?)
+}
+
+async fn print_trade(
+    service: &LedgerService,
+    locale: &LocaleProfile,
+    verb: &str,
+    units: &Currency,
+    result: &crate::application::TransferResult,
+) -> Result<()> {
+    let transfer = &result.transfer;
+    let currency = service
+        .get_wallet_currency(&result.from_wallet_name)
+        .await?;
+    println!(
+        "{} {} {} @ {} for {}: {} -> {} ({})",
+        verb,
+        format_money(locale, transfer.quantity.unwrap_or(0), units),
+        units.code,
+        transfer.unit_price.unwrap_or(0.0),
+        locale.format_money(transfer.amount_cents, &currency),
+        result.from_wallet_name,
+        result.to_wallet_name,
+        transfer.id
+    );
     Ok(())
 }
 
+fn wallet_name(names: &std::collections::HashMap<Uuid, String>, id: Uuid) -> &str {
+    names.get(&id).map(|s| s.as_str()).unwrap_or("?")
+}
+
+/// Parse a lot pick given as `LOT_ID=UNITS`, with the units in the commodity's decimals.
+fn parse_lot_pick(
+    locale: &LocaleProfile,
+    spec: &str,
+    units: &Currency,
+    rounding: &str,
+) -> Result<LotPick> {
+    let (id, quantity) = spec
+        .split_once('=')
+        .ok_or_else(|| anyhow::anyhow!("Invalid lot '{}'. Use LOT_ID=UNITS", spec))?;
+    let lot_id = Uuid::parse_str(id.trim()).context("Invalid lot ID format (expected UUID)")?;
+    let quantity = parse_money(locale, quantity.trim(), units, rounding)?;
+    Ok(LotPick { lot_id, quantity })
+}
+
+/// Parse a split leg given as `WALLET=AMOUNT[,CATEGORY]`, with the amount in `currency`.
+fn parse_split_leg(
+    locale: &LocaleProfile,
+    spec: &str,
+    currency: &Currency,
+    rounding: &str,
+) -> Result<crate::application::SplitLeg> {
+    let (wallet, rest) = spec
+        .split_once('=')
+        .ok_or_else(|| anyhow::anyhow!("Invalid leg '{}'. Use WALLET=AMOUNT[,CATEGORY]", spec))?;
+    // With a decimal comma ("45,00") the amount itself may contain the separator,
+    // so only split off a category when the whole remainder is not an amount.
+    let (amount, category) = match rest.rsplit_once(',') {
+        Some((amount, category)) if parse_money(locale, rest, currency, rounding).is_err() => {
+            (amount, Some(category.trim().to_string()))
+        }
+        _ => (rest, None),
+    };
+
+    Ok(crate::application::SplitLeg {
+        to_wallet: wallet.trim().to_string(),
+        amount_cents: parse_money(locale, amount, currency, rounding)
+            .with_context(|| format!("Invalid amount in leg '{}'", spec))?,
+        category: category.filter(|c| !c.is_empty()),
+        description: None,
+    })
+}
+
+/// Parse an amount written in the active locale into a currency's minor units,
+/// rounding extra decimals as requested.
+fn parse_money(
+    locale: &LocaleProfile,
+    input: &str,
+    currency: &Currency,
+    rounding: &str,
+) -> Result<Cents> {
+    let rounding: RoundingMode = rounding.parse().map_err(|e| anyhow::anyhow!("{}", e))?;
+    locale
+        .parse_amount(input, currency, rounding)
+        .with_context(|| {
+            format!(
+                "Invalid amount '{}' for {} ({} decimals, {} locale)",
+                input, currency.code, currency.minor_units, locale.name
+            )
+        })
+}
+
+/// Format an amount with the decimals of its currency, in the active locale.
+fn format_money(locale: &LocaleProfile, amount: Cents, currency: &Currency) -> String {
+    locale.format_amount(amount, currency)
+}
+
+/// Format an amount held in a wallet, falling back to two decimals for unknown wallets.
+fn wallet_money(
+    locale: &LocaleProfile,
+    currencies: &std::collections::HashMap<Uuid, Currency>,
+    wallet_id: Uuid,
+    amount: Cents,
+) -> String {
+    match currencies.get(&wallet_id) {
+        Some(currency) => format_money(locale, amount, currency),
+        None => locale.format_cents(amount),
+    }
+}
+
+/// The override for `--override-lock`, logged under the author (defaults to $USER).
+fn lock_override(reason: Option<String>, author: Option<String>) -> Option<LockOverrideRequest> {
+    reason.map(|reason| {
+        LockOverrideRequest::new(reason).with_author(author.or_else(|| std::env::var("USER").ok()))
+    })
+}
+
+/// A name indented to its level in a tree.
+fn tree_label(name: &str, depth: usize) -> String {
+    format!("{}{}", "  ".repeat(depth), name)
+}
+
 fn truncate(s: &str, max_len: usize) -> String {
     if s.len() <= max_len {
         s.to_string()
@@ -1408,6 +5101,18 @@ fn truncate(s: &str, max_len: usize) -> String {
     }
 }
 
+/// Parse a calendar day given as YYYY-MM-DD.
+fn parse_day(date_str: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
+        .with_context(|| format!("Invalid date format '{}'. Use YYYY-MM-DD", date_str))
+}
+
+fn parse_rollover(policy: &str) -> Result<crate::domain::RolloverPolicy> {
+    policy
+        .parse()
+        .map_err(|e| anyhow::anyhow!("{}. Valid policies: none, carry-positive, carry-both", e))
+}
+
 fn parse_date(date_str: &str) -> Result<chrono::DateTime<chrono::Utc>> {
     use chrono::NaiveDate;
 
@@ -1426,18 +5131,31 @@ fn parse_date(date_str: &str) -> Result<chrono::DateTime<chrono::Utc>> {
     ))
 }
 
-async fn run_budget_command(service: &LedgerService, cmd: BudgetCommands) -> Result<()> {
+async fn run_budget_command(
+    service: &LedgerService,
+    locale: &LocaleProfile,
+    cmd: BudgetCommands,
+) -> Result<()> {
     use crate::domain::PeriodType;
 
     match cmd {
         BudgetCommands::Create {
             name,
             category,
+            from_wallets,
+            to_wallets,
+            tags,
+            all_tags,
             amount,
             period,
+            currency,
+            rollover,
+            rollover_since,
+            alert_thresholds,
         } => {
-            let amount_cents =
-                parse_cents(&amount).context("Invalid amount format. Use '400.00' or '400'")?;
+            let budget_currency = report_currency(service, currency.as_deref()).await?;
+            let amount_cents = parse_money(locale, &amount, &budget_currency, "reject")
+                .context("Invalid amount format. Use '400.00' or '400'")?;
 
             let period_type: PeriodType = period.parse().map_err(|e| {
                 anyhow::anyhow!(
@@ -1446,15 +5164,36 @@ async fn run_budget_command(service: &LedgerService, cmd: BudgetCommands) -> Res
                     e
                 )
             })?;
+            let rollover = parse_rollover(&rollover)?;
+            let rollover_since = rollover_since.as_deref().map(parse_day).transpose()?;
+
+            let tag_match = if all_tags {
+                TagMatch::All
+            } else {
+                TagMatch::Any
+            };
+            // The first category is the budget's own, the others join its filter
+            let mut categories = category.into_iter();
+            let category = categories.next().unwrap_or_default();
 
             let budget = service
-                .create_budget(name.clone(), category, amount_cents, period_type)
+                .create_budget_with(
+                    BudgetRequest::new(name, category, amount_cents, period_type)
+                        .with_categories(categories.collect())
+                        .with_from_wallets(from_wallets)
+                        .with_to_wallets(to_wallets)
+                        .with_tags(tags, tag_match)
+                        .with_currency(currency)
+                        .with_rollover(rollover, rollover_since)
+                        .with_alert_thresholds(alert_thresholds),
+                )
                 .await?;
+            let names = service.get_wallet_names().await?;
             println!(
                 "Created budget: {} ({}, {} per {})",
                 budget.name,
-                budget.category,
-                format_cents(budget.amount_cents),
+                budget_scope(&budget.category, &budget.filter, &names),
+                format_budget_amount(service, locale, &budget, budget.amount_cents).await?,
                 budget.period_type
             );
         }
@@ -1464,17 +5203,18 @@ async fn run_budget_command(service: &LedgerService, cmd: BudgetCommands) -> Res
             if budgets.is_empty() {
                 println!("No budgets found.");
             } else {
+                let names = service.get_wallet_names().await?;
                 println!(
-                    "{:<20} {:<15} {:>12} {:<10}",
-                    "NAME", "CATEGORY", "AMOUNT", "PERIOD"
+                    "{:<20} {:<30} {:>12} {:<10}",
+                    "NAME", "SCOPE", "AMOUNT", "PERIOD"
                 );
-                println!("{}", "-".repeat(60));
+                println!("{}", "-".repeat(75));
                 for budget in budgets {
                     println!(
-                        "{:<20} {:<15} {:>12} {:<10}",
+                        "{:<20} {:<30} {:>12} {:<10}",
                         budget.name,
-                        budget.category,
-                        format_cents(budget.amount_cents),
+                        truncate(&budget_scope(&budget.category, &budget.filter, &names), 30),
+                        format_budget_amount(service, locale, &budget, budget.amount_cents).await?,
                         budget.period_type
                     );
                 }
@@ -1487,23 +5227,152 @@ async fn run_budget_command(service: &LedgerService, cmd: BudgetCommands) -> Res
                 println!("No budgets found.");
             } else {
                 println!(
-                    "{:<20} {:<10} {:>12} {:>12} {:>12}",
-                    "BUDGET", "PERIOD", "LIMIT", "SPENT", "REMAINING"
+                    "{:<20} {:<10} {:>12} {:>12} {:>12} {:>12} {:>12}",
+                    "BUDGET", "PERIOD", "LIMIT", "CARRIED", "EFFECTIVE", "SPENT", "REMAINING"
                 );
-                println!("{}", "-".repeat(70));
+                println!("{}", "-".repeat(96));
                 for status in statuses {
                     println!(
-                        "{:<20} {:<10} {:>12} {:>12} {:>12}",
+                        "{:<20} {:<10} {:>12} {:>12} {:>12} {:>12} {:>12}",
                         status.budget.name,
                         status.budget.period_type,
-                        format_cents(status.budget.amount_cents),
-                        format_cents(status.spent),
-                        format_cents(status.remaining),
+                        format_budget_amount(
+                            service,
+                            locale,
+                            &status.budget,
+                            status.budget.amount_cents
+                        )
+                        .await?,
+                        format_budget_amount(service, locale, &status.budget, status.carried)
+                            .await?,
+                        format_budget_amount(
+                            service,
+                            locale,
+                            &status.budget,
+                            status.effective_limit()
+                        )
+                        .await?,
+                        format_budget_amount(service, locale, &status.budget, status.spent).await?,
+                        format_budget_amount(service, locale, &status.budget, status.remaining)
+                            .await?,
+                    );
+                }
+            }
+        }
+
+        BudgetCommands::Rollover {
+            name,
+            policy,
+            since,
+        } => {
+            let policy = parse_rollover(&policy)?;
+            let since = match since {
+                Some(since) => Some(parse_day(&since)?),
+                None => service.get_budget(&name).await?.rollover_since,
+            };
+            let budget = service.set_budget_rollover(&name, policy, since).await?;
+            println!(
+                "Budget {} now rolls over: {} (from {})",
+                budget.name,
+                budget.rollover,
+                budget.rollover_start().format("%Y-%m-%d")
+            );
+        }
+
+        BudgetCommands::RolloverHistory { name } => {
+            let budget = service.get_budget(&name).await?;
+            let history = service.get_budget_rollover_history(&name).await?;
+            println!("Budget: {} (rollover: {})", budget.name, budget.rollover);
+            if history.is_empty() {
+                println!(
+                    "No past periods since {}.",
+                    budget.rollover_start().format("%Y-%m-%d")
+                );
+            } else {
+                println!(
+                    "{:<12} {:>12} {:>12} {:>12} {:>12} {:>12}",
+                    "PERIOD", "LIMIT", "CARRIED IN", "EFFECTIVE", "SPENT", "CARRIED OUT"
+                );
+                println!("{}", "-".repeat(80));
+                for carry in &history {
+                    println!(
+                        "{:<12} {:>12} {:>12} {:>12} {:>12} {:>12}",
+                        carry.period_start.format("%Y-%m-%d").to_string(),
+                        format_budget_amount(service, locale, &budget, carry.limit).await?,
+                        format_budget_amount(service, locale, &budget, carry.carried_in).await?,
+                        format_budget_amount(service, locale, &budget, carry.effective_limit())
+                            .await?,
+                        format_budget_amount(service, locale, &budget, carry.spent).await?,
+                        format_budget_amount(service, locale, &budget, carry.carried_out).await?,
+                    );
+                }
+            }
+        }
+
+        BudgetCommands::Alerts { name, thresholds } => {
+            let budget = service
+                .set_budget_alert_thresholds(&name, thresholds)
+                .await?;
+            if budget.alert_thresholds.is_empty() {
+                println!("Budget {} no longer raises alerts", budget.name);
+            } else {
+                println!(
+                    "Budget {} alerts at {}",
+                    budget.name,
+                    format_thresholds(&budget.alert_thresholds)
+                );
+            }
+        }
+
+        BudgetCommands::Events { name } => {
+            let events = service.list_budget_events(name.as_deref()).await?;
+            if events.is_empty() {
+                println!("No budget alerts found.");
+            } else {
+                println!(
+                    "{:<17} {:<20} {:<12} {:>6} {:>12} {:>12}",
+                    "FIRED", "BUDGET", "PERIOD", "ALERT", "SPENT", "LIMIT"
+                );
+                println!("{}", "-".repeat(84));
+                for event in &events {
+                    let currency = report_currency(service, event.currency.as_deref()).await?;
+                    println!(
+                        "{:<17} {:<20} {:<12} {:>6} {:>12} {:>12}",
+                        event.fired_at.format("%Y-%m-%d %H:%M").to_string(),
+                        truncate(&event.budget, 20),
+                        event.period_start.format("%Y-%m-%d").to_string(),
+                        format!("{}%", event.threshold),
+                        format_money(locale, event.spent, &currency),
+                        format_money(locale, event.limit, &currency),
                     );
                 }
             }
         }
 
+        BudgetCommands::Notify {
+            command,
+            file,
+            clear,
+        } => {
+            let hooks = if clear {
+                service
+                    .set_budget_alert_hooks(BudgetAlertHooks::default())
+                    .await?
+            } else if command.is_some() || file.is_some() {
+                let current = service.get_budget_alert_hooks().await?;
+                service
+                    .set_budget_alert_hooks(BudgetAlertHooks {
+                        command: command.or(current.command),
+                        file: file.or(current.file),
+                    })
+                    .await?
+            } else {
+                service.get_budget_alert_hooks().await?
+            };
+            println!("Command: {}", hooks.command.as_deref().unwrap_or("(none)"));
+            println!("File:    {}", hooks.file.as_deref().unwrap_or("(none)"));
+        }
+
         BudgetCommands::Delete { name } => {
             service.delete_budget(&name).await?;
             println!("Deleted budget: {}", name);
@@ -1513,7 +5382,304 @@ async fn run_budget_command(service: &LedgerService, cmd: BudgetCommands) -> Res
     Ok(())
 }
 
-async fn run_scheduled_command(service: &LedgerService, command: ScheduledCommands) -> Result<()> {
+async fn run_envelope_command(
+    service: &LedgerService,
+    locale: &LocaleProfile,
+    cmd: EnvelopeCommands,
+) -> Result<()> {
+    let this_month = Utc::now().date_naive();
+
+    match cmd {
+        EnvelopeCommands::Create {
+            name,
+            category,
+            currency,
+            start,
+        } => {
+            let start = match start {
+                Some(start) => parse_month(&start)?,
+                None => this_month,
+            };
+            let envelope = service
+                .create_envelope(name, category, currency, start)
+                .await?;
+            println!(
+                "Created envelope: {} ({}, {} from {})",
+                envelope.name,
+                envelope.category,
+                envelope.currency,
+                envelope.start_month.format("%Y-%m")
+            );
+        }
+
+        EnvelopeCommands::List => {
+            let envelopes = service.list_envelopes().await?;
+            if envelopes.is_empty() {
+                println!("No envelopes found.");
+                return Ok(());
+            }
+            println!(
+                "{:<20} {:<20} {:<8} {:<8}",
+                "NAME", "CATEGORY", "CURRENCY", "FROM"
+            );
+            println!("{}", "-".repeat(59));
+            for envelope in envelopes {
+                println!(
+                    "{:<20} {:<20} {:<8} {:<8}",
+                    truncate(&envelope.name, 20),
+                    truncate(&envelope.category, 20),
+                    envelope.currency,
+                    envelope.start_month.format("%Y-%m").to_string()
+                );
+            }
+        }
+
+        EnvelopeCommands::Assign {
+            name,
+            amount,
+            month,
+        } => {
+            let month = match month {
+                Some(month) => parse_month(&month)?,
+                None => this_month,
+            };
+            let envelope = service.get_envelope(&name).await?;
+            let currency = service.get_currency(&envelope.currency).await?;
+            let amount_cents = parse_money(locale, &amount, &currency, "reject")?;
+            let assignment = service
+                .assign_to_envelope(&name, month, amount_cents)
+                .await?;
+            println!(
+                "Assigned {} to {} for {}",
+                format_money(locale, assignment.amount_cents, &currency),
+                envelope.name,
+                assignment.month.format("%Y-%m")
+            );
+            print_to_be_assigned(service, locale, month, &currency).await?;
+        }
+
+        EnvelopeCommands::Move {
+            from,
+            to,
+            amount,
+            month,
+        } => {
+            let month = match month {
+                Some(month) => parse_month(&month)?,
+                None => this_month,
+            };
+            let currency = service
+                .get_currency(&service.get_envelope(&from).await?.currency)
+                .await?;
+            let amount_cents = parse_money(locale, &amount, &currency, "reject")?;
+            let (_, given) = service
+                .move_between_envelopes(&from, &to, month, amount_cents)
+                .await?;
+            println!(
+                "Moved {} from {} to {} for {}",
+                format_money(locale, given.amount_cents, &currency),
+                from,
+                to,
+                given.month.format("%Y-%m")
+            );
+        }
+
+        EnvelopeCommands::Status { month } => {
+            let month = match month {
+                Some(month) => parse_month(&month)?,
+                None => this_month,
+            };
+            let statuses = service.get_envelope_status(month).await?;
+            if statuses.is_empty() {
+                println!("No envelopes found.");
+                return Ok(());
+            }
+            for status in statuses {
+                let currency = service.get_currency(&status.currency).await?;
+                println!(
+                    "Envelopes {} ({})",
+                    status.month.format("%Y-%m"),
+                    status.currency
+                );
+                println!(
+                    "To be assigned: {}",
+                    format_money(locale, status.to_be_assigned, &currency)
+                );
+                println!();
+                println!(
+                    "{:<20} {:>12} {:>12} {:>12}",
+                    "ENVELOPE", "ASSIGNED", "ACTIVITY", "AVAILABLE"
+                );
+                println!("{}", "-".repeat(59));
+                for envelope in &status.envelopes {
+                    println!(
+                        "{:<20} {:>12} {:>12} {:>12}{}",
+                        truncate(&envelope.envelope.name, 20),
+                        format_money(locale, envelope.assigned, &currency),
+                        format_money(locale, envelope.activity, &currency),
+                        format_money(locale, envelope.available, &currency),
+                        if envelope.is_overspent() {
+                            "  overspent"
+                        } else {
+                            ""
+                        }
+                    );
+                }
+                println!();
+            }
+        }
+
+        EnvelopeCommands::Delete { name } => {
+            service.delete_envelope(&name).await?;
+            println!("Deleted envelope: {}", name);
+        }
+    }
+
+    Ok(())
+}
+
+/// Print what is left to assign in a currency after an assignment, warning
+/// when more was assigned than came in.
+async fn print_to_be_assigned(
+    service: &LedgerService,
+    locale: &LocaleProfile,
+    month: NaiveDate,
+    currency: &Currency,
+) -> Result<()> {
+    let statuses = service.get_envelope_status(month).await?;
+    if let Some(status) = statuses.iter().find(|s| s.currency == currency.code) {
+        println!(
+            "To be assigned: {}",
+            format_money(locale, status.to_be_assigned, currency)
+        );
+        if status.to_be_assigned < 0 {
+            println!("Warning: more was assigned than came in");
+        }
+    }
+    Ok(())
+}
+
+/// Parse a month given as YYYY-MM (or a day in it).
+fn parse_month(month_str: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(&format!("{}-01", month_str), "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(month_str, "%Y-%m-%d"))
+        .with_context(|| format!("Invalid month '{}'. Use YYYY-MM", month_str))
+}
+
+/// One block character per value, scaled between the lowest and highest value.
+fn sparkline(values: &[Cents]) -> Vec<char> {
+    const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let min = values.iter().min().copied().unwrap_or(0);
+    let max = values.iter().max().copied().unwrap_or(0);
+    let range = (max - min) as i128;
+    values
+        .iter()
+        .map(|&value| {
+            if range == 0 {
+                return BLOCKS[BLOCKS.len() / 2];
+            }
+            let level = (value - min) as i128 * (BLOCKS.len() as i128 - 1) / range;
+            BLOCKS[level as usize]
+        })
+        .collect()
+}
+
+/// Currency used to format a report: the reporting currency when converted,
+/// two decimals otherwise.
+async fn report_currency(service: &LedgerService, code: Option<&str>) -> Result<Currency> {
+    Ok(match code {
+        Some(code) => service.get_currency(code).await?,
+        None => Currency::new("", 2),
+    })
+}
+
+/// What a budget counts, e.g. "dining, games from Card tagged trip|holiday".
+fn budget_scope(
+    category: &str,
+    filter: &crate::domain::BudgetFilter,
+    names: &std::collections::HashMap<Uuid, String>,
+) -> String {
+    let mut parts = Vec::new();
+    let categories: Vec<&str> = std::iter::once(category)
+        .filter(|category| !category.is_empty())
+        .chain(filter.categories.iter().map(String::as_str))
+        .collect();
+    if !categories.is_empty() {
+        parts.push(categories.join(", "));
+    }
+    for (label, wallets) in [("from", &filter.from_wallets), ("to", &filter.to_wallets)] {
+        if !wallets.is_empty() {
+            let wallets: Vec<&str> = wallets.iter().map(|id| wallet_name(names, *id)).collect();
+            parts.push(format!("{} {}", label, wallets.join(", ")));
+        }
+    }
+    if !filter.tags.is_empty() {
+        let separator = match filter.tag_match {
+            TagMatch::Any => "|",
+            TagMatch::All => "+",
+        };
+        parts.push(format!("tagged {}", filter.tags.join(separator)));
+    }
+    parts.join(" ")
+}
+
+/// Alert thresholds as shown to the user, e.g. "80%, 100%".
+fn format_thresholds(thresholds: &[u32]) -> String {
+    thresholds
+        .iter()
+        .map(|t| format!("{}%", t))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// One line per budget alert raised, or alert check that failed, since the last call.
+async fn budget_warnings(service: &LedgerService, locale: &LocaleProfile) -> Result<Vec<String>> {
+    let mut lines = Vec::new();
+    for event in service.take_budget_events() {
+        let currency = report_currency(service, event.currency.as_deref()).await?;
+        lines.push(format!(
+            "Warning: budget {} reached {}% of its limit: {} of {} spent",
+            event.budget,
+            event.threshold,
+            format_money(locale, event.spent, &currency),
+            format_money(locale, event.limit, &currency)
+        ));
+        if let Some(error) = &event.delivery_error {
+            lines.push(format!("  Alert could not be delivered: {}", error));
+        }
+    }
+    for error in service.take_budget_alert_errors() {
+        lines.push(format!(
+            "Warning: budget alerts could not be checked: {}",
+            error
+        ));
+    }
+    Ok(lines)
+}
+
+async fn print_budget_warnings(service: &LedgerService, locale: &LocaleProfile) -> Result<()> {
+    for line in budget_warnings(service, locale).await? {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+/// Format a budget amount with the decimals of the budget's currency (two if it has none).
+async fn format_budget_amount(
+    service: &LedgerService,
+    locale: &LocaleProfile,
+    budget: &crate::domain::Budget,
+    amount: Cents,
+) -> Result<String> {
+    let currency = report_currency(service, budget.currency.as_deref()).await?;
+    Ok(format_money(locale, amount, &currency))
+}
+
+async fn run_scheduled_command(
+    service: &LedgerService,
+    locale: &LocaleProfile,
+    command: ScheduledCommands,
+) -> Result<()> {
     use crate::domain::RecurrencePattern;
 
     match command {
@@ -1527,8 +5693,12 @@ async fn run_scheduled_command(service: &LedgerService, command: ScheduledComman
             end_date,
             description,
             category,
+            tags,
         } => {
-            let amount_cents = parse_cents(&amount)?;
+            let currency = service.get_wallet_currency(&from).await?;
+            let amount_cents = parse_money(locale, &amount, &currency, "reject")?;
+            // Checked before anything is created
+            let tags = normalize_tags(&tags).map_err(|e| anyhow::anyhow!("Invalid tag: {}", e))?;
             let pattern: RecurrencePattern = pattern
                 .parse()
                 .map_err(|e| anyhow::anyhow!("Invalid pattern: {}. Error: {}", pattern, e))?;
@@ -1548,11 +5718,21 @@ async fn run_scheduled_command(service: &LedgerService, command: ScheduledComman
                     category,
                 )
                 .await?;
+            let scheduled = if tags.is_empty() {
+                scheduled
+            } else {
+                service
+                    .set_scheduled_transfer_tags(&scheduled.name, tags)
+                    .await?
+            };
 
             println!("Created scheduled transfer: {}", scheduled.name);
             println!("  From: {}", from);
             println!("  To: {}", to);
-            println!("  Amount: {}", format_cents(scheduled.amount_cents));
+            println!(
+                "  Amount: {}",
+                format_money(locale, scheduled.amount_cents, &currency)
+            );
             println!("  Pattern: {}", scheduled.pattern);
             println!("  Start: {}", scheduled.start_date.format("%Y-%m-%d"));
             if let Some(end_date) = scheduled.end_date {
@@ -1565,6 +5745,7 @@ async fn run_scheduled_command(service: &LedgerService, command: ScheduledComman
             if scheduled.is_empty() {
                 println!("No scheduled transfers found.");
             } else {
+                let currencies = service.get_wallet_currencies().await?;
                 println!(
                     "{:<20} {:<15} {:<15} {:>12} {:<10} {:<12}",
                     "NAME", "FROM", "TO", "AMOUNT", "PATTERN", "STATUS"
@@ -1578,7 +5759,7 @@ async fn run_scheduled_command(service: &LedgerService, command: ScheduledComman
                         truncate(&st.name, 20),
                         format!("{:.8}", st.from_wallet),
                         format!("{:.8}", st.to_wallet),
-                        format_cents(st.amount_cents),
+                        wallet_money(locale, &currencies, st.from_wallet, st.amount_cents),
                         st.pattern,
                         st.status,
                     );
@@ -1588,13 +5769,17 @@ async fn run_scheduled_command(service: &LedgerService, command: ScheduledComman
 
         ScheduledCommands::Show { name } => {
             let st = service.get_scheduled_transfer(&name).await?;
+            let currencies = service.get_wallet_currencies().await?;
             let now = Utc::now();
 
             println!("Scheduled Transfer: {}", st.name);
             println!("  ID: {}", st.id);
             println!("  Status: {}", st.status);
             println!("  Pattern: {}", st.pattern);
-            println!("  Amount: {}", format_cents(st.amount_cents));
+            println!(
+                "  Amount: {}",
+                wallet_money(locale, &currencies, st.from_wallet, st.amount_cents)
+            );
             println!("  Start Date: {}", st.start_date.format("%Y-%m-%d"));
             if let Some(end_date) = st.end_date {
                 println!("  End Date: {}", end_date.format("%Y-%m-%d"));
@@ -1611,6 +5796,9 @@ async fn run_scheduled_command(service: &LedgerService, command: ScheduledComman
             if let Some(cat) = &st.category {
                 println!("  Category: {}", cat);
             }
+            if !st.tags.is_empty() {
+                println!("  Tags: {}", st.tags.join(", "));
+            }
         }
 
         ScheduledCommands::Pause { name } => {
@@ -1647,13 +5835,19 @@ async fn run_scheduled_command(service: &LedgerService, command: ScheduledComman
                 if results.is_empty() {
                     println!("No scheduled transfers due for execution.");
                 } else {
+                    let currencies = service.get_wallet_currencies().await?;
                     println!("Executed {} scheduled transfer(s):", results.len());
                     for result in results {
                         println!(
                             "  {} -> {}: {}",
                             result.from_wallet_name,
                             result.to_wallet_name,
-                            format_cents(result.transfer.amount_cents)
+                            wallet_money(
+                                locale,
+                                &currencies,
+                                result.transfer.from_wallet,
+                                result.transfer.amount_cents
+                            )
                         );
                     }
                 }
@@ -1666,12 +5860,15 @@ async fn run_scheduled_command(service: &LedgerService, command: ScheduledComman
                 .execute_scheduled_transfer(&name, exec_date, force)
                 .await?;
 
+            let currency = service
+                .get_wallet_currency(&result.from_wallet_name)
+                .await?;
             println!("Executed scheduled transfer: {}", name);
             println!(
                 "  {} -> {}: {}",
                 result.from_wallet_name,
                 result.to_wallet_name,
-                format_cents(result.transfer.amount_cents)
+                format_money(locale, result.transfer.amount_cents, &currency)
             );
             println!("  Transfer ID: {}", result.transfer.id);
         }
@@ -1682,6 +5879,7 @@ async fn run_scheduled_command(service: &LedgerService, command: ScheduledComman
 
 async fn run_forecast_command(
     service: &LedgerService,
+    locale: &LocaleProfile,
     months: usize,
     wallet_filter: Option<&str>,
 ) -> Result<()> {
@@ -1731,7 +5929,7 @@ async fn run_forecast_command(
 
         for wallet in &wallet_names {
             let balance = snapshot.wallet_balances.get(wallet).copied().unwrap_or(0);
-            print!("{:>15}", format_cents(balance));
+            print!("{:>15}", locale.format_cents(balance));
         }
 
         if let Some(event) = &snapshot.event {
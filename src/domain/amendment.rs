@@ -0,0 +1,251 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use super::{Transfer, TransferId};
+
+pub type AmendmentId = Uuid;
+
+/// A field of a transfer that can be corrected after it was recorded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AmendedField {
+    Description,
+    Category,
+    Tags,
+    Date,
+    Amount,
+}
+
+impl AmendedField {
+    pub const ALL: [AmendedField; 5] = [
+        AmendedField::Description,
+        AmendedField::Category,
+        AmendedField::Tags,
+        AmendedField::Date,
+        AmendedField::Amount,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AmendedField::Description => "description",
+            AmendedField::Category => "category",
+            AmendedField::Tags => "tags",
+            AmendedField::Date => "date",
+            AmendedField::Amount => "amount",
+        }
+    }
+
+    /// The field's current value on a transfer, rendered as it is stored in amendments.
+    pub fn value_of(&self, transfer: &Transfer) -> Option<String> {
+        match self {
+            AmendedField::Description => transfer.description.clone(),
+            AmendedField::Category => transfer.category.clone(),
+            AmendedField::Tags => {
+                if transfer.tags.is_empty() {
+                    None
+                } else {
+                    Some(transfer.tags.join(","))
+                }
+            }
+            AmendedField::Date => Some(transfer.timestamp.to_rfc3339()),
+            AmendedField::Amount => Some(transfer.amount_cents.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for AmendedField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for AmendedField {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "description" => Ok(AmendedField::Description),
+            "category" => Ok(AmendedField::Category),
+            "tags" => Ok(AmendedField::Tags),
+            "date" => Ok(AmendedField::Date),
+            "amount" => Ok(AmendedField::Amount),
+            _ => Err(format!("Invalid amended field: {}", s)),
+        }
+    }
+}
+
+/// One changed field, with its values rendered as text.
+/// Dates are RFC 3339, amounts are in cents and tags are comma-separated.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: AmendedField,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+/// An append-only record of a correction made to a transfer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Amendment {
+    pub id: AmendmentId,
+    pub transfer_id: TransferId,
+    /// When the correction was made
+    pub amended_at: DateTime<Utc>,
+    /// Who made the correction
+    pub author: Option<String>,
+    /// Why the correction was made
+    pub reason: Option<String>,
+    pub changes: Vec<FieldChange>,
+}
+
+impl Amendment {
+    /// Compare a transfer before and after an edit and record what changed.
+    /// Returns None when nothing changed.
+    pub fn between(before: &Transfer, after: &Transfer) -> Option<Self> {
+        let changes: Vec<FieldChange> = AmendedField::ALL
+            .iter()
+            .filter_map(|&field| {
+                let old_value = field.value_of(before);
+                let new_value = field.value_of(after);
+                (old_value != new_value).then_some(FieldChange {
+                    field,
+                    old_value,
+                    new_value,
+                })
+            })
+            .collect();
+
+        if changes.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            id: Uuid::new_v4(),
+            transfer_id: before.id,
+            amended_at: Utc::now(),
+            author: None,
+            reason: None,
+            changes,
+        })
+    }
+
+    pub fn with_author(mut self, author: impl Into<String>) -> Self {
+        self.author = Some(author.into());
+        self
+    }
+
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+
+    /// Returns true if the correction touched the given field
+    pub fn changes_field(&self, field: AmendedField) -> bool {
+        self.changes.iter().any(|c| c.field == field)
+    }
+}
+
+/// Count transfers whose current values differ from what their latest amendments
+/// set them to, i.e. transfers changed without leaving a record.
+/// Amendments must be in the order they were made.
+pub fn count_unrecorded_edits(transfers: &[Transfer], amendments: &[Amendment]) -> i64 {
+    let mut latest: HashMap<(TransferId, AmendedField), &Option<String>> = HashMap::new();
+    for amendment in amendments {
+        for change in &amendment.changes {
+            latest.insert((amendment.transfer_id, change.field), &change.new_value);
+        }
+    }
+
+    transfers
+        .iter()
+        .filter(|transfer| {
+            AmendedField::ALL.iter().any(|&field| {
+                latest
+                    .get(&(transfer.id, field))
+                    .is_some_and(|value| **value != field.value_of(transfer))
+            })
+        })
+        .count() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn transfer() -> Transfer {
+        Transfer::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            1000,
+            Utc.with_ymd_and_hms(2024, 1, 15, 0, 0, 0).unwrap(),
+        )
+        .with_description("Grocries")
+    }
+
+    #[test]
+    fn test_between_records_only_changed_fields() {
+        let before = transfer();
+        let mut after = before.clone();
+        after.description = Some("Groceries".to_string());
+        after.tags = vec!["food".to_string(), "weekly".to_string()];
+
+        let amendment = Amendment::between(&before, &after).unwrap();
+        assert_eq!(amendment.transfer_id, before.id);
+        assert_eq!(
+            amendment.changes,
+            vec![
+                FieldChange {
+                    field: AmendedField::Description,
+                    old_value: Some("Grocries".to_string()),
+                    new_value: Some("Groceries".to_string()),
+                },
+                FieldChange {
+                    field: AmendedField::Tags,
+                    old_value: None,
+                    new_value: Some("food,weekly".to_string()),
+                },
+            ]
+        );
+        assert!(!amendment.changes_field(AmendedField::Amount));
+    }
+
+    #[test]
+    fn test_between_without_changes() {
+        let before = transfer();
+        assert!(Amendment::between(&before, &before.clone()).is_none());
+    }
+
+    #[test]
+    fn test_amended_field_roundtrip() {
+        for field in AmendedField::ALL {
+            assert_eq!(field.as_str().parse::<AmendedField>().unwrap(), field);
+        }
+        assert!("payee".parse::<AmendedField>().is_err());
+    }
+
+    #[test]
+    fn test_count_unrecorded_edits() {
+        let before = transfer();
+        let mut after = before.clone();
+        after.amount_cents = 1200;
+        let amendment = Amendment::between(&before, &after).unwrap();
+
+        assert_eq!(
+            count_unrecorded_edits(
+                std::slice::from_ref(&after),
+                std::slice::from_ref(&amendment)
+            ),
+            0
+        );
+        // Fields without amendments are not checked
+        assert_eq!(
+            count_unrecorded_edits(std::slice::from_ref(&before), &[]),
+            0
+        );
+
+        // The amount went back without an amendment recording it
+        assert_eq!(count_unrecorded_edits(&[before], &[amendment]), 1);
+    }
+}
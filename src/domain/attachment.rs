@@ -0,0 +1,102 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use super::TransferId;
+
+pub type AttachmentId = Uuid;
+
+/// Largest file that can be attached to a transfer (25 MiB)
+pub const MAX_ATTACHMENT_BYTES: usize = 25 * 1024 * 1024;
+
+/// A file (receipt, invoice, warranty) kept next to the transfer it justifies.
+/// The content lives in a blob addressed by its SHA-256 hash, so the same file
+/// attached twice is stored once.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attachment {
+    pub id: AttachmentId,
+    pub transfer_id: TransferId,
+    /// SHA-256 of the content, hex encoded
+    pub hash: String,
+    /// Name of the file as attached
+    pub filename: String,
+    pub media_type: String,
+    /// Size of the content in bytes
+    pub size: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Attachment {
+    /// Describe `data` attached to a transfer under `filename`.
+    pub fn new(transfer_id: TransferId, filename: impl Into<String>, data: &[u8]) -> Self {
+        let filename = filename.into();
+        Self {
+            id: Uuid::new_v4(),
+            transfer_id,
+            hash: content_hash(data),
+            media_type: media_type_for(&filename).to_string(),
+            filename,
+            size: data.len() as i64,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// SHA-256 of some content, hex encoded: the address of its blob.
+pub fn content_hash(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Media type of a file, guessed from its extension.
+pub fn media_type_for(filename: &str) -> &'static str {
+    let extension = filename
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "pdf" => "application/pdf",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "heic" => "image/heic",
+        "tif" | "tiff" => "image/tiff",
+        "txt" => "text/plain",
+        "csv" => "text/csv",
+        "html" | "htm" => "text/html",
+        "xml" => "application/xml",
+        "json" => "application/json",
+        "zip" => "application/zip",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_hash() {
+        assert_eq!(
+            content_hash(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        let transfer_id = Uuid::new_v4();
+        let receipt = Attachment::new(transfer_id, "receipt.PDF", b"abc");
+        let copy = Attachment::new(transfer_id, "copy.pdf", b"abc");
+        assert_eq!(receipt.hash, copy.hash);
+        assert_eq!(receipt.size, 3);
+        assert_eq!(receipt.media_type, "application/pdf");
+    }
+
+    #[test]
+    fn test_media_type_for() {
+        assert_eq!(media_type_for("scan.jpeg"), "image/jpeg");
+        assert_eq!(media_type_for("invoice.2024.pdf"), "application/pdf");
+        assert_eq!(media_type_for("README"), "application/octet-stream");
+    }
+}
@@ -0,0 +1,89 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{Cents, WalletId, end_of_day};
+
+pub type BalanceAssertionId = Uuid;
+
+/// A statement that a wallet held exactly `balance_cents` at the end of `date`,
+/// as read off a bank statement. The balance is the wallet's own, signed as the
+/// ledger shows it (negative for what is owed on a liability).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BalanceAssertion {
+    pub id: BalanceAssertionId,
+    pub wallet_id: WalletId,
+    pub date: NaiveDate,
+    pub balance_cents: Cents,
+    pub created_at: DateTime<Utc>,
+}
+
+impl BalanceAssertion {
+    pub fn new(wallet_id: WalletId, date: NaiveDate, balance_cents: Cents) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            wallet_id,
+            date,
+            balance_cents,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// First instant after the asserted day: transfers before it count
+    pub fn cutoff(&self) -> DateTime<Utc> {
+        end_of_day(self.date)
+    }
+
+    /// When an adjustment making the assertion hold is dated: the last second of the day
+    pub fn adjustment_timestamp(&self) -> DateTime<Utc> {
+        self.cutoff() - Duration::seconds(1)
+    }
+}
+
+/// An assertion next to the balance the ledger actually computes for it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssertionCheck {
+    pub assertion: BalanceAssertion,
+    pub actual_cents: Cents,
+}
+
+impl AssertionCheck {
+    pub fn new(assertion: BalanceAssertion, actual_cents: Cents) -> Self {
+        Self {
+            assertion,
+            actual_cents,
+        }
+    }
+
+    /// What has to be added to the wallet for the assertion to hold
+    pub fn difference(&self) -> Cents {
+        self.assertion.balance_cents - self.actual_cents
+    }
+
+    pub fn holds(&self) -> bool {
+        self.difference() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assertion_covers_the_whole_day() {
+        let assertion = BalanceAssertion::new(
+            Uuid::new_v4(),
+            NaiveDate::from_ymd_opt(2026, 9, 30).unwrap(),
+            123456,
+        );
+        assert_eq!(assertion.cutoff().to_rfc3339(), "2026-10-01T00:00:00+00:00");
+        assert_eq!(
+            assertion.adjustment_timestamp().to_rfc3339(),
+            "2026-09-30T23:59:59+00:00"
+        );
+
+        let check = AssertionCheck::new(assertion, 120000);
+        assert!(!check.holds());
+        assert_eq!(check.difference(), 3456);
+    }
+}
@@ -1,8 +1,8 @@
-use chrono::{DateTime, Datelike, Duration, Utc};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use super::Cents;
+use super::{Cents, TagMatch, Transfer, TransferId, WalletId, is_within_category, start_of_day};
 
 pub type BudgetId = Uuid;
 
@@ -67,25 +67,16 @@ impl PeriodType {
                 )
             }
             PeriodType::Yearly => {
-                // Year starts on January 1st
-                let start = now
-                    .date_naive()
-                    .with_month(1)
-                    .unwrap()
-                    .with_day(1)
-                    .unwrap()
-                    .and_hms_opt(0, 0, 0)
-                    .unwrap();
-                let next_year = now
-                    .date_naive()
-                    .with_year(now.year() + 1)
-                    .unwrap()
-                    .with_month(1)
-                    .unwrap()
-                    .with_day(1)
-                    .unwrap()
-                    .and_hms_opt(0, 0, 0)
-                    .unwrap();
+                // Year starts on January 1st (built from the year, not the
+                // date, so Feb 29 works)
+                let year_start = |year| {
+                    NaiveDate::from_ymd_opt(year, 1, 1)
+                        .unwrap()
+                        .and_hms_opt(0, 0, 0)
+                        .unwrap()
+                };
+                let start = year_start(now.year());
+                let next_year = year_start(now.year() + 1);
                 (
                     DateTime::from_naive_utc_and_offset(start, Utc),
                     DateTime::from_naive_utc_and_offset(next_year, Utc),
@@ -95,6 +86,23 @@ impl PeriodType {
     }
 }
 
+impl PeriodType {
+    /// Every period that overlaps `[from, to)`, oldest first.
+    pub fn periods_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        let mut periods = Vec::new();
+        let (mut start, mut end) = self.current_period(from);
+        while start < to {
+            periods.push((start, end));
+            (start, end) = self.current_period(end);
+        }
+        periods
+    }
+}
+
 impl std::fmt::Display for PeriodType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.as_str())
@@ -114,13 +122,111 @@ impl std::str::FromStr for PeriodType {
     }
 }
 
+/// What becomes of what is left of a budget at the end of a period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RolloverPolicy {
+    /// Every period starts from the limit
+    #[default]
+    None,
+    /// Unspent money is added to the next period; overspending is forgiven
+    CarryPositive,
+    /// Unspent money is added to the next period and overspending taken from it
+    CarryBoth,
+}
+
+impl RolloverPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RolloverPolicy::None => "none",
+            RolloverPolicy::CarryPositive => "carry-positive",
+            RolloverPolicy::CarryBoth => "carry-both",
+        }
+    }
+
+    /// Amount carried into the next period when `left` remains at the end of one
+    pub fn carry(&self, left: Cents) -> Cents {
+        match self {
+            RolloverPolicy::None => 0,
+            RolloverPolicy::CarryPositive => left.max(0),
+            RolloverPolicy::CarryBoth => left,
+        }
+    }
+}
+
+impl std::fmt::Display for RolloverPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for RolloverPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(RolloverPolicy::None),
+            "carry-positive" => Ok(RolloverPolicy::CarryPositive),
+            "carry-both" => Ok(RolloverPolicy::CarryBoth),
+            _ => Err(format!("Invalid rollover policy: {}", s)),
+        }
+    }
+}
+
+/// What a budget counts besides its category. Every non-empty list narrows the
+/// transfers down: a transfer counts when it is in one of the categories, from
+/// one of the source wallets, to one of the destination wallets and carries the
+/// tags. Empty lists match everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BudgetFilter {
+    /// Further categories (sub-categories included) next to the budget's own
+    #[serde(default)]
+    pub categories: Vec<String>,
+    #[serde(default)]
+    pub from_wallets: Vec<WalletId>,
+    #[serde(default)]
+    pub to_wallets: Vec<WalletId>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub tag_match: TagMatch,
+}
+
+impl BudgetFilter {
+    pub fn is_empty(&self) -> bool {
+        self.categories.is_empty()
+            && self.from_wallets.is_empty()
+            && self.to_wallets.is_empty()
+            && self.tags.is_empty()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Budget {
     pub id: BudgetId,
     pub name: String,
+    /// Category tracked, including all its sub-categories; empty when the
+    /// budget is only scoped by its filter
     pub category: String,
+    /// Further categories, wallets and tags the budget is scoped by
+    #[serde(default)]
+    pub filter: BudgetFilter,
     pub period_type: PeriodType,
     pub amount_cents: Cents,
+    /// Currency of the limit; only transfers into wallets of this currency count.
+    /// `None` for budgets created before currencies were tracked (counts every currency).
+    #[serde(default)]
+    pub currency: Option<String>,
+    /// What is left at the end of a period and carries into the next one
+    #[serde(default)]
+    pub rollover: RolloverPolicy,
+    /// Day in the first period whose leftover carries over; `None` starts with
+    /// the period the budget was created in
+    #[serde(default)]
+    pub rollover_since: Option<NaiveDate>,
+    /// Percentages of the limit that raise a budget event once per period, ascending
+    #[serde(default)]
+    pub alert_thresholds: Vec<u32>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -137,14 +243,183 @@ impl Budget {
             category,
             period_type,
             amount_cents,
+            filter: BudgetFilter::default(),
+            currency: None,
+            rollover: RolloverPolicy::None,
+            rollover_since: None,
+            alert_thresholds: Vec::new(),
             created_at: Utc::now(),
         }
     }
 
+    pub fn with_currency(mut self, currency: String) -> Self {
+        self.currency = Some(currency);
+        self
+    }
+
+    pub fn with_filter(mut self, filter: BudgetFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// The budget's category followed by the further ones of its filter
+    pub fn categories(&self) -> Vec<&str> {
+        std::iter::once(self.category.as_str())
+            .filter(|category| !category.is_empty())
+            .chain(self.filter.categories.iter().map(String::as_str))
+            .collect()
+    }
+
+    /// Returns true if a transfer counts against the budget. The currency of
+    /// the destination wallet is not checked here.
+    pub fn matches(&self, transfer: &Transfer) -> bool {
+        let categories = self.categories();
+        let in_category = categories.is_empty()
+            || transfer.category.as_deref().is_some_and(|category| {
+                categories
+                    .iter()
+                    .any(|ancestor| is_within_category(category, ancestor))
+            });
+        in_category
+            && (self.filter.from_wallets.is_empty()
+                || self.filter.from_wallets.contains(&transfer.from_wallet))
+            && (self.filter.to_wallets.is_empty()
+                || self.filter.to_wallets.contains(&transfer.to_wallet))
+            && self
+                .filter
+                .tag_match
+                .matches(&transfer.tags, &self.filter.tags)
+    }
+
+    pub fn with_alert_thresholds(mut self, thresholds: Vec<u32>) -> Self {
+        self.alert_thresholds = thresholds;
+        self
+    }
+
+    pub fn with_rollover(mut self, rollover: RolloverPolicy, since: Option<NaiveDate>) -> Self {
+        self.rollover = rollover;
+        self.rollover_since = since;
+        self
+    }
+
     /// Get the current period for this budget.
     pub fn current_period(&self, now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
         self.period_type.current_period(now)
     }
+
+    /// Start of the first period whose leftover carries over.
+    pub fn rollover_start(&self) -> DateTime<Utc> {
+        let since = self
+            .rollover_since
+            .map(start_of_day)
+            .unwrap_or(self.created_at);
+        self.current_period(since).0
+    }
+
+    /// Start of the first period the budget covers: the one it was created in,
+    /// or the earlier one its rollover counts from.
+    pub fn first_period_start(&self) -> DateTime<Utc> {
+        self.current_period(self.created_at)
+            .0
+            .min(self.rollover_start())
+    }
+}
+
+/// Thresholds (percentages of `limit`) that `spent` has reached. With nothing
+/// to spend, any spending reaches them all.
+pub fn reached_thresholds(thresholds: &[u32], limit: Cents, spent: Cents) -> Vec<u32> {
+    if spent <= 0 {
+        return Vec::new();
+    }
+    thresholds
+        .iter()
+        .copied()
+        .filter(|&threshold| limit <= 0 || spent as i128 * 100 >= limit as i128 * threshold as i128)
+        .collect()
+}
+
+/// A budget reaching one of its alert thresholds in a period. Each threshold
+/// raises one event per period.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetEvent {
+    pub budget_id: BudgetId,
+    pub budget: String,
+    /// Percentage of the limit reached
+    pub threshold: u32,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    /// Limit of the period, with what rolled over into it
+    pub limit: Cents,
+    /// Spent in the period when the threshold was reached
+    pub spent: Cents,
+    /// Currency of the amounts; `None` when the budget counts any currency
+    pub currency: Option<String>,
+    /// Transfer that pushed the budget past the threshold
+    pub transfer_id: Option<TransferId>,
+    pub fired_at: DateTime<Utc>,
+    /// Why the event could not be handed to the configured command or file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delivery_error: Option<String>,
+}
+
+impl BudgetEvent {
+    /// Share of the limit spent, in percent
+    pub fn percentage(&self) -> f64 {
+        if self.limit != 0 {
+            (self.spent as f64) / (self.limit as f64) * 100.0
+        } else {
+            100.0
+        }
+    }
+
+    pub fn is_exceeded(&self) -> bool {
+        self.spent > self.limit
+    }
+}
+
+/// One past period of a budget with what was carried into and out of it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetCarry {
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub limit: Cents,
+    /// Carried in from the period before (negative for overspending)
+    pub carried_in: Cents,
+    pub spent: Cents,
+    /// Carried on into the next period
+    pub carried_out: Cents,
+}
+
+impl BudgetCarry {
+    /// Limit of the period with what was carried in
+    pub fn effective_limit(&self) -> Cents {
+        self.limit + self.carried_in
+    }
+}
+
+/// Walk a budget's past periods, given as `(start, end, spent)` oldest first,
+/// carrying what is left of each into the next one under the budget's policy.
+pub fn carry_over(
+    budget: &Budget,
+    periods: &[(DateTime<Utc>, DateTime<Utc>, Cents)],
+) -> Vec<BudgetCarry> {
+    let mut carried_in = 0;
+    periods
+        .iter()
+        .map(|&(period_start, period_end, spent)| {
+            let left = budget.amount_cents + carried_in - spent;
+            let carry = BudgetCarry {
+                period_start,
+                period_end,
+                limit: budget.amount_cents,
+                carried_in,
+                spent,
+                carried_out: budget.rollover.carry(left),
+            };
+            carried_in = carry.carried_out;
+            carry
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -158,6 +433,13 @@ mod tests {
             let parsed: PeriodType = s.parse().unwrap();
             assert_eq!(pt, parsed);
         }
+        for policy in [
+            RolloverPolicy::None,
+            RolloverPolicy::CarryPositive,
+            RolloverPolicy::CarryBoth,
+        ] {
+            assert_eq!(policy.as_str().parse::<RolloverPolicy>(), Ok(policy));
+        }
     }
 
     #[test]
@@ -172,6 +454,129 @@ mod tests {
         assert_eq!(end.format("%Y-%m-%d").to_string(), "2024-02-01");
     }
 
+    #[test]
+    fn test_budget_filter_matches() {
+        let card = Uuid::new_v4();
+        let expense = Uuid::new_v4();
+        let budget = Budget::new("fun".into(), "dining".into(), PeriodType::Monthly, 20000)
+            .with_filter(BudgetFilter {
+                categories: vec!["games".into()],
+                from_wallets: vec![card],
+                ..BudgetFilter::default()
+            });
+        assert_eq!(budget.categories(), vec!["dining", "games"]);
+
+        let transfer = |category: &str, from: WalletId| {
+            Transfer::new(from, expense, 1000, Utc::now()).with_category(category)
+        };
+        assert!(budget.matches(&transfer("dining:pizza", card)));
+        assert!(budget.matches(&transfer("games", card)));
+        assert!(!budget.matches(&transfer("concerts", card)));
+        assert!(!budget.matches(&transfer("games", Uuid::new_v4())));
+
+        // Without categories only the wallets and tags count
+        let card_budget = Budget::new("card".into(), String::new(), PeriodType::Monthly, 50000)
+            .with_filter(BudgetFilter {
+                from_wallets: vec![card],
+                tags: vec!["trip".into()],
+                ..BudgetFilter::default()
+            });
+        assert!(card_budget.categories().is_empty());
+        assert!(card_budget.matches(&transfer("concerts", card).with_tags(vec!["trip".into()])));
+        assert!(!card_budget.matches(&transfer("concerts", card)));
+    }
+
+    #[test]
+    fn test_reached_thresholds() {
+        let thresholds = [80, 100];
+        assert!(reached_thresholds(&thresholds, 40000, 31999).is_empty());
+        assert_eq!(reached_thresholds(&thresholds, 40000, 32000), vec![80]);
+        assert_eq!(reached_thresholds(&thresholds, 40000, 40000), vec![80, 100]);
+        assert_eq!(reached_thresholds(&thresholds, 0, 1), vec![80, 100]);
+        assert!(reached_thresholds(&thresholds, 0, 0).is_empty());
+    }
+
+    #[test]
+    fn test_periods_between() {
+        let from = DateTime::parse_from_rfc3339("2024-01-15T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let to = DateTime::parse_from_rfc3339("2024-03-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let starts: Vec<String> = PeriodType::Monthly
+            .periods_between(from, to)
+            .iter()
+            .map(|(start, _)| start.format("%Y-%m-%d").to_string())
+            .collect();
+        assert_eq!(starts, vec!["2024-01-01", "2024-02-01"]);
+    }
+
+    #[test]
+    fn test_periods_between_from_leap_day() {
+        let from = DateTime::parse_from_rfc3339("2024-02-29T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let to = DateTime::parse_from_rfc3339("2025-12-31T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let periods: Vec<(String, String)> = PeriodType::Yearly
+            .periods_between(from, to)
+            .iter()
+            .map(|(start, end)| {
+                (
+                    start.format("%Y-%m-%d").to_string(),
+                    end.format("%Y-%m-%d").to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            periods,
+            vec![
+                ("2024-01-01".to_string(), "2025-01-01".to_string()),
+                ("2025-01-01".to_string(), "2026-01-01".to_string()),
+            ]
+        );
+
+        let budget = Budget::new("roll".into(), "food".into(), PeriodType::Yearly, 50000)
+            .with_rollover(
+                RolloverPolicy::CarryPositive,
+                NaiveDate::from_ymd_opt(2024, 2, 29),
+            );
+        assert_eq!(
+            budget.rollover_start().format("%Y-%m-%d").to_string(),
+            "2024-01-01"
+        );
+    }
+
+    #[test]
+    fn test_carry_over_policies() {
+        let periods: Vec<(DateTime<Utc>, DateTime<Utc>, Cents)> = [30000, 55000, 10000]
+            .iter()
+            .map(|&spent| (Utc::now(), Utc::now(), spent))
+            .collect();
+        let carried = |policy: RolloverPolicy| -> Vec<Cents> {
+            let budget = Budget::new("food".into(), "food".into(), PeriodType::Monthly, 40000)
+                .with_rollover(policy, None);
+            carry_over(&budget, &periods)
+                .iter()
+                .map(|carry| carry.carried_out)
+                .collect()
+        };
+
+        assert_eq!(carried(RolloverPolicy::None), vec![0, 0, 0]);
+        // 100 left, then 50 overspent (forgiven), then 300 left
+        assert_eq!(
+            carried(RolloverPolicy::CarryPositive),
+            vec![10000, 0, 30000]
+        );
+        // 100 left, 50 overspent, then 250 left after making up for it
+        assert_eq!(
+            carried(RolloverPolicy::CarryBoth),
+            vec![10000, -5000, 25000]
+        );
+    }
+
     #[test]
     fn test_yearly_period() {
         let date = DateTime::parse_from_rfc3339("2024-06-15T12:00:00Z")
@@ -0,0 +1,204 @@
+use serde::{Deserialize, Serialize};
+
+use super::Cents;
+
+/// Separates the levels of a hierarchical category, e.g. "food:groceries".
+pub const CATEGORY_SEPARATOR: char = ':';
+
+/// Clean up a category as typed: whitespace around each level is dropped.
+/// Empty levels ("food::groceries", ":food") are rejected.
+pub fn normalize_category(category: &str) -> Result<String, String> {
+    let levels: Vec<&str> = category.split(CATEGORY_SEPARATOR).map(str::trim).collect();
+    if levels.iter().any(|level| level.is_empty()) {
+        return Err(format!("'{}' has an empty level", category));
+    }
+    Ok(levels.join(&CATEGORY_SEPARATOR.to_string()))
+}
+
+/// Number of levels in a category ("food:groceries" has 2).
+pub fn category_depth(category: &str) -> usize {
+    category.split(CATEGORY_SEPARATOR).count()
+}
+
+/// The ancestor of a category at the given depth, or the category itself if it
+/// is not that deep. `category_at_depth("food:groceries:organic", 1)` is "food".
+pub fn category_at_depth(category: &str, depth: usize) -> &str {
+    match category
+        .match_indices(CATEGORY_SEPARATOR)
+        .nth(depth.max(1) - 1)
+    {
+        Some((index, _)) => &category[..index],
+        None => category,
+    }
+}
+
+/// The parent of a category, if it has one.
+pub fn category_parent(category: &str) -> Option<&str> {
+    category
+        .rfind(CATEGORY_SEPARATOR)
+        .map(|index| &category[..index])
+}
+
+/// The last level of a category ("groceries" for "food:groceries").
+pub fn category_leaf(category: &str) -> &str {
+    match category.rfind(CATEGORY_SEPARATOR) {
+        Some(index) => &category[index + 1..],
+        None => category,
+    }
+}
+
+/// Returns true if `category` is `ancestor` or one of its descendants.
+pub fn is_within_category(category: &str, ancestor: &str) -> bool {
+    category == ancestor
+        || (category.len() > ancestor.len()
+            && category.starts_with(ancestor)
+            && category[ancestor.len()..].starts_with(CATEGORY_SEPARATOR))
+}
+
+/// A category with the totals of everything filed under it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CategoryNode {
+    /// Full category path
+    pub category: String,
+    /// Total of the category and all its descendants
+    pub total: Cents,
+    /// Number of transfers in the category and all its descendants
+    pub count: i64,
+    /// Sub-categories, largest total first
+    pub children: Vec<CategoryNode>,
+}
+
+impl CategoryNode {
+    /// Depth of the node (1 for a top-level category).
+    pub fn depth(&self) -> usize {
+        category_depth(&self.category)
+    }
+
+    /// The node followed by all its descendants, depth first, up to `max_depth`.
+    pub fn flatten(&self, max_depth: Option<usize>) -> Vec<&CategoryNode> {
+        let mut nodes = vec![self];
+        if max_depth.is_none_or(|max| self.depth() < max) {
+            for child in &self.children {
+                nodes.extend(child.flatten(max_depth));
+            }
+        }
+        nodes
+    }
+}
+
+/// Build the category tree from per-category (category, total, count) entries.
+/// Intermediate levels without transfers of their own are created as needed;
+/// every node carries the totals of its whole subtree.
+pub fn build_category_tree<'a>(
+    entries: impl IntoIterator<Item = (&'a str, Cents, i64)>,
+) -> Vec<CategoryNode> {
+    let mut roots: Vec<CategoryNode> = Vec::new();
+    for (category, total, count) in entries {
+        let mut level = &mut roots;
+        let mut end = 0;
+        loop {
+            end = category[end..]
+                .find(CATEGORY_SEPARATOR)
+                .map(|i| end + i)
+                .unwrap_or(category.len());
+            let path = &category[..end];
+            let position = match level.iter().position(|node| node.category == path) {
+                Some(position) => position,
+                None => {
+                    level.push(CategoryNode {
+                        category: path.to_string(),
+                        total: 0,
+                        count: 0,
+                        children: Vec::new(),
+                    });
+                    level.len() - 1
+                }
+            };
+            let node = &mut level[position];
+            node.total += total;
+            node.count += count;
+            if end == category.len() {
+                break;
+            }
+            end += 1;
+            level = &mut node.children;
+        }
+    }
+    sort_nodes(&mut roots);
+    roots
+}
+
+fn sort_nodes(nodes: &mut [CategoryNode]) {
+    nodes.sort_by(|a, b| b.total.cmp(&a.total).then(a.category.cmp(&b.category)));
+    for node in nodes {
+        sort_nodes(&mut node.children);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_category() {
+        assert_eq!(
+            normalize_category(" food : groceries ").unwrap(),
+            "food:groceries"
+        );
+        assert_eq!(normalize_category("rent").unwrap(), "rent");
+        assert!(normalize_category("food::groceries").is_err());
+        assert!(normalize_category(":food").is_err());
+        assert!(normalize_category("").is_err());
+    }
+
+    #[test]
+    fn test_category_levels() {
+        let category = "food:groceries:organic";
+        assert_eq!(category_depth(category), 3);
+        assert_eq!(category_at_depth(category, 1), "food");
+        assert_eq!(category_at_depth(category, 2), "food:groceries");
+        assert_eq!(category_at_depth(category, 5), category);
+        assert_eq!(category_parent(category), Some("food:groceries"));
+        assert_eq!(category_parent("food"), None);
+        assert_eq!(category_leaf(category), "organic");
+    }
+
+    #[test]
+    fn test_is_within_category() {
+        assert!(is_within_category("food", "food"));
+        assert!(is_within_category("food:groceries", "food"));
+        assert!(!is_within_category("foodstuff", "food"));
+        assert!(!is_within_category("food", "food:groceries"));
+    }
+
+    #[test]
+    fn test_build_category_tree() {
+        let tree = build_category_tree([
+            ("food:groceries", 3000, 2),
+            ("food:restaurants", 4500, 1),
+            ("food", 500, 1),
+            ("rent", 100000, 1),
+            ("food:groceries:organic", 1000, 1),
+        ]);
+
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree[0].category, "rent");
+        let food = &tree[1];
+        assert_eq!(food.total, 9000);
+        assert_eq!(food.count, 5);
+        assert_eq!(food.children[0].category, "food:restaurants");
+        assert_eq!(food.children[1].total, 4000);
+        assert_eq!(
+            food.children[1].children[0].category,
+            "food:groceries:organic"
+        );
+
+        let names: Vec<&str> = food
+            .flatten(Some(2))
+            .iter()
+            .map(|node| node.category.as_str())
+            .collect();
+        assert_eq!(names, vec!["food", "food:restaurants", "food:groceries"]);
+        assert_eq!(food.flatten(None).len(), 4);
+    }
+}
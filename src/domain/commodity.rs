@@ -0,0 +1,468 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{Cents, TransferId, WalletId};
+
+pub type CommodityPriceId = Uuid;
+
+/// A lot is identified by the buy transfer that created it.
+pub type LotId = TransferId;
+
+pub type LotDisposalId = Uuid;
+
+/// A dated price: one unit of `commodity` is worth `price` units of `currency`.
+/// A price applies from its effective date until a newer price for the commodity.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommodityPrice {
+    pub id: CommodityPriceId,
+    pub commodity: String,
+    pub currency: String,
+    pub price: f64,
+    pub effective_date: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl CommodityPrice {
+    pub fn new(
+        commodity: String,
+        currency: String,
+        price: f64,
+        effective_date: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            commodity,
+            currency,
+            price,
+            effective_date,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Returns true if the price can be used for valuations (finite and positive).
+    pub fn is_valid_price(price: f64) -> bool {
+        price.is_finite() && price > 0.0
+    }
+}
+
+/// Units held in a commodity wallet together with what they cost.
+/// Sales take units out at the average cost per unit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Position {
+    /// Units held, in the commodity's minor units
+    pub quantity: i64,
+    /// Total cost of the units held, in the wallet currency's minor units
+    pub cost_basis: Cents,
+}
+
+impl Position {
+    /// Add units bought for `cost`.
+    pub fn buy(&mut self, quantity: i64, cost: Cents) {
+        self.quantity += quantity;
+        self.cost_basis += cost;
+    }
+
+    /// Remove units and return the cost they carried (rounded half away from zero).
+    /// Selling every unit releases the whole remaining cost basis.
+    pub fn sell(&mut self, quantity: i64) -> Cents {
+        if self.quantity <= 0 {
+            self.quantity -= quantity;
+            return 0;
+        }
+        let released = if quantity >= self.quantity {
+            self.cost_basis
+        } else {
+            (self.cost_basis as f64 * quantity as f64 / self.quantity as f64).round() as Cents
+        };
+        self.quantity -= quantity;
+        self.cost_basis -= released;
+        released
+    }
+}
+
+/// How a sale picks the lots it consumes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LotMethod {
+    /// Oldest lots first
+    #[default]
+    Fifo,
+    /// Newest lots first
+    Lifo,
+    /// Units leave oldest-first, each carrying the average cost of all open lots
+    Average,
+    /// The seller names the lots and the units taken from each
+    Specific,
+}
+
+impl LotMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LotMethod::Fifo => "fifo",
+            LotMethod::Lifo => "lifo",
+            LotMethod::Average => "average",
+            LotMethod::Specific => "specific",
+        }
+    }
+}
+
+impl std::fmt::Display for LotMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for LotMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "fifo" => Ok(LotMethod::Fifo),
+            "lifo" => Ok(LotMethod::Lifo),
+            "average" | "avg" => Ok(LotMethod::Average),
+            "specific" => Ok(LotMethod::Specific),
+            _ => Err(format!("Invalid lot method: {}", s)),
+        }
+    }
+}
+
+/// Units bought in one trade, and how many of them are still held.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lot {
+    pub id: LotId,
+    pub wallet_id: WalletId,
+    pub acquired_at: DateTime<Utc>,
+    /// Units bought, in the commodity's minor units
+    pub quantity: i64,
+    /// What the units cost, in the wallet currency's minor units
+    pub cost_cents: Cents,
+    pub remaining_quantity: i64,
+    pub remaining_cost: Cents,
+}
+
+impl Lot {
+    pub fn new(
+        id: LotId,
+        wallet_id: WalletId,
+        acquired_at: DateTime<Utc>,
+        quantity: i64,
+        cost_cents: Cents,
+    ) -> Self {
+        Self {
+            id,
+            wallet_id,
+            acquired_at,
+            quantity,
+            cost_cents,
+            remaining_quantity: quantity,
+            remaining_cost: cost_cents,
+        }
+    }
+
+    /// Cost carried by `quantity` of the remaining units (the rest of the cost for the last units).
+    pub fn cost_of(&self, quantity: i64) -> Cents {
+        if quantity >= self.remaining_quantity {
+            self.remaining_cost
+        } else {
+            share(self.remaining_cost, quantity, self.remaining_quantity)
+        }
+    }
+
+    /// Take units out of the lot.
+    pub fn dispose(&mut self, quantity: i64, cost: Cents) {
+        self.remaining_quantity -= quantity;
+        self.remaining_cost -= cost;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.remaining_quantity > 0
+    }
+}
+
+/// Units a seller takes from a named lot (specific identification).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LotPick {
+    pub lot_id: LotId,
+    pub quantity: i64,
+}
+
+/// The part of a sale that consumed one lot, with its share of cost and proceeds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LotAllocation {
+    pub lot_id: LotId,
+    pub quantity: i64,
+    pub cost_cents: Cents,
+    pub proceeds_cents: Cents,
+}
+
+/// A stored allocation: units of a lot consumed by a sell transfer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LotDisposal {
+    pub id: LotDisposalId,
+    pub sell_transfer_id: TransferId,
+    pub wallet_id: WalletId,
+    pub lot_id: LotId,
+    pub acquired_at: DateTime<Utc>,
+    pub disposed_at: DateTime<Utc>,
+    pub quantity: i64,
+    pub cost_cents: Cents,
+    pub proceeds_cents: Cents,
+    pub method: LotMethod,
+}
+
+impl LotDisposal {
+    /// Realized gain (negative for a loss)
+    pub fn gain(&self) -> Cents {
+        self.proceeds_cents - self.cost_cents
+    }
+}
+
+/// Decide which open lots a sale of `quantity` units consumes and split its cost
+/// and proceeds between them. `lots` must be the open lots in acquisition order;
+/// `picks` are only used (and required) by `LotMethod::Specific`.
+pub fn allocate_sale(
+    lots: &[Lot],
+    quantity: i64,
+    proceeds: Cents,
+    method: LotMethod,
+    picks: &[LotPick],
+) -> Result<Vec<LotAllocation>, String> {
+    if quantity <= 0 {
+        return Err("quantity must be positive".to_string());
+    }
+    let available: i64 = lots.iter().map(|lot| lot.remaining_quantity).sum();
+    if quantity > available {
+        return Err(format!(
+            "only {} units in open lots, {} requested",
+            available, quantity
+        ));
+    }
+
+    // Units taken from each lot, as (index into `lots`, quantity)
+    let pieces: Vec<(usize, i64)> = match method {
+        LotMethod::Fifo | LotMethod::Average => take_in_order(lots, 0..lots.len(), quantity),
+        LotMethod::Lifo => take_in_order(lots, (0..lots.len()).rev(), quantity),
+        LotMethod::Specific => pick_lots(lots, quantity, picks)?,
+    };
+
+    let costs: Vec<Cents> = match method {
+        LotMethod::Average => {
+            let mut position = Position {
+                quantity: available,
+                cost_basis: lots.iter().map(|lot| lot.remaining_cost).sum(),
+            };
+            let total = position.sell(quantity);
+            distribute(total, &pieces, quantity)
+        }
+        _ => pieces.iter().map(|&(i, q)| lots[i].cost_of(q)).collect(),
+    };
+    let proceeds = distribute(proceeds, &pieces, quantity);
+
+    Ok(pieces
+        .iter()
+        .zip(costs)
+        .zip(proceeds)
+        .map(|((&(i, q), cost_cents), proceeds_cents)| LotAllocation {
+            lot_id: lots[i].id,
+            quantity: q,
+            cost_cents,
+            proceeds_cents,
+        })
+        .collect())
+}
+
+fn take_in_order(
+    lots: &[Lot],
+    order: impl Iterator<Item = usize>,
+    quantity: i64,
+) -> Vec<(usize, i64)> {
+    let mut left = quantity;
+    let mut pieces = Vec::new();
+    for i in order {
+        if left == 0 {
+            break;
+        }
+        let take = left.min(lots[i].remaining_quantity);
+        if take > 0 {
+            pieces.push((i, take));
+            left -= take;
+        }
+    }
+    pieces
+}
+
+fn pick_lots(lots: &[Lot], quantity: i64, picks: &[LotPick]) -> Result<Vec<(usize, i64)>, String> {
+    if picks.is_empty() {
+        return Err("specific identification needs the lots to sell from".to_string());
+    }
+    let mut pieces: Vec<(usize, i64)> = Vec::with_capacity(picks.len());
+    for pick in picks {
+        let index = lots
+            .iter()
+            .position(|lot| lot.id == pick.lot_id)
+            .ok_or_else(|| format!("lot {} is not open in this wallet", pick.lot_id))?;
+        if pieces.iter().any(|&(i, _)| i == index) {
+            return Err(format!("lot {} is picked twice", pick.lot_id));
+        }
+        if pick.quantity <= 0 || pick.quantity > lots[index].remaining_quantity {
+            return Err(format!(
+                "lot {} has {} units left, {} picked",
+                pick.lot_id, lots[index].remaining_quantity, pick.quantity
+            ));
+        }
+        pieces.push((index, pick.quantity));
+    }
+    let picked: i64 = pieces.iter().map(|&(_, q)| q).sum();
+    if picked != quantity {
+        return Err(format!(
+            "picked lots add up to {} units, the sale is for {}",
+            picked, quantity
+        ));
+    }
+    Ok(pieces)
+}
+
+/// Split `total` between pieces in proportion to their quantity; the last piece
+/// takes the rounding remainder so the parts always add up.
+fn distribute(total: Cents, pieces: &[(usize, i64)], quantity: i64) -> Vec<Cents> {
+    let mut left = total;
+    pieces
+        .iter()
+        .enumerate()
+        .map(|(n, &(_, q))| {
+            let part = if n + 1 == pieces.len() {
+                left
+            } else {
+                share(total, q, quantity)
+            };
+            left -= part;
+            part
+        })
+        .collect()
+}
+
+/// `total * part / whole`, rounded half away from zero.
+fn share(total: Cents, part: i64, whole: i64) -> Cents {
+    (total as f64 * part as f64 / whole as f64).round() as Cents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position_average_cost() {
+        let mut position = Position::default();
+        position.buy(10, 1000);
+        position.buy(10, 2000);
+        assert_eq!(position.cost_basis, 3000);
+
+        // Half the units carry half the cost, whatever each purchase cost
+        assert_eq!(position.sell(10), 1500);
+        assert_eq!(position.quantity, 10);
+        assert_eq!(position.sell(3), 450);
+
+        // The last units take whatever cost is left, so rounding never strands cents
+        assert_eq!(position.sell(7), 1050);
+        assert_eq!(position, Position::default());
+    }
+
+    #[test]
+    fn test_position_rounding() {
+        let mut position = Position::default();
+        position.buy(3, 100);
+        assert_eq!(position.sell(1), 33);
+        assert_eq!(position.sell(2), 67);
+    }
+
+    fn sample_lots() -> Vec<Lot> {
+        let wallet = Uuid::new_v4();
+        vec![
+            Lot::new(Uuid::new_v4(), wallet, Utc::now(), 10, 1000),
+            Lot::new(Uuid::new_v4(), wallet, Utc::now(), 10, 2000),
+        ]
+    }
+
+    #[test]
+    fn test_allocate_fifo_and_lifo() {
+        let lots = sample_lots();
+
+        let fifo = allocate_sale(&lots, 15, 3000, LotMethod::Fifo, &[]).unwrap();
+        assert_eq!(fifo.len(), 2);
+        assert_eq!((fifo[0].lot_id, fifo[0].quantity), (lots[0].id, 10));
+        assert_eq!(fifo[0].cost_cents, 1000);
+        assert_eq!(fifo[1].cost_cents, 1000);
+        assert_eq!(fifo[0].proceeds_cents + fifo[1].proceeds_cents, 3000);
+
+        let lifo = allocate_sale(&lots, 15, 3000, LotMethod::Lifo, &[]).unwrap();
+        assert_eq!((lifo[0].lot_id, lifo[0].quantity), (lots[1].id, 10));
+        assert_eq!(lifo[0].cost_cents, 2000);
+        assert_eq!(lifo[1].cost_cents, 500);
+    }
+
+    #[test]
+    fn test_allocate_average_and_specific() {
+        let lots = sample_lots();
+
+        // 5 units at the 150.00 average, whichever lot they come from
+        let average = allocate_sale(&lots, 5, 1000, LotMethod::Average, &[]).unwrap();
+        assert_eq!(average.len(), 1);
+        assert_eq!(average[0].cost_cents, 750);
+
+        let picks = [
+            LotPick {
+                lot_id: lots[1].id,
+                quantity: 4,
+            },
+            LotPick {
+                lot_id: lots[0].id,
+                quantity: 1,
+            },
+        ];
+        let specific = allocate_sale(&lots, 5, 1000, LotMethod::Specific, &picks).unwrap();
+        assert_eq!(specific[0].cost_cents, 800);
+        assert_eq!(specific[1].cost_cents, 100);
+        assert_eq!(specific[0].proceeds_cents, 800);
+        assert_eq!(specific[1].proceeds_cents, 200);
+    }
+
+    #[test]
+    fn test_allocate_rejects_bad_requests() {
+        let lots = sample_lots();
+        assert!(allocate_sale(&lots, 21, 100, LotMethod::Fifo, &[]).is_err());
+        assert!(allocate_sale(&lots, 5, 100, LotMethod::Specific, &[]).is_err());
+
+        let too_many = [LotPick {
+            lot_id: lots[0].id,
+            quantity: 11,
+        }];
+        assert!(allocate_sale(&lots, 11, 100, LotMethod::Specific, &too_many).is_err());
+        let short = [LotPick {
+            lot_id: lots[0].id,
+            quantity: 3,
+        }];
+        assert!(allocate_sale(&lots, 5, 100, LotMethod::Specific, &short).is_err());
+    }
+
+    #[test]
+    fn test_lot_method_roundtrip() {
+        for method in [
+            LotMethod::Fifo,
+            LotMethod::Lifo,
+            LotMethod::Average,
+            LotMethod::Specific,
+        ] {
+            assert_eq!(method.as_str().parse::<LotMethod>().unwrap(), method);
+        }
+        assert!("hifo".parse::<LotMethod>().is_err());
+    }
+
+    #[test]
+    fn test_valid_prices() {
+        assert!(CommodityPrice::is_valid_price(98.5));
+        assert!(!CommodityPrice::is_valid_price(0.0));
+        assert!(!CommodityPrice::is_valid_price(-1.0));
+        assert!(!CommodityPrice::is_valid_price(f64::NAN));
+    }
+}
@@ -0,0 +1,28 @@
+use chrono::{DateTime, NaiveDate, Utc};
+
+/// First instant after `date`: a balance "as of" a day counts every transfer before it
+pub fn end_of_day(date: NaiveDate) -> DateTime<Utc> {
+    date.succ_opt()
+        .and_then(|day| day.and_hms_opt(0, 0, 0))
+        .map(|day| day.and_utc())
+        .unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+/// First instant of `date` (UTC)
+pub fn start_of_day(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0)
+        .map(|day| day.and_utc())
+        .unwrap_or(DateTime::<Utc>::MIN_UTC)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_day_bounds() {
+        let day = NaiveDate::from_ymd_opt(2024, 12, 31).unwrap();
+        assert_eq!(start_of_day(day).to_rfc3339(), "2024-12-31T00:00:00+00:00");
+        assert_eq!(end_of_day(day).to_rfc3339(), "2025-01-01T00:00:00+00:00");
+    }
+}
@@ -0,0 +1,155 @@
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{Cents, start_of_day};
+
+pub type EnvelopeId = Uuid;
+pub type EnvelopeAssignmentId = Uuid;
+
+/// An envelope of zero-based budgeting. Money is assigned to it from the "to be
+/// assigned" pool of its currency and stays in it, month after month, until it
+/// is spent. Spending is every transfer in its category (sub-categories
+/// included) into a wallet of its currency, counted from its first month.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Envelope {
+    pub id: EnvelopeId,
+    pub name: String,
+    pub category: String,
+    pub currency: String,
+    /// First month the envelope is budgeted (first day of the month)
+    pub start_month: NaiveDate,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Envelope {
+    pub fn new(name: String, category: String, currency: String, start_month: NaiveDate) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name,
+            category,
+            currency,
+            start_month: month_start(start_month),
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Money put into an envelope for a month, or taken out of it when negative.
+/// A move between envelopes is a pair of assignments naming each other; other
+/// assignments come from (or go back to) the "to be assigned" pool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvelopeAssignment {
+    pub id: EnvelopeAssignmentId,
+    pub envelope_id: EnvelopeId,
+    /// First day of the month the money is assigned in
+    pub month: NaiveDate,
+    pub amount_cents: Cents,
+    /// Envelope the money was moved from or to
+    pub counterpart: Option<EnvelopeId>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl EnvelopeAssignment {
+    pub fn new(envelope_id: EnvelopeId, month: NaiveDate, amount_cents: Cents) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            envelope_id,
+            month: month_start(month),
+            amount_cents,
+            counterpart: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn with_counterpart(mut self, counterpart: EnvelopeId) -> Self {
+        self.counterpart = Some(counterpart);
+        self
+    }
+}
+
+/// An envelope in one month.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvelopeMonth {
+    pub envelope: Envelope,
+    pub month: NaiveDate,
+    /// Assigned in the month
+    pub assigned: Cents,
+    /// Spending in the month as a negative amount (refunds are positive)
+    pub activity: Cents,
+    /// What is left at the end of the month, carried into the next one
+    pub available: Cents,
+}
+
+impl EnvelopeMonth {
+    /// Available amount brought over from the months before
+    pub fn carried_over(&self) -> Cents {
+        self.available - self.assigned - self.activity
+    }
+
+    pub fn is_overspent(&self) -> bool {
+        self.available < 0
+    }
+}
+
+/// First day of the month holding `date`
+pub fn month_start(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+/// First day of the month after the one holding `date`
+pub fn next_month_start(date: NaiveDate) -> NaiveDate {
+    let (year, month) = if date.month() == 12 {
+        (date.year() + 1, 1)
+    } else {
+        (date.year(), date.month() + 1)
+    };
+    NaiveDate::from_ymd_opt(year, month, 1).unwrap_or(NaiveDate::MAX)
+}
+
+/// First and last instant (exclusive) of the month holding `date`
+pub fn month_bounds(date: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+    (
+        start_of_day(month_start(date)),
+        start_of_day(next_month_start(date)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_month_bounds() {
+        assert_eq!(month_start(date("2024-02-29")), date("2024-02-01"));
+        assert_eq!(next_month_start(date("2024-12-15")), date("2025-01-01"));
+        let (start, end) = month_bounds(date("2024-02-10"));
+        assert_eq!(start.to_rfc3339(), "2024-02-01T00:00:00+00:00");
+        assert_eq!(end.to_rfc3339(), "2024-03-01T00:00:00+00:00");
+    }
+
+    #[test]
+    fn test_envelope_month_carries_over() {
+        let envelope = Envelope::new(
+            "Groceries".into(),
+            "food".into(),
+            "EUR".into(),
+            date("2024-01-15"),
+        );
+        assert_eq!(envelope.start_month, date("2024-01-01"));
+
+        let month = EnvelopeMonth {
+            envelope,
+            month: date("2024-02-01"),
+            assigned: 30000,
+            activity: -42000,
+            available: -2000,
+        };
+        assert_eq!(month.carried_over(), 10000);
+        assert!(month.is_overspent());
+    }
+}
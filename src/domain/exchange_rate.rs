@@ -0,0 +1,290 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use uuid::Uuid;
+
+use super::{Cents, Currency, RoundingMode};
+
+pub type ExchangeRateId = Uuid;
+
+/// Most significant digits a rate may be entered with.
+const MAX_RATE_DIGITS: usize = 18;
+
+/// Decimals shown for a rate that has no finite decimal form (e.g. an inverted 1.1).
+const RATE_DISPLAY_DECIMALS: u32 = 10;
+
+/// An exchange rate held exactly as a fraction: one unit of one currency is
+/// worth `numerator / denominator` units of another. Rates are entered as
+/// decimals ("1.085") and can be inverted without losing precision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rate {
+    numerator: i128,
+    denominator: i128,
+}
+
+impl Rate {
+    /// Converting a currency into itself.
+    pub const ONE: Rate = Rate {
+        numerator: 1,
+        denominator: 1,
+    };
+
+    /// A rate of `numerator / denominator`; both must be positive.
+    pub fn new(numerator: i128, denominator: i128) -> Option<Self> {
+        if numerator <= 0 || denominator <= 0 {
+            return None;
+        }
+        let divisor = gcd(numerator, denominator);
+        Some(Self {
+            numerator: numerator / divisor,
+            denominator: denominator / divisor,
+        })
+    }
+
+    /// The rate of the opposite direction.
+    pub fn inverse(&self) -> Self {
+        Self {
+            numerator: self.denominator,
+            denominator: self.numerator,
+        }
+    }
+
+    /// The rate between minor units of `from` and `to`, given a rate between their major units.
+    /// Example: EUR -> JPY at 160 gives 8/5 (1 cent = 1.6 yen)
+    pub fn between_minor_units(&self, from: &Currency, to: &Currency) -> Option<Self> {
+        let shift = |exponent: u32| 10_i128.checked_pow(exponent);
+        let (numerator, denominator) = if to.minor_units >= from.minor_units {
+            let factor = shift(to.minor_units - from.minor_units)?;
+            (self.numerator.checked_mul(factor)?, self.denominator)
+        } else {
+            let factor = shift(from.minor_units - to.minor_units)?;
+            (self.numerator, self.denominator.checked_mul(factor)?)
+        };
+        Self::new(numerator, denominator)
+    }
+
+    /// Convert an amount at this rate, rounding the result as `rounding` says.
+    /// Returns None when the result overflows, or when `RoundingMode::Reject`
+    /// meets an amount that does not convert exactly.
+    /// Example: Rate 1.085 applied to 10000 -> 10850
+    pub fn apply(&self, amount: Cents, rounding: RoundingMode) -> Option<Cents> {
+        let product = (amount as i128).checked_mul(self.numerator)?;
+        let converted = rounding.divide(product, self.denominator)?;
+        Cents::try_from(converted).ok()
+    }
+
+    /// The rate as a float, for scaling prices that are themselves floats.
+    pub fn to_f64(&self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+}
+
+impl fmt::Display for Rate {
+    /// Exact decimals when the rate has a finite decimal form, otherwise rounded
+    /// to `RATE_DISPLAY_DECIMALS` places; trailing zeros are dropped.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let exact = (0..=MAX_RATE_DIGITS as u32)
+            .find(|&decimals| (10_i128.pow(decimals) % self.denominator) == 0);
+        let decimals = exact.unwrap_or(RATE_DISPLAY_DECIMALS);
+        let scale = 10_i128.pow(decimals);
+        let Some(scaled) = self
+            .numerator
+            .checked_mul(scale)
+            .and_then(|n| RoundingMode::HalfUp.divide(n, self.denominator))
+        else {
+            return f.pad(&self.to_f64().to_string());
+        };
+        let (units, fraction) = (scaled / scale, scaled % scale);
+        if fraction == 0 {
+            return f.pad(&units.to_string());
+        }
+        let fraction = format!("{:0width$}", fraction, width = decimals as usize);
+        f.pad(&format!("{}.{}", units, fraction.trim_end_matches('0')))
+    }
+}
+
+impl std::str::FromStr for Rate {
+    type Err = ParseRateError;
+
+    /// Parse a positive decimal rate such as "1.085" or "160".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (units, decimals) = s.split_once('.').unwrap_or((s, ""));
+        let is_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+        if (units.is_empty() && decimals.is_empty()) || !is_digits(units) || !is_digits(decimals) {
+            return Err(ParseRateError::InvalidFormat);
+        }
+        let decimals = decimals.trim_end_matches('0');
+        let digits = format!("{}{}", units, decimals);
+        let digits = digits.trim_start_matches('0');
+        if digits.len() > MAX_RATE_DIGITS {
+            return Err(ParseRateError::TooPrecise);
+        }
+        let numerator: i128 = if digits.is_empty() {
+            0
+        } else {
+            digits.parse().map_err(|_| ParseRateError::InvalidFormat)?
+        };
+        Self::new(numerator, 10_i128.pow(decimals.len() as u32)).ok_or(ParseRateError::NotPositive)
+    }
+}
+
+impl Serialize for Rate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Rate {
+    /// Reads the decimal string written by `Serialize`, or a plain number as
+    /// found in snapshots written before rates were exact.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Stored {
+            Text(String),
+            Number(f64),
+        }
+        let text = match Stored::deserialize(deserializer)? {
+            Stored::Text(text) => text,
+            Stored::Number(number) => number.to_string(),
+        };
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseRateError {
+    InvalidFormat,
+    NotPositive,
+    TooPrecise,
+}
+
+impl fmt::Display for ParseRateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseRateError::InvalidFormat => write!(f, "invalid rate format"),
+            ParseRateError::NotPositive => write!(f, "rate must be positive"),
+            ParseRateError::TooPrecise => {
+                write!(f, "too many digits (at most {} allowed)", MAX_RATE_DIGITS)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseRateError {}
+
+fn gcd(mut a: i128, mut b: i128) -> i128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// A dated exchange rate: one unit of `base` is worth `rate` units of `quote`.
+/// A rate applies from its effective date until a newer rate for the same pair.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExchangeRate {
+    pub id: ExchangeRateId,
+    pub base: String,
+    pub quote: String,
+    pub rate: Rate,
+    pub effective_date: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ExchangeRate {
+    pub fn new(base: String, quote: String, rate: Rate, effective_date: DateTime<Utc>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            base,
+            quote,
+            rate,
+            effective_date,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Convert an amount between minor units at `rate`, rounding as `rounding` says.
+/// Example: convert_amount(10000, "1.085" as a rate, RoundingMode::HalfUp) -> Some(10850)
+pub fn convert_amount(amount_cents: Cents, rate: &Rate, rounding: RoundingMode) -> Option<Cents> {
+    rate.apply(amount_cents, rounding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rate(s: &str) -> Rate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_convert_amount() {
+        let half_up = RoundingMode::HalfUp;
+        assert_eq!(convert_amount(10000, &rate("1.085"), half_up), Some(10850));
+        assert_eq!(convert_amount(10000, &rate("1"), half_up), Some(10000));
+        assert_eq!(convert_amount(333, &rate("0.5"), half_up), Some(167)); // 166.5 rounds up
+        assert_eq!(convert_amount(-333, &rate("0.5"), half_up), Some(-167));
+        assert_eq!(convert_amount(0, &rate("1.2"), half_up), Some(0));
+
+        assert_eq!(
+            convert_amount(333, &rate("0.5"), RoundingMode::HalfEven),
+            Some(166)
+        );
+        assert_eq!(
+            convert_amount(333, &rate("0.5"), RoundingMode::Truncate),
+            Some(166)
+        );
+        assert_eq!(
+            convert_amount(333, &rate("0.5"), RoundingMode::Reject),
+            None
+        );
+        assert_eq!(
+            convert_amount(i64::MAX, &rate("2"), half_up),
+            None,
+            "overflow"
+        );
+    }
+
+    #[test]
+    fn test_rates_are_exact() {
+        assert_eq!(rate("1.10"), rate("1.1"));
+        assert_eq!(rate("1.1").to_string(), "1.1");
+        assert_eq!(rate("160").to_string(), "160");
+        assert_eq!(rate("0.00625").to_string(), "0.00625");
+
+        // Inverting a rate and back loses nothing
+        let inverse = rate("1.1").inverse();
+        assert_eq!(inverse.to_string(), "0.9090909091");
+        assert_eq!(inverse.inverse(), rate("1.1"));
+        assert_eq!(inverse.apply(11000, RoundingMode::Reject), Some(10000));
+
+        let eur = Currency::iso("EUR");
+        let jpy = Currency::iso("JPY");
+        let per_cent = rate("160").between_minor_units(&eur, &jpy).unwrap();
+        assert_eq!(per_cent, Rate::new(8, 5).unwrap());
+        assert_eq!(per_cent.apply(1050, RoundingMode::Reject), Some(1680));
+    }
+
+    #[test]
+    fn test_parse_rates() {
+        assert_eq!(".5".parse::<Rate>(), Ok(rate("0.5")));
+        assert_eq!("0".parse::<Rate>(), Err(ParseRateError::NotPositive));
+        assert_eq!("-1.1".parse::<Rate>(), Err(ParseRateError::InvalidFormat));
+        assert_eq!("1e5".parse::<Rate>(), Err(ParseRateError::InvalidFormat));
+        assert_eq!(
+            "1.0000000000000000001".parse::<Rate>(),
+            Err(ParseRateError::TooPrecise)
+        );
+
+        let json = serde_json::to_string(&rate("1.085")).unwrap();
+        assert_eq!(json, "\"1.085\"");
+        assert_eq!(serde_json::from_str::<Rate>(&json).unwrap(), rate("1.085"));
+        assert_eq!(
+            serde_json::from_str::<Rate>("1.085").unwrap(),
+            rate("1.085")
+        );
+    }
+}
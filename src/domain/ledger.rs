@@ -1,6 +1,9 @@
 use std::collections::HashMap;
 
-use super::{Cents, Transfer, Wallet, WalletId, WalletType};
+use super::{
+    Cents, Transfer, Wallet, WalletId, WalletTreeEntry, WalletType, count_invalid_wallet_parents,
+    wallet_tree,
+};
 
 /// Compute the balance for a single wallet from a list of transfers.
 /// Balance = sum of incoming transfers - sum of outgoing transfers
@@ -93,6 +96,8 @@ pub struct IntegrityReport {
     pub balance_by_type: HashMap<WalletType, Cents>,
     pub total_balance: Cents,
     pub is_balanced: bool,
+    /// Every wallet with the subtotal of its sub-wallets, in tree order
+    pub wallet_tree: Vec<WalletTreeEntry>,
     pub issues: Vec<IntegrityIssue>,
 }
 
@@ -108,6 +113,18 @@ pub enum IntegrityIssue {
     InvalidWalletReferences(i64),
     InvalidAmounts(i64),
     UnbalancedLedger(Cents),
+    /// Reconciled transfers that changed or disappeared after being reconciled
+    ReconciledTransfersModified(i64),
+    /// Reversals recorded against transfers after they were reconciled
+    ReconciledTransfersReversed(i64),
+    /// Transfers whose values differ from what their amendment history says
+    UnrecordedEdits(i64),
+    /// Wallets under a missing parent, a parent of another type or currency, or their own descendant
+    InvalidWalletHierarchy(i64),
+    /// Transfers recorded or amended inside a locked period without a logged override
+    LockedPeriodViolations(i64),
+    /// Balance assertions the computed balance of their wallet does not match
+    FailedBalanceAssertions(i64),
 }
 
 impl std::fmt::Display for IntegrityIssue {
@@ -123,6 +140,36 @@ impl std::fmt::Display for IntegrityIssue {
             IntegrityIssue::UnbalancedLedger(diff) => {
                 write!(f, "Ledger is unbalanced by {} cents", diff)
             }
+            IntegrityIssue::ReconciledTransfersModified(count) => {
+                write!(
+                    f,
+                    "{} reconciled transfers were edited after reconciling",
+                    count
+                )
+            }
+            IntegrityIssue::ReconciledTransfersReversed(count) => {
+                write!(f, "{} reversals touch already reconciled transfers", count)
+            }
+            IntegrityIssue::UnrecordedEdits(count) => {
+                write!(
+                    f,
+                    "{} transfers were changed without an amendment record",
+                    count
+                )
+            }
+            IntegrityIssue::InvalidWalletHierarchy(count) => {
+                write!(f, "{} wallets have an invalid parent wallet", count)
+            }
+            IntegrityIssue::LockedPeriodViolations(count) => {
+                write!(
+                    f,
+                    "{} transfers were recorded or changed in a locked period without an override",
+                    count
+                )
+            }
+            IntegrityIssue::FailedBalanceAssertions(count) => {
+                write!(f, "{} balance assertions do not hold", count)
+            }
         }
     }
 }
@@ -162,6 +209,10 @@ pub fn build_integrity_report(
     if !is_balanced {
         issues.push(IntegrityIssue::UnbalancedLedger(total_balance));
     }
+    let invalid_parents = count_invalid_wallet_parents(wallets);
+    if invalid_parents > 0 {
+        issues.push(IntegrityIssue::InvalidWalletHierarchy(invalid_parents));
+    }
 
     IntegrityReport {
         wallet_count,
@@ -169,6 +220,7 @@ pub fn build_integrity_report(
         balance_by_type,
         total_balance,
         is_balanced,
+        wallet_tree: wallet_tree(wallets, balances),
         issues,
     }
 }
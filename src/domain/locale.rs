@@ -0,0 +1,548 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::{
+    Cents, Currency, ParseCentsError, RoundingMode, format_minor_units, parse_minor_units,
+};
+
+/// Name of the profile used when no locale is configured.
+pub const DEFAULT_LOCALE: &str = "plain";
+
+/// Where the currency symbol goes when formatting an amount.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolPlacement {
+    /// No symbol; the currency code follows the amount where one is shown
+    None,
+    /// Symbol before the amount ("$12.50")
+    Before,
+    /// Symbol after the amount ("12,50 €")
+    After,
+}
+
+impl SymbolPlacement {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SymbolPlacement::None => "none",
+            SymbolPlacement::Before => "before",
+            SymbolPlacement::After => "after",
+        }
+    }
+}
+
+impl fmt::Display for SymbolPlacement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for SymbolPlacement {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(SymbolPlacement::None),
+            "before" => Ok(SymbolPlacement::Before),
+            "after" => Ok(SymbolPlacement::After),
+            _ => Err(format!("Invalid symbol placement: {}", s)),
+        }
+    }
+}
+
+/// How negative amounts are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NegativeStyle {
+    /// "-12.50"
+    LeadingMinus,
+    /// "12.50-"
+    TrailingMinus,
+    /// "(12.50)"
+    Parentheses,
+}
+
+impl NegativeStyle {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NegativeStyle::LeadingMinus => "leading-minus",
+            NegativeStyle::TrailingMinus => "trailing-minus",
+            NegativeStyle::Parentheses => "parentheses",
+        }
+    }
+}
+
+impl fmt::Display for NegativeStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for NegativeStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "leading-minus" | "minus" => Ok(NegativeStyle::LeadingMinus),
+            "trailing-minus" => Ok(NegativeStyle::TrailingMinus),
+            "parentheses" => Ok(NegativeStyle::Parentheses),
+            _ => Err(format!("Invalid negative style: {}", s)),
+        }
+    }
+}
+
+/// Conventions for reading and writing amounts as text.
+/// Only human-facing text uses a profile; JSON always carries integer minor units.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocaleProfile {
+    pub name: String,
+    pub decimal_separator: char,
+    pub grouping_separator: Option<char>,
+    pub symbol_placement: SymbolPlacement,
+    /// Put a space between the symbol and the digits
+    pub symbol_space: bool,
+    pub negative_style: NegativeStyle,
+}
+
+impl Default for LocaleProfile {
+    fn default() -> Self {
+        Self::new(DEFAULT_LOCALE.to_string(), '.')
+    }
+}
+
+impl LocaleProfile {
+    /// A profile with the given decimal separator, no grouping, no symbol and a leading minus.
+    pub fn new(name: String, decimal_separator: char) -> Self {
+        Self {
+            name,
+            decimal_separator,
+            grouping_separator: None,
+            symbol_placement: SymbolPlacement::None,
+            symbol_space: false,
+            negative_style: NegativeStyle::LeadingMinus,
+        }
+    }
+
+    pub fn with_grouping(mut self, separator: Option<char>) -> Self {
+        self.grouping_separator = separator;
+        self
+    }
+
+    pub fn with_symbol(mut self, placement: SymbolPlacement, space: bool) -> Self {
+        self.symbol_placement = placement;
+        self.symbol_space = space;
+        self
+    }
+
+    pub fn with_negative_style(mut self, style: NegativeStyle) -> Self {
+        self.negative_style = style;
+        self
+    }
+
+    /// Profiles shipped with the application.
+    pub fn builtin() -> Vec<LocaleProfile> {
+        vec![
+            LocaleProfile::default(),
+            LocaleProfile::new("en-US".into(), '.')
+                .with_grouping(Some(','))
+                .with_symbol(SymbolPlacement::Before, false),
+            LocaleProfile::new("en-GB".into(), '.')
+                .with_grouping(Some(','))
+                .with_symbol(SymbolPlacement::Before, false),
+            LocaleProfile::new("de-DE".into(), ',')
+                .with_grouping(Some('.'))
+                .with_symbol(SymbolPlacement::After, true),
+            LocaleProfile::new("fr-FR".into(), ',')
+                .with_grouping(Some(' '))
+                .with_symbol(SymbolPlacement::After, true),
+            LocaleProfile::new("nl-NL".into(), ',')
+                .with_grouping(Some('.'))
+                .with_symbol(SymbolPlacement::Before, true),
+            LocaleProfile::new("de-CH".into(), '.')
+                .with_grouping(Some('\''))
+                .with_symbol(SymbolPlacement::Before, true),
+            LocaleProfile::new("accounting".into(), '.')
+                .with_grouping(Some(','))
+                .with_negative_style(NegativeStyle::Parentheses),
+        ]
+    }
+
+    /// Look up a built-in profile by name (case-insensitive).
+    pub fn find_builtin(name: &str) -> Option<LocaleProfile> {
+        Self::builtin()
+            .into_iter()
+            .find(|profile| profile.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Check that amounts written with this profile can be read back unambiguously.
+    pub fn validate(&self) -> Result<(), String> {
+        let reserved = |c: char| c.is_ascii_digit() || matches!(c, '-' | '(' | ')');
+        if reserved(self.decimal_separator) || self.decimal_separator.is_whitespace() {
+            return Err(format!(
+                "'{}' cannot be used as a decimal separator",
+                self.decimal_separator
+            ));
+        }
+        if let Some(grouping) = self.grouping_separator {
+            if reserved(grouping) {
+                return Err(format!(
+                    "'{}' cannot be used as a grouping separator",
+                    grouping
+                ));
+            }
+            if grouping == self.decimal_separator {
+                return Err("Decimal and grouping separators must differ".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Format an amount without a currency symbol.
+    /// Example: de-DE formats 123456 EUR as "1.234,56"
+    pub fn format_amount(&self, amount: Cents, currency: &Currency) -> String {
+        let digits = self.group_digits(amount, currency.minor_units);
+        self.apply_sign(digits, amount < 0)
+    }
+
+    /// Format an amount in two decimals, for totals that are not tied to one currency.
+    pub fn format_cents(&self, amount: Cents) -> String {
+        self.format_amount(amount, &Currency::new("", 2))
+    }
+
+    /// Format an amount with its currency, placing the symbol as the profile says.
+    /// Profiles without a symbol placement append the currency code instead.
+    /// Example: de-DE formats 123456 EUR as "1.234,56 €", plain as "1234.56 EUR"
+    pub fn format_money(&self, amount: Cents, currency: &Currency) -> String {
+        let digits = self.group_digits(amount, currency.minor_units);
+        let symbol = currency_symbol(&currency.code);
+        let space = if self.symbol_space || symbol.chars().count() > 1 {
+            " "
+        } else {
+            ""
+        };
+        let body = match self.symbol_placement {
+            _ if symbol.is_empty() => digits,
+            SymbolPlacement::None => format!("{} {}", digits, currency.code),
+            SymbolPlacement::Before => format!("{}{}{}", symbol, space, digits),
+            SymbolPlacement::After => format!("{}{}{}", digits, space, symbol),
+        };
+        self.apply_sign(body, amount < 0)
+    }
+
+    /// Parse an amount written in this profile's conventions.
+    /// Any negative style is accepted, as is the currency's symbol or code on either side.
+    /// Example: de-DE parses "€ 1.234,56" and "-1.234,56 EUR" for EUR
+    pub fn parse_amount(
+        &self,
+        input: &str,
+        currency: &Currency,
+        rounding: RoundingMode,
+    ) -> Result<Cents, ParseCentsError> {
+        let (negative, body) = strip_sign(input.trim());
+        let body = strip_currency(body, currency);
+        let (negative, body) = if negative {
+            (true, body)
+        } else {
+            strip_sign(body)
+        };
+
+        let (units, decimals) = match body.split_once(self.decimal_separator) {
+            Some((units, decimals)) => (units, Some(decimals)),
+            None => (body, None),
+        };
+        let units = self.ungroup(units)?;
+
+        let canonical = match decimals {
+            Some(decimals) => format!("{}.{}", units, decimals),
+            None => units,
+        };
+        if canonical.starts_with('-') || canonical.starts_with('+') {
+            return Err(ParseCentsError::InvalidFormat);
+        }
+        let amount = parse_minor_units(&canonical, currency.minor_units, rounding)?;
+        Ok(if negative { -amount } else { amount })
+    }
+
+    /// Remove grouping separators, checking that groups have three digits.
+    fn ungroup(&self, units: &str) -> Result<String, ParseCentsError> {
+        let units: String = units.chars().filter(|c| !is_space(*c)).collect();
+        let grouping = match self.grouping_separator {
+            Some(grouping) if !is_space(grouping) => grouping,
+            _ => return Ok(units),
+        };
+        if !units.contains(grouping) {
+            return Ok(units);
+        }
+
+        // "12.50" in a profile grouping with '.' is a typo, not twelve hundred fifty
+        let mut groups = units.split(grouping);
+        let first = groups.next().unwrap_or_default();
+        if first.is_empty() || first.len() > 3 {
+            return Err(ParseCentsError::InvalidFormat);
+        }
+        let mut result = first.to_string();
+        for group in groups {
+            if group.len() != 3 {
+                return Err(ParseCentsError::InvalidFormat);
+            }
+            result.push_str(group);
+        }
+        Ok(result)
+    }
+
+    fn group_digits(&self, amount: Cents, minor_units: u32) -> String {
+        let canonical = format_minor_units(amount, minor_units);
+        let canonical = canonical.trim_start_matches('-');
+        let (units, decimals) = match canonical.split_once('.') {
+            Some((units, decimals)) => (units, Some(decimals)),
+            None => (canonical, None),
+        };
+
+        let mut grouped = String::new();
+        for (i, digit) in units.chars().enumerate() {
+            if i > 0 && (units.len() - i) % 3 == 0 {
+                if let Some(grouping) = self.grouping_separator {
+                    grouped.push(grouping);
+                }
+            }
+            grouped.push(digit);
+        }
+        if let Some(decimals) = decimals {
+            grouped.push(self.decimal_separator);
+            grouped.push_str(decimals);
+        }
+        grouped
+    }
+
+    fn apply_sign(&self, body: String, negative: bool) -> String {
+        if !negative {
+            return body;
+        }
+        match self.negative_style {
+            NegativeStyle::LeadingMinus => format!("-{}", body),
+            NegativeStyle::TrailingMinus => format!("{}-", body),
+            NegativeStyle::Parentheses => format!("({})", body),
+        }
+    }
+}
+
+impl fmt::Display for LocaleProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// Display symbol for a currency code; the code itself when there is no common symbol.
+pub fn currency_symbol(code: &str) -> &str {
+    match code {
+        "EUR" => "€",
+        "USD" => "$",
+        "GBP" => "£",
+        "JPY" | "CNY" => "¥",
+        "INR" => "₹",
+        "KRW" => "₩",
+        "PLN" => "zł",
+        "SEK" | "NOK" | "DKK" => "kr",
+        "BTC" => "₿",
+        _ => code,
+    }
+}
+
+fn is_space(c: char) -> bool {
+    c.is_whitespace() || c == '\u{202f}'
+}
+
+/// Split off a leading or trailing minus, or enclosing parentheses.
+fn strip_sign(input: &str) -> (bool, &str) {
+    let input = input.trim_matches(is_space);
+    if let Some(inner) = input.strip_prefix('(').and_then(|s| s.strip_suffix(')')) {
+        return (true, inner.trim_matches(is_space));
+    }
+    if let Some(rest) = input.strip_prefix('-') {
+        return (true, rest.trim_matches(is_space));
+    }
+    if let Some(rest) = input.strip_suffix('-') {
+        return (true, rest.trim_matches(is_space));
+    }
+    (false, input)
+}
+
+/// Remove the currency's symbol or code from either end of the input.
+fn strip_currency<'a>(input: &'a str, currency: &Currency) -> &'a str {
+    let mut input = input.trim_matches(is_space);
+    for marker in [currency_symbol(&currency.code), currency.code.as_str()] {
+        if marker.is_empty() {
+            continue;
+        }
+        if let Some(rest) = input.strip_prefix(marker) {
+            input = rest;
+        } else if let Some(rest) = input.strip_suffix(marker) {
+            input = rest;
+        }
+        input = input.trim_matches(is_space);
+    }
+    input
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eur() -> Currency {
+        Currency::iso("EUR")
+    }
+
+    fn locale(name: &str) -> LocaleProfile {
+        LocaleProfile::find_builtin(name).unwrap()
+    }
+
+    #[test]
+    fn test_plain_profile_matches_canonical_format() {
+        let plain = LocaleProfile::default();
+        assert_eq!(plain.format_amount(123456, &eur()), "1234.56");
+        assert_eq!(plain.format_amount(-5, &eur()), "-0.05");
+        assert_eq!(plain.format_money(123456, &eur()), "1234.56 EUR");
+        assert_eq!(
+            plain.parse_amount("1234.56", &eur(), RoundingMode::Reject),
+            Ok(123456)
+        );
+        assert!(
+            plain
+                .parse_amount("1,234.56", &eur(), RoundingMode::Reject)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_format_with_grouping_and_symbol() {
+        assert_eq!(
+            locale("de-DE").format_amount(123456789, &eur()),
+            "1.234.567,89"
+        );
+        assert_eq!(locale("de-DE").format_money(123456, &eur()), "1.234,56 €");
+        assert_eq!(
+            locale("en-US").format_money(-123456, &Currency::iso("USD")),
+            "-$1,234.56"
+        );
+        assert_eq!(locale("fr-FR").format_money(99, &eur()), "0,99 €");
+        assert_eq!(
+            locale("de-CH").format_money(123456, &Currency::iso("CHF")),
+            "CHF 1'234.56"
+        );
+        assert_eq!(
+            locale("en-US").format_money(1500000, &Currency::iso("JPY")),
+            "¥1,500,000"
+        );
+    }
+
+    #[test]
+    fn test_negative_styles() {
+        let accounting = locale("accounting");
+        assert_eq!(accounting.format_amount(-123456, &eur()), "(1,234.56)");
+        let trailing =
+            LocaleProfile::new("x".into(), ',').with_negative_style(NegativeStyle::TrailingMinus);
+        assert_eq!(trailing.format_amount(-1250, &eur()), "12,50-");
+
+        for input in ["(1,234.56)", "-1,234.56", "1,234.56-"] {
+            assert_eq!(
+                accounting.parse_amount(input, &eur(), RoundingMode::Reject),
+                Ok(-123456)
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_european_input() {
+        let de = locale("de-DE");
+        for input in [
+            "1.234,56",
+            "1234,56",
+            "€ 1.234,56",
+            "1.234,56 €",
+            "1.234,56 EUR",
+        ] {
+            assert_eq!(
+                de.parse_amount(input, &eur(), RoundingMode::Reject),
+                Ok(123456),
+                "{}",
+                input
+            );
+        }
+        assert_eq!(
+            de.parse_amount("€ 12,50", &eur(), RoundingMode::Reject),
+            Ok(1250)
+        );
+        assert_eq!(
+            de.parse_amount("-€12,50", &eur(), RoundingMode::Reject),
+            Ok(-1250)
+        );
+        assert_eq!(
+            de.parse_amount("1.500", &eur(), RoundingMode::Reject),
+            Ok(150000)
+        );
+
+        // A dot followed by two digits is a mistyped decimal, not a group
+        assert_eq!(
+            de.parse_amount("12.50", &eur(), RoundingMode::Reject),
+            Err(ParseCentsError::InvalidFormat)
+        );
+        assert_eq!(
+            de.parse_amount("12,505", &eur(), RoundingMode::Reject),
+            Err(ParseCentsError::ExcessPrecision { max_decimals: 2 })
+        );
+        assert_eq!(
+            de.parse_amount("12,505", &eur(), RoundingMode::HalfUp),
+            Ok(1251)
+        );
+
+        let fr = locale("fr-FR");
+        assert_eq!(
+            fr.parse_amount("1 234,56 €", &eur(), RoundingMode::Reject),
+            Ok(123456)
+        );
+        assert_eq!(
+            fr.parse_amount("1\u{202f}234,56", &eur(), RoundingMode::Reject),
+            Ok(123456)
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_garbage() {
+        let de = locale("de-DE");
+        for input in ["", "€", "abc", "--12", "$12,50", "1,2,3", "(12,50"] {
+            assert!(
+                de.parse_amount(input, &eur(), RoundingMode::Reject)
+                    .is_err(),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for profile in LocaleProfile::builtin() {
+            for amount in [0, 5, -5, 123456, -987654321] {
+                let formatted = profile.format_money(amount, &eur());
+                assert_eq!(
+                    profile.parse_amount(&formatted, &eur(), RoundingMode::Reject),
+                    Ok(amount),
+                    "{} {}",
+                    profile.name,
+                    formatted
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(locale("de-DE").validate().is_ok());
+        assert!(LocaleProfile::new("x".into(), '1').validate().is_err());
+        assert!(
+            LocaleProfile::new("x".into(), ',')
+                .with_grouping(Some(','))
+                .validate()
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use chrono::{DateTime, Utc};
+
+use super::{Cents, Transfer, WalletId};
+
+/// Days either side of a statement line's date searched for a matching transfer.
+pub const DEFAULT_MATCH_WINDOW_DAYS: i64 = 3;
+
+/// How far ahead the best candidate must score to be taken without confirmation.
+const CLEAR_LEAD: f64 = 0.2;
+
+/// A line of a bank statement being imported, with its wallets resolved.
+#[derive(Debug, Clone)]
+pub struct StatementLine {
+    pub from_wallet: WalletId,
+    pub to_wallet: WalletId,
+    pub amount_cents: Cents,
+    pub timestamp: DateTime<Utc>,
+    pub description: Option<String>,
+}
+
+/// An existing transfer that could be the ledger entry for a statement line.
+#[derive(Debug, Clone)]
+pub struct MatchCandidate {
+    pub transfer: Transfer,
+    /// 0.0 to 1.0; higher is a closer match
+    pub score: f64,
+}
+
+/// What to do with a statement line after looking for its transfer.
+#[derive(Debug, Clone)]
+pub enum MatchOutcome {
+    /// Nothing in the ledger fits: import the line as a new transfer
+    Unmatched,
+    /// One transfer clearly fits
+    Matched(Box<MatchCandidate>),
+    /// Several transfers fit about as well; a person has to pick
+    Ambiguous(Vec<MatchCandidate>),
+}
+
+/// Score the transfers that could stand for a statement line, best first.
+///
+/// A candidate moves exactly the line's amount out of the line's source wallet
+/// or into its destination wallet, within `window_days` of the line's date.
+/// Reversals and transfers already tied to a bank reference are left out.
+/// The score weighs date distance, description likeness and whether both
+/// wallets agree.
+pub fn find_match_candidates(
+    line: &StatementLine,
+    transfers: &[Transfer],
+    window_days: i64,
+) -> Vec<MatchCandidate> {
+    let mut candidates: Vec<MatchCandidate> = transfers
+        .iter()
+        .filter(|t| t.amount_cents == line.amount_cents)
+        .filter(|t| t.from_wallet == line.from_wallet || t.to_wallet == line.to_wallet)
+        .filter(|t| !t.is_reversal() && t.external_ref.is_none())
+        .filter_map(|t| {
+            let days = (t.timestamp - line.timestamp).num_days().abs();
+            if days > window_days {
+                return None;
+            }
+            let date_score = 1.0 - days as f64 / (window_days + 1) as f64;
+            let description_score = match (&line.description, &t.description) {
+                (Some(a), Some(b)) => description_similarity(a, b),
+                _ => 0.0,
+            };
+            let wallet_score = if t.from_wallet == line.from_wallet && t.to_wallet == line.to_wallet
+            {
+                1.0
+            } else {
+                0.0
+            };
+            Some(MatchCandidate {
+                transfer: t.clone(),
+                score: 0.4 * date_score + 0.4 * description_score + 0.2 * wallet_score,
+            })
+        })
+        .collect();
+
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    candidates
+}
+
+/// Decide between the candidates for a line: a single candidate is a match,
+/// and so is a best candidate well ahead of the next one.
+pub fn resolve_match(mut candidates: Vec<MatchCandidate>) -> MatchOutcome {
+    match candidates.len() {
+        0 => MatchOutcome::Unmatched,
+        1 => MatchOutcome::Matched(Box::new(candidates.remove(0))),
+        _ if candidates[0].score - candidates[1].score >= CLEAR_LEAD => {
+            MatchOutcome::Matched(Box::new(candidates.remove(0)))
+        }
+        _ => MatchOutcome::Ambiguous(candidates),
+    }
+}
+
+/// How alike two descriptions are, from 0.0 to 1.0: the Dice coefficient of
+/// their letter pairs, ignoring case, punctuation and spacing.
+/// Example: "AMAZON MKTPLACE" vs "Amazon" -> 0.53
+pub fn description_similarity(a: &str, b: &str) -> f64 {
+    let a = bigrams(a);
+    let b = bigrams(b);
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+
+    let mut remaining = b.clone();
+    let mut shared = 0;
+    for pair in &a {
+        if let Some(pos) = remaining.iter().position(|p| p == pair) {
+            remaining.swap_remove(pos);
+            shared += 1;
+        }
+    }
+    2.0 * shared as f64 / (a.len() + b.len()) as f64
+}
+
+fn bigrams(s: &str) -> Vec<(char, char)> {
+    let chars: Vec<char> = s
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect();
+    chars.windows(2).map(|w| (w[0], w[1])).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn line(from: WalletId, to: WalletId, amount: Cents, description: &str) -> StatementLine {
+        StatementLine {
+            from_wallet: from,
+            to_wallet: to,
+            amount_cents: amount,
+            timestamp: Utc::now(),
+            description: Some(description.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_description_similarity() {
+        assert_eq!(description_similarity("Coffee", "coffee!"), 1.0);
+        assert_eq!(description_similarity("abc", "xyz"), 0.0);
+        assert_eq!(description_similarity("", "abc"), 0.0);
+        let partial = description_similarity("AMAZON MKTPLACE", "Amazon");
+        assert!(partial > 0.5 && partial < 0.6);
+    }
+
+    #[test]
+    fn test_candidates_need_amount_wallet_and_window() {
+        let bank = Uuid::new_v4();
+        let shop = Uuid::new_v4();
+        let other = Uuid::new_v4();
+        let now = Utc::now();
+        let transfers = vec![
+            Transfer::new(bank, shop, 4599, now - Duration::days(2)).with_description("Groceries"),
+            Transfer::new(bank, shop, 4500, now).with_description("Groceries"),
+            Transfer::new(other, shop, 4599, now).with_description("Groceries"),
+            Transfer::new(bank, shop, 4599, now - Duration::days(5)).with_description("Groceries"),
+            Transfer::new(bank, shop, 4599, now)
+                .with_description("Groceries")
+                .with_external_ref("BANK-1"),
+        ];
+
+        let candidates = find_match_candidates(&line(bank, shop, 4599, "GROCERIES"), &transfers, 3);
+        assert_eq!(candidates.len(), 2);
+        // Two days off with both wallets agreeing scores as a same-day match on one wallet
+        assert_eq!(candidates[0].score, candidates[1].score);
+        assert!(matches!(
+            resolve_match(candidates),
+            MatchOutcome::Ambiguous(_)
+        ));
+    }
+
+    #[test]
+    fn test_resolve_prefers_clear_winner() {
+        let bank = Uuid::new_v4();
+        let shop = Uuid::new_v4();
+        let now = Utc::now();
+        let transfers = vec![
+            Transfer::new(bank, shop, 1200, now).with_description("Coffee Bar"),
+            Transfer::new(bank, shop, 1200, now - Duration::days(3)).with_description("Cinema"),
+        ];
+
+        let candidates =
+            find_match_candidates(&line(bank, shop, 1200, "COFFEE BAR 123"), &transfers, 3);
+        match resolve_match(candidates) {
+            MatchOutcome::Matched(candidate) => assert_eq!(candidate.transfer.id, transfers[0].id),
+            other => panic!("expected a match, got {:?}", other),
+        }
+        assert!(matches!(resolve_match(Vec::new()), MatchOutcome::Unmatched));
+    }
+}
@@ -1,13 +1,47 @@
+mod amendment;
+mod attachment;
+mod balance_assertion;
 mod budget;
+mod category;
+mod commodity;
+mod dates;
+mod envelope;
+mod exchange_rate;
 mod ledger;
+mod locale;
+mod matching;
 mod money;
+mod net_worth;
+mod payee;
+mod period_lock;
+mod reconciliation;
+mod register;
 mod scheduled_transfer;
+mod tag;
 mod transfer;
 mod wallet;
+mod year_close;
 
+pub use amendment::*;
+pub use attachment::*;
+pub use balance_assertion::*;
 pub use budget::*;
+pub use category::*;
+pub use commodity::*;
+pub use dates::*;
+pub use envelope::*;
+pub use exchange_rate::*;
 pub use ledger::*;
+pub use locale::*;
+pub use matching::*;
 pub use money::*;
+pub use net_worth::*;
+pub use payee::*;
+pub use period_lock::*;
+pub use reconciliation::*;
+pub use register::*;
 pub use scheduled_transfer::*;
+pub use tag::*;
 pub use transfer::*;
 pub use wallet::*;
+pub use year_close::*;
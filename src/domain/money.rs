@@ -1,83 +1,357 @@
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
-/// Money is represented as integer cents to avoid floating-point precision issues.
-/// For EUR/USD, 1 unit = 100 cents, so €50.00 = 5000 cents.
+use super::Rate;
+
+/// Amounts are stored as integers in the currency's minor unit to avoid floating-point
+/// precision issues. For EUR/USD, 1 unit = 100 cents, so €50.00 = 5000; for JPY the
+/// minor unit is the yen itself, and for KWD it is the fils (1/1000).
 pub type Cents = i64;
 
+/// Currencies whose minor unit is not 1/100, per ISO 4217 (plus common crypto assets).
+/// Anything not listed uses two decimals unless registered otherwise.
+const MINOR_UNIT_EXCEPTIONS: &[(&str, u32)] = &[
+    ("BIF", 0),
+    ("CLP", 0),
+    ("DJF", 0),
+    ("GNF", 0),
+    ("ISK", 0),
+    ("JPY", 0),
+    ("KMF", 0),
+    ("KRW", 0),
+    ("PYG", 0),
+    ("RWF", 0),
+    ("UGX", 0),
+    ("VND", 0),
+    ("VUV", 0),
+    ("XAF", 0),
+    ("XOF", 0),
+    ("XPF", 0),
+    ("BHD", 3),
+    ("IQD", 3),
+    ("JOD", 3),
+    ("KWD", 3),
+    ("LYD", 3),
+    ("OMR", 3),
+    ("TND", 3),
+    ("BTC", 8),
+    ("LTC", 8),
+];
+
+/// Largest supported minor-unit exponent (keeps 10^n well inside an i64).
+pub const MAX_MINOR_UNITS: u32 = 12;
+
+/// A currency code together with its minor-unit exponent (number of decimals).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Currency {
+    pub code: String,
+    pub minor_units: u32,
+}
+
+impl Currency {
+    pub fn new(code: impl Into<String>, minor_units: u32) -> Self {
+        Self {
+            code: code.into().to_uppercase(),
+            minor_units,
+        }
+    }
+
+    /// Look up a currency in the built-in table, defaulting to two decimals.
+    pub fn iso(code: &str) -> Self {
+        let code = code.to_uppercase();
+        let minor_units = MINOR_UNIT_EXCEPTIONS
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map(|(_, units)| *units)
+            .unwrap_or(2);
+        Self { code, minor_units }
+    }
+
+    /// Number of minor units in one major unit (100 for EUR, 1 for JPY).
+    pub fn scale(&self) -> i64 {
+        10_i64.pow(self.minor_units)
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code)
+    }
+}
+
+/// How to handle input with more decimals than the currency allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RoundingMode {
+    /// Refuse the input
+    #[default]
+    Reject,
+    /// Round half away from zero
+    HalfUp,
+    /// Round half to even (banker's rounding)
+    HalfEven,
+    /// Drop the extra digits
+    Truncate,
+}
+
+impl RoundingMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RoundingMode::Reject => "reject",
+            RoundingMode::HalfUp => "half-up",
+            RoundingMode::HalfEven => "half-even",
+            RoundingMode::Truncate => "truncate",
+        }
+    }
+
+    /// Divide `numerator` by a positive `denominator`, rounding the quotient.
+    /// Returns None for `Reject` when the division leaves a remainder.
+    /// Example: RoundingMode::HalfUp.divide(-5, 2) -> Some(-3)
+    pub fn divide(&self, numerator: i128, denominator: i128) -> Option<i128> {
+        let quotient = numerator / denominator;
+        let remainder = numerator % denominator;
+        if remainder == 0 {
+            return Some(quotient);
+        }
+        let away_from_zero = quotient + numerator.signum();
+        let twice = remainder.abs() * 2;
+        match self {
+            RoundingMode::Reject => None,
+            RoundingMode::Truncate => Some(quotient),
+            RoundingMode::HalfUp if twice >= denominator => Some(away_from_zero),
+            RoundingMode::HalfEven if twice > denominator => Some(away_from_zero),
+            RoundingMode::HalfEven if twice == denominator && quotient % 2 != 0 => {
+                Some(away_from_zero)
+            }
+            RoundingMode::HalfUp | RoundingMode::HalfEven => Some(quotient),
+        }
+    }
+}
+
+impl fmt::Display for RoundingMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for RoundingMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "reject" => Ok(RoundingMode::Reject),
+            "half-up" => Ok(RoundingMode::HalfUp),
+            "half-even" => Ok(RoundingMode::HalfEven),
+            "truncate" => Ok(RoundingMode::Truncate),
+            _ => Err(format!("Invalid rounding mode: {}", s)),
+        }
+    }
+}
+
+/// An amount in a specific currency, held in that currency's minor units.
+/// Used where amounts are parsed, formatted or converted; stored records keep
+/// plain `Cents` in the currency of their wallet or budget.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Money {
+    pub amount: Cents,
+    pub currency: Currency,
+}
+
+impl Money {
+    pub fn new(amount: Cents, currency: Currency) -> Self {
+        Self { amount, currency }
+    }
+
+    /// Parse a decimal string in the given currency.
+    /// Example: Money::parse("12.5", Currency::iso("EUR"), RoundingMode::Reject) -> 1250 EUR
+    pub fn parse(
+        input: &str,
+        currency: Currency,
+        rounding: RoundingMode,
+    ) -> Result<Self, ParseCentsError> {
+        let amount = parse_minor_units(input, currency.minor_units, rounding)?;
+        Ok(Self { amount, currency })
+    }
+
+    /// The amount as a plain decimal string, without the currency code.
+    pub fn format_amount(&self) -> String {
+        format_minor_units(self.amount, self.currency.minor_units)
+    }
+
+    /// Add two amounts. Returns None if the currencies differ or the sum overflows.
+    pub fn checked_add(&self, other: &Money) -> Option<Money> {
+        if self.currency != other.currency {
+            return None;
+        }
+        Some(Money::new(
+            self.amount.checked_add(other.amount)?,
+            self.currency.clone(),
+        ))
+    }
+
+    /// Subtract an amount. Returns None if the currencies differ or the result overflows.
+    pub fn checked_sub(&self, other: &Money) -> Option<Money> {
+        if self.currency != other.currency {
+            return None;
+        }
+        Some(Money::new(
+            self.amount.checked_sub(other.amount)?,
+            self.currency.clone(),
+        ))
+    }
+
+    /// Convert into another currency. `rate` is in major units (1 self = rate target);
+    /// the result is rounded to the target's minor unit as `rounding` says.
+    /// Returns None on overflow, or when `Reject` meets an inexact conversion.
+    pub fn convert(&self, rate: &Rate, target: Currency, rounding: RoundingMode) -> Option<Money> {
+        let amount = rate
+            .between_minor_units(&self.currency, &target)?
+            .apply(self.amount, rounding)?;
+        Some(Money::new(amount, target))
+    }
+
+    /// Value a quantity of units (self) at a price per whole unit in `target`,
+    /// rounded half away from zero to the target's minor unit.
+    pub fn at_price(&self, price: f64, target: Currency) -> Money {
+        let factor = minor_unit_factor(price, &self.currency, &target);
+        Money::new((self.amount as f64 * factor).round() as Cents, target)
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.format_amount(), self.currency)
+    }
+}
+
+/// Factor that converts minor units of `from` into minor units of `to` at a major-unit price.
+/// Example: BTC -> EUR at 50000.0 gives 0.0005 (1 satoshi = 0.0005 cents)
+pub fn minor_unit_factor(rate: f64, from: &Currency, to: &Currency) -> f64 {
+    rate * 10f64.powi(to.minor_units as i32 - from.minor_units as i32)
+}
+
 /// Format cents as a human-readable currency string.
 /// Example: 5000 -> "50.00", -1234 -> "-12.34"
 pub fn format_cents(cents: Cents) -> String {
-    let sign = if cents < 0 { "-" } else { "" };
-    let abs_cents = cents.abs();
-    let units = abs_cents / 100;
-    let remainder = abs_cents % 100;
-    format!("{}{}.{:02}", sign, units, remainder)
+    format_minor_units(cents, 2)
+}
+
+/// Format an amount in minor units with the given number of decimals.
+/// Example: format_minor_units(1234567, 3) -> "1234.567", format_minor_units(500, 0) -> "500"
+pub fn format_minor_units(amount: Cents, minor_units: u32) -> String {
+    let sign = if amount < 0 { "-" } else { "" };
+    let abs_amount = amount.unsigned_abs();
+    if minor_units == 0 {
+        return format!("{}{}", sign, abs_amount);
+    }
+    let scale = 10_u64.pow(minor_units);
+    let units = abs_amount / scale;
+    let remainder = abs_amount % scale;
+    format!(
+        "{}{}.{:0width$}",
+        sign,
+        units,
+        remainder,
+        width = minor_units as usize
+    )
 }
 
-/// Parse a decimal string into cents.
+/// Parse a decimal string into cents. Input with more than two decimals is rejected.
 /// Example: "50.00" -> 5000, "12.5" -> 1250, "100" -> 10000
 pub fn parse_cents(input: &str) -> Result<Cents, ParseCentsError> {
+    parse_minor_units(input, 2, RoundingMode::Reject)
+}
+
+/// Parse a decimal string into minor units with the given number of decimals.
+/// Extra decimals are handled according to `rounding`.
+/// Example: parse_minor_units("1.2345", 3, RoundingMode::HalfUp) -> 1235
+pub fn parse_minor_units(
+    input: &str,
+    minor_units: u32,
+    rounding: RoundingMode,
+) -> Result<Cents, ParseCentsError> {
     let input = input.trim();
     let negative = input.starts_with('-');
     let input = input.trim_start_matches('-');
 
-    let parts: Vec<&str> = input.split('.').collect();
-    match parts.len() {
-        1 => {
-            // No decimal point, treat as whole units
-            let units: i64 = parts[0]
-                .parse()
-                .map_err(|_| ParseCentsError::InvalidFormat)?;
-            let cents = units * 100;
-            Ok(if negative { -cents } else { cents })
-        }
-        2 => {
-            let units: i64 = if parts[0].is_empty() {
-                0
-            } else {
-                parts[0]
-                    .parse()
-                    .map_err(|_| ParseCentsError::InvalidFormat)?
-            };
-
-            // Handle decimal part - pad or truncate to 2 digits
-            let decimal_str = parts[1];
-            let decimal_cents: i64 = match decimal_str.len() {
-                0 => 0,
-                1 => {
-                    // Single digit like "5" means 50 cents
-                    decimal_str
-                        .parse::<i64>()
-                        .map_err(|_| ParseCentsError::InvalidFormat)?
-                        * 10
-                }
-                2 => decimal_str
-                    .parse()
-                    .map_err(|_| ParseCentsError::InvalidFormat)?,
-                _ => {
-                    // More than 2 decimal places - truncate
-                    decimal_str[..2]
-                        .parse()
-                        .map_err(|_| ParseCentsError::InvalidFormat)?
-                }
-            };
-
-            let cents = units * 100 + decimal_cents;
-            Ok(if negative { -cents } else { cents })
+    let (units_str, decimal_str) = match input.split_once('.') {
+        Some((units, decimals)) => (units, decimals),
+        None => (input, ""),
+    };
+
+    let is_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    if (units_str.is_empty() && decimal_str.is_empty())
+        || !is_digits(units_str)
+        || !is_digits(decimal_str)
+    {
+        return Err(ParseCentsError::InvalidFormat);
+    }
+
+    let units: i64 = if units_str.is_empty() {
+        0
+    } else {
+        units_str.parse().map_err(|_| ParseCentsError::Overflow)?
+    };
+
+    let digits = minor_units as usize;
+    let (kept, dropped) = if decimal_str.len() > digits {
+        decimal_str.split_at(digits)
+    } else {
+        (decimal_str, "")
+    };
+
+    // Pad kept decimals to the full exponent: "5" with 2 decimals means 50
+    let mut fraction: i64 = if kept.is_empty() {
+        0
+    } else {
+        kept.parse::<i64>().map_err(|_| ParseCentsError::Overflow)?
+            * 10_i64.pow((digits - kept.len()) as u32)
+    };
+
+    if dropped.chars().any(|c| c != '0') {
+        let first = dropped.as_bytes()[0] - b'0';
+        let rest_nonzero = dropped[1..].chars().any(|c| c != '0');
+        let round_up = match rounding {
+            RoundingMode::Reject => {
+                return Err(ParseCentsError::ExcessPrecision {
+                    max_decimals: minor_units,
+                });
+            }
+            RoundingMode::Truncate => false,
+            RoundingMode::HalfUp => first >= 5,
+            RoundingMode::HalfEven => {
+                let last_kept = if digits == 0 { units } else { fraction };
+                let last_kept_odd = last_kept % 2 == 1;
+                first > 5 || (first == 5 && (rest_nonzero || last_kept_odd))
+            }
+        };
+        if round_up {
+            fraction += 1;
         }
-        _ => Err(ParseCentsError::InvalidFormat),
     }
+
+    let amount = units
+        .checked_mul(10_i64.pow(minor_units))
+        .and_then(|a| a.checked_add(fraction))
+        .ok_or(ParseCentsError::Overflow)?;
+    Ok(if negative { -amount } else { amount })
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ParseCentsError {
     InvalidFormat,
+    ExcessPrecision { max_decimals: u32 },
+    Overflow,
 }
 
 impl fmt::Display for ParseCentsError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ParseCentsError::InvalidFormat => write!(f, "invalid money format"),
+            ParseCentsError::ExcessPrecision { max_decimals } => {
+                write!(f, "too many decimals (at most {} allowed)", max_decimals)
+            }
+            ParseCentsError::Overflow => write!(f, "amount is too large"),
         }
     }
 }
@@ -108,12 +382,104 @@ mod tests {
         assert_eq!(parse_cents("0.01"), Ok(1));
         assert_eq!(parse_cents(".50"), Ok(50));
         assert_eq!(parse_cents("-50.00"), Ok(-5000));
-        assert_eq!(parse_cents("100.999"), Ok(10099)); // Truncates
+        assert_eq!(parse_cents("100.990"), Ok(10099)); // Trailing zeros are fine
+        assert_eq!(
+            parse_cents("100.999"),
+            Err(ParseCentsError::ExcessPrecision { max_decimals: 2 })
+        );
     }
 
     #[test]
     fn test_parse_cents_invalid() {
         assert!(parse_cents("abc").is_err());
         assert!(parse_cents("12.34.56").is_err());
+        assert!(parse_cents("").is_err());
+        assert!(parse_cents("1e5").is_err());
+        assert_eq!(
+            parse_cents("99999999999999999999"),
+            Err(ParseCentsError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_minor_units_per_currency() {
+        assert_eq!(Currency::iso("eur").minor_units, 2);
+        assert_eq!(Currency::iso("JPY").minor_units, 0);
+        assert_eq!(Currency::iso("KWD").minor_units, 3);
+        assert_eq!(Currency::iso("BTC").minor_units, 8);
+
+        assert_eq!(format_minor_units(1500, 0), "1500");
+        assert_eq!(format_minor_units(1234567, 3), "1234.567");
+        assert_eq!(format_minor_units(-5, 3), "-0.005");
+        assert_eq!(format_minor_units(150000000, 8), "1.50000000");
+
+        let jpy = Money::parse("1500", Currency::iso("JPY"), RoundingMode::Reject).unwrap();
+        assert_eq!(jpy.amount, 1500);
+        assert_eq!(jpy.to_string(), "1500 JPY");
+        assert!(Money::parse("1500.5", Currency::iso("JPY"), RoundingMode::Reject).is_err());
+
+        let kwd = Money::parse("12.345", Currency::iso("KWD"), RoundingMode::Reject).unwrap();
+        assert_eq!(kwd.amount, 12345);
+        assert_eq!(kwd.format_amount(), "12.345");
+    }
+
+    #[test]
+    fn test_rounding_modes() {
+        let parse = |s, mode| parse_minor_units(s, 2, mode).unwrap();
+        assert_eq!(parse("100.999", RoundingMode::Truncate), 10099);
+        assert_eq!(parse("100.999", RoundingMode::HalfUp), 10100);
+        assert_eq!(parse("1.005", RoundingMode::HalfUp), 101);
+        assert_eq!(parse("1.005", RoundingMode::HalfEven), 100);
+        assert_eq!(parse("1.015", RoundingMode::HalfEven), 102);
+        assert_eq!(parse("1.0051", RoundingMode::HalfEven), 101);
+        assert_eq!(parse("-1.005", RoundingMode::HalfUp), -101);
+
+        for mode in ["reject", "half-up", "half-even", "truncate"] {
+            assert_eq!(mode.parse::<RoundingMode>().unwrap().as_str(), mode);
+        }
+    }
+
+    #[test]
+    fn test_money_arithmetic_and_conversion() {
+        let eur = Currency::iso("EUR");
+        let a = Money::new(1050, eur.clone());
+        let b = Money::new(250, eur.clone());
+        assert_eq!(a.checked_add(&b), Some(Money::new(1300, eur.clone())));
+        assert_eq!(a.checked_sub(&b), Some(Money::new(800, eur.clone())));
+
+        let yen = Money::new(1000, Currency::iso("JPY"));
+        assert_eq!(a.checked_add(&yen), None);
+
+        // 10.50 EUR at 160 JPY/EUR = 1680 JPY
+        let rate = |s: &str| s.parse::<Rate>().unwrap();
+        let jpy = Currency::iso("JPY");
+        let converted = a.convert(&rate("160"), jpy.clone(), RoundingMode::Reject);
+        assert_eq!(converted, Some(Money::new(1680, jpy.clone())));
+        // 1000 JPY at 0.00625 EUR/JPY = 6.25 EUR
+        let converted = yen.convert(&rate("0.00625"), eur.clone(), RoundingMode::Reject);
+        assert_eq!(converted.unwrap().amount, 625);
+        // 1 JPY = 0.625 cents: rounding decides, Reject refuses
+        let one_yen = Money::new(1, jpy);
+        let to_eur = |mode| one_yen.convert(&rate("0.00625"), eur.clone(), mode);
+        assert_eq!(to_eur(RoundingMode::HalfUp).unwrap().amount, 1);
+        assert_eq!(to_eur(RoundingMode::Truncate).unwrap().amount, 0);
+        assert_eq!(to_eur(RoundingMode::Reject), None);
+
+        // 0.5 BTC at 50000 EUR/BTC = 25000.00 EUR
+        let btc = Money::new(50_000_000, Currency::iso("BTC"));
+        assert_eq!(btc.at_price(50000.0, eur).amount, 2_500_000);
+    }
+
+    #[test]
+    fn test_divide_rounds() {
+        let divide = |mode: RoundingMode, n| mode.divide(n, 4);
+        assert_eq!(divide(RoundingMode::HalfUp, 10), Some(3)); // 2.5
+        assert_eq!(divide(RoundingMode::HalfUp, -10), Some(-3));
+        assert_eq!(divide(RoundingMode::HalfEven, 10), Some(2));
+        assert_eq!(divide(RoundingMode::HalfEven, 14), Some(4)); // 3.5
+        assert_eq!(divide(RoundingMode::HalfEven, -11), Some(-3)); // -2.75
+        assert_eq!(divide(RoundingMode::Truncate, -11), Some(-2));
+        assert_eq!(divide(RoundingMode::Reject, 11), None);
+        assert_eq!(divide(RoundingMode::Reject, 12), Some(3));
     }
 }
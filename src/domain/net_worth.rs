@@ -0,0 +1,210 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::{Cents, WalletId, WalletType};
+
+/// How often a history is sampled: at the end of every day, week (Sunday) or month.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SampleInterval {
+    Day,
+    Week,
+    Month,
+}
+
+impl SampleInterval {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SampleInterval::Day => "day",
+            SampleInterval::Week => "week",
+            SampleInterval::Month => "month",
+        }
+    }
+
+    /// Last day of the interval holding `date`
+    pub fn period_end(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            SampleInterval::Day => date,
+            SampleInterval::Week => {
+                date + Duration::days(6 - date.weekday().num_days_from_monday() as i64)
+            }
+            SampleInterval::Month => {
+                let (year, month) = if date.month() == 12 {
+                    (date.year() + 1, 1)
+                } else {
+                    (date.year(), date.month() + 1)
+                };
+                NaiveDate::from_ymd_opt(year, month, 1)
+                    .and_then(|first| first.pred_opt())
+                    .unwrap_or(NaiveDate::MAX)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for SampleInterval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for SampleInterval {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "day" | "daily" => Ok(SampleInterval::Day),
+            "week" | "weekly" => Ok(SampleInterval::Week),
+            "month" | "monthly" => Ok(SampleInterval::Month),
+            _ => Err(format!("Invalid sample interval: {}", s)),
+        }
+    }
+}
+
+/// Days a history from `from` to `to` is sampled on: the end of every interval
+/// in the range, and `to` itself when the range stops mid-interval.
+pub fn sample_dates(from: NaiveDate, to: NaiveDate, interval: SampleInterval) -> Vec<NaiveDate> {
+    let mut dates = Vec::new();
+    let mut date = from;
+    while date <= to {
+        let end = interval.period_end(date).min(to);
+        dates.push(end);
+        match end.succ_opt() {
+            Some(next) => date = next,
+            None => break,
+        }
+    }
+    dates
+}
+
+/// Net worth at the end of a day. Liabilities are counted as what is owed,
+/// so the net worth is assets minus liabilities.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetWorthPoint {
+    pub date: NaiveDate,
+    pub total_assets: Cents,
+    pub total_liabilities: Cents,
+    pub net_worth: Cents,
+}
+
+/// Balances (or units) of every wallet at the end of each sample date, walking
+/// forward from `opening` (amounts before the first sample's range) through
+/// `changes`, the per-day changes of each wallet in date order. A single pass
+/// over the changes keeps this linear however many days are sampled.
+pub fn balances_on(
+    opening: &HashMap<WalletId, i64>,
+    changes: &[(NaiveDate, WalletId, i64)],
+    dates: &[NaiveDate],
+) -> Vec<HashMap<WalletId, i64>> {
+    let mut balances = opening.clone();
+    let mut changes = changes.iter().peekable();
+    dates
+        .iter()
+        .map(|&date| {
+            while let Some((_, wallet_id, change)) = changes.next_if(|(day, _, _)| *day <= date) {
+                *balances.entry(*wallet_id).or_insert(0) += change;
+            }
+            balances.clone()
+        })
+        .collect()
+}
+
+/// Net worth on `date` from the values of the wallets, all in one currency.
+/// Only asset and liability wallets count.
+pub fn net_worth_point(
+    date: NaiveDate,
+    wallet_types: &HashMap<WalletId, WalletType>,
+    values: &HashMap<WalletId, Cents>,
+) -> NetWorthPoint {
+    let mut total_assets: Cents = 0;
+    let mut total_liabilities: Cents = 0;
+    for (wallet_id, value) in values {
+        match wallet_types.get(wallet_id) {
+            Some(WalletType::Asset) => total_assets += value,
+            Some(WalletType::Liability) => total_liabilities += value.abs(),
+            _ => {}
+        }
+    }
+    NetWorthPoint {
+        date,
+        total_assets,
+        total_liabilities,
+        net_worth: total_assets - total_liabilities,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_sample_dates_end_each_interval() {
+        assert_eq!(
+            sample_dates(
+                date("2024-01-15"),
+                date("2024-03-10"),
+                SampleInterval::Month
+            ),
+            vec![date("2024-01-31"), date("2024-02-29"), date("2024-03-10")]
+        );
+        // 2024-01-01 is a Monday
+        assert_eq!(
+            sample_dates(date("2024-01-01"), date("2024-01-14"), SampleInterval::Week),
+            vec![date("2024-01-07"), date("2024-01-14")]
+        );
+        assert_eq!(
+            sample_dates(date("2024-12-31"), date("2025-01-01"), SampleInterval::Day),
+            vec![date("2024-12-31"), date("2025-01-01")]
+        );
+        assert!(
+            sample_dates(date("2024-02-01"), date("2024-01-01"), SampleInterval::Day).is_empty()
+        );
+    }
+
+    #[test]
+    fn test_balances_walk_forward() {
+        let checking = Uuid::new_v4();
+        let card = Uuid::new_v4();
+        let salary = Uuid::new_v4();
+        let wallet_types = HashMap::from([
+            (checking, WalletType::Asset),
+            (card, WalletType::Liability),
+            (salary, WalletType::Income),
+        ]);
+        let opening = HashMap::from([(checking, 1000), (salary, -1000)]);
+        let changes = vec![
+            (date("2024-01-02"), checking, 5000),
+            (date("2024-01-02"), salary, -5000),
+            (date("2024-01-03"), card, -2000),
+            // Paying the card off
+            (date("2024-01-05"), card, 2000),
+            (date("2024-01-05"), checking, -2000),
+        ];
+        let dates = sample_dates(date("2024-01-01"), date("2024-01-05"), SampleInterval::Day);
+
+        let totals: Vec<(Cents, Cents, Cents)> = balances_on(&opening, &changes, &dates)
+            .iter()
+            .zip(&dates)
+            .map(|(balances, &date)| {
+                let point = net_worth_point(date, &wallet_types, balances);
+                (point.total_assets, point.total_liabilities, point.net_worth)
+            })
+            .collect();
+        assert_eq!(
+            totals,
+            vec![
+                (1000, 0, 1000),
+                (6000, 0, 6000),
+                (6000, 2000, 4000),
+                (6000, 2000, 4000),
+                (4000, 0, 4000),
+            ]
+        );
+    }
+}
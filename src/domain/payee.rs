@@ -0,0 +1,126 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::WalletId;
+
+pub type PayeeId = Uuid;
+
+/// Someone the ledger pays or receives money from: a shop, an employer, a landlord.
+/// Transfers can reference a payee; its defaults fill in what the transfer leaves out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Payee {
+    pub id: PayeeId,
+    pub name: String,
+    /// Other spellings of the name, as they show up in bank statement descriptions
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Category for transfers to or from this payee that don't name one
+    pub default_category: Option<String>,
+    /// Counterparty wallet (e.g. an expense wallet) for transfers that leave a side open
+    pub default_wallet: Option<WalletId>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Payee {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            aliases: Vec::new(),
+            default_category: None,
+            default_wallet: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn with_aliases(mut self, aliases: Vec<String>) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    pub fn with_default_category(mut self, category: impl Into<String>) -> Self {
+        self.default_category = Some(category.into());
+        self
+    }
+
+    pub fn with_default_wallet(mut self, wallet_id: WalletId) -> Self {
+        self.default_wallet = Some(wallet_id);
+        self
+    }
+
+    /// The name followed by the aliases
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.name.as_str()).chain(self.aliases.iter().map(String::as_str))
+    }
+
+    /// Returns true if `name` is the payee's name or one of its aliases (ignoring case)
+    pub fn is_named(&self, name: &str) -> bool {
+        let name = name.trim().to_lowercase();
+        self.names().any(|n| n.to_lowercase() == name)
+    }
+}
+
+/// Returns true if `term` occurs in `text` as whole words, ignoring case.
+fn contains_words(text: &str, term: &str) -> bool {
+    let text = text.to_lowercase();
+    let term = term.trim().to_lowercase();
+    if term.is_empty() {
+        return false;
+    }
+    text.match_indices(&term).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + term.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+/// Find the payee a transfer description refers to: the payee whose name or alias
+/// appears in it as whole words. When several match, the longest name wins, so
+/// "Shell Garage" beats "Shell"; ties go to the payee listed first.
+pub fn detect_payee<'a>(description: &str, payees: &'a [Payee]) -> Option<&'a Payee> {
+    let mut best: Option<(&Payee, usize)> = None;
+    for payee in payees {
+        for name in payee.names() {
+            let len = name.trim().chars().count();
+            if best.is_none_or(|(_, best_len)| len > best_len) && contains_words(description, name)
+            {
+                best = Some((payee, len));
+            }
+        }
+    }
+    best.map(|(payee, _)| payee)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_payee() {
+        let payees = vec![
+            Payee::new("Shell"),
+            Payee::new("Shell Garage"),
+            Payee::new("Acme Corp").with_aliases(vec!["ACME PAYROLL".to_string()]),
+        ];
+
+        let found = detect_payee("CARD 1234 shell garage berlin", &payees).unwrap();
+        assert_eq!(found.name, "Shell Garage");
+        let found = detect_payee("Shell, Main St.", &payees).unwrap();
+        assert_eq!(found.name, "Shell");
+        let found = detect_payee("SEPA acme payroll 2024-01", &payees).unwrap();
+        assert_eq!(found.name, "Acme Corp");
+
+        // Only whole words count
+        assert!(detect_payee("Seashells", &payees).is_none());
+        assert!(detect_payee("", &payees).is_none());
+    }
+
+    #[test]
+    fn test_is_named() {
+        let payee = Payee::new("Acme Corp").with_aliases(vec!["ACME PAYROLL".to_string()]);
+        assert!(payee.is_named("acme corp"));
+        assert!(payee.is_named(" Acme Payroll "));
+        assert!(!payee.is_named("Acme"));
+    }
+}
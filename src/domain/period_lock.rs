@@ -0,0 +1,295 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use super::{AmendedField, Amendment, Transfer, TransferId, Wallet, WalletId, wallet_ancestors};
+
+pub type PeriodLockId = Uuid;
+
+/// Closes the books up to a date, for the whole ledger or for one wallet and its
+/// sub-wallets. Locks are never edited: setting, moving or lifting a lock appends
+/// a new entry, and the latest entry for a scope is the one in force.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeriodLock {
+    pub id: PeriodLockId,
+    /// Locked wallet; `None` locks the whole ledger
+    pub wallet_id: Option<WalletId>,
+    /// Last locked day (inclusive); `None` lifts the lock
+    pub lock_date: Option<NaiveDate>,
+    /// When the lock was set
+    pub locked_at: DateTime<Utc>,
+    pub author: Option<String>,
+    pub reason: Option<String>,
+}
+
+impl PeriodLock {
+    pub fn new(wallet_id: Option<WalletId>, lock_date: Option<NaiveDate>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            wallet_id,
+            lock_date,
+            locked_at: Utc::now(),
+            author: None,
+            reason: None,
+        }
+    }
+
+    pub fn with_author(mut self, author: impl Into<String>) -> Self {
+        self.author = Some(author.into());
+        self
+    }
+
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+
+    /// Returns true if the lock closes the day of `timestamp`
+    pub fn covers(&self, timestamp: DateTime<Utc>) -> bool {
+        self.lock_date
+            .is_some_and(|lock_date| timestamp.date_naive() <= lock_date)
+    }
+}
+
+/// What an override of a period lock let through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LockedAction {
+    Transfer,
+    Reversal,
+    Amendment,
+}
+
+impl LockedAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LockedAction::Transfer => "transfer",
+            LockedAction::Reversal => "reversal",
+            LockedAction::Amendment => "amendment",
+        }
+    }
+}
+
+impl std::fmt::Display for LockedAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for LockedAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "transfer" => Ok(LockedAction::Transfer),
+            "reversal" => Ok(LockedAction::Reversal),
+            "amendment" => Ok(LockedAction::Amendment),
+            _ => Err(format!("Invalid locked action: {}", s)),
+        }
+    }
+}
+
+/// Logged record of a change let into a locked period.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockOverride {
+    pub id: Uuid,
+    /// The transfer recorded or changed
+    pub transfer_id: TransferId,
+    pub action: LockedAction,
+    pub reason: String,
+    pub author: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl LockOverride {
+    pub fn new(transfer_id: TransferId, action: LockedAction, reason: impl Into<String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            transfer_id,
+            action,
+            reason: reason.into(),
+            author: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn with_author(mut self, author: impl Into<String>) -> Self {
+        self.author = Some(author.into());
+        self
+    }
+}
+
+/// The lock in force for a scope (`None` for the ledger) at a point in time: the
+/// latest lock set for that scope at or before `at`, unless it lifted the lock.
+/// `locks` must be in the order they were set.
+pub fn lock_in_force(
+    locks: &[PeriodLock],
+    scope: Option<WalletId>,
+    at: DateTime<Utc>,
+) -> Option<&PeriodLock> {
+    locks
+        .iter()
+        .rfind(|lock| lock.wallet_id == scope && lock.locked_at <= at)
+        .filter(|lock| lock.lock_date.is_some())
+}
+
+/// The lock in force at `at` that closes the day of `timestamp` for a transfer
+/// touching `touched` wallets: the ledger lock, or the lock of one of the
+/// wallets or of a wallet above them.
+pub fn find_covering_lock<'a>(
+    locks: &'a [PeriodLock],
+    wallets: &[Wallet],
+    touched: &[&Wallet],
+    timestamp: DateTime<Utc>,
+    at: DateTime<Utc>,
+) -> Option<&'a PeriodLock> {
+    let mut scopes: Vec<Option<WalletId>> = vec![None];
+    for wallet in touched {
+        scopes.push(Some(wallet.id));
+        scopes.extend(
+            wallet_ancestors(wallet, wallets)
+                .iter()
+                .map(|ancestor| Some(ancestor.id)),
+        );
+    }
+    scopes
+        .into_iter()
+        .filter_map(|scope| lock_in_force(locks, scope, at))
+        .find(|lock| lock.covers(timestamp))
+}
+
+/// Count transfers recorded or amended inside a period that was already locked at
+/// the time, without a logged override. Locks and amendments must be in the order
+/// they were made.
+pub fn count_lock_violations(
+    locks: &[PeriodLock],
+    wallets: &[Wallet],
+    transfers: &[Transfer],
+    amendments: &[Amendment],
+    overridden: &HashSet<TransferId>,
+) -> i64 {
+    if locks.is_empty() {
+        return 0;
+    }
+    let wallet_refs = |transfer: &Transfer| -> Vec<&Wallet> {
+        wallets
+            .iter()
+            .filter(|w| w.id == transfer.from_wallet || w.id == transfer.to_wallet)
+            .collect()
+    };
+
+    let mut violations: HashSet<TransferId> = HashSet::new();
+    for transfer in transfers {
+        if overridden.contains(&transfer.id) {
+            continue;
+        }
+        let touched = wallet_refs(transfer);
+        if find_covering_lock(
+            locks,
+            wallets,
+            &touched,
+            transfer.timestamp,
+            transfer.recorded_at,
+        )
+        .is_some()
+        {
+            violations.insert(transfer.id);
+        }
+    }
+
+    for amendment in amendments {
+        if overridden.contains(&amendment.transfer_id) {
+            continue;
+        }
+        let Some(transfer) = transfers.iter().find(|t| t.id == amendment.transfer_id) else {
+            continue;
+        };
+        // A date correction touches both the day it left and the day it moved to
+        let mut dates: Vec<DateTime<Utc>> = amendment
+            .changes
+            .iter()
+            .filter(|change| change.field == AmendedField::Date)
+            .flat_map(|change| [&change.old_value, &change.new_value])
+            .filter_map(|value| value.as_deref())
+            .filter_map(|value| DateTime::parse_from_rfc3339(value).ok())
+            .map(|date| date.with_timezone(&Utc))
+            .collect();
+        if dates.is_empty() {
+            dates.push(transfer.timestamp);
+        }
+        let touched = wallet_refs(transfer);
+        if dates.iter().any(|date| {
+            find_covering_lock(locks, wallets, &touched, *date, amendment.amended_at).is_some()
+        }) {
+            violations.insert(transfer.id);
+        }
+    }
+
+    violations.len() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::WalletType;
+    use chrono::{Duration, TimeZone};
+
+    fn day(y: i32, m: u32, d: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_lock_in_force() {
+        let wallet = Uuid::new_v4();
+        let mut set = PeriodLock::new(None, NaiveDate::from_ymd_opt(2023, 12, 31));
+        set.locked_at = day(2024, 2, 1);
+        let mut lifted = PeriodLock::new(None, None);
+        lifted.locked_at = day(2024, 3, 1);
+        let mut wallet_lock = PeriodLock::new(Some(wallet), NaiveDate::from_ymd_opt(2024, 1, 31));
+        wallet_lock.locked_at = day(2024, 2, 1);
+        let locks = vec![set, wallet_lock, lifted];
+
+        assert!(lock_in_force(&locks, None, day(2024, 1, 15)).is_none());
+        let lock = lock_in_force(&locks, None, day(2024, 2, 15)).unwrap();
+        assert!(lock.covers(day(2023, 12, 31) + Duration::hours(23)));
+        assert!(!lock.covers(day(2024, 1, 1)));
+        assert!(lock_in_force(&locks, None, day(2024, 3, 15)).is_none());
+        assert!(lock_in_force(&locks, Some(wallet), day(2024, 3, 15)).is_some());
+    }
+
+    #[test]
+    fn test_count_lock_violations() {
+        let checking = Wallet::new("Checking".into(), WalletType::Asset, "EUR".into());
+        let expense = Wallet::new("Expense".into(), WalletType::Expense, "EUR".into());
+        let mut lock = PeriodLock::new(Some(checking.id), NaiveDate::from_ymd_opt(2023, 12, 31));
+        lock.locked_at = day(2024, 2, 1);
+
+        // Recorded before the lock was set: fine
+        let mut early = Transfer::new(checking.id, expense.id, 100, day(2023, 12, 1));
+        early.recorded_at = day(2024, 1, 5);
+        // Backdated into the locked year after the lock was set
+        let mut late = Transfer::new(checking.id, expense.id, 100, day(2023, 12, 2));
+        late.recorded_at = day(2024, 2, 5);
+        // Same, but let through with an override
+        let mut overridden = Transfer::new(checking.id, expense.id, 100, day(2023, 12, 3));
+        overridden.recorded_at = day(2024, 2, 5);
+
+        let wallets = vec![checking, expense];
+        let transfers = vec![early.clone(), late, overridden.clone()];
+        let mut edited = early.clone();
+        edited.description = Some("fixed".into());
+        let mut amendment = Amendment::between(&early, &edited).unwrap();
+        amendment.amended_at = day(2024, 2, 6);
+
+        let skip = HashSet::from([overridden.id]);
+        assert_eq!(
+            count_lock_violations(&[lock.clone()], &wallets, &transfers, &[], &skip),
+            1
+        );
+        assert_eq!(
+            count_lock_violations(&[lock], &wallets, &transfers, &[amendment], &skip),
+            2
+        );
+    }
+}
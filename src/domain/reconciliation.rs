@@ -0,0 +1,166 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{Cents, Transfer, WalletId};
+
+pub type ReconciliationId = Uuid;
+
+/// How far a transfer has been matched against a bank statement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClearedState {
+    /// Recorded in the ledger, not yet seen on a statement
+    #[default]
+    Uncleared,
+    /// Seen on a statement, not yet part of a finished reconciliation
+    Cleared,
+    /// Locked in by a finished reconciliation
+    Reconciled,
+}
+
+impl ClearedState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClearedState::Uncleared => "uncleared",
+            ClearedState::Cleared => "cleared",
+            ClearedState::Reconciled => "reconciled",
+        }
+    }
+
+    /// One-letter marker for transfer lists: blank, `c` or `R`
+    pub fn marker(&self) -> char {
+        match self {
+            ClearedState::Uncleared => ' ',
+            ClearedState::Cleared => 'c',
+            ClearedState::Reconciled => 'R',
+        }
+    }
+
+    /// Returns true if the transfer has shown up on a statement
+    pub fn is_cleared(&self) -> bool {
+        *self != ClearedState::Uncleared
+    }
+}
+
+impl std::fmt::Display for ClearedState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for ClearedState {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "uncleared" => Ok(ClearedState::Uncleared),
+            "cleared" => Ok(ClearedState::Cleared),
+            "reconciled" => Ok(ClearedState::Reconciled),
+            _ => Err(format!("Invalid cleared state: {}", s)),
+        }
+    }
+}
+
+/// A reconcile session: a wallet's cleared transfers checked against the
+/// ending balance of a bank statement. Finishing it reconciles them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reconciliation {
+    pub id: ReconciliationId,
+    pub wallet_id: WalletId,
+    /// Last day covered by the statement
+    pub statement_date: DateTime<Utc>,
+    /// Ending balance printed on the statement
+    pub statement_balance: Cents,
+    pub started_at: DateTime<Utc>,
+    /// Set once the session is finished; open sessions have none
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl Reconciliation {
+    pub fn new(
+        wallet_id: WalletId,
+        statement_date: DateTime<Utc>,
+        statement_balance: Cents,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            wallet_id,
+            statement_date,
+            statement_balance,
+            started_at: Utc::now(),
+            finished_at: None,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished_at.is_some()
+    }
+
+    /// Returns true if a transfer at `timestamp` can appear on the statement
+    /// (any time up to the end of the statement date).
+    pub fn covers(&self, timestamp: DateTime<Utc>) -> bool {
+        timestamp < self.statement_date + chrono::Duration::days(1)
+    }
+
+    /// What is still unexplained: the statement balance minus the cleared balance.
+    pub fn difference(&self, cleared_balance: Cents) -> Cents {
+        self.statement_balance - cleared_balance
+    }
+}
+
+/// Balance of a wallet counting only transfers that have cleared.
+pub fn cleared_balance(wallet_id: WalletId, transfers: &[Transfer]) -> Cents {
+    transfers
+        .iter()
+        .filter(|t| t.cleared.is_cleared())
+        .fold(0, |balance, t| {
+            if t.to_wallet == wallet_id {
+                balance + t.amount_cents
+            } else if t.from_wallet == wallet_id {
+                balance - t.amount_cents
+            } else {
+                balance
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cleared_state_roundtrip() {
+        for state in [
+            ClearedState::Uncleared,
+            ClearedState::Cleared,
+            ClearedState::Reconciled,
+        ] {
+            assert_eq!(state.as_str().parse::<ClearedState>(), Ok(state));
+        }
+        assert!("pending".parse::<ClearedState>().is_err());
+        assert!(!ClearedState::Uncleared.is_cleared());
+        assert!(ClearedState::Reconciled.is_cleared());
+    }
+
+    #[test]
+    fn test_cleared_balance_and_difference() {
+        let bank = Uuid::new_v4();
+        let other = Uuid::new_v4();
+        let now = Utc::now();
+        let transfers = vec![
+            Transfer::new(other, bank, 10000, now).with_cleared(ClearedState::Reconciled),
+            Transfer::new(bank, other, 2500, now).with_cleared(ClearedState::Cleared),
+            Transfer::new(bank, other, 999, now),
+        ];
+
+        let balance = cleared_balance(bank, &transfers);
+        assert_eq!(balance, 7500);
+
+        let session = Reconciliation::new(bank, now, 7000);
+        assert_eq!(session.difference(balance), -500);
+        assert!(!session.is_finished());
+        assert!(session.covers(now + chrono::Duration::hours(23)));
+        assert!(!session.covers(now + chrono::Duration::days(1)));
+    }
+}
@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Cents, Transfer, WalletId};
+
+/// One line of a wallet register: a transfer seen from the wallet, with the
+/// balance it left behind, as on a bank statement.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegisterEntry {
+    pub transfer: Transfer,
+    /// The other wallet of the transfer
+    pub counterpart: WalletId,
+    /// Change to the wallet's balance: positive for money in, negative for money out
+    pub amount_cents: Cents,
+    /// Balance of the wallet after the transfer
+    pub balance: Cents,
+}
+
+/// Register of `wallet_id` over `transfers`, in date order (ties in recording
+/// order), starting from `opening_balance`. Transfers not touching the wallet
+/// are left out.
+pub fn build_register(
+    wallet_id: WalletId,
+    opening_balance: Cents,
+    mut transfers: Vec<Transfer>,
+) -> Vec<RegisterEntry> {
+    transfers.retain(|t| t.from_wallet == wallet_id || t.to_wallet == wallet_id);
+    transfers.sort_by_key(|t| (t.timestamp, t.sequence));
+
+    let mut balance = opening_balance;
+    transfers
+        .into_iter()
+        .map(|transfer| {
+            let (counterpart, amount_cents) = if transfer.to_wallet == wallet_id {
+                (transfer.from_wallet, transfer.amount_cents)
+            } else {
+                (transfer.to_wallet, -transfer.amount_cents)
+            };
+            balance += amount_cents;
+            RegisterEntry {
+                transfer,
+                counterpart,
+                amount_cents,
+                balance,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::start_of_day;
+    use chrono::{DateTime, NaiveDate, Utc};
+    use uuid::Uuid;
+
+    fn at(date: &str) -> DateTime<Utc> {
+        start_of_day(NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap())
+    }
+
+    #[test]
+    fn test_register_runs_the_balance_in_date_order() {
+        let checking = Uuid::new_v4();
+        let income = Uuid::new_v4();
+        let expense = Uuid::new_v4();
+
+        let mut rent = Transfer::new(checking, expense, 50000, at("2024-02-01"));
+        rent.sequence = 1;
+        // Recorded later, but dated first
+        let mut salary = Transfer::new(income, checking, 300000, at("2024-01-31"));
+        salary.sequence = 2;
+        let mut unrelated = Transfer::new(income, expense, 100, at("2024-01-15"));
+        unrelated.sequence = 3;
+
+        let register = build_register(checking, 1000, vec![rent, salary, unrelated]);
+        assert_eq!(register.len(), 2);
+        assert_eq!(register[0].counterpart, income);
+        assert_eq!(register[0].amount_cents, 300000);
+        assert_eq!(register[0].balance, 301000);
+        assert_eq!(register[1].counterpart, expense);
+        assert_eq!(register[1].amount_cents, -50000);
+        assert_eq!(register[1].balance, 251000);
+    }
+}
@@ -99,6 +99,9 @@ pub struct ScheduledTransfer {
     pub last_executed_at: Option<DateTime<Utc>>,
     pub description: Option<String>,
     pub category: Option<String>,
+    /// Tags given to every transfer the schedule creates
+    #[serde(default)]
+    pub tags: Vec<String>,
     pub status: ScheduleStatus,
     pub created_at: DateTime<Utc>,
 }
@@ -125,6 +128,7 @@ impl ScheduledTransfer {
             last_executed_at: None,
             description: None,
             category: None,
+            tags: Vec::new(),
             status: ScheduleStatus::Active,
             created_at: Utc::now(),
         }
@@ -148,6 +152,12 @@ impl ScheduledTransfer {
         self
     }
 
+    /// Set tags
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
     /// Calculate the next execution date after a given reference date
     pub fn next_execution_date(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
         // If completed or paused, no next execution
@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+
+/// Clean up tags as typed: each is trimmed and lowercased, and repeats are
+/// dropped (first occurrence wins). Empty tags and tags containing the `,` or `;`
+/// used to list tags in exports are rejected.
+pub fn normalize_tags<S: AsRef<str>>(tags: &[S]) -> Result<Vec<String>, String> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = tag.as_ref().trim().to_lowercase();
+        if tag.is_empty() {
+            return Err("tags cannot be empty".to_string());
+        }
+        if tag.contains([',', ';']) {
+            return Err(format!("'{}' contains a ',' or ';'", tag));
+        }
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    Ok(normalized)
+}
+
+/// How a transfer filter with several tags matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagMatch {
+    /// Transfers carrying at least one of the tags
+    #[default]
+    Any,
+    /// Transfers carrying every tag
+    All,
+}
+
+impl TagMatch {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TagMatch::Any => "any",
+            TagMatch::All => "all",
+        }
+    }
+
+    /// Returns true if a transfer with `tags` matches the `wanted` tags.
+    /// An empty `wanted` list matches everything.
+    pub fn matches(&self, tags: &[String], wanted: &[String]) -> bool {
+        if wanted.is_empty() {
+            return true;
+        }
+        match self {
+            TagMatch::Any => wanted.iter().any(|tag| tags.contains(tag)),
+            TagMatch::All => wanted.iter().all(|tag| tags.contains(tag)),
+        }
+    }
+}
+
+impl std::fmt::Display for TagMatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for TagMatch {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "any" => Ok(TagMatch::Any),
+            "all" => Ok(TagMatch::All),
+            _ => Err(format!("Invalid tag match: {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_tags() {
+        assert_eq!(
+            normalize_tags(&[" Vacation", "italy ", "vacation"]).unwrap(),
+            vec!["vacation", "italy"]
+        );
+        assert!(normalize_tags::<&str>(&[]).unwrap().is_empty());
+        assert!(normalize_tags(&["  "]).is_err());
+        assert!(normalize_tags(&["a,b"]).is_err());
+        assert!(normalize_tags(&["a;b"]).is_err());
+    }
+
+    #[test]
+    fn test_tag_match() {
+        let tags = vec!["vacation".to_string(), "italy".to_string()];
+        let wanted = vec!["italy".to_string(), "work".to_string()];
+        assert!(TagMatch::Any.matches(&tags, &wanted));
+        assert!(!TagMatch::All.matches(&tags, &wanted));
+        assert!(TagMatch::All.matches(&tags, &tags));
+        assert!(TagMatch::All.matches(&tags, &[]));
+        assert_eq!("ALL".parse::<TagMatch>().unwrap(), TagMatch::All);
+        assert!("some".parse::<TagMatch>().is_err());
+    }
+}
@@ -2,12 +2,16 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use super::{Cents, WalletId};
+use super::{Cents, ClearedState, PayeeId, WalletId};
 
 pub type TransferId = Uuid;
 
+/// Identifier shared by all legs of a split transfer.
+pub type SplitId = Uuid;
+
 /// A transfer represents an atomic movement of money from one wallet to another.
-/// Transfers are immutable - corrections are made via compensating transfers (reversals).
+/// Amounts are undone with compensating transfers (reversals); typos and wrong dates are
+/// corrected in place, with every correction kept as an append-only [`Amendment`](super::Amendment).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transfer {
     pub id: TransferId,
@@ -25,7 +29,8 @@ pub struct Transfer {
     pub recorded_at: DateTime<Utc>,
     /// Human-readable description
     pub description: Option<String>,
-    /// Category for budgeting/reporting (e.g., "groceries", "utilities")
+    /// Category for budgeting/reporting (e.g., "utilities"); levels are separated by
+    /// colons, as in "food:groceries"
     pub category: Option<String>,
     /// Additional tags for filtering/reporting
     pub tags: Vec<String>,
@@ -33,6 +38,20 @@ pub struct Transfer {
     pub reverses: Option<TransferId>,
     /// External reference (bank transaction ID, receipt number, etc.)
     pub external_ref: Option<String>,
+    /// If this transfer is one leg of a split, the ID shared by all its legs
+    pub split_id: Option<SplitId>,
+    /// Units of the commodity bought or sold, in the commodity's minor units
+    #[serde(default)]
+    pub quantity: Option<i64>,
+    /// Price paid or received per unit, in the holding wallet's currency
+    #[serde(default)]
+    pub unit_price: Option<f64>,
+    /// Whether the transfer has shown up on a bank statement
+    #[serde(default)]
+    pub cleared: ClearedState,
+    /// Who the money was paid to or received from
+    #[serde(default)]
+    pub payee_id: Option<PayeeId>,
 }
 
 impl Transfer {
@@ -57,6 +76,11 @@ impl Transfer {
             tags: Vec::new(),
             reverses: None,
             external_ref: None,
+            split_id: None,
+            quantity: None,
+            unit_price: None,
+            cleared: ClearedState::Uncleared,
+            payee_id: None,
         }
     }
 
@@ -85,14 +109,46 @@ impl Transfer {
         self
     }
 
+    pub fn with_split(mut self, split_id: SplitId) -> Self {
+        self.split_id = Some(split_id);
+        self
+    }
+
+    pub fn with_quantity(mut self, quantity: i64, unit_price: f64) -> Self {
+        self.quantity = Some(quantity);
+        self.unit_price = Some(unit_price);
+        self
+    }
+
+    pub fn with_cleared(mut self, cleared: ClearedState) -> Self {
+        self.cleared = cleared;
+        self
+    }
+
+    pub fn with_payee(mut self, payee_id: PayeeId) -> Self {
+        self.payee_id = Some(payee_id);
+        self
+    }
+
+    /// Returns true if this transfer buys or sells units of a commodity
+    pub fn is_trade(&self) -> bool {
+        self.quantity.is_some()
+    }
+
     /// Returns true if this transfer is a reversal of another transfer
     pub fn is_reversal(&self) -> bool {
         self.reverses.is_some()
     }
 
-    /// Create a full reversal of this transfer (swaps from/to wallets)
+    /// Returns true if this transfer is one leg of a split transfer
+    pub fn is_split_leg(&self) -> bool {
+        self.split_id.is_some()
+    }
+
+    /// Create a full reversal of this transfer (swaps from/to wallets).
+    /// A trade's units move back along with the cash.
     pub fn create_reversal(&self) -> Self {
-        Transfer::new(
+        let mut reversal = Transfer::new(
             self.to_wallet,
             self.from_wallet,
             self.amount_cents,
@@ -102,7 +158,10 @@ impl Transfer {
         .with_description(format!(
             "Reversal of: {}",
             self.description.as_deref().unwrap_or("(no description)")
-        ))
+        ));
+        reversal.quantity = self.quantity;
+        reversal.unit_price = self.unit_price;
+        reversal
     }
 
     /// Create a partial reversal of this transfer
@@ -168,6 +227,30 @@ mod tests {
         assert_eq!(partial.reverses, Some(original.id));
     }
 
+    #[test]
+    fn test_split_legs_share_split_id() {
+        let (from, to) = sample_wallet_ids();
+        let split_id = Uuid::new_v4();
+        let leg = Transfer::new(from, to, 2500, Utc::now()).with_split(split_id);
+
+        assert!(leg.is_split_leg());
+        assert_eq!(leg.split_id, Some(split_id));
+        assert!(!Transfer::new(from, to, 2500, Utc::now()).is_split_leg());
+    }
+
+    #[test]
+    fn test_trade_reversal_moves_units_back() {
+        let (cash, holding) = sample_wallet_ids();
+        let buy = Transfer::new(cash, holding, 49250, Utc::now()).with_quantity(500, 98.5);
+        assert!(buy.is_trade());
+
+        let reversal = buy.create_reversal();
+        assert_eq!(reversal.from_wallet, holding);
+        assert_eq!(reversal.quantity, Some(500));
+        assert_eq!(reversal.unit_price, Some(98.5));
+        assert!(!Transfer::new(cash, holding, 100, Utc::now()).is_trade());
+    }
+
     #[test]
     #[should_panic(expected = "Transfer amount must be positive")]
     fn test_transfer_requires_positive_amount() {
@@ -1,7 +1,10 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
+use super::{Cents, LotMethod};
+
 pub type WalletId = Uuid;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -71,6 +74,18 @@ pub struct Wallet {
     pub description: Option<String>,
     pub created_at: DateTime<Utc>,
     pub archived_at: Option<DateTime<Utc>>,
+    /// Commodity held in units (e.g. a fund ticker); `None` for cash wallets
+    #[serde(default)]
+    pub commodity: Option<String>,
+    /// How sales from this holding pick lots by default
+    #[serde(default)]
+    pub lot_method: LotMethod,
+    /// Wallet this one is filed under; `None` for a top-level wallet
+    #[serde(default)]
+    pub parent_id: Option<WalletId>,
+    /// Accept transfers even while the wallet has sub-wallets
+    #[serde(default)]
+    pub allow_postings: bool,
 }
 
 impl Wallet {
@@ -86,6 +101,10 @@ impl Wallet {
             description: None,
             created_at: Utc::now(),
             archived_at: None,
+            commodity: None,
+            lot_method: LotMethod::default(),
+            parent_id: None,
+            allow_postings: false,
         }
     }
 
@@ -99,6 +118,31 @@ impl Wallet {
         self
     }
 
+    pub fn with_commodity(mut self, commodity: impl Into<String>) -> Self {
+        self.commodity = Some(commodity.into().to_uppercase());
+        self
+    }
+
+    pub fn with_lot_method(mut self, method: LotMethod) -> Self {
+        self.lot_method = method;
+        self
+    }
+
+    pub fn with_parent(mut self, parent_id: WalletId) -> Self {
+        self.parent_id = Some(parent_id);
+        self
+    }
+
+    pub fn with_allow_postings(mut self, allow: bool) -> Self {
+        self.allow_postings = allow;
+        self
+    }
+
+    /// Returns true if this wallet holds units of a commodity rather than cash
+    pub fn is_holding(&self) -> bool {
+        self.commodity.is_some()
+    }
+
     pub fn is_archived(&self) -> bool {
         self.archived_at.is_some()
     }
@@ -108,6 +152,128 @@ impl Wallet {
     }
 }
 
+/// Separates the levels of a wallet path, e.g. "Bank > Checking".
+pub const WALLET_PATH_SEPARATOR: &str = " > ";
+
+/// The ancestors of a wallet, nearest first. Parents missing from `wallets` end
+/// the chain, and so does a wallet seen twice, so a damaged tree cannot loop.
+pub fn wallet_ancestors<'a>(wallet: &Wallet, wallets: &'a [Wallet]) -> Vec<&'a Wallet> {
+    let by_id: HashMap<WalletId, &Wallet> = wallets.iter().map(|w| (w.id, w)).collect();
+    let mut seen = HashSet::from([wallet.id]);
+    let mut ancestors = Vec::new();
+    let mut parent_id = wallet.parent_id;
+    while let Some(parent) = parent_id.and_then(|id| by_id.get(&id)) {
+        if !seen.insert(parent.id) {
+            break;
+        }
+        ancestors.push(*parent);
+        parent_id = parent.parent_id;
+    }
+    ancestors
+}
+
+/// Full path of a wallet from its top-level ancestor ("Bank > Checking").
+pub fn wallet_path(wallet: &Wallet, wallets: &[Wallet]) -> String {
+    let mut names: Vec<&str> = wallet_ancestors(wallet, wallets)
+        .iter()
+        .rev()
+        .map(|w| w.name.as_str())
+        .collect();
+    names.push(&wallet.name);
+    names.join(WALLET_PATH_SEPARATOR)
+}
+
+/// Count wallets whose place in the tree is invalid: a parent that does not exist,
+/// has another type or currency, or is the wallet's own descendant.
+pub fn count_invalid_wallet_parents(wallets: &[Wallet]) -> i64 {
+    let by_id: HashMap<WalletId, &Wallet> = wallets.iter().map(|w| (w.id, w)).collect();
+    wallets
+        .iter()
+        .filter(|wallet| {
+            let Some(parent_id) = wallet.parent_id else {
+                return false;
+            };
+            match by_id.get(&parent_id) {
+                None => true,
+                Some(parent) => {
+                    parent.wallet_type != wallet.wallet_type
+                        || parent.currency != wallet.currency
+                        || wallet_ancestors(wallet, wallets)
+                            .iter()
+                            .any(|ancestor| ancestor.parent_id == Some(wallet.id))
+                }
+            }
+        })
+        .count() as i64
+}
+
+/// A wallet's place in the wallet tree, with the balances of its subtree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalletTreeEntry {
+    pub wallet_id: WalletId,
+    /// 0 for a top-level wallet
+    pub depth: usize,
+    /// The wallet's own balance
+    pub balance: Cents,
+    /// Balance of the wallet and all its sub-wallets
+    pub subtotal: Cents,
+    pub has_children: bool,
+}
+
+/// Walk the wallet tree depth first, parents before their children and siblings
+/// by name. Wallets whose parent is not in `wallets` are shown at the top level.
+pub fn wallet_tree(
+    wallets: &[Wallet],
+    balances: &HashMap<WalletId, Cents>,
+) -> Vec<WalletTreeEntry> {
+    let ids: HashSet<WalletId> = wallets.iter().map(|w| w.id).collect();
+    let mut children: HashMap<Option<WalletId>, Vec<&Wallet>> = HashMap::new();
+    for wallet in wallets {
+        let parent = wallet.parent_id.filter(|id| ids.contains(id));
+        children.entry(parent).or_default().push(wallet);
+    }
+    for siblings in children.values_mut() {
+        siblings.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    let mut entries = Vec::with_capacity(wallets.len());
+    let mut visited = HashSet::new();
+    for root in children.get(&None).cloned().unwrap_or_default() {
+        push_subtree(root, 0, &children, balances, &mut visited, &mut entries);
+    }
+    entries
+}
+
+fn push_subtree(
+    wallet: &Wallet,
+    depth: usize,
+    children: &HashMap<Option<WalletId>, Vec<&Wallet>>,
+    balances: &HashMap<WalletId, Cents>,
+    visited: &mut HashSet<WalletId>,
+    entries: &mut Vec<WalletTreeEntry>,
+) -> Cents {
+    if !visited.insert(wallet.id) {
+        return 0;
+    }
+    let balance = balances.get(&wallet.id).copied().unwrap_or(0);
+    let position = entries.len();
+    entries.push(WalletTreeEntry {
+        wallet_id: wallet.id,
+        depth,
+        balance,
+        subtotal: balance,
+        has_children: false,
+    });
+
+    let mut subtotal = balance;
+    for child in children.get(&Some(wallet.id)).into_iter().flatten() {
+        entries[position].has_children = true;
+        subtotal += push_subtree(child, depth + 1, children, balances, visited, entries);
+    }
+    entries[position].subtotal = subtotal;
+    subtotal
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -161,4 +327,77 @@ mod tests {
         assert!(WalletType::Expense.is_external());
         assert!(WalletType::Equity.is_external());
     }
+
+    fn bank_tree() -> Vec<Wallet> {
+        let bank = Wallet::new("Bank".into(), WalletType::Asset, "EUR".into());
+        let savings =
+            Wallet::new("Savings".into(), WalletType::Asset, "EUR".into()).with_parent(bank.id);
+        let checking =
+            Wallet::new("Checking".into(), WalletType::Asset, "EUR".into()).with_parent(bank.id);
+        let holiday =
+            Wallet::new("Holiday".into(), WalletType::Asset, "EUR".into()).with_parent(savings.id);
+        let cash = Wallet::new("Cash".into(), WalletType::Asset, "EUR".into());
+        vec![holiday, savings, cash, bank, checking]
+    }
+
+    #[test]
+    fn test_wallet_path() {
+        let wallets = bank_tree();
+        assert_eq!(
+            wallet_path(&wallets[0], &wallets),
+            "Bank > Savings > Holiday"
+        );
+        assert_eq!(wallet_path(&wallets[2], &wallets), "Cash");
+        assert_eq!(wallet_ancestors(&wallets[0], &wallets).len(), 2);
+    }
+
+    #[test]
+    fn test_wallet_tree_subtotals() {
+        let wallets = bank_tree();
+        let balances: HashMap<WalletId, Cents> = HashMap::from([
+            (wallets[0].id, 500),
+            (wallets[1].id, 1000),
+            (wallets[2].id, 50),
+            (wallets[4].id, 2000),
+        ]);
+
+        let tree = wallet_tree(&wallets, &balances);
+        let name_of = |id: WalletId| {
+            wallets
+                .iter()
+                .find(|w| w.id == id)
+                .map(|w| w.name.as_str())
+                .unwrap()
+        };
+        let rows: Vec<(&str, usize, Cents, Cents)> = tree
+            .iter()
+            .map(|e| (name_of(e.wallet_id), e.depth, e.balance, e.subtotal))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("Bank", 0, 0, 3500),
+                ("Checking", 1, 2000, 2000),
+                ("Savings", 1, 1000, 1500),
+                ("Holiday", 2, 500, 500),
+                ("Cash", 0, 50, 50),
+            ]
+        );
+        assert!(tree[0].has_children);
+        assert!(!tree[1].has_children);
+    }
+
+    #[test]
+    fn test_count_invalid_wallet_parents() {
+        let mut wallets = bank_tree();
+        assert_eq!(count_invalid_wallet_parents(&wallets), 0);
+
+        // Bank under its own grandchild, and Cash in another currency under Bank
+        wallets[3].parent_id = Some(wallets[0].id);
+        assert_eq!(count_invalid_wallet_parents(&wallets), 3);
+        wallets[3].parent_id = None;
+        wallets[2].currency = "USD".into();
+        wallets[2].parent_id = Some(wallets[3].id);
+        assert_eq!(count_invalid_wallet_parents(&wallets), 1);
+    }
 }
@@ -0,0 +1,185 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use super::{Cents, SplitId, Transfer, Wallet, WalletId, WalletType};
+
+/// A closed financial year. Closing moves the year's income and expense balances
+/// into the retained earnings equity wallets; the closing entries share a split ID
+/// so they can be told apart (and reversed) as one transaction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YearClose {
+    pub year: i32,
+    pub closed_at: DateTime<Utc>,
+    /// Split shared by the closing entries
+    pub closing_split_id: SplitId,
+    /// Split shared by the opening-balance transfers that replaced the year's
+    /// history when it was archived
+    pub opening_split_id: Option<SplitId>,
+    /// Read-only database file holding the archived history
+    pub archive_path: Option<String>,
+}
+
+impl YearClose {
+    pub fn new(year: i32) -> Self {
+        Self {
+            year,
+            closed_at: Utc::now(),
+            closing_split_id: Uuid::new_v4(),
+            opening_split_id: None,
+            archive_path: None,
+        }
+    }
+
+    /// First instant after the year
+    pub fn end(&self) -> DateTime<Utc> {
+        year_start(self.year + 1)
+    }
+
+    /// When closing and opening entries are dated: the last second of the year
+    pub fn closing_timestamp(&self) -> DateTime<Utc> {
+        self.end() - Duration::seconds(1)
+    }
+
+    pub fn is_archived(&self) -> bool {
+        self.archive_path.is_some()
+    }
+}
+
+/// First instant of a year (UTC)
+pub fn year_start(year: i32) -> DateTime<Utc> {
+    NaiveDate::from_ymd_opt(year, 1, 1)
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|date| date.and_utc())
+        .unwrap_or(DateTime::<Utc>::MIN_UTC)
+}
+
+/// Transfer that changes the balance of `wallet_id` by `amount_cents`, booked
+/// against `counterpart`: money comes in from the counterpart for a positive
+/// amount and goes out to it for a negative one. `None` for zero.
+pub fn settle_balance(
+    wallet_id: WalletId,
+    counterpart: WalletId,
+    amount_cents: Cents,
+    timestamp: DateTime<Utc>,
+) -> Option<Transfer> {
+    match amount_cents {
+        0 => None,
+        amount if amount > 0 => Some(Transfer::new(counterpart, wallet_id, amount, timestamp)),
+        amount => Some(Transfer::new(wallet_id, counterpart, -amount, timestamp)),
+    }
+}
+
+/// Closing entries that bring every income and expense wallet back to zero,
+/// each against the retained earnings wallet of its currency.
+pub fn closing_entries(
+    wallets: &[Wallet],
+    balances: &HashMap<WalletId, Cents>,
+    retained_earnings: &HashMap<String, WalletId>,
+    timestamp: DateTime<Utc>,
+) -> Vec<Transfer> {
+    wallets
+        .iter()
+        .filter(|w| matches!(w.wallet_type, WalletType::Income | WalletType::Expense))
+        .filter_map(|wallet| {
+            let balance = balances.get(&wallet.id).copied().unwrap_or(0);
+            let retained = retained_earnings.get(&wallet.currency)?;
+            settle_balance(wallet.id, *retained, -balance, timestamp)
+        })
+        .collect()
+}
+
+/// Opening-balance transfers that carry the balance of every wallet into a ledger
+/// that starts after `timestamp`, each against the opening balances wallet of its
+/// currency. The opening balances wallets themselves end up with their own
+/// balance, since every transfer books the opposite amount to them.
+pub fn opening_entries(
+    wallets: &[Wallet],
+    balances: &HashMap<WalletId, Cents>,
+    opening_balances: &HashMap<String, WalletId>,
+    timestamp: DateTime<Utc>,
+) -> Vec<Transfer> {
+    let counterparts: Vec<WalletId> = opening_balances.values().copied().collect();
+    wallets
+        .iter()
+        .filter(|wallet| !counterparts.contains(&wallet.id))
+        .filter_map(|wallet| {
+            let balance = balances.get(&wallet.id).copied().unwrap_or(0);
+            let counterpart = opening_balances.get(&wallet.currency)?;
+            settle_balance(wallet.id, *counterpart, balance, timestamp)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_year_bounds() {
+        let close = YearClose::new(2023);
+        assert_eq!(close.end(), year_start(2024));
+        assert_eq!(
+            close.closing_timestamp().to_rfc3339(),
+            "2023-12-31T23:59:59+00:00"
+        );
+    }
+
+    #[test]
+    fn test_closing_entries_zero_income_and_expenses() {
+        let checking = Wallet::new("Checking".into(), WalletType::Asset, "EUR".into());
+        let salary = Wallet::new("Salary".into(), WalletType::Income, "EUR".into());
+        let rent = Wallet::new("Rent".into(), WalletType::Expense, "EUR".into());
+        let retained = Wallet::new("Retained".into(), WalletType::Equity, "EUR".into());
+        let balances = HashMap::from([
+            (checking.id, 150000),
+            (salary.id, -300000),
+            (rent.id, 150000),
+        ]);
+        let retained_earnings = HashMap::from([("EUR".to_string(), retained.id)]);
+        let timestamp = YearClose::new(2023).closing_timestamp();
+
+        let wallets = vec![checking, salary.clone(), rent.clone(), retained.clone()];
+        let entries = closing_entries(&wallets, &balances, &retained_earnings, timestamp);
+        assert_eq!(entries.len(), 2);
+
+        let mut after = balances.clone();
+        for entry in &entries {
+            *after.entry(entry.from_wallet).or_insert(0) -= entry.amount_cents;
+            *after.entry(entry.to_wallet).or_insert(0) += entry.amount_cents;
+        }
+        assert_eq!(after[&salary.id], 0);
+        assert_eq!(after[&rent.id], 0);
+        // Net income of the year
+        assert_eq!(after[&retained.id], -150000);
+    }
+
+    #[test]
+    fn test_opening_entries_rebuild_balances() {
+        let checking = Wallet::new("Checking".into(), WalletType::Asset, "EUR".into());
+        let card = Wallet::new("Card".into(), WalletType::Liability, "EUR".into());
+        let retained = Wallet::new("Retained".into(), WalletType::Equity, "EUR".into());
+        let opening = Wallet::new("Opening".into(), WalletType::Equity, "EUR".into());
+        let balances = HashMap::from([
+            (checking.id, 150000),
+            (card.id, -20000),
+            (retained.id, -130000),
+        ]);
+        let opening_balances = HashMap::from([("EUR".to_string(), opening.id)]);
+        let timestamp = YearClose::new(2023).closing_timestamp();
+
+        let wallets = vec![checking, card, retained, opening.clone()];
+        let entries = opening_entries(&wallets, &balances, &opening_balances, timestamp);
+        assert_eq!(entries.len(), 3);
+
+        let mut rebuilt: HashMap<WalletId, Cents> = HashMap::new();
+        for entry in &entries {
+            *rebuilt.entry(entry.from_wallet).or_insert(0) -= entry.amount_cents;
+            *rebuilt.entry(entry.to_wallet).or_insert(0) += entry.amount_cents;
+        }
+        assert_eq!(rebuilt[&opening.id], 0);
+        rebuilt.remove(&opening.id);
+        assert_eq!(rebuilt, balances);
+    }
+}
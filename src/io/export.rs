@@ -1,10 +1,15 @@
 use anyhow::Result;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::io::Write;
 
 use crate::application::LedgerService;
-use crate::domain::{Budget, ScheduledTransfer, Transfer, Wallet};
+use crate::domain::{
+    Amendment, Attachment, BalanceAssertion, Budget, Cents, CommodityPrice, Currency, Envelope,
+    EnvelopeAssignment, ExchangeRate, LocaleProfile, LockOverride, LotDisposal, Payee, PeriodLock,
+    Reconciliation, ScheduledTransfer, Transfer, Wallet, YearClose,
+};
 
 /// Database snapshot for full export/import
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,21 +20,109 @@ pub struct DatabaseSnapshot {
     pub transfers: Vec<Transfer>,
     pub budgets: Vec<Budget>,
     pub scheduled_transfers: Vec<ScheduledTransfer>,
+    #[serde(default)]
+    pub exchange_rates: Vec<ExchangeRate>,
+    #[serde(default)]
+    pub commodity_prices: Vec<CommodityPrice>,
+    #[serde(default)]
+    pub lot_disposals: Vec<LotDisposal>,
+    #[serde(default)]
+    pub reconciliations: Vec<Reconciliation>,
+    #[serde(default)]
+    pub amendments: Vec<Amendment>,
+    #[serde(default)]
+    pub payees: Vec<Payee>,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    /// Content of the attached files, one entry per distinct file
+    #[serde(default)]
+    pub attachment_blobs: Vec<AttachmentBlob>,
+    #[serde(default)]
+    pub period_locks: Vec<PeriodLock>,
+    #[serde(default)]
+    pub lock_overrides: Vec<LockOverride>,
+    #[serde(default)]
+    pub year_closes: Vec<YearClose>,
+    #[serde(default)]
+    pub balance_assertions: Vec<BalanceAssertion>,
+    #[serde(default)]
+    pub envelopes: Vec<Envelope>,
+    #[serde(default)]
+    pub envelope_assignments: Vec<EnvelopeAssignment>,
+}
+
+/// Content of an attached file in a snapshot, base64 encoded in JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentBlob {
+    /// SHA-256 of the content, as referenced by `Attachment::hash`
+    pub hash: String,
+    #[serde(with = "base64_data")]
+    pub data: Vec<u8>,
+}
+
+mod base64_data {
+    use base64::Engine;
+    use base64::engine::general_purpose::STANDARD;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(data))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        STANDARD.decode(encoded).map_err(serde::de::Error::custom)
+    }
 }
 
 /// Exporter for converting ledger data to various formats
 pub struct Exporter<'a> {
     service: &'a LedgerService,
+    locale: Option<LocaleProfile>,
 }
 
 impl<'a> Exporter<'a> {
     pub fn new(service: &'a LedgerService) -> Self {
-        Self { service }
+        Self {
+            service,
+            locale: None,
+        }
+    }
+
+    /// Write CSV amounts as localized decimals instead of integer minor units.
+    /// JSON exports are not affected.
+    pub fn with_locale(mut self, locale: Option<LocaleProfile>) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Header for amount columns: decimals when localized, minor units otherwise
+    fn amount_header(&self) -> &'static str {
+        if self.locale.is_some() {
+            "amount"
+        } else {
+            "amount_cents"
+        }
+    }
+
+    fn amount_field(&self, amount: Cents, currency: &Currency) -> String {
+        match &self.locale {
+            Some(locale) => locale.format_amount(amount, currency),
+            None => amount.to_string(),
+        }
     }
 
     /// Export transfers to CSV format
     pub async fn export_transfers_csv<W: Write>(&self, writer: W) -> Result<usize> {
         let transfers = self.service.list_all_transfers().await?;
+        let currencies = self.service.get_wallet_currencies().await?;
+        let payees: HashMap<_, _> = self
+            .service
+            .list_payees()
+            .await?
+            .into_iter()
+            .map(|payee| (payee.id, payee.name))
+            .collect();
         let mut csv_writer = csv::Writer::from_writer(writer);
 
         // Write header
@@ -39,12 +132,14 @@ impl<'a> Exporter<'a> {
             "timestamp",
             "from_wallet",
             "to_wallet",
-            "amount_cents",
+            self.amount_header(),
             "description",
             "category",
             "tags",
             "reverses",
             "external_ref",
+            "split_id",
+            "payee",
         ])?;
 
         let mut count = 0;
@@ -59,7 +154,7 @@ impl<'a> Exporter<'a> {
                 transfer.timestamp.to_rfc3339(),
                 from_wallet.name,
                 to_wallet.name,
-                transfer.amount_cents.to_string(),
+                self.amount_field(transfer.amount_cents, &currencies[&transfer.from_wallet]),
                 transfer.description.clone().unwrap_or_default(),
                 transfer.category.clone().unwrap_or_default(),
                 transfer.tags.join(";"),
@@ -68,6 +163,14 @@ impl<'a> Exporter<'a> {
                     .map(|id| id.to_string())
                     .unwrap_or_default(),
                 transfer.external_ref.clone().unwrap_or_default(),
+                transfer
+                    .split_id
+                    .map(|id| id.to_string())
+                    .unwrap_or_default(),
+                transfer
+                    .payee_id
+                    .and_then(|id| payees.get(&id).cloned())
+                    .unwrap_or_default(),
             ])?;
             count += 1;
         }
@@ -87,11 +190,12 @@ impl<'a> Exporter<'a> {
         let mut count = 0;
         for wallet in &wallets {
             let balance = self.service.get_balance(&wallet.name).await?;
+            let currency = self.service.get_currency(&wallet.currency).await?;
             csv_writer.write_record([
                 &wallet.name,
                 wallet.wallet_type.as_str(),
                 &wallet.currency,
-                &balance.balance.to_string(),
+                &self.amount_field(balance.balance, &currency),
             ])?;
             count += 1;
         }
@@ -105,16 +209,49 @@ impl<'a> Exporter<'a> {
         let budgets = self.service.list_budgets().await?;
         let mut csv_writer = csv::Writer::from_writer(writer);
 
-        // Write header
-        csv_writer.write_record(["name", "category", "amount_cents", "period"])?;
+        let names = self.service.get_wallet_names().await?;
+        let wallet_list = |ids: &[crate::domain::WalletId]| {
+            ids.iter()
+                .map(|id| names.get(id).map(String::as_str).unwrap_or("?"))
+                .collect::<Vec<_>>()
+                .join(";")
+        };
+
+        // Write header; several categories, wallets, tags or alerts are separated by ';'
+        csv_writer.write_record([
+            "name",
+            "category",
+            self.amount_header(),
+            "period",
+            "from_wallets",
+            "to_wallets",
+            "tags",
+            "tag_match",
+            "alerts",
+        ])?;
 
         let mut count = 0;
         for budget in &budgets {
+            // Budgets without a currency are in two decimals
+            let currency = match &budget.currency {
+                Some(code) => self.service.get_currency(code).await?,
+                None => Currency::new("", 2),
+            };
             csv_writer.write_record([
                 &budget.name,
-                &budget.category,
-                &budget.amount_cents.to_string(),
+                &budget.categories().join(";"),
+                &self.amount_field(budget.amount_cents, &currency),
                 budget.period_type.as_str(),
+                &wallet_list(&budget.filter.from_wallets),
+                &wallet_list(&budget.filter.to_wallets),
+                &budget.filter.tags.join(";"),
+                budget.filter.tag_match.as_str(),
+                &budget
+                    .alert_thresholds
+                    .iter()
+                    .map(u32::to_string)
+                    .collect::<Vec<_>>()
+                    .join(";"),
             ])?;
             count += 1;
         }
@@ -126,6 +263,7 @@ impl<'a> Exporter<'a> {
     /// Export scheduled transfers to CSV format
     pub async fn export_scheduled_csv<W: Write>(&self, writer: W) -> Result<usize> {
         let scheduled = self.service.list_scheduled_transfers(true).await?;
+        let currencies = self.service.get_wallet_currencies().await?;
         let mut csv_writer = csv::Writer::from_writer(writer);
 
         // Write header
@@ -133,7 +271,7 @@ impl<'a> Exporter<'a> {
             "name",
             "from_wallet",
             "to_wallet",
-            "amount_cents",
+            self.amount_header(),
             "pattern",
             "start_date",
             "end_date",
@@ -151,7 +289,7 @@ impl<'a> Exporter<'a> {
                 &st.name,
                 &from_wallet.name,
                 &to_wallet.name,
-                &st.amount_cents.to_string(),
+                &self.amount_field(st.amount_cents, &currencies[&st.from_wallet]),
                 st.pattern.as_str(),
                 &st.start_date.to_rfc3339(),
                 &st.end_date.map(|d| d.to_rfc3339()).unwrap_or_default(),
@@ -166,12 +304,127 @@ impl<'a> Exporter<'a> {
         Ok(count)
     }
 
+    /// Export the gains realized in a calendar year to CSV format, one row per lot sold
+    pub async fn export_realized_gains_csv<W: Write>(&self, writer: W, year: i32) -> Result<usize> {
+        let report = self.service.get_realized_gains_report(year).await?;
+        let mut csv_writer = csv::Writer::from_writer(writer);
+
+        // Write header
+        csv_writer.write_record([
+            "wallet",
+            "commodity",
+            "lot_id",
+            "acquired_at",
+            "disposed_at",
+            "quantity",
+            "currency",
+            "cost_basis",
+            "proceeds",
+            "gain",
+            "method",
+        ])?;
+
+        let mut count = 0;
+        for gain in &report.gains {
+            let units = Currency::new(gain.commodity.clone(), gain.quantity_decimals);
+            let currency = self.service.get_currency(&gain.currency).await?;
+            csv_writer.write_record([
+                &gain.wallet_name,
+                &gain.commodity,
+                &gain.lot_id.to_string(),
+                &gain.acquired_at.to_rfc3339(),
+                &gain.disposed_at.to_rfc3339(),
+                &self.amount_field(gain.quantity, &units),
+                &gain.currency,
+                &self.amount_field(gain.cost_basis, &currency),
+                &self.amount_field(gain.proceeds, &currency),
+                &self.amount_field(gain.gain, &currency),
+                gain.method.as_str(),
+            ])?;
+            count += 1;
+        }
+
+        csv_writer.flush()?;
+        Ok(count)
+    }
+
+    /// Export the register of a wallet to CSV format, one row per transfer with
+    /// the running balance
+    pub async fn export_register_csv<W: Write>(
+        &self,
+        writer: W,
+        wallet: &str,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+    ) -> Result<usize> {
+        let register = self.service.get_register(wallet, from, to).await?;
+        let currency = self.service.get_currency(&register.wallet.currency).await?;
+        let wallet_names = self.service.get_wallet_names().await?;
+        let mut csv_writer = csv::Writer::from_writer(writer);
+
+        // Write header
+        let balance_header = if self.locale.is_some() {
+            "balance"
+        } else {
+            "balance_cents"
+        };
+        csv_writer.write_record([
+            "id",
+            "timestamp",
+            "counterpart",
+            "description",
+            "category",
+            self.amount_header(),
+            balance_header,
+        ])?;
+
+        let mut count = 0;
+        for entry in &register.entries {
+            csv_writer.write_record([
+                &entry.transfer.id.to_string(),
+                &entry.transfer.timestamp.to_rfc3339(),
+                &wallet_names
+                    .get(&entry.counterpart)
+                    .cloned()
+                    .unwrap_or_default(),
+                &entry.transfer.description.clone().unwrap_or_default(),
+                &entry.transfer.category.clone().unwrap_or_default(),
+                &self.amount_field(entry.amount_cents, &currency),
+                &self.amount_field(entry.balance, &currency),
+            ])?;
+            count += 1;
+        }
+
+        csv_writer.flush()?;
+        Ok(count)
+    }
+
     /// Export full database as JSON snapshot
     pub async fn export_full_json<W: Write>(&self, mut writer: W) -> Result<DatabaseSnapshot> {
         let wallets = self.service.list_wallets(true).await?;
         let transfers = self.service.list_all_transfers().await?;
         let budgets = self.service.list_budgets().await?;
         let scheduled_transfers = self.service.list_scheduled_transfers(true).await?;
+        let exchange_rates = self.service.list_exchange_rates().await?;
+        let commodity_prices = self.service.list_prices(None).await?;
+        let lot_disposals = self.service.list_lot_disposals().await?;
+        let reconciliations = self.service.list_reconciliations(None).await?;
+        let amendments = self.service.list_amendments().await?;
+        let payees = self.service.list_payees().await?;
+        let attachments = self.service.list_all_attachments().await?;
+        let attachment_blobs = self
+            .service
+            .list_attachment_data()
+            .await?
+            .into_iter()
+            .map(|(hash, data)| AttachmentBlob { hash, data })
+            .collect();
+        let period_locks = self.service.list_period_locks().await?;
+        let lock_overrides = self.service.list_lock_overrides().await?;
+        let year_closes = self.service.list_year_closes().await?;
+        let balance_assertions = self.service.list_balance_assertions().await?;
+        let envelopes = self.service.list_envelopes().await?;
+        let envelope_assignments = self.service.list_envelope_assignments().await?;
 
         let snapshot = DatabaseSnapshot {
             version: env!("CARGO_PKG_VERSION").to_string(),
@@ -180,6 +433,20 @@ impl<'a> Exporter<'a> {
             transfers,
             budgets,
             scheduled_transfers,
+            exchange_rates,
+            commodity_prices,
+            lot_disposals,
+            reconciliations,
+            amendments,
+            payees,
+            attachments,
+            attachment_blobs,
+            period_locks,
+            lock_overrides,
+            year_closes,
+            balance_assertions,
+            envelopes,
+            envelope_assignments,
         };
 
         let json = serde_json::to_string_pretty(&snapshot)?;
@@ -1,9 +1,14 @@
 use anyhow::Result;
 use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::{HashMap, HashSet};
 use std::io::Read;
 
-use crate::application::LedgerService;
-use crate::domain::{WalletType, parse_cents};
+use crate::application::{AppError, LedgerService, LockOverrideRequest, TransferRequest};
+use crate::domain::{
+    Cents, ClearedState, Currency, DEFAULT_MATCH_WINDOW_DAYS, LocaleProfile, MatchCandidate,
+    MatchOutcome, Money, RoundingMode, StatementLine, TransferId, WalletType, parse_cents,
+    resolve_match,
+};
 use crate::io::export::DatabaseSnapshot;
 
 /// Result of an import operation
@@ -12,6 +17,22 @@ pub struct ImportResult {
     pub imported: usize,
     pub skipped: usize,
     pub errors: Vec<ImportError>,
+    /// Lines paired with a transfer already in the ledger
+    pub matched: usize,
+    /// Lines left out because several transfers could be theirs
+    pub ambiguous: Vec<AmbiguousLine>,
+}
+
+/// A statement line that fits more than one existing transfer. It is neither
+/// matched nor imported until confirmed through `ImportOptions::confirmed`.
+#[derive(Debug, Clone)]
+pub struct AmbiguousLine {
+    pub line: usize,
+    pub amount_cents: Cents,
+    pub timestamp: DateTime<Utc>,
+    pub description: Option<String>,
+    /// Best first
+    pub candidates: Vec<MatchCandidate>,
 }
 
 /// Error that occurred during import
@@ -29,6 +50,17 @@ pub struct ImportOptions {
     pub skip_duplicates: bool,
     pub create_missing_wallets: bool,
     pub validate_only: bool,
+    /// Read CSV amounts in this locale's format (plain decimals when unset)
+    pub locale: Option<LocaleProfile>,
+    /// Pair lines with transfers already entered by hand instead of importing them again
+    pub match_existing: bool,
+    /// Days either side of a line's date to look for its transfer (3 when unset)
+    pub match_window_days: Option<i64>,
+    /// Answers for ambiguous lines, by line number: the transfer the line is,
+    /// or None to import it as a new transfer
+    pub confirmed: HashMap<usize, Option<TransferId>>,
+    /// Lets lines dated inside a locked period in; the override is logged per transfer
+    pub lock_override: Option<LockOverrideRequest>,
 }
 
 /// Importer for loading data into the ledger
@@ -51,6 +83,10 @@ impl<'a> Importer<'a> {
         let mut imported = 0;
         let mut skipped = 0;
         let mut errors = Vec::new();
+        let mut matched = 0;
+        let mut ambiguous = Vec::new();
+        // Transfers already paired with a line of this statement
+        let mut used: HashSet<TransferId> = HashSet::new();
 
         for (line_num, result) in csv_reader.records().enumerate() {
             let line = line_num + 2; // +2 for header and 0-indexing
@@ -86,19 +122,22 @@ impl<'a> Importer<'a> {
                     Some(s.to_string())
                 }
             });
-
-            // Validate and parse
-            let amount_cents = match parse_cents(amount_str) {
-                Ok(a) => a,
-                Err(e) => {
-                    errors.push(ImportError {
-                        line,
-                        field: Some("amount_cents".to_string()),
-                        error: format!("Invalid amount: {}", e),
-                    });
-                    continue;
-                }
-            };
+            let tags: Vec<String> = record
+                .get(8)
+                .unwrap_or("")
+                .split(';')
+                .filter(|tag| !tag.trim().is_empty())
+                .map(|tag| tag.to_string())
+                .collect();
+            let external_ref = record
+                .get(10)
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string());
+            // Without a payee column the payee is detected from the description
+            let payee = record
+                .get(12)
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string());
 
             let timestamp = match parse_timestamp(timestamp_str) {
                 Ok(ts) => ts,
@@ -133,39 +172,142 @@ impl<'a> Importer<'a> {
                 }
             }
 
+            // Amounts are read in the source wallet's currency (two decimals if it is unknown)
+            let currency = self.service.get_wallet_currency(from_wallet).await.ok();
+            let parsed_amount = match (&options.locale, currency) {
+                (Some(locale), currency) => locale.parse_amount(
+                    amount_str,
+                    &currency.unwrap_or_else(|| Currency::new("", 2)),
+                    RoundingMode::Reject,
+                ),
+                (None, Some(currency)) => Money::parse(amount_str, currency, RoundingMode::Reject)
+                    .map(|money| money.amount),
+                (None, None) => parse_cents(amount_str),
+            };
+            let amount_cents = match parsed_amount {
+                Ok(a) => a,
+                Err(e) => {
+                    errors.push(ImportError {
+                        line,
+                        field: Some("amount_cents".to_string()),
+                        error: format!("Invalid amount: {}", e),
+                    });
+                    continue;
+                }
+            };
+
+            // Lines already in the ledger: the same transfer ID or the same bank reference
+            if options.skip_duplicates || options.match_existing {
+                match self
+                    .is_duplicate(record.get(0).unwrap_or(""), external_ref.as_deref())
+                    .await
+                {
+                    Ok(true) => {
+                        skipped += 1;
+                        continue;
+                    }
+                    Ok(false) => {}
+                    Err(e) => {
+                        errors.push(ImportError {
+                            line,
+                            field: None,
+                            error: format!("Duplicate check failed: {}", e),
+                        });
+                        continue;
+                    }
+                }
+            }
+
+            if options.match_existing {
+                let outcome = match self
+                    .match_line(
+                        line,
+                        from_wallet,
+                        to_wallet,
+                        amount_cents,
+                        timestamp,
+                        &description,
+                        &options,
+                        &used,
+                    )
+                    .await
+                {
+                    Ok(outcome) => outcome,
+                    Err(e) => {
+                        errors.push(ImportError {
+                            line,
+                            field: None,
+                            error: format!("Matching failed: {}", e),
+                        });
+                        continue;
+                    }
+                };
+
+                match outcome {
+                    MatchOutcome::Matched(candidate) => {
+                        used.insert(candidate.transfer.id);
+                        if !(options.dry_run || options.validate_only)
+                            && let Err(e) = self
+                                .service
+                                .match_statement_line(candidate.transfer.id, external_ref.clone())
+                                .await
+                        {
+                            errors.push(ImportError {
+                                line,
+                                field: None,
+                                error: format!("Matching failed: {}", e),
+                            });
+                            continue;
+                        }
+                        matched += 1;
+                        continue;
+                    }
+                    MatchOutcome::Ambiguous(candidates) => {
+                        ambiguous.push(AmbiguousLine {
+                            line,
+                            amount_cents,
+                            timestamp,
+                            description,
+                            candidates,
+                        });
+                        continue;
+                    }
+                    MatchOutcome::Unmatched => {}
+                }
+            }
+
             // Skip actual import if dry run or validate only
             if options.dry_run || options.validate_only {
                 imported += 1;
                 continue;
             }
 
-            // Import the transfer
-            match self
-                .service
-                .record_transfer(
-                    from_wallet,
-                    to_wallet,
-                    amount_cents,
-                    timestamp,
-                    description.clone(),
-                    category.clone(),
-                    true, // force (allow negative balances during import)
-                )
-                .await
-            {
+            // Lines of a matched statement have shown up at the bank
+            let cleared = if options.match_existing {
+                ClearedState::Cleared
+            } else {
+                ClearedState::Uncleared
+            };
+            let request = TransferRequest::new(from_wallet, to_wallet, amount_cents, timestamp)
+                .with_description(description)
+                .with_category(category)
+                .with_tags(tags)
+                .with_payee(payee)
+                .with_external_ref(external_ref)
+                .with_cleared(cleared)
+                .with_lock_override(options.lock_override.clone())
+                .with_force(true); // allow negative balances during import
+
+            match self.service.record_transfer_with(request).await {
                 Ok(_) => {
                     imported += 1;
                 }
                 Err(e) => {
-                    if options.skip_duplicates {
-                        skipped += 1;
-                    } else {
-                        errors.push(ImportError {
-                            line,
-                            field: None,
-                            error: format!("Transfer creation failed: {}", e),
-                        });
-                    }
+                    errors.push(ImportError {
+                        line,
+                        field: None,
+                        error: format!("Transfer creation failed: {}", e),
+                    });
                 }
             }
         }
@@ -174,9 +316,78 @@ impl<'a> Importer<'a> {
             imported,
             skipped,
             errors,
+            matched,
+            ambiguous,
         })
     }
 
+    /// Whether a line is already in the ledger, by transfer ID or bank reference.
+    async fn is_duplicate(&self, id: &str, external_ref: Option<&str>) -> Result<bool> {
+        if let Ok(id) = uuid::Uuid::parse_str(id) {
+            match self.service.get_transfer_info(id).await {
+                Ok(_) => return Ok(true),
+                Err(AppError::TransferNotFound(_)) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        match external_ref {
+            Some(external_ref) => Ok(self
+                .service
+                .find_transfer_by_external_ref(external_ref)
+                .await?
+                .is_some()),
+            None => Ok(false),
+        }
+    }
+
+    /// Look for the transfer a statement line stands for, honouring confirmations.
+    #[allow(clippy::too_many_arguments)]
+    async fn match_line(
+        &self,
+        line: usize,
+        from_wallet: &str,
+        to_wallet: &str,
+        amount_cents: Cents,
+        timestamp: DateTime<Utc>,
+        description: &Option<String>,
+        options: &ImportOptions,
+        used: &HashSet<TransferId>,
+    ) -> Result<MatchOutcome> {
+        match options.confirmed.get(&line) {
+            Some(Some(id)) => {
+                if used.contains(id) {
+                    anyhow::bail!("transfer {} is already matched to another line", id);
+                }
+                let transfer = self.service.get_transfer_info(*id).await?.transfer;
+                return Ok(MatchOutcome::Matched(Box::new(MatchCandidate {
+                    transfer,
+                    score: 1.0,
+                })));
+            }
+            Some(None) => return Ok(MatchOutcome::Unmatched),
+            None => {}
+        }
+
+        let statement = StatementLine {
+            from_wallet: self.service.get_wallet(from_wallet).await?.id,
+            to_wallet: self.service.get_wallet(to_wallet).await?.id,
+            amount_cents,
+            timestamp,
+            description: description.clone(),
+        };
+        let window = options
+            .match_window_days
+            .unwrap_or(DEFAULT_MATCH_WINDOW_DAYS);
+        let candidates = self
+            .service
+            .find_statement_matches(&statement, window)
+            .await?
+            .into_iter()
+            .filter(|c| !used.contains(&c.transfer.id))
+            .collect();
+        Ok(resolve_match(candidates))
+    }
+
     /// Import full database from JSON snapshot
     pub async fn import_full_json<R: Read>(
         &self,
@@ -195,9 +406,17 @@ impl<'a> Importer<'a> {
                 imported: snapshot.wallets.len()
                     + snapshot.transfers.len()
                     + snapshot.budgets.len()
-                    + snapshot.scheduled_transfers.len(),
+                    + snapshot.scheduled_transfers.len()
+                    + snapshot.exchange_rates.len()
+                    + snapshot.commodity_prices.len()
+                    + snapshot.lot_disposals.len()
+                    + snapshot.reconciliations.len()
+                    + snapshot.payees.len()
+                    + snapshot.attachments.len(),
                 skipped: 0,
                 errors,
+                matched: 0,
+                ambiguous: Vec::new(),
             });
         }
 
@@ -219,6 +438,8 @@ impl<'a> Importer<'a> {
             imported,
             skipped,
             errors,
+            matched: 0,
+            ambiguous: Vec::new(),
         })
     }
 }
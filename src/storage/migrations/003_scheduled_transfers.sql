@@ -16,6 +16,6 @@ CREATE TABLE IF NOT EXISTS scheduled_transfers (
     FOREIGN KEY (to_wallet_id) REFERENCES wallets(id)
 );
 
-CREATE INDEX idx_scheduled_status ON scheduled_transfers(status);
-CREATE INDEX idx_scheduled_pattern ON scheduled_transfers(pattern);
-CREATE INDEX idx_scheduled_next ON scheduled_transfers(last_executed_at, start_date);
+CREATE INDEX IF NOT EXISTS idx_scheduled_status ON scheduled_transfers(status);
+CREATE INDEX IF NOT EXISTS idx_scheduled_pattern ON scheduled_transfers(pattern);
+CREATE INDEX IF NOT EXISTS idx_scheduled_next ON scheduled_transfers(last_executed_at, start_date);
@@ -0,0 +1,6 @@
+-- Migration 005: Split transfers
+-- Transfers sharing a split_id are legs of one logical transaction
+
+ALTER TABLE transfers ADD COLUMN split_id TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_transfers_split ON transfers(split_id);
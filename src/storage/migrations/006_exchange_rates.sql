@@ -0,0 +1,15 @@
+-- Migration 006: Exchange rates
+-- One unit of base_currency is worth `rate` units of quote_currency from effective_date on.
+-- Rates are exact decimal strings ("1.085"), never floats.
+
+CREATE TABLE IF NOT EXISTS exchange_rates (
+    id              TEXT PRIMARY KEY,
+    base_currency   TEXT NOT NULL,
+    quote_currency  TEXT NOT NULL,
+    rate            TEXT NOT NULL,
+    effective_date  TEXT NOT NULL,
+    created_at      TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_exchange_rates_pair
+ON exchange_rates(base_currency, quote_currency, effective_date);
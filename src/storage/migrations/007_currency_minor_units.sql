@@ -0,0 +1,102 @@
+-- Migration 007: Per-currency minor units
+-- Amounts used to be stored in hundredths for every currency. From now on they are
+-- stored in each currency's minor unit (yen for JPY, fils for KWD, satoshi for BTC).
+
+-- Currencies whose minor unit differs from the built-in table (e.g. crypto assets)
+CREATE TABLE IF NOT EXISTS currencies (
+    code          TEXT PRIMARY KEY,
+    minor_units   INTEGER NOT NULL CHECK (minor_units BETWEEN 0 AND 12),
+    created_at    TEXT NOT NULL
+);
+
+-- Budgets carry the currency their amount is expressed in (NULL: legacy, any currency)
+ALTER TABLE budgets ADD COLUMN currency TEXT;
+
+-- Rescale existing amounts of currencies without two decimals.
+-- Keep this list in sync with MINOR_UNIT_EXCEPTIONS in src/domain/money.rs.
+UPDATE transfers
+SET amount_cents = MAX(1, CAST(ROUND(amount_cents / 100.0) AS INTEGER))
+WHERE from_wallet_id IN (
+    SELECT id FROM wallets WHERE UPPER(currency) IN (
+        'BIF', 'CLP', 'DJF', 'GNF', 'ISK', 'JPY', 'KMF', 'KRW',
+        'PYG', 'RWF', 'UGX', 'VND', 'VUV', 'XAF', 'XOF', 'XPF'
+    )
+);
+
+UPDATE transfers
+SET amount_cents = amount_cents * 10
+WHERE from_wallet_id IN (
+    SELECT id FROM wallets WHERE UPPER(currency) IN (
+        'BHD', 'IQD', 'JOD', 'KWD', 'LYD', 'OMR', 'TND'
+    )
+);
+
+UPDATE transfers
+SET amount_cents = amount_cents * 1000000
+WHERE from_wallet_id IN (
+    SELECT id FROM wallets WHERE UPPER(currency) IN ('BTC', 'LTC')
+);
+
+UPDATE scheduled_transfers
+SET amount_cents = MAX(1, CAST(ROUND(amount_cents / 100.0) AS INTEGER))
+WHERE from_wallet_id IN (
+    SELECT id FROM wallets WHERE UPPER(currency) IN (
+        'BIF', 'CLP', 'DJF', 'GNF', 'ISK', 'JPY', 'KMF', 'KRW',
+        'PYG', 'RWF', 'UGX', 'VND', 'VUV', 'XAF', 'XOF', 'XPF'
+    )
+);
+
+UPDATE scheduled_transfers
+SET amount_cents = amount_cents * 10
+WHERE from_wallet_id IN (
+    SELECT id FROM wallets WHERE UPPER(currency) IN (
+        'BHD', 'IQD', 'JOD', 'KWD', 'LYD', 'OMR', 'TND'
+    )
+);
+
+UPDATE scheduled_transfers
+SET amount_cents = amount_cents * 1000000
+WHERE from_wallet_id IN (
+    SELECT id FROM wallets WHERE UPPER(currency) IN ('BTC', 'LTC')
+);
+
+-- Budget limits were stored in hundredths as well. A legacy budget whose spending all
+-- went into wallets of one such currency takes that currency, and its limit is rescaled
+-- with it. Budgets without spending, or spanning several currencies, stay legacy.
+UPDATE budgets
+SET currency = (
+    SELECT MIN(UPPER(w.currency))
+    FROM transfers t JOIN wallets w ON w.id = t.to_wallet_id
+    WHERE t.category = budgets.category
+)
+WHERE currency IS NULL
+AND (
+    SELECT COUNT(DISTINCT UPPER(w.currency))
+    FROM transfers t JOIN wallets w ON w.id = t.to_wallet_id
+    WHERE t.category = budgets.category
+) = 1
+AND (
+    SELECT MIN(UPPER(w.currency))
+    FROM transfers t JOIN wallets w ON w.id = t.to_wallet_id
+    WHERE t.category = budgets.category
+) IN (
+    'BIF', 'CLP', 'DJF', 'GNF', 'ISK', 'JPY', 'KMF', 'KRW',
+    'PYG', 'RWF', 'UGX', 'VND', 'VUV', 'XAF', 'XOF', 'XPF',
+    'BHD', 'IQD', 'JOD', 'KWD', 'LYD', 'OMR', 'TND',
+    'BTC', 'LTC'
+);
+
+UPDATE budgets
+SET amount_cents = MAX(1, CAST(ROUND(amount_cents / 100.0) AS INTEGER))
+WHERE currency IN (
+    'BIF', 'CLP', 'DJF', 'GNF', 'ISK', 'JPY', 'KMF', 'KRW',
+    'PYG', 'RWF', 'UGX', 'VND', 'VUV', 'XAF', 'XOF', 'XPF'
+);
+
+UPDATE budgets
+SET amount_cents = amount_cents * 10
+WHERE currency IN ('BHD', 'IQD', 'JOD', 'KWD', 'LYD', 'OMR', 'TND');
+
+UPDATE budgets
+SET amount_cents = amount_cents * 1000000
+WHERE currency IN ('BTC', 'LTC');
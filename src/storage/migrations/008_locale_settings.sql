@@ -0,0 +1,19 @@
+-- Migration 008: Locale profiles and ledger settings
+-- Custom profiles for reading and writing amounts; built-in profiles live in code
+
+CREATE TABLE IF NOT EXISTS locale_profiles (
+    name                TEXT PRIMARY KEY,
+    decimal_separator   TEXT NOT NULL,
+    grouping_separator  TEXT,
+    symbol_placement    TEXT NOT NULL CHECK (symbol_placement IN ('none', 'before', 'after')),
+    symbol_space        INTEGER NOT NULL DEFAULT 0,
+    negative_style      TEXT NOT NULL CHECK (negative_style IN ('leading-minus', 'trailing-minus', 'parentheses')),
+    created_at          TEXT NOT NULL
+);
+
+-- Ledger-wide key/value settings (e.g. the default locale)
+CREATE TABLE IF NOT EXISTS settings (
+    key         TEXT PRIMARY KEY,
+    value       TEXT NOT NULL,
+    updated_at  TEXT NOT NULL
+);
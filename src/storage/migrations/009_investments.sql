@@ -0,0 +1,24 @@
+-- Migration 009: Investment holdings
+-- A holding wallet keeps units of a commodity (a fund, a share, a metal) and is
+-- denominated in the cash currency its trades are settled in.
+
+ALTER TABLE wallets ADD COLUMN commodity TEXT;
+
+-- Buy and sell transfers record the units traded (in the commodity's minor units)
+-- and the price paid per unit, in the holding wallet's currency
+ALTER TABLE transfers ADD COLUMN quantity INTEGER;
+ALTER TABLE transfers ADD COLUMN unit_price REAL;
+
+-- Price history: one unit of `commodity` is worth `price` units of `currency`
+-- from effective_date on
+CREATE TABLE IF NOT EXISTS commodity_prices (
+    id              TEXT PRIMARY KEY,
+    commodity       TEXT NOT NULL,
+    currency        TEXT NOT NULL,
+    price           REAL NOT NULL CHECK (price > 0),
+    effective_date  TEXT NOT NULL,
+    created_at      TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_commodity_prices_commodity
+ON commodity_prices(commodity, effective_date);
@@ -0,0 +1,24 @@
+-- Migration 010: Lot tracking
+-- Every buy of a holding is a lot (identified by the buy transfer). Sales record
+-- which lots they consumed, with each lot's share of cost and proceeds.
+
+-- Default way sales pick lots in a holding wallet: fifo, lifo or average
+ALTER TABLE wallets ADD COLUMN lot_method TEXT NOT NULL DEFAULT 'fifo'
+    CHECK (lot_method IN ('fifo', 'lifo', 'average'));
+
+CREATE TABLE IF NOT EXISTS lot_disposals (
+    id                TEXT PRIMARY KEY,
+    sell_transfer_id  TEXT NOT NULL REFERENCES transfers(id),
+    wallet_id         TEXT NOT NULL REFERENCES wallets(id),
+    lot_id            TEXT NOT NULL REFERENCES transfers(id),
+    acquired_at       TEXT NOT NULL,
+    disposed_at       TEXT NOT NULL,
+    quantity          INTEGER NOT NULL CHECK (quantity > 0),
+    cost_cents        INTEGER NOT NULL,
+    proceeds_cents    INTEGER NOT NULL,
+    method            TEXT NOT NULL CHECK (method IN ('fifo', 'lifo', 'average', 'specific'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_lot_disposals_wallet ON lot_disposals(wallet_id);
+CREATE INDEX IF NOT EXISTS idx_lot_disposals_lot ON lot_disposals(lot_id);
+CREATE INDEX IF NOT EXISTS idx_lot_disposals_disposed ON lot_disposals(disposed_at);
@@ -0,0 +1,33 @@
+-- Migration 011: Bank reconciliation
+-- Every transfer carries how far it has been matched against a bank statement
+
+ALTER TABLE transfers ADD COLUMN cleared TEXT NOT NULL DEFAULT 'uncleared'
+    CHECK (cleared IN ('uncleared', 'cleared', 'reconciled'));
+
+-- A reconcile session per statement; at most one open session per wallet
+CREATE TABLE IF NOT EXISTS reconciliations (
+    id                  TEXT PRIMARY KEY,
+    wallet_id           TEXT NOT NULL REFERENCES wallets(id),
+    statement_date      TEXT NOT NULL,
+    statement_balance   INTEGER NOT NULL,
+    started_at          TEXT NOT NULL,
+    finished_at         TEXT
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_reconciliations_open
+ON reconciliations(wallet_id) WHERE finished_at IS NULL;
+
+-- What each transfer looked like when a session reconciled it, so later edits
+-- and reversals can be told apart from what was agreed with the bank
+CREATE TABLE IF NOT EXISTS reconciled_transfers (
+    reconciliation_id   TEXT NOT NULL REFERENCES reconciliations(id),
+    transfer_id         TEXT NOT NULL,
+    from_wallet_id      TEXT NOT NULL,
+    to_wallet_id        TEXT NOT NULL,
+    amount_cents        INTEGER NOT NULL,
+    timestamp           TEXT NOT NULL,
+    PRIMARY KEY (reconciliation_id, transfer_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_reconciled_transfers_transfer
+ON reconciled_transfers(transfer_id);
@@ -0,0 +1,5 @@
+-- Migration 012: Statement matching
+-- Imported statement lines are recognised by their bank reference
+
+CREATE INDEX IF NOT EXISTS idx_transfers_external_ref
+ON transfers(external_ref) WHERE external_ref IS NOT NULL;
@@ -0,0 +1,48 @@
+-- Migration 013: Transfer amendments
+-- Corrections update a transfer in place; each one is kept here, append-only
+
+CREATE TABLE IF NOT EXISTS transfer_amendments (
+    id              TEXT PRIMARY KEY,
+    transfer_id     TEXT NOT NULL REFERENCES transfers(id),
+    amended_at      TEXT NOT NULL,
+    author          TEXT,
+    reason          TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_transfer_amendments_transfer
+ON transfer_amendments(transfer_id, amended_at);
+
+-- One row per field the amendment changed
+CREATE TABLE IF NOT EXISTS amendment_changes (
+    amendment_id    TEXT NOT NULL REFERENCES transfer_amendments(id),
+    position        INTEGER NOT NULL,
+    field           TEXT NOT NULL
+        CHECK (field IN ('description', 'category', 'tags', 'date', 'amount')),
+    old_value       TEXT,
+    new_value       TEXT,
+    PRIMARY KEY (amendment_id, position)
+);
+
+CREATE TRIGGER IF NOT EXISTS transfer_amendments_no_update
+BEFORE UPDATE ON transfer_amendments
+BEGIN
+    SELECT RAISE(ABORT, 'transfer amendments are append-only');
+END;
+
+CREATE TRIGGER IF NOT EXISTS transfer_amendments_no_delete
+BEFORE DELETE ON transfer_amendments
+BEGIN
+    SELECT RAISE(ABORT, 'transfer amendments are append-only');
+END;
+
+CREATE TRIGGER IF NOT EXISTS amendment_changes_no_update
+BEFORE UPDATE ON amendment_changes
+BEGIN
+    SELECT RAISE(ABORT, 'transfer amendments are append-only');
+END;
+
+CREATE TRIGGER IF NOT EXISTS amendment_changes_no_delete
+BEFORE DELETE ON amendment_changes
+BEGIN
+    SELECT RAISE(ABORT, 'transfer amendments are append-only');
+END;
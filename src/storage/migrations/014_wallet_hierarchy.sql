@@ -0,0 +1,10 @@
+-- Migration 014: Wallet hierarchy
+-- Wallets form trees (e.g. "Bank" > "Checking"). Parent and child share a type
+-- and a currency so subtotals add up.
+
+ALTER TABLE wallets ADD COLUMN parent_id TEXT REFERENCES wallets(id);
+
+-- Wallets with sub-wallets only take transfers when this is set
+ALTER TABLE wallets ADD COLUMN allow_postings INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS idx_wallets_parent ON wallets(parent_id);
@@ -0,0 +1,39 @@
+-- Migration 015: Tag index
+-- Transfers keep their tags as a JSON array in `transfers.tags`. This table holds
+-- one row per (transfer, tag) so tag filters and reports use an index instead of
+-- scanning JSON text. Triggers keep it in step with the JSON column.
+
+CREATE TABLE IF NOT EXISTS transfer_tags (
+    transfer_id  TEXT NOT NULL REFERENCES transfers(id),
+    tag          TEXT NOT NULL,
+    PRIMARY KEY (transfer_id, tag)
+);
+
+CREATE INDEX IF NOT EXISTS idx_transfer_tags_tag ON transfer_tags(tag, transfer_id);
+
+INSERT OR IGNORE INTO transfer_tags (transfer_id, tag)
+SELECT t.id, j.value
+FROM transfers t, json_each(CASE WHEN json_valid(t.tags) THEN t.tags ELSE '[]' END) j
+WHERE j.type = 'text';
+
+CREATE TRIGGER IF NOT EXISTS transfer_tags_on_insert
+AFTER INSERT ON transfers
+BEGIN
+    INSERT OR IGNORE INTO transfer_tags (transfer_id, tag)
+    SELECT NEW.id, j.value
+    FROM json_each(CASE WHEN json_valid(NEW.tags) THEN NEW.tags ELSE '[]' END) j
+    WHERE j.type = 'text';
+END;
+
+CREATE TRIGGER IF NOT EXISTS transfer_tags_on_update
+AFTER UPDATE OF tags ON transfers
+BEGIN
+    DELETE FROM transfer_tags WHERE transfer_id = OLD.id;
+    INSERT OR IGNORE INTO transfer_tags (transfer_id, tag)
+    SELECT NEW.id, j.value
+    FROM json_each(CASE WHEN json_valid(NEW.tags) THEN NEW.tags ELSE '[]' END) j
+    WHERE j.type = 'text';
+END;
+
+-- Tags applied to every transfer a schedule creates (JSON array, like transfers.tags)
+ALTER TABLE scheduled_transfers ADD COLUMN tags TEXT;
@@ -0,0 +1,21 @@
+-- Migration 016: Payees
+-- Who a transfer was paid to or received from, with the spellings that identify
+-- them in statement descriptions and the defaults their transfers pick up.
+
+CREATE TABLE IF NOT EXISTS payees (
+    id                 TEXT PRIMARY KEY NOT NULL,
+    name               TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    default_category   TEXT,
+    default_wallet_id  TEXT REFERENCES wallets(id),
+    created_at         TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS payee_aliases (
+    payee_id  TEXT NOT NULL REFERENCES payees(id),
+    alias     TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    PRIMARY KEY (payee_id, alias)
+);
+
+ALTER TABLE transfers ADD COLUMN payee_id TEXT REFERENCES payees(id);
+
+CREATE INDEX IF NOT EXISTS idx_transfers_payee ON transfers(payee_id, timestamp);
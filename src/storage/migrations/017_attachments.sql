@@ -0,0 +1,22 @@
+-- Migration 017: Attachments
+-- Files kept next to transfers. Content is stored once per SHA-256 hash in
+-- attachment_blobs; attachments link a transfer to a blob under a file name.
+
+CREATE TABLE IF NOT EXISTS attachment_blobs (
+    hash        TEXT PRIMARY KEY NOT NULL,
+    size        INTEGER NOT NULL,
+    data        BLOB NOT NULL,
+    created_at  TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS attachments (
+    id           TEXT PRIMARY KEY NOT NULL,
+    transfer_id  TEXT NOT NULL REFERENCES transfers(id),
+    hash         TEXT NOT NULL REFERENCES attachment_blobs(hash),
+    filename     TEXT NOT NULL,
+    media_type   TEXT NOT NULL,
+    created_at   TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_attachments_transfer ON attachments(transfer_id);
+CREATE INDEX IF NOT EXISTS idx_attachments_hash ON attachments(hash);
@@ -0,0 +1,27 @@
+-- Migration 018: Period locks
+-- Lock dates close the books for the whole ledger (wallet_id NULL) or for one
+-- wallet. Entries are append-only; the latest entry of a scope is in force and
+-- a NULL lock_date lifts the lock. Changes let into a locked period are logged
+-- in lock_overrides.
+
+CREATE TABLE IF NOT EXISTS period_locks (
+    id          TEXT PRIMARY KEY NOT NULL,
+    wallet_id   TEXT REFERENCES wallets(id),
+    lock_date   TEXT,
+    locked_at   TEXT NOT NULL,
+    author      TEXT,
+    reason      TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_period_locks_scope ON period_locks(wallet_id, locked_at);
+
+CREATE TABLE IF NOT EXISTS lock_overrides (
+    id           TEXT PRIMARY KEY NOT NULL,
+    transfer_id  TEXT NOT NULL,
+    action       TEXT NOT NULL,
+    reason       TEXT NOT NULL,
+    author       TEXT,
+    created_at   TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_lock_overrides_transfer ON lock_overrides(transfer_id);
@@ -0,0 +1,12 @@
+-- Migration 019: Year-end closing
+-- One row per closed year. The closing entries (and, once the year is archived
+-- into its own file, the opening-balance transfers that replace its history)
+-- are found through their split IDs.
+
+CREATE TABLE IF NOT EXISTS year_closes (
+    year               INTEGER PRIMARY KEY NOT NULL,
+    closed_at          TEXT NOT NULL,
+    closing_split_id   TEXT NOT NULL,
+    opening_split_id   TEXT,
+    archive_path       TEXT
+);
@@ -0,0 +1,13 @@
+-- Migration 020: Balance assertions
+-- "On this day the wallet held exactly this much", as read off a statement. One
+-- assertion per wallet and day; integrity checks compare it with the balance
+-- computed from the transfers up to the end of the day.
+
+CREATE TABLE IF NOT EXISTS balance_assertions (
+    id              TEXT PRIMARY KEY NOT NULL,
+    wallet_id       TEXT NOT NULL REFERENCES wallets(id),
+    date            TEXT NOT NULL,
+    balance_cents   INTEGER NOT NULL,
+    created_at      TEXT NOT NULL,
+    UNIQUE (wallet_id, date)
+);
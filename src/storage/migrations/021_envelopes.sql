@@ -0,0 +1,25 @@
+-- Migration 021: Envelope budgeting
+-- Zero-based budgeting next to the category budgets: income feeds a "to be
+-- assigned" pool per currency, from which money is assigned to envelopes for
+-- a month. Moves between envelopes are pairs of assignments naming each other.
+
+CREATE TABLE IF NOT EXISTS envelopes (
+    id            TEXT PRIMARY KEY NOT NULL,
+    name          TEXT NOT NULL UNIQUE,
+    category      TEXT NOT NULL,
+    currency      TEXT NOT NULL,
+    start_month   TEXT NOT NULL,
+    created_at    TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS envelope_assignments (
+    id              TEXT PRIMARY KEY NOT NULL,
+    envelope_id     TEXT NOT NULL REFERENCES envelopes(id),
+    month           TEXT NOT NULL,
+    amount_cents    INTEGER NOT NULL,
+    counterpart_id  TEXT REFERENCES envelopes(id),
+    created_at      TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_envelope_assignments_envelope
+    ON envelope_assignments(envelope_id, month);
@@ -0,0 +1,10 @@
+-- Migration 022: Budget rollover
+-- A budget can carry what is left at the end of a period into the next one:
+-- nothing (the default), only unspent money, or overspending as well.
+-- Carried amounts are computed from the transfers, so only the policy and the
+-- day it starts from are stored.
+
+ALTER TABLE budgets ADD COLUMN rollover TEXT NOT NULL DEFAULT 'none';
+
+-- Day in the first period whose leftover carries over (NULL: period of created_at)
+ALTER TABLE budgets ADD COLUMN rollover_since TEXT;
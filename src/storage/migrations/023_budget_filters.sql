@@ -0,0 +1,6 @@
+-- Migration 023: Budget filters
+-- Besides its category, a budget can be scoped by further categories, source
+-- wallets, destination wallets and tags. The filter is stored as JSON, with
+-- wallets referenced by ID; an empty object keeps the category-only behaviour.
+
+ALTER TABLE budgets ADD COLUMN filter TEXT NOT NULL DEFAULT '{}';
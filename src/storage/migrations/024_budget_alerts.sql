@@ -0,0 +1,18 @@
+-- Migration 024: Budget alerts
+-- Budgets raise an event when spending reaches one of their thresholds
+-- (percentages of the limit). Every event raised is kept, and the primary key
+-- lets each threshold fire only once per period.
+
+ALTER TABLE budgets ADD COLUMN alert_thresholds TEXT NOT NULL DEFAULT '[]';
+
+CREATE TABLE IF NOT EXISTS budget_events (
+    budget_id     TEXT NOT NULL REFERENCES budgets(id),
+    period_start  TEXT NOT NULL,
+    threshold     INTEGER NOT NULL,
+    period_end    TEXT NOT NULL,
+    limit_cents   INTEGER NOT NULL,
+    spent_cents   INTEGER NOT NULL,
+    transfer_id   TEXT,
+    fired_at      TEXT NOT NULL,
+    PRIMARY KEY (budget_id, period_start, threshold)
+);
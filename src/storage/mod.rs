@@ -13,3 +13,94 @@ pub const MIGRATION_003_SCHEDULED: &str = include_str!("migrations/003_scheduled
 
 /// SQL migration for reporting indexes
 pub const MIGRATION_004_REPORTING: &str = include_str!("migrations/004_reporting_indexes.sql");
+
+/// SQL migration for split transfers
+pub const MIGRATION_005_SPLITS: &str = include_str!("migrations/005_split_transfers.sql");
+
+/// SQL migration for exchange rates
+pub const MIGRATION_006_EXCHANGE_RATES: &str = include_str!("migrations/006_exchange_rates.sql");
+
+/// SQL migration for per-currency minor units
+pub const MIGRATION_007_MINOR_UNITS: &str = include_str!("migrations/007_currency_minor_units.sql");
+
+/// SQL migration for locale profiles and settings
+pub const MIGRATION_008_LOCALE: &str = include_str!("migrations/008_locale_settings.sql");
+
+/// SQL migration for investment holdings and commodity prices
+pub const MIGRATION_009_INVESTMENTS: &str = include_str!("migrations/009_investments.sql");
+
+/// SQL migration for lot tracking and realized gains
+pub const MIGRATION_010_LOTS: &str = include_str!("migrations/010_lot_tracking.sql");
+
+/// SQL migration for bank reconciliation
+pub const MIGRATION_011_RECONCILIATION: &str = include_str!("migrations/011_reconciliation.sql");
+
+/// SQL migration for matching imported statement lines
+pub const MIGRATION_012_MATCHING: &str = include_str!("migrations/012_statement_matching.sql");
+
+/// SQL migration for the transfer amendment history
+pub const MIGRATION_013_AMENDMENTS: &str = include_str!("migrations/013_transfer_amendments.sql");
+
+/// SQL migration for wallet hierarchies
+pub const MIGRATION_014_WALLET_HIERARCHY: &str =
+    include_str!("migrations/014_wallet_hierarchy.sql");
+
+/// SQL migration for the transfer tag index
+pub const MIGRATION_015_TAGS: &str = include_str!("migrations/015_transfer_tags.sql");
+
+/// SQL migration for payees
+pub const MIGRATION_016_PAYEES: &str = include_str!("migrations/016_payees.sql");
+
+/// SQL migration for transfer attachments
+pub const MIGRATION_017_ATTACHMENTS: &str = include_str!("migrations/017_attachments.sql");
+
+/// SQL migration for period locks
+pub const MIGRATION_018_PERIOD_LOCKS: &str = include_str!("migrations/018_period_locks.sql");
+
+/// SQL migration for year-end closing
+pub const MIGRATION_019_YEAR_CLOSES: &str = include_str!("migrations/019_year_closes.sql");
+
+/// SQL migration for balance assertions
+pub const MIGRATION_020_BALANCE_ASSERTIONS: &str =
+    include_str!("migrations/020_balance_assertions.sql");
+
+/// SQL migration for envelope budgeting
+pub const MIGRATION_021_ENVELOPES: &str = include_str!("migrations/021_envelopes.sql");
+
+/// SQL migration for budget rollover
+pub const MIGRATION_022_BUDGET_ROLLOVER: &str = include_str!("migrations/022_budget_rollover.sql");
+
+/// SQL migration for budget filters
+pub const MIGRATION_023_BUDGET_FILTERS: &str = include_str!("migrations/023_budget_filters.sql");
+
+/// SQL migration for budget alerts
+pub const MIGRATION_024_BUDGET_ALERTS: &str = include_str!("migrations/024_budget_alerts.sql");
+
+/// All migrations in the order they are applied.
+/// Migration N (1-based) brings the schema to version N, tracked via `PRAGMA user_version`.
+pub const MIGRATIONS: &[&str] = &[
+    MIGRATION_001_INITIAL,
+    MIGRATION_002_BUDGETS,
+    MIGRATION_003_SCHEDULED,
+    MIGRATION_004_REPORTING,
+    MIGRATION_005_SPLITS,
+    MIGRATION_006_EXCHANGE_RATES,
+    MIGRATION_007_MINOR_UNITS,
+    MIGRATION_008_LOCALE,
+    MIGRATION_009_INVESTMENTS,
+    MIGRATION_010_LOTS,
+    MIGRATION_011_RECONCILIATION,
+    MIGRATION_012_MATCHING,
+    MIGRATION_013_AMENDMENTS,
+    MIGRATION_014_WALLET_HIERARCHY,
+    MIGRATION_015_TAGS,
+    MIGRATION_016_PAYEES,
+    MIGRATION_017_ATTACHMENTS,
+    MIGRATION_018_PERIOD_LOCKS,
+    MIGRATION_019_YEAR_CLOSES,
+    MIGRATION_020_BALANCE_ASSERTIONS,
+    MIGRATION_021_ENVELOPES,
+    MIGRATION_022_BUDGET_ROLLOVER,
+    MIGRATION_023_BUDGET_FILTERS,
+    MIGRATION_024_BUDGET_ALERTS,
+];
@@ -1,14 +1,17 @@
 use anyhow::{Context, Result};
-use chrono::{DateTime, Utc};
-use sqlx::{Row, SqlitePool};
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::{Row, SqliteConnection, SqlitePool};
+use std::collections::HashMap;
 use uuid::Uuid;
 
-use crate::domain::{Cents, Transfer, TransferId, Wallet, WalletId};
-
-use super::{
-    MIGRATION_001_INITIAL, MIGRATION_002_BUDGETS, MIGRATION_003_SCHEDULED, MIGRATION_004_REPORTING,
+use crate::domain::{
+    Attachment, AttachmentId, BalanceAssertion, Cents, Envelope, EnvelopeAssignment, EnvelopeId,
+    LockOverride, Payee, PayeeId, PeriodLock, SplitId, TagMatch, Transfer, TransferId, Wallet,
+    WalletId, YearClose,
 };
 
+use super::MIGRATIONS;
+
 /// Statistics for ledger integrity verification.
 #[derive(Debug, Clone)]
 pub struct IntegrityStats {
@@ -17,6 +20,81 @@ pub struct IntegrityStats {
     pub has_sequence_gaps: bool,
     pub invalid_wallet_refs: i64,
     pub invalid_amounts: i64,
+    pub modified_reconciled: i64,
+    pub reversed_reconciled: i64,
+}
+
+/// Which transfers of another database to copy, along with the rows tied to them.
+enum TransferRange {
+    All,
+    /// Transfers dated at or after the instant
+    From(DateTime<Utc>),
+    /// Transfers dated before the instant
+    Before(DateTime<Utc>),
+}
+
+impl TransferRange {
+    /// `WHERE` clause selecting the rows of `table` in the attached `source`
+    /// database that belong to the range (empty when every row does). Every
+    /// table needs a rule here, so a new one cannot be copied whole by mistake.
+    fn filter(&self, table: &str) -> Result<String> {
+        let condition = |column: &str| match self {
+            TransferRange::All => None,
+            TransferRange::From(start) => Some(format!("{} >= '{}'", column, start.to_rfc3339())),
+            TransferRange::Before(end) => Some(format!("{} < '{}'", column, end.to_rfc3339())),
+        };
+        let kept = condition("timestamp")
+            .map(|condition| format!("SELECT id FROM source.transfers WHERE {}", condition));
+        let filter = match table {
+            // Not tied to transfers: copied whole
+            "wallets"
+            | "currencies"
+            | "exchange_rates"
+            | "commodity_prices"
+            | "locale_profiles"
+            | "settings"
+            | "payees"
+            | "payee_aliases"
+            | "scheduled_transfers"
+            | "budgets"
+            | "envelopes"
+            | "envelope_assignments"
+            | "balance_assertions"
+            | "period_locks"
+            | "reconciliations"
+            | "year_closes" => None,
+            "transfers" => condition("timestamp"),
+            "transfer_tags"
+            | "transfer_amendments"
+            | "attachments"
+            | "reconciled_transfers"
+            | "lock_overrides" => kept.map(|kept| format!("transfer_id IN ({})", kept)),
+            "amendment_changes" => kept.map(|kept| {
+                format!(
+                    "amendment_id IN (SELECT id FROM source.transfer_amendments WHERE transfer_id IN ({}))",
+                    kept
+                )
+            }),
+            "lot_disposals" => kept.map(|kept| format!("sell_transfer_id IN ({})", kept)),
+            "attachment_blobs" => kept.map(|kept| {
+                format!(
+                    "hash IN (SELECT hash FROM source.attachments WHERE transfer_id IN ({}))",
+                    kept
+                )
+            }),
+            // Events follow the transfer that raised them, or their period without one
+            "budget_events" => kept.zip(condition("period_start")).map(|(kept, period)| {
+                format!(
+                    "transfer_id IN ({}) OR (transfer_id IS NULL AND {})",
+                    kept, period
+                )
+            }),
+            _ => anyhow::bail!("No rule for copying table {}", table),
+        };
+        Ok(filter
+            .map(|filter| format!(" WHERE {}", filter))
+            .unwrap_or_default())
+    }
 }
 
 /// Repository for persisting and querying wallets and transfers.
@@ -39,27 +117,32 @@ impl Repository {
         Ok(Self::new(pool))
     }
 
-    /// Run database migrations.
+    /// Run pending database migrations.
+    /// The schema version is tracked in `PRAGMA user_version`, so each migration runs once.
     pub async fn migrate(&self) -> Result<()> {
-        sqlx::query(MIGRATION_001_INITIAL)
-            .execute(&self.pool)
-            .await
-            .context("Failed to run migration 001")?;
-
-        sqlx::query(MIGRATION_002_BUDGETS)
-            .execute(&self.pool)
+        let current: i64 = sqlx::query("PRAGMA user_version")
+            .fetch_one(&self.pool)
             .await
-            .context("Failed to run migration 002")?;
+            .context("Failed to read schema version")?
+            .get(0);
 
-        sqlx::query(MIGRATION_003_SCHEDULED)
-            .execute(&self.pool)
-            .await
-            .context("Failed to run migration 003")?;
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            let version = index as i64 + 1;
+            if version <= current {
+                continue;
+            }
 
-        sqlx::query(MIGRATION_004_REPORTING)
-            .execute(&self.pool)
-            .await
-            .context("Failed to run migration 004")?;
+            let mut tx = self.pool.begin().await?;
+            sqlx::query(migration)
+                .execute(&mut *tx)
+                .await
+                .with_context(|| format!("Failed to run migration {:03}", version))?;
+            sqlx::query(&format!("PRAGMA user_version = {}", version))
+                .execute(&mut *tx)
+                .await
+                .context("Failed to update schema version")?;
+            tx.commit().await?;
+        }
 
         Ok(())
     }
@@ -79,8 +162,8 @@ impl Repository {
     pub async fn save_wallet(&self, wallet: &Wallet) -> Result<()> {
         sqlx::query(
             r#"
-            INSERT INTO wallets (id, name, wallet_type, currency, allow_negative, description, created_at, archived_at)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            INSERT INTO wallets (id, name, wallet_type, currency, allow_negative, description, created_at, archived_at, commodity, lot_method, parent_id, allow_postings)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(wallet.id.to_string())
@@ -91,6 +174,10 @@ impl Repository {
         .bind(&wallet.description)
         .bind(wallet.created_at.to_rfc3339())
         .bind(wallet.archived_at.map(|dt| dt.to_rfc3339()))
+        .bind(&wallet.commodity)
+        .bind(wallet.lot_method.as_str())
+        .bind(wallet.parent_id.map(|id| id.to_string()))
+        .bind(wallet.allow_postings)
         .execute(&self.pool)
         .await
         .context("Failed to save wallet")?;
@@ -101,7 +188,7 @@ impl Repository {
     pub async fn get_wallet(&self, id: WalletId) -> Result<Option<Wallet>> {
         let row = sqlx::query(
             r#"
-            SELECT id, name, wallet_type, currency, allow_negative, description, created_at, archived_at
+            SELECT id, name, wallet_type, currency, allow_negative, description, created_at, archived_at, commodity, lot_method, parent_id, allow_postings
             FROM wallets
             WHERE id = ?
             "#,
@@ -121,7 +208,7 @@ impl Repository {
     pub async fn get_wallet_by_name(&self, name: &str) -> Result<Option<Wallet>> {
         let row = sqlx::query(
             r#"
-            SELECT id, name, wallet_type, currency, allow_negative, description, created_at, archived_at
+            SELECT id, name, wallet_type, currency, allow_negative, description, created_at, archived_at, commodity, lot_method, parent_id, allow_postings
             FROM wallets
             WHERE name = ?
             "#,
@@ -140,9 +227,9 @@ impl Repository {
     /// List all wallets (optionally including archived).
     pub async fn list_wallets(&self, include_archived: bool) -> Result<Vec<Wallet>> {
         let query = if include_archived {
-            "SELECT id, name, wallet_type, currency, allow_negative, description, created_at, archived_at FROM wallets ORDER BY name"
+            "SELECT id, name, wallet_type, currency, allow_negative, description, created_at, archived_at, commodity, lot_method, parent_id, allow_postings FROM wallets ORDER BY name"
         } else {
-            "SELECT id, name, wallet_type, currency, allow_negative, description, created_at, archived_at FROM wallets WHERE archived_at IS NULL ORDER BY name"
+            "SELECT id, name, wallet_type, currency, allow_negative, description, created_at, archived_at, commodity, lot_method, parent_id, allow_postings FROM wallets WHERE archived_at IS NULL ORDER BY name"
         };
 
         let rows = sqlx::query(query)
@@ -153,6 +240,59 @@ impl Repository {
         rows.iter().map(Self::row_to_wallet).collect()
     }
 
+    /// Change how sales from a holding wallet pick lots by default.
+    pub async fn update_wallet_lot_method(
+        &self,
+        id: WalletId,
+        method: crate::domain::LotMethod,
+    ) -> Result<()> {
+        sqlx::query("UPDATE wallets SET lot_method = ? WHERE id = ?")
+            .bind(method.as_str())
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .context("Failed to update lot method")?;
+        Ok(())
+    }
+
+    /// Move a wallet under another wallet, or to the top level with `None`.
+    pub async fn update_wallet_parent(
+        &self,
+        id: WalletId,
+        parent_id: Option<WalletId>,
+    ) -> Result<()> {
+        sqlx::query("UPDATE wallets SET parent_id = ? WHERE id = ?")
+            .bind(parent_id.map(|id| id.to_string()))
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .context("Failed to update parent wallet")?;
+        Ok(())
+    }
+
+    /// Allow or forbid transfers into a wallet that has sub-wallets.
+    pub async fn update_wallet_allow_postings(&self, id: WalletId, allow: bool) -> Result<()> {
+        sqlx::query("UPDATE wallets SET allow_postings = ? WHERE id = ?")
+            .bind(allow)
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .context("Failed to update wallet postings")?;
+        Ok(())
+    }
+
+    /// Returns true if the wallet has sub-wallets that are not archived.
+    pub async fn has_child_wallets(&self, id: WalletId) -> Result<bool> {
+        let row = sqlx::query(
+            "SELECT EXISTS(SELECT 1 FROM wallets WHERE parent_id = ? AND archived_at IS NULL) AS has_children",
+        )
+        .bind(id.to_string())
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to check for sub-wallets")?;
+        Ok(row.get::<i32, _>("has_children") != 0)
+    }
+
     /// Archive a wallet (soft delete).
     pub async fn archive_wallet(&self, id: WalletId) -> Result<()> {
         let now = Utc::now().to_rfc3339();
@@ -170,6 +310,8 @@ impl Repository {
         let wallet_type_str: String = row.get("wallet_type");
         let created_at_str: String = row.get("created_at");
         let archived_at_str: Option<String> = row.get("archived_at");
+        let lot_method_str: String = row.get("lot_method");
+        let parent_id_str: Option<String> = row.get("parent_id");
 
         Ok(Wallet {
             id: Uuid::parse_str(&id_str).context("Invalid wallet ID")?,
@@ -188,6 +330,15 @@ impl Repository {
                 .transpose()
                 .context("Invalid archived_at timestamp")?
                 .map(|dt| dt.with_timezone(&Utc)),
+            commodity: row.get("commodity"),
+            lot_method: lot_method_str.parse().map_err(|e| {
+                anyhow::anyhow!("Invalid lot method: {}. Error: {}", lot_method_str, e)
+            })?,
+            parent_id: parent_id_str
+                .map(|s| Uuid::parse_str(&s))
+                .transpose()
+                .context("Invalid parent wallet ID")?,
+            allow_postings: row.get::<i32, _>("allow_postings") != 0,
         })
     }
 
@@ -198,16 +349,38 @@ impl Repository {
     /// Save a new transfer to the database.
     /// Automatically assigns the next sequence number.
     pub async fn save_transfer(&self, transfer: &mut Transfer) -> Result<()> {
-        // Get and increment sequence number atomically
-        let sequence = self.next_sequence().await?;
+        self.save_transfers(std::slice::from_mut(transfer), &[])
+            .await
+    }
+
+    /// Save several transfers atomically (e.g. the legs of a split), together with
+    /// the lock overrides that let them into a locked period.
+    /// Either all transfers are stored with consecutive sequence numbers, or none is.
+    pub async fn save_transfers(
+        &self,
+        transfers: &mut [Transfer],
+        overrides: &[LockOverride],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for transfer in transfers.iter_mut() {
+            Self::insert_transfer(&mut tx, transfer).await?;
+        }
+        Self::insert_lock_overrides(&mut tx, overrides).await?;
+        tx.commit().await.context("Failed to commit transfers")?;
+        Ok(())
+    }
+
+    async fn insert_transfer(conn: &mut SqliteConnection, transfer: &mut Transfer) -> Result<()> {
+        // Get and increment sequence number within the caller's transaction
+        let sequence = Self::next_sequence(&mut *conn).await?;
         transfer.sequence = sequence;
 
         let tags_json = serde_json::to_string(&transfer.tags)?;
 
         sqlx::query(
             r#"
-            INSERT INTO transfers (id, sequence, from_wallet_id, to_wallet_id, amount_cents, timestamp, recorded_at, description, category, tags, reverses, external_ref)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            INSERT INTO transfers (id, sequence, from_wallet_id, to_wallet_id, amount_cents, timestamp, recorded_at, description, category, tags, reverses, external_ref, split_id, quantity, unit_price, cleared, payee_id)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(transfer.id.to_string())
@@ -222,7 +395,12 @@ impl Repository {
         .bind(&tags_json)
         .bind(transfer.reverses.map(|id| id.to_string()))
         .bind(&transfer.external_ref)
-        .execute(&self.pool)
+        .bind(transfer.split_id.map(|id| id.to_string()))
+        .bind(transfer.quantity)
+        .bind(transfer.unit_price)
+        .bind(transfer.cleared.as_str())
+        .bind(transfer.payee_id.map(|id| id.to_string()))
+        .execute(&mut *conn)
         .await
         .context("Failed to save transfer")?;
 
@@ -230,7 +408,7 @@ impl Repository {
     }
 
     /// Get the next sequence number and increment the counter.
-    async fn next_sequence(&self) -> Result<i64> {
+    async fn next_sequence(conn: &mut SqliteConnection) -> Result<i64> {
         let row = sqlx::query(
             r#"
             UPDATE sequence_counter
@@ -239,7 +417,7 @@ impl Repository {
             RETURNING value
             "#,
         )
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *conn)
         .await
         .context("Failed to get next sequence number")?;
 
@@ -250,7 +428,7 @@ impl Repository {
     pub async fn get_transfer(&self, id: TransferId) -> Result<Option<Transfer>> {
         let row = sqlx::query(
             r#"
-            SELECT id, sequence, from_wallet_id, to_wallet_id, amount_cents, timestamp, recorded_at, description, category, tags, reverses, external_ref
+            SELECT id, sequence, from_wallet_id, to_wallet_id, amount_cents, timestamp, recorded_at, description, category, tags, reverses, external_ref, split_id, quantity, unit_price, cleared, payee_id
             FROM transfers
             WHERE id = ?
             "#,
@@ -270,7 +448,7 @@ impl Repository {
     pub async fn list_transfers(&self) -> Result<Vec<Transfer>> {
         let rows = sqlx::query(
             r#"
-            SELECT id, sequence, from_wallet_id, to_wallet_id, amount_cents, timestamp, recorded_at, description, category, tags, reverses, external_ref
+            SELECT id, sequence, from_wallet_id, to_wallet_id, amount_cents, timestamp, recorded_at, description, category, tags, reverses, external_ref, split_id, quantity, unit_price, cleared, payee_id
             FROM transfers
             ORDER BY sequence
             "#,
@@ -286,7 +464,7 @@ impl Repository {
     pub async fn list_transfers_for_wallet(&self, wallet_id: WalletId) -> Result<Vec<Transfer>> {
         let rows = sqlx::query(
             r#"
-            SELECT id, sequence, from_wallet_id, to_wallet_id, amount_cents, timestamp, recorded_at, description, category, tags, reverses, external_ref
+            SELECT id, sequence, from_wallet_id, to_wallet_id, amount_cents, timestamp, recorded_at, description, category, tags, reverses, external_ref, split_id, quantity, unit_price, cleared, payee_id
             FROM transfers
             WHERE from_wallet_id = ? OR to_wallet_id = ?
             ORDER BY sequence
@@ -301,18 +479,68 @@ impl Repository {
         rows.iter().map(Self::row_to_transfer).collect()
     }
 
-    /// List transfers with optional filters.
+    /// Get the transfer carrying an external reference (the first one recorded).
+    pub async fn get_transfer_by_external_ref(
+        &self,
+        external_ref: &str,
+    ) -> Result<Option<Transfer>> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, sequence, from_wallet_id, to_wallet_id, amount_cents, timestamp, recorded_at, description, category, tags, reverses, external_ref, split_id, quantity, unit_price, cleared, payee_id
+            FROM transfers
+            WHERE external_ref = ?
+            ORDER BY sequence
+            LIMIT 1
+            "#,
+        )
+        .bind(external_ref)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch transfer by external reference")?;
+
+        row.as_ref().map(Self::row_to_transfer).transpose()
+    }
+
+    /// Mark a transfer as seen on a statement: cleared unless already reconciled,
+    /// and given the statement's reference unless it has one.
+    pub async fn update_transfer_match(
+        &self,
+        id: TransferId,
+        external_ref: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE transfers
+            SET external_ref = COALESCE(external_ref, ?),
+                cleared = CASE WHEN cleared = 'uncleared' THEN 'cleared' ELSE cleared END
+            WHERE id = ?
+            "#,
+        )
+        .bind(external_ref)
+        .bind(id.to_string())
+        .execute(&self.pool)
+        .await
+        .context("Failed to match transfer")?;
+
+        Ok(())
+    }
+
+    /// List transfers with optional filters. A category filter includes its sub-categories;
+    /// a tag filter keeps transfers with any or all of the (distinct) tags.
+    #[allow(clippy::too_many_arguments)]
     pub async fn list_transfers_filtered(
         &self,
         wallet_id: Option<WalletId>,
         category: Option<&str>,
+        tags: &[String],
+        tag_match: TagMatch,
         from_date: Option<DateTime<Utc>>,
         to_date: Option<DateTime<Utc>>,
         limit: Option<usize>,
     ) -> Result<Vec<Transfer>> {
         // Build query dynamically based on filters
         let mut query = String::from(
-            "SELECT id, sequence, from_wallet_id, to_wallet_id, amount_cents, timestamp, recorded_at, description, category, tags, reverses, external_ref FROM transfers WHERE 1=1",
+            "SELECT id, sequence, from_wallet_id, to_wallet_id, amount_cents, timestamp, recorded_at, description, category, tags, reverses, external_ref, split_id, quantity, unit_price, cleared, payee_id FROM transfers WHERE 1=1",
         );
 
         // Collect all string bindings first so they live long enough
@@ -324,7 +552,21 @@ impl Repository {
             query.push_str(" AND (from_wallet_id = ? OR to_wallet_id = ?)");
         }
         if category.is_some() {
-            query.push_str(" AND category = ?");
+            query.push_str(" AND (category = ? OR substr(category, 1, length(?) + 1) = ? || ':')");
+        }
+        if !tags.is_empty() {
+            let placeholders = vec!["?"; tags.len()].join(", ");
+            query.push_str(&format!(
+                " AND id IN (SELECT transfer_id FROM transfer_tags WHERE tag IN ({})",
+                placeholders
+            ));
+            if tag_match == TagMatch::All {
+                query.push_str(&format!(
+                    " GROUP BY transfer_id HAVING COUNT(*) = {}",
+                    tags.len()
+                ));
+            }
+            query.push(')');
         }
         if from_date.is_some() {
             query.push_str(" AND timestamp >= ?");
@@ -346,7 +588,10 @@ impl Repository {
             sql_query = sql_query.bind(wid_str).bind(wid_str);
         }
         if let Some(cat) = category {
-            sql_query = sql_query.bind(cat);
+            sql_query = sql_query.bind(cat).bind(cat).bind(cat);
+        }
+        for tag in tags {
+            sql_query = sql_query.bind(tag);
         }
         if let Some(ref fd_str) = from_date_str {
             sql_query = sql_query.bind(fd_str);
@@ -419,6 +664,125 @@ impl Repository {
         Ok(balances)
     }
 
+    /// Net change of every wallet's balance on each day (UTC) with transfers in
+    /// `[start, end)`, as `(day, wallet, change)` in day order. Summed in one
+    /// query, so walking balances through a long history stays cheap.
+    pub async fn daily_balance_changes(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<(NaiveDate, WalletId, Cents)>> {
+        let start = start.to_rfc3339();
+        let end = end.to_rfc3339();
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                substr(timestamp, 1, 10) as day,
+                wallet_id,
+                SUM(amount) as change
+            FROM (
+                SELECT timestamp, to_wallet_id as wallet_id, amount_cents as amount FROM transfers
+                WHERE timestamp >= ? AND timestamp < ?
+                UNION ALL
+                SELECT timestamp, from_wallet_id as wallet_id, -amount_cents as amount FROM transfers
+                WHERE timestamp >= ? AND timestamp < ?
+            )
+            GROUP BY day, wallet_id
+            ORDER BY day
+            "#,
+        )
+        .bind(&start)
+        .bind(&end)
+        .bind(&start)
+        .bind(&end)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to compute daily balance changes")?;
+
+        rows.iter()
+            .map(|row| {
+                let day: String = row.get("day");
+                let wallet_id: String = row.get("wallet_id");
+                Ok((
+                    NaiveDate::parse_from_str(&day, "%Y-%m-%d").context("Invalid transfer date")?,
+                    Uuid::parse_str(&wallet_id).context("Invalid wallet ID")?,
+                    row.get("change"),
+                ))
+            })
+            .collect()
+    }
+
+    /// Net change of every wallet's commodity units on each day (UTC) with trades
+    /// in `[start, end)`, as `(day, wallet, change)` in day order.
+    pub async fn daily_quantity_changes(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<(NaiveDate, WalletId, i64)>> {
+        let start = start.to_rfc3339();
+        let end = end.to_rfc3339();
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                substr(timestamp, 1, 10) as day,
+                wallet_id,
+                SUM(quantity) as change
+            FROM (
+                SELECT timestamp, to_wallet_id as wallet_id, quantity FROM transfers
+                WHERE quantity IS NOT NULL AND timestamp >= ? AND timestamp < ?
+                UNION ALL
+                SELECT timestamp, from_wallet_id as wallet_id, -quantity FROM transfers
+                WHERE quantity IS NOT NULL AND timestamp >= ? AND timestamp < ?
+            )
+            GROUP BY day, wallet_id
+            ORDER BY day
+            "#,
+        )
+        .bind(&start)
+        .bind(&end)
+        .bind(&start)
+        .bind(&end)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to compute daily quantity changes")?;
+
+        rows.iter()
+            .map(|row| {
+                let day: String = row.get("day");
+                let wallet_id: String = row.get("wallet_id");
+                Ok((
+                    NaiveDate::parse_from_str(&day, "%Y-%m-%d").context("Invalid transfer date")?,
+                    Uuid::parse_str(&wallet_id).context("Invalid wallet ID")?,
+                    row.get("change"),
+                ))
+            })
+            .collect()
+    }
+
+    /// Compute the units of commodity held by a wallet (bought in minus sold out).
+    pub async fn compute_quantity(&self, wallet_id: WalletId) -> Result<i64> {
+        let wallet_id_str = wallet_id.to_string();
+
+        let row = sqlx::query(
+            r#"
+            SELECT
+                COALESCE(SUM(CASE WHEN to_wallet_id = ? THEN quantity ELSE 0 END), 0) -
+                COALESCE(SUM(CASE WHEN from_wallet_id = ? THEN quantity ELSE 0 END), 0) as quantity
+            FROM transfers
+            WHERE (from_wallet_id = ? OR to_wallet_id = ?) AND quantity IS NOT NULL
+            "#,
+        )
+        .bind(&wallet_id_str)
+        .bind(&wallet_id_str)
+        .bind(&wallet_id_str)
+        .bind(&wallet_id_str)
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to compute quantity")?;
+
+        Ok(row.get("quantity"))
+    }
+
     /// Get all transfers that reverse a given transfer (for partial reversal tracking).
     pub async fn get_reversals_for_transfer(
         &self,
@@ -426,7 +790,7 @@ impl Repository {
     ) -> Result<Vec<Transfer>> {
         let rows = sqlx::query(
             r#"
-            SELECT id, sequence, from_wallet_id, to_wallet_id, amount_cents, timestamp, recorded_at, description, category, tags, reverses, external_ref
+            SELECT id, sequence, from_wallet_id, to_wallet_id, amount_cents, timestamp, recorded_at, description, category, tags, reverses, external_ref, split_id, quantity, unit_price, cleared, payee_id
             FROM transfers
             WHERE reverses = ?
             ORDER BY sequence
@@ -440,6 +804,24 @@ impl Repository {
         rows.iter().map(Self::row_to_transfer).collect()
     }
 
+    /// Get all legs of a split transfer, ordered by sequence number.
+    pub async fn get_split_legs(&self, split_id: SplitId) -> Result<Vec<Transfer>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, sequence, from_wallet_id, to_wallet_id, amount_cents, timestamp, recorded_at, description, category, tags, reverses, external_ref, split_id, quantity, unit_price, cleared, payee_id
+            FROM transfers
+            WHERE split_id = ?
+            ORDER BY sequence
+            "#,
+        )
+        .bind(split_id.to_string())
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to get split legs")?;
+
+        rows.iter().map(Self::row_to_transfer).collect()
+    }
+
     /// Get total amount already reversed for a transfer.
     pub async fn get_total_reversed(&self, transfer_id: TransferId) -> Result<Cents> {
         let row = sqlx::query(
@@ -570,12 +952,46 @@ impl Repository {
         .await?
         .get("count");
 
+        // Reconciled transfers that no longer match what was reconciled
+        let modified_reconciled: i64 = sqlx::query(
+            r#"
+            SELECT COUNT(DISTINCT r.transfer_id) as count
+            FROM reconciled_transfers r
+            LEFT JOIN transfers t ON t.id = r.transfer_id
+            WHERE t.id IS NULL
+               OR t.from_wallet_id != r.from_wallet_id
+               OR t.to_wallet_id != r.to_wallet_id
+               OR t.amount_cents != r.amount_cents
+               OR t.timestamp != r.timestamp
+               OR t.cleared != 'reconciled'
+            "#,
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .get("count");
+
+        // Reversals recorded after the transfer they reverse was reconciled
+        let reversed_reconciled: i64 = sqlx::query(
+            r#"
+            SELECT COUNT(DISTINCT rev.id) as count
+            FROM transfers rev
+            JOIN reconciled_transfers r ON r.transfer_id = rev.reverses
+            JOIN reconciliations s ON s.id = r.reconciliation_id
+            WHERE rev.recorded_at > s.finished_at
+            "#,
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .get("count");
+
         Ok(IntegrityStats {
             wallet_count,
             transfer_count,
             has_sequence_gaps,
             invalid_wallet_refs: invalid_refs,
             invalid_amounts,
+            modified_reconciled,
+            reversed_reconciled,
         })
     }
 
@@ -587,15 +1003,26 @@ impl Repository {
     pub async fn save_budget(&self, budget: &crate::domain::Budget) -> Result<()> {
         sqlx::query(
             r#"
-            INSERT INTO budgets (id, name, category, period_type, amount_cents, created_at)
-            VALUES (?, ?, ?, ?, ?, ?)
+            INSERT INTO budgets (id, name, category, filter, period_type, amount_cents,
+                                 currency, rollover, rollover_since, alert_thresholds,
+                                 created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(budget.id.to_string())
         .bind(&budget.name)
         .bind(&budget.category)
+        .bind(serde_json::to_string(&budget.filter)?)
         .bind(budget.period_type.as_str())
         .bind(budget.amount_cents)
+        .bind(&budget.currency)
+        .bind(budget.rollover.as_str())
+        .bind(
+            budget
+                .rollover_since
+                .map(|d| d.format("%Y-%m-%d").to_string()),
+        )
+        .bind(serde_json::to_string(&budget.alert_thresholds)?)
         .bind(budget.created_at.to_rfc3339())
         .execute(&self.pool)
         .await
@@ -607,7 +1034,8 @@ impl Repository {
     pub async fn get_budget_by_name(&self, name: &str) -> Result<Option<crate::domain::Budget>> {
         let row = sqlx::query(
             r#"
-            SELECT id, name, category, period_type, amount_cents, created_at
+            SELECT id, name, category, filter, period_type, amount_cents, currency,
+                   rollover, rollover_since, alert_thresholds, created_at
             FROM budgets
             WHERE name = ?
             "#,
@@ -627,7 +1055,8 @@ impl Repository {
     pub async fn list_budgets(&self) -> Result<Vec<crate::domain::Budget>> {
         let rows = sqlx::query(
             r#"
-            SELECT id, name, category, period_type, amount_cents, created_at
+            SELECT id, name, category, filter, period_type, amount_cents, currency,
+                   rollover, rollover_since, alert_thresholds, created_at
             FROM budgets
             ORDER BY name
             "#,
@@ -639,33 +1068,205 @@ impl Repository {
         rows.iter().map(Self::row_to_budget).collect()
     }
 
-    /// Delete a budget.
+    /// Change the rollover policy of a budget.
+    pub async fn update_budget_rollover(
+        &self,
+        id: crate::domain::BudgetId,
+        rollover: crate::domain::RolloverPolicy,
+        since: Option<NaiveDate>,
+    ) -> Result<()> {
+        sqlx::query("UPDATE budgets SET rollover = ?, rollover_since = ? WHERE id = ?")
+            .bind(rollover.as_str())
+            .bind(since.map(|d| d.format("%Y-%m-%d").to_string()))
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .context("Failed to update budget rollover")?;
+        Ok(())
+    }
+
+    /// Change the alert thresholds of a budget.
+    pub async fn update_budget_alert_thresholds(
+        &self,
+        id: crate::domain::BudgetId,
+        thresholds: &[u32],
+    ) -> Result<()> {
+        sqlx::query("UPDATE budgets SET alert_thresholds = ? WHERE id = ?")
+            .bind(serde_json::to_string(thresholds)?)
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .context("Failed to update budget alert thresholds")?;
+        Ok(())
+    }
+
+    /// Delete a budget along with the events it raised.
     pub async fn delete_budget(&self, name: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "DELETE FROM budget_events WHERE budget_id IN (SELECT id FROM budgets WHERE name = ?)",
+        )
+        .bind(name)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to delete budget events")?;
         sqlx::query("DELETE FROM budgets WHERE name = ?")
             .bind(name)
-            .execute(&self.pool)
+            .execute(&mut *tx)
             .await
             .context("Failed to delete budget")?;
+        tx.commit().await?;
         Ok(())
     }
 
-    /// Sum transfers by category within a date range.
+    /// Record a budget event unless its threshold already fired in the period.
+    /// Returns false when it had.
+    pub async fn save_budget_event(&self, event: &crate::domain::BudgetEvent) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO budget_events (budget_id, period_start, threshold, period_end,
+                                                 limit_cents, spent_cents, transfer_id, fired_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(event.budget_id.to_string())
+        .bind(event.period_start.to_rfc3339())
+        .bind(event.threshold)
+        .bind(event.period_end.to_rfc3339())
+        .bind(event.limit)
+        .bind(event.spent)
+        .bind(event.transfer_id.map(|id| id.to_string()))
+        .bind(event.fired_at.to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .context("Failed to save budget event")?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// List the events raised by all budgets, or one, most recent first.
+    pub async fn list_budget_events(
+        &self,
+        budget_id: Option<crate::domain::BudgetId>,
+    ) -> Result<Vec<crate::domain::BudgetEvent>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT e.budget_id, b.name, b.currency, e.period_start, e.threshold, e.period_end,
+                   e.limit_cents, e.spent_cents, e.transfer_id, e.fired_at
+            FROM budget_events e
+            JOIN budgets b ON b.id = e.budget_id
+            WHERE ? IS NULL OR e.budget_id = ?
+            ORDER BY e.fired_at DESC, e.threshold DESC
+            "#,
+        )
+        .bind(budget_id.map(|id| id.to_string()))
+        .bind(budget_id.map(|id| id.to_string()))
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list budget events")?;
+
+        rows.iter().map(Self::row_to_budget_event).collect()
+    }
+
+    /// Sum the transfers counting against a budget within a date range: those
+    /// matching its categories (sub-categories included), wallets and tags, and
+    /// when it has a currency, going into wallets of that currency.
+    pub async fn sum_transfers_for_budget(
+        &self,
+        budget: &crate::domain::Budget,
+        from_date: DateTime<Utc>,
+        to_date: DateTime<Utc>,
+    ) -> Result<Cents> {
+        let categories = budget.categories();
+        let filter = &budget.filter;
+        let mut query = String::from(
+            r#"
+            SELECT COALESCE(SUM(t.amount_cents), 0) as total
+            FROM transfers t
+            JOIN wallets w ON w.id = t.to_wallet_id
+            WHERE t.timestamp >= ? AND t.timestamp < ?
+              AND (? IS NULL OR UPPER(w.currency) = UPPER(?))"#,
+        );
+
+        if !categories.is_empty() {
+            let conditions = vec![
+                "t.category = ? OR substr(t.category, 1, length(?) + 1) = ? || ':'";
+                categories.len()
+            ];
+            query.push_str(&format!(" AND ({})", conditions.join(" OR ")));
+        }
+        for (column, wallets) in [
+            ("t.from_wallet_id", &filter.from_wallets),
+            ("t.to_wallet_id", &filter.to_wallets),
+        ] {
+            if !wallets.is_empty() {
+                let placeholders = vec!["?"; wallets.len()].join(", ");
+                query.push_str(&format!(" AND {} IN ({})", column, placeholders));
+            }
+        }
+        if !filter.tags.is_empty() {
+            let placeholders = vec!["?"; filter.tags.len()].join(", ");
+            query.push_str(&format!(
+                " AND t.id IN (SELECT transfer_id FROM transfer_tags WHERE tag IN ({})",
+                placeholders
+            ));
+            if filter.tag_match == TagMatch::All {
+                query.push_str(&format!(
+                    " GROUP BY transfer_id HAVING COUNT(*) = {}",
+                    filter.tags.len()
+                ));
+            }
+            query.push(')');
+        }
+
+        let mut sql_query = sqlx::query(&query)
+            .bind(from_date.to_rfc3339())
+            .bind(to_date.to_rfc3339())
+            .bind(&budget.currency)
+            .bind(&budget.currency);
+        for category in &categories {
+            sql_query = sql_query.bind(*category).bind(*category).bind(*category);
+        }
+        for wallet_id in filter.from_wallets.iter().chain(&filter.to_wallets) {
+            sql_query = sql_query.bind(wallet_id.to_string());
+        }
+        for tag in &filter.tags {
+            sql_query = sql_query.bind(tag);
+        }
+
+        let row = sql_query
+            .fetch_one(&self.pool)
+            .await
+            .context("Failed to sum transfers for budget")?;
+
+        Ok(row.get("total"))
+    }
+
+    /// Sum transfers in a category and all its sub-categories within a date range.
+    /// When a currency is given, only transfers into wallets of that currency count.
     pub async fn sum_transfers_by_category(
         &self,
         category: &str,
+        currency: Option<&str>,
         from_date: DateTime<Utc>,
         to_date: DateTime<Utc>,
     ) -> Result<Cents> {
         let row = sqlx::query(
             r#"
-            SELECT COALESCE(SUM(amount_cents), 0) as total
-            FROM transfers
-            WHERE category = ? AND timestamp >= ? AND timestamp < ?
+            SELECT COALESCE(SUM(t.amount_cents), 0) as total
+            FROM transfers t
+            JOIN wallets w ON w.id = t.to_wallet_id
+            WHERE (t.category = ? OR substr(t.category, 1, length(?) + 1) = ? || ':')
+              AND t.timestamp >= ? AND t.timestamp < ?
+              AND (? IS NULL OR UPPER(w.currency) = UPPER(?))
             "#,
         )
         .bind(category)
+        .bind(category)
+        .bind(category)
         .bind(from_date.to_rfc3339())
         .bind(to_date.to_rfc3339())
+        .bind(currency)
+        .bind(currency)
         .fetch_one(&self.pool)
         .await
         .context("Failed to sum transfers by category")?;
@@ -755,24 +1356,2264 @@ impl Repository {
         Ok(results)
     }
 
-    // ========================
-    // Scheduled Transfer operations
-    // ========================
-
-    /// Save a new scheduled transfer to the database.
-    pub async fn save_scheduled_transfer(
+    /// Aggregate transfers by category and destination wallet currency within a date range.
+    /// Used by reports that convert totals into a single reporting currency.
+    pub async fn aggregate_by_category_and_currency(
         &self,
-        st: &crate::domain::ScheduledTransfer,
-    ) -> Result<()> {
-        sqlx::query(
+        from_date: DateTime<Utc>,
+        to_date: DateTime<Utc>,
+    ) -> Result<Vec<(String, crate::application::CategoryAggregate)>> {
+        let rows = sqlx::query(
             r#"
-            INSERT INTO scheduled_transfers (id, name, from_wallet_id, to_wallet_id, amount_cents, pattern, start_date, end_date, last_executed_at, description, category, status, created_at)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            SELECT
+                t.category,
+                w.currency,
+                COUNT(*) as count,
+                SUM(t.amount_cents) as total
+            FROM transfers t
+            JOIN wallets w ON w.id = t.to_wallet_id
+            WHERE t.category IS NOT NULL
+              AND t.timestamp >= ?
+              AND t.timestamp < ?
+            GROUP BY t.category, w.currency
             "#,
         )
-        .bind(st.id.to_string())
-        .bind(&st.name)
-        .bind(st.from_wallet.to_string())
+        .bind(from_date.to_rfc3339())
+        .bind(to_date.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to aggregate transfers by category and currency")?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            let count: i64 = row.get("count");
+            let total: Cents = row.get("total");
+            let average = if count > 0 { total / count } else { 0 };
+
+            results.push((
+                row.get("currency"),
+                crate::application::CategoryAggregate {
+                    category: row.get("category"),
+                    count,
+                    total,
+                    average,
+                },
+            ));
+        }
+
+        Ok(results)
+    }
+
+    /// Aggregate tagged transfers by tag and destination wallet currency within a
+    /// date range. A transfer with several tags counts once under each of them.
+    /// Returns (tag, currency, count, total) rows.
+    pub async fn aggregate_by_tag_and_currency(
+        &self,
+        from_date: DateTime<Utc>,
+        to_date: DateTime<Utc>,
+    ) -> Result<Vec<(String, String, i64, Cents)>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                tt.tag,
+                w.currency,
+                COUNT(*) as count,
+                SUM(t.amount_cents) as total
+            FROM transfer_tags tt
+            JOIN transfers t ON t.id = tt.transfer_id
+            JOIN wallets w ON w.id = t.to_wallet_id
+            WHERE t.timestamp >= ?
+              AND t.timestamp < ?
+            GROUP BY tt.tag, w.currency
+            "#,
+        )
+        .bind(from_date.to_rfc3339())
+        .bind(to_date.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to aggregate transfers by tag")?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                (
+                    row.get("tag"),
+                    row.get("currency"),
+                    row.get("count"),
+                    row.get("total"),
+                )
+            })
+            .collect())
+    }
+
+    /// Total of the transfers carrying at least one tag within a date range, each
+    /// transfer counted once, per destination wallet currency.
+    pub async fn sum_tagged_by_currency(
+        &self,
+        from_date: DateTime<Utc>,
+        to_date: DateTime<Utc>,
+    ) -> Result<Vec<(String, Cents)>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT w.currency, SUM(t.amount_cents) as total
+            FROM transfers t
+            JOIN wallets w ON w.id = t.to_wallet_id
+            WHERE t.id IN (SELECT transfer_id FROM transfer_tags)
+              AND t.timestamp >= ?
+              AND t.timestamp < ?
+            GROUP BY w.currency
+            "#,
+        )
+        .bind(from_date.to_rfc3339())
+        .bind(to_date.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to sum tagged transfers")?;
+
+        Ok(rows
+            .iter()
+            .map(|row| (row.get("currency"), row.get("total")))
+            .collect())
+    }
+
+    /// Aggregate transfers by wallet type and wallet currency within a date range.
+    /// Returns (inflow, outflow) for each (wallet type, currency) pair.
+    pub async fn aggregate_by_wallet_type_and_currency(
+        &self,
+        from_date: DateTime<Utc>,
+        to_date: DateTime<Utc>,
+    ) -> Result<std::collections::HashMap<(crate::domain::WalletType, String), (Cents, Cents)>>
+    {
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                w.wallet_type,
+                w.currency,
+                SUM(CASE WHEN t.to_wallet_id = w.id THEN t.amount_cents ELSE 0 END) as inflow,
+                SUM(CASE WHEN t.from_wallet_id = w.id THEN t.amount_cents ELSE 0 END) as outflow
+            FROM wallets w
+            LEFT JOIN transfers t ON (t.from_wallet_id = w.id OR t.to_wallet_id = w.id)
+            WHERE t.timestamp >= ? AND t.timestamp < ?
+            GROUP BY w.wallet_type, w.currency
+            "#,
+        )
+        .bind(from_date.to_rfc3339())
+        .bind(to_date.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to aggregate transfers by wallet type and currency")?;
+
+        let mut results = std::collections::HashMap::new();
+        for row in rows {
+            let wallet_type_str: String = row.get("wallet_type");
+            if let Ok(wallet_type) = wallet_type_str.parse::<crate::domain::WalletType>() {
+                let currency: String = row.get("currency");
+                let inflow: Cents = row.get("inflow");
+                let outflow: Cents = row.get("outflow");
+                results.insert((wallet_type, currency), (inflow, outflow));
+            }
+        }
+
+        Ok(results)
+    }
+
+    // ========================
+    // Exchange rate operations
+    // ========================
+
+    /// Save a new exchange rate.
+    pub async fn save_exchange_rate(&self, rate: &crate::domain::ExchangeRate) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO exchange_rates (id, base_currency, quote_currency, rate, effective_date, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(rate.id.to_string())
+        .bind(&rate.base)
+        .bind(&rate.quote)
+        .bind(rate.rate.to_string())
+        .bind(rate.effective_date.to_rfc3339())
+        .bind(rate.created_at.to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .context("Failed to save exchange rate")?;
+        Ok(())
+    }
+
+    /// Find the most recent rate for a currency pair effective on or before the given date.
+    pub async fn find_exchange_rate(
+        &self,
+        base: &str,
+        quote: &str,
+        as_of: DateTime<Utc>,
+    ) -> Result<Option<crate::domain::ExchangeRate>> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, base_currency, quote_currency, rate, effective_date, created_at
+            FROM exchange_rates
+            WHERE base_currency = ? AND quote_currency = ? AND effective_date <= ?
+            ORDER BY effective_date DESC, created_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(base)
+        .bind(quote)
+        .bind(as_of.to_rfc3339())
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to find exchange rate")?;
+
+        match row {
+            Some(row) => Ok(Some(Self::row_to_exchange_rate(&row)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// List exchange rates, ordered by pair and effective date.
+    pub async fn list_exchange_rates(&self) -> Result<Vec<crate::domain::ExchangeRate>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, base_currency, quote_currency, rate, effective_date, created_at
+            FROM exchange_rates
+            ORDER BY base_currency, quote_currency, effective_date
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list exchange rates")?;
+
+        rows.iter().map(Self::row_to_exchange_rate).collect()
+    }
+
+    fn row_to_exchange_rate(row: &sqlx::sqlite::SqliteRow) -> Result<crate::domain::ExchangeRate> {
+        let id_str: String = row.get("id");
+        let effective_date_str: String = row.get("effective_date");
+        let created_at_str: String = row.get("created_at");
+        let rate_str: String = row.get("rate");
+
+        Ok(crate::domain::ExchangeRate {
+            id: Uuid::parse_str(&id_str).context("Invalid exchange rate ID")?,
+            base: row.get("base_currency"),
+            quote: row.get("quote_currency"),
+            rate: rate_str.parse().context("Invalid exchange rate")?,
+            effective_date: DateTime::parse_from_rfc3339(&effective_date_str)
+                .context("Invalid effective_date")?
+                .with_timezone(&Utc),
+            created_at: DateTime::parse_from_rfc3339(&created_at_str)
+                .context("Invalid created_at")?
+                .with_timezone(&Utc),
+        })
+    }
+
+    // ========================
+    // Commodity price operations
+    // ========================
+
+    /// Save a new commodity price.
+    pub async fn save_commodity_price(&self, price: &crate::domain::CommodityPrice) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO commodity_prices (id, commodity, currency, price, effective_date, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(price.id.to_string())
+        .bind(&price.commodity)
+        .bind(&price.currency)
+        .bind(price.price)
+        .bind(price.effective_date.to_rfc3339())
+        .bind(price.created_at.to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .context("Failed to save commodity price")?;
+        Ok(())
+    }
+
+    /// Find the most recent price for a commodity effective on or before the given date.
+    pub async fn find_commodity_price(
+        &self,
+        commodity: &str,
+        as_of: DateTime<Utc>,
+    ) -> Result<Option<crate::domain::CommodityPrice>> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, commodity, currency, price, effective_date, created_at
+            FROM commodity_prices
+            WHERE commodity = ? AND effective_date <= ?
+            ORDER BY effective_date DESC, created_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(commodity)
+        .bind(as_of.to_rfc3339())
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to find commodity price")?;
+
+        match row {
+            Some(row) => Ok(Some(Self::row_to_commodity_price(&row)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// List commodity prices (optionally for one commodity), ordered by commodity and date.
+    pub async fn list_commodity_prices(
+        &self,
+        commodity: Option<&str>,
+    ) -> Result<Vec<crate::domain::CommodityPrice>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, commodity, currency, price, effective_date, created_at
+            FROM commodity_prices
+            WHERE ? IS NULL OR commodity = ?
+            ORDER BY commodity, effective_date
+            "#,
+        )
+        .bind(commodity)
+        .bind(commodity)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list commodity prices")?;
+
+        rows.iter().map(Self::row_to_commodity_price).collect()
+    }
+
+    fn row_to_commodity_price(
+        row: &sqlx::sqlite::SqliteRow,
+    ) -> Result<crate::domain::CommodityPrice> {
+        let id_str: String = row.get("id");
+        let effective_date_str: String = row.get("effective_date");
+        let created_at_str: String = row.get("created_at");
+
+        Ok(crate::domain::CommodityPrice {
+            id: Uuid::parse_str(&id_str).context("Invalid commodity price ID")?,
+            commodity: row.get("commodity"),
+            currency: row.get("currency"),
+            price: row.get("price"),
+            effective_date: DateTime::parse_from_rfc3339(&effective_date_str)
+                .context("Invalid effective_date")?
+                .with_timezone(&Utc),
+            created_at: DateTime::parse_from_rfc3339(&created_at_str)
+                .context("Invalid created_at")?
+                .with_timezone(&Utc),
+        })
+    }
+
+    // ========================
+    // Lot operations
+    // ========================
+
+    /// Save the transfers of a sale together with the lot disposals they make and
+    /// the lock overrides that let them in, atomically.
+    pub async fn save_sale(
+        &self,
+        transfers: &mut [Transfer],
+        disposals: &[crate::domain::LotDisposal],
+        overrides: &[LockOverride],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for transfer in transfers.iter_mut() {
+            Self::insert_transfer(&mut tx, transfer).await?;
+        }
+        for disposal in disposals {
+            sqlx::query(
+                r#"
+                INSERT INTO lot_disposals (id, sell_transfer_id, wallet_id, lot_id, acquired_at, disposed_at, quantity, cost_cents, proceeds_cents, method)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(disposal.id.to_string())
+            .bind(disposal.sell_transfer_id.to_string())
+            .bind(disposal.wallet_id.to_string())
+            .bind(disposal.lot_id.to_string())
+            .bind(disposal.acquired_at.to_rfc3339())
+            .bind(disposal.disposed_at.to_rfc3339())
+            .bind(disposal.quantity)
+            .bind(disposal.cost_cents)
+            .bind(disposal.proceeds_cents)
+            .bind(disposal.method.as_str())
+            .execute(&mut *tx)
+            .await
+            .context("Failed to save lot disposal")?;
+        }
+        Self::insert_lock_overrides(&mut tx, overrides).await?;
+        tx.commit().await.context("Failed to commit sale")?;
+        Ok(())
+    }
+
+    /// List every lot disposal of a wallet, including those of reversed sales.
+    pub async fn list_lot_disposals_for_wallet(
+        &self,
+        wallet_id: WalletId,
+    ) -> Result<Vec<crate::domain::LotDisposal>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, sell_transfer_id, wallet_id, lot_id, acquired_at, disposed_at, quantity, cost_cents, proceeds_cents, method
+            FROM lot_disposals
+            WHERE wallet_id = ?
+            ORDER BY disposed_at
+            "#,
+        )
+        .bind(wallet_id.to_string())
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list lot disposals")?;
+
+        rows.iter().map(Self::row_to_lot_disposal).collect()
+    }
+
+    /// List lot disposals of sales made in a date range (start inclusive, end exclusive),
+    /// leaving out sales that were reversed.
+    pub async fn list_lot_disposals_between(
+        &self,
+        from_date: DateTime<Utc>,
+        to_date: DateTime<Utc>,
+    ) -> Result<Vec<crate::domain::LotDisposal>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT d.id, d.sell_transfer_id, d.wallet_id, d.lot_id, d.acquired_at, d.disposed_at, d.quantity, d.cost_cents, d.proceeds_cents, d.method
+            FROM lot_disposals d
+            WHERE d.disposed_at >= ? AND d.disposed_at < ?
+              AND NOT EXISTS (SELECT 1 FROM transfers r WHERE r.reverses = d.sell_transfer_id)
+            ORDER BY d.disposed_at, d.acquired_at
+            "#,
+        )
+        .bind(from_date.to_rfc3339())
+        .bind(to_date.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list lot disposals")?;
+
+        rows.iter().map(Self::row_to_lot_disposal).collect()
+    }
+
+    /// List every lot disposal, reversed sales included.
+    pub async fn list_lot_disposals(&self) -> Result<Vec<crate::domain::LotDisposal>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, sell_transfer_id, wallet_id, lot_id, acquired_at, disposed_at, quantity, cost_cents, proceeds_cents, method
+            FROM lot_disposals
+            ORDER BY disposed_at, acquired_at
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list lot disposals")?;
+
+        rows.iter().map(Self::row_to_lot_disposal).collect()
+    }
+
+    /// Count disposals from a lot made by sales that still stand (not reversed).
+    pub async fn count_active_disposals_for_lot(&self, lot_id: TransferId) -> Result<i64> {
+        let row = sqlx::query(
+            r#"
+            SELECT COUNT(*) as count
+            FROM lot_disposals d
+            WHERE d.lot_id = ?
+              AND NOT EXISTS (SELECT 1 FROM transfers r WHERE r.reverses = d.sell_transfer_id)
+            "#,
+        )
+        .bind(lot_id.to_string())
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to count lot disposals")?;
+
+        Ok(row.get("count"))
+    }
+
+    fn row_to_lot_disposal(row: &sqlx::sqlite::SqliteRow) -> Result<crate::domain::LotDisposal> {
+        let id_str: String = row.get("id");
+        let sell_str: String = row.get("sell_transfer_id");
+        let wallet_str: String = row.get("wallet_id");
+        let lot_str: String = row.get("lot_id");
+        let acquired_at_str: String = row.get("acquired_at");
+        let disposed_at_str: String = row.get("disposed_at");
+        let method_str: String = row.get("method");
+
+        Ok(crate::domain::LotDisposal {
+            id: Uuid::parse_str(&id_str).context("Invalid lot disposal ID")?,
+            sell_transfer_id: Uuid::parse_str(&sell_str).context("Invalid sell transfer ID")?,
+            wallet_id: Uuid::parse_str(&wallet_str).context("Invalid wallet ID")?,
+            lot_id: Uuid::parse_str(&lot_str).context("Invalid lot ID")?,
+            acquired_at: DateTime::parse_from_rfc3339(&acquired_at_str)
+                .context("Invalid acquired_at")?
+                .with_timezone(&Utc),
+            disposed_at: DateTime::parse_from_rfc3339(&disposed_at_str)
+                .context("Invalid disposed_at")?
+                .with_timezone(&Utc),
+            quantity: row.get("quantity"),
+            cost_cents: row.get("cost_cents"),
+            proceeds_cents: row.get("proceeds_cents"),
+            method: method_str
+                .parse()
+                .map_err(|e| anyhow::anyhow!("Invalid lot method: {}. Error: {}", method_str, e))?,
+        })
+    }
+
+    // ========================
+    // Reconciliation operations
+    // ========================
+
+    /// Set the cleared state of transfers.
+    pub async fn update_transfers_cleared(
+        &self,
+        ids: &[TransferId],
+        state: crate::domain::ClearedState,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for id in ids {
+            sqlx::query("UPDATE transfers SET cleared = ? WHERE id = ?")
+                .bind(state.as_str())
+                .bind(id.to_string())
+                .execute(&mut *tx)
+                .await
+                .context("Failed to update cleared state")?;
+        }
+        tx.commit()
+            .await
+            .context("Failed to commit cleared states")?;
+        Ok(())
+    }
+
+    /// Save a new reconcile session.
+    pub async fn save_reconciliation(
+        &self,
+        reconciliation: &crate::domain::Reconciliation,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO reconciliations (id, wallet_id, statement_date, statement_balance, started_at, finished_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(reconciliation.id.to_string())
+        .bind(reconciliation.wallet_id.to_string())
+        .bind(reconciliation.statement_date.to_rfc3339())
+        .bind(reconciliation.statement_balance)
+        .bind(reconciliation.started_at.to_rfc3339())
+        .bind(reconciliation.finished_at.map(|dt| dt.to_rfc3339()))
+        .execute(&self.pool)
+        .await
+        .context("Failed to save reconciliation")?;
+
+        Ok(())
+    }
+
+    /// Get the open reconcile session of a wallet, if any.
+    pub async fn get_open_reconciliation(
+        &self,
+        wallet_id: WalletId,
+    ) -> Result<Option<crate::domain::Reconciliation>> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, wallet_id, statement_date, statement_balance, started_at, finished_at
+            FROM reconciliations
+            WHERE wallet_id = ? AND finished_at IS NULL
+            "#,
+        )
+        .bind(wallet_id.to_string())
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch reconciliation")?;
+
+        row.as_ref().map(Self::row_to_reconciliation).transpose()
+    }
+
+    /// List reconcile sessions, newest statement first; all wallets when `wallet_id` is None.
+    pub async fn list_reconciliations(
+        &self,
+        wallet_id: Option<WalletId>,
+    ) -> Result<Vec<crate::domain::Reconciliation>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, wallet_id, statement_date, statement_balance, started_at, finished_at
+            FROM reconciliations
+            WHERE ? IS NULL OR wallet_id = ?
+            ORDER BY statement_date DESC, started_at DESC
+            "#,
+        )
+        .bind(wallet_id.map(|id| id.to_string()))
+        .bind(wallet_id.map(|id| id.to_string()))
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list reconciliations")?;
+
+        rows.iter().map(Self::row_to_reconciliation).collect()
+    }
+
+    /// Delete an open reconcile session. Finished sessions are kept.
+    pub async fn delete_open_reconciliation(
+        &self,
+        id: crate::domain::ReconciliationId,
+    ) -> Result<bool> {
+        let result =
+            sqlx::query("DELETE FROM reconciliations WHERE id = ? AND finished_at IS NULL")
+                .bind(id.to_string())
+                .execute(&self.pool)
+                .await
+                .context("Failed to delete reconciliation")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Finish a reconcile session: mark its transfers reconciled and keep a copy
+    /// of what they looked like, atomically.
+    pub async fn finish_reconciliation(
+        &self,
+        reconciliation: &crate::domain::Reconciliation,
+        transfers: &[Transfer],
+    ) -> Result<()> {
+        let finished_at = reconciliation
+            .finished_at
+            .context("Reconciliation has no finish time")?;
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("UPDATE reconciliations SET finished_at = ? WHERE id = ?")
+            .bind(finished_at.to_rfc3339())
+            .bind(reconciliation.id.to_string())
+            .execute(&mut *tx)
+            .await
+            .context("Failed to finish reconciliation")?;
+
+        for transfer in transfers {
+            sqlx::query("UPDATE transfers SET cleared = 'reconciled' WHERE id = ?")
+                .bind(transfer.id.to_string())
+                .execute(&mut *tx)
+                .await
+                .context("Failed to reconcile transfer")?;
+            sqlx::query(
+                r#"
+                INSERT INTO reconciled_transfers (reconciliation_id, transfer_id, from_wallet_id, to_wallet_id, amount_cents, timestamp)
+                VALUES (?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(reconciliation.id.to_string())
+            .bind(transfer.id.to_string())
+            .bind(transfer.from_wallet.to_string())
+            .bind(transfer.to_wallet.to_string())
+            .bind(transfer.amount_cents)
+            .bind(transfer.timestamp.to_rfc3339())
+            .execute(&mut *tx)
+            .await
+            .context("Failed to record reconciled transfer")?;
+        }
+
+        tx.commit()
+            .await
+            .context("Failed to commit reconciliation")?;
+        Ok(())
+    }
+
+    /// Count the transfers a finished session reconciled.
+    pub async fn count_reconciled_transfers(
+        &self,
+        id: crate::domain::ReconciliationId,
+    ) -> Result<i64> {
+        let row = sqlx::query(
+            "SELECT COUNT(*) as count FROM reconciled_transfers WHERE reconciliation_id = ?",
+        )
+        .bind(id.to_string())
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to count reconciled transfers")?;
+
+        Ok(row.get("count"))
+    }
+
+    fn row_to_reconciliation(
+        row: &sqlx::sqlite::SqliteRow,
+    ) -> Result<crate::domain::Reconciliation> {
+        let id_str: String = row.get("id");
+        let wallet_str: String = row.get("wallet_id");
+        let statement_date_str: String = row.get("statement_date");
+        let started_at_str: String = row.get("started_at");
+        let finished_at_str: Option<String> = row.get("finished_at");
+
+        Ok(crate::domain::Reconciliation {
+            id: Uuid::parse_str(&id_str).context("Invalid reconciliation ID")?,
+            wallet_id: Uuid::parse_str(&wallet_str).context("Invalid wallet ID")?,
+            statement_date: DateTime::parse_from_rfc3339(&statement_date_str)
+                .context("Invalid statement_date")?
+                .with_timezone(&Utc),
+            statement_balance: row.get("statement_balance"),
+            started_at: DateTime::parse_from_rfc3339(&started_at_str)
+                .context("Invalid started_at")?
+                .with_timezone(&Utc),
+            finished_at: finished_at_str
+                .map(|s| DateTime::parse_from_rfc3339(&s).map(|dt| dt.with_timezone(&Utc)))
+                .transpose()
+                .context("Invalid finished_at")?,
+        })
+    }
+
+    // ========================
+    // Amendment operations
+    // ========================
+
+    /// Store corrected transfers together with their amendment records and the lock
+    /// overrides that let them in, atomically.
+    pub async fn amend_transfers(
+        &self,
+        amended: &[(Transfer, crate::domain::Amendment)],
+        overrides: &[LockOverride],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for (transfer, amendment) in amended {
+            Self::insert_amendment(&mut tx, transfer, amendment).await?;
+        }
+        Self::insert_lock_overrides(&mut tx, overrides).await?;
+        tx.commit().await.context("Failed to commit amendments")?;
+        Ok(())
+    }
+
+    async fn insert_amendment(
+        conn: &mut SqliteConnection,
+        transfer: &Transfer,
+        amendment: &crate::domain::Amendment,
+    ) -> Result<()> {
+        let tags_json = serde_json::to_string(&transfer.tags)?;
+
+        sqlx::query(
+            r#"
+            UPDATE transfers
+            SET description = ?, category = ?, tags = ?, timestamp = ?, amount_cents = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(&transfer.description)
+        .bind(&transfer.category)
+        .bind(&tags_json)
+        .bind(transfer.timestamp.to_rfc3339())
+        .bind(transfer.amount_cents)
+        .bind(transfer.id.to_string())
+        .execute(&mut *conn)
+        .await
+        .context("Failed to amend transfer")?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO transfer_amendments (id, transfer_id, amended_at, author, reason)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(amendment.id.to_string())
+        .bind(amendment.transfer_id.to_string())
+        .bind(amendment.amended_at.to_rfc3339())
+        .bind(&amendment.author)
+        .bind(&amendment.reason)
+        .execute(&mut *conn)
+        .await
+        .context("Failed to save amendment")?;
+
+        for (position, change) in amendment.changes.iter().enumerate() {
+            sqlx::query(
+                r#"
+                INSERT INTO amendment_changes (amendment_id, position, field, old_value, new_value)
+                VALUES (?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(amendment.id.to_string())
+            .bind(position as i64)
+            .bind(change.field.as_str())
+            .bind(&change.old_value)
+            .bind(&change.new_value)
+            .execute(&mut *conn)
+            .await
+            .context("Failed to save amendment change")?;
+        }
+
+        Ok(())
+    }
+
+    /// List the amendments of one transfer, or of all transfers when `transfer_id` is None,
+    /// oldest first.
+    pub async fn list_amendments(
+        &self,
+        transfer_id: Option<TransferId>,
+    ) -> Result<Vec<crate::domain::Amendment>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT a.id, a.transfer_id, a.amended_at, a.author, a.reason,
+                   c.field, c.old_value, c.new_value
+            FROM transfer_amendments a
+            JOIN amendment_changes c ON c.amendment_id = a.id
+            WHERE ? IS NULL OR a.transfer_id = ?
+            ORDER BY a.amended_at, a.rowid, c.position
+            "#,
+        )
+        .bind(transfer_id.map(|id| id.to_string()))
+        .bind(transfer_id.map(|id| id.to_string()))
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list amendments")?;
+
+        let mut amendments: Vec<crate::domain::Amendment> = Vec::new();
+        for row in &rows {
+            let id_str: String = row.get("id");
+            let id = Uuid::parse_str(&id_str).context("Invalid amendment ID")?;
+            let field_str: String = row.get("field");
+            let change = crate::domain::FieldChange {
+                field: field_str.parse().map_err(|e| {
+                    anyhow::anyhow!("Invalid amended field: {}. Error: {}", field_str, e)
+                })?,
+                old_value: row.get("old_value"),
+                new_value: row.get("new_value"),
+            };
+
+            match amendments.last_mut() {
+                Some(last) if last.id == id => last.changes.push(change),
+                _ => {
+                    let transfer_str: String = row.get("transfer_id");
+                    let amended_at_str: String = row.get("amended_at");
+                    amendments.push(crate::domain::Amendment {
+                        id,
+                        transfer_id: Uuid::parse_str(&transfer_str)
+                            .context("Invalid transfer ID")?,
+                        amended_at: DateTime::parse_from_rfc3339(&amended_at_str)
+                            .context("Invalid amended_at")?
+                            .with_timezone(&Utc),
+                        author: row.get("author"),
+                        reason: row.get("reason"),
+                        changes: vec![change],
+                    });
+                }
+            }
+        }
+
+        Ok(amendments)
+    }
+
+    // ========================
+    // Attachment operations
+    // ========================
+
+    /// Save an attachment, storing its content unless a blob with the same hash exists.
+    pub async fn save_attachment(&self, attachment: &Attachment, data: &[u8]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO attachment_blobs (hash, size, data, created_at)
+            VALUES (?, ?, ?, ?)
+            "#,
+        )
+        .bind(&attachment.hash)
+        .bind(data.len() as i64)
+        .bind(data)
+        .bind(attachment.created_at.to_rfc3339())
+        .execute(&mut *tx)
+        .await
+        .context("Failed to save attachment content")?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO attachments (id, transfer_id, hash, filename, media_type, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(attachment.id.to_string())
+        .bind(attachment.transfer_id.to_string())
+        .bind(&attachment.hash)
+        .bind(&attachment.filename)
+        .bind(&attachment.media_type)
+        .bind(attachment.created_at.to_rfc3339())
+        .execute(&mut *tx)
+        .await
+        .context("Failed to save attachment")?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Get an attachment by ID.
+    pub async fn get_attachment(&self, id: AttachmentId) -> Result<Option<Attachment>> {
+        let row = sqlx::query(
+            r#"
+            SELECT a.id, a.transfer_id, a.hash, a.filename, a.media_type, a.created_at, b.size
+            FROM attachments a
+            JOIN attachment_blobs b ON b.hash = a.hash
+            WHERE a.id = ?
+            "#,
+        )
+        .bind(id.to_string())
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch attachment")?;
+
+        row.map(|row| Self::row_to_attachment(&row)).transpose()
+    }
+
+    /// List attachments, of one transfer or of all, oldest first.
+    pub async fn list_attachments(
+        &self,
+        transfer_id: Option<TransferId>,
+    ) -> Result<Vec<Attachment>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT a.id, a.transfer_id, a.hash, a.filename, a.media_type, a.created_at, b.size
+            FROM attachments a
+            JOIN attachment_blobs b ON b.hash = a.hash
+            WHERE ? IS NULL OR a.transfer_id = ?
+            ORDER BY a.created_at, a.filename
+            "#,
+        )
+        .bind(transfer_id.map(|id| id.to_string()))
+        .bind(transfer_id.map(|id| id.to_string()))
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list attachments")?;
+
+        rows.iter().map(Self::row_to_attachment).collect()
+    }
+
+    /// Get the content stored under a hash.
+    pub async fn get_attachment_data(&self, hash: &str) -> Result<Option<Vec<u8>>> {
+        let row = sqlx::query("SELECT data FROM attachment_blobs WHERE hash = ?")
+            .bind(hash)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch attachment content")?;
+
+        Ok(row.map(|row| row.get("data")))
+    }
+
+    /// List all stored contents as (hash, data) pairs.
+    pub async fn list_attachment_data(&self) -> Result<Vec<(String, Vec<u8>)>> {
+        let rows = sqlx::query("SELECT hash, data FROM attachment_blobs ORDER BY hash")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to list attachment contents")?;
+
+        Ok(rows
+            .iter()
+            .map(|row| (row.get("hash"), row.get("data")))
+            .collect())
+    }
+
+    /// Delete an attachment, and its content when no other attachment shares it.
+    pub async fn delete_attachment(&self, attachment: &Attachment) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM attachments WHERE id = ?")
+            .bind(attachment.id.to_string())
+            .execute(&mut *tx)
+            .await
+            .context("Failed to delete attachment")?;
+        sqlx::query(
+            r#"
+            DELETE FROM attachment_blobs
+            WHERE hash = ?
+              AND NOT EXISTS (SELECT 1 FROM attachments WHERE hash = ?)
+            "#,
+        )
+        .bind(&attachment.hash)
+        .bind(&attachment.hash)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to delete attachment content")?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    fn row_to_attachment(row: &sqlx::sqlite::SqliteRow) -> Result<Attachment> {
+        let id_str: String = row.get("id");
+        let transfer_id_str: String = row.get("transfer_id");
+        let created_at_str: String = row.get("created_at");
+
+        Ok(Attachment {
+            id: Uuid::parse_str(&id_str).context("Invalid attachment ID")?,
+            transfer_id: Uuid::parse_str(&transfer_id_str).context("Invalid transfer ID")?,
+            hash: row.get("hash"),
+            filename: row.get("filename"),
+            media_type: row.get("media_type"),
+            size: row.get("size"),
+            created_at: DateTime::parse_from_rfc3339(&created_at_str)
+                .context("Invalid created_at")?
+                .with_timezone(&Utc),
+        })
+    }
+
+    // ========================
+    // Payee operations
+    // ========================
+
+    /// Save a new payee with its aliases.
+    pub async fn save_payee(&self, payee: &Payee) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO payees (id, name, default_category, default_wallet_id, created_at)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(payee.id.to_string())
+        .bind(&payee.name)
+        .bind(&payee.default_category)
+        .bind(payee.default_wallet.map(|id| id.to_string()))
+        .bind(payee.created_at.to_rfc3339())
+        .execute(&mut *tx)
+        .await
+        .context("Failed to save payee")?;
+
+        for alias in &payee.aliases {
+            sqlx::query("INSERT INTO payee_aliases (payee_id, alias) VALUES (?, ?)")
+                .bind(payee.id.to_string())
+                .bind(alias)
+                .execute(&mut *tx)
+                .await
+                .context("Failed to save payee alias")?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Get a payee by ID.
+    pub async fn get_payee(&self, id: PayeeId) -> Result<Option<Payee>> {
+        let row = sqlx::query(
+            "SELECT id, name, default_category, default_wallet_id, created_at FROM payees WHERE id = ?",
+        )
+        .bind(id.to_string())
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch payee")?;
+
+        match row {
+            Some(row) => Ok(Some(self.row_to_payee_with_aliases(&row).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get a payee by its name or one of its aliases (case-insensitive).
+    pub async fn get_payee_by_name(&self, name: &str) -> Result<Option<Payee>> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, name, default_category, default_wallet_id, created_at
+            FROM payees
+            WHERE name = ?
+               OR id IN (SELECT payee_id FROM payee_aliases WHERE alias = ?)
+            "#,
+        )
+        .bind(name)
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch payee")?;
+
+        match row {
+            Some(row) => Ok(Some(self.row_to_payee_with_aliases(&row).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// List all payees with their aliases, by name.
+    pub async fn list_payees(&self) -> Result<Vec<Payee>> {
+        let rows = sqlx::query(
+            "SELECT id, name, default_category, default_wallet_id, created_at FROM payees ORDER BY name COLLATE NOCASE",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list payees")?;
+
+        let alias_rows =
+            sqlx::query("SELECT payee_id, alias FROM payee_aliases ORDER BY alias COLLATE NOCASE")
+                .fetch_all(&self.pool)
+                .await
+                .context("Failed to list payee aliases")?;
+        let mut aliases: std::collections::HashMap<String, Vec<String>> =
+            std::collections::HashMap::new();
+        for row in alias_rows {
+            aliases
+                .entry(row.get("payee_id"))
+                .or_default()
+                .push(row.get("alias"));
+        }
+
+        rows.iter()
+            .map(|row| {
+                let id: String = row.get("id");
+                let payee_aliases = aliases.remove(&id).unwrap_or_default();
+                Self::row_to_payee(row, payee_aliases)
+            })
+            .collect()
+    }
+
+    /// Add an alias to a payee.
+    pub async fn add_payee_alias(&self, id: PayeeId, alias: &str) -> Result<()> {
+        sqlx::query("INSERT INTO payee_aliases (payee_id, alias) VALUES (?, ?)")
+            .bind(id.to_string())
+            .bind(alias)
+            .execute(&self.pool)
+            .await
+            .context("Failed to save payee alias")?;
+        Ok(())
+    }
+
+    /// Remove an alias from a payee. Returns false if the payee had no such alias.
+    pub async fn remove_payee_alias(&self, id: PayeeId, alias: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM payee_aliases WHERE payee_id = ? AND alias = ?")
+            .bind(id.to_string())
+            .bind(alias)
+            .execute(&self.pool)
+            .await
+            .context("Failed to remove payee alias")?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Update the category and wallet a payee's transfers default to.
+    pub async fn update_payee_defaults(
+        &self,
+        id: PayeeId,
+        default_category: Option<&str>,
+        default_wallet: Option<WalletId>,
+    ) -> Result<()> {
+        sqlx::query("UPDATE payees SET default_category = ?, default_wallet_id = ? WHERE id = ?")
+            .bind(default_category)
+            .bind(default_wallet.map(|id| id.to_string()))
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .context("Failed to update payee defaults")?;
+        Ok(())
+    }
+
+    /// Aggregate transfers by payee within a date range.
+    /// Returns (payee ID, count, total, first timestamp, last timestamp) rows.
+    pub async fn aggregate_by_payee(
+        &self,
+        from_date: DateTime<Utc>,
+        to_date: DateTime<Utc>,
+    ) -> Result<Vec<(PayeeId, i64, Cents, DateTime<Utc>, DateTime<Utc>)>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                payee_id,
+                COUNT(*) as count,
+                SUM(amount_cents) as total,
+                MIN(timestamp) as first_at,
+                MAX(timestamp) as last_at
+            FROM transfers
+            WHERE payee_id IS NOT NULL
+              AND timestamp >= ?
+              AND timestamp < ?
+            GROUP BY payee_id
+            "#,
+        )
+        .bind(from_date.to_rfc3339())
+        .bind(to_date.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to aggregate transfers by payee")?;
+
+        let mut results = Vec::with_capacity(rows.len());
+        for row in rows {
+            let id_str: String = row.get("payee_id");
+            let first_str: String = row.get("first_at");
+            let last_str: String = row.get("last_at");
+            results.push((
+                Uuid::parse_str(&id_str).context("Invalid payee ID")?,
+                row.get("count"),
+                row.get("total"),
+                DateTime::parse_from_rfc3339(&first_str)
+                    .context("Invalid timestamp")?
+                    .with_timezone(&Utc),
+                DateTime::parse_from_rfc3339(&last_str)
+                    .context("Invalid timestamp")?
+                    .with_timezone(&Utc),
+            ));
+        }
+        Ok(results)
+    }
+
+    async fn row_to_payee_with_aliases(&self, row: &sqlx::sqlite::SqliteRow) -> Result<Payee> {
+        let id: String = row.get("id");
+        let aliases: Vec<String> = sqlx::query(
+            "SELECT alias FROM payee_aliases WHERE payee_id = ? ORDER BY alias COLLATE NOCASE",
+        )
+        .bind(&id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to fetch payee aliases")?
+        .iter()
+        .map(|row| row.get("alias"))
+        .collect();
+        Self::row_to_payee(row, aliases)
+    }
+
+    fn row_to_payee(row: &sqlx::sqlite::SqliteRow, aliases: Vec<String>) -> Result<Payee> {
+        let id_str: String = row.get("id");
+        let wallet_str: Option<String> = row.get("default_wallet_id");
+        let created_at_str: String = row.get("created_at");
+
+        Ok(Payee {
+            id: Uuid::parse_str(&id_str).context("Invalid payee ID")?,
+            name: row.get("name"),
+            aliases,
+            default_category: row.get("default_category"),
+            default_wallet: wallet_str
+                .map(|s| Uuid::parse_str(&s))
+                .transpose()
+                .context("Invalid default wallet ID")?,
+            created_at: DateTime::parse_from_rfc3339(&created_at_str)
+                .context("Invalid created_at")?
+                .with_timezone(&Utc),
+        })
+    }
+
+    // ========================
+    // Period lock operations
+    // ========================
+
+    /// Append a lock entry.
+    pub async fn save_period_lock(&self, lock: &PeriodLock) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO period_locks (id, wallet_id, lock_date, locked_at, author, reason)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(lock.id.to_string())
+        .bind(lock.wallet_id.map(|id| id.to_string()))
+        .bind(
+            lock.lock_date
+                .map(|date| date.format("%Y-%m-%d").to_string()),
+        )
+        .bind(lock.locked_at.to_rfc3339())
+        .bind(&lock.author)
+        .bind(&lock.reason)
+        .execute(&self.pool)
+        .await
+        .context("Failed to save period lock")?;
+        Ok(())
+    }
+
+    /// List all lock entries in the order they were set.
+    pub async fn list_period_locks(&self) -> Result<Vec<PeriodLock>> {
+        let rows = sqlx::query(
+            "SELECT id, wallet_id, lock_date, locked_at, author, reason FROM period_locks ORDER BY locked_at, rowid",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list period locks")?;
+
+        rows.iter().map(Self::row_to_period_lock).collect()
+    }
+
+    /// Log changes let into a locked period, within the transaction saving them.
+    async fn insert_lock_overrides(
+        conn: &mut SqliteConnection,
+        overrides: &[LockOverride],
+    ) -> Result<()> {
+        for entry in overrides {
+            sqlx::query(
+                r#"
+                INSERT INTO lock_overrides (id, transfer_id, action, reason, author, created_at)
+                VALUES (?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(entry.id.to_string())
+            .bind(entry.transfer_id.to_string())
+            .bind(entry.action.as_str())
+            .bind(&entry.reason)
+            .bind(&entry.author)
+            .bind(entry.created_at.to_rfc3339())
+            .execute(&mut *conn)
+            .await
+            .context("Failed to save lock override")?;
+        }
+        Ok(())
+    }
+
+    /// List the logged lock overrides, oldest first.
+    pub async fn list_lock_overrides(&self) -> Result<Vec<LockOverride>> {
+        let rows = sqlx::query(
+            "SELECT id, transfer_id, action, reason, author, created_at FROM lock_overrides ORDER BY created_at, rowid",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list lock overrides")?;
+
+        rows.iter().map(Self::row_to_lock_override).collect()
+    }
+
+    fn row_to_period_lock(row: &sqlx::sqlite::SqliteRow) -> Result<PeriodLock> {
+        let id_str: String = row.get("id");
+        let wallet_str: Option<String> = row.get("wallet_id");
+        let lock_date_str: Option<String> = row.get("lock_date");
+        let locked_at_str: String = row.get("locked_at");
+
+        Ok(PeriodLock {
+            id: Uuid::parse_str(&id_str).context("Invalid period lock ID")?,
+            wallet_id: wallet_str
+                .map(|s| Uuid::parse_str(&s))
+                .transpose()
+                .context("Invalid wallet ID")?,
+            lock_date: lock_date_str
+                .map(|s| chrono::NaiveDate::parse_from_str(&s, "%Y-%m-%d"))
+                .transpose()
+                .context("Invalid lock date")?,
+            locked_at: DateTime::parse_from_rfc3339(&locked_at_str)
+                .context("Invalid locked_at")?
+                .with_timezone(&Utc),
+            author: row.get("author"),
+            reason: row.get("reason"),
+        })
+    }
+
+    fn row_to_lock_override(row: &sqlx::sqlite::SqliteRow) -> Result<LockOverride> {
+        let id_str: String = row.get("id");
+        let transfer_id_str: String = row.get("transfer_id");
+        let action_str: String = row.get("action");
+        let created_at_str: String = row.get("created_at");
+
+        Ok(LockOverride {
+            id: Uuid::parse_str(&id_str).context("Invalid lock override ID")?,
+            transfer_id: Uuid::parse_str(&transfer_id_str).context("Invalid transfer ID")?,
+            action: action_str
+                .parse()
+                .map_err(|e| anyhow::anyhow!("Invalid locked action: {}", e))?,
+            reason: row.get("reason"),
+            author: row.get("author"),
+            created_at: DateTime::parse_from_rfc3339(&created_at_str)
+                .context("Invalid created_at")?
+                .with_timezone(&Utc),
+        })
+    }
+
+    // ========================
+    // Year close operations
+    // ========================
+
+    /// Compute balances of all wallets from the transfers dated before `end`.
+    pub async fn compute_all_balances_before(
+        &self,
+        end: DateTime<Utc>,
+    ) -> Result<HashMap<WalletId, Cents>> {
+        let end = end.to_rfc3339();
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                wallet_id,
+                SUM(amount) as balance
+            FROM (
+                SELECT to_wallet_id as wallet_id, amount_cents as amount FROM transfers WHERE timestamp < ?
+                UNION ALL
+                SELECT from_wallet_id as wallet_id, -amount_cents as amount FROM transfers WHERE timestamp < ?
+            )
+            GROUP BY wallet_id
+            "#,
+        )
+        .bind(&end)
+        .bind(&end)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to compute balances")?;
+
+        Self::rows_to_wallet_amounts(&rows, "balance")
+    }
+
+    /// Compute the units of commodity held by every wallet from the transfers dated
+    /// before `end`. Wallets that never traded are not in the map.
+    pub async fn compute_all_quantities_before(
+        &self,
+        end: DateTime<Utc>,
+    ) -> Result<HashMap<WalletId, i64>> {
+        let end = end.to_rfc3339();
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                wallet_id,
+                SUM(quantity) as quantity
+            FROM (
+                SELECT to_wallet_id as wallet_id, quantity FROM transfers
+                WHERE quantity IS NOT NULL AND timestamp < ?
+                UNION ALL
+                SELECT from_wallet_id as wallet_id, -quantity FROM transfers
+                WHERE quantity IS NOT NULL AND timestamp < ?
+            )
+            GROUP BY wallet_id
+            "#,
+        )
+        .bind(&end)
+        .bind(&end)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to compute quantities")?;
+
+        Self::rows_to_wallet_amounts(&rows, "quantity")
+    }
+
+    fn rows_to_wallet_amounts(
+        rows: &[sqlx::sqlite::SqliteRow],
+        column: &str,
+    ) -> Result<HashMap<WalletId, i64>> {
+        let mut amounts = HashMap::new();
+        for row in rows {
+            let wallet_id_str: String = row.get("wallet_id");
+            let wallet_id = Uuid::parse_str(&wallet_id_str).context("Invalid wallet ID")?;
+            amounts.insert(wallet_id, row.get(column));
+        }
+        Ok(amounts)
+    }
+
+    /// Record a closed year together with its closing entries, atomically.
+    pub async fn save_year_close(&self, close: &YearClose, entries: &mut [Transfer]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for entry in entries.iter_mut() {
+            Self::insert_transfer(&mut tx, entry).await?;
+        }
+        sqlx::query(
+            r#"
+            INSERT INTO year_closes (year, closed_at, closing_split_id, opening_split_id, archive_path)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(close.year)
+        .bind(close.closed_at.to_rfc3339())
+        .bind(close.closing_split_id.to_string())
+        .bind(close.opening_split_id.map(|id| id.to_string()))
+        .bind(&close.archive_path)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to save year close")?;
+        tx.commit().await.context("Failed to commit year close")?;
+        Ok(())
+    }
+
+    /// Record the archive of a closed year and the opening-balance transfers that
+    /// replace its history, with their lock overrides, atomically. Sales from lots that stayed in the archive
+    /// are pointed at the opening transfer of their holding.
+    pub async fn save_year_archive(
+        &self,
+        close: &YearClose,
+        openings: &mut [Transfer],
+        overrides: &[LockOverride],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for opening in openings.iter_mut() {
+            Self::insert_transfer(&mut tx, opening).await?;
+            if opening.quantity.is_some() {
+                sqlx::query(
+                    r#"
+                    UPDATE lot_disposals SET lot_id = ?
+                    WHERE wallet_id = ? AND lot_id NOT IN (SELECT id FROM transfers)
+                    "#,
+                )
+                .bind(opening.id.to_string())
+                .bind(opening.to_wallet.to_string())
+                .execute(&mut *tx)
+                .await
+                .context("Failed to move disposals to the opening lot")?;
+            }
+        }
+        sqlx::query("UPDATE year_closes SET opening_split_id = ?, archive_path = ? WHERE year = ?")
+            .bind(close.opening_split_id.map(|id| id.to_string()))
+            .bind(&close.archive_path)
+            .bind(close.year)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to update year close")?;
+        Self::insert_lock_overrides(&mut tx, overrides).await?;
+        tx.commit().await.context("Failed to commit year archive")?;
+        Ok(())
+    }
+
+    /// List closed years, oldest first.
+    pub async fn list_year_closes(&self) -> Result<Vec<YearClose>> {
+        let rows = sqlx::query(
+            "SELECT year, closed_at, closing_split_id, opening_split_id, archive_path FROM year_closes ORDER BY year",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list year closes")?;
+
+        rows.iter().map(Self::row_to_year_close).collect()
+    }
+
+    /// Write a consistent copy of the whole database to a new file.
+    pub async fn backup_to(&self, path: &str) -> Result<()> {
+        sqlx::query("VACUUM INTO ?")
+            .bind(path)
+            .execute(&self.pool)
+            .await
+            .with_context(|| format!("Failed to copy the database to {}", path))?;
+        Ok(())
+    }
+
+    /// Close every connection to the database.
+    pub async fn close(&self) {
+        self.pool.close().await;
+    }
+
+    /// Open a private in-memory database holding a copy of the database at
+    /// `source_path`.
+    pub async fn open_in_memory_copy(source_path: &str) -> Result<Self> {
+        // An in-memory database lives as long as its one connection does
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .min_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await
+            .context("Failed to open in-memory database")?;
+        let repo = Self::new(pool);
+        repo.migrate().await?;
+        repo.copy_from(source_path, TransferRange::All, true)
+            .await?;
+        Ok(repo)
+    }
+
+    /// Fill a fresh database with what the database at `source_path` holds from
+    /// `start` on: transfers dated `start` or later with the rows that belong to
+    /// them, and everything that is not tied to a transfer. Sequence numbers are
+    /// renumbered from 1.
+    pub async fn roll_forward_from(&self, source_path: &str, start: DateTime<Utc>) -> Result<()> {
+        self.copy_from(source_path, TransferRange::From(start), true)
+            .await
+    }
+
+    /// Bring the history of an archived year back in from its archive file: the
+    /// opening-balance transfers that stood in for it are dropped, and the
+    /// archive's transfers dated before `end` come before the ones already here.
+    pub async fn merge_archive(
+        &self,
+        archive_path: &str,
+        end: DateTime<Utc>,
+        opening_split_id: Option<SplitId>,
+    ) -> Result<()> {
+        if let Some(split_id) = opening_split_id {
+            let mut conn = self.pool.acquire().await?;
+            // Sales from the opening lots keep pointing at them until the lots
+            // they came from are back
+            sqlx::query("PRAGMA foreign_keys = OFF")
+                .execute(&mut *conn)
+                .await?;
+            let opening = "SELECT id FROM transfers WHERE split_id = ?";
+            let mut dropped = Ok(());
+            for statement in [
+                format!(
+                    "DELETE FROM transfer_tags WHERE transfer_id IN ({})",
+                    opening
+                ),
+                format!(
+                    "DELETE FROM lock_overrides WHERE transfer_id IN ({})",
+                    opening
+                ),
+                "DELETE FROM transfers WHERE split_id = ?".to_string(),
+            ] {
+                dropped = sqlx::query(&statement)
+                    .bind(split_id.to_string())
+                    .execute(&mut *conn)
+                    .await
+                    .map(|_| ())
+                    .context("Failed to drop opening balances");
+                if dropped.is_err() {
+                    break;
+                }
+            }
+            Self::restore_foreign_keys(&mut conn, dropped).await?;
+        }
+        self.copy_from(archive_path, TransferRange::Before(end), false)
+            .await
+    }
+
+    /// Copy the tables of the database at `source_path` into this one. Rows
+    /// already here are overwritten when `replace` is set and kept otherwise.
+    /// Sequence numbers end up contiguous, with copied transfers first.
+    async fn copy_from(
+        &self,
+        source_path: &str,
+        range: TransferRange,
+        replace: bool,
+    ) -> Result<()> {
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query("PRAGMA foreign_keys = OFF")
+            .execute(&mut *conn)
+            .await?;
+        // Read-only URI: a plain file name would share the open flags of this
+        // database, and be opened in memory when this one is
+        let source_uri = format!(
+            "file:{}?mode=ro",
+            source_path
+                .replace('%', "%25")
+                .replace('?', "%3f")
+                .replace('#', "%23")
+        );
+        let result = match sqlx::query("ATTACH DATABASE ? AS source")
+            .bind(&source_uri)
+            .execute(&mut *conn)
+            .await
+            .with_context(|| format!("Failed to open {}", source_path))
+        {
+            Ok(_) => {
+                let copied = Self::copy_tables(&mut conn, &range, replace).await;
+                let detached = sqlx::query("DETACH DATABASE source")
+                    .execute(&mut *conn)
+                    .await
+                    .context("Failed to close the source database");
+                if detached.is_err() {
+                    // Still attached, the connection cannot go back to the pool
+                    conn.close_on_drop();
+                }
+                copied.and(detached.map(|_| ()))
+            }
+            Err(e) => Err(e),
+        };
+        Self::restore_foreign_keys(&mut conn, result).await
+    }
+
+    /// Turn foreign keys back on for a pooled connection once the work done
+    /// with them off has `result`. A connection that cannot be restored is
+    /// closed instead of going back to the pool.
+    async fn restore_foreign_keys<T>(
+        conn: &mut sqlx::pool::PoolConnection<sqlx::Sqlite>,
+        result: Result<T>,
+    ) -> Result<T> {
+        let restored = sqlx::query("PRAGMA foreign_keys = ON")
+            .execute(&mut **conn)
+            .await
+            .context("Failed to turn foreign keys back on");
+        if restored.is_err() {
+            conn.close_on_drop();
+        }
+        let value = result?;
+        restored?;
+        Ok(value)
+    }
+
+    async fn copy_tables(
+        conn: &mut SqliteConnection,
+        range: &TransferRange,
+        replace: bool,
+    ) -> Result<()> {
+        let mut tx = sqlx::Connection::begin(&mut *conn).await?;
+
+        // Make room below the transfers already here for the copied ones
+        sqlx::query(
+            r#"
+            UPDATE transfers SET sequence = -sequence - (SELECT COALESCE(MAX(sequence), 0) FROM source.transfers)
+            "#,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to renumber transfers")?;
+        sqlx::query("UPDATE transfers SET sequence = -sequence")
+            .execute(&mut *tx)
+            .await
+            .context("Failed to renumber transfers")?;
+
+        let tables: Vec<String> = sqlx::query(
+            r#"
+            SELECT name FROM source.sqlite_master
+            WHERE type = 'table' AND name NOT LIKE 'sqlite_%' AND name != 'sequence_counter'
+            "#,
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .context("Failed to list source tables")?
+        .iter()
+        .map(|row| row.get("name"))
+        .collect();
+
+        for table in tables {
+            let target_columns = Self::table_columns(&mut tx, "main", &table).await?;
+            let columns: Vec<String> = Self::table_columns(&mut tx, "source", &table)
+                .await?
+                .into_iter()
+                .filter(|column| target_columns.contains(column))
+                .map(|column| format!("\"{}\"", column))
+                .collect();
+            if columns.is_empty() {
+                continue;
+            }
+            let columns = columns.join(", ");
+            sqlx::query(&format!(
+                "INSERT OR {} INTO main.\"{table}\" ({columns}) SELECT {columns} FROM source.\"{table}\"{}",
+                if replace { "REPLACE" } else { "IGNORE" },
+                range.filter(&table)?,
+            ))
+            .execute(&mut *tx)
+            .await
+            .with_context(|| format!("Failed to copy {}", table))?;
+        }
+
+        // Close the gaps left by transfers that were not copied
+        sqlx::query("UPDATE transfers SET sequence = -sequence")
+            .execute(&mut *tx)
+            .await
+            .context("Failed to renumber transfers")?;
+        sqlx::query(
+            r#"
+            UPDATE transfers SET sequence = ordered.position
+            FROM (SELECT id, ROW_NUMBER() OVER (ORDER BY sequence DESC) AS position FROM transfers) AS ordered
+            WHERE ordered.id = transfers.id
+            "#,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to renumber transfers")?;
+        sqlx::query(
+            r#"
+            UPDATE sequence_counter SET value = (SELECT COALESCE(MAX(sequence), 0) FROM transfers)
+            WHERE name = 'transfer_sequence'
+            "#,
+        )
+        .execute(&mut *tx)
+        .await
+        .context("Failed to update sequence counter")?;
+
+        tx.commit().await.context("Failed to commit copy")?;
+        Ok(())
+    }
+
+    async fn table_columns(
+        conn: &mut SqliteConnection,
+        schema: &str,
+        table: &str,
+    ) -> Result<Vec<String>> {
+        let rows = sqlx::query(&format!("PRAGMA {}.table_info(\"{}\")", schema, table))
+            .fetch_all(&mut *conn)
+            .await
+            .with_context(|| format!("Failed to read the columns of {}", table))?;
+        Ok(rows.iter().map(|row| row.get("name")).collect())
+    }
+
+    fn row_to_year_close(row: &sqlx::sqlite::SqliteRow) -> Result<YearClose> {
+        let closed_at_str: String = row.get("closed_at");
+        let closing_split_str: String = row.get("closing_split_id");
+        let opening_split_str: Option<String> = row.get("opening_split_id");
+
+        Ok(YearClose {
+            year: row.get("year"),
+            closed_at: DateTime::parse_from_rfc3339(&closed_at_str)
+                .context("Invalid closed_at")?
+                .with_timezone(&Utc),
+            closing_split_id: Uuid::parse_str(&closing_split_str).context("Invalid split ID")?,
+            opening_split_id: opening_split_str
+                .map(|s| Uuid::parse_str(&s))
+                .transpose()
+                .context("Invalid split ID")?,
+            archive_path: row.get("archive_path"),
+        })
+    }
+
+    // ========================
+    // Balance assertion operations
+    // ========================
+
+    /// Save an assertion, replacing the one made for the same wallet and day,
+    /// together with the transfer adjusting the balance to it, atomically.
+    pub async fn save_balance_assertion(
+        &self,
+        assertion: &BalanceAssertion,
+        adjustment: Option<&mut Transfer>,
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        if let Some(transfer) = adjustment {
+            Self::insert_transfer(&mut tx, transfer).await?;
+        }
+        sqlx::query(
+            r#"
+            INSERT INTO balance_assertions (id, wallet_id, date, balance_cents, created_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT (wallet_id, date) DO UPDATE SET
+                id = excluded.id,
+                balance_cents = excluded.balance_cents,
+                created_at = excluded.created_at
+            "#,
+        )
+        .bind(assertion.id.to_string())
+        .bind(assertion.wallet_id.to_string())
+        .bind(assertion.date.format("%Y-%m-%d").to_string())
+        .bind(assertion.balance_cents)
+        .bind(assertion.created_at.to_rfc3339())
+        .execute(&mut *tx)
+        .await
+        .context("Failed to save balance assertion")?;
+        tx.commit()
+            .await
+            .context("Failed to commit balance assertion")?;
+        Ok(())
+    }
+
+    /// List assertions by day.
+    pub async fn list_balance_assertions(&self) -> Result<Vec<BalanceAssertion>> {
+        let rows = sqlx::query(
+            "SELECT id, wallet_id, date, balance_cents, created_at FROM balance_assertions ORDER BY date, created_at",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list balance assertions")?;
+
+        rows.iter().map(Self::row_to_balance_assertion).collect()
+    }
+
+    /// Delete the assertion made for a wallet and day. Returns false if there was none.
+    pub async fn delete_balance_assertion(
+        &self,
+        wallet_id: WalletId,
+        date: chrono::NaiveDate,
+    ) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM balance_assertions WHERE wallet_id = ? AND date = ?")
+            .bind(wallet_id.to_string())
+            .bind(date.format("%Y-%m-%d").to_string())
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete balance assertion")?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Compute the balance of a wallet from the transfers dated before `end`.
+    pub async fn compute_balance_before(
+        &self,
+        wallet_id: WalletId,
+        end: DateTime<Utc>,
+    ) -> Result<Cents> {
+        let wallet_id_str = wallet_id.to_string();
+
+        let row = sqlx::query(
+            r#"
+            SELECT
+                COALESCE(SUM(CASE WHEN to_wallet_id = ? THEN amount_cents ELSE 0 END), 0) -
+                COALESCE(SUM(CASE WHEN from_wallet_id = ? THEN amount_cents ELSE 0 END), 0) as balance
+            FROM transfers
+            WHERE (from_wallet_id = ? OR to_wallet_id = ?) AND timestamp < ?
+            "#,
+        )
+        .bind(&wallet_id_str)
+        .bind(&wallet_id_str)
+        .bind(&wallet_id_str)
+        .bind(&wallet_id_str)
+        .bind(end.to_rfc3339())
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to compute balance")?;
+
+        Ok(row.get("balance"))
+    }
+
+    fn row_to_balance_assertion(row: &sqlx::sqlite::SqliteRow) -> Result<BalanceAssertion> {
+        let id_str: String = row.get("id");
+        let wallet_str: String = row.get("wallet_id");
+        let date_str: String = row.get("date");
+        let created_at_str: String = row.get("created_at");
+
+        Ok(BalanceAssertion {
+            id: Uuid::parse_str(&id_str).context("Invalid balance assertion ID")?,
+            wallet_id: Uuid::parse_str(&wallet_str).context("Invalid wallet ID")?,
+            date: chrono::NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
+                .context("Invalid assertion date")?,
+            balance_cents: row.get("balance_cents"),
+            created_at: DateTime::parse_from_rfc3339(&created_at_str)
+                .context("Invalid created_at")?
+                .with_timezone(&Utc),
+        })
+    }
+
+    // ========================
+    // Envelope operations
+    // ========================
+
+    /// Save a new envelope.
+    pub async fn save_envelope(&self, envelope: &Envelope) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO envelopes (id, name, category, currency, start_month, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(envelope.id.to_string())
+        .bind(&envelope.name)
+        .bind(&envelope.category)
+        .bind(&envelope.currency)
+        .bind(envelope.start_month.format("%Y-%m-%d").to_string())
+        .bind(envelope.created_at.to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .context("Failed to save envelope")?;
+        Ok(())
+    }
+
+    /// Get an envelope by name.
+    pub async fn get_envelope_by_name(&self, name: &str) -> Result<Option<Envelope>> {
+        let row = sqlx::query(
+            "SELECT id, name, category, currency, start_month, created_at FROM envelopes WHERE name = ?",
+        )
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch envelope by name")?;
+
+        row.as_ref().map(Self::row_to_envelope).transpose()
+    }
+
+    /// List envelopes by name.
+    pub async fn list_envelopes(&self) -> Result<Vec<Envelope>> {
+        let rows = sqlx::query(
+            "SELECT id, name, category, currency, start_month, created_at FROM envelopes ORDER BY name",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list envelopes")?;
+
+        rows.iter().map(Self::row_to_envelope).collect()
+    }
+
+    /// Delete an envelope with its assignments; moves that named it keep their
+    /// amount but no longer their counterpart.
+    pub async fn delete_envelope(&self, envelope_id: EnvelopeId) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        let id = envelope_id.to_string();
+        sqlx::query(
+            "UPDATE envelope_assignments SET counterpart_id = NULL WHERE counterpart_id = ?",
+        )
+        .bind(&id)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to detach envelope moves")?;
+        sqlx::query("DELETE FROM envelope_assignments WHERE envelope_id = ?")
+            .bind(&id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to delete envelope assignments")?;
+        sqlx::query("DELETE FROM envelopes WHERE id = ?")
+            .bind(&id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to delete envelope")?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Save assignments atomically (both halves of a move, for instance).
+    pub async fn save_envelope_assignments(
+        &self,
+        assignments: &[EnvelopeAssignment],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for assignment in assignments {
+            sqlx::query(
+                r#"
+                INSERT INTO envelope_assignments (id, envelope_id, month, amount_cents, counterpart_id, created_at)
+                VALUES (?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(assignment.id.to_string())
+            .bind(assignment.envelope_id.to_string())
+            .bind(assignment.month.format("%Y-%m-%d").to_string())
+            .bind(assignment.amount_cents)
+            .bind(assignment.counterpart.map(|id| id.to_string()))
+            .bind(assignment.created_at.to_rfc3339())
+            .execute(&mut *tx)
+            .await
+            .context("Failed to save envelope assignment")?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// List assignments by month, in the order they were made.
+    pub async fn list_envelope_assignments(&self) -> Result<Vec<EnvelopeAssignment>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, envelope_id, month, amount_cents, counterpart_id, created_at
+            FROM envelope_assignments
+            ORDER BY month, created_at
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list envelope assignments")?;
+
+        rows.iter().map(Self::row_to_envelope_assignment).collect()
+    }
+
+    /// Net income in `[from_date, to_date)`: what income wallets of the currency
+    /// paid out to other wallets, less what was paid back into them.
+    pub async fn sum_income(
+        &self,
+        currency: &str,
+        from_date: DateTime<Utc>,
+        to_date: DateTime<Utc>,
+    ) -> Result<Cents> {
+        let row = sqlx::query(
+            r#"
+            SELECT COALESCE(SUM(CASE WHEN f.wallet_type = 'income' THEN t.amount_cents ELSE -t.amount_cents END), 0) as total
+            FROM transfers t
+            JOIN wallets f ON f.id = t.from_wallet_id
+            JOIN wallets w ON w.id = t.to_wallet_id
+            WHERE (f.wallet_type = 'income') != (w.wallet_type = 'income')
+              AND UPPER(CASE WHEN f.wallet_type = 'income' THEN f.currency ELSE w.currency END) = UPPER(?)
+              AND t.timestamp >= ? AND t.timestamp < ?
+            "#,
+        )
+        .bind(currency)
+        .bind(from_date.to_rfc3339())
+        .bind(to_date.to_rfc3339())
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to sum income")?;
+
+        Ok(row.get("total"))
+    }
+
+    fn row_to_envelope(row: &sqlx::sqlite::SqliteRow) -> Result<Envelope> {
+        let id_str: String = row.get("id");
+        let start_str: String = row.get("start_month");
+        let created_at_str: String = row.get("created_at");
+
+        Ok(Envelope {
+            id: Uuid::parse_str(&id_str).context("Invalid envelope ID")?,
+            name: row.get("name"),
+            category: row.get("category"),
+            currency: row.get("currency"),
+            start_month: NaiveDate::parse_from_str(&start_str, "%Y-%m-%d")
+                .context("Invalid envelope start month")?,
+            created_at: DateTime::parse_from_rfc3339(&created_at_str)
+                .context("Invalid created_at")?
+                .with_timezone(&Utc),
+        })
+    }
+
+    fn row_to_envelope_assignment(row: &sqlx::sqlite::SqliteRow) -> Result<EnvelopeAssignment> {
+        let id_str: String = row.get("id");
+        let envelope_str: String = row.get("envelope_id");
+        let month_str: String = row.get("month");
+        let counterpart_str: Option<String> = row.get("counterpart_id");
+        let created_at_str: String = row.get("created_at");
+
+        Ok(EnvelopeAssignment {
+            id: Uuid::parse_str(&id_str).context("Invalid envelope assignment ID")?,
+            envelope_id: Uuid::parse_str(&envelope_str).context("Invalid envelope ID")?,
+            month: NaiveDate::parse_from_str(&month_str, "%Y-%m-%d")
+                .context("Invalid assignment month")?,
+            amount_cents: row.get("amount_cents"),
+            counterpart: counterpart_str
+                .map(|id| Uuid::parse_str(&id).context("Invalid envelope ID"))
+                .transpose()?,
+            created_at: DateTime::parse_from_rfc3339(&created_at_str)
+                .context("Invalid created_at")?
+                .with_timezone(&Utc),
+        })
+    }
+
+    // ========================
+    // Currency operations
+    // ========================
+
+    /// Save or replace a registered currency.
+    pub async fn save_currency(&self, currency: &crate::domain::Currency) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO currencies (code, minor_units, created_at)
+            VALUES (?, ?, ?)
+            ON CONFLICT(code) DO UPDATE SET minor_units = excluded.minor_units
+            "#,
+        )
+        .bind(&currency.code)
+        .bind(currency.minor_units as i64)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .context("Failed to save currency")?;
+        Ok(())
+    }
+
+    /// Get a registered currency by code.
+    pub async fn get_currency(&self, code: &str) -> Result<Option<crate::domain::Currency>> {
+        let row = sqlx::query("SELECT code, minor_units FROM currencies WHERE code = ?")
+            .bind(code.to_uppercase())
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch currency")?;
+
+        Ok(row.map(|row| Self::row_to_currency(&row)))
+    }
+
+    /// List registered currencies.
+    pub async fn list_currencies(&self) -> Result<Vec<crate::domain::Currency>> {
+        let rows = sqlx::query("SELECT code, minor_units FROM currencies ORDER BY code")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to list currencies")?;
+
+        Ok(rows.iter().map(Self::row_to_currency).collect())
+    }
+
+    /// Count transfers from wallets holding the given currency.
+    pub async fn count_transfers_in_currency(&self, code: &str) -> Result<i64> {
+        let row = sqlx::query(
+            r#"
+            SELECT COUNT(*) as count
+            FROM transfers t
+            JOIN wallets w ON w.id = t.from_wallet_id
+            WHERE UPPER(w.currency) = ?
+            "#,
+        )
+        .bind(code.to_uppercase())
+        .fetch_one(&self.pool)
+        .await
+        .context("Failed to count transfers in currency")?;
+
+        Ok(row.get("count"))
+    }
+
+    fn row_to_currency(row: &sqlx::sqlite::SqliteRow) -> crate::domain::Currency {
+        let minor_units: i64 = row.get("minor_units");
+        crate::domain::Currency::new(row.get::<String, _>("code"), minor_units as u32)
+    }
+
+    // ========================
+    // Settings and locale operations
+    // ========================
+
+    /// Get a ledger setting by key.
+    pub async fn get_setting(&self, key: &str) -> Result<Option<String>> {
+        let row = sqlx::query("SELECT value FROM settings WHERE key = ?")
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to fetch setting")?;
+
+        Ok(row.map(|row| row.get("value")))
+    }
+
+    /// Set a ledger setting, replacing any previous value.
+    pub async fn set_setting(&self, key: &str, value: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO settings (key, value, updated_at)
+            VALUES (?, ?, ?)
+            ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(key)
+        .bind(value)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .context("Failed to save setting")?;
+        Ok(())
+    }
+
+    /// Remove a ledger setting.
+    pub async fn delete_setting(&self, key: &str) -> Result<()> {
+        sqlx::query("DELETE FROM settings WHERE key = ?")
+            .bind(key)
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete setting")?;
+        Ok(())
+    }
+
+    /// Save or replace a custom locale profile.
+    pub async fn save_locale_profile(&self, profile: &crate::domain::LocaleProfile) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO locale_profiles (name, decimal_separator, grouping_separator, symbol_placement, symbol_space, negative_style, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(name) DO UPDATE SET
+                decimal_separator = excluded.decimal_separator,
+                grouping_separator = excluded.grouping_separator,
+                symbol_placement = excluded.symbol_placement,
+                symbol_space = excluded.symbol_space,
+                negative_style = excluded.negative_style
+            "#,
+        )
+        .bind(&profile.name)
+        .bind(profile.decimal_separator.to_string())
+        .bind(profile.grouping_separator.map(|c| c.to_string()))
+        .bind(profile.symbol_placement.as_str())
+        .bind(profile.symbol_space)
+        .bind(profile.negative_style.as_str())
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await
+        .context("Failed to save locale profile")?;
+        Ok(())
+    }
+
+    /// Get a custom locale profile by name (case-insensitive).
+    pub async fn get_locale_profile(
+        &self,
+        name: &str,
+    ) -> Result<Option<crate::domain::LocaleProfile>> {
+        let row = sqlx::query(
+            r#"
+            SELECT name, decimal_separator, grouping_separator, symbol_placement, symbol_space, negative_style
+            FROM locale_profiles
+            WHERE name = ? COLLATE NOCASE
+            "#,
+        )
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to fetch locale profile")?;
+
+        row.map(|row| Self::row_to_locale_profile(&row)).transpose()
+    }
+
+    /// List custom locale profiles.
+    pub async fn list_locale_profiles(&self) -> Result<Vec<crate::domain::LocaleProfile>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT name, decimal_separator, grouping_separator, symbol_placement, symbol_space, negative_style
+            FROM locale_profiles
+            ORDER BY name
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list locale profiles")?;
+
+        rows.iter().map(Self::row_to_locale_profile).collect()
+    }
+
+    fn row_to_locale_profile(
+        row: &sqlx::sqlite::SqliteRow,
+    ) -> Result<crate::domain::LocaleProfile> {
+        use crate::domain::{LocaleProfile, NegativeStyle, SymbolPlacement};
+
+        let first_char = |s: String| s.chars().next();
+        let decimal_separator = first_char(row.get("decimal_separator"))
+            .context("Locale profile has an empty decimal separator")?;
+        let grouping_separator = row
+            .get::<Option<String>, _>("grouping_separator")
+            .and_then(first_char);
+        let placement_str: String = row.get("symbol_placement");
+        let symbol_placement: SymbolPlacement = placement_str.parse().map_err(|e| {
+            anyhow::anyhow!("Invalid symbol placement: {}. Error: {}", placement_str, e)
+        })?;
+        let negative_str: String = row.get("negative_style");
+        let negative_style: NegativeStyle = negative_str.parse().map_err(|e| {
+            anyhow::anyhow!("Invalid negative style: {}. Error: {}", negative_str, e)
+        })?;
+
+        Ok(LocaleProfile::new(row.get("name"), decimal_separator)
+            .with_grouping(grouping_separator)
+            .with_symbol(symbol_placement, row.get::<i32, _>("symbol_space") != 0)
+            .with_negative_style(negative_style))
+    }
+
+    // ========================
+    // Scheduled Transfer operations
+    // ========================
+
+    /// Save a new scheduled transfer to the database.
+    pub async fn save_scheduled_transfer(
+        &self,
+        st: &crate::domain::ScheduledTransfer,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO scheduled_transfers (id, name, from_wallet_id, to_wallet_id, amount_cents, pattern, start_date, end_date, last_executed_at, description, category, status, created_at, tags)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(st.id.to_string())
+        .bind(&st.name)
+        .bind(st.from_wallet.to_string())
         .bind(st.to_wallet.to_string())
         .bind(st.amount_cents)
         .bind(st.pattern.as_str())
@@ -783,6 +3624,7 @@ impl Repository {
         .bind(&st.category)
         .bind(st.status.as_str())
         .bind(st.created_at.to_rfc3339())
+        .bind(serde_json::to_string(&st.tags)?)
         .execute(&self.pool)
         .await
         .context("Failed to save scheduled transfer")?;
@@ -796,7 +3638,7 @@ impl Repository {
     ) -> Result<Option<crate::domain::ScheduledTransfer>> {
         let row = sqlx::query(
             r#"
-            SELECT id, name, from_wallet_id, to_wallet_id, amount_cents, pattern, start_date, end_date, last_executed_at, description, category, status, created_at
+            SELECT id, name, from_wallet_id, to_wallet_id, amount_cents, pattern, start_date, end_date, last_executed_at, description, category, status, created_at, tags
             FROM scheduled_transfers
             WHERE id = ?
             "#,
@@ -819,7 +3661,7 @@ impl Repository {
     ) -> Result<Option<crate::domain::ScheduledTransfer>> {
         let row = sqlx::query(
             r#"
-            SELECT id, name, from_wallet_id, to_wallet_id, amount_cents, pattern, start_date, end_date, last_executed_at, description, category, status, created_at
+            SELECT id, name, from_wallet_id, to_wallet_id, amount_cents, pattern, start_date, end_date, last_executed_at, description, category, status, created_at, tags
             FROM scheduled_transfers
             WHERE name = ?
             "#,
@@ -841,9 +3683,9 @@ impl Repository {
         include_inactive: bool,
     ) -> Result<Vec<crate::domain::ScheduledTransfer>> {
         let query = if include_inactive {
-            "SELECT id, name, from_wallet_id, to_wallet_id, amount_cents, pattern, start_date, end_date, last_executed_at, description, category, status, created_at FROM scheduled_transfers ORDER BY name"
+            "SELECT id, name, from_wallet_id, to_wallet_id, amount_cents, pattern, start_date, end_date, last_executed_at, description, category, status, created_at, tags FROM scheduled_transfers ORDER BY name"
         } else {
-            "SELECT id, name, from_wallet_id, to_wallet_id, amount_cents, pattern, start_date, end_date, last_executed_at, description, category, status, created_at FROM scheduled_transfers WHERE status = 'active' ORDER BY name"
+            "SELECT id, name, from_wallet_id, to_wallet_id, amount_cents, pattern, start_date, end_date, last_executed_at, description, category, status, created_at, tags FROM scheduled_transfers WHERE status = 'active' ORDER BY name"
         };
 
         let rows = sqlx::query(query)
@@ -884,6 +3726,21 @@ impl Repository {
         Ok(())
     }
 
+    /// Replace the tags of a scheduled transfer.
+    pub async fn update_scheduled_transfer_tags(
+        &self,
+        id: crate::domain::ScheduledTransferId,
+        tags: &[String],
+    ) -> Result<()> {
+        sqlx::query("UPDATE scheduled_transfers SET tags = ? WHERE id = ?")
+            .bind(serde_json::to_string(tags)?)
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await
+            .context("Failed to update scheduled transfer tags")?;
+        Ok(())
+    }
+
     /// Delete a scheduled transfer.
     pub async fn delete_scheduled_transfer(
         &self,
@@ -909,6 +3766,7 @@ impl Repository {
         let last_executed_str: Option<String> = row.get("last_executed_at");
         let status_str: String = row.get("status");
         let created_at_str: String = row.get("created_at");
+        let tags_json: Option<String> = row.get("tags");
 
         Ok(crate::domain::ScheduledTransfer {
             id: Uuid::parse_str(&id_str).context("Invalid scheduled transfer ID")?,
@@ -934,6 +3792,11 @@ impl Repository {
                 .map(|dt| dt.with_timezone(&Utc)),
             description: row.get("description"),
             category: row.get("category"),
+            tags: tags_json
+                .map(|json| serde_json::from_str(&json))
+                .transpose()
+                .context("Invalid scheduled transfer tags")?
+                .unwrap_or_default(),
             status: status_str.parse().map_err(|e| {
                 anyhow::anyhow!("Invalid schedule status: {}. Error: {}", status_str, e)
             })?,
@@ -946,22 +3809,63 @@ impl Repository {
     fn row_to_budget(row: &sqlx::sqlite::SqliteRow) -> Result<crate::domain::Budget> {
         let id_str: String = row.get("id");
         let period_type_str: String = row.get("period_type");
+        let rollover_str: String = row.get("rollover");
+        let rollover_since_str: Option<String> = row.get("rollover_since");
+        let filter_json: String = row.get("filter");
+        let thresholds_json: String = row.get("alert_thresholds");
         let created_at_str: String = row.get("created_at");
 
         Ok(crate::domain::Budget {
             id: Uuid::parse_str(&id_str).context("Invalid budget ID")?,
             name: row.get("name"),
             category: row.get("category"),
+            filter: serde_json::from_str(&filter_json).context("Invalid budget filter")?,
             period_type: period_type_str.parse().map_err(|e| {
                 anyhow::anyhow!("Invalid period type: {}. Error: {}", period_type_str, e)
             })?,
             amount_cents: row.get("amount_cents"),
+            currency: row.get("currency"),
+            rollover: rollover_str.parse().map_err(|e| anyhow::anyhow!("{}", e))?,
+            rollover_since: rollover_since_str
+                .map(|s| NaiveDate::parse_from_str(&s, "%Y-%m-%d"))
+                .transpose()
+                .context("Invalid rollover_since date")?,
+            alert_thresholds: serde_json::from_str(&thresholds_json)
+                .context("Invalid budget alert thresholds")?,
             created_at: DateTime::parse_from_rfc3339(&created_at_str)
                 .context("Invalid created_at timestamp")?
                 .with_timezone(&Utc),
         })
     }
 
+    fn row_to_budget_event(row: &sqlx::sqlite::SqliteRow) -> Result<crate::domain::BudgetEvent> {
+        let budget_id_str: String = row.get("budget_id");
+        let transfer_id_str: Option<String> = row.get("transfer_id");
+        let timestamp = |column: &str| -> Result<DateTime<Utc>> {
+            let value: String = row.get(column);
+            Ok(DateTime::parse_from_rfc3339(&value)
+                .with_context(|| format!("Invalid {} timestamp", column))?
+                .with_timezone(&Utc))
+        };
+
+        Ok(crate::domain::BudgetEvent {
+            budget_id: Uuid::parse_str(&budget_id_str).context("Invalid budget ID")?,
+            budget: row.get("name"),
+            threshold: row.get("threshold"),
+            period_start: timestamp("period_start")?,
+            period_end: timestamp("period_end")?,
+            limit: row.get("limit_cents"),
+            spent: row.get("spent_cents"),
+            currency: row.get("currency"),
+            transfer_id: transfer_id_str
+                .map(|id| Uuid::parse_str(&id))
+                .transpose()
+                .context("Invalid transfer ID")?,
+            fired_at: timestamp("fired_at")?,
+            delivery_error: None,
+        })
+    }
+
     fn row_to_transfer(row: &sqlx::sqlite::SqliteRow) -> Result<Transfer> {
         let id_str: String = row.get("id");
         let from_wallet_str: String = row.get("from_wallet_id");
@@ -970,6 +3874,9 @@ impl Repository {
         let recorded_at_str: String = row.get("recorded_at");
         let tags_json: String = row.get("tags");
         let reverses_str: Option<String> = row.get("reverses");
+        let split_id_str: Option<String> = row.get("split_id");
+        let cleared_str: String = row.get("cleared");
+        let payee_id_str: Option<String> = row.get("payee_id");
 
         Ok(Transfer {
             id: Uuid::parse_str(&id_str).context("Invalid transfer ID")?,
@@ -991,6 +3898,53 @@ impl Repository {
                 .transpose()
                 .context("Invalid reverses ID")?,
             external_ref: row.get("external_ref"),
+            split_id: split_id_str
+                .map(|s| Uuid::parse_str(&s))
+                .transpose()
+                .context("Invalid split ID")?,
+            quantity: row.get("quantity"),
+            unit_price: row.get("unit_price"),
+            cleared: cleared_str.parse().map_err(|e| {
+                anyhow::anyhow!("Invalid cleared state: {}. Error: {}", cleared_str, e)
+            })?,
+            payee_id: payee_id_str
+                .map(|s| Uuid::parse_str(&s))
+                .transpose()
+                .context("Invalid payee ID")?,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_every_table_has_a_copy_rule() {
+        let repo = Repository::connect("sqlite::memory:").await.unwrap();
+        repo.migrate().await.unwrap();
+        let tables: Vec<String> = sqlx::query(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' AND name != 'sequence_counter'",
+        )
+        .fetch_all(&repo.pool)
+        .await
+        .unwrap()
+        .iter()
+        .map(|row| row.get("name"))
+        .collect();
+        assert!(tables.contains(&"budget_events".to_string()));
+
+        let range = TransferRange::Before(Utc::now());
+        for table in &tables {
+            assert!(range.filter(table).is_ok(), "{} has no copy rule", table);
+        }
+        assert_eq!(range.filter("wallets").unwrap(), "");
+        assert!(
+            range
+                .filter("budget_events")
+                .unwrap()
+                .contains("transfer_id IN")
+        );
+        assert!(range.filter("unknown").is_err());
+    }
+}
@@ -0,0 +1,204 @@
+mod common;
+
+use anyhow::Result;
+use common::{StandardWallets, parse_date, test_service};
+use pecunio::application::{AppError, SplitLeg, TransferEdit};
+use pecunio::domain::{AmendedField, IntegrityIssue};
+use sqlx::SqlitePool;
+
+#[tokio::test]
+async fn test_amendments_correct_transfer_and_keep_history() -> Result<()> {
+    let (service, temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    StandardWallets::fund_checking(&service, 100000, parse_date("2024-01-01")).await?;
+
+    let groceries = service
+        .record_transfer(
+            "Checking",
+            "Expense",
+            4500,
+            parse_date("2024-01-31"),
+            Some("Grocries".into()),
+            Some("dining".into()),
+            false,
+        )
+        .await?
+        .transfer;
+
+    // Fix the typo and the category, then the date and the amount
+    let first = service
+        .amend_transfer(
+            groceries.id,
+            TransferEdit::new()
+                .with_description(Some("Groceries".into()))
+                .with_category(Some("groceries".into()))
+                .with_author(Some("alice".into())),
+        )
+        .await?;
+    assert!(first.amendment.changes_field(AmendedField::Category));
+    service
+        .amend_transfer(
+            groceries.id,
+            TransferEdit::new()
+                .with_timestamp(parse_date("2024-02-01"))
+                .with_amount(5400)
+                .with_reason(Some("receipt says 54.00".into())),
+        )
+        .await?;
+
+    let info = service.get_transfer_info(groceries.id).await?;
+    assert_eq!(info.transfer.sequence, groceries.sequence);
+    assert_eq!(info.transfer.description.as_deref(), Some("Groceries"));
+    assert_eq!(info.transfer.amount_cents, 5400);
+    assert_eq!(info.transfer.timestamp, parse_date("2024-02-01"));
+    assert_eq!(info.amendments.len(), 2);
+    assert_eq!(info.amendments[0].author.as_deref(), Some("alice"));
+    assert_eq!(info.amendments[0].changes.len(), 2);
+    let fields: Vec<AmendedField> = info.amendments[1].changes.iter().map(|c| c.field).collect();
+    assert_eq!(fields, vec![AmendedField::Date, AmendedField::Amount]);
+    assert_eq!(
+        info.amendments[1].changes[1].old_value.as_deref(),
+        Some("4500")
+    );
+
+    // Reports only see the current values
+    assert_eq!(service.get_balance("Checking").await?.balance, 94600);
+    let january = service
+        .get_category_report(parse_date("2024-01-01"), parse_date("2024-02-01"))
+        .await?;
+    assert!(january.categories.is_empty());
+    let february = service
+        .get_category_report(parse_date("2024-02-01"), parse_date("2024-03-01"))
+        .await?;
+    assert_eq!(february.categories.len(), 1);
+    assert_eq!(february.categories[0].category, "groceries");
+    assert_eq!(february.categories[0].total, 5400);
+
+    let unchanged = service
+        .amend_transfer(
+            groceries.id,
+            TransferEdit::new().with_description(Some("Groceries".into())),
+        )
+        .await;
+    assert!(matches!(unchanged, Err(AppError::InvalidAmendment(_))));
+    assert!(service.check_integrity().await?.is_healthy());
+
+    // The history cannot be rewritten, and changes made around it are reported
+    let db_path = temp.path().join("test.db");
+    let pool = SqlitePool::connect(&format!("sqlite:{}", db_path.to_str().unwrap())).await?;
+    let deleted = sqlx::query("DELETE FROM transfer_amendments")
+        .execute(&pool)
+        .await;
+    assert!(deleted.is_err());
+    sqlx::query("UPDATE transfers SET amount_cents = 4500 WHERE id = ?")
+        .bind(groceries.id.to_string())
+        .execute(&pool)
+        .await?;
+    let report = service.check_integrity().await?;
+    assert!(report.issues.contains(&IntegrityIssue::UnrecordedEdits(1)));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_amendments_keep_ledger_consistent() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    StandardWallets::fund_checking(&service, 10000, parse_date("2024-01-01")).await?;
+
+    let rent = service
+        .record_transfer(
+            "Checking",
+            "Expense",
+            6000,
+            parse_date("2024-01-05"),
+            None,
+            None,
+            false,
+        )
+        .await?
+        .transfer;
+    service.reverse_transfer(rent.id, Some(2000)).await?;
+
+    let below_reversed = service
+        .amend_transfer(rent.id, TransferEdit::new().with_amount(1500))
+        .await;
+    assert!(matches!(below_reversed, Err(AppError::InvalidAmendment(_))));
+    let overdrawn = service
+        .amend_transfer(rent.id, TransferEdit::new().with_amount(20000))
+        .await;
+    assert!(matches!(overdrawn, Err(AppError::InsufficientFunds { .. })));
+    service
+        .amend_transfer(
+            rent.id,
+            TransferEdit::new().with_amount(20000).with_force(true),
+        )
+        .await?;
+    assert_eq!(service.get_balance("Checking").await?.balance, -8000);
+
+    // A new date moves every leg of a split; its amounts cannot change
+    let split = service
+        .record_split_transfer(
+            "Savings",
+            vec![
+                SplitLeg {
+                    to_wallet: "Expense".into(),
+                    amount_cents: 300,
+                    category: None,
+                    description: None,
+                },
+                SplitLeg {
+                    to_wallet: "Checking".into(),
+                    amount_cents: 700,
+                    category: None,
+                    description: None,
+                },
+            ],
+            parse_date("2024-01-10"),
+            None,
+            true,
+        )
+        .await?;
+    let first_leg = split.legs[0].transfer.id;
+    let resized = service
+        .amend_transfer(first_leg, TransferEdit::new().with_amount(400))
+        .await;
+    assert!(matches!(resized, Err(AppError::InvalidSplit(_))));
+    let moved = service
+        .amend_transfer(
+            first_leg,
+            TransferEdit::new().with_timestamp(parse_date("2024-01-12")),
+        )
+        .await?;
+    assert_eq!(moved.split_amendments.len(), 1);
+    let other_leg = service.get_transfer_info(split.legs[1].transfer.id).await?;
+    assert_eq!(other_leg.transfer.timestamp, parse_date("2024-01-12"));
+    assert_eq!(other_leg.amendments.len(), 1);
+
+    // Reconciled transfers keep the amount and date agreed with the bank
+    service
+        .start_reconciliation("Checking", parse_date("2024-01-31"), 10000)
+        .await?;
+    let funding = service
+        .list_transfers(Some("Checking"))
+        .await?
+        .into_iter()
+        .find(|t| t.amount_cents == 10000)
+        .unwrap();
+    service.tick_transfers("Checking", &[funding.id]).await?;
+    service.finish_reconciliation("Checking").await?;
+    let reconciled = service
+        .amend_transfer(funding.id, TransferEdit::new().with_amount(11000))
+        .await;
+    assert!(matches!(reconciled, Err(AppError::InvalidAmendment(_))));
+    service
+        .amend_transfer(
+            funding.id,
+            TransferEdit::new().with_description(Some("Salary".into())),
+        )
+        .await?;
+
+    assert!(service.check_integrity().await?.is_healthy());
+
+    Ok(())
+}
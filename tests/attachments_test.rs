@@ -0,0 +1,110 @@
+mod common;
+
+use anyhow::Result;
+use common::{StandardWallets, parse_date, test_service};
+use pecunio::application::{AppError, LedgerService};
+use pecunio::domain::TransferId;
+use pecunio::io::{DatabaseSnapshot, Exporter};
+use sqlx::SqlitePool;
+
+const RECEIPT: &[u8] = b"%PDF-1.4 receipt for a washing machine";
+
+async fn record_purchase(service: &LedgerService) -> Result<TransferId> {
+    StandardWallets::create_basic(service).await?;
+    StandardWallets::fund_checking(service, 100000, parse_date("2024-01-01")).await?;
+    let result = service
+        .record_transfer(
+            "Checking",
+            "Expense",
+            49900,
+            parse_date("2024-03-01"),
+            Some("Washing machine".into()),
+            None,
+            false,
+        )
+        .await?;
+    Ok(result.transfer.id)
+}
+
+#[tokio::test]
+async fn test_attachments_share_content() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    let transfer_id = record_purchase(&service).await?;
+
+    let receipt = service
+        .attach_file(transfer_id, "scans/receipt.pdf", RECEIPT)
+        .await?;
+    assert_eq!(receipt.filename, "receipt.pdf");
+    assert_eq!(receipt.media_type, "application/pdf");
+    assert_eq!(receipt.size, RECEIPT.len() as i64);
+    let warranty = service
+        .attach_file(transfer_id, "warranty.pdf", RECEIPT)
+        .await?;
+    assert_eq!(warranty.hash, receipt.hash);
+
+    let info = service.get_transfer_info(transfer_id).await?;
+    assert_eq!(info.attachments.len(), 2);
+    // The same content is stored once
+    assert_eq!(service.list_attachment_data().await?.len(), 1);
+
+    service.remove_attachment(receipt.id).await?;
+    let (_, data) = service.read_attachment(warranty.id).await?;
+    assert_eq!(data, RECEIPT);
+    service.remove_attachment(warranty.id).await?;
+    assert!(service.list_attachment_data().await?.is_empty());
+    assert!(matches!(
+        service.read_attachment(warranty.id).await,
+        Err(AppError::AttachmentNotFound(_))
+    ));
+
+    let empty = service.attach_file(transfer_id, "empty.txt", b"").await;
+    assert!(matches!(empty, Err(AppError::InvalidAttachment(_))));
+    let orphan = service
+        .attach_file(uuid::Uuid::new_v4(), "receipt.pdf", RECEIPT)
+        .await;
+    assert!(matches!(orphan, Err(AppError::TransferNotFound(_))));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_attachments_in_full_export() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    let transfer_id = record_purchase(&service).await?;
+    let receipt = service
+        .attach_file(transfer_id, "receipt.pdf", RECEIPT)
+        .await?;
+
+    let mut json = Vec::new();
+    Exporter::new(&service).export_full_json(&mut json).await?;
+    let snapshot: DatabaseSnapshot = serde_json::from_slice(&json)?;
+    assert_eq!(snapshot.attachments.len(), 1);
+    assert_eq!(snapshot.attachments[0].transfer_id, transfer_id);
+    assert_eq!(snapshot.attachment_blobs.len(), 1);
+    assert_eq!(snapshot.attachment_blobs[0].hash, receipt.hash);
+    assert_eq!(snapshot.attachment_blobs[0].data, RECEIPT);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tampered_attachment_is_rejected() -> Result<()> {
+    let (service, temp) = test_service().await?;
+    let transfer_id = record_purchase(&service).await?;
+    let receipt = service
+        .attach_file(transfer_id, "receipt.pdf", RECEIPT)
+        .await?;
+
+    let db_path = temp.path().join("test.db");
+    let pool = SqlitePool::connect(&format!("sqlite:{}", db_path.to_str().unwrap())).await?;
+    sqlx::query("UPDATE attachment_blobs SET data = ? WHERE hash = ?")
+        .bind(b"something else".as_slice())
+        .bind(&receipt.hash)
+        .execute(&pool)
+        .await?;
+
+    let read = service.read_attachment(receipt.id).await;
+    assert!(matches!(read, Err(AppError::InvalidAttachment(_))));
+
+    Ok(())
+}
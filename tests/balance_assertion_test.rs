@@ -0,0 +1,190 @@
+mod common;
+
+use anyhow::Result;
+use common::{StandardWallets, parse_date, parse_day, test_service};
+use pecunio::application::AppError;
+use pecunio::domain::{IntegrityIssue, WalletType};
+use sqlx::SqlitePool;
+
+#[tokio::test]
+async fn test_assertion_covers_the_end_of_its_day() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    StandardWallets::fund_checking(&service, 150000, parse_date("2026-09-01")).await?;
+    service
+        .record_transfer(
+            "Checking",
+            "Expense",
+            26544,
+            parse_date("2026-09-30"),
+            Some("Groceries".into()),
+            None,
+            false,
+        )
+        .await?;
+    // The next day is after the assertion
+    service
+        .record_transfer(
+            "Checking",
+            "Expense",
+            1000,
+            parse_date("2026-10-01"),
+            None,
+            None,
+            false,
+        )
+        .await?;
+
+    let result = service
+        .assert_balance("Checking", parse_day("2026-09-30"), 123456, false)
+        .await?;
+    assert!(result.check.holds());
+    assert!(result.adjustment.is_none());
+    assert!(service.check_integrity().await?.is_healthy());
+
+    // A transfer backdated into the asserted period breaks the assertion
+    service
+        .record_transfer(
+            "Checking",
+            "Expense",
+            500,
+            parse_date("2026-09-15"),
+            None,
+            None,
+            false,
+        )
+        .await?;
+    let checks = service.check_balance_assertions().await?;
+    assert_eq!(checks.len(), 1);
+    assert_eq!(checks[0].actual_cents, 122956);
+    assert_eq!(checks[0].difference(), 500);
+    let report = service.check_integrity().await?;
+    assert!(
+        report
+            .issues
+            .contains(&IntegrityIssue::FailedBalanceAssertions(1))
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_adjust_posts_the_difference_against_equity() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    StandardWallets::fund_checking(&service, 100000, parse_date("2026-09-01")).await?;
+    service
+        .create_wallet("Card".into(), WalletType::Liability, "EUR".into(), None)
+        .await?;
+    service
+        .record_transfer(
+            "Card",
+            "Expense",
+            4000,
+            parse_date("2026-09-10"),
+            None,
+            None,
+            false,
+        )
+        .await?;
+
+    // Bank fees never recorded: the statement shows less than the ledger
+    let checking = service
+        .assert_balance("Checking", parse_day("2026-09-30"), 99250, true)
+        .await?;
+    assert!(checking.check.holds());
+    let adjustment = checking.adjustment.expect("an adjustment");
+    assert_eq!(adjustment.from_wallet_name, "Checking");
+    assert_eq!(adjustment.to_wallet_name, "Adjustments EUR");
+    assert_eq!(adjustment.transfer.amount_cents, 750);
+    assert_eq!(
+        adjustment.transfer.timestamp.to_rfc3339(),
+        "2026-09-30T23:59:59+00:00"
+    );
+
+    // More is owed on the card than recorded
+    let card = service
+        .assert_balance("Card", parse_day("2026-09-30"), -4500, true)
+        .await?;
+    let adjustment = card.adjustment.expect("an adjustment");
+    assert_eq!(adjustment.from_wallet_name, "Card");
+    assert_eq!(adjustment.transfer.amount_cents, 500);
+    assert_eq!(service.get_balance("Card").await?.balance, -4500);
+    assert_eq!(service.get_balance("Adjustments EUR").await?.balance, 1250);
+    assert!(service.check_integrity().await?.is_healthy());
+
+    // Nothing to adjust once it holds
+    let again = service
+        .assert_balance("Card", parse_day("2026-09-30"), -4500, true)
+        .await?;
+    assert!(again.adjustment.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_adjustment_is_not_kept_without_its_assertion() -> Result<()> {
+    let (service, temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    StandardWallets::fund_checking(&service, 100000, parse_date("2026-09-01")).await?;
+
+    // Make saving the assertion itself fail
+    let db_path = temp.path().join("test.db");
+    let pool = SqlitePool::connect(&format!("sqlite:{}", db_path.to_str().unwrap())).await?;
+    sqlx::query(
+        "CREATE TRIGGER refuse_assertions BEFORE INSERT ON balance_assertions BEGIN SELECT RAISE(ABORT, 'refused'); END",
+    )
+    .execute(&pool)
+    .await?;
+    assert!(
+        service
+            .assert_balance("Checking", parse_day("2026-09-30"), 99250, true)
+            .await
+            .is_err()
+    );
+    assert_eq!(service.get_balance("Checking").await?.balance, 100000);
+
+    // Asserting again posts the adjustment once
+    sqlx::query("DROP TRIGGER refuse_assertions")
+        .execute(&pool)
+        .await?;
+    service
+        .assert_balance("Checking", parse_day("2026-09-30"), 99250, true)
+        .await?;
+    assert_eq!(service.get_balance("Checking").await?.balance, 99250);
+    assert_eq!(service.list_balance_assertions().await?.len(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_assertions_are_replaced_and_removed() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    StandardWallets::fund_checking(&service, 100000, parse_date("2026-09-01")).await?;
+
+    let wrong = service
+        .assert_balance("Checking", parse_day("2026-09-30"), 90000, false)
+        .await?;
+    assert!(!wrong.check.holds());
+    service
+        .assert_balance("Checking", parse_day("2026-09-30"), 100000, false)
+        .await?;
+    let assertions = service.list_balance_assertions().await?;
+    assert_eq!(assertions.len(), 1);
+    assert_eq!(assertions[0].balance_cents, 100000);
+    assert!(service.check_integrity().await?.is_healthy());
+
+    service
+        .remove_balance_assertion("Checking", parse_day("2026-09-30"))
+        .await?;
+    assert!(service.list_balance_assertions().await?.is_empty());
+    assert!(matches!(
+        service
+            .remove_balance_assertion("Checking", parse_day("2026-09-30"))
+            .await,
+        Err(AppError::BalanceAssertionNotFound(_))
+    ));
+
+    Ok(())
+}
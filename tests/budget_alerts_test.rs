@@ -0,0 +1,242 @@
+mod common;
+
+use anyhow::Result;
+use chrono::{Months, Utc};
+use common::{StandardWallets, food_budget, parse_date, test_service};
+use pecunio::application::{
+    AppError, BudgetAlertHooks, BudgetRequest, LedgerService, SplitLeg, TransferRequest,
+};
+use pecunio::domain::{PeriodType, RecurrencePattern};
+
+/// Funded standard wallets and a food budget warning at 80% and 100% of its limit
+async fn alerting_budget(service: &LedgerService) -> Result<()> {
+    StandardWallets::create_basic(service).await?;
+    StandardWallets::fund_checking_now(service, 500000).await?;
+    food_budget(service, |request| {
+        request.with_alert_thresholds(vec![100, 80, 80])
+    })
+    .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_thresholds_fire_once_per_period() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    alerting_budget(&service).await?;
+    assert_eq!(
+        service.get_budget("food").await?.alert_thresholds,
+        vec![80, 100]
+    );
+
+    StandardWallets::spend(&service, 20000, Utc::now(), "food").await?;
+    assert!(service.take_budget_events().is_empty());
+
+    // Crossing 80% fires once, however much more is spent below 100%
+    StandardWallets::spend(&service, 13000, Utc::now(), "food:groceries").await?;
+    StandardWallets::spend(&service, 1000, Utc::now(), "food").await?;
+    let events = service.take_budget_events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].threshold, 80);
+    assert_eq!(events[0].spent, 33000);
+    assert_eq!(events[0].limit, 40000);
+
+    // Other categories do not count
+    StandardWallets::spend(&service, 90000, Utc::now(), "rent").await?;
+    assert!(service.take_budget_events().is_empty());
+
+    StandardWallets::spend(&service, 10000, Utc::now(), "food").await?;
+    let events = service.take_budget_events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].threshold, 100);
+    assert!(events[0].is_exceeded());
+    StandardWallets::spend(&service, 10000, Utc::now(), "food").await?;
+    assert!(service.take_budget_events().is_empty());
+
+    let history = service.list_budget_events(Some("food")).await?;
+    let thresholds: Vec<u32> = history.iter().map(|e| e.threshold).collect();
+    assert_eq!(thresholds, vec![100, 80]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_past_periods_raise_no_alerts() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    alerting_budget(&service).await?;
+
+    let last_year = Utc::now() - Months::new(12);
+    StandardWallets::fund_checking(&service, 100000, last_year).await?;
+    service
+        .record_transfer_with(
+            TransferRequest::new("Checking", "Expense", 50000, last_year)
+                .with_category(Some("food".into())),
+        )
+        .await?;
+    assert!(service.take_budget_events().is_empty());
+    assert!(service.list_budget_events(None).await?.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_leap_day_transfer_under_a_yearly_budget() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    StandardWallets::fund_checking(&service, 100000, parse_date("2024-01-01")).await?;
+    service
+        .create_budget_with(
+            BudgetRequest::new("yearly food", "food", 400000, PeriodType::Yearly)
+                .with_alert_thresholds(vec![1]),
+        )
+        .await?;
+
+    StandardWallets::spend(&service, 10000, parse_date("2024-02-29"), "food").await?;
+    assert!(service.take_budget_events().is_empty());
+    assert!(service.take_budget_alert_errors().is_empty());
+    assert_eq!(service.get_balance("Expense").await?.balance, 10000);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_alerts_from_splits_and_scheduled_transfers() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    alerting_budget(&service).await?;
+
+    service
+        .record_split_transfer(
+            "Checking",
+            vec![
+                SplitLeg {
+                    to_wallet: "Expense".into(),
+                    amount_cents: 20000,
+                    category: Some("food".into()),
+                    description: None,
+                },
+                SplitLeg {
+                    to_wallet: "Expense".into(),
+                    amount_cents: 15000,
+                    category: Some("food:groceries".into()),
+                    description: None,
+                },
+            ],
+            Utc::now(),
+            None,
+            false,
+        )
+        .await?;
+    let events = service.take_budget_events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].spent, 35000);
+
+    service
+        .create_scheduled_transfer(
+            "Groceries".into(),
+            "Checking",
+            "Expense",
+            5000,
+            RecurrencePattern::Weekly,
+            Utc::now(),
+            None,
+            None,
+            Some("food".into()),
+        )
+        .await?;
+    service
+        .execute_scheduled_transfer("Groceries", None, true)
+        .await?;
+    let events = service.take_budget_events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].threshold, 100);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_alerts_delivered_to_file() -> Result<()> {
+    let (service, temp) = test_service().await?;
+    alerting_budget(&service).await?;
+
+    let path = temp.path().join("alerts.jsonl");
+    let hooks = service
+        .set_budget_alert_hooks(BudgetAlertHooks {
+            command: Some(" ".into()),
+            file: Some(path.to_string_lossy().into_owned()),
+        })
+        .await?;
+    assert_eq!(hooks.command, None);
+    assert_eq!(service.get_budget_alert_hooks().await?, hooks);
+
+    StandardWallets::spend(&service, 45000, Utc::now(), "food").await?;
+    let events = service.take_budget_events();
+    assert_eq!(events.len(), 2);
+    assert!(events.iter().all(|e| e.delivery_error.is_none()));
+
+    let lines: Vec<serde_json::Value> = std::fs::read_to_string(&path)?
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["budget"], "food");
+    assert_eq!(lines[0]["threshold"], 80);
+    assert_eq!(lines[1]["threshold"], 100);
+    assert_eq!(lines[1]["spent"], 45000);
+
+    service
+        .set_budget_alert_hooks(BudgetAlertHooks::default())
+        .await?;
+    assert_eq!(
+        service.get_budget_alert_hooks().await?,
+        BudgetAlertHooks::default()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_failing_hook_keeps_the_transfer() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    alerting_budget(&service).await?;
+    service
+        .set_budget_alert_hooks(BudgetAlertHooks {
+            command: Some("exit 3".into()),
+            file: None,
+        })
+        .await?;
+
+    StandardWallets::spend(&service, 35000, Utc::now(), "food").await?;
+    let events = service.take_budget_events();
+    assert_eq!(events.len(), 1);
+    assert!(events[0].delivery_error.is_some());
+    assert!(service.take_budget_alert_errors().is_empty());
+    assert_eq!(service.get_budget_status("food").await?.spent, 35000);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_alert_thresholds_are_validated() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    alerting_budget(&service).await?;
+
+    assert!(matches!(
+        service
+            .set_budget_alert_thresholds("food", vec![50, 0])
+            .await,
+        Err(AppError::InvalidBudget(_))
+    ));
+    let budget = service.set_budget_alert_thresholds("food", vec![]).await?;
+    assert!(budget.alert_thresholds.is_empty());
+    assert!(
+        service
+            .get_budget("food")
+            .await?
+            .alert_thresholds
+            .is_empty()
+    );
+
+    StandardWallets::spend(&service, 45000, Utc::now(), "food").await?;
+    assert!(service.take_budget_events().is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use anyhow::Result;
+use chrono::Utc;
+use common::{StandardWallets, test_service};
+use pecunio::application::{AppError, BudgetRequest, LedgerService};
+use pecunio::domain::{PeriodType, TagMatch, WalletType};
+
+/// Dining, games and concerts paid from checking or the credit card, some of
+/// it on a trip.
+async fn fun_spending(service: &LedgerService) -> Result<()> {
+    StandardWallets::create_basic(service).await?;
+    service
+        .create_wallet("Card".into(), WalletType::Liability, "EUR".into(), None)
+        .await?;
+    StandardWallets::fund_checking_now(service, 100000).await?;
+    let spending: [(&str, i64, &str, &[&str]); 4] = [
+        ("Checking", 4000, "dining:pizza", &[]),
+        ("Card", 6000, "games", &["trip"]),
+        ("Card", 2500, "concerts", &["trip", "holiday"]),
+        ("Card", 9000, "groceries", &[]),
+    ];
+    for (from, amount, category, tags) in spending {
+        StandardWallets::spend_from(service, from, amount, Utc::now(), category, tags).await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_budget_spanning_several_categories() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    fun_spending(&service).await?;
+
+    let budget = service
+        .create_budget_with(
+            BudgetRequest::new("Fun money", "dining", 20000, PeriodType::Monthly)
+                .with_categories(vec!["games".into(), "concerts".into(), "dining".into()]),
+        )
+        .await?;
+    // The budget's own category is not repeated in its filter
+    assert_eq!(budget.filter.categories, vec!["games", "concerts"]);
+    assert_eq!(budget.categories(), vec!["dining", "games", "concerts"]);
+
+    let status = service.get_budget_status("Fun money").await?;
+    assert_eq!(status.spent, 12500);
+    assert_eq!(status.remaining, 7500);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_budget_scoped_by_wallets_and_tags() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    fun_spending(&service).await?;
+
+    service
+        .create_budget_with(
+            BudgetRequest::new("Credit card", "", 50000, PeriodType::Monthly)
+                .with_from_wallets(vec!["Card".into()]),
+        )
+        .await?;
+    service
+        .create_budget_with(
+            BudgetRequest::new("Trip", "", 10000, PeriodType::Monthly)
+                .with_tags(vec!["Trip".into()], TagMatch::Any),
+        )
+        .await?;
+    service
+        .create_budget_with(
+            BudgetRequest::new("Holiday fun", "", 10000, PeriodType::Monthly)
+                .with_tags(vec!["trip".into(), "holiday".into()], TagMatch::All)
+                .with_from_wallets(vec!["Card".into()])
+                .with_to_wallets(vec!["Expense".into()]),
+        )
+        .await?;
+
+    let spent = |name: &str, statuses: &[pecunio::application::BudgetStatus]| {
+        statuses
+            .iter()
+            .find(|s| s.budget.name == name)
+            .map(|s| s.spent)
+    };
+    let statuses = service.get_all_budget_statuses().await?;
+    assert_eq!(spent("Credit card", &statuses), Some(17500));
+    assert_eq!(spent("Trip", &statuses), Some(8500));
+    assert_eq!(spent("Holiday fun", &statuses), Some(2500));
+
+    // The filter survives a round trip through the database
+    let card = service.get_wallet("Card").await?;
+    let budget = service.get_budget("Credit card").await?;
+    assert_eq!(budget.category, "");
+    assert_eq!(budget.filter.from_wallets, vec![card.id]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_budget_needs_a_scope() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+
+    assert!(matches!(
+        service
+            .create_budget_with(BudgetRequest::new("All", " ", 10000, PeriodType::Monthly))
+            .await,
+        Err(AppError::InvalidBudget(_))
+    ));
+    assert!(matches!(
+        service
+            .create_budget_with(
+                BudgetRequest::new("Card", "", 10000, PeriodType::Monthly)
+                    .with_from_wallets(vec!["Missing".into()])
+            )
+            .await,
+        Err(AppError::WalletNotFound(_))
+    ));
+    Ok(())
+}
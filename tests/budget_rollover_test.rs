@@ -0,0 +1,95 @@
+mod common;
+
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Months, NaiveDate, Utc};
+use common::{StandardWallets, food_budget, test_service};
+use pecunio::application::LedgerService;
+use pecunio::domain::{RolloverPolicy, start_of_day};
+
+/// First day of the month `back` months before the current one
+fn month_ago(back: u32) -> NaiveDate {
+    let today = Utc::now().date_naive();
+    today.with_day(1).unwrap() - Months::new(back)
+}
+
+fn mid_month(back: u32) -> DateTime<Utc> {
+    start_of_day(month_ago(back).with_day(10).unwrap())
+}
+
+/// A 400.00 monthly food budget rolling over since three months ago, with
+/// 300.00, 550.00 and 100.00 spent in those months.
+async fn food_history(service: &LedgerService, rollover: RolloverPolicy) -> Result<()> {
+    StandardWallets::create_basic(service).await?;
+    StandardWallets::fund_checking(service, 500000, start_of_day(month_ago(4))).await?;
+    food_budget(service, |request| {
+        request.with_rollover(rollover, Some(month_ago(3)))
+    })
+    .await?;
+    for (back, amount) in [(3, 30000), (2, 55000), (1, 10000)] {
+        StandardWallets::spend(service, amount, mid_month(back), "food").await?;
+    }
+    // Spending from before the rollover start is left out
+    StandardWallets::spend(service, 90000, mid_month(4), "food").await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rollover_policies_set_effective_limit() -> Result<()> {
+    for (policy, carried) in [
+        (RolloverPolicy::None, 0),
+        (RolloverPolicy::CarryPositive, 30000),
+        (RolloverPolicy::CarryBoth, 25000),
+    ] {
+        let (service, _temp) = test_service().await?;
+        food_history(&service, policy).await?;
+
+        let status = service.get_budget_status("food").await?;
+        assert_eq!(status.carried, carried, "{}", policy);
+        assert_eq!(status.effective_limit(), 40000 + carried);
+        assert_eq!(status.spent, 0);
+        assert_eq!(status.remaining, 40000 + carried);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rollover_history_and_policy_change() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    food_history(&service, RolloverPolicy::CarryBoth).await?;
+
+    let history = service.get_budget_rollover_history("food").await?;
+    let lines: Vec<(NaiveDate, i64, i64, i64)> = history
+        .iter()
+        .map(|c| {
+            (
+                c.period_start.date_naive(),
+                c.carried_in,
+                c.spent,
+                c.carried_out,
+            )
+        })
+        .collect();
+    assert_eq!(
+        lines,
+        vec![
+            (month_ago(3), 0, 30000, 10000),
+            (month_ago(2), 10000, 55000, -5000),
+            (month_ago(1), -5000, 10000, 25000),
+        ]
+    );
+
+    // Starting later leaves the earlier periods out
+    let budget = service
+        .set_budget_rollover("food", RolloverPolicy::CarryPositive, Some(month_ago(1)))
+        .await?;
+    assert_eq!(budget.rollover, RolloverPolicy::CarryPositive);
+    assert_eq!(
+        service.get_budget("food").await?.rollover_since,
+        Some(month_ago(1))
+    );
+    let history = service.get_budget_rollover_history("food").await?;
+    assert_eq!(history.len(), 1);
+    assert_eq!(service.get_budget_status("food").await?.carried, 30000);
+
+    Ok(())
+}
@@ -0,0 +1,190 @@
+mod common;
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use common::{StandardWallets, parse_date, parse_day, test_service};
+use pecunio::application::{AppError, BudgetRequest};
+use pecunio::domain::{PeriodType, RolloverPolicy};
+use sqlx::SqlitePool;
+use tempfile::TempDir;
+
+/// Pretend a budget was created on `day`, as a report only covers the periods since
+async fn backdate_budget(temp: &TempDir, name: &str, day: &str) -> Result<()> {
+    let db_path = temp.path().join("test.db");
+    let pool = SqlitePool::connect(&format!("sqlite:{}", db_path.to_str().unwrap())).await?;
+    sqlx::query("UPDATE budgets SET created_at = ? WHERE name = ?")
+        .bind(parse_date(day).to_rfc3339())
+        .bind(name)
+        .execute(&pool)
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_budget_variance_per_period() -> Result<()> {
+    let (service, temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    StandardWallets::fund_checking(&service, 500000, parse_date("2023-12-01")).await?;
+    service
+        .create_budget("food".into(), "food".into(), 40000, PeriodType::Monthly)
+        .await?;
+    backdate_budget(&temp, "food", "2023-12-15").await?;
+    // The rollover start counts as the budget's first period
+    service
+        .create_budget_with(
+            BudgetRequest::new("fun", "fun", 10000, PeriodType::Monthly)
+                .with_rollover(RolloverPolicy::CarryPositive, Some(parse_day("2024-01-01"))),
+        )
+        .await?;
+    for (day, amount, category) in [
+        ("2024-01-10", 30000, "food"),
+        ("2024-02-10", 50000, "food:groceries"),
+        ("2024-03-10", 40000, "food"),
+        ("2024-01-20", 4000, "fun"),
+        ("2024-02-20", 15000, "fun"),
+    ] {
+        service
+            .record_transfer(
+                "Checking",
+                "Expense",
+                amount,
+                parse_date(day),
+                None,
+                Some(category.into()),
+                false,
+            )
+            .await?;
+    }
+
+    let report = service
+        .get_budget_variance_report(parse_day("2024-01-15"), parse_day("2024-03-31"), None)
+        .await?;
+    assert_eq!(report.budgets.len(), 2);
+
+    let food = &report.budgets[0];
+    let periods: Vec<(NaiveDate, i64, i64, i64)> = food
+        .periods
+        .iter()
+        .map(|p| (p.period_start.date_naive(), p.limit, p.actual, p.variance))
+        .collect();
+    assert_eq!(
+        periods,
+        vec![
+            (parse_day("2024-01-01"), 40000, 30000, 10000),
+            (parse_day("2024-02-01"), 40000, 50000, -10000),
+            (parse_day("2024-03-01"), 40000, 40000, 0),
+        ]
+    );
+    assert_eq!(food.periods[0].variance_percentage, 25.0);
+    assert_eq!(food.periods[1].variance_percentage, -25.0);
+    assert_eq!(food.periods_exceeded, 1);
+    assert_eq!(food.total_variance, 0);
+
+    // Limits include what rolled over: 60.00 left in January
+    let fun = &report.budgets[1];
+    assert_eq!(fun.periods[1].limit, 16000);
+    assert_eq!(fun.periods[1].variance, 1000);
+    assert_eq!(fun.periods_exceeded, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_budget_variance_for_one_budget() -> Result<()> {
+    let (service, temp) = test_service().await?;
+    service
+        .create_budget("food".into(), "food".into(), 40000, PeriodType::Weekly)
+        .await?;
+    backdate_budget(&temp, "food", "2023-12-01").await?;
+    service
+        .create_budget("fun".into(), "fun".into(), 10000, PeriodType::Monthly)
+        .await?;
+
+    // 2024-01-01 is a Monday; the week holding the 14th ends after it
+    let report = service
+        .get_budget_variance_report(
+            parse_day("2024-01-01"),
+            parse_day("2024-01-14"),
+            Some("food"),
+        )
+        .await?;
+    assert_eq!(report.budgets.len(), 1);
+    assert_eq!(report.budgets[0].periods.len(), 2);
+    assert_eq!(report.budgets[0].total_limit, 80000);
+
+    assert!(matches!(
+        service
+            .get_budget_variance_report(parse_day("2024-02-01"), parse_day("2024-01-01"), None)
+            .await,
+        Err(AppError::InvalidDateRange(_))
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_budget_variance_skips_periods_before_the_budget() -> Result<()> {
+    let (service, temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    StandardWallets::fund_checking(&service, 500000, parse_date("2024-01-01")).await?;
+    service
+        .create_budget("food".into(), "food".into(), 40000, PeriodType::Monthly)
+        .await?;
+    backdate_budget(&temp, "food", "2024-03-20").await?;
+    StandardWallets::spend(&service, 30000, parse_date("2024-02-10"), "food").await?;
+    StandardWallets::spend(&service, 10000, parse_date("2024-04-10"), "food").await?;
+
+    let report = service
+        .get_budget_variance_report(parse_day("2024-01-01"), parse_day("2024-04-30"), None)
+        .await?;
+    let food = &report.budgets[0];
+    let starts: Vec<NaiveDate> = food
+        .periods
+        .iter()
+        .map(|p| p.period_start.date_naive())
+        .collect();
+    assert_eq!(
+        starts,
+        vec![parse_day("2024-03-01"), parse_day("2024-04-01")]
+    );
+    assert_eq!(food.total_limit, 80000);
+    assert_eq!(food.total_actual, 10000);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_budget_variance_from_leap_day() -> Result<()> {
+    let (service, temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    StandardWallets::fund_checking(&service, 500000, parse_date("2024-01-01")).await?;
+    service
+        .create_budget("food".into(), "food".into(), 400000, PeriodType::Yearly)
+        .await?;
+    backdate_budget(&temp, "food", "2024-01-01").await?;
+    StandardWallets::spend(&service, 30000, parse_date("2024-02-29"), "food").await?;
+    StandardWallets::spend(&service, 50000, parse_date("2025-02-28"), "food").await?;
+
+    let report = service
+        .get_budget_variance_report(parse_day("2024-02-29"), parse_day("2025-12-31"), None)
+        .await?;
+    let periods: Vec<(NaiveDate, NaiveDate, i64)> = report.budgets[0]
+        .periods
+        .iter()
+        .map(|p| {
+            (
+                p.period_start.date_naive(),
+                p.period_end.date_naive(),
+                p.actual,
+            )
+        })
+        .collect();
+    assert_eq!(
+        periods,
+        vec![
+            (parse_day("2024-01-01"), parse_day("2025-01-01"), 30000),
+            (parse_day("2025-01-01"), parse_day("2026-01-01"), 50000),
+        ]
+    );
+
+    Ok(())
+}
@@ -280,3 +280,48 @@ async fn test_budget_only_counts_current_period() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_parent_budget_covers_sub_categories() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    StandardWallets::fund_checking_now(&service, 100000).await?;
+
+    for (amount, category) in [
+        (4000, "food:groceries"),
+        (2500, "food:restaurants:lunch"),
+        (1500, "food"),
+        (900, "foodbank"),
+    ] {
+        service
+            .record_transfer(
+                "Checking",
+                "Expense",
+                amount,
+                Utc::now(),
+                None,
+                Some(category.to_string()),
+                false,
+            )
+            .await?;
+    }
+
+    service
+        .create_budget("food".into(), "food".into(), 10000, PeriodType::Monthly)
+        .await?;
+    service
+        .create_budget(
+            "eating out".into(),
+            "food : restaurants".into(),
+            5000,
+            PeriodType::Monthly,
+        )
+        .await?;
+
+    assert_eq!(service.get_budget_status("food").await?.spent, 8000);
+    let eating_out = service.get_budget_status("eating out").await?;
+    assert_eq!(eating_out.budget.category, "food:restaurants");
+    assert_eq!(eating_out.spent, 2500);
+
+    Ok(())
+}
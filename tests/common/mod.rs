@@ -4,8 +4,8 @@
 
 use anyhow::Result;
 use chrono::{DateTime, NaiveDate, Utc};
-use pecunio::application::LedgerService;
-use pecunio::domain::WalletType;
+use pecunio::application::{BudgetRequest, LedgerService, TransferRequest};
+use pecunio::domain::{Budget, PeriodType, Rate, WalletType};
 use tempfile::TempDir;
 
 /// Helper to create a test service with a temporary database
@@ -25,6 +25,16 @@ pub fn parse_date(date_str: &str) -> DateTime<Utc> {
         .and_utc()
 }
 
+/// Helper to parse a date string into the NaiveDate of that day
+pub fn parse_day(date_str: &str) -> NaiveDate {
+    parse_date(date_str).date_naive()
+}
+
+/// Helper to parse a decimal exchange rate such as "1.085"
+pub fn rate(rate_str: &str) -> Rate {
+    rate_str.parse().unwrap()
+}
+
 /// Test fixture: Standard wallet setup
 pub struct StandardWallets;
 
@@ -85,4 +95,43 @@ impl StandardWallets {
     pub async fn fund_checking_now(service: &LedgerService, amount: i64) -> Result<()> {
         Self::fund_checking(service, amount, Utc::now()).await
     }
+
+    /// Spend from checking into the expense wallet under a category
+    pub async fn spend(
+        service: &LedgerService,
+        amount: i64,
+        date: DateTime<Utc>,
+        category: &str,
+    ) -> Result<()> {
+        Self::spend_from(service, "Checking", amount, date, category, &[]).await
+    }
+
+    /// Spend from any wallet into the expense wallet under a category, with tags
+    pub async fn spend_from(
+        service: &LedgerService,
+        from: &str,
+        amount: i64,
+        date: DateTime<Utc>,
+        category: &str,
+        tags: &[&str],
+    ) -> Result<()> {
+        service
+            .record_transfer_with(
+                TransferRequest::new(from, "Expense", amount, date)
+                    .with_category(Some(category.into()))
+                    .with_tags(tags.iter().map(|tag| tag.to_string()).collect()),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+/// Test fixture: a 400.00 monthly budget named "food" on the food category,
+/// with whatever else `configure` sets on the request
+pub async fn food_budget(
+    service: &LedgerService,
+    configure: impl FnOnce(BudgetRequest) -> BudgetRequest,
+) -> Result<Budget> {
+    let request = BudgetRequest::new("food", "food", 40000, PeriodType::Monthly);
+    Ok(service.create_budget_with(configure(request)).await?)
 }
@@ -0,0 +1,263 @@
+mod common;
+
+use anyhow::Result;
+use chrono::Utc;
+use common::{StandardWallets, parse_date, rate, test_service};
+use pecunio::application::{AppError, LedgerService, TransferRequest};
+use pecunio::domain::{PeriodType, Rate, WalletType};
+
+/// Basic EUR wallets plus a USD checking account and a USD expense wallet
+async fn setup_multi_currency(service: &LedgerService) -> Result<()> {
+    StandardWallets::create_basic(service).await?;
+    service
+        .create_wallet("US Checking".into(), WalletType::Asset, "USD".into(), None)
+        .await?;
+    service
+        .create_wallet("US Travel".into(), WalletType::Expense, "USD".into(), None)
+        .await?;
+    StandardWallets::fund_checking(service, 100000, parse_date("2024-01-01")).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_exchange_rate_lookup() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+
+    service
+        .add_exchange_rate("eur", "usd", rate("1.10"), parse_date("2024-01-01"))
+        .await?;
+    service
+        .add_exchange_rate("EUR", "USD", rate("1.20"), parse_date("2024-03-01"))
+        .await?;
+
+    // Latest rate effective on or before the date
+    let found = service
+        .get_exchange_rate("EUR", "USD", parse_date("2024-02-15"))
+        .await?;
+    assert_eq!(found, rate("1.1"));
+    let found = service
+        .get_exchange_rate("EUR", "USD", parse_date("2024-03-01"))
+        .await?;
+    assert_eq!(found, rate("1.2"));
+
+    // Inverse pair, held exactly
+    let found = service
+        .get_exchange_rate("USD", "EUR", parse_date("2024-03-10"))
+        .await?;
+    assert_eq!(found, rate("1.2").inverse());
+    assert_eq!(found.to_string(), "0.8333333333");
+
+    // Nothing before the first rate
+    let missing = service
+        .get_exchange_rate("EUR", "USD", parse_date("2023-12-31"))
+        .await;
+    assert!(matches!(
+        missing,
+        Err(AppError::ExchangeRateNotFound { .. })
+    ));
+
+    let invalid = service
+        .add_exchange_rate("EUR", "EUR", rate("1"), parse_date("2024-01-01"))
+        .await;
+    assert!(matches!(invalid, Err(AppError::InvalidExchangeRate(_))));
+    assert!("0".parse::<Rate>().is_err());
+
+    assert_eq!(service.list_exchange_rates().await?.len(), 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cross_currency_transfer() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    setup_multi_currency(&service).await?;
+
+    // Without a stored rate the transfer is rejected
+    let result = service
+        .record_transfer(
+            "Checking",
+            "US Checking",
+            10000,
+            parse_date("2024-02-01"),
+            None,
+            None,
+            false,
+        )
+        .await;
+    assert!(matches!(result, Err(AppError::ExchangeRateNotFound { .. })));
+
+    // Explicit rate
+    let result = service
+        .record_transfer_with(
+            TransferRequest::new("Checking", "US Checking", 10000, parse_date("2024-02-01"))
+                .with_exchange_rate(Some(rate("1.085"))),
+        )
+        .await?;
+    let conversion = result.converted.as_ref().unwrap();
+    assert_eq!(conversion.amount_cents, 10850);
+    assert_eq!(conversion.currency, "USD");
+    assert_eq!(result.transfer.split_id, conversion.leg.split_id);
+
+    // Stored rate
+    service
+        .add_exchange_rate("EUR", "USD", rate("1.10"), parse_date("2024-01-01"))
+        .await?;
+    service
+        .record_transfer(
+            "Checking",
+            "US Travel",
+            5000,
+            parse_date("2024-02-02"),
+            Some("Hotel".to_string()),
+            Some("travel".to_string()),
+            false,
+        )
+        .await?;
+
+    assert_eq!(service.get_balance("Checking").await?.balance, 85000);
+    assert_eq!(service.get_balance("US Checking").await?.balance, 10850);
+    assert_eq!(service.get_balance("US Travel").await?.balance, 5500);
+    assert_eq!(service.get_balance("Exchange EUR").await?.balance, 15000);
+    assert_eq!(service.get_balance("Exchange USD").await?.balance, -16350);
+
+    // The category lands on the incoming leg, in the destination currency
+    let report = service
+        .get_category_report(parse_date("2024-02-01"), parse_date("2024-03-01"))
+        .await?;
+    assert_eq!(report.categories.len(), 1);
+    assert_eq!(report.categories[0].total, 5500);
+
+    assert!(service.check_integrity().await?.is_healthy());
+
+    // Reversing either leg reverses the whole conversion
+    let reversal = service.reverse_transfer(result.transfer.id, None).await?;
+    assert_eq!(reversal.split_reversals.len(), 2);
+    assert_eq!(service.get_balance("US Checking").await?.balance, 0);
+    assert_eq!(service.get_balance("Checking").await?.balance, 95000);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_conversions_round_exact_rates() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    setup_multi_currency(&service).await?;
+    service
+        .add_exchange_rate("EUR", "USD", rate("1.005"), parse_date("2024-01-01"))
+        .await?;
+    assert_eq!(
+        service.list_exchange_rates().await?[0].rate.to_string(),
+        "1.005"
+    );
+
+    // 1.00 EUR is exactly 1.005 USD, rounded half up (floats make it 100.4999...)
+    let result = service
+        .record_transfer(
+            "Checking",
+            "US Checking",
+            100,
+            parse_date("2024-02-01"),
+            None,
+            None,
+            false,
+        )
+        .await?;
+    assert_eq!(result.converted.unwrap().amount_cents, 101);
+
+    // The inverse of a stored rate converts back without drift
+    service
+        .record_transfer_with(
+            TransferRequest::new("US Checking", "Checking", 100500, parse_date("2024-02-02"))
+                .with_force(true),
+        )
+        .await?;
+    assert_eq!(service.get_balance("Checking").await?.balance, 199900);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_same_currency_rejects_rate() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    setup_multi_currency(&service).await?;
+
+    let result = service
+        .record_transfer_with(
+            TransferRequest::new("Checking", "Savings", 1000, Utc::now())
+                .with_exchange_rate(Some(rate("1.1"))),
+        )
+        .await;
+    assert!(matches!(result, Err(AppError::InvalidExchangeRate(_))));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_converted_reports() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    setup_multi_currency(&service).await?;
+    service
+        .add_exchange_rate("EUR", "USD", rate("1.25"), parse_date("2024-01-01"))
+        .await?;
+
+    service
+        .create_wallet("Food".into(), WalletType::Expense, "EUR".into(), None)
+        .await?;
+    service
+        .record_transfer(
+            "Checking",
+            "Food",
+            2000,
+            parse_date("2024-02-05"),
+            None,
+            Some("food".to_string()),
+            false,
+        )
+        .await?;
+    service
+        .record_transfer(
+            "Checking",
+            "US Travel",
+            4000,
+            parse_date("2024-02-06"),
+            None,
+            Some("food".to_string()),
+            false,
+        )
+        .await?;
+
+    // 20.00 EUR + 50.00 USD (40.00 EUR at 1.25)
+    let report = service
+        .get_category_report_converted(parse_date("2024-02-01"), parse_date("2024-03-01"), "EUR")
+        .await?;
+    assert_eq!(report.currency.as_deref(), Some("EUR"));
+    assert_eq!(report.categories.len(), 1);
+    assert_eq!(report.categories[0].total, 6000);
+    assert_eq!(report.categories[0].count, 2);
+
+    // Net worth: 940.00 EUR checking
+    let net_worth = service.get_net_worth_report_converted("USD").await?;
+    assert_eq!(net_worth.total_assets, 117500);
+    assert_eq!(net_worth.currency.as_deref(), Some("USD"));
+
+    // Cash flow: income of 1000.00 EUR in January, converted to USD
+    let cashflow = service
+        .get_cashflow_report_converted(
+            parse_date("2024-01-01"),
+            parse_date("2024-02-01"),
+            PeriodType::Monthly,
+            "USD",
+        )
+        .await?;
+    assert_eq!(cashflow.periods.len(), 1);
+    assert_eq!(cashflow.periods[0].inflow, 125000);
+
+    // A missing rate is an error, not a silent mixed-currency sum
+    let missing = service.get_net_worth_report_converted("GBP").await;
+    assert!(matches!(
+        missing,
+        Err(AppError::ExchangeRateNotFound { .. })
+    ));
+
+    Ok(())
+}
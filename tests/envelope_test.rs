@@ -0,0 +1,160 @@
+mod common;
+
+use anyhow::Result;
+use common::{StandardWallets, parse_date, parse_day, test_service};
+use pecunio::application::{AppError, EnvelopeStatus, LedgerService};
+
+/// (assigned, activity, available) of an envelope
+fn line(status: &EnvelopeStatus, name: &str) -> (i64, i64, i64) {
+    let envelope = status
+        .envelopes
+        .iter()
+        .find(|e| e.envelope.name == name)
+        .expect("envelope in status");
+    (envelope.assigned, envelope.activity, envelope.available)
+}
+
+/// A January salary of 3,000.00 split between groceries and rent, with
+/// groceries overspent in February.
+async fn budget_january(service: &LedgerService) -> Result<()> {
+    StandardWallets::create_basic(service).await?;
+    StandardWallets::fund_checking(service, 300000, parse_date("2024-01-05")).await?;
+    for (name, category) in [("Groceries", "food"), ("Rent", "housing")] {
+        service
+            .create_envelope(
+                name.into(),
+                category.into(),
+                "EUR".into(),
+                parse_day("2024-01-01"),
+            )
+            .await?;
+    }
+    service
+        .assign_to_envelope("Groceries", parse_day("2024-01-01"), 40000)
+        .await?;
+    service
+        .assign_to_envelope("Rent", parse_day("2024-01-01"), 120000)
+        .await?;
+    StandardWallets::spend(service, 120000, parse_date("2024-01-01"), "housing").await?;
+    StandardWallets::spend(service, 35000, parse_date("2024-01-10"), "food:groceries").await?;
+    StandardWallets::spend(service, 8000, parse_date("2024-02-03"), "food").await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_envelopes_roll_over_between_months() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    budget_january(&service).await?;
+
+    let january = service.get_envelope_status(parse_day("2024-01-31")).await?;
+    assert_eq!(january.len(), 1);
+    let january = &january[0];
+    assert_eq!(january.month, parse_day("2024-01-01"));
+    assert_eq!(january.to_be_assigned, 140000);
+    assert_eq!(line(january, "Groceries"), (40000, -35000, 5000));
+    assert_eq!(line(january, "Rent"), (120000, -120000, 0));
+
+    // What was left in January is spent in February, and more
+    let february = &service.get_envelope_status(parse_day("2024-02-01")).await?[0];
+    assert_eq!(february.to_be_assigned, 140000);
+    assert_eq!(line(february, "Groceries"), (0, -8000, -3000));
+    let groceries = &february.envelopes[0];
+    assert_eq!(groceries.carried_over(), 5000);
+    assert!(groceries.is_overspent());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_assign_and_move_between_envelopes() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    budget_january(&service).await?;
+
+    service
+        .assign_to_envelope("Rent", parse_day("2024-02-01"), 120000)
+        .await?;
+    let (taken, given) = service
+        .move_between_envelopes("Rent", "Groceries", parse_day("2024-02-15"), 3000)
+        .await?;
+    assert_eq!(taken.amount_cents, -3000);
+    assert_eq!(given.amount_cents, 3000);
+    assert_eq!(given.counterpart, Some(taken.envelope_id));
+
+    let february = &service.get_envelope_status(parse_day("2024-02-01")).await?[0];
+    assert_eq!(february.to_be_assigned, 20000);
+    assert_eq!(line(february, "Groceries"), (3000, -8000, 0));
+    assert_eq!(line(february, "Rent"), (117000, 0, 117000));
+    // Later assignments leave the earlier months alone
+    let january = &service.get_envelope_status(parse_day("2024-01-01")).await?[0];
+    assert_eq!(january.to_be_assigned, 140000);
+
+    // Money taken back returns to the pool
+    service
+        .assign_to_envelope("Rent", parse_day("2024-02-01"), -17000)
+        .await?;
+    service.delete_envelope("Groceries").await?;
+    let february = &service.get_envelope_status(parse_day("2024-02-01")).await?[0];
+    assert_eq!(february.envelopes.len(), 1);
+    assert_eq!(february.to_be_assigned, 300000 - 120000 - 100000);
+    assert_eq!(line(february, "Rent"), (100000, 0, 100000));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_invalid_envelope_operations() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    budget_january(&service).await?;
+    service
+        .create_envelope(
+            "Travel".into(),
+            "travel".into(),
+            "USD".into(),
+            parse_day("2024-03-01"),
+        )
+        .await?;
+
+    assert!(matches!(
+        service
+            .create_envelope(
+                "Rent".into(),
+                "housing".into(),
+                "EUR".into(),
+                parse_day("2024-01-01")
+            )
+            .await,
+        Err(AppError::EnvelopeAlreadyExists(_))
+    ));
+    assert!(matches!(
+        service
+            .move_between_envelopes("Groceries", "Travel", parse_day("2024-03-01"), 1000)
+            .await,
+        Err(AppError::InvalidEnvelope(_))
+    ));
+    assert!(matches!(
+        service
+            .move_between_envelopes("Rent", "Rent", parse_day("2024-03-01"), 1000)
+            .await,
+        Err(AppError::InvalidEnvelope(_))
+    ));
+    assert!(matches!(
+        service
+            .assign_to_envelope("Travel", parse_day("2024-02-01"), 1000)
+            .await,
+        Err(AppError::InvalidEnvelope(_))
+    ));
+    assert!(matches!(
+        service
+            .assign_to_envelope("Missing", parse_day("2024-03-01"), 1000)
+            .await,
+        Err(AppError::EnvelopeNotFound(_))
+    ));
+
+    // Each currency has its own pool
+    let statuses = service.get_envelope_status(parse_day("2024-03-01")).await?;
+    assert_eq!(statuses.len(), 2);
+    assert_eq!(statuses[1].currency, "USD");
+    assert_eq!(statuses[1].to_be_assigned, 0);
+
+    Ok(())
+}
@@ -0,0 +1,226 @@
+mod common;
+
+use anyhow::Result;
+use common::{StandardWallets, parse_date, rate, test_service};
+use pecunio::application::{AppError, LedgerService, TradeRequest};
+
+/// Checking funded with 10,000.00 EUR and a "Broker VWCE" wallet holding units
+/// counted with two decimals.
+async fn setup_holding(service: &LedgerService) -> Result<()> {
+    StandardWallets::create_basic(service).await?;
+    StandardWallets::fund_checking(service, 1_000_000, parse_date("2024-01-01")).await?;
+    service
+        .create_commodity_wallet("Broker VWCE".into(), "vwce".into(), "EUR".into(), None)
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_buy_and_sell_move_units_and_cash() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    setup_holding(&service).await?;
+
+    // 10.00 units at 100.00 = 1,000.00 EUR
+    let buy = service
+        .record_buy(TradeRequest::new(
+            "Broker VWCE",
+            "Checking",
+            1000,
+            100.0,
+            parse_date("2024-02-01"),
+        ))
+        .await?;
+    assert_eq!(buy.transfer.amount_cents, 100000);
+    assert_eq!(buy.transfer.quantity, Some(1000));
+    assert_eq!(buy.from_wallet_name, "Checking");
+    assert_eq!(service.get_balance("Checking").await?.balance, 900000);
+
+    // 4.00 units at 110.00 = 440.00 EUR of proceeds
+    let sell = service
+        .record_sell(TradeRequest::new(
+            "Broker VWCE",
+            "Checking",
+            400,
+            110.0,
+            parse_date("2024-03-01"),
+        ))
+        .await?;
+    assert_eq!(sell.trade.transfer.amount_cents, 44000);
+    assert_eq!(sell.trade.from_wallet_name, "Broker VWCE");
+    assert_eq!(service.get_balance("Checking").await?.balance, 944000);
+    assert_eq!(
+        service.get_wallet_info("Broker VWCE").await?.quantity,
+        Some(600)
+    );
+
+    // Cannot sell more than is held
+    let oversell = service
+        .record_sell(TradeRequest::new(
+            "Broker VWCE",
+            "Checking",
+            700,
+            110.0,
+            parse_date("2024-03-02"),
+        ))
+        .await;
+    assert!(matches!(
+        oversell,
+        Err(AppError::InsufficientUnits {
+            held: 600,
+            required: 700,
+            ..
+        })
+    ));
+
+    // Units only move by trading
+    let plain = service
+        .record_transfer(
+            "Checking",
+            "Broker VWCE",
+            1000,
+            parse_date("2024-03-03"),
+            None,
+            None,
+            false,
+        )
+        .await;
+    assert!(matches!(plain, Err(AppError::InvalidTrade(_))));
+
+    // Each trade recorded its price
+    let prices = service.list_prices(Some("VWCE")).await?;
+    assert_eq!(prices.len(), 2);
+    assert!(service.check_integrity().await?.is_healthy());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_holdings_report_and_net_worth_use_latest_price() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    setup_holding(&service).await?;
+
+    service
+        .record_buy(TradeRequest::new(
+            "Broker VWCE",
+            "Checking",
+            1000,
+            100.0,
+            parse_date("2024-02-01"),
+        ))
+        .await?;
+    service
+        .record_buy(TradeRequest::new(
+            "Broker VWCE",
+            "Checking",
+            1000,
+            120.0,
+            parse_date("2024-02-15"),
+        ))
+        .await?;
+    // Selling half consumes the first lot (FIFO), leaving the 1,200.00 EUR one
+    service
+        .record_sell(TradeRequest::new(
+            "Broker VWCE",
+            "Checking",
+            1000,
+            115.0,
+            parse_date("2024-03-01"),
+        ))
+        .await?;
+    service
+        .add_price("VWCE", "EUR", 125.5, parse_date("2024-04-01"))
+        .await?;
+
+    let report = service.get_holdings_report().await?;
+    assert_eq!(report.holdings.len(), 1);
+    let holding = &report.holdings[0];
+    assert_eq!(holding.commodity, "VWCE");
+    assert_eq!(holding.quantity, 1000);
+    assert_eq!(holding.price, Some(125.5));
+    assert_eq!(holding.market_value, 125500);
+    assert_eq!(holding.cost_basis, 120000);
+    assert_eq!(holding.unrealized_gain, 5500);
+    assert_eq!(report.total_unrealized_gain, 5500);
+
+    // Cash: 10,000 - 1,000 - 1,200 + 1,150 = 8,950; holding at 1,255.00
+    let net_worth = service.get_net_worth_report().await?;
+    assert_eq!(net_worth.total_assets, 895000 + 125500);
+    let broker = net_worth
+        .assets
+        .iter()
+        .find(|a| a.wallet_name == "Broker VWCE")
+        .unwrap();
+    assert_eq!(broker.balance, 125500);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reversed_buy_leaves_no_holding() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    setup_holding(&service).await?;
+
+    let buy = service
+        .record_buy(TradeRequest::new(
+            "Broker VWCE",
+            "Checking",
+            500,
+            100.0,
+            parse_date("2024-02-01"),
+        ))
+        .await?;
+
+    let partial = service.reverse_transfer(buy.transfer.id, Some(100)).await;
+    assert!(matches!(partial, Err(AppError::InvalidTrade(_))));
+
+    let reversal = service.reverse_transfer(buy.transfer.id, None).await?;
+    assert_eq!(reversal.reversal.quantity, Some(500));
+    assert_eq!(
+        service.get_wallet_info("Broker VWCE").await?.quantity,
+        Some(0)
+    );
+    assert_eq!(service.get_balance("Checking").await?.balance, 1_000_000);
+    assert!(service.get_holdings_report().await?.holdings.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_price_in_other_currency_is_converted() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    setup_holding(&service).await?;
+    service.register_currency("VWCE", 4).await?;
+
+    // 2.5 units at 100.00 EUR
+    service
+        .record_buy(TradeRequest::new(
+            "Broker VWCE",
+            "Checking",
+            25000,
+            100.0,
+            parse_date("2024-02-01"),
+        ))
+        .await?;
+    service
+        .add_exchange_rate("EUR", "USD", rate("1.25"), parse_date("2024-01-01"))
+        .await?;
+    service
+        .add_price("VWCE", "USD", 150.0, parse_date("2024-05-01"))
+        .await?;
+
+    let price = service
+        .get_price("VWCE", "EUR", parse_date("2024-06-01"))
+        .await?;
+    assert!((price.price - 120.0).abs() < 1e-9);
+
+    let report = service.get_holdings_report().await?;
+    assert_eq!(report.holdings[0].quantity_decimals, 4);
+    assert_eq!(report.holdings[0].market_value, 30000);
+
+    let missing = service
+        .get_price("GOLD", "EUR", parse_date("2024-06-01"))
+        .await;
+    assert!(matches!(missing, Err(AppError::PriceNotFound { .. })));
+
+    Ok(())
+}
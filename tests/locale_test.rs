@@ -0,0 +1,115 @@
+mod common;
+
+use anyhow::Result;
+use common::{StandardWallets, parse_date, test_service};
+use pecunio::application::AppError;
+use pecunio::domain::{LocaleProfile, NegativeStyle, SymbolPlacement};
+use pecunio::io::{Exporter, ImportOptions, Importer};
+
+#[tokio::test]
+async fn test_locale_profiles_and_default() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+
+    // Nothing configured: callers fall back to the plain format
+    assert!(service.get_default_locale().await?.is_none());
+
+    let de = service.get_locale("de-de").await?;
+    assert_eq!(de.name, "de-DE");
+    assert_eq!(de.decimal_separator, ',');
+
+    let custom = LocaleProfile::new("de-AT".into(), ',')
+        .with_grouping(Some(' '))
+        .with_symbol(SymbolPlacement::Before, true)
+        .with_negative_style(NegativeStyle::Parentheses);
+    service.save_locale(custom.clone()).await?;
+    assert_eq!(service.get_locale("de-AT").await?, custom);
+    assert!(service.list_locales().await?.contains(&custom));
+
+    let builtin = service
+        .save_locale(LocaleProfile::new("en-US".into(), ','))
+        .await;
+    assert!(matches!(builtin, Err(AppError::InvalidLocale(_))));
+    let ambiguous = service
+        .save_locale(LocaleProfile::new("odd".into(), ',').with_grouping(Some(',')))
+        .await;
+    assert!(matches!(ambiguous, Err(AppError::InvalidLocale(_))));
+
+    service.set_default_locale(Some("de-AT")).await?;
+    assert_eq!(service.get_default_locale().await?, Some(custom));
+    service.set_default_locale(None).await?;
+    assert!(service.get_default_locale().await?.is_none());
+
+    let missing = service.set_default_locale(Some("xx-XX")).await;
+    assert!(matches!(missing, Err(AppError::LocaleNotFound(_))));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_localized_csv_round_trip() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    StandardWallets::fund_checking(&service, 123456, parse_date("2024-01-01")).await?;
+    service
+        .record_transfer(
+            "Checking",
+            "Expense",
+            1250,
+            parse_date("2024-01-02"),
+            Some("Lunch".to_string()),
+            None,
+            false,
+        )
+        .await?;
+
+    let de = service.get_locale("de-DE").await?;
+    let mut csv = Vec::new();
+    Exporter::new(&service)
+        .with_locale(Some(de.clone()))
+        .export_transfers_csv(&mut csv)
+        .await?;
+    let csv = String::from_utf8(csv)?;
+    assert!(csv.lines().next().unwrap().contains(",amount,"));
+    assert!(csv.contains("\"1.234,56\""));
+    assert!(csv.contains("\"12,50\""));
+
+    // Read back into a fresh ledger with the same locale
+    let (target, _target_temp) = test_service().await?;
+    StandardWallets::create_basic(&target).await?;
+    let options = ImportOptions {
+        locale: Some(de),
+        ..Default::default()
+    };
+    let result = Importer::new(&target)
+        .import_transfers_csv(csv.as_bytes(), options)
+        .await?;
+    assert_eq!(result.imported, 2);
+    assert!(result.errors.is_empty());
+    assert_eq!(target.get_balance("Checking").await?.balance, 122206);
+
+    // Without the locale the same file is rejected rather than misread
+    let result = Importer::new(&target)
+        .import_transfers_csv(csv.as_bytes(), ImportOptions::default())
+        .await?;
+    assert_eq!(result.imported, 0);
+    assert_eq!(result.errors.len(), 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_json_export_stays_canonical() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    StandardWallets::fund_checking(&service, 123456, parse_date("2024-01-01")).await?;
+
+    let mut json = Vec::new();
+    Exporter::new(&service)
+        .with_locale(Some(service.get_locale("de-DE").await?))
+        .export_full_json(&mut json)
+        .await?;
+    let json: serde_json::Value = serde_json::from_slice(&json)?;
+    assert_eq!(json["transfers"][0]["amount_cents"], 123456);
+
+    Ok(())
+}
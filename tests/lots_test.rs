@@ -0,0 +1,175 @@
+mod common;
+
+use anyhow::Result;
+use common::{StandardWallets, parse_date, test_service};
+use pecunio::application::{AppError, LedgerService, TradeRequest};
+use pecunio::domain::{LotMethod, LotPick};
+use pecunio::io::Exporter;
+
+/// Checking funded with 10,000.00 EUR and two lots in "Broker VWCE":
+/// 10.00 units at 100.00 and 10.00 units at 120.00.
+async fn setup_lots(service: &LedgerService) -> Result<()> {
+    StandardWallets::create_basic(service).await?;
+    StandardWallets::fund_checking(service, 1_000_000, parse_date("2024-01-01")).await?;
+    service
+        .create_commodity_wallet("Broker VWCE".into(), "VWCE".into(), "EUR".into(), None)
+        .await?;
+    for (price, date) in [(100.0, "2024-02-01"), (120.0, "2024-02-15")] {
+        service
+            .record_buy(TradeRequest::new(
+                "Broker VWCE",
+                "Checking",
+                1000,
+                price,
+                parse_date(date),
+            ))
+            .await?;
+    }
+    Ok(())
+}
+
+fn sale(quantity: i64, price: f64, date: &str) -> TradeRequest {
+    TradeRequest::new("Broker VWCE", "Checking", quantity, price, parse_date(date))
+}
+
+#[tokio::test]
+async fn test_fifo_and_lifo_book_realized_gains() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    setup_lots(&service).await?;
+
+    // FIFO (the default): 5.00 units of the 100.00 lot sold at 110.00
+    let fifo = service.record_sell(sale(500, 110.0, "2024-03-01")).await?;
+    assert_eq!(fifo.cost_basis, 50000);
+    assert_eq!(fifo.realized_gain, 5000);
+    assert_eq!(fifo.disposals.len(), 1);
+    assert_eq!(fifo.disposals[0].method, LotMethod::Fifo);
+    let leg = fifo.gain_leg.expect("gain leg");
+    assert_eq!(leg.split_id, fifo.trade.transfer.split_id);
+    assert_eq!(
+        service.get_balance("Realized Gains EUR").await?.balance,
+        -5000
+    );
+
+    // LIFO: 5.00 units of the 120.00 lot sold at 110.00 is a loss
+    let lifo = service
+        .record_sell(sale(500, 110.0, "2024-03-02").with_lot_method(Some(LotMethod::Lifo)))
+        .await?;
+    assert_eq!(lifo.cost_basis, 60000);
+    assert_eq!(lifo.realized_gain, -5000);
+    assert_eq!(
+        service.get_balance("Realized Losses EUR").await?.balance,
+        5000
+    );
+
+    // The holding is carried at the cost of what remains in its lots
+    let lots = service.list_lots("Broker VWCE").await?;
+    assert_eq!(lots.len(), 2);
+    assert_eq!(lots[0].remaining_quantity, 500);
+    assert_eq!(lots[1].remaining_quantity, 500);
+    assert_eq!(service.get_balance("Broker VWCE").await?.balance, 110000);
+    assert_eq!(
+        service.get_holdings_report().await?.total_cost_basis,
+        110000
+    );
+    assert!(service.check_integrity().await?.is_healthy());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_specific_lots_and_average_cost() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    setup_lots(&service).await?;
+    let lots = service.list_lots("Broker VWCE").await?;
+
+    // Picking lots implies specific identification
+    let specific = service
+        .record_sell(sale(300, 130.0, "2024-03-01").with_lots(vec![
+            LotPick {
+                lot_id: lots[1].id,
+                quantity: 200,
+            },
+            LotPick {
+                lot_id: lots[0].id,
+                quantity: 100,
+            },
+        ]))
+        .await?;
+    assert_eq!(specific.cost_basis, 24000 + 10000);
+    assert_eq!(specific.realized_gain, 39000 - 34000);
+    assert_eq!(specific.disposals[0].method, LotMethod::Specific);
+
+    // Picks must add up to the quantity sold
+    let short = service
+        .record_sell(sale(300, 130.0, "2024-03-02").with_lots(vec![LotPick {
+            lot_id: lots[0].id,
+            quantity: 100,
+        }]))
+        .await;
+    assert!(matches!(short, Err(AppError::InvalidTrade(_))));
+
+    // Average cost as the wallet default: 1,700 units cost 1,860.00
+    service
+        .set_lot_method("Broker VWCE", LotMethod::Average)
+        .await?;
+    let average = service.record_sell(sale(1700, 100.0, "2024-03-03")).await?;
+    assert_eq!(average.cost_basis, 186000);
+    assert_eq!(average.realized_gain, 170000 - 186000);
+    assert!(service.list_lots("Broker VWCE").await?.is_empty());
+
+    let specific_default = service
+        .set_lot_method("Broker VWCE", LotMethod::Specific)
+        .await;
+    assert!(matches!(specific_default, Err(AppError::InvalidTrade(_))));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reversals_and_yearly_gains_report() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    setup_lots(&service).await?;
+
+    let kept = service.record_sell(sale(500, 110.0, "2024-03-01")).await?;
+    let undone = service.record_sell(sale(500, 110.0, "2024-03-02")).await?;
+    service.record_sell(sale(200, 150.0, "2025-01-10")).await?;
+
+    // A lot that has been sold from cannot be reversed
+    let buy_id = kept.disposals[0].lot_id;
+    let blocked = service.reverse_transfer(buy_id, None).await;
+    assert!(matches!(blocked, Err(AppError::InvalidTrade(_))));
+
+    // Reversing a sale undoes its gain and gives the units back to the lot
+    service
+        .reverse_transfer(undone.trade.transfer.id, None)
+        .await?;
+    assert_eq!(
+        service.get_balance("Realized Gains EUR").await?.balance,
+        -5000 - 6000
+    );
+    // The 2025 sale had already moved on to the second lot
+    let lots = service.list_lots("Broker VWCE").await?;
+    assert_eq!(lots[0].remaining_quantity, 500);
+    assert_eq!(lots[1].remaining_quantity, 800);
+
+    let report = service.get_realized_gains_report(2024).await?;
+    assert_eq!(report.gains.len(), 1);
+    assert_eq!(report.gains[0].acquired_at, parse_date("2024-02-01"));
+    assert_eq!(report.total_proceeds, 55000);
+    assert_eq!(report.total_gain, 5000);
+    assert_eq!(
+        service.get_realized_gains_report(2025).await?.total_gain,
+        6000
+    );
+
+    let mut csv = Vec::new();
+    let count = Exporter::new(&service)
+        .export_realized_gains_csv(&mut csv, 2024)
+        .await?;
+    assert_eq!(count, 1);
+    let csv = String::from_utf8(csv)?;
+    assert!(csv.starts_with("wallet,commodity,lot_id,"));
+    assert!(csv.contains(",50000,55000,5000,fifo"));
+
+    Ok(())
+}
@@ -0,0 +1,287 @@
+mod common;
+
+use anyhow::Result;
+use chrono::Utc;
+use common::{StandardWallets, parse_date, rate, test_service};
+use pecunio::application::{AppError, BudgetRequest, LedgerService};
+use pecunio::domain::{PeriodType, RoundingMode, WalletType};
+use pecunio::storage::MIGRATIONS;
+use sqlx::sqlite::SqlitePool;
+use tempfile::TempDir;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_amounts_use_currency_minor_units() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+
+    assert_eq!(service.get_currency("JPY").await?.minor_units, 0);
+    assert_eq!(service.get_currency("kwd").await?.minor_units, 3);
+    assert_eq!(service.get_currency("EUR").await?.minor_units, 2);
+
+    let yen = service
+        .parse_amount("1500", "JPY", RoundingMode::Reject)
+        .await?;
+    assert_eq!(yen.amount, 1500);
+
+    let fils = service
+        .parse_amount("12.345", "KWD", RoundingMode::Reject)
+        .await?;
+    assert_eq!(fils.amount, 12345);
+
+    // Excess precision is rejected unless a rounding mode is given
+    let rejected = service
+        .parse_amount("100.999", "EUR", RoundingMode::Reject)
+        .await;
+    assert!(matches!(rejected, Err(AppError::InvalidAmount(_))));
+    let rounded = service
+        .parse_amount("100.999", "EUR", RoundingMode::HalfUp)
+        .await?;
+    assert_eq!(rounded.amount, 10100);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_register_currency() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+
+    let eth = service.register_currency("eth", 9).await?;
+    assert_eq!(eth.code, "ETH");
+    assert_eq!(service.get_currency("ETH").await?.minor_units, 9);
+    assert_eq!(service.list_currencies().await?.len(), 1);
+
+    let too_precise = service.register_currency("XYZ", 18).await;
+    assert!(matches!(too_precise, Err(AppError::InvalidCurrency(_))));
+
+    // Once transfers exist, the minor units are fixed
+    service
+        .create_wallet("Cold".into(), WalletType::Asset, "ETH".into(), None)
+        .await?;
+    service
+        .create_wallet("Mining".into(), WalletType::Income, "ETH".into(), None)
+        .await?;
+    service
+        .record_transfer(
+            "Mining",
+            "Cold",
+            1_500_000_000,
+            Utc::now(),
+            None,
+            None,
+            true,
+        )
+        .await?;
+
+    let changed = service.register_currency("ETH", 6).await;
+    assert!(matches!(changed, Err(AppError::CurrencyInUse(_))));
+    // Re-registering the same minor units is harmless
+    service.register_currency("ETH", 9).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cross_currency_transfer_respects_minor_units() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    service
+        .create_wallet("Tokyo Cash".into(), WalletType::Asset, "JPY".into(), None)
+        .await?;
+    StandardWallets::fund_checking(&service, 100000, parse_date("2024-01-01")).await?;
+    service
+        .add_exchange_rate("EUR", "JPY", rate("160"), parse_date("2024-01-01"))
+        .await?;
+
+    // 100.00 EUR -> 16000 JPY
+    let result = service
+        .record_transfer(
+            "Checking",
+            "Tokyo Cash",
+            10000,
+            parse_date("2024-02-01"),
+            None,
+            None,
+            false,
+        )
+        .await?;
+    assert_eq!(result.converted.unwrap().amount_cents, 16000);
+
+    // Converted back for net worth: 900.00 EUR + 100.00 EUR
+    let net_worth = service.get_net_worth_report_converted("EUR").await?;
+    assert_eq!(net_worth.total_assets, 100000);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_budget_currency_scopes_spending() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_with_expense_categories(&service).await?;
+    service
+        .create_wallet("Tokyo Cash".into(), WalletType::Asset, "JPY".into(), None)
+        .await?;
+    service
+        .create_wallet("Tokyo Food".into(), WalletType::Expense, "JPY".into(), None)
+        .await?;
+    service
+        .create_wallet(
+            "Tokyo Salary".into(),
+            WalletType::Income,
+            "JPY".into(),
+            None,
+        )
+        .await?;
+    StandardWallets::fund_checking_now(&service, 100000).await?;
+    service
+        .record_transfer(
+            "Tokyo Salary",
+            "Tokyo Cash",
+            50000,
+            Utc::now(),
+            None,
+            None,
+            true,
+        )
+        .await?;
+
+    service
+        .record_transfer(
+            "Checking",
+            "Groceries",
+            2500,
+            Utc::now(),
+            None,
+            Some("food".to_string()),
+            false,
+        )
+        .await?;
+    service
+        .record_transfer(
+            "Tokyo Cash",
+            "Tokyo Food",
+            1800,
+            Utc::now(),
+            None,
+            Some("food".to_string()),
+            false,
+        )
+        .await?;
+
+    let budget = service
+        .create_budget_with(
+            BudgetRequest::new("Food EUR", "food", 40000, PeriodType::Monthly)
+                .with_currency(Some("eur".to_string())),
+        )
+        .await?;
+    assert_eq!(budget.currency.as_deref(), Some("EUR"));
+    assert_eq!(service.get_budget_status("Food EUR").await?.spent, 2500);
+
+    service
+        .create_budget_with(
+            BudgetRequest::new("Food JPY", "food", 30000, PeriodType::Monthly)
+                .with_currency(Some("JPY".to_string())),
+        )
+        .await?;
+    assert_eq!(service.get_budget_status("Food JPY").await?.spent, 1800);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_migration_rescales_existing_amounts() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let db_path = temp_dir.path().join("legacy.db");
+    let db_path = db_path.to_str().unwrap();
+
+    // Build a database at schema version 6, where every amount was stored in hundredths
+    let pool = SqlitePool::connect(&format!("sqlite:{}?mode=rwc", db_path)).await?;
+    for migration in &MIGRATIONS[..6] {
+        sqlx::query(migration).execute(&pool).await?;
+    }
+    sqlx::query("PRAGMA user_version = 6")
+        .execute(&pool)
+        .await?;
+
+    // Seed through plain SQL: the repository always speaks the latest schema
+    let wallets = [
+        ("Salary", "income", "JPY"),
+        ("Cash", "asset", "JPY"),
+        ("Dinar", "income", "KWD"),
+        ("Bank", "asset", "KWD"),
+        ("Euro", "income", "EUR"),
+        ("Checking", "asset", "EUR"),
+    ];
+    let mut ids = Vec::new();
+    for (name, wallet_type, currency) in wallets {
+        let id = Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO wallets (id, name, wallet_type, currency, allow_negative, created_at) VALUES (?, ?, ?, ?, 1, ?)",
+        )
+        .bind(&id)
+        .bind(name)
+        .bind(wallet_type)
+        .bind(currency)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&pool)
+        .await?;
+        ids.push(id);
+    }
+    // 1500 JPY, 12.34 KWD and 50.00 EUR as parsed by the old two-decimal parser
+    let legacy = [
+        (0, 1, 150000, "yen"),
+        (2, 3, 1234, "dinar"),
+        (4, 5, 5000, "euro"),
+    ];
+    for (sequence, (from, to, amount, category)) in legacy.into_iter().enumerate() {
+        let now = Utc::now().to_rfc3339();
+        sqlx::query(
+            "INSERT INTO transfers (id, sequence, from_wallet_id, to_wallet_id, amount_cents, timestamp, recorded_at, category, tags) VALUES (?, ?, ?, ?, ?, ?, ?, ?, '[]')",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(sequence as i64 + 1)
+        .bind(&ids[from])
+        .bind(&ids[to])
+        .bind(amount)
+        .bind(&now)
+        .bind(&now)
+        .bind(category)
+        .execute(&pool)
+        .await?;
+    }
+    // Limits of 3000 JPY, 20.000 KWD and 80.00 EUR, also in hundredths
+    for (category, amount) in [("yen", 300000), ("dinar", 2000), ("euro", 8000)] {
+        sqlx::query(
+            "INSERT INTO budgets (id, name, category, period_type, amount_cents, created_at) VALUES (?, ?, ?, 'monthly', ?, ?)",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(category)
+        .bind(category)
+        .bind(amount)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&pool)
+        .await?;
+    }
+    sqlx::query("UPDATE sequence_counter SET value = 3 WHERE name = 'transfer_sequence'")
+        .execute(&pool)
+        .await?;
+    pool.close().await;
+
+    let service = LedgerService::connect(db_path).await?;
+    assert_eq!(service.get_balance("Cash").await?.balance, 1500);
+    assert_eq!(service.get_balance("Bank").await?.balance, 12340);
+    assert_eq!(service.get_balance("Checking").await?.balance, 5000);
+    assert!(service.check_integrity().await?.is_healthy());
+
+    // Budgets spending in one currency take it, with the limit rescaled like the spending
+    let yen = service.get_budget_status("yen").await?;
+    assert_eq!(yen.budget.currency.as_deref(), Some("JPY"));
+    assert_eq!((yen.budget.amount_cents, yen.spent), (3000, 1500));
+    let dinar = service.get_budget_status("dinar").await?;
+    assert_eq!(dinar.budget.currency.as_deref(), Some("KWD"));
+    assert_eq!((dinar.budget.amount_cents, dinar.spent), (20000, 12340));
+    let euro = service.get_budget("euro").await?;
+    assert_eq!(euro.currency, None);
+    assert_eq!(euro.amount_cents, 8000);
+
+    Ok(())
+}
@@ -0,0 +1,184 @@
+mod common;
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use common::{StandardWallets, parse_date, parse_day, rate, test_service};
+use pecunio::application::{AppError, TradeRequest};
+use pecunio::domain::{SampleInterval, WalletType};
+
+#[tokio::test]
+async fn test_net_worth_history_samples_each_month() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    service
+        .create_wallet("Card".into(), WalletType::Liability, "EUR".into(), None)
+        .await?;
+    StandardWallets::fund_checking(&service, 200000, parse_date("2023-12-31")).await?;
+    StandardWallets::fund_checking(&service, 300000, parse_date("2024-01-31")).await?;
+    service
+        .record_transfer(
+            "Card",
+            "Expense",
+            45000,
+            parse_date("2024-02-10"),
+            None,
+            None,
+            false,
+        )
+        .await?;
+    service
+        .record_transfer(
+            "Checking",
+            "Card",
+            45000,
+            parse_date("2024-03-05"),
+            None,
+            None,
+            false,
+        )
+        .await?;
+
+    let history = service
+        .get_net_worth_history(
+            parse_day("2024-01-01"),
+            parse_day("2024-03-15"),
+            SampleInterval::Month,
+            "EUR",
+        )
+        .await?;
+    let points: Vec<(NaiveDate, i64, i64, i64)> = history
+        .points
+        .iter()
+        .map(|p| (p.date, p.total_assets, p.total_liabilities, p.net_worth))
+        .collect();
+    assert_eq!(
+        points,
+        vec![
+            (parse_day("2024-01-31"), 500000, 0, 500000),
+            (parse_day("2024-02-29"), 500000, 45000, 455000),
+            (parse_day("2024-03-15"), 455000, 0, 455000),
+        ]
+    );
+
+    // Every sample agrees with the balances as of its day
+    let daily = service
+        .get_net_worth_history(
+            parse_day("2023-12-30"),
+            parse_day("2024-03-15"),
+            SampleInterval::Day,
+            "EUR",
+        )
+        .await?;
+    assert_eq!(daily.points.len(), 77);
+    for point in daily.points.iter().step_by(7) {
+        let balances = service.get_all_balances_as_of(point.date).await?;
+        let assets: i64 = balances
+            .iter()
+            .filter(|e| e.wallet.wallet_type == WalletType::Asset)
+            .map(|e| e.balance)
+            .sum();
+        assert_eq!(point.total_assets, assets, "{}", point.date);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_net_worth_history_rejects_reversed_range() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    let reversed = service
+        .get_net_worth_history(
+            parse_day("2024-02-01"),
+            parse_day("2024-01-01"),
+            SampleInterval::Week,
+            "EUR",
+        )
+        .await;
+    assert!(matches!(reversed, Err(AppError::InvalidDateRange(_))));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_net_worth_history_converts_and_values_holdings() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    StandardWallets::fund_checking(&service, 500000, parse_date("2023-12-31")).await?;
+    service
+        .create_wallet("US Cash".into(), WalletType::Asset, "USD".into(), None)
+        .await?;
+    service
+        .create_wallet("US Income".into(), WalletType::Income, "USD".into(), None)
+        .await?;
+    service
+        .create_commodity_wallet("Broker VWCE".into(), "vwce".into(), "EUR".into(), None)
+        .await?;
+    service
+        .add_exchange_rate("USD", "EUR", rate("0.8"), parse_date("2024-01-01"))
+        .await?;
+    service
+        .add_exchange_rate("USD", "EUR", rate("0.9"), parse_date("2024-02-01"))
+        .await?;
+    service
+        .record_transfer(
+            "US Income",
+            "US Cash",
+            100000,
+            parse_date("2024-01-10"),
+            None,
+            None,
+            false,
+        )
+        .await?;
+    // 10.00 units at 100.00, later priced at 120.00
+    service
+        .record_buy(TradeRequest::new(
+            "Broker VWCE",
+            "Checking",
+            1000,
+            100.0,
+            parse_date("2024-01-15"),
+        ))
+        .await?;
+    service
+        .add_price("vwce", "EUR", 120.0, parse_date("2024-02-20"))
+        .await?;
+
+    // December needs no USD rate: nothing was held in dollars yet
+    let history = service
+        .get_net_worth_history(
+            parse_day("2023-12-01"),
+            parse_day("2024-02-29"),
+            SampleInterval::Month,
+            "EUR",
+        )
+        .await?;
+    assert_eq!(history.currency, "EUR");
+    let totals: Vec<(NaiveDate, i64)> = history
+        .points
+        .iter()
+        .map(|p| (p.date, p.net_worth))
+        .collect();
+    assert_eq!(
+        totals,
+        vec![
+            (parse_day("2023-12-31"), 500000),
+            // 4,000.00 cash + 1,000.00 of units + 1,000.00 USD at 0.8
+            (parse_day("2024-01-31"), 580000),
+            // The units now at 120.00 and the dollars at 0.9
+            (parse_day("2024-02-29"), 610000),
+        ]
+    );
+
+    let in_dollars = service
+        .get_net_worth_history(
+            parse_day("2024-01-31"),
+            parse_day("2024-01-31"),
+            SampleInterval::Day,
+            "usd",
+        )
+        .await?;
+    assert_eq!(in_dollars.currency, "USD");
+    assert_eq!(in_dollars.points[0].net_worth, 725000);
+
+    Ok(())
+}
@@ -0,0 +1,158 @@
+mod common;
+
+use anyhow::Result;
+use common::{StandardWallets, parse_date, parse_day, test_service};
+use pecunio::application::{AppError, OpeningBalance};
+use pecunio::domain::{ClearedState, WalletType};
+
+#[tokio::test]
+async fn test_opening_balances_post_against_equity() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    service
+        .create_wallet("Card".into(), WalletType::Liability, "EUR".into(), None)
+        .await?;
+
+    let result = service
+        .record_opening_balances(
+            vec![
+                OpeningBalance::new("Checking", 150000),
+                OpeningBalance::new("Card", 30000),
+                OpeningBalance::new("Savings", 0),
+            ],
+            parse_day("2024-01-01"),
+        )
+        .await?;
+    assert_eq!(result.posted.len(), 2);
+    assert!(result.already_posted.is_empty());
+
+    assert_eq!(service.get_balance("Checking").await?.balance, 150000);
+    // What is owed on a liability is a negative balance
+    assert_eq!(service.get_balance("Card").await?.balance, -30000);
+    let equity = service.get_wallet("Opening Balances EUR").await?;
+    assert_eq!(equity.wallet_type, WalletType::Equity);
+    assert_eq!(
+        service.get_balance("Opening Balances EUR").await?.balance,
+        -120000
+    );
+
+    let checking = &result.posted[0];
+    assert_eq!(checking.from_wallet_name, "Opening Balances EUR");
+    assert_eq!(checking.to_wallet_name, "Checking");
+    assert_eq!(checking.transfer.timestamp, parse_date("2024-01-01"));
+    assert_eq!(checking.transfer.cleared, ClearedState::Cleared);
+    let card = &result.posted[1];
+    assert_eq!(card.from_wallet_name, "Card");
+    assert_eq!(card.to_wallet_name, "Opening Balances EUR");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_opening_balance_is_not_posted_twice() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    service
+        .record_opening_balances(
+            vec![OpeningBalance::new("Checking", 150000)],
+            parse_day("2024-01-01"),
+        )
+        .await?;
+
+    let again = service
+        .record_opening_balances(
+            vec![
+                OpeningBalance::new("Checking", 160000),
+                OpeningBalance::new("Savings", 50000),
+            ],
+            parse_day("2024-02-01"),
+        )
+        .await?;
+    assert_eq!(again.posted.len(), 1);
+    assert_eq!(again.posted[0].to_wallet_name, "Savings");
+    assert_eq!(again.already_posted.len(), 1);
+    assert_eq!(again.already_posted[0].0, "Checking");
+    assert_eq!(again.already_posted[0].1.amount_cents, 150000);
+    assert_eq!(service.get_balance("Checking").await?.balance, 150000);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reversed_opening_balance_can_be_reposted() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    let wrong = service
+        .record_opening_balances(
+            vec![OpeningBalance::new("Checking", 150000)],
+            parse_day("2024-01-01"),
+        )
+        .await?;
+    let wrong_id = wrong.posted[0].transfer.id;
+
+    // Partly reversed, the opening balance still stands
+    service.reverse_transfer(wrong_id, Some(50000)).await?;
+    let again = service
+        .record_opening_balances(
+            vec![OpeningBalance::new("Checking", 160000)],
+            parse_day("2024-01-01"),
+        )
+        .await?;
+    assert!(again.posted.is_empty());
+    assert_eq!(again.already_posted[0].1.id, wrong_id);
+
+    service.reverse_transfer(wrong_id, Some(100000)).await?;
+    let corrected = service
+        .record_opening_balances(
+            vec![OpeningBalance::new("Checking", 160000)],
+            parse_day("2024-01-01"),
+        )
+        .await?;
+    assert_eq!(corrected.posted.len(), 1);
+    assert!(corrected.already_posted.is_empty());
+    assert_eq!(service.get_balance("Checking").await?.balance, 160000);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_invalid_opening_balances_post_nothing() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+
+    let income = service
+        .record_opening_balances(
+            vec![
+                OpeningBalance::new("Checking", 150000),
+                OpeningBalance::new("Income", 1000),
+            ],
+            parse_day("2024-01-01"),
+        )
+        .await;
+    assert!(matches!(income, Err(AppError::InvalidOpeningBalance(_))));
+
+    let twice = service
+        .record_opening_balances(
+            vec![
+                OpeningBalance::new("Checking", 150000),
+                OpeningBalance::new("Checking", 140000),
+            ],
+            parse_day("2024-01-01"),
+        )
+        .await;
+    assert!(matches!(twice, Err(AppError::InvalidOpeningBalance(_))));
+    assert!(service.list_transfers(None).await?.is_empty());
+
+    service
+        .lock_period(None, parse_day("2023-12-31"), None, None)
+        .await?;
+    let locked = service
+        .record_opening_balances(
+            vec![OpeningBalance::new("Checking", 150000)],
+            parse_day("2023-12-31"),
+        )
+        .await;
+    assert!(matches!(locked, Err(AppError::PeriodLocked { .. })));
+
+    Ok(())
+}
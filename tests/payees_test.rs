@@ -0,0 +1,166 @@
+mod common;
+
+use anyhow::Result;
+use common::{StandardWallets, parse_date, test_service};
+use pecunio::application::{AppError, TransferRequest};
+use pecunio::io::{DatabaseSnapshot, Exporter, ImportOptions, Importer};
+
+#[tokio::test]
+async fn test_payee_defaults_fill_in_transfers() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    StandardWallets::fund_checking(&service, 100000, parse_date("2024-01-01")).await?;
+    let shell = service
+        .create_payee(
+            "Shell".into(),
+            vec!["SHELL STATION".into()],
+            Some("Car:Fuel".into()),
+            Some("Expense"),
+        )
+        .await?;
+    assert_eq!(shell.aliases, vec!["SHELL STATION"]);
+
+    // An explicit payee fills the open wallet and the missing category
+    let fuel = service
+        .record_transfer_with(
+            TransferRequest::new("Checking", "", 4000, parse_date("2024-02-01"))
+                .with_payee(Some("shell station".into())),
+        )
+        .await?;
+    assert_eq!(fuel.to_wallet_name, "Expense");
+    assert_eq!(fuel.transfer.category.as_deref(), Some("Car:Fuel"));
+    assert_eq!(fuel.transfer.payee_id, Some(shell.id));
+
+    // Without one, the payee is detected from the description; a given category wins
+    let detected = service
+        .record_transfer_with(
+            TransferRequest::new("Checking", "Expense", 1500, parse_date("2024-02-02"))
+                .with_description(Some("CARD 4411 Shell Station Berlin".into()))
+                .with_category(Some("car:wash".into())),
+        )
+        .await?;
+    assert_eq!(detected.transfer.payee_id, Some(shell.id));
+    assert_eq!(detected.transfer.category.as_deref(), Some("car:wash"));
+    let info = service.get_transfer_info(detected.transfer.id).await?;
+    assert_eq!(info.payee.unwrap().name, "Shell");
+
+    let unrelated = service
+        .record_transfer_with(
+            TransferRequest::new("Checking", "Expense", 500, parse_date("2024-02-03"))
+                .with_description(Some("Seashells".into())),
+        )
+        .await?;
+    assert_eq!(unrelated.transfer.payee_id, None);
+
+    let unknown = service
+        .record_transfer_with(
+            TransferRequest::new("Checking", "Expense", 500, parse_date("2024-02-03"))
+                .with_payee(Some("Aral".into())),
+        )
+        .await;
+    assert!(matches!(unknown, Err(AppError::PayeeNotFound(_))));
+
+    let taken = service
+        .create_payee("Shell Station".into(), vec![], None, None)
+        .await;
+    assert!(matches!(taken, Err(AppError::PayeeAlreadyExists(_))));
+    service
+        .create_payee("Aral".into(), vec![], None, None)
+        .await?;
+    let no_wallet = service
+        .record_transfer_with(
+            TransferRequest::new("Checking", "", 500, parse_date("2024-02-03"))
+                .with_payee(Some("Aral".into())),
+        )
+        .await;
+    assert!(matches!(no_wallet, Err(AppError::InvalidPayee(_))));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_import_detects_payees_and_export_keeps_them() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    service
+        .create_payee(
+            "Acme Corp".into(),
+            vec!["ACME PAYROLL".into()],
+            Some("salary".into()),
+            Some("Income"),
+        )
+        .await?;
+
+    let csv = "id,sequence,timestamp,from_wallet,to_wallet,amount,description,category,tags,reverses,external_ref,split_id\n\
+               ,,2024-01-31,Income,Checking,3000.00,SEPA ACME PAYROLL JAN,,,,,\n\
+               ,,2024-02-01,Checking,Expense,12.00,Bakery,,,,,\n";
+    let result = Importer::new(&service)
+        .import_transfers_csv(csv.as_bytes(), ImportOptions::default())
+        .await?;
+    assert_eq!(result.imported, 2);
+    let transfers = service.list_all_transfers().await?;
+    let salary = transfers.iter().find(|t| t.amount_cents == 300000).unwrap();
+    assert_eq!(salary.category.as_deref(), Some("salary"));
+    assert!(salary.payee_id.is_some());
+
+    let mut csv_out = Vec::new();
+    Exporter::new(&service)
+        .export_transfers_csv(&mut csv_out)
+        .await?;
+    let csv_out = String::from_utf8(csv_out)?;
+    assert!(csv_out.lines().next().unwrap().ends_with(",payee"));
+    assert!(csv_out.contains(",Acme Corp"));
+
+    let mut json = Vec::new();
+    Exporter::new(&service).export_full_json(&mut json).await?;
+    let snapshot: DatabaseSnapshot = serde_json::from_slice(&json)?;
+    assert_eq!(snapshot.payees.len(), 1);
+    assert_eq!(snapshot.payees[0].aliases, vec!["ACME PAYROLL"]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_top_payees_report() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    StandardWallets::fund_checking(&service, 100000, parse_date("2024-01-01")).await?;
+    for name in ["Landlord", "Bakery", "Cinema"] {
+        service
+            .create_payee(name.into(), vec![], None, Some("Expense"))
+            .await?;
+    }
+
+    for (payee, amount, date) in [
+        ("Landlord", 50000, "2024-03-01"),
+        ("Bakery", 500, "2024-03-02"),
+        ("Bakery", 700, "2024-03-06"),
+        ("Bakery", 300, "2024-03-12"),
+        ("Cinema", 1200, "2024-03-15"),
+    ] {
+        service
+            .record_transfer_with(
+                TransferRequest::new("Checking", "", amount, parse_date(date))
+                    .with_payee(Some(payee.into())),
+            )
+            .await?;
+    }
+
+    let report = service
+        .get_payee_report(parse_date("2024-03-01"), parse_date("2024-04-01"), Some(2))
+        .await?;
+    // The total covers payees cut off by the limit
+    assert_eq!(report.total, 52700);
+    let rows: Vec<(&str, i64, i64)> = report
+        .payees
+        .iter()
+        .map(|p| (p.payee.as_str(), p.total, p.count))
+        .collect();
+    assert_eq!(rows, vec![("Landlord", 50000, 1), ("Bakery", 1500, 3)]);
+    assert_eq!(report.payees[0].average_interval_days, None);
+    assert_eq!(report.payees[1].average_interval_days, Some(5.0));
+    assert_eq!(report.payees[1].average, 500);
+    assert_eq!(report.payees[1].last_date, parse_date("2024-03-12"));
+
+    Ok(())
+}
@@ -0,0 +1,351 @@
+mod common;
+
+use anyhow::Result;
+use common::{StandardWallets, parse_date, parse_day, rate, test_service};
+use pecunio::application::{
+    AppError, LockOverrideRequest, SplitLeg, TransferEdit, TransferRequest,
+};
+use pecunio::domain::{IntegrityIssue, LockedAction, WalletType};
+use pecunio::io::{ImportOptions, Importer};
+use sqlx::SqlitePool;
+
+#[tokio::test]
+async fn test_ledger_lock_rejects_backdated_changes() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    StandardWallets::fund_checking(&service, 100000, parse_date("2023-06-01")).await?;
+    let rent = service
+        .record_transfer(
+            "Checking",
+            "Expense",
+            50000,
+            parse_date("2023-12-01"),
+            Some("Rent".into()),
+            None,
+            false,
+        )
+        .await?;
+
+    service
+        .lock_period(None, parse_day("2023-12-31"), Some("alice".into()), None)
+        .await?;
+
+    let backdated = service
+        .record_transfer(
+            "Checking",
+            "Expense",
+            1000,
+            parse_date("2023-12-31"),
+            None,
+            None,
+            false,
+        )
+        .await;
+    assert!(matches!(
+        backdated,
+        Err(AppError::PeriodLocked { lock_date, .. }) if lock_date == parse_day("2023-12-31")
+    ));
+    // The day after the lock date is open
+    service
+        .record_transfer(
+            "Checking",
+            "Expense",
+            1000,
+            parse_date("2024-01-01"),
+            None,
+            None,
+            false,
+        )
+        .await?;
+
+    let split = service
+        .record_split_transfer(
+            "Checking",
+            vec![SplitLeg {
+                to_wallet: "Expense".into(),
+                amount_cents: 500,
+                category: None,
+                description: None,
+            }],
+            parse_date("2023-11-30"),
+            None,
+            false,
+        )
+        .await;
+    assert!(matches!(split, Err(AppError::PeriodLocked { .. })));
+
+    // Moving a transfer out of, into, or within the locked period is an amendment in it
+    for edit in [
+        TransferEdit::new().with_timestamp(parse_date("2024-01-05")),
+        TransferEdit::new().with_description(Some("Rent December".into())),
+    ] {
+        let amended = service.amend_transfer(rent.transfer.id, edit).await;
+        assert!(matches!(amended, Err(AppError::PeriodLocked { .. })));
+    }
+
+    let csv = "id,sequence,timestamp,from_wallet,to_wallet,amount,description,category,tags,reverses,external_ref,split_id\n\
+               ,,2023-12-20,Checking,Expense,12.00,Bakery,,,,,\n";
+    let result = Importer::new(&service)
+        .import_transfers_csv(csv.as_bytes(), ImportOptions::default())
+        .await?;
+    assert_eq!(result.imported, 0);
+    assert_eq!(result.errors.len(), 1);
+    assert!(result.errors[0].error.contains("Period locked"));
+
+    // Lifting the lock opens the period again
+    service.unlock_period(None, None, None).await?;
+    assert!(service.get_period_locks().await?.is_empty());
+    service
+        .amend_transfer(
+            rent.transfer.id,
+            TransferEdit::new().with_description(Some("Rent December".into())),
+        )
+        .await?;
+    assert!(matches!(
+        service.unlock_period(None, None, None).await,
+        Err(AppError::InvalidPeriodLock(_))
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lock_override_is_logged() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    StandardWallets::fund_checking(&service, 100000, parse_date("2023-06-01")).await?;
+    service
+        .lock_period(
+            None,
+            parse_day("2023-12-31"),
+            None,
+            Some("Filed 2023".into()),
+        )
+        .await?;
+
+    let late_invoice = service
+        .record_transfer_with(
+            TransferRequest::new("Checking", "Expense", 2500, parse_date("2023-12-28"))
+                .with_lock_override(Some(
+                    LockOverrideRequest::new("Invoice arrived late")
+                        .with_author(Some("alice".into())),
+                )),
+        )
+        .await?;
+    let fixed = service
+        .amend_transfer(
+            late_invoice.transfer.id,
+            TransferEdit::new()
+                .with_amount(2000)
+                .with_lock_override(Some(LockOverrideRequest::new("Typo"))),
+        )
+        .await?;
+    assert_eq!(fixed.transfer.amount_cents, 2000);
+
+    let blank = service
+        .record_transfer_with(
+            TransferRequest::new("Checking", "Expense", 100, parse_date("2023-12-28"))
+                .with_lock_override(Some(LockOverrideRequest::new("  "))),
+        )
+        .await;
+    assert!(matches!(blank, Err(AppError::InvalidPeriodLock(_))));
+
+    let overrides = service.list_lock_overrides().await?;
+    let logged: Vec<(LockedAction, &str, Option<&str>)> = overrides
+        .iter()
+        .map(|o| (o.action, o.reason.as_str(), o.author.as_deref()))
+        .collect();
+    assert_eq!(
+        logged,
+        vec![
+            (
+                LockedAction::Transfer,
+                "Invoice arrived late",
+                Some("alice")
+            ),
+            (LockedAction::Amendment, "Typo", None),
+        ]
+    );
+    assert!(
+        overrides
+            .iter()
+            .all(|o| o.transfer_id == late_invoice.transfer.id)
+    );
+
+    // Overridden changes are not integrity violations
+    let report = service.check_integrity().await?;
+    assert!(report.is_healthy(), "{:?}", report.issues);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_overridden_transfer_is_not_kept_without_its_log() -> Result<()> {
+    let (service, temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    StandardWallets::fund_checking(&service, 100000, parse_date("2023-06-01")).await?;
+    service
+        .lock_period(None, parse_day("2023-12-31"), None, None)
+        .await?;
+
+    // Make logging the override fail
+    let db_path = temp.path().join("test.db");
+    let pool = SqlitePool::connect(&format!("sqlite:{}", db_path.to_str().unwrap())).await?;
+    sqlx::query(
+        "CREATE TRIGGER refuse_overrides BEFORE INSERT ON lock_overrides BEGIN SELECT RAISE(ABORT, 'refused'); END",
+    )
+    .execute(&pool)
+    .await?;
+    let refused = service
+        .record_transfer_with(
+            TransferRequest::new("Checking", "Expense", 2500, parse_date("2023-12-28"))
+                .with_lock_override(Some(LockOverrideRequest::new("Invoice arrived late"))),
+        )
+        .await;
+    assert!(refused.is_err());
+    assert_eq!(service.get_balance("Checking").await?.balance, 100000);
+    assert!(service.check_integrity().await?.is_healthy());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lock_on_an_exchange_wallet_covers_conversions() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    StandardWallets::fund_checking(&service, 100000, parse_date("2023-06-01")).await?;
+    service
+        .create_wallet("Dollars".into(), WalletType::Asset, "USD".into(), None)
+        .await?;
+    service
+        .create_wallet(
+            "Exchange USD".into(),
+            WalletType::Equity,
+            "USD".into(),
+            None,
+        )
+        .await?;
+    service
+        .add_exchange_rate("EUR", "USD", rate("1.1"), parse_date("2023-01-01"))
+        .await?;
+    service
+        .lock_period(Some("Exchange USD"), parse_day("2023-12-31"), None, None)
+        .await?;
+
+    let converted = service
+        .record_transfer_with(TransferRequest::new(
+            "Checking",
+            "Dollars",
+            10000,
+            parse_date("2023-12-15"),
+        ))
+        .await;
+    match converted {
+        Err(AppError::PeriodLocked { scope, .. }) => assert_eq!(scope, "wallet Exchange USD"),
+        other => panic!("expected a period lock error, got {:?}", other.err()),
+    }
+
+    let result = service
+        .record_transfer_with(
+            TransferRequest::new("Checking", "Dollars", 10000, parse_date("2023-12-15"))
+                .with_lock_override(Some(LockOverrideRequest::new("Late conversion"))),
+        )
+        .await?;
+    let leg = result.converted.expect("a conversion").leg;
+    let logged: Vec<_> = service
+        .list_lock_overrides()
+        .await?
+        .iter()
+        .map(|o| o.transfer_id)
+        .collect();
+    assert_eq!(logged, vec![result.transfer.id, leg.id]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_wallet_lock_covers_sub_wallets_only() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    StandardWallets::fund_checking(&service, 100000, parse_date("2023-06-01")).await?;
+    service
+        .create_sub_wallet("Savings:Holiday".into(), "Savings", None)
+        .await?;
+    service
+        .lock_period(Some("Savings"), parse_day("2023-12-31"), None, None)
+        .await?;
+
+    let into_sub = service
+        .record_transfer(
+            "Checking",
+            "Savings:Holiday",
+            1000,
+            parse_date("2023-12-15"),
+            None,
+            None,
+            false,
+        )
+        .await;
+    match into_sub {
+        Err(AppError::PeriodLocked { scope, .. }) => assert_eq!(scope, "wallet Savings"),
+        other => panic!("expected a period lock error, got {:?}", other.err()),
+    }
+    // Wallets outside the locked one stay open
+    service
+        .record_transfer(
+            "Checking",
+            "Expense",
+            1000,
+            parse_date("2023-12-15"),
+            None,
+            None,
+            false,
+        )
+        .await?;
+
+    let locks = service.get_period_locks().await?;
+    assert_eq!(locks.len(), 1);
+    assert!(locks[0].wallet_id.is_some());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_integrity_reports_changes_inside_an_earlier_lock() -> Result<()> {
+    let (service, temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    StandardWallets::fund_checking(&service, 100000, parse_date("2023-06-01")).await?;
+    service
+        .record_transfer(
+            "Checking",
+            "Expense",
+            1000,
+            parse_date("2023-12-15"),
+            None,
+            None,
+            false,
+        )
+        .await?;
+
+    // Locking after the fact does not make what was recorded before a violation
+    service
+        .lock_period(None, parse_day("2023-12-31"), None, None)
+        .await?;
+    assert!(service.check_integrity().await?.is_healthy());
+
+    // A lock that was already in force when the transfers were written is
+    let db_path = temp.path().join("test.db");
+    let pool = SqlitePool::connect(&format!("sqlite:{}", db_path.to_str().unwrap())).await?;
+    sqlx::query("UPDATE period_locks SET locked_at = '2024-01-01T00:00:00+00:00'")
+        .execute(&pool)
+        .await?;
+
+    let report = service.check_integrity().await?;
+    assert!(
+        report
+            .issues
+            .contains(&IntegrityIssue::LockedPeriodViolations(2))
+    );
+
+    Ok(())
+}
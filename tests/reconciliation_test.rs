@@ -0,0 +1,139 @@
+mod common;
+
+use anyhow::Result;
+use common::{StandardWallets, parse_date, test_service};
+use pecunio::application::{AppError, LedgerService};
+use pecunio::domain::{ClearedState, IntegrityIssue, TransferId};
+use sqlx::SqlitePool;
+
+/// Checking funded with 1,000.00 EUR on Jan 1st, then 120.00 and 30.00 of
+/// spending in January and 50.00 in February. Returns the transfer IDs in order.
+async fn setup_statement(service: &LedgerService) -> Result<Vec<TransferId>> {
+    StandardWallets::create_basic(service).await?;
+    let mut ids = Vec::new();
+    for (from, to, amount, date) in [
+        ("Income", "Checking", 100000, "2024-01-01"),
+        ("Checking", "Expense", 12000, "2024-01-10"),
+        ("Checking", "Expense", 3000, "2024-01-31"),
+        ("Checking", "Expense", 5000, "2024-02-02"),
+    ] {
+        let result = service
+            .record_transfer(from, to, amount, parse_date(date), None, None, true)
+            .await?;
+        ids.push(result.transfer.id);
+    }
+    Ok(ids)
+}
+
+#[tokio::test]
+async fn test_reconcile_session_locks_cleared_transfers() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    let ids = setup_statement(&service).await?;
+
+    // The January statement shows the income and the first expense only
+    let status = service
+        .start_reconciliation("Checking", parse_date("2024-01-31"), 88000)
+        .await?;
+    assert_eq!(status.pending.len(), 3);
+    assert_eq!(status.difference, 88000);
+
+    let again = service
+        .start_reconciliation("Checking", parse_date("2024-01-31"), 88000)
+        .await;
+    assert!(matches!(again, Err(AppError::ReconciliationInProgress(_))));
+
+    // February's transfer cannot be on a January statement
+    let late = service.tick_transfers("Checking", &[ids[3]]).await;
+    assert!(matches!(late, Err(AppError::InvalidReconciliation(_))));
+
+    let status = service
+        .tick_transfers("Checking", &[ids[0], ids[1], ids[2]])
+        .await?;
+    assert_eq!(status.cleared_balance, 85000);
+    assert_eq!(status.difference, 3000);
+    let off = service.finish_reconciliation("Checking").await;
+    assert!(matches!(
+        off,
+        Err(AppError::ReconciliationDifference {
+            difference: 3000,
+            ..
+        })
+    ));
+
+    let status = service.untick_transfers("Checking", &[ids[2]]).await?;
+    assert_eq!(status.difference, 0);
+    service.finish_reconciliation("Checking").await?;
+
+    let mut states = Vec::new();
+    for id in &ids {
+        states.push(service.get_transfer_info(*id).await?.transfer.cleared);
+    }
+    assert_eq!(
+        states,
+        vec![
+            ClearedState::Reconciled,
+            ClearedState::Reconciled,
+            ClearedState::Uncleared,
+            ClearedState::Uncleared,
+        ]
+    );
+
+    // Reconciled transfers are locked; the session is closed
+    let retick = service
+        .start_reconciliation("Checking", parse_date("2024-02-29"), 80000)
+        .await?;
+    assert_eq!(retick.pending.len(), 2);
+    assert_eq!(retick.cleared_balance, 88000);
+    let locked = service.untick_transfers("Checking", &[ids[0]]).await;
+    assert!(matches!(locked, Err(AppError::InvalidReconciliation(_))));
+    service.cancel_reconciliation("Checking").await?;
+    assert!(matches!(
+        service.get_reconciliation_status("Checking").await,
+        Err(AppError::NoOpenReconciliation(_))
+    ));
+
+    let sessions = service.list_reconciliations(Some("Checking")).await?;
+    assert_eq!(sessions.len(), 1);
+    assert!(sessions[0].is_finished());
+    assert!(service.check_integrity().await?.is_healthy());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_integrity_flags_changes_to_reconciled_transfers() -> Result<()> {
+    let (service, temp) = test_service().await?;
+    let ids = setup_statement(&service).await?;
+
+    service
+        .start_reconciliation("Checking", parse_date("2024-01-31"), 85000)
+        .await?;
+    service
+        .tick_transfers("Checking", &[ids[0], ids[1], ids[2]])
+        .await?;
+    service.finish_reconciliation("Checking").await?;
+
+    // Reversing a reconciled transfer is allowed but flagged
+    service.reverse_transfer(ids[2], None).await?;
+    let report = service.check_integrity().await?;
+    assert_eq!(
+        report.issues,
+        vec![IntegrityIssue::ReconciledTransfersReversed(1)]
+    );
+
+    // So is editing one behind the ledger's back
+    let db_path = temp.path().join("test.db");
+    let pool = SqlitePool::connect(&format!("sqlite:{}", db_path.to_str().unwrap())).await?;
+    sqlx::query("UPDATE transfers SET amount_cents = 12500 WHERE id = ?")
+        .bind(ids[1].to_string())
+        .execute(&pool)
+        .await?;
+    let report = service.check_integrity().await?;
+    assert!(
+        report
+            .issues
+            .contains(&IntegrityIssue::ReconciledTransfersModified(1))
+    );
+
+    Ok(())
+}
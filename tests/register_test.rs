@@ -0,0 +1,173 @@
+mod common;
+
+use anyhow::Result;
+use common::{StandardWallets, parse_date, parse_day, test_service};
+use pecunio::application::LedgerService;
+use pecunio::io::Exporter;
+
+/// Salary on the last day of January and February, rent on the first of
+/// February and March; rent for February is recorded last.
+async fn record_two_months(service: &LedgerService) -> Result<()> {
+    StandardWallets::create_basic(service).await?;
+    StandardWallets::fund_checking(service, 300000, parse_date("2024-01-31")).await?;
+    StandardWallets::fund_checking(service, 300000, parse_date("2024-02-29")).await?;
+    service
+        .record_transfer(
+            "Checking",
+            "Expense",
+            120000,
+            parse_date("2024-03-01"),
+            Some("Rent March".into()),
+            None,
+            false,
+        )
+        .await?;
+    service
+        .record_transfer(
+            "Checking",
+            "Expense",
+            120000,
+            parse_date("2024-02-01"),
+            Some("Rent February".into()),
+            None,
+            false,
+        )
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_balances_as_of_a_day() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    record_two_months(&service).await?;
+
+    let checking = service
+        .get_balance_as_of("Checking", parse_day("2024-01-31"))
+        .await?;
+    assert_eq!(checking.balance, 300000);
+    let checking = service
+        .get_balance_as_of("Checking", parse_day("2024-02-01"))
+        .await?;
+    assert_eq!(checking.balance, 180000);
+    let checking = service
+        .get_balance_as_of("Checking", parse_day("2023-12-31"))
+        .await?;
+    assert_eq!(checking.balance, 0);
+
+    let all = service
+        .get_all_balances_as_of(parse_day("2024-02-29"))
+        .await?;
+    let balance = |name: &str| {
+        all.iter()
+            .find(|entry| entry.wallet.name == name)
+            .map(|entry| entry.balance)
+    };
+    assert_eq!(balance("Checking"), Some(480000));
+    assert_eq!(balance("Income"), Some(-600000));
+    assert_eq!(balance("Expense"), Some(120000));
+
+    // Today's balance counts everything
+    assert_eq!(service.get_balance("Checking").await?.balance, 360000);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_archived_wallets_keep_their_past_balances() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    record_two_months(&service).await?;
+    service
+        .create_sub_wallet("Savings:Holiday".into(), "Savings", None)
+        .await?;
+    for (from, to, day) in [
+        ("Checking", "Savings:Holiday", "2024-02-10"),
+        ("Savings:Holiday", "Checking", "2024-03-10"),
+    ] {
+        service
+            .record_transfer(from, to, 50000, parse_date(day), None, None, false)
+            .await?;
+    }
+    service.archive_wallet("Savings:Holiday").await?;
+    service.archive_wallet("Savings").await?;
+
+    let savings = service
+        .get_balance_as_of("Savings", parse_day("2024-02-29"))
+        .await?;
+    assert_eq!((savings.balance, savings.subtotal), (0, 50000));
+    let holiday = service
+        .get_balance_as_of("Savings:Holiday", parse_day("2024-02-29"))
+        .await?;
+    assert_eq!(holiday.balance, 50000);
+    let all = service
+        .get_all_balances_as_of(parse_day("2024-02-29"))
+        .await?;
+    assert!(
+        all.iter()
+            .any(|entry| entry.wallet.name == "Savings:Holiday")
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_register_runs_balance_in_date_order() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    record_two_months(&service).await?;
+
+    let register = service.get_register("Checking", None, None).await?;
+    assert_eq!(register.opening_balance, 0);
+    let lines: Vec<(Option<&str>, i64, i64)> = register
+        .entries
+        .iter()
+        .map(|e| (e.transfer.description.as_deref(), e.amount_cents, e.balance))
+        .collect();
+    assert_eq!(
+        lines,
+        vec![
+            (None, 300000, 300000),
+            (Some("Rent February"), -120000, 180000),
+            (None, 300000, 480000),
+            (Some("Rent March"), -120000, 360000),
+        ]
+    );
+    assert_eq!(register.closing_balance(), 360000);
+
+    // A range starts from the balance before its first day
+    let february = service
+        .get_register(
+            "Checking",
+            Some(parse_day("2024-02-01")),
+            Some(parse_day("2024-02-29")),
+        )
+        .await?;
+    assert_eq!(february.opening_balance, 300000);
+    assert_eq!(february.entries.len(), 2);
+    assert_eq!(february.closing_balance(), 480000);
+    let income = service.get_wallet("Income").await?;
+    assert_eq!(february.entries[1].counterpart, income.id);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_register_exports_to_csv() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    record_two_months(&service).await?;
+
+    let mut output = Vec::new();
+    let count = Exporter::new(&service)
+        .export_register_csv(&mut output, "Checking", Some(parse_day("2024-02-01")), None)
+        .await?;
+    assert_eq!(count, 3);
+
+    let csv = String::from_utf8(output)?;
+    let rows: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        rows[0],
+        "id,timestamp,counterpart,description,category,amount_cents,balance_cents"
+    );
+    assert!(rows[1].ends_with(",Expense,Rent February,,-120000,180000"));
+    assert!(rows[3].ends_with(",Expense,Rent March,,-120000,360000"));
+
+    Ok(())
+}
@@ -1,8 +1,9 @@
 mod common;
 
 use anyhow::Result;
-use common::{parse_date, test_service};
-use pecunio::domain::{PeriodType, RecurrencePattern, WalletType};
+use common::{StandardWallets, parse_date, test_service};
+use pecunio::application::{AppError, TransferFilter};
+use pecunio::domain::{PeriodType, RecurrencePattern, TagMatch, WalletType};
 
 #[tokio::test]
 async fn test_category_report() -> Result<()> {
@@ -505,3 +506,101 @@ async fn test_auto_execution_integration() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_hierarchical_categories_roll_up() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    StandardWallets::fund_checking(&service, 500000, parse_date("2024-01-01")).await?;
+
+    for (amount, date, category) in [
+        (4000, "2024-01-05", " food : groceries "),
+        (2500, "2024-01-12", "food:restaurants"),
+        (1000, "2024-01-20", "food:groceries:organic"),
+        (90000, "2024-01-02", "rent"),
+        (3000, "2024-02-03", "food:groceries"),
+    ] {
+        service
+            .record_transfer(
+                "Checking",
+                "Expense",
+                amount,
+                parse_date(date),
+                None,
+                Some(category.to_string()),
+                false,
+            )
+            .await?;
+    }
+    let bad = service
+        .record_transfer(
+            "Checking",
+            "Expense",
+            100,
+            parse_date("2024-01-21"),
+            None,
+            Some("food::snacks".to_string()),
+            false,
+        )
+        .await;
+    assert!(matches!(bad, Err(AppError::InvalidCategory(_))));
+
+    let report = service
+        .get_category_report(parse_date("2024-01-01"), parse_date("2024-02-01"))
+        .await?;
+    // Levels are trimmed when recorded; the report lists categories as recorded
+    assert!(
+        report
+            .categories
+            .iter()
+            .any(|c| c.category == "food:groceries")
+    );
+    assert_eq!(report.categories.len(), 4);
+
+    let top = report.clone().rolled_up(1);
+    assert_eq!(top.categories.len(), 2);
+    assert_eq!(top.categories[1].category, "food");
+    assert_eq!(top.categories[1].total, 7500);
+    assert_eq!(top.categories[1].count, 3);
+    let second = report.clone().rolled_up(2);
+    let groceries = second
+        .categories
+        .iter()
+        .find(|c| c.category == "food:groceries")
+        .unwrap();
+    assert_eq!(groceries.total, 5000);
+
+    let tree = report.tree();
+    let food = tree.iter().find(|n| n.category == "food").unwrap();
+    assert_eq!(food.total, 7500);
+    assert_eq!(food.children.len(), 2);
+
+    let cashflow = service
+        .get_cashflow_report(
+            parse_date("2024-01-01"),
+            parse_date("2024-03-01"),
+            PeriodType::Monthly,
+        )
+        .await?
+        .rolled_up(1);
+    let february = &cashflow.periods[1].categories;
+    assert_eq!(february.len(), 1);
+    assert_eq!(february[0].category, "food");
+    assert_eq!(february[0].total, 3000);
+
+    // Filtering by a parent category includes its sub-categories
+    let filtered = service
+        .list_transfers_filtered(TransferFilter {
+            wallet: None,
+            category: Some("food".to_string()),
+            tags: Vec::new(),
+            tag_match: TagMatch::Any,
+            from_date: None,
+            to_date: None,
+            limit: None,
+        })
+        .await?;
+    assert_eq!(filtered.len(), 4);
+
+    Ok(())
+}
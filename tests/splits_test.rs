@@ -0,0 +1,220 @@
+mod common;
+
+use anyhow::Result;
+use chrono::Utc;
+use common::{StandardWallets, test_service};
+use pecunio::application::{AppError, SplitLeg};
+use pecunio::domain::{PeriodType, WalletType};
+
+fn leg(to_wallet: &str, amount_cents: i64, category: &str) -> SplitLeg {
+    SplitLeg {
+        to_wallet: to_wallet.to_string(),
+        amount_cents,
+        category: Some(category.to_string()),
+        description: None,
+    }
+}
+
+/// Checking, Income plus Groceries, Household and Pharmacy expense wallets
+async fn setup_receipt_wallets(service: &pecunio::application::LedgerService) -> Result<()> {
+    StandardWallets::create_basic(service).await?;
+    for name in ["Groceries", "Household", "Pharmacy"] {
+        service
+            .create_wallet(name.into(), WalletType::Expense, "EUR".into(), None)
+            .await?;
+    }
+    StandardWallets::fund_checking_now(service, 100000).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_record_split_transfer() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    setup_receipt_wallets(&service).await?;
+
+    let result = service
+        .record_split_transfer(
+            "Checking",
+            vec![
+                leg("Groceries", 4500, "groceries"),
+                leg("Household", 1250, "household"),
+                leg("Pharmacy", 820, "pharmacy"),
+            ],
+            Utc::now(),
+            Some("Supermarket receipt".to_string()),
+            false,
+        )
+        .await?;
+
+    assert_eq!(result.total, 6570);
+    assert_eq!(result.legs.len(), 3);
+    assert!(
+        result
+            .legs
+            .iter()
+            .all(|l| l.transfer.split_id == Some(result.split_id))
+    );
+    assert!(
+        result
+            .legs
+            .iter()
+            .all(|l| l.transfer.description.as_deref() == Some("Supermarket receipt"))
+    );
+
+    assert_eq!(
+        service.get_balance("Checking").await?.balance,
+        100000 - 6570
+    );
+    assert_eq!(service.get_balance("Groceries").await?.balance, 4500);
+    assert_eq!(service.get_balance("Household").await?.balance, 1250);
+    assert_eq!(service.get_balance("Pharmacy").await?.balance, 820);
+
+    // Any leg shows the whole split
+    let info = service
+        .get_transfer_info(result.legs[1].transfer.id)
+        .await?;
+    assert_eq!(info.split_legs.len(), 3);
+
+    assert!(service.check_integrity().await?.is_healthy());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_split_is_atomic() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    setup_receipt_wallets(&service).await?;
+
+    // The last leg targets a missing wallet: nothing must be stored
+    let result = service
+        .record_split_transfer(
+            "Checking",
+            vec![
+                leg("Groceries", 4500, "groceries"),
+                leg("Missing", 1000, "misc"),
+            ],
+            Utc::now(),
+            None,
+            false,
+        )
+        .await;
+    assert!(matches!(result, Err(AppError::WalletNotFound(_))));
+
+    // Each leg fits the balance, but the total does not
+    let result = service
+        .record_split_transfer(
+            "Checking",
+            vec![
+                leg("Groceries", 60000, "groceries"),
+                leg("Household", 60000, "household"),
+            ],
+            Utc::now(),
+            None,
+            false,
+        )
+        .await;
+    assert!(matches!(result, Err(AppError::InsufficientFunds { .. })));
+
+    // Only the funding transfer exists
+    assert_eq!(service.list_transfers(None).await?.len(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_split_legs_count_in_reports_and_budgets() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    setup_receipt_wallets(&service).await?;
+
+    service
+        .record_split_transfer(
+            "Checking",
+            vec![
+                leg("Groceries", 4500, "groceries"),
+                leg("Household", 1250, "household"),
+            ],
+            Utc::now(),
+            None,
+            false,
+        )
+        .await?;
+
+    let (from, to) = PeriodType::Monthly.current_period(Utc::now());
+    let report = service.get_category_report(from, to).await?;
+    let groceries = report
+        .categories
+        .iter()
+        .find(|c| c.category == "groceries")
+        .unwrap();
+    let household = report
+        .categories
+        .iter()
+        .find(|c| c.category == "household")
+        .unwrap();
+    assert_eq!(groceries.total, 4500);
+    assert_eq!(household.total, 1250);
+
+    service
+        .create_budget(
+            "Food".to_string(),
+            "groceries".to_string(),
+            10000,
+            PeriodType::Monthly,
+        )
+        .await?;
+    let status = service.get_budget_status("Food").await?;
+    assert_eq!(status.spent, 4500);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reverse_split_as_unit() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    setup_receipt_wallets(&service).await?;
+
+    let split = service
+        .record_split_transfer(
+            "Checking",
+            vec![
+                leg("Groceries", 4500, "groceries"),
+                leg("Pharmacy", 820, "pharmacy"),
+            ],
+            Utc::now(),
+            None,
+            false,
+        )
+        .await?;
+
+    // Partial reversal of a leg is rejected
+    let partial = service
+        .reverse_transfer(split.legs[0].transfer.id, Some(1000))
+        .await;
+    assert!(matches!(partial, Err(AppError::InvalidSplit(_))));
+
+    // Reversing one leg reverses the whole split
+    let result = service
+        .reverse_transfer(split.legs[1].transfer.id, None)
+        .await?;
+    assert_eq!(result.split_reversals.len(), 2);
+    assert_eq!(result.reversal.reverses, Some(split.legs[1].transfer.id));
+
+    let reversal_split = result.split_reversals[0].split_id;
+    assert!(reversal_split.is_some());
+    assert_ne!(reversal_split, Some(split.split_id));
+
+    assert_eq!(service.get_balance("Checking").await?.balance, 100000);
+    assert_eq!(service.get_balance("Groceries").await?.balance, 0);
+    assert_eq!(service.get_balance("Pharmacy").await?.balance, 0);
+
+    // A second reversal is rejected
+    let again = service
+        .reverse_transfer(split.legs[0].transfer.id, None)
+        .await;
+    assert!(matches!(
+        again,
+        Err(AppError::ReversalExceedsOriginal { .. })
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use anyhow::Result;
+use common::{StandardWallets, parse_date, test_service};
+use pecunio::application::{LedgerService, TransferRequest};
+use pecunio::domain::{ClearedState, TransferId};
+use pecunio::io::{ImportOptions, Importer};
+
+const HEADER: &str = "id,sequence,timestamp,from_wallet,to_wallet,amount,description,category,tags,reverses,external_ref,split_id\n";
+
+/// A statement line in the export layout, without ID or sequence.
+fn statement_line(date: &str, amount: &str, description: &str, external_ref: &str) -> String {
+    format!(
+        ",,{},Checking,Expense,{},{},,,,{},\n",
+        date, amount, description, external_ref
+    )
+}
+
+async fn record(
+    service: &LedgerService,
+    amount: i64,
+    date: &str,
+    description: &str,
+) -> Result<TransferId> {
+    let result = service
+        .record_transfer_with(
+            TransferRequest::new("Checking", "Expense", amount, parse_date(date))
+                .with_description(Some(description.to_string()))
+                .with_force(true),
+        )
+        .await?;
+    Ok(result.transfer.id)
+}
+
+fn matching() -> ImportOptions {
+    ImportOptions {
+        match_existing: true,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_statement_lines_match_hand_entered_transfers() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    let groceries = record(&service, 4599, "2024-01-10", "Groceries Lidl").await?;
+    let cinema_a = record(&service, 2000, "2024-01-14", "Cinema").await?;
+    let cinema_b = record(&service, 2000, "2024-01-14", "Cinema").await?;
+
+    let csv = [
+        HEADER.to_string(),
+        statement_line("2024-01-11", "45.99", "LIDL GROCERIES 0042", "BANK-1"),
+        statement_line("2024-01-14", "20.00", "CINEMA CITY", "BANK-2"),
+        statement_line("2024-01-15", "7.50", "Bakery", "BANK-3"),
+    ]
+    .concat();
+
+    let result = Importer::new(&service)
+        .import_transfers_csv(csv.as_bytes(), matching())
+        .await?;
+    assert_eq!(result.matched, 1);
+    assert_eq!(result.imported, 1);
+    assert!(result.errors.is_empty());
+
+    // The grocery entry is now known to the bank
+    let matched = service.get_transfer_info(groceries).await?.transfer;
+    assert_eq!(matched.cleared, ClearedState::Cleared);
+    assert_eq!(matched.external_ref.as_deref(), Some("BANK-1"));
+    let bakery = service
+        .find_transfer_by_external_ref("BANK-3")
+        .await?
+        .expect("imported line");
+    assert_eq!(bakery.cleared, ClearedState::Cleared);
+
+    // Two identical cinema entries: the line waits for confirmation
+    assert_eq!(result.ambiguous.len(), 1);
+    let ambiguous = &result.ambiguous[0];
+    assert_eq!(ambiguous.line, 3);
+    assert_eq!(ambiguous.candidates.len(), 2);
+    assert_eq!(
+        service.get_balance("Expense").await?.balance,
+        4599 + 2000 + 2000 + 750
+    );
+
+    // Importing again with the answer: matched lines are recognised by reference
+    let mut options = matching();
+    options.confirmed.insert(3, Some(cinema_b));
+    let result = Importer::new(&service)
+        .import_transfers_csv(csv.as_bytes(), options)
+        .await?;
+    assert_eq!(result.skipped, 2);
+    assert_eq!(result.matched, 1);
+    assert_eq!(result.imported, 0);
+    assert!(result.ambiguous.is_empty());
+    assert_eq!(
+        service.get_transfer_info(cinema_b).await?.transfer.cleared,
+        ClearedState::Cleared
+    );
+    assert_eq!(
+        service.get_transfer_info(cinema_a).await?.transfer.cleared,
+        ClearedState::Uncleared
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_skip_duplicates_only_skips_real_duplicates() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+
+    let csv = [
+        HEADER.to_string(),
+        statement_line("2024-01-11", "45.99", "Groceries", "BANK-1"),
+        ",,2024-01-12,Checking,Nowhere,12.00,Lost,,,,BANK-2,\n".to_string(),
+    ]
+    .concat();
+    let options = ImportOptions {
+        skip_duplicates: true,
+        ..Default::default()
+    };
+
+    let first = Importer::new(&service)
+        .import_transfers_csv(csv.as_bytes(), options.clone())
+        .await?;
+    assert_eq!(first.imported, 1);
+    assert_eq!(first.skipped, 0);
+    // A missing wallet is an error, not a duplicate
+    assert_eq!(first.errors.len(), 1);
+    assert_eq!(first.errors[0].line, 3);
+
+    let second = Importer::new(&service)
+        .import_transfers_csv(csv.as_bytes(), options)
+        .await?;
+    assert_eq!(second.imported, 0);
+    assert_eq!(second.skipped, 1);
+    assert_eq!(second.errors.len(), 1);
+    assert_eq!(service.get_balance("Expense").await?.balance, 4599);
+
+    Ok(())
+}
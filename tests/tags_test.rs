@@ -0,0 +1,167 @@
+mod common;
+
+use anyhow::Result;
+use common::{StandardWallets, parse_date, test_service};
+use pecunio::application::{AppError, TransferEdit, TransferFilter, TransferRequest};
+use pecunio::domain::{RecurrencePattern, TagMatch};
+
+fn tag_filter(tags: &[&str], tag_match: TagMatch) -> TransferFilter {
+    TransferFilter {
+        wallet: None,
+        category: None,
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        tag_match,
+        from_date: None,
+        to_date: None,
+        limit: None,
+    }
+}
+
+fn tags(tags: &[&str]) -> Vec<String> {
+    tags.iter().map(|tag| tag.to_string()).collect()
+}
+
+#[tokio::test]
+async fn test_tags_record_and_filter() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    StandardWallets::fund_checking(&service, 100000, parse_date("2024-01-01")).await?;
+
+    let hotel = service
+        .record_transfer_with(
+            TransferRequest::new("Checking", "Expense", 30000, parse_date("2024-07-01"))
+                .with_tags(tags(&[" Vacation", "italy", "vacation"])),
+        )
+        .await?
+        .transfer;
+    assert_eq!(hotel.tags, vec!["vacation", "italy"]);
+    service
+        .record_transfer_with(
+            TransferRequest::new("Checking", "Expense", 5000, parse_date("2024-07-02"))
+                .with_tags(tags(&["vacation"])),
+        )
+        .await?;
+    service
+        .record_transfer_with(
+            TransferRequest::new("Checking", "Expense", 2000, parse_date("2024-07-03"))
+                .with_tags(tags(&["work"])),
+        )
+        .await?;
+
+    let any = service
+        .list_transfers_filtered(tag_filter(&["italy", "work"], TagMatch::Any))
+        .await?;
+    assert_eq!(any.len(), 2);
+    let all = service
+        .list_transfers_filtered(tag_filter(&["vacation", "Italy"], TagMatch::All))
+        .await?;
+    assert_eq!(all.len(), 1);
+    assert_eq!(all[0].id, hotel.id);
+
+    // Retagging through an amendment moves the transfer between filters
+    service
+        .amend_transfer(hotel.id, TransferEdit::new().with_tags(tags(&["work"])))
+        .await?;
+    let vacation = service
+        .list_transfers_filtered(tag_filter(&["vacation"], TagMatch::Any))
+        .await?;
+    assert_eq!(vacation.len(), 1);
+    let work = service
+        .list_transfers_filtered(tag_filter(&["work"], TagMatch::Any))
+        .await?;
+    assert_eq!(work.len(), 2);
+
+    let invalid = service
+        .record_transfer_with(
+            TransferRequest::new("Checking", "Expense", 100, parse_date("2024-07-04"))
+                .with_tags(tags(&["a;b"])),
+        )
+        .await;
+    assert!(matches!(invalid, Err(AppError::InvalidTag(_))));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_scheduled_transfers_carry_tags() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    StandardWallets::fund_checking(&service, 100000, parse_date("2024-01-01")).await?;
+
+    service
+        .create_scheduled_transfer(
+            "Gym".into(),
+            "Checking",
+            "Expense",
+            3000,
+            RecurrencePattern::Monthly,
+            parse_date("2024-01-15"),
+            None,
+            None,
+            None,
+        )
+        .await?;
+    let scheduled = service
+        .set_scheduled_transfer_tags("Gym", tags(&["Health", "subscription"]))
+        .await?;
+    assert_eq!(scheduled.tags, vec!["health", "subscription"]);
+    assert_eq!(
+        service.get_scheduled_transfer("Gym").await?.tags,
+        scheduled.tags
+    );
+
+    let executed = service
+        .execute_scheduled_transfer("Gym", Some(parse_date("2024-01-15")), true)
+        .await?;
+    assert_eq!(executed.transfer.tags, vec!["health", "subscription"]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tag_report() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    StandardWallets::fund_checking(&service, 100000, parse_date("2024-01-01")).await?;
+
+    for (amount, transfer_tags) in [
+        (30000, vec!["vacation", "italy"]),
+        (10000, vec!["vacation"]),
+        (5000, vec!["car"]),
+        (7000, vec![]),
+    ] {
+        service
+            .record_transfer_with(
+                TransferRequest::new("Checking", "Expense", amount, parse_date("2024-07-01"))
+                    .with_tags(tags(&transfer_tags)),
+            )
+            .await?;
+    }
+
+    let report = service
+        .get_tag_report(parse_date("2024-07-01"), parse_date("2024-08-01"))
+        .await?;
+    // Each tagged transfer counts once in the total, untagged ones not at all
+    assert_eq!(report.total, 45000);
+    let rows: Vec<(&str, i64, i64)> = report
+        .tags
+        .iter()
+        .map(|tag| (tag.tag.as_str(), tag.total, tag.count))
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            ("vacation", 40000, 2),
+            ("italy", 30000, 1),
+            ("car", 5000, 1)
+        ]
+    );
+    assert!((report.tags[0].percentage - 88.88).abs() < 0.01);
+
+    let june = service
+        .get_tag_report(parse_date("2024-06-01"), parse_date("2024-07-01"))
+        .await?;
+    assert!(june.tags.is_empty());
+
+    Ok(())
+}
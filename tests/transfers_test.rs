@@ -4,7 +4,7 @@ use anyhow::Result;
 use chrono::Utc;
 use common::{StandardWallets, parse_date, test_service};
 use pecunio::application::TransferFilter;
-use pecunio::domain::WalletType;
+use pecunio::domain::{TagMatch, WalletType};
 
 #[tokio::test]
 async fn test_date_support_for_transfers() -> Result<()> {
@@ -102,6 +102,8 @@ async fn test_transfer_filtering_by_date_range() -> Result<()> {
     let filter = TransferFilter {
         wallet: None,
         category: None,
+        tags: Vec::new(),
+        tag_match: TagMatch::Any,
         from_date: Some(parse_date("2024-01-01")),
         to_date: Some(parse_date("2024-01-31")),
         limit: None,
@@ -161,6 +163,8 @@ async fn test_transfer_filtering_by_category() -> Result<()> {
     let filter = TransferFilter {
         wallet: None,
         category: Some("groceries".to_string()),
+        tags: Vec::new(),
+        tag_match: TagMatch::Any,
         from_date: None,
         to_date: None,
         limit: None,
@@ -197,6 +201,8 @@ async fn test_transfer_filtering_by_wallet() -> Result<()> {
     let filter = TransferFilter {
         wallet: Some("Checking".to_string()),
         category: None,
+        tags: Vec::new(),
+        tag_match: TagMatch::Any,
         from_date: None,
         to_date: None,
         limit: None,
@@ -280,6 +286,8 @@ async fn test_combined_filters() -> Result<()> {
     let filter = TransferFilter {
         wallet: Some("Checking".to_string()),
         category: Some("groceries".to_string()),
+        tags: Vec::new(),
+        tag_match: TagMatch::Any,
         from_date: Some(parse_date("2024-01-01")),
         to_date: Some(parse_date("2024-01-31")),
         limit: None,
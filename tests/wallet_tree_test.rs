@@ -0,0 +1,178 @@
+mod common;
+
+use anyhow::Result;
+use common::{StandardWallets, parse_date, test_service};
+use pecunio::application::AppError;
+use pecunio::domain::{IntegrityIssue, WalletType};
+use pecunio::io::{DatabaseSnapshot, Exporter};
+use sqlx::SqlitePool;
+
+#[tokio::test]
+async fn test_wallet_tree_subtotals() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    service
+        .create_wallet("Bank".into(), WalletType::Asset, "EUR".into(), None)
+        .await?;
+    service.move_wallet("Checking", Some("Bank")).await?;
+    service.move_wallet("Savings", Some("Bank")).await?;
+    let holiday = service
+        .create_sub_wallet("Holiday".into(), "Savings", None)
+        .await?;
+    assert_eq!(holiday.wallet_type, WalletType::Asset);
+    assert_eq!(holiday.currency, "EUR");
+
+    StandardWallets::fund_checking(&service, 100000, parse_date("2024-01-01")).await?;
+    service
+        .record_transfer(
+            "Checking",
+            "Holiday",
+            25000,
+            parse_date("2024-01-02"),
+            None,
+            None,
+            false,
+        )
+        .await?;
+
+    let balances = service.get_all_balances().await?;
+    let rows: Vec<(&str, usize, i64, i64)> = balances
+        .iter()
+        .filter(|entry| entry.wallet.wallet_type == WalletType::Asset)
+        .map(|entry| {
+            (
+                entry.wallet.name.as_str(),
+                entry.depth,
+                entry.balance,
+                entry.subtotal,
+            )
+        })
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            ("Bank", 0, 0, 100000),
+            ("Checking", 1, 75000, 75000),
+            ("Savings", 1, 0, 25000),
+            ("Holiday", 2, 25000, 25000),
+        ]
+    );
+    assert_eq!(service.get_balance("Savings").await?.subtotal, 25000);
+
+    // Net worth totals count each wallet once; subtotals roll up the tree
+    let net_worth = service.get_net_worth_report().await?;
+    assert_eq!(net_worth.total_assets, 100000);
+    assert_eq!(net_worth.assets[0].wallet_name, "Bank");
+    assert_eq!(net_worth.assets[0].subtotal, 100000);
+    assert_eq!(net_worth.assets[3].depth, 2);
+
+    let report = service.check_integrity().await?;
+    assert!(report.is_healthy());
+    assert_eq!(report.wallet_tree[0].subtotal, 100000);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_transfers_go_into_leaf_wallets() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    StandardWallets::fund_checking(&service, 10000, parse_date("2024-01-01")).await?;
+    service
+        .create_sub_wallet("Fuel".into(), "Expense", None)
+        .await?;
+
+    let into_parent = service
+        .record_transfer(
+            "Checking",
+            "Expense",
+            1000,
+            parse_date("2024-01-02"),
+            None,
+            None,
+            false,
+        )
+        .await;
+    assert!(matches!(into_parent, Err(AppError::WalletHasSubWallets(_))));
+    service
+        .record_transfer(
+            "Checking",
+            "Fuel",
+            1000,
+            parse_date("2024-01-02"),
+            None,
+            None,
+            false,
+        )
+        .await?;
+
+    // Unless the parent is configured to take postings itself
+    service.set_wallet_allow_postings("Expense", true).await?;
+    service
+        .record_transfer(
+            "Checking",
+            "Expense",
+            500,
+            parse_date("2024-01-03"),
+            None,
+            None,
+            false,
+        )
+        .await?;
+    let expense = service.get_balance("Expense").await?;
+    assert_eq!(expense.balance, 500);
+    assert_eq!(expense.subtotal, 1500);
+
+    let wrong_type = service.move_wallet("Savings", Some("Expense")).await;
+    assert!(matches!(
+        wrong_type,
+        Err(AppError::InvalidWalletHierarchy(_))
+    ));
+    let cycle = service.move_wallet("Expense", Some("Fuel")).await;
+    assert!(matches!(cycle, Err(AppError::InvalidWalletHierarchy(_))));
+    let archived_parent = service.archive_wallet("Expense").await;
+    assert!(matches!(
+        archived_parent,
+        Err(AppError::InvalidWalletHierarchy(_))
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_wallet_tree_survives_export_and_integrity_check() -> Result<()> {
+    let (service, temp) = test_service().await?;
+    StandardWallets::create_basic(&service).await?;
+    let fuel = service
+        .create_sub_wallet("Fuel".into(), "Expense", None)
+        .await?;
+
+    let mut json = Vec::new();
+    Exporter::new(&service).export_full_json(&mut json).await?;
+    let snapshot: DatabaseSnapshot = serde_json::from_slice(&json)?;
+    let exported = snapshot.wallets.iter().find(|w| w.name == "Fuel").unwrap();
+    let expense = snapshot
+        .wallets
+        .iter()
+        .find(|w| w.name == "Expense")
+        .unwrap();
+    assert_eq!(exported.parent_id, Some(expense.id));
+
+    // A parent changed behind the ledger's back is reported
+    let db_path = temp.path().join("test.db");
+    let pool = SqlitePool::connect(&format!("sqlite:{}", db_path.to_str().unwrap())).await?;
+    let checking = service.get_wallet("Checking").await?;
+    sqlx::query("UPDATE wallets SET parent_id = ? WHERE id = ?")
+        .bind(checking.id.to_string())
+        .bind(fuel.id.to_string())
+        .execute(&pool)
+        .await?;
+    let report = service.check_integrity().await?;
+    assert!(
+        report
+            .issues
+            .contains(&IntegrityIssue::InvalidWalletHierarchy(1))
+    );
+
+    Ok(())
+}
@@ -0,0 +1,186 @@
+mod common;
+
+use anyhow::Result;
+use common::{StandardWallets, parse_date, test_service};
+use pecunio::application::{AppError, LedgerService, TradeRequest};
+
+/// Two years of salary and spending: 2023 nets 1,500.00, 2024 nets 500.00.
+async fn record_two_years(service: &LedgerService) -> Result<()> {
+    StandardWallets::create_basic(service).await?;
+    StandardWallets::fund_checking(service, 300000, parse_date("2023-01-31")).await?;
+    service
+        .record_transfer(
+            "Checking",
+            "Expense",
+            150000,
+            parse_date("2023-06-15"),
+            Some("Rent".into()),
+            Some("housing".into()),
+            false,
+        )
+        .await?;
+    StandardWallets::fund_checking(service, 100000, parse_date("2024-01-31")).await?;
+    service
+        .record_transfer(
+            "Checking",
+            "Expense",
+            50000,
+            parse_date("2024-02-15"),
+            Some("Rent".into()),
+            Some("housing".into()),
+            false,
+        )
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_close_year_moves_net_income_to_retained_earnings() -> Result<()> {
+    let (service, _temp) = test_service().await?;
+    record_two_years(&service).await?;
+
+    let close = service.close_year(2023).await?;
+    assert_eq!(close.year, 2023);
+    assert!(!close.is_archived());
+
+    // Income and expenses only hold what came after the closed year
+    assert_eq!(service.get_balance("Income").await?.balance, -100000);
+    assert_eq!(service.get_balance("Expense").await?.balance, 50000);
+    assert_eq!(
+        service.get_balance("Retained Earnings EUR").await?.balance,
+        -150000
+    );
+    assert_eq!(service.get_balance("Checking").await?.balance, 200000);
+
+    // Closing entries are not income or expenses of the year
+    let report = service
+        .get_income_expense_report(parse_date("2023-01-01"), parse_date("2024-01-01"))
+        .await?;
+    assert_eq!(report.total_income, 300000);
+    assert_eq!(report.total_expense, 150000);
+
+    // The closed year is locked
+    let locks = service.get_period_locks().await?;
+    assert_eq!(locks.len(), 1);
+    assert_eq!(
+        locks[0].lock_date,
+        Some(close.closing_timestamp().date_naive())
+    );
+    assert!(service.check_integrity().await?.is_healthy());
+
+    assert!(matches!(
+        service.close_year(2023).await,
+        Err(AppError::YearAlreadyClosed(2023))
+    ));
+    assert!(matches!(
+        service.close_year(2022).await,
+        Err(AppError::InvalidYearClose(_))
+    ));
+    assert!(matches!(
+        service.close_year(2999).await,
+        Err(AppError::InvalidYearClose(_))
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_archive_year_starts_the_live_file_with_opening_balances() -> Result<()> {
+    let (service, temp) = test_service().await?;
+    record_two_years(&service).await?;
+    service
+        .create_commodity_wallet("Broker VWCE".into(), "vwce".into(), "EUR".into(), None)
+        .await?;
+    // 10.00 units at 100.00, half of them sold after the year at 120.00
+    service
+        .record_buy(TradeRequest::new(
+            "Broker VWCE",
+            "Checking",
+            1000,
+            100.0,
+            parse_date("2023-09-01"),
+        ))
+        .await?;
+    service
+        .record_sell(TradeRequest::new(
+            "Broker VWCE",
+            "Checking",
+            500,
+            120.0,
+            parse_date("2024-03-01"),
+        ))
+        .await?;
+
+    service.close_year(2023).await?;
+    let before = service.get_all_balances().await?;
+
+    let db_path = temp.path().join("test.db");
+    let archive_path = temp.path().join("2023.db");
+    let (service, close) = service
+        .archive_year(
+            db_path.to_str().unwrap(),
+            2023,
+            archive_path.to_str().unwrap(),
+        )
+        .await?;
+    assert!(close.is_archived());
+    assert!(std::fs::metadata(&archive_path)?.permissions().readonly());
+    assert!(matches!(
+        service
+            .archive_year(db_path.to_str().unwrap(), 2023, "again.db")
+            .await,
+        Err(AppError::InvalidYearClose(_))
+    ));
+    let service = LedgerService::connect(db_path.to_str().unwrap()).await?;
+
+    // Balances and units are carried over unchanged
+    for entry in &before {
+        let balance = service.get_balance(&entry.wallet.name).await?.balance;
+        assert_eq!(balance, entry.balance, "{}", entry.wallet.name);
+    }
+    assert_eq!(
+        service.get_wallet_info("Broker VWCE").await?.quantity,
+        Some(500)
+    );
+
+    // The units left form one lot at their cost
+    let sell = service
+        .record_sell(TradeRequest::new(
+            "Broker VWCE",
+            "Checking",
+            500,
+            130.0,
+            parse_date("2024-06-01"),
+        ))
+        .await?;
+    assert_eq!(sell.trade.transfer.amount_cents, 65000);
+    assert_eq!(sell.cost_basis, 50000);
+    assert_eq!(sell.realized_gain, 15000);
+
+    // The live file only holds the opening balances and what came after the year
+    let transfers = service.list_transfers(None).await?;
+    assert!(transfers.iter().all(|t| {
+        t.timestamp >= parse_date("2024-01-01") || t.split_id == close.opening_split_id
+    }));
+    assert!(service.check_integrity().await?.is_healthy());
+    let report = service
+        .get_income_expense_report(parse_date("2023-01-01"), parse_date("2024-01-01"))
+        .await?;
+    assert_eq!(report.total_income, 0);
+
+    // Reports can still span the archived year
+    let merged = LedgerService::open_with_archives(db_path.to_str().unwrap()).await?;
+    let report = merged
+        .get_income_expense_report(parse_date("2023-01-01"), parse_date("2025-01-01"))
+        .await?;
+    // Salaries and the gains on the units sold
+    assert_eq!(report.total_income, 425000);
+    assert_eq!(report.total_expense, 200000);
+    for entry in service.get_all_balances().await? {
+        let balance = merged.get_balance(&entry.wallet.name).await?.balance;
+        assert_eq!(balance, entry.balance, "{}", entry.wallet.name);
+    }
+    assert!(merged.check_integrity().await?.is_healthy());
+
+    Ok(())
+}